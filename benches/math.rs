@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tune::{math, pitch::Ratio};
+
+fn odd_factors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("odd_factors");
+    group.bench_function("u16", |b| {
+        b.iter(|| {
+            (1..=u16::MAX)
+                .map(|n| math::odd_factors_u16(black_box(n)))
+                .max()
+        })
+    });
+    group.bench_function("u32", |b| {
+        b.iter(|| {
+            (1..=u32::from(u16::MAX))
+                .map(|n| math::odd_factors_u32(black_box(n << 16)))
+                .max()
+        })
+    });
+    group.bench_function("u64", |b| {
+        b.iter(|| {
+            (1..=u64::from(u16::MAX))
+                .map(|n| math::odd_factors_u64(black_box(n << 48)))
+                .max()
+        })
+    });
+    group.finish();
+}
+
+fn nearest_fraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nearest_fraction");
+    for odd_limit in [11, 1001, 100_001, 10_000_001] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(odd_limit),
+            &odd_limit,
+            |b, &odd_limit| {
+                b.iter(|| {
+                    (1..100)
+                        .map(|step| Ratio::from_cents(f64::from(step) * 12.345))
+                        .map(|ratio| ratio.nearest_fraction(black_box(odd_limit)).numer)
+                        .max()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, odd_factors, nearest_fraction);
+criterion_main!(benches);
@@ -274,6 +274,7 @@ impl TunableSynth for TunableFluid {
     }
 
     fn note_off(&mut self, channel: usize, stopped_note: Note, _velocity: u8) -> SendCommandResult {
+        // SoundFont 2 modulators have no note-off velocity source. Hence, the velocity is dropped.
         if let Some(stopped_note) = stopped_note.checked_midi_number() {
             let channel = self.get_channel(channel);
             self.send_command(move |s| {
@@ -4,6 +4,8 @@ pub struct BufferWriter {
     pub(crate) sample_width_secs: f64,
     pub(crate) readable: ReadableBuffers,
     pub(crate) writeable: WaveformBuffer,
+    /// Set when a stage tried to write NaN or infinite values.
+    pub(crate) non_finite_values_written: bool,
 }
 
 impl BufferWriter {
@@ -18,11 +20,12 @@ impl BufferWriter {
     pub fn read_0_and_write(
         &mut self,
         out_buffer: OutBuffer,
-        out_level: f64,
+        out_level: impl Into<OutLevel>,
         mut f: impl FnMut() -> f64,
     ) {
-        self.read_n_and_write(out_buffer, |_, write_access| {
-            write_access.write(iter::repeat_with(|| f() * out_level))
+        let OutLevel { level, pan } = out_level.into();
+        self.read_n_and_write(out_buffer, pan, |_, write_access| {
+            write_access.write(iter::repeat_with(|| f() * level))
         });
     }
 
@@ -30,15 +33,16 @@ impl BufferWriter {
         &mut self,
         in_buffer: InBuffer,
         out_buffer: OutBuffer,
-        out_level: f64,
+        out_level: impl Into<OutLevel>,
         mut f: impl FnMut(f64) -> f64,
     ) {
-        self.read_n_and_write(out_buffer, |read_access, write_access| {
+        let OutLevel { level, pan } = out_level.into();
+        self.read_n_and_write(out_buffer, pan, |read_access, write_access| {
             write_access.write(
                 read_access
                     .read(in_buffer)
                     .iter()
-                    .map(|&src| f(src) * out_level),
+                    .map(|&src| f(src) * level),
             )
         });
     }
@@ -47,16 +51,17 @@ impl BufferWriter {
         &mut self,
         in_buffers: (InBuffer, InBuffer),
         out_buffer: OutBuffer,
-        out_level: f64,
+        out_level: impl Into<OutLevel>,
         mut f: impl FnMut(f64, f64) -> f64,
     ) {
-        self.read_n_and_write(out_buffer, |read_access, write_access| {
+        let OutLevel { level, pan } = out_level.into();
+        self.read_n_and_write(out_buffer, pan, |read_access, write_access| {
             write_access.write(
                 read_access
                     .read(in_buffers.0)
                     .iter()
                     .zip(read_access.read(in_buffers.1))
-                    .map(|(&src_0, &src_1)| f(src_0, src_1) * out_level),
+                    .map(|(&src_0, &src_1)| f(src_0, src_1) * level),
             )
         });
     }
@@ -64,11 +69,63 @@ impl BufferWriter {
     fn read_n_and_write(
         &mut self,
         out_buffer: OutBuffer,
-        mut rw_access_fn: impl FnMut(&ReadableBuffers, &mut WaveformBuffer),
+        pan: f64,
+        mut rw_access_fn: impl FnMut(&ReadableBuffers, &mut WaveformBuffer) -> WriteResult,
     ) {
-        self.readable.swap(out_buffer, &mut self.writeable);
-        rw_access_fn(&self.readable, &mut self.writeable);
-        self.readable.swap(out_buffer, &mut self.writeable);
+        if pan == 0.0 || !matches!(out_buffer, OutBuffer::AudioOut) {
+            self.readable.swap(out_buffer, &mut self.writeable);
+            let write_result = rw_access_fn(&self.readable, &mut self.writeable);
+            self.readable.swap(out_buffer, &mut self.writeable);
+            self.record(write_result);
+            return;
+        }
+
+        // Render into the placeholder buffer first s.t. the result can be distributed to the mid and the side channel
+        self.writeable.clear(self.readable.mix.len);
+        let write_result = rw_access_fn(&self.readable, &mut self.writeable);
+        self.record(write_result);
+
+        let (mid_gain, side_gain) = mid_side_gains(pan);
+        let rendered = self.writeable.read();
+        self.readable
+            .audio_out
+            .write(rendered.iter().map(|&src| src * mid_gain));
+        self.readable
+            .audio_out_side
+            .write(rendered.iter().map(|&src| src * side_gain));
+    }
+
+    pub(crate) fn record(&mut self, write_result: WriteResult) {
+        if write_result == WriteResult::NonFiniteValuesReplaced {
+            self.non_finite_values_written = true;
+        }
+    }
+}
+
+/// Returns the gains of the mid and the side channel for a stereo position `pan` (-1 = left, 0 = center, 1 = right).
+///
+/// A balance law is used: Moving a signal to one side attenuates the opposite channel while the near channel keeps its full level. This way, centered signals are identical to the former mono output.
+fn mid_side_gains(pan: f64) -> (f64, f64) {
+    let pan = pan.clamp(-1.0, 1.0);
+    let left_gain = (1.0 - pan).min(1.0);
+    let right_gain = (1.0 + pan).min(1.0);
+    (
+        (left_gain + right_gain) / 2.0,
+        (left_gain - right_gain) / 2.0,
+    )
+}
+
+/// Level and stereo position at which a stage writes to its [`OutBuffer`].
+#[derive(Copy, Clone, Debug)]
+pub struct OutLevel {
+    pub level: f64,
+    /// Stereo position (-1 = left, 0 = center, 1 = right). Only applies to [`OutBuffer::AudioOut`].
+    pub pan: f64,
+}
+
+impl From<f64> for OutLevel {
+    fn from(level: f64) -> Self {
+        Self { level, pan: 0.0 }
     }
 }
 
@@ -87,8 +144,12 @@ pub enum OutBuffer {
 pub(crate) struct ReadableBuffers {
     pub audio_in: WaveformBuffer,
     pub intermediate: Vec<WaveformBuffer>,
+    /// Mid channel of the waveform output, i.e. (L + R) / 2.
     pub audio_out: WaveformBuffer,
+    /// Side channel of the waveform output, i.e. (L - R) / 2. Only written by stages that are not centered.
+    pub audio_out_side: WaveformBuffer,
     pub mix: WaveformBuffer,
+    pub mix_side: WaveformBuffer,
 }
 
 impl ReadableBuffers {
@@ -141,19 +202,38 @@ impl WaveformBuffer {
         }
     }
 
-    pub fn write(&mut self, items: impl Iterator<Item = f64>) {
+    /// Writes or adds `items` to the buffer. Denormal values are flushed to zero and NaN or infinite values are replaced with zero s.t. they cannot spread to other stages or voices.
+    pub fn write(&mut self, items: impl Iterator<Item = f64>) -> WriteResult {
+        let mut result = WriteResult::Ok;
+        let mut sanitize = |src: f64| {
+            if src.is_finite() {
+                crate::flush_denormal(src)
+            } else {
+                result = WriteResult::NonFiniteValuesReplaced;
+                0.0
+            }
+        };
+
         match self.dirty {
             true => {
                 for (dest, src) in self.storage[..self.len].iter_mut().zip(items) {
-                    *dest = src
+                    *dest = sanitize(src)
                 }
                 self.dirty = false;
             }
             false => {
                 for (dest, src) in self.storage[..self.len].iter_mut().zip(items) {
-                    *dest += src
+                    *dest += sanitize(src)
                 }
             }
         }
+
+        result
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WriteResult {
+    Ok,
+    NonFiniteValuesReplaced,
+}
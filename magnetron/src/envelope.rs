@@ -47,12 +47,27 @@ impl<A: AutomationSpec> Spec<A> for EnvelopeSpec<A> {
 
                 let amplitude_increment = (to_amplitude - saved_amplitude) / buffer_len_f64;
 
-                let out_buffer = buffers.readable.audio_out.read();
-                buffers.readable.mix.write(out_buffer.iter().map(|src| {
+                let readable = &mut buffers.readable;
+                let side_write_result = (!readable.audio_out_side.dirty).then(|| {
+                    let mut amplitude = saved_amplitude;
+                    let side_buffer = readable.audio_out_side.read();
+                    readable.mix_side.write(side_buffer.iter().map(|src| {
+                        let result = src * amplitude;
+                        amplitude += amplitude_increment;
+                        result
+                    }))
+                });
+
+                let out_buffer = readable.audio_out.read();
+                let write_result = readable.mix.write(out_buffer.iter().map(|src| {
                     let result = src * saved_amplitude;
                     saved_amplitude += amplitude_increment;
                     result
                 }));
+                buffers.record(write_result);
+                if let Some(side_write_result) = side_write_result {
+                    buffers.record(side_write_result);
+                }
 
                 match release_progress < 1.0 {
                     true => StageState::Active,
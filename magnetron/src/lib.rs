@@ -24,9 +24,12 @@ impl Magnetron {
                     audio_in: WaveformBuffer::new(zeros.clone()),
                     intermediate: vec![WaveformBuffer::new(zeros.clone()); num_buffers],
                     audio_out: WaveformBuffer::new(zeros.clone()),
+                    audio_out_side: WaveformBuffer::new(zeros.clone()),
                     mix: WaveformBuffer::new(zeros.clone()),
+                    mix_side: WaveformBuffer::new(zeros.clone()),
                 },
                 writeable: WaveformBuffer::new(zeros), // Empty Vec acting as a placeholder
+                non_finite_values_written: false,
             },
         }
     }
@@ -34,6 +37,7 @@ impl Magnetron {
     pub fn clear(&mut self, len: usize) {
         self.buffers.readable.audio_in.clear(len);
         self.buffers.readable.mix.clear(len);
+        self.buffers.readable.mix_side.clear(len);
     }
 
     pub fn set_audio_in(&mut self, mut buffer_content: impl FnMut() -> f64) {
@@ -51,6 +55,7 @@ impl Magnetron {
             buffer.clear(len);
         }
         buffers.readable.audio_out.clear(len);
+        buffers.readable.audio_out_side.clear(len);
 
         let render_window_secs = buffers.sample_width_secs * len as f64;
         let context = AutomationContext {
@@ -58,15 +63,42 @@ impl Magnetron {
             payload,
         };
 
-        for stage in &mut waveform.stages {
+        buffers.non_finite_values_written = false;
+        for (stage_index, stage) in waveform.stages.iter_mut().enumerate() {
             stage.render(buffers, &context);
+            if buffers.non_finite_values_written {
+                waveform.mute(stage_index);
+                return;
+            }
         }
-        waveform.is_active = waveform.envelope.render(buffers, &context).is_active();
+        let envelope_state = waveform.envelope.render(buffers, &context);
+        if buffers.non_finite_values_written {
+            waveform.mute(waveform.stages.len());
+            return;
+        }
+        waveform.is_active = envelope_state.is_active();
     }
 
+    /// Mid channel of the mixed waveforms, i.e. (L + R) / 2. For centered waveforms, this is the mono output.
     pub fn mix(&self) -> &[f64] {
         self.buffers.readable.mix.read()
     }
+
+    /// Side channel of the mixed waveforms, i.e. (L - R) / 2. Silent unless a stage is panned.
+    pub fn mix_side(&self) -> &[f64] {
+        self.buffers.readable.mix_side.read()
+    }
+}
+
+/// Flushes values that are too small to be represented as normal floating-point numbers to zero.
+///
+/// Denormal numbers occur in decaying feedback loops, e.g. in filters, and slow down arithmetic operations considerably.
+pub fn flush_denormal(value: f64) -> f64 {
+    if value.abs() < f64::MIN_POSITIVE {
+        0.0
+    } else {
+        value
+    }
 }
 
 pub struct Stage<T> {
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use crate::{
     automation::{AutomatedValue, Automation, AutomationContext, AutomationSpec},
@@ -9,6 +13,7 @@ use crate::{
 pub struct Creator<A> {
     templates: HashMap<String, A>,
     envelopes: HashMap<String, EnvelopeSpec<A>>,
+    seeds: SeedSource,
 }
 
 impl<A> Creator<A> {
@@ -16,11 +21,23 @@ impl<A> Creator<A> {
         Self {
             templates,
             envelopes,
+            seeds: SeedSource::new(RandomState::new().build_hasher().finish()),
         }
     }
 
-    fn new_without_nesting() -> Creator<A> {
-        Self::new(HashMap::new(), HashMap::new())
+    /// Makes the sequence of seeds returned by [`Creator::next_seed`] reproducible, e.g. for offline renders.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seeds = SeedSource::new(seed);
+        self
+    }
+
+    /// Returns the seed for the next random number generator to be created, e.g. for a noise stage.
+    pub fn next_seed(&self) -> u64 {
+        self.seeds.next()
+    }
+
+    fn new_without_nesting(&self) -> Creator<A> {
+        Self::new(HashMap::new(), HashMap::new()).with_seed(self.next_seed())
     }
 
     pub fn create<S: Spec<A>>(&self, spec: S) -> S::Created {
@@ -33,7 +50,7 @@ impl<A> Creator<A> {
     {
         self.templates
             .get(template_name)
-            .map(|spec| Self::new_without_nesting().create(spec))
+            .map(|spec| self.new_without_nesting().create(spec))
     }
 
     pub fn create_envelope(&self, envelope_name: &str) -> Option<Stage<A::Context>>
@@ -80,6 +97,29 @@ impl<A> Creator<A> {
     }
 }
 
+/// Generates well-distributed seeds using the SplitMix64 algorithm.
+struct SeedSource {
+    state: AtomicU64,
+}
+
+impl SeedSource {
+    fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
 pub trait Spec<A> {
     type Created;
 
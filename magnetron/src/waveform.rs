@@ -4,6 +4,15 @@ pub struct Waveform<T> {
     pub stages: Vec<Stage<T>>,
     pub envelope: Stage<T>,
     pub is_active: bool,
+    /// Index of the stage that produced NaN or infinite values. An index of `stages.len()` refers to the envelope.
+    pub faulty_stage: Option<usize>,
+}
+
+impl<T> Waveform<T> {
+    pub(crate) fn mute(&mut self, faulty_stage: usize) {
+        self.is_active = false;
+        self.faulty_stage = Some(faulty_stage);
+    }
 }
 
 #[derive(Copy, Clone)]
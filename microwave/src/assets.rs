@@ -1,13 +1,17 @@
-use std::{fs::File, path::Path};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
-use magnetron::envelope::EnvelopeSpec;
+use magnetron::{envelope::EnvelopeSpec, spec::Creator};
 use serde::{Deserialize, Serialize};
 use tune_cli::{CliError, CliResult};
 
 use crate::{
-    control::LiveParameter,
+    audio::AudioStage,
+    control::{LiveParameter, LiveParameterStorage, ModulationRoute},
     magnetron::{
-        effects::{EchoSpec, EffectSpec, RotarySpeakerSpec, SchroederReverbSpec},
+        effects::{EchoSpec, EffectSpec, LimiterSpec, RotarySpeakerSpec, SchroederReverbSpec},
         filter::{Filter, FilterKind, RingModulator},
         oscillator::{Modulation, OscillatorKind, OscillatorSpec},
         signal::{SignalKind, SignalSpec},
@@ -16,6 +20,7 @@ use crate::{
         InBufferSpec, NamedEnvelopeSpec, OutBufferSpec, OutSpec, StageSpec, TemplateSpec,
         WaveformProperty, WaveformSpec,
     },
+    model::PointerMapping,
 };
 
 #[derive(Deserialize, Serialize)]
@@ -25,6 +30,36 @@ pub struct MicrowaveConfig {
     pub waveforms: Vec<WaveformSpec<LfSource<WaveformProperty, LiveParameter>>>,
     pub effect_templates: Vec<TemplateSpec<LfSource<NoAccess, LiveParameter>>>,
     pub effects: Vec<EffectSpec<LfSource<NoAccess, LiveParameter>>>,
+    #[serde(default)]
+    pub pointer_mapping: PointerMapping,
+    #[serde(default)]
+    pub scales: Vec<ScaleSpec>,
+    #[serde(default)]
+    pub keyboards: Vec<KeyboardSpec>,
+    #[serde(default)]
+    pub modulation_routes: Vec<ModulationRoute>,
+}
+
+/// A scale that can be selected at runtime in addition to the one given on the command line.
+#[derive(Deserialize, Serialize)]
+pub struct ScaleSpec {
+    pub scl_file_location: PathBuf,
+    /// If absent, the keyboard mapping given on the command line is used. Cue scenes keep the current keyboard mapping instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kbm_file_location: Option<PathBuf>,
+}
+
+/// An on-screen keyboard strip. The strips are stacked from the bottom of the window upwards.
+#[derive(Deserialize, Serialize)]
+pub struct KeyboardSpec {
+    /// If absent, the currently selected scale is rendered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scl_file_location: Option<PathBuf>,
+    /// Color pattern of the scale degrees starting at the reference key, e.g. wgrwwgrwgrwgrwwgr for 17-EDO. If absent, keys are colored like a 12-EDO piano keyboard.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<String>,
+    /// Fraction of the window height covered by the strip.
+    pub height: f32,
 }
 
 impl MicrowaveConfig {
@@ -47,6 +82,22 @@ impl MicrowaveConfig {
             Ok(waveforms)
         }
     }
+
+    /// Creates the effects of the config. The effect templates are consumed.
+    pub fn take_effects(&mut self) -> Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>> {
+        let effect_templates = self
+            .effect_templates
+            .drain(..)
+            .map(|spec| (spec.name, spec.value))
+            .collect();
+
+        let creator = Creator::new(effect_templates, Default::default());
+
+        self.effects
+            .iter()
+            .map(|spec| creator.create(spec))
+            .collect()
+    }
 }
 
 pub fn get_builtin_waveforms() -> MicrowaveConfig {
@@ -169,10 +220,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 kind: OscillatorKind::Sin,
                 frequency: LfSource::template("WaveformPitch"),
                 phase: None,
+                position: None,
+                naive: false,
                 modulation: Modulation::None,
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0),
+                    pan: None,
                 },
             })],
         },
@@ -183,10 +237,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 kind: OscillatorKind::Sin3,
                 frequency: LfSource::template("WaveformPitch"),
                 phase: None,
+                position: None,
+                naive: false,
                 modulation: Modulation::None,
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0),
+                    pan: None,
                 },
             })],
         },
@@ -198,10 +255,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -212,6 +272,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -223,10 +284,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 kind: OscillatorKind::Triangle,
                 frequency: LfSource::template("WaveformPitch"),
                 phase: None,
+                position: None,
+                naive: false,
                 modulation: Modulation::None,
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0),
+                    pan: None,
                 },
             })],
         },
@@ -238,10 +302,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -250,6 +317,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -261,10 +329,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 kind: OscillatorKind::Square,
                 frequency: LfSource::template("WaveformPitch"),
                 phase: None,
+                position: None,
+                naive: false,
                 modulation: Modulation::None,
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0 / 4.0),
+                    pan: None,
                 },
             })],
         },
@@ -275,10 +346,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 kind: OscillatorKind::Sawtooth,
                 frequency: LfSource::template("WaveformPitch"),
                 phase: None,
+                position: None,
+                naive: false,
                 modulation: Modulation::None,
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0 / 2.0),
+                    pan: None,
                 },
             })],
         },
@@ -290,20 +364,26 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::Value(0.995) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 4.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::Value(1.005) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 4.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -316,20 +396,26 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::Value(0.995) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 4.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::Value(2.0 * 1.005) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 4.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -342,10 +428,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0 / 2.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -362,6 +451,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -374,22 +464,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(2.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -402,22 +498,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -430,22 +532,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(880.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -458,22 +566,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -486,22 +600,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(1),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -520,6 +640,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -532,40 +653,52 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(8.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(2.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-4.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(4.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(2.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(8.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-1.0 / 15.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -578,40 +711,52 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(8.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(2.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-4.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(4.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(2.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(6.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-1.0 / 15.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -624,40 +769,52 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(8.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(2.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-4.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(4.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(2.0 / 15.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(8.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-1.0 / 15.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -670,22 +827,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -698,10 +861,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(440.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
@@ -722,12 +888,15 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                         }
                         .wrap(),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -740,6 +909,8 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
@@ -749,18 +920,22 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             map1: LfSource::Value(880.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -773,22 +948,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(880.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -801,22 +982,28 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(4400.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::ByFrequency {
                         mod_buffer: InBufferSpec::Buffer(0),
                     },
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 2.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -829,50 +1016,65 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(16.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(3.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-8.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(5.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(4.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(7.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-2.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(9.0) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 31.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -885,50 +1087,65 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(16.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(2.9966) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-8.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(5.0394) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(4.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(7.1272) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(-2.0 / 31.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::Value(8.9797) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0 / 31.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -941,6 +1158,8 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
@@ -951,6 +1170,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             to: LfSource::Value(0.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -968,6 +1188,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -987,6 +1208,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             to: LfSource::Value(0.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1004,6 +1226,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1017,6 +1240,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(0.3),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1034,6 +1258,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1046,6 +1271,8 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
@@ -1056,6 +1283,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             to: LfSource::Value(0.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1073,6 +1301,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1092,6 +1321,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             to: LfSource::Value(0.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1105,6 +1335,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1118,6 +1349,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(0.3),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1135,6 +1367,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(1),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -1146,6 +1379,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1158,6 +1392,8 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
@@ -1167,6 +1403,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                             map1: LfSource::Value(1.0),
                         }
                         .wrap(),
+                        pan: None,
                     },
                 }),
                 StageSpec::Waveguide(WaveguideSpec {
@@ -1179,6 +1416,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(0.5),
+                        pan: None,
                     },
                 }),
             ],
@@ -1191,20 +1429,26 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(1.5) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(1),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::RingModulator(RingModulator {
@@ -1212,6 +1456,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1224,20 +1469,26 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sin3,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Oscillator(OscillatorSpec {
                     kind: OscillatorKind::Sin,
                     frequency: LfSource::Value(2.5) * LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(1),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::RingModulator(RingModulator {
@@ -1245,6 +1496,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1257,10 +1509,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0 / 2.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -1278,6 +1533,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1290,10 +1546,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Sawtooth,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0 / 2.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -1312,6 +1571,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1324,10 +1584,13 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     kind: OscillatorKind::Triangle,
                     frequency: LfSource::template("WaveformPitch"),
                     phase: None,
+                    position: None,
+                    naive: false,
                     modulation: Modulation::None,
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::Buffer(0),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
                 StageSpec::Filter(Filter {
@@ -1345,6 +1608,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                     out_spec: OutSpec {
                         out_buffer: OutBufferSpec::audio_out(),
                         out_level: LfSource::Value(1.0),
+                        pan: None,
                     },
                 }),
             ],
@@ -1367,6 +1631,7 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
                 out_spec: OutSpec {
                     out_buffer: OutBufferSpec::audio_out(),
                     out_level: LfSource::Value(1.0),
+                    pan: None,
                 },
             })],
         },
@@ -1433,6 +1698,11 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
             acceleration: LfSource::Value(6.0),
             deceleration: LfSource::Value(12.0),
         }),
+        EffectSpec::Limiter(LimiterSpec {
+            lookahead: 256,
+            threshold: LfSource::Value(0.9),
+            release: LfSource::Value(0.2),
+        }),
     ];
 
     MicrowaveConfig {
@@ -1441,5 +1711,9 @@ pub fn get_builtin_waveforms() -> MicrowaveConfig {
         waveforms,
         effect_templates,
         effects,
+        pointer_mapping: PointerMapping::default(),
+        scales: Vec::new(),
+        keyboards: Vec::new(),
+        modulation_routes: Vec::new(),
     }
 }
@@ -2,10 +2,11 @@ use std::{
     fs::File,
     io::BufWriter,
     sync::{
-        mpsc::{self, Receiver, Sender},
+        mpsc::{self, Receiver, Sender, SyncSender},
         Arc,
     },
     thread,
+    time::Instant,
 };
 
 use chrono::Local;
@@ -18,7 +19,10 @@ use hound::{WavSpec, WavWriter};
 use magnetron::automation::AutomationContext;
 use ringbuf::Producer;
 
-use crate::control::{LiveParameter, LiveParameterStorage};
+use crate::{
+    control::{LiveParameter, LiveParameterStorage},
+    governor::{LoadGovernor, LoadStatus, Quality},
+};
 
 pub fn get_output_stream_params(
     output_buffer_size: u32,
@@ -44,6 +48,7 @@ pub struct AudioOptions {
     pub input_buffer_size: u32,
     pub exchange_buffer_size: usize,
     pub wav_file_prefix: String,
+    pub adaptive_quality: bool,
 }
 
 pub struct AudioModel {
@@ -53,30 +58,45 @@ pub struct AudioModel {
     // Not dead, actually. Audio-in is active as long as this Stream is not dropped.
     #[allow(dead_code)]
     input_stream: Option<Stream>,
+    updates: Sender<UpdateFn>,
+    load_status: LoadStatus,
+    bypassed_stages: Receiver<BypassedStage>,
 }
 
 impl AudioModel {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         audio_stages: Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>>,
+        effects: Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>>,
         output_stream_params: (Device, StreamConfig, SampleFormat),
         options: AudioOptions,
         storage: LiveParameterStorage,
         storage_updates: Receiver<LiveParameterStorage>,
         audio_in: Producer<f64>,
+        master_out: Producer<f32>,
+        scope_out: Producer<f32>,
     ) -> Self {
         let (send, recv) = mpsc::channel();
+        // A bounded channel does not allocate on the audio thread
+        let (bypassed_send, bypassed_recv) = mpsc::sync_channel(16);
 
         let sample_rate = output_stream_params.1.sample_rate;
+        let load_status = LoadStatus::default();
         let audio_out = AudioOut {
             renderer: AudioRenderer {
                 buffer: vec![0.0; usize::try_from(options.output_buffer_size).unwrap() * 4],
-                audio_stages,
+                audio_stages: audio_stages.into_iter().map(GuardedStage::new).collect(),
+                effects: effects.into_iter().map(GuardedStage::new).collect(),
+                bypassed_stages: bypassed_send,
                 storage,
                 storage_updates,
                 current_wav_writer: None,
                 sample_rate_hz: sample_rate.0,
                 wav_file_prefix: Arc::new(options.wav_file_prefix),
                 updates: send.clone(),
+                master_out,
+                scope_out,
+                governor: LoadGovernor::new(options.adaptive_quality, load_status.clone()),
             },
             updates: recv,
         };
@@ -90,8 +110,51 @@ impl AudioModel {
             input_stream: options
                 .audio_in_enabled
                 .then(|| audio_in.start_stream(options.input_buffer_size, sample_rate)),
+            updates: send,
+            load_status,
+            bypassed_stages: bypassed_recv,
+        }
+    }
+
+    /// Reports the stages that have been bypassed by the audio thread since the last call.
+    pub fn report_bypassed_stages(&self) {
+        for bypassed_stage in self.bypassed_stages.try_iter() {
+            let stage_description = match bypassed_stage {
+                BypassedStage::AudioStage(index) => format!("Audio stage {}", index + 1),
+                BypassedStage::Effect(index) => format!("Effect {}", index + 1),
+            };
+            println!("[ERROR] {stage_description} produced NaN or infinite values. The stage has been bypassed.");
         }
     }
+
+    /// Mutes all audio stages, e.g. cuts the voices of sample players and clears the buffers of delay effects.
+    pub fn mute(&self) {
+        send_update(&self.updates, |renderer| {
+            for audio_stage in renderer
+                .audio_stages
+                .iter_mut()
+                .chain(&mut renderer.effects)
+            {
+                audio_stage.stage.mute();
+            }
+        })
+    }
+
+    /// Replaces the effects applied to the output of the audio stages, e.g. after the config file has been modified. Bypassed effects get a second chance.
+    pub fn replace_effects(&self, effects: Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>>) {
+        let mut effects: Vec<_> = effects.into_iter().map(GuardedStage::new).collect();
+        send_update(&self.updates, |renderer| {
+            for effect in &mut effects {
+                effect.stage.set_quality(renderer.governor.quality());
+            }
+            renderer.effects = effects
+        })
+    }
+
+    /// The quality and the load of the audio-out callback as determined by the [`LoadGovernor`].
+    pub fn load_status(&self) -> &LoadStatus {
+        &self.load_status
+    }
 }
 
 struct AudioOut {
@@ -131,13 +194,49 @@ impl AudioOut {
 
 struct AudioRenderer {
     buffer: Vec<f64>,
-    audio_stages: Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>>,
+    audio_stages: Vec<GuardedStage>,
+    effects: Vec<GuardedStage>,
+    bypassed_stages: SyncSender<BypassedStage>,
     storage: LiveParameterStorage,
     storage_updates: Receiver<LiveParameterStorage>,
     current_wav_writer: Option<WavWriter<BufWriter<File>>>,
     sample_rate_hz: u32,
     wav_file_prefix: Arc<String>,
     updates: Sender<UpdateFn>,
+    master_out: Producer<f32>,
+    scope_out: Producer<f32>,
+    governor: LoadGovernor,
+}
+
+/// An [`AudioStage`] that is no longer rendered once it produced NaN or infinite values. Muting is not sufficient since not every stage can reset its internal state.
+struct GuardedStage {
+    stage: Box<dyn AudioStage<((), LiveParameterStorage)>>,
+    bypassed: bool,
+}
+
+impl GuardedStage {
+    fn new(stage: Box<dyn AudioStage<((), LiveParameterStorage)>>) -> Self {
+        Self {
+            stage,
+            bypassed: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BypassedStage {
+    AudioStage(usize),
+    Effect(usize),
+}
+
+/// Replaces NaN or infinite values with zero s.t. a single faulty stage cannot take down the whole audio output. Returns `true` if any values were replaced.
+fn replace_non_finite_values(buffer: &mut [f64]) -> bool {
+    let mut values_replaced = false;
+    for sample in buffer.iter_mut().filter(|sample| !sample.is_finite()) {
+        *sample = 0.0;
+        values_replaced = true;
+    }
+    values_replaced
 }
 
 impl AudioRenderer {
@@ -161,19 +260,69 @@ impl AudioRenderer {
             render_window_secs: buffer.len() as f64 / self.sample_rate_hz as f64,
             payload: &((), self.storage),
         };
-        for audio_stage in &mut self.audio_stages {
-            audio_stage.render(buffer_f64, &context);
+        let render_start = Instant::now();
+        let num_audio_stages = self.audio_stages.len();
+        for (stage_index, audio_stage) in self
+            .audio_stages
+            .iter_mut()
+            .chain(&mut self.effects)
+            .enumerate()
+        {
+            if audio_stage.bypassed {
+                continue;
+            }
+            audio_stage.stage.render(buffer_f64, &context);
+            if replace_non_finite_values(buffer_f64) {
+                audio_stage.bypassed = true;
+                let bypassed_stage = match stage_index.checked_sub(num_audio_stages) {
+                    None => BypassedStage::AudioStage(stage_index),
+                    Some(effect_index) => BypassedStage::Effect(effect_index),
+                };
+                // Reports are dropped if the UI thread is too slow
+                let _ = self.bypassed_stages.try_send(bypassed_stage);
+            }
         }
 
+        let buffer_secs = buffer.len() as f64 / 2.0 / self.sample_rate_hz as f64;
+        let previous_quality = self.governor.quality();
+        let new_quality = self.governor.update(render_start.elapsed(), buffer_secs);
+
         for (src, dst) in buffer_f64.iter().zip(buffer.iter_mut()) {
             *dst = T::from(&(*src as f32));
         }
 
+        // Mono mix for the spectrogram and the scope. Samples are dropped if the UI thread is too slow.
+        let mono_mix = || {
+            buffer_f64
+                .chunks(2)
+                .map(|frame| ((frame[0] + frame[1]) / 2.0) as f32)
+        };
+        self.master_out.push_iter(&mut mono_mix());
+        self.scope_out.push_iter(&mut mono_mix());
+
         if let Some(wav_writer) = &mut self.current_wav_writer {
             for &sample in &*buffer {
                 wav_writer.write_sample(sample.to_f32()).unwrap();
             }
         }
+
+        if let Some(quality) = new_quality {
+            self.set_quality(previous_quality, quality);
+        }
+    }
+
+    fn set_quality(&mut self, previous_quality: Quality, quality: Quality) {
+        let load_percent = self.governor.load() * 100.0;
+        if quality < previous_quality {
+            println!(
+                "[WARNING] Audio load at {load_percent:.0}% - Lowering quality to {quality:?}"
+            );
+        } else {
+            println!("[INFO] Audio load at {load_percent:.0}% - Raising quality to {quality:?}");
+        }
+        for audio_stage in self.audio_stages.iter_mut().chain(&mut self.effects) {
+            audio_stage.stage.set_quality(quality);
+        }
     }
 
     fn set_recording_active(&self, recording_active: bool) {
@@ -185,8 +334,12 @@ impl AudioRenderer {
                 let wav_writer = create_wav_writer(sample_rate_hz, &wav_file_prefix);
                 send_update(&updates, move |renderer| {
                     renderer.current_wav_writer = Some(wav_writer);
-                    for audio_stage in &mut renderer.audio_stages {
-                        audio_stage.mute();
+                    for audio_stage in renderer
+                        .audio_stages
+                        .iter_mut()
+                        .chain(&mut renderer.effects)
+                    {
+                        audio_stage.stage.mute();
                     }
                 })
             } else {
@@ -285,4 +438,7 @@ pub trait AudioStage<T>: Send {
     fn render(&mut self, buffer: &mut [f64], context: &AutomationContext<T>);
 
     fn mute(&mut self);
+
+    /// Adapts the rendering effort to the given [`Quality`], e.g. by limiting the number of voices. Does nothing by default.
+    fn set_quality(&mut self, _quality: Quality) {}
 }
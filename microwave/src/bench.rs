@@ -1,7 +1,7 @@
 use std::{collections::BTreeMap, env, fs::File, io::Write, path::Path, thread, time::Instant};
 
 use magnetron::{spec::Creator, waveform::WaveformProperties, Magnetron};
-use rand::prelude::SliceRandom;
+use rand::{prelude::SliceRandom, rngs::SmallRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use tune_cli::{CliError, CliResult};
 
@@ -16,12 +16,16 @@ const SAMPLE_WIDTH_SECS: f64 = 1.0 / 44100.0;
 const NUM_RENDER_CYCLES: u16 = 50;
 const NUM_SIMULTANEOUS_WAVEFORMS: u16 = 25;
 
-pub fn run_benchmark() -> CliResult<()> {
+pub fn run_benchmark(seed: Option<u64>) -> CliResult<()> {
     let mut report = load_performance_report()?;
 
     let mut full_spec = assets::get_builtin_waveforms();
 
-    full_spec.waveforms.shuffle(&mut rand::thread_rng());
+    let mut rng = match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_entropy(),
+    };
+    full_spec.waveforms.shuffle(&mut rng);
 
     let templates = full_spec
         .waveform_templates
@@ -34,7 +38,7 @@ pub fn run_benchmark() -> CliResult<()> {
         .into_iter()
         .map(|spec| (spec.name, spec.spec))
         .collect();
-    let creator = Creator::new(templates, envelopes);
+    let creator = Creator::new(templates, envelopes).with_seed(rng.gen());
 
     for waveform_spec in full_spec.waveforms {
         run_benchmark_for_waveform(&mut report, &creator, waveform_spec);
@@ -1,8 +1,38 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::magnetron::source::StorageAccess;
+use crate::magnetron::source::{StorageAccess, TempoAccess};
+
+/// Tempo at which [`LiveParameter::Tempo`] is 0.
+pub const MIN_TEMPO_BPM: f64 = 30.0;
+
+/// Tempo at which [`LiveParameter::Tempo`] is 1.
+///
+/// The range is chosen s.t. every MIDI controller step changes the tempo by exactly 2 BPM, e.g. 120 BPM is reached at controller value 45.
+pub const MAX_TEMPO_BPM: f64 = 284.0;
+
+/// Glide time at which [`LiveParameter::Glide`] is 1 [s].
+pub const MAX_GLIDE_SECS: f64 = 2.0;
+
+/// Audio-in gain at which [`LiveParameter::InputGain`] is 0 [dB].
+pub const MIN_INPUT_GAIN_DB: f64 = -24.0;
+
+/// Audio-in gain at which [`LiveParameter::InputGain`] is 1 [dB].
+pub const MAX_INPUT_GAIN_DB: f64 = 24.0;
+
+/// Noise gate threshold at which [`LiveParameter::InputGate`] is 0 [dB]. Lower values disable the gate.
+pub const MIN_INPUT_GATE_DB: f64 = -80.0;
+
+/// Converts the given tempo to the corresponding value of [`LiveParameter::Tempo`].
+pub fn tempo_as_parameter_value(tempo_bpm: f64) -> f64 {
+    (tempo_bpm - MIN_TEMPO_BPM) / (MAX_TEMPO_BPM - MIN_TEMPO_BPM)
+}
 
 #[derive(Clone)]
 pub struct LiveParameterMapper {
@@ -24,6 +54,12 @@ impl LiveParameterMapper {
         self.ccn_mapping.get(&parameter).copied()
     }
 
+    pub fn mappings(&self) -> impl Iterator<Item = (LiveParameter, u8)> + '_ {
+        self.ccn_mapping
+            .iter()
+            .map(|(&parameter, &ccn)| (parameter, ccn))
+    }
+
     pub fn resolve_ccn(&self, controller: u8) -> Vec<LiveParameter> {
         self.ccn_mapping
             .iter()
@@ -32,6 +68,48 @@ impl LiveParameterMapper {
     }
 }
 
+/// Routes a MIDI source to a [`LiveParameter`] in addition to the control change numbers given on the command line.
+///
+/// The source value is normalized to the range [0, 1], shaped by `curve` and then mapped linearly s.t. 0 maps to `map0` and 1 maps to `map1`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModulationRoute {
+    pub source: ModulationSource,
+    pub target: LiveParameter,
+    #[serde(default)]
+    pub map0: f64,
+    #[serde(default = "default_map1")]
+    pub map1: f64,
+    /// Exponent applied to the normalized source value. Values above 1 provide finer control over small source values.
+    #[serde(default = "default_curve")]
+    pub curve: f64,
+}
+
+fn default_map1() -> f64 {
+    1.0
+}
+
+fn default_curve() -> f64 {
+    1.0
+}
+
+impl ModulationRoute {
+    pub fn map(&self, normalized_value: f64) -> f64 {
+        let shaped_value = normalized_value.clamp(0.0, 1.0).powf(self.curve);
+        self.map0 + (self.map1 - self.map0) * shaped_value
+    }
+}
+
+/// A MIDI-in source that can be routed to a [`LiveParameter`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ModulationSource {
+    ControlChange(u8),
+    ChannelPressure,
+    /// The most recent polyphonic key pressure of any key.
+    KeyPressure,
+    /// Full downward bend is normalized to 0, full upward bend to 1.
+    PitchBend,
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct LiveParameterStorage {
     modulation: f64,
@@ -55,6 +133,15 @@ pub struct LiveParameterStorage {
     sound_10: f64,
     channel_pressure: f64,
     pitch_bend: f64,
+    morph: f64,
+    tempo: f64,
+    glide: f64,
+    input_gain: f64,
+    input_gate: f64,
+    input_monitor: f64,
+    freeze: f64,
+    drone: f64,
+    movable_root: f64,
 }
 
 impl LiveParameterStorage {
@@ -81,6 +168,15 @@ impl LiveParameterStorage {
             LiveParameter::Sound10 => &mut self.sound_10,
             LiveParameter::ChannelPressure => &mut self.channel_pressure,
             LiveParameter::PitchBend => &mut self.pitch_bend,
+            LiveParameter::Morph => &mut self.morph,
+            LiveParameter::Tempo => &mut self.tempo,
+            LiveParameter::Glide => &mut self.glide,
+            LiveParameter::InputGain => &mut self.input_gain,
+            LiveParameter::InputGate => &mut self.input_gate,
+            LiveParameter::InputMonitor => &mut self.input_monitor,
+            LiveParameter::Freeze => &mut self.freeze,
+            LiveParameter::Drone => &mut self.drone,
+            LiveParameter::MovableRoot => &mut self.movable_root,
         } = value.max(-1.0).min(1.0)
     }
 
@@ -107,12 +203,77 @@ impl LiveParameterStorage {
             LiveParameter::Sound10 => self.sound_10,
             LiveParameter::ChannelPressure => self.channel_pressure,
             LiveParameter::PitchBend => self.pitch_bend,
+            LiveParameter::Morph => self.morph,
+            LiveParameter::Tempo => self.tempo,
+            LiveParameter::Glide => self.glide,
+            LiveParameter::InputGain => self.input_gain,
+            LiveParameter::InputGate => self.input_gate,
+            LiveParameter::InputMonitor => self.input_monitor,
+            LiveParameter::Freeze => self.freeze,
+            LiveParameter::Drone => self.drone,
+            LiveParameter::MovableRoot => self.movable_root,
         }
     }
 
     pub fn is_active(&self, parameter: LiveParameter) -> bool {
         self.read_parameter(parameter) >= 0.5
     }
+
+    pub fn set_tempo_bpm(&mut self, tempo_bpm: f64) {
+        self.set_parameter(LiveParameter::Tempo, tempo_as_parameter_value(tempo_bpm));
+    }
+
+    pub fn set_glide_secs(&mut self, glide_secs: f64) {
+        self.set_parameter(LiveParameter::Glide, glide_secs / MAX_GLIDE_SECS);
+    }
+
+    /// Time it takes a new note to slide from the pitch of the previous note to its own pitch. Zero if glide is disabled.
+    pub fn glide_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.glide.max(0.0) * MAX_GLIDE_SECS)
+    }
+
+    pub fn set_input_gain_db(&mut self, gain_db: f64) {
+        self.set_parameter(
+            LiveParameter::InputGain,
+            (gain_db - MIN_INPUT_GAIN_DB) / (MAX_INPUT_GAIN_DB - MIN_INPUT_GAIN_DB),
+        );
+    }
+
+    /// Linear amplification factor applied to the audio-in signal.
+    pub fn input_gain(&self) -> f64 {
+        let gain_db =
+            MIN_INPUT_GAIN_DB + self.input_gain.max(0.0) * (MAX_INPUT_GAIN_DB - MIN_INPUT_GAIN_DB);
+        db_to_amplitude(gain_db)
+    }
+
+    /// Sets the noise gate threshold of the audio-in signal. `None` disables the gate.
+    pub fn set_input_gate_db(&mut self, threshold_db: Option<f64>) {
+        self.set_parameter(
+            LiveParameter::InputGate,
+            threshold_db.map_or(0.0, |threshold_db| 1.0 - threshold_db / MIN_INPUT_GATE_DB),
+        );
+    }
+
+    /// Linear amplitude below which the audio-in signal is muted. `None` if the gate is disabled.
+    pub fn input_gate_threshold(&self) -> Option<f64> {
+        (self.input_gate > 0.0)
+            .then(|| db_to_amplitude((1.0 - self.input_gate) * MIN_INPUT_GATE_DB))
+    }
+
+    /// Level at which the processed audio-in signal is mixed into the output. Zero if monitoring is disabled.
+    pub fn input_monitor_level(&self) -> f64 {
+        self.input_monitor.max(0.0)
+    }
+}
+
+fn db_to_amplitude(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+impl TempoAccess for LiveParameterStorage {
+    fn tempo_bpm(&self) -> f64 {
+        MIN_TEMPO_BPM + self.tempo.max(0.0) * (MAX_TEMPO_BPM - MIN_TEMPO_BPM)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -138,6 +299,79 @@ pub enum LiveParameter {
     Sound10,
     ChannelPressure,
     PitchBend,
+    /// Position between the current scale preset (0) and the next one (1).
+    Morph,
+    /// Tempo of the global transport, mapped linearly from [`MIN_TEMPO_BPM`] (0) to [`MAX_TEMPO_BPM`] (1).
+    Tempo,
+    /// Glide time of new notes, mapped linearly from 0 (no glide) to [`MAX_GLIDE_SECS`] (1).
+    Glide,
+    /// Gain of the audio-in signal, mapped linearly from [`MIN_INPUT_GAIN_DB`] (0) to [`MAX_INPUT_GAIN_DB`] (1).
+    InputGain,
+    /// Noise gate threshold of the audio-in signal, mapped linearly from [`MIN_INPUT_GATE_DB`] (0, gate disabled) to 0 dB (1).
+    InputGate,
+    /// Level at which the audio-in signal is monitored on the output, from 0 (monitoring disabled) to 1 (unity gain).
+    InputMonitor,
+    /// Switch that captures the held notes of the waveform synth into a loop.
+    Freeze,
+    /// Switch that plucks the strings of the drone.
+    Drone,
+    /// Switch that makes the scale root follow the lowest held note.
+    MovableRoot,
+}
+
+const LIVE_PARAMETER_NAMES: [(&str, LiveParameter); 30] = [
+    ("modulation", LiveParameter::Modulation),
+    ("breath", LiveParameter::Breath),
+    ("foot", LiveParameter::Foot),
+    ("volume", LiveParameter::Volume),
+    ("expression", LiveParameter::Expression),
+    ("damper", LiveParameter::Damper),
+    ("sostenuto", LiveParameter::Sostenuto),
+    ("soft", LiveParameter::Soft),
+    ("legato", LiveParameter::Legato),
+    ("sound-1", LiveParameter::Sound1),
+    ("sound-2", LiveParameter::Sound2),
+    ("sound-3", LiveParameter::Sound3),
+    ("sound-4", LiveParameter::Sound4),
+    ("sound-5", LiveParameter::Sound5),
+    ("sound-6", LiveParameter::Sound6),
+    ("sound-7", LiveParameter::Sound7),
+    ("sound-8", LiveParameter::Sound8),
+    ("sound-9", LiveParameter::Sound9),
+    ("sound-10", LiveParameter::Sound10),
+    ("channel-pressure", LiveParameter::ChannelPressure),
+    ("pitch-bend", LiveParameter::PitchBend),
+    ("morph", LiveParameter::Morph),
+    ("tempo", LiveParameter::Tempo),
+    ("glide", LiveParameter::Glide),
+    ("input-gain", LiveParameter::InputGain),
+    ("input-gate", LiveParameter::InputGate),
+    ("input-monitor", LiveParameter::InputMonitor),
+    ("freeze", LiveParameter::Freeze),
+    ("drone", LiveParameter::Drone),
+    ("movable-root", LiveParameter::MovableRoot),
+];
+
+impl FromStr for LiveParameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LIVE_PARAMETER_NAMES
+            .iter()
+            .find(|&&(name, _)| name == s)
+            .map(|&(_, parameter)| parameter)
+            .ok_or_else(|| format!("Unknown live parameter '{s}'"))
+    }
+}
+
+impl Display for LiveParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (name, _) = LIVE_PARAMETER_NAMES
+            .iter()
+            .find(|&&(_, parameter)| parameter == *self)
+            .unwrap();
+        f.write_str(name)
+    }
 }
 
 impl StorageAccess for LiveParameter {
@@ -176,8 +410,110 @@ impl ParameterValue for u8 {
 
 #[cfg(test)]
 mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
     use super::*;
 
+    #[test]
+    fn parse_live_parameter() {
+        assert_eq!("breath".parse(), Ok(LiveParameter::Breath));
+        assert_eq!("sound-10".parse(), Ok(LiveParameter::Sound10));
+        assert_eq!("morph".parse(), Ok(LiveParameter::Morph));
+        assert_eq!("tempo".parse(), Ok(LiveParameter::Tempo));
+        assert_eq!("glide".parse(), Ok(LiveParameter::Glide));
+        assert_eq!("input-gate".parse(), Ok(LiveParameter::InputGate));
+        assert_eq!("freeze".parse(), Ok(LiveParameter::Freeze));
+        assert_eq!("movable-root".parse(), Ok(LiveParameter::MovableRoot));
+        assert_eq!(
+            "channel-pressure".parse(),
+            Ok(LiveParameter::ChannelPressure)
+        );
+        assert_eq!(LiveParameter::Sound10.to_string(), "sound-10");
+        assert_eq!(LiveParameter::MovableRoot.to_string(), "movable-root");
+        assert_eq!(
+            "Breath".parse::<LiveParameter>(),
+            Err("Unknown live parameter 'Breath'".to_owned())
+        );
+    }
+
+    #[test]
+    fn map_modulation_route() {
+        let route: ModulationRoute =
+            serde_yaml::from_str("source: ChannelPressure\ntarget: Sound1").unwrap();
+        assert_eq!(route.map(0.0), 0.0);
+        assert_eq!(route.map(0.5), 0.5);
+        assert_eq!(route.map(1.0), 1.0);
+
+        let route: ModulationRoute = serde_yaml::from_str(
+            "source:\n  ControlChange: 21\ntarget: Expression\nmap0: 1.0\nmap1: -1.0\ncurve: 2.0",
+        )
+        .unwrap();
+        assert_eq!(route.source, ModulationSource::ControlChange(21));
+        assert_eq!(route.map(0.0), 1.0);
+        assert_eq!(route.map(0.5), 0.5);
+        assert_eq!(route.map(1.0), -1.0);
+        assert_eq!(route.map(2.0), -1.0);
+    }
+
+    #[test]
+    fn set_and_read_tempo() {
+        let mut storage = LiveParameterStorage::default();
+        assert_eq!(storage.tempo_bpm(), MIN_TEMPO_BPM);
+
+        storage.set_tempo_bpm(120.0);
+        assert_eq!(storage.read_parameter(LiveParameter::Tempo).as_u8(), 45);
+        assert_eq!(storage.tempo_bpm(), 120.0);
+
+        for controller_value in 0..128u8 {
+            storage.set_parameter(LiveParameter::Tempo, controller_value.as_f64());
+            assert_eq!(
+                storage.tempo_bpm(),
+                MIN_TEMPO_BPM + 2.0 * f64::from(controller_value)
+            );
+        }
+
+        storage.set_tempo_bpm(1000.0);
+        assert_eq!(storage.tempo_bpm(), MAX_TEMPO_BPM);
+    }
+
+    #[test]
+    fn set_and_read_glide_time() {
+        let mut storage = LiveParameterStorage::default();
+        assert!(storage.glide_duration().is_zero());
+
+        storage.set_glide_secs(0.5);
+        assert_eq!(storage.read_parameter(LiveParameter::Glide), 0.25);
+        assert_eq!(storage.glide_duration(), Duration::from_millis(500));
+
+        storage.set_glide_secs(10.0);
+        assert_eq!(storage.glide_duration(), Duration::from_secs(2));
+
+        storage.set_parameter(LiveParameter::Glide, -1.0);
+        assert!(storage.glide_duration().is_zero());
+    }
+
+    #[test]
+    fn set_and_read_input_controls() {
+        let mut storage = LiveParameterStorage::default();
+        assert_approx_eq!(storage.input_gain(), 0.063_095_734_448_019);
+        assert_eq!(storage.input_gate_threshold(), None);
+        assert_eq!(storage.input_monitor_level(), 0.0);
+
+        storage.set_input_gain_db(0.0);
+        assert_eq!(storage.read_parameter(LiveParameter::InputGain), 0.5);
+        assert_approx_eq!(storage.input_gain(), 1.0);
+
+        storage.set_input_gain_db(12.0);
+        assert_approx_eq!(storage.input_gain(), 3.981_071_705_534_972);
+
+        storage.set_input_gate_db(Some(-40.0));
+        assert_eq!(storage.read_parameter(LiveParameter::InputGate), 0.5);
+        assert_approx_eq!(storage.input_gate_threshold().unwrap(), 0.01);
+
+        storage.set_input_gate_db(None);
+        assert_eq!(storage.input_gate_threshold(), None);
+    }
+
     #[test]
     fn as_f64_as_u8_invertibility() {
         for i in 0..128 {
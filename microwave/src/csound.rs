@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    mem,
+    net::{SocketAddr, UdpSocket},
+    sync::mpsc::Sender,
+};
+
+use tune::{
+    pitch::Pitch,
+    scala::{KbmRoot, Scl},
+};
+use tune_cli::CliResult;
+
+use crate::piano::Backend;
+
+/// Csound distinguishes instances of the same instrument by the fractional part of the instrument number.
+/// The IDs wrap around after this value, skipping the IDs of notes that are still held.
+const MAX_INSTANCE_ID: u16 = 9999;
+
+/// Plays every note as a held score event on a Csound instance started with the `--port` option.
+///
+/// The notes are sent as score lines via Csound's UDP server, i.e. no Csound library (e.g. via the `csound` crate) needs to be linked and Csound can run in a separate process or on a different machine.
+/// Every note is a separate instance (e.g. `i 1.0001`) with an indefinite duration. p4 is the exact frequency in Hz and p5 the velocity.
+/// Pitch changes are sent as tied notes and controllers are written to control channels.
+pub struct CsoundBackend<I, S> {
+    info_sender: Sender<I>,
+    socket: UdpSocket,
+    server_address: SocketAddr,
+    instruments: Vec<u16>,
+    curr_program: usize,
+    next_instance_id: u16,
+    active_instances: HashMap<S, (u16, u16)>,
+}
+
+pub fn create<I, S>(
+    info_sender: Sender<I>,
+    server_address: SocketAddr,
+    instruments: Vec<u16>,
+) -> CliResult<CsoundBackend<I, S>> {
+    if instruments.is_empty() {
+        return Err("At least one Csound instrument is required"
+            .to_owned()
+            .into());
+    }
+
+    let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+
+    Ok(CsoundBackend {
+        info_sender,
+        socket,
+        server_address,
+        instruments,
+        curr_program: 0,
+        next_instance_id: 1,
+        active_instances: HashMap::new(),
+    })
+}
+
+impl<I, S: Eq + Hash> CsoundBackend<I, S> {
+    fn send(&self, message: &str) {
+        if let Err(err) = self.socket.send_to(message.as_bytes(), self.server_address) {
+            eprintln!(
+                "[WARNING] Could not send message to Csound at {}: {err}",
+                self.server_address
+            );
+        }
+    }
+
+    /// Returns the next instance of the current instrument that is not held by an active note.
+    ///
+    /// If all instances are in use, the oldest ID is reused s.t. the new note is tied to the held one.
+    fn allocate_instance(&mut self) -> (u16, u16) {
+        let instrument = self.instruments[self.curr_program];
+        let first_candidate = self.next_instance_id;
+        loop {
+            let instance = (instrument, self.next_instance_id);
+            self.next_instance_id = self.next_instance_id % MAX_INSTANCE_ID + 1;
+            if !self
+                .active_instances
+                .values()
+                .any(|&active| active == instance)
+                || self.next_instance_id == first_candidate
+            {
+                return instance;
+            }
+        }
+    }
+
+    fn send_note(&self, (instrument, instance_id): (u16, u16), pitch: Pitch, velocity: u8) {
+        self.send(&format!(
+            "$i {instrument}.{instance_id:04} 0 -1 {} {velocity}",
+            pitch.as_hz()
+        ));
+    }
+
+    /// A negative instrument number turns off the held instance.
+    fn stop_instance(&self, (instrument, instance_id): (u16, u16)) {
+        self.send(&format!("$i -{instrument}.{instance_id:04} 0 0"));
+    }
+
+    fn set_channel(&self, channel: &str, value: f64) {
+        self.send(&format!("@{channel} {value}"));
+    }
+}
+
+impl<I: From<CsoundInfo> + Send, S: Copy + Eq + Hash + Send> Backend<S> for CsoundBackend<I, S> {
+    fn set_tuning(&mut self, _tuning: (&Scl, KbmRoot)) {}
+
+    fn set_no_tuning(&mut self) {}
+
+    fn send_status(&mut self) {
+        self.info_sender
+            .send(
+                CsoundInfo {
+                    server_address: self.server_address,
+                    program_number: self.curr_program,
+                    instrument: self.instruments[self.curr_program],
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+
+    fn start(&mut self, id: S, _degree: i32, pitch: Pitch, velocity: u8) {
+        let instance = self.allocate_instance();
+
+        if let Some(replaced_instance) = self.active_instances.insert(id, instance) {
+            self.stop_instance(replaced_instance);
+        }
+        self.send_note(instance, pitch, velocity);
+    }
+
+    fn update_pitch(&mut self, id: S, _degree: i32, pitch: Pitch, velocity: u8) {
+        if let Some(&instance) = self.active_instances.get(&id) {
+            self.send_note(instance, pitch, velocity);
+        }
+    }
+
+    fn update_pressure(&mut self, _id: S, _pressure: u8) {}
+
+    fn stop(&mut self, id: S, _velocity: u8) {
+        if let Some(instance) = self.active_instances.remove(&id) {
+            self.stop_instance(instance);
+        }
+    }
+
+    fn all_sound_off(&mut self) {
+        for (_, instance) in mem::take(&mut self.active_instances) {
+            self.stop_instance(instance);
+        }
+    }
+
+    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        self.curr_program = update_fn(self.curr_program).min(self.instruments.len() - 1);
+        self.send_status();
+    }
+
+    fn num_programs(&self) -> usize {
+        self.instruments.len()
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        self.set_channel(&format!("cc{controller}"), f64::from(value) / 127.0);
+    }
+
+    fn channel_pressure(&mut self, pressure: u8) {
+        self.set_channel("pressure", f64::from(pressure) / 127.0);
+    }
+
+    fn pitch_bend(&mut self, value: i16) {
+        self.set_channel("bend", f64::from(value) / 8192.0);
+    }
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+}
+
+pub struct CsoundInfo {
+    pub server_address: SocketAddr,
+    pub program_number: usize,
+    pub instrument: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::*;
+
+    fn create_backend() -> (
+        CsoundBackend<CsoundInfo, u8>,
+        UdpSocket,
+        mpsc::Receiver<CsoundInfo>,
+    ) {
+        let server = UdpSocket::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let (info_send, info_recv) = mpsc::channel();
+        let backend = create(info_send, server.local_addr().unwrap(), vec![3, 7]).unwrap();
+        (backend, server, info_recv)
+    }
+
+    fn receive(server: &UdpSocket) -> String {
+        let mut buffer = [0; 256];
+        let len = server.recv(&mut buffer).unwrap();
+        String::from_utf8(buffer[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn send_score_lines() {
+        let (mut backend, server, info_recv) = create_backend();
+
+        backend.start(0, 0, Pitch::from_hz(440.0), 100);
+        assert_eq!(receive(&server), "$i 3.0001 0 -1 440 100");
+
+        backend.update_pitch(0, 0, Pitch::from_hz(220.0), 100);
+        assert_eq!(receive(&server), "$i 3.0001 0 -1 220 100");
+
+        backend.pitch_bend(4096);
+        assert_eq!(receive(&server), "@bend 0.5");
+
+        backend.stop(0, 100);
+        assert_eq!(receive(&server), "$i -3.0001 0 0");
+
+        backend.program_change(Box::new(|program| program + 5));
+        assert_eq!(info_recv.try_recv().unwrap().instrument, 7);
+        backend.start(1, 0, Pitch::from_hz(440.0), 64);
+        assert_eq!(receive(&server), "$i 7.0002 0 -1 440 64");
+    }
+
+    #[test]
+    fn skip_held_instances_on_wrap_around() {
+        let (mut backend, server, _) = create_backend();
+
+        backend.start(0, 0, Pitch::from_hz(440.0), 100);
+        assert_eq!(receive(&server), "$i 3.0001 0 -1 440 100");
+
+        backend.next_instance_id = MAX_INSTANCE_ID;
+        backend.start(1, 0, Pitch::from_hz(440.0), 100);
+        assert_eq!(receive(&server), "$i 3.9999 0 -1 440 100");
+
+        // Instance 1 is still held
+        backend.start(2, 0, Pitch::from_hz(440.0), 100);
+        assert_eq!(receive(&server), "$i 3.0002 0 -1 440 100");
+    }
+}
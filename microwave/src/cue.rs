@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tune_cli::{CliError, CliResult};
+
+use crate::{assets::ScaleSpec, control::LiveParameter, model::ScalePreset};
+
+/// An ordered list of scenes to step through during a live set.
+#[derive(Deserialize, Serialize)]
+pub struct CueListSpec {
+    pub scenes: Vec<SceneSpec>,
+}
+
+/// The state to apply when a scene is entered. Absent fields leave the current state unchanged.
+#[derive(Deserialize, Serialize)]
+pub struct SceneSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scl_file_location: Option<PathBuf>,
+    /// If absent, the current keyboard mapping is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kbm_file_location: Option<PathBuf>,
+    /// Waveform number of the waveform synth or program number of the MIDI or soundfont backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<u8>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub parameters: HashMap<LiveParameter, f64>,
+}
+
+impl CueListSpec {
+    pub fn load(location: &Path) -> CliResult<Self> {
+        println!("[INFO] Loading cue list `{}`", location.display());
+        let file = File::open(location)?;
+        serde_yaml::from_reader(file)
+            .map_err(|err| CliError::CommandError(format!("Could not deserialize file: {err}")))
+    }
+}
+
+/// A [`SceneSpec`] with all files loaded in advance s.t. entering the scene does not cause any delays.
+pub struct Scene {
+    pub name: String,
+    pub scale: Option<ScalePreset>,
+    pub program: Option<u8>,
+    pub parameters: Vec<(LiveParameter, f64)>,
+}
+
+impl Scene {
+    pub fn load(
+        spec: SceneSpec,
+        create_scale_preset: impl Fn(ScaleSpec) -> CliResult<ScalePreset>,
+    ) -> CliResult<Self> {
+        let scale = match (spec.scl_file_location, spec.kbm_file_location) {
+            (Some(scl_file_location), kbm_file_location) => Some(create_scale_preset(ScaleSpec {
+                scl_file_location,
+                kbm_file_location,
+            })?),
+            (None, Some(_)) => {
+                return Err(CliError::CommandError(format!(
+                    "Scene `{}` has a kbm file but no scl file",
+                    spec.name
+                )))
+            }
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            name: spec.name,
+            scale,
+            program: spec.program,
+            parameters: spec.parameters.into_iter().collect(),
+        })
+    }
+}
+
+/// Keeps track of the current scene of a cue list.
+#[derive(Default)]
+pub struct CueList {
+    scenes: Vec<Scene>,
+    curr_scene: Option<usize>,
+    num_handled_triggers: u64,
+}
+
+impl CueList {
+    pub fn new(scenes: Vec<Scene>) -> Self {
+        Self {
+            scenes,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_scenes(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Returns the index and the [`Scene`] that has been entered most recently.
+    pub fn curr_scene(&self) -> Option<(usize, &Scene)> {
+        self.curr_scene.map(|index| (index, &self.scenes[index]))
+    }
+
+    /// Moves `delta` scenes forward or backward without wrapping around.
+    ///
+    /// Returns the new scene or [`None`] if the current scene did not change.
+    pub fn step_by(&mut self, delta: isize) -> Option<&Scene> {
+        if self.scenes.is_empty() {
+            return None;
+        }
+
+        let next_scene = match self.curr_scene {
+            Some(curr_scene) => (curr_scene as isize + delta).max(0) as usize,
+            None if delta > 0 => delta as usize - 1,
+            None => return None,
+        }
+        .min(self.scenes.len() - 1);
+
+        if self.curr_scene == Some(next_scene) {
+            return None;
+        }
+        self.curr_scene = Some(next_scene);
+        Some(&self.scenes[next_scene])
+    }
+
+    /// Returns the number of cue switch triggers that have not yet been handled.
+    pub fn take_triggers(&mut self, num_total_triggers: u64) -> u64 {
+        let num_new_triggers = num_total_triggers.saturating_sub(self.num_handled_triggers);
+        self.num_handled_triggers = num_total_triggers;
+        num_new_triggers
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use tune::pitch::Ratio;
+
+/// A string of the drone, tuned relative to the root of the current scale.
+#[derive(Clone, Copy, Debug)]
+pub enum DroneString {
+    /// Scale degree, e.g. 0 for the root.
+    Degree(i32),
+    /// Interval above the root, e.g. 3/2. The string is tuned to the nearest scale degree.
+    Ratio(Ratio),
+}
+
+impl FromStr for DroneString {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(degree) => Ok(DroneString::Degree(degree)),
+            Err(_) => s.parse().map(DroneString::Ratio),
+        }
+    }
+}
+
+/// Plucks the strings of a tanpura-like drone one after another.
+///
+/// A strumming cycle is followed by a rest of the length of one pluck interval s.t. the cycles are audible.
+pub struct Drone {
+    strings: Vec<DroneString>,
+    pluck_interval: Duration,
+    velocity: u8,
+    /// The index of the next string to be plucked and when it is due.
+    next_pluck: Option<(usize, Instant)>,
+}
+
+impl Drone {
+    pub fn new(strings: Vec<DroneString>, cycle_duration: Duration, velocity: u8) -> Self {
+        let num_intervals = u32::try_from(strings.len() + 1).unwrap_or(u32::MAX);
+        Self {
+            strings,
+            pluck_interval: cycle_duration / num_intervals,
+            velocity,
+            next_pluck: None,
+        }
+    }
+
+    pub fn velocity(&self) -> u8 {
+        self.velocity
+    }
+
+    /// Returns the index and the definition of the string to be plucked at `now`, if any. The first string is due immediately after the drone has been started.
+    pub fn pluck_due(&mut self, now: Instant) -> Option<(usize, DroneString)> {
+        let (index, due) = *self.next_pluck.get_or_insert((0, now));
+        if due > now {
+            return None;
+        }
+
+        let next_index = (index + 1) % self.strings.len().max(1);
+        let num_intervals = if next_index == 0 { 2 } else { 1 };
+        // Late plucks postpone the following ones s.t. no bursts occur after a stall
+        let next_due = (due + num_intervals * self.pluck_interval).max(now);
+        self.next_pluck = Some((next_index, next_due));
+
+        self.strings.get(index).map(|&string| (index, string))
+    }
+
+    /// Returns `true` if the drone was running.
+    pub fn stop(&mut self) -> bool {
+        self.next_pluck.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn parse_drone_strings() {
+        assert!(matches!("0".parse(), Ok(DroneString::Degree(0))));
+        assert!(matches!("-7".parse(), Ok(DroneString::Degree(-7))));
+        match "3/2".parse() {
+            Ok(DroneString::Ratio(ratio)) => assert_approx_eq!(ratio.as_float(), 1.5),
+            _ => unreachable!(),
+        }
+        assert!("fifth".parse::<DroneString>().is_err());
+    }
+
+    #[test]
+    fn strum_strings_with_rest() {
+        let strings = vec![
+            DroneString::Degree(-5),
+            DroneString::Degree(0),
+            DroneString::Degree(-12),
+        ];
+        let mut drone = Drone::new(strings, Duration::from_secs(4), 100);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        let plucks: Vec<_> = [0, 500, 1000, 1500, 2000, 2500, 3000, 3500, 4000, 4500]
+            .into_iter()
+            .map(|millis| drone.pluck_due(at(millis)).map(|(index, _)| index))
+            .collect();
+        assert_eq!(
+            plucks,
+            [
+                Some(0),
+                None,
+                Some(1),
+                None,
+                Some(2),
+                None,
+                None,
+                None,
+                Some(0),
+                None
+            ]
+        );
+
+        assert!(drone.stop());
+        assert!(!drone.stop());
+        assert_eq!(drone.pluck_due(at(4600)).map(|(index, _)| index), Some(0));
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use tune::key::PianoKey;
+
+use crate::{control::LiveParameter, model::SourceId, piano::TuningMode};
+
+/// Writes every engine event together with a microsecond timestamp to a JSONL file.
+///
+/// The file is written on a separate thread s.t. the engine is never blocked by file IO.
+pub struct EventLog {
+    entry_send: Sender<LogEntry>,
+    start: Instant,
+}
+
+impl EventLog {
+    pub fn create(location: &Path) -> io::Result<Self> {
+        let mut writer = LineWriter::new(File::create(location)?);
+        let (entry_send, entry_recv) = mpsc::channel::<LogEntry>();
+
+        thread::spawn(move || {
+            for entry in entry_recv {
+                if let Err(err) = serde_json::to_writer(&mut writer, &entry)
+                    .map_err(io::Error::from)
+                    .and_then(|()| writeln!(writer))
+                {
+                    eprintln!("[WARNING] Cannot write event log: {err}");
+                }
+            }
+        });
+
+        Ok(Self {
+            entry_send,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, event: LoggedEvent) {
+        let entry = LogEntry {
+            time_us: self.start.elapsed().as_micros() as u64,
+            event,
+        };
+
+        // The writer thread only terminates when the sender is dropped
+        self.entry_send.send(entry).unwrap();
+    }
+}
+
+/// A single line of the event log.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogEntry {
+    pub time_us: u64,
+    pub event: LoggedEvent,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum LoggedEvent {
+    NoteOn {
+        source: LoggedSource,
+        degree: i32,
+        pitch_hz: f64,
+        velocity: u8,
+    },
+    PitchUpdate {
+        source: LoggedSource,
+        degree: i32,
+        pitch_hz: f64,
+    },
+    NoteOff {
+        source: LoggedSource,
+        velocity: u8,
+    },
+    KeyPressure {
+        source: LoggedSource,
+        pressure: u8,
+    },
+    Parameter {
+        parameter: LiveParameter,
+        value: f64,
+    },
+    ControlChange {
+        controller: u8,
+        value: u8,
+    },
+    PitchBend {
+        value: i16,
+    },
+    ProgramChange {
+        program: u8,
+    },
+    ProgramStep {
+        delta: i8,
+    },
+    AllSoundOff,
+    Tuning {
+        mode: TuningMode,
+        ref_key: i32,
+        ref_pitch_hz: f64,
+        root_offset: i32,
+    },
+}
+
+/// Serializable counterpart of [`SourceId`].
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub enum LoggedSource {
+    Mouse,
+    Touchpad(u64),
+    Keyboard(i8, i8),
+    Midi(i32),
+    Drone(usize),
+}
+
+impl From<SourceId> for LoggedSource {
+    fn from(id: SourceId) -> Self {
+        match id {
+            SourceId::Mouse => LoggedSource::Mouse,
+            SourceId::Touchpad(id) => LoggedSource::Touchpad(id),
+            SourceId::Keyboard(x, y) => LoggedSource::Keyboard(x, y),
+            SourceId::Midi(key) => LoggedSource::Midi(key.midi_number()),
+            SourceId::Drone(index) => LoggedSource::Drone(index),
+        }
+    }
+}
+
+impl From<LoggedSource> for SourceId {
+    fn from(source: LoggedSource) -> Self {
+        match source {
+            LoggedSource::Mouse => SourceId::Mouse,
+            LoggedSource::Touchpad(id) => SourceId::Touchpad(id),
+            LoggedSource::Keyboard(x, y) => SourceId::Keyboard(x, y),
+            LoggedSource::Midi(midi_number) => {
+                SourceId::Midi(PianoKey::from_midi_number(midi_number))
+            }
+            LoggedSource::Drone(index) => SourceId::Drone(index),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::mpsc::{self, Sender};
+
+use tune::{
+    midi::ChannelMessageType,
+    pitch::Pitch,
+    scala::{KbmRoot, Scl},
+};
+use tune_cli::{shared::midi, CliResult};
+
+use crate::piano::Backend;
+
+/// Manufacturer ID reserved for non-commercial use.
+const NON_COMMERCIAL_ID: u8 = 0x7d;
+
+/// Field ID of the SysEx message containing the name of the selected waveform.
+const WAVEFORM_NAME_FIELD: u8 = 0x01;
+
+/// Field ID of the SysEx message containing the description of the current scale.
+const SCALE_NAME_FIELD: u8 = 0x02;
+
+/// Reports the state of microwave to a MIDI controller s.t. its displays, LED rings or motorized faders stay in sync.
+///
+/// Parameter values are sent as control changes, the selected program as a program change.
+/// Waveform and scale names are sent as SysEx messages of the form `F0 7D <field> <ASCII text> F7`.
+/// After every program change, all known controller values are sent again s.t. controllers that switch pages can restore their state.
+/// The [`crate::piano::PianoEngine`] sends the initial program and parameter values on startup and limits the program to the number of programs of the selected backend.
+pub struct MidiFeedback {
+    midi_send: Sender<Vec<u8>>,
+    channel: u8,
+    waveform_names: Vec<String>,
+    curr_program: usize,
+    controller_values: [Option<u8>; 128],
+}
+
+pub fn create(
+    target_port: &str,
+    channel: u8,
+    waveform_names: Vec<String>,
+) -> CliResult<MidiFeedback> {
+    if channel >= 16 {
+        return Err(format!("MIDI feedback channel {channel} is out of range").into());
+    }
+
+    let (_, mut midi_out) = midi::connect_to_out_device("microwave-feedback", target_port)?;
+
+    let (midi_send, midi_recv) = mpsc::channel::<Vec<u8>>();
+
+    crate::task::spawn(async move {
+        for message in midi_recv {
+            midi_out.send(&message).unwrap();
+        }
+    });
+
+    Ok(MidiFeedback {
+        midi_send,
+        channel,
+        waveform_names,
+        curr_program: 0,
+        controller_values: [None; 128],
+    })
+}
+
+impl MidiFeedback {
+    fn send_channel_message(&self, message_type: ChannelMessageType) {
+        let message = message_type.in_channel(self.channel).unwrap();
+        self.midi_send
+            .send(message.to_raw_message().to_vec())
+            .unwrap();
+    }
+
+    fn send_text(&self, field: u8, text: &str) {
+        let mut message = vec![0xf0, NON_COMMERCIAL_ID, field];
+        message.extend(text.chars().map(|c| match c.is_ascii() {
+            true => c as u8,
+            false => b'?',
+        }));
+        message.push(0xf7);
+        self.midi_send.send(message).unwrap();
+    }
+}
+
+impl<S> Backend<S> for MidiFeedback {
+    fn set_tuning(&mut self, (scl, _): (&Scl, KbmRoot)) {
+        self.send_text(SCALE_NAME_FIELD, scl.description());
+    }
+
+    fn set_no_tuning(&mut self) {}
+
+    fn send_status(&mut self) {}
+
+    fn start(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pitch(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pressure(&mut self, _id: S, _pressure: u8) {}
+
+    fn stop(&mut self, _id: S, _velocity: u8) {}
+
+    fn all_sound_off(&mut self) {}
+
+    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        self.curr_program = update_fn(self.curr_program).min(127);
+
+        self.send_channel_message(ChannelMessageType::ProgramChange {
+            program: u8::try_from(self.curr_program).unwrap(),
+        });
+        if let Some(waveform_name) = self.waveform_names.get(self.curr_program) {
+            self.send_text(WAVEFORM_NAME_FIELD, waveform_name);
+        }
+        for (controller, value) in (0..).zip(self.controller_values) {
+            if let Some(value) = value {
+                self.send_channel_message(ChannelMessageType::ControlChange { controller, value });
+            }
+        }
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        if let Some(stored_value) = self.controller_values.get_mut(usize::from(controller)) {
+            *stored_value = Some(value);
+            self.send_channel_message(ChannelMessageType::ControlChange { controller, value });
+        }
+    }
+
+    fn channel_pressure(&mut self, _pressure: u8) {}
+
+    fn pitch_bend(&mut self, _value: i16) {}
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+}
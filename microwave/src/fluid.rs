@@ -2,25 +2,51 @@ use std::{
     fmt::Debug,
     fs::File,
     hash::Hash,
-    path::Path,
-    sync::{mpsc::Sender, Arc},
+    mem,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
 };
 
 use fluid_xenth::{
-    oxisynth::{MidiEvent, SoundFont, SynthDescriptor},
-    TunableFluid, Xenth,
+    oxisynth::{MidiEvent, Preset, SoundFont, SoundFontId, SynthDescriptor},
+    ChannelCommand, TunableFluid, Xenth,
 };
 use magnetron::automation::AutomationContext;
 use tune::{
     pitch::Pitch,
     scala::{KbmRoot, Scl},
+    tuner::UntunableKey,
 };
 use tune_cli::CliResult;
 
-use crate::{audio::AudioStage, piano::Backend, tunable::TunableBackend};
+use crate::{
+    audio::AudioStage,
+    midi::{ALL_SOUND_OFF_CCN, BANK_SELECT_LSB_CCN, BANK_SELECT_MSB_CCN},
+    piano::Backend,
+    tunable::{JitClashes, TunableBackend},
+};
+
+/// Number of channels available to each bank.
+const POLYPHONY_PER_BANK: u8 = 16;
+
+/// The banks that are scanned for presets when a soundfont is loaded. This covers the melodic GM banks and the usual drum bank 128.
+const MAX_SCANNED_BANK: u32 = 128;
 
+/// Plays notes on a soundfont synthesizer.
+///
+/// The channels of the synthesizer are split into two banks. On every tuning change, the backend switches to the other bank.
+/// This way, the channel tunings of notes in their release phase remain untouched s.t. the release samples keep their original pitch.
+///
+/// If the scale fits into the 128 keys of the synthesizer, every key is tuned exactly on a single channel of the bank. Otherwise, the keys are distributed across the channels of the bank s.t. each note is detuned by 50c at most.
+///
+/// Programs and soundfont banks are always selected on all channels. Drum kits are regular soundfont banks (usually bank 128).
 pub struct FluidBackend<I, S> {
-    backend: TunableBackend<S, TunableFluid>,
+    banks: [TunableBackend<S, TunableFluid>; 2],
+    curr_bank: usize,
     soundfont_file_location: Option<Arc<str>>,
     info_sender: Sender<I>,
 }
@@ -29,145 +55,306 @@ pub fn create<I, S: Copy + Eq + Hash>(
     info_sender: Sender<I>,
     soundfont_file_location: Option<&Path>,
     sample_rate: f64,
-) -> CliResult<(FluidBackend<I, S>, FluidSynth)> {
+) -> CliResult<(FluidBackend<I, S>, FluidSynth, SoundFontUpdates)> {
     let synth_descriptor = SynthDescriptor {
         sample_rate: sample_rate as f32,
+        midi_channels: 2 * POLYPHONY_PER_BANK,
+        // All channels must sound the same since notes are distributed across them
+        drums_channel_active: false,
         ..Default::default()
     };
 
-    let (mut xenth, xenth_control) = fluid_xenth::create::<S>(synth_descriptor, 16).unwrap();
+    let (mut xenth, xenth_control) =
+        fluid_xenth::create::<S>(synth_descriptor, POLYPHONY_PER_BANK).unwrap();
+    let mut banks = xenth_control
+        .into_iter()
+        .map(|synth| TunableBackend::new(synth).with_single_channel_tuning());
 
+    let mut soundfont_id = None;
+    let mut soundfont_presets = Vec::new();
     if let Some(soundfont_file_location) = soundfont_file_location {
-        let mut soundfont_file = File::open(soundfont_file_location)?;
-        let soundfont = SoundFont::load(&mut soundfont_file)
-            .map_err(|()| "Could not load soundfont".to_owned())?;
-        xenth.synth_mut().add_font(soundfont, false);
+        let loaded = load_soundfont(soundfont_file_location)?;
+        soundfont_id = Some(xenth.synth_mut().add_font(loaded.soundfont, false));
+        soundfont_presets = loaded.presets;
     }
 
+    let (loaded_send, loaded_recv) = mpsc::channel();
+    let (replaced_send, replaced_recv) = mpsc::channel();
+
     Ok((
         FluidBackend {
-            backend: TunableBackend::new(xenth_control.into_iter().next().unwrap()),
+            banks: [banks.next().unwrap(), banks.next().unwrap()],
+            curr_bank: 0,
             soundfont_file_location: soundfont_file_location
                 .and_then(Path::to_str)
                 .map(|l| l.to_owned().into()),
             info_sender,
         },
-        FluidSynth { xenth },
+        FluidSynth {
+            xenth,
+            soundfont_id,
+            soundfont_presets,
+            loaded_soundfonts: loaded_recv,
+            replaced_presets: replaced_send,
+        },
+        SoundFontUpdates {
+            loaded_soundfonts: loaded_send,
+            replaced_presets: replaced_recv,
+            last_replaced_presets: Vec::new(),
+        },
     ))
 }
 
+/// A soundfont together with handles to its presets.
+pub struct LoadedSoundFont {
+    soundfont: SoundFont,
+    presets: Vec<Arc<Preset>>,
+}
+
+fn load_soundfont(soundfont_file_location: &Path) -> CliResult<LoadedSoundFont> {
+    let mut soundfont_file = File::open(soundfont_file_location)?;
+    let soundfont =
+        SoundFont::load(&mut soundfont_file).map_err(|()| "Could not load soundfont".to_owned())?;
+    let presets = (0..=MAX_SCANNED_BANK)
+        .flat_map(|bank| (0..128).map(move |program| (bank, program)))
+        .filter_map(|(bank, program)| soundfont.preset(bank, program))
+        .collect();
+    Ok(LoadedSoundFont { soundfont, presets })
+}
+
+/// Hands over reloaded soundfonts to the [`FluidSynth`].
+///
+/// Removing a soundfont from the synthesizer would free its samples on the audio thread.
+/// To prevent this, the [`FluidSynth`] holds handles to the presets of the active soundfont and returns them once the soundfont has been replaced.
+/// The samples are then freed on the thread that owns the [`SoundFontUpdates`].
+pub struct SoundFontUpdates {
+    loaded_soundfonts: Sender<LoadedSoundFont>,
+    replaced_presets: Receiver<Vec<Arc<Preset>>>,
+    /// Voices of a replaced soundfont can outlast the swap. Hence, the handles are only dropped when the next soundfont is replaced.
+    last_replaced_presets: Vec<Arc<Preset>>,
+}
+
+impl SoundFontUpdates {
+    /// Loading a soundfont can take a while. Do it in the background to keep the UI responsive.
+    pub fn reload(&self, location: PathBuf) {
+        let loaded_soundfonts = self.loaded_soundfonts.clone();
+        thread::spawn(move || {
+            println!("[INFO] Reloading soundfont `{}`", location.display());
+            match load_soundfont(&location) {
+                Ok(loaded) => loaded_soundfonts.send(loaded).unwrap(),
+                Err(err) => println!("[ERROR] Could not reload soundfont: {err:?}"),
+            }
+        });
+    }
+
+    pub fn drop_replaced_soundfonts(&mut self) {
+        for presets in self.replaced_presets.try_iter() {
+            self.last_replaced_presets = presets;
+        }
+    }
+}
+
+impl<I, S: Copy + Eq + Hash + Send + Debug> FluidBackend<I, S> {
+    fn bank_mut(&mut self) -> &mut TunableBackend<S, TunableFluid> {
+        &mut self.banks[self.curr_bank]
+    }
+
+    /// Releases the held notes of the current bank without touching its channel tunings and activates the other bank.
+    fn switch_bank(&mut self) {
+        self.bank_mut().set_no_tuning();
+        self.curr_bank = 1 - self.curr_bank;
+    }
+
+    fn send_to_all_banks(&mut self, mut create_command: impl FnMut() -> ChannelCommand) {
+        for bank in &mut self.banks {
+            bank.send_monophonic_message(create_command());
+        }
+    }
+}
+
 impl<I: From<FluidInfo> + Send + 'static, S: Copy + Eq + Hash + Send + Debug> Backend<S>
     for FluidBackend<I, S>
 {
     fn set_tuning(&mut self, tuning: (&Scl, KbmRoot)) {
-        self.backend.set_tuning(tuning);
+        if !self.bank_mut().has_tuning(tuning) {
+            self.switch_bank();
+            self.bank_mut().set_tuning(tuning);
+        }
     }
 
     fn set_no_tuning(&mut self) {
-        self.backend.set_no_tuning();
+        if self.bank_mut().is_aot() {
+            self.switch_bank();
+        }
+        self.bank_mut().set_no_tuning();
     }
 
     fn send_status(&mut self) {
-        let is_tuned = self.backend.is_tuned();
+        let is_tuned = self.bank_mut().is_tuned();
+        let untunable_degrees = self.bank_mut().untunable_degrees().to_vec();
+        let jit_clashes = self.bank_mut().jit_clashes().clone();
         let soundfont_file_location = self.soundfont_file_location.clone();
         let info_sender = self.info_sender.clone();
+        let mut status_sent = false;
 
-        self.backend
+        self.bank_mut()
             .send_monophonic_message(Box::new(move |s, channel| {
-                if channel == 0 {
-                    let preset = s.channel_preset(0);
+                if !status_sent {
+                    let preset = s.channel_preset(channel);
                     let program = preset.map(|p| p.num());
                     let program_name = preset.map(|p| p.name()).map(str::to_owned);
+                    let (soundfont_id, bank, _) = s.get_program(channel)?;
+                    let programs = soundfont_id
+                        .and_then(|soundfont_id| s.sfont(soundfont_id))
+                        .map(|soundfont| {
+                            (0..128)
+                                .filter_map(|program| soundfont.preset(bank, program))
+                                .map(|preset| (preset.num(), preset.name().to_owned()))
+                                .collect()
+                        })
+                        .unwrap_or_default();
                     info_sender
                         .send(
                             FluidInfo {
                                 soundfont_file_location: soundfont_file_location.clone(),
+                                bank,
                                 program,
                                 program_name,
+                                programs,
                                 is_tuned,
+                                untunable_degrees: untunable_degrees.clone(),
+                                jit_clashes: jit_clashes.clone(),
                             }
                             .into(),
                         )
                         .unwrap();
+                    status_sent = true;
                 }
                 Ok(())
             }));
     }
 
     fn start(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
-        self.backend.start(id, degree, pitch, velocity);
+        if self.bank_mut().start(id, degree, pitch, velocity) {
+            self.send_status();
+        }
     }
 
     fn update_pitch(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
-        self.backend.update_pitch(id, degree, pitch, velocity);
+        self.bank_mut().update_pitch(id, degree, pitch, velocity);
     }
 
     fn update_pressure(&mut self, id: S, pressure: u8) {
-        self.backend.update_pressure(id, pressure);
+        self.bank_mut().update_pressure(id, pressure);
     }
 
     fn stop(&mut self, id: S, velocity: u8) {
-        self.backend.stop(id, velocity);
+        self.bank_mut().stop(id, velocity);
     }
 
-    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
-        self.backend
-            .send_monophonic_message(Box::new(move |s, channel| {
+    fn all_sound_off(&mut self) {
+        for bank in &mut self.banks {
+            bank.release_all();
+        }
+        // All Sound Off also cuts the release phase of the notes
+        self.control_change(ALL_SOUND_OFF_CCN, 0);
+    }
+
+    fn program_change(&mut self, update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        let update_fn = Arc::new(Mutex::new(update_fn));
+        self.send_to_all_banks(|| {
+            let update_fn = update_fn.clone();
+            Box::new(move |s, channel| {
                 let (_, _, curr_program) = s.get_program(channel)?;
-                let updated_program =
-                    u8::try_from(update_fn(usize::try_from(curr_program).unwrap()).min(127))
-                        .unwrap();
+                let updated_program = u8::try_from(
+                    (update_fn.lock().unwrap())(usize::try_from(curr_program).unwrap()).min(127),
+                )
+                .unwrap();
                 s.send_event(MidiEvent::ProgramChange {
                     channel,
                     program_id: updated_program,
                 })
-            }));
+            })
+        });
     }
 
     fn control_change(&mut self, controller: u8, value: u8) {
-        self.backend
-            .send_monophonic_message(Box::new(move |s, channel| {
+        let is_bank_select = matches!(controller, BANK_SELECT_MSB_CCN | BANK_SELECT_LSB_CCN);
+        self.send_to_all_banks(|| {
+            Box::new(move |s, channel| {
                 s.send_event(MidiEvent::ControlChange {
                     channel,
                     ctrl: controller,
                     value,
-                })
-            }));
+                })?;
+                if is_bank_select {
+                    // The bank is applied immediately instead of waiting for the next program change
+                    let (_, _, program) = s.get_program(channel)?;
+                    s.send_event(MidiEvent::ProgramChange {
+                        channel,
+                        program_id: u8::try_from(program).unwrap(),
+                    })?;
+                }
+                Ok(())
+            })
+        });
     }
 
     fn channel_pressure(&mut self, pressure: u8) {
-        self.backend
-            .send_monophonic_message(Box::new(move |s, channel| {
+        self.send_to_all_banks(|| {
+            Box::new(move |s, channel| {
                 s.send_event(MidiEvent::ChannelPressure {
                     channel,
                     value: pressure,
                 })
-            }));
+            })
+        });
     }
 
     fn pitch_bend(&mut self, value: i16) {
-        self.backend
-            .send_monophonic_message(Box::new(move |s, channel| {
+        self.send_to_all_banks(|| {
+            Box::new(move |s, channel| {
                 s.send_event(MidiEvent::PitchBend {
                     channel,
                     value: u16::try_from(value + 8192).unwrap(),
                 })
-            }));
+            })
+        });
     }
 
     fn toggle_envelope_type(&mut self) {}
 
     fn has_legato(&self) -> bool {
-        self.backend.is_aot()
+        self.banks[self.curr_bank].is_aot()
+    }
+
+    fn has_continuous_pitch(&self) -> bool {
+        !self.banks[self.curr_bank].is_aot()
     }
 }
 
 pub struct FluidSynth {
     xenth: Xenth,
+    soundfont_id: Option<SoundFontId>,
+    soundfont_presets: Vec<Arc<Preset>>,
+    loaded_soundfonts: Receiver<LoadedSoundFont>,
+    replaced_presets: Sender<Vec<Arc<Preset>>>,
 }
 
 impl<T> AudioStage<T> for FluidSynth {
     fn render(&mut self, buffer: &mut [f64], _context: &AutomationContext<T>) {
+        for loaded in self.loaded_soundfonts.try_iter() {
+            let synth = self.xenth.synth_mut();
+            if let Some(soundfont_id) = self.soundfont_id.take() {
+                synth.remove_font(soundfont_id, false).unwrap();
+            }
+            // Reset the presets s.t. the channels refer to the presets of the new soundfont
+            self.soundfont_id = Some(synth.add_font(loaded.soundfont, true));
+            let replaced_presets = mem::replace(&mut self.soundfont_presets, loaded.presets);
+            // The receiver only disappears on shutdown
+            let _ = self.replaced_presets.send(replaced_presets);
+        }
+
         let mut index = 0;
         self.xenth
             .write(buffer.len() / 2, |(l, r)| {
@@ -184,7 +371,12 @@ impl<T> AudioStage<T> for FluidSynth {
 
 pub struct FluidInfo {
     pub soundfont_file_location: Option<Arc<str>>,
+    pub bank: u32,
     pub program: Option<u32>,
     pub program_name: Option<String>,
+    /// Numbers and names of the programs the soundfont provides in the current bank.
+    pub programs: Vec<(u32, String)>,
     pub is_tuned: bool,
+    pub untunable_degrees: Vec<UntunableKey<i32>>,
+    pub jit_clashes: JitClashes,
 }
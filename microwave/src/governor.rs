@@ -0,0 +1,220 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Audio load above which the quality is reduced.
+const OVERLOAD_THRESHOLD: f64 = 0.8;
+
+/// Audio load below which the quality may be restored.
+const RECOVERY_THRESHOLD: f64 = 0.5;
+
+/// Time the load must stay below [`RECOVERY_THRESHOLD`] before the quality is raised by one level [s].
+const RECOVERY_SECS: f64 = 5.0;
+
+/// Minimum time between two quality reductions s.t. the load average can settle [s].
+const HOLD_SECS: f64 = 0.5;
+
+/// Time constant of the load average [s].
+const LOAD_SMOOTHING_SECS: f64 = 0.2;
+
+/// Rendering quality of the audio stages. Lower levels trade sound quality for CPU time.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Quality {
+    Minimal,
+    Reduced,
+    Full,
+}
+
+impl Quality {
+    fn lower(self) -> Option<Self> {
+        match self {
+            Quality::Full => Some(Quality::Reduced),
+            Quality::Reduced => Some(Quality::Minimal),
+            Quality::Minimal => None,
+        }
+    }
+
+    fn higher(self) -> Option<Self> {
+        match self {
+            Quality::Full => None,
+            Quality::Reduced => Some(Quality::Full),
+            Quality::Minimal => Some(Quality::Reduced),
+        }
+    }
+
+    /// Maximum number of voices a synth should render at the same time. `None` if unlimited.
+    pub fn max_voices(self) -> Option<usize> {
+        match self {
+            Quality::Full => None,
+            Quality::Reduced => Some(24),
+            Quality::Minimal => Some(12),
+        }
+    }
+
+    /// Fraction of the resonators a reverb should render.
+    pub fn reverb_density(self) -> f64 {
+        match self {
+            Quality::Full => 1.0,
+            Quality::Reduced => 0.5,
+            Quality::Minimal => 0.25,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Quality::Reduced,
+            2 => Quality::Minimal,
+            _ => Quality::Full,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Quality::Full => 0,
+            Quality::Reduced => 1,
+            Quality::Minimal => 2,
+        }
+    }
+}
+
+/// The current quality and audio load, shared between the audio thread and the UI thread.
+#[derive(Clone, Default)]
+pub struct LoadStatus {
+    quality: Arc<AtomicU8>,
+    load_percent: Arc<AtomicU8>,
+}
+
+impl LoadStatus {
+    pub fn quality(&self) -> Quality {
+        Quality::from_u8(self.quality.load(Ordering::Relaxed))
+    }
+
+    /// Smoothed fraction of the available time the audio callback needs for rendering, in percent.
+    pub fn load_percent(&self) -> u8 {
+        self.load_percent.load(Ordering::Relaxed)
+    }
+}
+
+/// Monitors the headroom of the audio callback and lowers the [`Quality`] before buffer underruns occur.
+///
+/// The quality is raised again, one level at a time, once the load has been low for a while.
+pub struct LoadGovernor {
+    adaptive: bool,
+    status: LoadStatus,
+    quality: Quality,
+    load: f64,
+    secs_since_change: f64,
+    secs_below_recovery_threshold: f64,
+}
+
+impl LoadGovernor {
+    /// If `adaptive` is not set, the load is measured but the quality is kept at [`Quality::Full`].
+    pub fn new(adaptive: bool, status: LoadStatus) -> Self {
+        Self {
+            adaptive,
+            status,
+            quality: Quality::Full,
+            load: 0.0,
+            secs_since_change: 0.0,
+            secs_below_recovery_threshold: 0.0,
+        }
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    pub fn load(&self) -> f64 {
+        self.load
+    }
+
+    /// Records that it took `render_time` to render `buffer_secs` of audio. Returns the new quality if it has changed.
+    pub fn update(&mut self, render_time: Duration, buffer_secs: f64) -> Option<Quality> {
+        let current_load = render_time.as_secs_f64() / buffer_secs;
+        let smoothing = (-buffer_secs / LOAD_SMOOTHING_SECS).exp();
+        self.load = current_load + smoothing * (self.load - current_load);
+        self.status.load_percent.store(
+            (self.load * 100.0).round().min(255.0) as u8,
+            Ordering::Relaxed,
+        );
+
+        self.secs_since_change += buffer_secs;
+        if self.load < RECOVERY_THRESHOLD {
+            self.secs_below_recovery_threshold += buffer_secs;
+        } else {
+            self.secs_below_recovery_threshold = 0.0;
+        }
+
+        if !self.adaptive {
+            return None;
+        }
+
+        let new_quality = if self.load > OVERLOAD_THRESHOLD && self.secs_since_change >= HOLD_SECS {
+            self.quality.lower()
+        } else if self.secs_below_recovery_threshold >= RECOVERY_SECS {
+            self.quality.higher()
+        } else {
+            None
+        }?;
+
+        self.quality = new_quality;
+        self.secs_since_change = 0.0;
+        self.secs_below_recovery_threshold = 0.0;
+        self.status
+            .quality
+            .store(new_quality.as_u8(), Ordering::Relaxed);
+
+        Some(new_quality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER_SECS: f64 = 0.01;
+
+    fn run(governor: &mut LoadGovernor, load: f64, secs: f64) -> Vec<Quality> {
+        let render_time = Duration::from_secs_f64(load * BUFFER_SECS);
+        (0..(secs / BUFFER_SECS).round() as usize)
+            .filter_map(|_| governor.update(render_time, BUFFER_SECS))
+            .collect()
+    }
+
+    #[test]
+    fn adapt_quality_to_load() {
+        let status = LoadStatus::default();
+        let mut governor = LoadGovernor::new(true, status.clone());
+
+        assert!(run(&mut governor, 0.6, 2.0).is_empty());
+        assert_eq!(status.load_percent(), 60);
+
+        assert_eq!(
+            run(&mut governor, 0.95, 1.0),
+            [Quality::Reduced, Quality::Minimal]
+        );
+        assert_eq!(status.quality(), Quality::Minimal);
+
+        assert!(run(&mut governor, 0.6, 10.0).is_empty());
+
+        assert_eq!(
+            run(&mut governor, 0.3, 12.0),
+            [Quality::Reduced, Quality::Full]
+        );
+        assert_eq!(status.quality(), Quality::Full);
+    }
+
+    #[test]
+    fn keep_quality_if_not_adaptive() {
+        let status = LoadStatus::default();
+        let mut governor = LoadGovernor::new(false, status.clone());
+
+        assert!(run(&mut governor, 2.0, 2.0).is_empty());
+        assert_eq!(status.load_percent(), 200);
+        assert_eq!(status.quality(), Quality::Full);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+use tune::pitch::Pitch;
+
+use crate::{model::SourceId, piano::PressedKey};
+
+/// Records the pitches of the played notes s.t. they can be reviewed in a scrolling piano roll.
+///
+/// Every note is stored as a sequence of segments with constant pitch. A note that changes its pitch, e.g. during a glissando, starts a new segment.
+pub struct PitchHistory {
+    pub mode: HistoryMode,
+    time_span_secs: f32,
+    segments: Vec<PitchSegment>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistoryMode {
+    Hidden,
+    Visible,
+    VisibleWithRatios,
+}
+
+impl HistoryMode {
+    pub fn toggle(&mut self) {
+        *self = match *self {
+            HistoryMode::Hidden => HistoryMode::Visible,
+            HistoryMode::Visible => HistoryMode::VisibleWithRatios,
+            HistoryMode::VisibleWithRatios => HistoryMode::Hidden,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PitchSegment {
+    id: SourceId,
+    pub pitch: Pitch,
+    pub start_secs: f32,
+    /// [`None`] while the note is still sounding.
+    pub end_secs: Option<f32>,
+}
+
+impl PitchHistory {
+    pub fn new(time_span_secs: f32) -> Self {
+        Self {
+            mode: HistoryMode::Hidden,
+            time_span_secs,
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn time_span_secs(&self) -> f32 {
+        self.time_span_secs
+    }
+
+    pub fn segments(&self) -> &[PitchSegment] {
+        &self.segments
+    }
+
+    /// Closes the segments of released or retuned notes, opens segments for new pitches and forgets about segments that are out of the time span.
+    ///
+    /// The history is recorded even if it is hidden s.t. it is complete when the user decides to look at it.
+    pub fn update(&mut self, now_secs: f32, pressed_keys: &HashMap<SourceId, PressedKey>) {
+        let mut sounding_ids = HashSet::new();
+        for segment in &mut self.segments {
+            if segment.end_secs.is_some() {
+                continue;
+            }
+            match pressed_keys.get(&segment.id) {
+                Some(pressed_key) if pressed_key.pitch == segment.pitch => {
+                    sounding_ids.insert(segment.id);
+                }
+                _ => segment.end_secs = Some(now_secs),
+            }
+        }
+
+        for (&id, pressed_key) in pressed_keys {
+            if !sounding_ids.contains(&id) {
+                self.segments.push(PitchSegment {
+                    id,
+                    pitch: pressed_key.pitch,
+                    start_secs: now_secs,
+                    end_secs: None,
+                });
+            }
+        }
+
+        let oldest_secs = now_secs - self.time_span_secs;
+        self.segments.retain(|segment| {
+            segment
+                .end_secs
+                .map_or(true, |end_secs| end_secs >= oldest_secs)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressed_key(hz: f64) -> PressedKey {
+        PressedKey {
+            backend: 0,
+            degree: 0,
+            pitch: Pitch::from_hz(hz),
+        }
+    }
+
+    fn describe(history: &PitchHistory) -> Vec<(f64, f32, Option<f32>)> {
+        let mut segments: Vec<_> = history
+            .segments()
+            .iter()
+            .map(|segment| {
+                (
+                    segment.pitch.as_hz().round(),
+                    segment.start_secs,
+                    segment.end_secs,
+                )
+            })
+            .collect();
+        segments.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        segments
+    }
+
+    #[test]
+    fn record_segments_of_pressed_keys() {
+        let mut history = PitchHistory::new(10.0);
+        let mut pressed_keys = HashMap::new();
+
+        pressed_keys.insert(SourceId::Mouse, pressed_key(440.0));
+        pressed_keys.insert(SourceId::Keyboard(0, 0), pressed_key(330.0));
+        history.update(1.0, &pressed_keys);
+        history.update(2.0, &pressed_keys);
+        assert_eq!(describe(&history), [(330.0, 1.0, None), (440.0, 1.0, None)]);
+
+        // Glissando
+        pressed_keys.insert(SourceId::Mouse, pressed_key(550.0));
+        history.update(3.0, &pressed_keys);
+        assert_eq!(
+            describe(&history),
+            [
+                (330.0, 1.0, None),
+                (440.0, 1.0, Some(3.0)),
+                (550.0, 3.0, None)
+            ]
+        );
+
+        // Release
+        pressed_keys.remove(&SourceId::Keyboard(0, 0));
+        history.update(4.0, &pressed_keys);
+        assert_eq!(
+            describe(&history),
+            [
+                (330.0, 1.0, Some(4.0)),
+                (440.0, 1.0, Some(3.0)),
+                (550.0, 3.0, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn forget_segments_out_of_time_span() {
+        let mut history = PitchHistory::new(5.0);
+        let mut pressed_keys = HashMap::new();
+
+        pressed_keys.insert(SourceId::Mouse, pressed_key(440.0));
+        history.update(0.0, &pressed_keys);
+        pressed_keys.clear();
+        history.update(1.0, &pressed_keys);
+        pressed_keys.insert(SourceId::Mouse, pressed_key(220.0));
+        history.update(2.0, &pressed_keys);
+
+        history.update(6.0, &pressed_keys);
+        assert_eq!(
+            describe(&history),
+            [(220.0, 2.0, None), (440.0, 0.0, Some(1.0))]
+        );
+
+        // Sounding notes are kept regardless of their age
+        history.update(20.0, &pressed_keys);
+        assert_eq!(describe(&history), [(220.0, 2.0, None)]);
+    }
+}
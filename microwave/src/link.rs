@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tune_cli::CliResult;
+
+const MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 76, 78, 75);
+const MULTICAST_PORT: u16 = 20808;
+
+const PROTOCOL_HEADER: &[u8] = b"_asdp_v\x01";
+
+const MESSAGE_ALIVE: u8 = 1;
+const MESSAGE_RESPONSE: u8 = 2;
+const MESSAGE_BYEBYE: u8 = 3;
+
+const KEY_TIMELINE: u32 = u32::from_be_bytes(*b"tmln");
+const KEY_SESSION_MEMBERSHIP: u32 = u32::from_be_bytes(*b"sess");
+
+type NodeId = [u8; 8];
+
+/// Listens to the Ableton Link peers on the local network and calls `on_tempo_change` whenever the tempo of the prevailing session changes.
+///
+/// microwave does not announce itself as a peer. As a consequence, it follows the tempo of the other Link-enabled applications but cannot change it.
+///
+/// This is a minimal parser of the IPv4 discovery messages, not a binding to the official Link SDK. Only the tempo of the timeline is evaluated, i.e. beat and phase are not aligned.
+pub fn listen(mut on_tempo_change: impl FnMut(f64) + Send + 'static) -> CliResult<()> {
+    let socket = bind_multicast_socket()?;
+
+    println!("[INFO] Listening to Ableton Link peers on {MULTICAST_ADDR}:{MULTICAST_PORT}");
+
+    thread::spawn(move || {
+        let mut peers = Peers::default();
+        let mut current_tempo_bpm = None;
+        let mut buffer = [0; 512];
+
+        loop {
+            match socket.recv(&mut buffer) {
+                Ok(num_bytes) => {
+                    if let Some(message) = parse_message(&buffer[..num_bytes]) {
+                        peers.process(message, Instant::now());
+                    }
+                }
+                // The read timeout ensures that expired peers are cleaned up
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(err) => {
+                    println!("[WARNING] Could not receive Link message ({err})");
+                    return;
+                }
+            }
+
+            if let Some(tempo_bpm) = peers.session_tempo_bpm(Instant::now()) {
+                if current_tempo_bpm != Some(tempo_bpm) {
+                    current_tempo_bpm = Some(tempo_bpm);
+                    on_tempo_change(tempo_bpm);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn bind_multicast_socket() -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Other Link-enabled applications on the same host listen on the same port
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MULTICAST_PORT).into())?;
+    socket.join_multicast_v4(&MULTICAST_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+
+    let socket = UdpSocket::from(socket);
+    socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    Ok(socket)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    State {
+        node_id: NodeId,
+        ttl_secs: u8,
+        session_id: NodeId,
+        tempo_bpm: f64,
+    },
+    ByeBye {
+        node_id: NodeId,
+    },
+}
+
+/// Parses a Link discovery message. Messages without timeline or session membership information are ignored.
+fn parse_message(bytes: &[u8]) -> Option<Message> {
+    let bytes = bytes.strip_prefix(PROTOCOL_HEADER)?;
+
+    let message_type = *bytes.first()?;
+    let ttl_secs = *bytes.get(1)?;
+    // Bytes 2 and 3 contain the group ID which is always 0
+    let node_id = bytes.get(4..12)?.try_into().ok()?;
+    let mut payload = bytes.get(12..)?;
+
+    if message_type == MESSAGE_BYEBYE {
+        return Some(Message::ByeBye { node_id });
+    }
+    if message_type != MESSAGE_ALIVE && message_type != MESSAGE_RESPONSE {
+        return None;
+    }
+
+    let mut session_id = None;
+    let mut tempo_bpm = None;
+
+    while !payload.is_empty() {
+        let key = u32::from_be_bytes(payload.get(0..4)?.try_into().ok()?);
+        let size = u32::from_be_bytes(payload.get(4..8)?.try_into().ok()?);
+        let value = payload.get(8..8 + usize::try_from(size).ok()?)?;
+
+        match key {
+            KEY_TIMELINE => {
+                let micros_per_beat = i64::from_be_bytes(value.get(0..8)?.try_into().ok()?);
+                if micros_per_beat > 0 {
+                    tempo_bpm = Some(60_000_000.0 / micros_per_beat as f64);
+                }
+            }
+            KEY_SESSION_MEMBERSHIP => session_id = Some(value.get(0..8)?.try_into().ok()?),
+            _ => {}
+        }
+
+        payload = &payload[8 + value.len()..];
+    }
+
+    Some(Message::State {
+        node_id,
+        ttl_secs,
+        session_id: session_id?,
+        tempo_bpm: tempo_bpm?,
+    })
+}
+
+#[derive(Default)]
+struct Peers {
+    peers: HashMap<NodeId, Peer>,
+}
+
+struct Peer {
+    session_id: NodeId,
+    tempo_bpm: f64,
+    updated_at: Instant,
+    expires_at: Instant,
+}
+
+impl Peers {
+    fn process(&mut self, message: Message, now: Instant) {
+        match message {
+            Message::State {
+                node_id,
+                ttl_secs,
+                session_id,
+                tempo_bpm,
+            } => {
+                self.peers.insert(
+                    node_id,
+                    Peer {
+                        session_id,
+                        tempo_bpm,
+                        updated_at: now,
+                        expires_at: now + Duration::from_secs(ttl_secs.into()),
+                    },
+                );
+            }
+            Message::ByeBye { node_id } => {
+                self.peers.remove(&node_id);
+            }
+        }
+    }
+
+    /// Returns the tempo of the session with the most peers. Within a session, the most recent announcement wins.
+    fn session_tempo_bpm(&mut self, now: Instant) -> Option<f64> {
+        self.peers.retain(|_, peer| peer.expires_at >= now);
+
+        let mut session_sizes = HashMap::<_, usize>::new();
+        for peer in self.peers.values() {
+            *session_sizes.entry(peer.session_id).or_default() += 1;
+        }
+
+        let (session_id, _) = session_sizes
+            .into_iter()
+            .max_by_key(|&(session_id, size)| (size, session_id))?;
+
+        self.peers
+            .values()
+            .filter(|peer| peer.session_id == session_id)
+            .max_by_key(|peer| peer.updated_at)
+            .map(|peer| peer.tempo_bpm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_message(
+        message_type: u8,
+        node_id: u8,
+        session_id: u8,
+        micros_per_beat: i64,
+    ) -> Vec<u8> {
+        let mut message = PROTOCOL_HEADER.to_vec();
+        message.extend([message_type, 5, 0, 0]);
+        message.extend([node_id; 8]);
+
+        // Unknown entries are skipped
+        message.extend(b"mep4");
+        message.extend(6u32.to_be_bytes());
+        message.extend([127, 0, 0, 1, 0x51, 0x50]);
+
+        message.extend(b"tmln");
+        message.extend(24u32.to_be_bytes());
+        message.extend(micros_per_beat.to_be_bytes());
+        message.extend(0i64.to_be_bytes());
+        message.extend(0i64.to_be_bytes());
+
+        message.extend(b"sess");
+        message.extend(8u32.to_be_bytes());
+        message.extend([session_id; 8]);
+
+        message
+    }
+
+    fn alive(peers: &mut Peers, node_id: u8, session_id: u8, micros_per_beat: i64, now: Instant) {
+        let message = state_message(MESSAGE_ALIVE, node_id, session_id, micros_per_beat);
+        peers.process(parse_message(&message).unwrap(), now);
+    }
+
+    #[test]
+    fn parse_state_messages() {
+        assert_eq!(
+            parse_message(&state_message(MESSAGE_ALIVE, 1, 2, 500_000)),
+            Some(Message::State {
+                node_id: [1; 8],
+                ttl_secs: 5,
+                session_id: [2; 8],
+                tempo_bpm: 120.0,
+            })
+        );
+        assert_eq!(
+            parse_message(&state_message(MESSAGE_RESPONSE, 3, 4, 400_000)),
+            Some(Message::State {
+                node_id: [3; 8],
+                ttl_secs: 5,
+                session_id: [4; 8],
+                tempo_bpm: 150.0,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_byebye_message() {
+        let mut message = PROTOCOL_HEADER.to_vec();
+        message.extend([MESSAGE_BYEBYE, 0, 0, 0]);
+        message.extend([1; 8]);
+
+        assert_eq!(
+            parse_message(&message),
+            Some(Message::ByeBye { node_id: [1; 8] })
+        );
+    }
+
+    #[test]
+    fn reject_malformed_messages() {
+        let message = state_message(MESSAGE_ALIVE, 1, 2, 500_000);
+
+        assert_eq!(parse_message(&message[1..]), None);
+        assert_eq!(parse_message(&message[..message.len() - 1]), None);
+        assert_eq!(parse_message(&state_message(4, 1, 2, 500_000)), None);
+        assert_eq!(parse_message(&state_message(MESSAGE_ALIVE, 1, 2, 0)), None);
+    }
+
+    #[test]
+    fn follow_tempo_of_largest_session() {
+        let mut peers = Peers::default();
+        let now = Instant::now();
+
+        alive(&mut peers, 1, 1, 500_000, now);
+        alive(&mut peers, 2, 2, 400_000, now);
+        alive(&mut peers, 3, 2, 400_000, now);
+        assert_eq!(peers.session_tempo_bpm(now), Some(150.0));
+
+        // Latest tempo change within the session wins
+        alive(&mut peers, 2, 2, 600_000, now + Duration::from_secs(1));
+        assert_eq!(peers.session_tempo_bpm(now), Some(100.0));
+
+        peers.process(Message::ByeBye { node_id: [3; 8] }, now);
+        alive(&mut peers, 4, 1, 500_000, now);
+        assert_eq!(peers.session_tempo_bpm(now), Some(120.0));
+
+        // Peers expire after their TTL
+        assert_eq!(
+            peers.session_tempo_bpm(now + Duration::from_millis(5500)),
+            Some(100.0)
+        );
+        assert_eq!(peers.session_tempo_bpm(now + Duration::from_secs(7)), None);
+    }
+}
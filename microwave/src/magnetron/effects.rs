@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, f64::consts::TAU};
+use std::{cmp::Ordering, collections::VecDeque, f64::consts::TAU};
 
 use magnetron::{
     automation::{Automation, AutomationContext, AutomationSpec},
@@ -6,7 +6,7 @@ use magnetron::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::audio::AudioStage;
+use crate::{audio::AudioStage, governor::Quality};
 
 use super::util::{
     AllPassDelay, CombFilter, DelayLine, Interaction, OnePoleLowPass, SuccessiveInteractions,
@@ -17,6 +17,7 @@ pub enum EffectSpec<A> {
     Echo(EchoSpec<A>),
     SchroederReverb(SchroederReverbSpec<A>),
     RotarySpeaker(RotarySpeakerSpec<A>),
+    Limiter(LimiterSpec<A>),
 }
 
 impl<A: AutomationSpec> Spec<A> for EffectSpec<A> {
@@ -27,6 +28,7 @@ impl<A: AutomationSpec> Spec<A> for EffectSpec<A> {
             EffectSpec::Echo(spec) => Box::new(creator.create(spec)),
             EffectSpec::SchroederReverb(spec) => Box::new(creator.create(spec)),
             EffectSpec::RotarySpeaker(spec) => Box::new(creator.create(spec)),
+            EffectSpec::Limiter(spec) => Box::new(creator.create(spec)),
         }
     }
 }
@@ -184,6 +186,7 @@ impl<A: AutomationSpec> Spec<A> for SchroederReverbSpec<A> {
             combs,
             comb_feedback: creator.create(&self.comb_feedback),
             cutoff_hz: creator.create(&self.cutoff),
+            num_active_combs: self.combs.len(),
         }
     }
 }
@@ -207,6 +210,8 @@ pub struct SchroederReverb<T> {
     combs: Vec<Comb<T>>,
     comb_feedback: Automation<T>,
     cutoff_hz: Automation<T>,
+    /// Number of comb filters that are rendered. Reduced when the audio load is high.
+    num_active_combs: usize,
 }
 
 impl<T> AudioStage<T> for SchroederReverb<T> {
@@ -255,12 +260,14 @@ impl<T> AudioStage<T> for SchroederReverb<T> {
                 let mut reverbed_l = 0.0;
                 let mut reverbed_r = 0.0;
 
-                for (comb_l, comb_r, .., delay_l, delay_r) in &mut self.combs {
+                for (comb_l, comb_r, .., delay_l, delay_r) in
+                    &mut self.combs[..self.num_active_combs]
+                {
                     reverbed_l += comb_l.process_sample_fract(*delay_l, diffused_l);
                     reverbed_r += comb_r.process_sample_fract(*delay_r, diffused_r);
                 }
 
-                let normalization = self.combs.len() as f64;
+                let normalization = self.num_active_combs as f64;
 
                 *signal_l += reverbed_l / normalization;
                 *signal_r += reverbed_r / normalization;
@@ -278,6 +285,19 @@ impl<T> AudioStage<T> for SchroederReverb<T> {
             comb.1.mute();
         }
     }
+
+    fn set_quality(&mut self, quality: Quality) {
+        let num_combs = self.combs.len();
+        self.num_active_combs = ((num_combs as f64 * quality.reverb_density()).ceil() as usize)
+            .max(1)
+            .min(num_combs);
+
+        // Inactive combs start from silence when they are reactivated
+        for comb in &mut self.combs[self.num_active_combs..] {
+            comb.0.mute();
+            comb.1.mute();
+        }
+    }
 }
 
 /// A simulation of two (stereo) speakers oscillating radially wrt. the listener
@@ -392,3 +412,179 @@ impl<T> AudioStage<T> for RotarySpeaker<T> {
         self.delay_line_r.mute();
     }
 }
+
+/// A lookahead peak limiter that keeps the output below a given threshold, e.g. to prevent clipping when many waveforms are layered
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LimiterSpec<A> {
+    /// Lookahead time (samples). The output is delayed by this amount minus one sample s.t. the gain can be reduced smoothly before a peak arrives.
+    pub lookahead: usize,
+
+    /// Maximum output amplitude
+    pub threshold: A,
+
+    /// Time it takes the gain to recover after a peak (s)
+    pub release: A,
+}
+
+impl<A: AutomationSpec> Spec<A> for LimiterSpec<A> {
+    type Created = Limiter<A::Context>;
+
+    fn use_creator(&self, creator: &Creator<A>) -> Self::Created {
+        let lookahead = self.lookahead.max(1);
+        Limiter {
+            delay_line: vec![(0.0, 0.0); lookahead],
+            gains: vec![1.0; lookahead],
+            gain_sum: lookahead as f64,
+            minima: VecDeque::with_capacity(lookahead),
+            position: 0,
+            num_processed_samples: 0,
+            released_gain: 1.0,
+            threshold: creator.create(&self.threshold),
+            release_secs: creator.create(&self.release),
+        }
+    }
+}
+
+pub struct Limiter<T> {
+    delay_line: Vec<(f64, f64)>,
+    /// Released gains of the most recent samples. Their average is the applied gain.
+    gains: Vec<f64>,
+    gain_sum: f64,
+    /// Sample indices and required gains in ascending order of both, s.t. the first entry is the minimum required gain within the lookahead window.
+    minima: VecDeque<(usize, f64)>,
+    position: usize,
+    num_processed_samples: usize,
+    released_gain: f64,
+    threshold: Automation<T>,
+    release_secs: Automation<T>,
+}
+
+impl<T> Limiter<T> {
+    /// Returns the minimum required gain within the lookahead window.
+    fn hold_minimum(&mut self, required_gain: f64) -> f64 {
+        let lookahead = self.delay_line.len();
+        while let Some(&(index, _)) = self.minima.front() {
+            if index + lookahead > self.num_processed_samples {
+                break;
+            }
+            self.minima.pop_front();
+        }
+
+        while let Some(&(_, gain)) = self.minima.back() {
+            if gain < required_gain {
+                break;
+            }
+            self.minima.pop_back();
+        }
+        self.minima
+            .push_back((self.num_processed_samples, required_gain));
+
+        self.minima.front().map_or(1.0, |&(_, gain)| gain)
+    }
+}
+
+impl<T> AudioStage<T> for Limiter<T> {
+    fn render(&mut self, buffer: &mut [f64], context: &AutomationContext<T>) {
+        let (threshold, release_secs) =
+            context.read(&mut (&mut self.threshold, &mut self.release_secs));
+
+        let sample_width_secs = context.render_window_secs / buffer.len() as f64;
+        let release_coefficient = if release_secs > 0.0 {
+            (-sample_width_secs / release_secs).exp()
+        } else {
+            0.0
+        };
+
+        let lookahead = self.delay_line.len();
+
+        for signal_sample in buffer.chunks_mut(2) {
+            if let [signal_l, signal_r] = signal_sample {
+                let peak = signal_l.abs().max(signal_r.abs());
+                let required_gain = if peak > threshold {
+                    (threshold / peak).max(0.0)
+                } else {
+                    1.0
+                };
+
+                let held_gain = self.hold_minimum(required_gain);
+
+                self.released_gain = if held_gain < self.released_gain {
+                    held_gain
+                } else {
+                    held_gain + release_coefficient * (self.released_gain - held_gain)
+                };
+
+                // Averaging over the lookahead window turns gain steps into ramps that are complete when the peak leaves the delay line
+                self.gain_sum += self.released_gain - self.gains[self.position];
+                self.gains[self.position] = self.released_gain;
+
+                self.delay_line[self.position] = (*signal_l, *signal_r);
+                self.position = (self.position + 1) % lookahead;
+                let (delayed_l, delayed_r) = self.delay_line[self.position];
+
+                if self.position == 0 {
+                    // Prevent rounding errors from accumulating
+                    self.gain_sum = self.gains.iter().sum();
+                }
+                self.num_processed_samples += 1;
+
+                let gain = self.gain_sum / lookahead as f64;
+                *signal_l = delayed_l * gain;
+                *signal_r = delayed_r * gain;
+            }
+        }
+    }
+
+    fn mute(&mut self) {
+        self.delay_line
+            .iter_mut()
+            .for_each(|sample| *sample = (0.0, 0.0));
+        self.gains.iter_mut().for_each(|gain| *gain = 1.0);
+        self.gain_sum = self.gains.len() as f64;
+        self.minima.clear();
+        self.released_gain = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        control::{LiveParameter, LiveParameterStorage},
+        magnetron::source::{LfSource, NoAccess},
+    };
+
+    use super::*;
+
+    #[test]
+    fn limit_peaks() {
+        let spec: LimiterSpec<LfSource<NoAccess, LiveParameter>> =
+            serde_yaml::from_str("lookahead: 4\nthreshold: 0.5\nrelease: 0.0").unwrap();
+        let mut limiter = Creator::new(HashMap::new(), HashMap::new()).create(&spec);
+
+        let mut buffer = [[0.25, -0.25], [0.25, -0.25], [1.0, -0.5], [0.25, -0.25]].repeat(4);
+        buffer.extend([[0.25, -0.25]; 8]);
+        let mut buffer = buffer.concat();
+
+        let payload = ((), LiveParameterStorage::default());
+        let context = AutomationContext {
+            render_window_secs: buffer.len() as f64 / 44100.0,
+            payload: &payload,
+        };
+        limiter.render(&mut buffer, &context);
+
+        // The output is delayed by the lookahead minus one sample
+        assert_eq!(buffer[..6], [0.0; 6]);
+        for sample in &buffer {
+            assert!(
+                sample.abs() <= 0.5 + 1e-12,
+                "{sample} exceeds the threshold"
+            );
+        }
+        // Peaks are reduced exactly to the threshold
+        assert!(buffer.iter().any(|&sample| (sample - 0.5).abs() < 1e-12));
+        // The gain recovers immediately since the release time is zero
+        assert_eq!(buffer[buffer.len() - 2..], [0.25, -0.25]);
+    }
+}
@@ -2,6 +2,7 @@ use std::f64::consts::TAU;
 
 use magnetron::{
     automation::AutomationSpec,
+    flush_denormal,
     spec::{Creator, Spec},
     Stage, StageState,
 };
@@ -69,20 +70,16 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
         let out_buffer = self.out_spec.out_buffer.buffer();
 
         match &self.kind {
-            FilterKind::Copy => {
-                creator.create_stage(&self.out_spec.out_level, move |buffers, out_level| {
-                    buffers.read_1_and_write(in_buffer, out_buffer, out_level, |s| s);
-                    StageState::Active
-                })
-            }
-            FilterKind::Pow3 => {
-                creator.create_stage(&self.out_spec.out_level, move |buffers, out_level| {
-                    buffers.read_1_and_write(in_buffer, out_buffer, out_level, |s| s * s * s);
-                    StageState::Active
-                })
-            }
+            FilterKind::Copy => creator.create_stage(&self.out_spec, move |buffers, out_level| {
+                buffers.read_1_and_write(in_buffer, out_buffer, out_level, |s| s);
+                StageState::Active
+            }),
+            FilterKind::Pow3 => creator.create_stage(&self.out_spec, move |buffers, out_level| {
+                buffers.read_1_and_write(in_buffer, out_buffer, out_level, |s| s * s * s);
+                StageState::Active
+            }),
             FilterKind::Clip { limit } => creator.create_stage(
-                (&self.out_spec.out_level, limit),
+                (&self.out_spec, limit),
                 move |buffers, (out_level, limit)| {
                     buffers.read_1_and_write(in_buffer, out_buffer, out_level, |s| {
                         s.max(-limit).min(limit)
@@ -93,12 +90,12 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::LowPass { cutoff } => {
                 let mut out = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, cutoff),
+                    (&self.out_spec, cutoff),
                     move |buffers, (out_level, cutoff)| {
                         let omega_0 = TAU * cutoff * buffers.sample_width_secs();
                         let alpha = (1.0 + omega_0.recip()).recip();
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |input| {
-                            out += alpha * (input - out);
+                            out = flush_denormal(out + alpha * (input - out));
                             out
                         });
                         StageState::Active
@@ -108,7 +105,7 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::LowPass2 { resonance, quality } => {
                 let (mut y1, mut y2, mut x1, mut x2) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, resonance, quality),
+                    (&self.out_spec, resonance, quality),
                     move |buffers, (out_level, resonance, quality)| {
                         let quality = quality.max(1e-10);
 
@@ -125,7 +122,9 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
                         let a2 = 1.0 - alpha;
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |x0| {
-                            let y0 = (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+                            let y0 = flush_denormal(
+                                (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0,
+                            );
                             x2 = x1;
                             x1 = x0;
                             y2 = y1;
@@ -140,12 +139,12 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::HighPass { cutoff } => {
                 let (mut out, mut last_input) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, cutoff),
+                    (&self.out_spec, cutoff),
                     move |buffers, (out_level, cutoff)| {
                         let alpha = 1.0 / (1.0 + TAU * buffers.sample_width_secs() * cutoff);
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |input| {
-                            out = alpha * (out + input - last_input);
+                            out = flush_denormal(alpha * (out + input - last_input));
                             last_input = input;
                             out
                         });
@@ -157,7 +156,7 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::HighPass2 { resonance, quality } => {
                 let (mut y1, mut y2, mut x1, mut x2) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, resonance, quality),
+                    (&self.out_spec, resonance, quality),
                     move |buffers, (out_level, resonance, quality)| {
                         let quality = quality.max(1e-10);
 
@@ -174,7 +173,9 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
                         let a2 = 1.0 - alpha;
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |x0| {
-                            let y0 = (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+                            let y0 = flush_denormal(
+                                (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0,
+                            );
                             x2 = x1;
                             x1 = x0;
                             y2 = y1;
@@ -189,7 +190,7 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::BandPass { center, quality } => {
                 let (mut y1, mut y2, mut x1, mut x2) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, center, quality),
+                    (&self.out_spec, center, quality),
                     move |buffers, (out_level, center, quality)| {
                         let quality = quality.max(1e-10);
 
@@ -206,7 +207,9 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
                         let a2 = 1.0 - alpha;
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |x0| {
-                            let y0 = (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+                            let y0 = flush_denormal(
+                                (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0,
+                            );
                             x2 = x1;
                             x1 = x0;
                             y2 = y1;
@@ -221,7 +224,7 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::Notch { center, quality } => {
                 let (mut y1, mut y2, mut x1, mut x2) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, center, quality),
+                    (&self.out_spec, center, quality),
                     move |buffers, (out_level, center, quality)| {
                         let quality = quality.max(1e-10);
 
@@ -238,7 +241,9 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
                         let a2 = 1.0 - alpha;
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |x0| {
-                            let y0 = (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+                            let y0 = flush_denormal(
+                                (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0,
+                            );
                             x2 = x1;
                             x1 = x0;
                             y2 = y1;
@@ -253,7 +258,7 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
             FilterKind::AllPass { corner, quality } => {
                 let (mut y1, mut y2, mut x1, mut x2) = Default::default();
                 creator.create_stage(
-                    (&self.out_spec.out_level, corner, quality),
+                    (&self.out_spec, corner, quality),
                     move |buffers, (out_level, corner, quality)| {
                         let quality = quality.max(1e-10);
 
@@ -270,7 +275,9 @@ impl<A: AutomationSpec> Spec<A> for Filter<A> {
                         let a2 = b0;
 
                         buffers.read_1_and_write(in_buffer, out_buffer, out_level, |x0| {
-                            let y0 = (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+                            let y0 = flush_denormal(
+                                (b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2) / a0,
+                            );
                             x2 = x1;
                             x1 = x0;
                             y2 = y1;
@@ -300,7 +307,7 @@ impl<A: AutomationSpec> Spec<A> for RingModulator<A> {
         let in_buffers = (self.in_buffers.0.buffer(), self.in_buffers.1.buffer());
         let out_buffer = self.out_spec.out_buffer.buffer();
 
-        creator.create_stage(&self.out_spec.out_level, move |buffers, out_level| {
+        creator.create_stage(&self.out_spec, move |buffers, out_level| {
             buffers.read_2_and_write(in_buffers, out_buffer, out_level, |source_1, source_2| {
                 source_1 * source_2
             });
@@ -1,6 +1,6 @@
 use magnetron::{
-    automation::AutomationSpec,
-    buffer::{InBuffer, OutBuffer},
+    automation::{AutomatedValue, Automation, AutomationContext, AutomationSpec},
+    buffer::{InBuffer, OutBuffer, OutLevel},
     envelope::EnvelopeSpec,
     spec::{Creator, Spec},
     waveform::{Waveform, WaveformProperties},
@@ -16,14 +16,14 @@ use self::{
     waveguide::WaveguideSpec,
 };
 
-mod util;
-
 pub mod effects;
 pub mod filter;
 pub mod oscillator;
 pub mod signal;
 pub mod source;
+pub mod util;
 pub mod waveguide;
+pub mod wavetable;
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct TemplateSpec<A> {
@@ -62,6 +62,7 @@ impl<T, A: AutomationSpec<Context = (WaveformProperties, T)>> Spec<A> for Wavefo
                 creator.create_stage((), |_, _| StageState::Exhausted)
             }),
             is_active: true,
+            faulty_stage: None,
         }
     }
 }
@@ -146,6 +147,36 @@ impl InBufferSpec {
 pub struct OutSpec<A> {
     pub out_buffer: OutBufferSpec,
     pub out_level: A,
+    /// Stereo position of the output (-1 = left, 0 = center, 1 = right). Only applies to [`OutBufferSpec::AudioOut`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pan: Option<A>,
+}
+
+impl<A: AutomationSpec> Spec<A> for OutSpec<A> {
+    type Created = OutAutomation<A::Context>;
+
+    fn use_creator(&self, creator: &Creator<A>) -> Self::Created {
+        OutAutomation {
+            level: creator.create(&self.out_level),
+            pan: creator.create(&self.pan),
+        }
+    }
+}
+
+pub struct OutAutomation<T> {
+    level: Automation<T>,
+    pan: Option<Automation<T>>,
+}
+
+impl<T> AutomatedValue<T> for OutAutomation<T> {
+    type Value = OutLevel;
+
+    fn use_context(&mut self, context: &AutomationContext<T>) -> Self::Value {
+        OutLevel {
+            level: context.read(&mut self.level),
+            pan: context.read(&mut self.pan).unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -246,6 +277,115 @@ mod tests {
         assert_eq!(buffers.mix(), &[0f64; 128]);
     }
 
+    #[test]
+    fn pan_stages() {
+        let spec = parse_stages_spec(
+            r"
+- Oscillator:
+    kind: Sin
+    frequency: WaveformPitch
+    modulation: None
+    out_buffer: AudioOut
+    out_level: 1.0
+    pan: -1.0
+- Oscillator:
+    kind: Sin
+    frequency: WaveformPitch
+    modulation: None
+    out_buffer: AudioOut
+    out_level: 1.0
+    pan: 0.5",
+        );
+        let mut waveform = creator().create(&spec);
+
+        let mut buffers = magnetron();
+
+        buffers.clear(NUM_SAMPLES);
+        assert_eq!(buffers.mix_side(), &[0.0; NUM_SAMPLES]);
+
+        // Left: 1.0 + 0.5, right: 0.0 + 1.0
+        buffers.write(&mut waveform, &payload(440.0, 1.0));
+        assert_buffer_mix_is(&buffers, |t| 1.25 * t * (TAU * 440.0 * t).sin());
+        let mut time = 0.0;
+        for sample in buffers.mix_side() {
+            assert_approx_eq!(sample, 0.25 * time * (TAU * 440.0 * time).sin());
+            time += SAMPLE_WIDTH_SECS;
+        }
+    }
+
+    #[test]
+    fn reproducible_noise() {
+        let spec = parse_stages_spec(
+            r"
+- Signal:
+    kind: PinkNoise
+    out_buffer: AudioOut
+    out_level: 1.0",
+        );
+
+        let render = |creator: Creator<_>| {
+            let mut waveform = creator.create(&spec);
+            let mut buffers = magnetron();
+            buffers.clear(NUM_SAMPLES);
+            buffers.write(&mut waveform, &payload(440.0, 1.0));
+            buffers.mix().to_vec()
+        };
+
+        let first_render = render(creator().with_seed(42));
+        assert_eq!(render(creator().with_seed(42)), first_render);
+        assert_ne!(render(creator().with_seed(43)), first_render);
+        assert_ne!(render(creator()), first_render);
+    }
+
+    #[test]
+    fn mute_waveform_producing_nan() {
+        let spec = parse_stages_spec(
+            r"
+- Oscillator:
+    kind: Sin
+    frequency: WaveformPitch
+    modulation: None
+    out_buffer: 0
+    out_level: 1.0
+- Filter:
+    kind: Copy
+    in_buffer: 0
+    out_buffer: AudioOut
+    out_level: .nan",
+        );
+        let mut waveform = creator().create(&spec);
+
+        let mut buffers = magnetron();
+
+        buffers.clear(NUM_SAMPLES);
+        buffers.write(&mut waveform, &payload(440.0, 1.0));
+        assert_eq!(buffers.mix(), &[0.0; NUM_SAMPLES]);
+        assert!(!waveform.is_active);
+        assert_eq!(waveform.faulty_stage, Some(1));
+    }
+
+    #[test]
+    fn flush_denormals_to_zero() {
+        let spec = parse_stages_spec(
+            r"
+- Oscillator:
+    kind: Sin
+    frequency: WaveformPitch
+    modulation: None
+    out_buffer: AudioOut
+    out_level: 1e-310",
+        );
+        let mut waveform = creator().create(&spec);
+
+        let mut buffers = magnetron();
+
+        buffers.clear(NUM_SAMPLES);
+        buffers.write(&mut waveform, &payload(440.0, 1.0));
+        assert_eq!(buffers.mix(), &[0.0; NUM_SAMPLES]);
+        assert!(waveform.is_active);
+        assert_eq!(waveform.faulty_stage, None);
+    }
+
     #[test]
     fn mix_two_waveforms() {
         let spec = parse_stages_spec(
@@ -1,13 +1,13 @@
-use std::f64::consts::TAU;
+use std::{f64::consts::TAU, ops::Not};
 
 use magnetron::{
-    buffer::BufferWriter,
+    buffer::{BufferWriter, OutLevel},
     spec::{Creator, Spec},
     Stage, StageState,
 };
 use serde::{Deserialize, Serialize};
 
-use super::{AutomationSpec, InBufferSpec, OutSpec};
+use super::{wavetable::Wavetable, AutomationSpec, InBufferSpec, OutSpec};
 
 #[derive(Clone, Deserialize, Serialize)]
 pub enum OscillatorKind {
@@ -16,6 +16,7 @@ pub enum OscillatorKind {
     Triangle,
     Square,
     Sawtooth,
+    Wavetable(Wavetable),
 }
 
 impl OscillatorKind {
@@ -32,10 +33,14 @@ impl OscillatorKind {
                 (((0.75 + phase).fract() - 0.5).abs() - 0.25) * 4.0
             }),
             OscillatorKind::Square => {
-                oscillator_runner.apply_oscillator_fn(|phase: f64| (0.5 - phase).signum())
+                oscillator_runner.apply_discontinuous_shape(DiscontinuousShape::Square)
+            }
+            OscillatorKind::Sawtooth => {
+                oscillator_runner.apply_discontinuous_shape(DiscontinuousShape::Sawtooth)
+            }
+            OscillatorKind::Wavetable(wavetable) => {
+                oscillator_runner.apply_wavetable(wavetable.clone())
             }
-            OscillatorKind::Sawtooth => oscillator_runner
-                .apply_oscillator_fn(|phase: f64| ((0.5 + phase).fract() - 0.5) * 2.0),
         }
     }
 }
@@ -47,6 +52,60 @@ pub trait OscillatorRunner {
         &self,
         oscillator_fn: impl FnMut(f64) -> f64 + Send + 'static,
     ) -> Self::Result;
+
+    /// Applies a waveform with discontinuities. Runners that do not know about the oscillator's frequency use the naive, i.e. aliasing, waveform.
+    fn apply_discontinuous_shape(&self, shape: DiscontinuousShape) -> Self::Result {
+        self.apply_oscillator_fn(move |phase| shape.naive(phase))
+    }
+
+    /// Plays back a wavetable. Runners that do not know about the oscillator's frequency and position play the first frame with all harmonics.
+    fn apply_wavetable(&self, wavetable: Wavetable) -> Self::Result {
+        self.apply_oscillator_fn(move |phase| wavetable.sample(phase, 0.0, 0))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum DiscontinuousShape {
+    Square,
+    Sawtooth,
+}
+
+impl DiscontinuousShape {
+    fn naive(self, phase: f64) -> f64 {
+        match self {
+            DiscontinuousShape::Square => (0.5 - phase).signum(),
+            DiscontinuousShape::Sawtooth => ((0.5 + phase).fract() - 0.5) * 2.0,
+        }
+    }
+
+    /// Smooths the jumps of the naive waveform with a polynomial band-limited step (PolyBLEP) whose width is the phase increment `d_phase` of one sample.
+    fn poly_blep(self, phase: f64, d_phase: f64) -> f64 {
+        let shifted_phase = (0.5 + phase).fract();
+        match self {
+            DiscontinuousShape::Square => {
+                // Unlike `signum`, take the value after the jump at phase 0.5
+                let naive = if phase < 0.5 { 1.0 } else { -1.0 };
+                naive + poly_blep_residual(phase, d_phase)
+                    - poly_blep_residual(shifted_phase, d_phase)
+            }
+            DiscontinuousShape::Sawtooth => {
+                self.naive(phase) - poly_blep_residual(shifted_phase, d_phase)
+            }
+        }
+    }
+}
+
+/// Difference between a band-limited and a naive upward step of height 2 located at `phase` = 0.
+fn poly_blep_residual(phase: f64, d_phase: f64) -> f64 {
+    if phase < d_phase {
+        let t = phase / d_phase;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - d_phase {
+        let t = (phase - 1.0) / d_phase;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -54,6 +113,11 @@ pub struct OscillatorSpec<A> {
     pub kind: OscillatorKind,
     pub frequency: A,
     pub phase: Option<A>,
+    /// Morphs between the frames of a [`OscillatorKind::Wavetable`] (0 = first frame, 1 = last frame). Ignored by the other kinds.
+    pub position: Option<A>,
+    /// Disables the anti-aliasing of the [`OscillatorKind::Square`] and [`OscillatorKind::Sawtooth`] kinds.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub naive: bool,
     #[serde(flatten)]
     pub modulation: Modulation,
     #[serde(flatten)]
@@ -89,16 +153,40 @@ impl<A: AutomationSpec> OscillatorRunner for StageOscillatorRunner<'_, A> {
 
     fn apply_oscillator_fn(
         &self,
-        mut oscillator_fn: impl FnMut(f64) -> f64 + Send + 'static,
+        oscillator_fn: impl FnMut(f64) -> f64 + Send + 'static,
     ) -> Self::Result {
+        self.apply_waveform(FunctionWaveform(oscillator_fn))
+    }
+
+    fn apply_discontinuous_shape(&self, shape: DiscontinuousShape) -> Self::Result {
+        if self.spec.naive {
+            return self.apply_oscillator_fn(move |phase| shape.naive(phase));
+        }
+        self.apply_waveform(PolyBlepWaveform {
+            shape,
+            d_phase: 0.0,
+        })
+    }
+
+    fn apply_wavetable(&self, wavetable: Wavetable) -> Self::Result {
+        self.apply_waveform(WavetableWaveform {
+            wavetable,
+            position: 0.0,
+            level: 0,
+        })
+    }
+}
+
+impl<A: AutomationSpec> StageOscillatorRunner<'_, A> {
+    fn apply_waveform(&self, waveform: impl Waveform) -> Stage<A::Context> {
         let out_buffer = self.spec.out_spec.out_buffer.buffer();
 
         match &self.spec.modulation {
             Modulation::None => {
                 let mut phase = 0.0;
-                self.apply_modulation_fn(move |buffers, out_level, d_phase| {
+                self.apply_modulation_fn(waveform, move |buffers, waveform, out_level, d_phase| {
                     buffers.read_0_and_write(out_buffer, out_level, || {
-                        let signal = oscillator_fn(phase);
+                        let signal = waveform.sample(phase);
                         phase = (phase + d_phase).rem_euclid(1.0);
                         signal
                     });
@@ -108,9 +196,9 @@ impl<A: AutomationSpec> OscillatorRunner for StageOscillatorRunner<'_, A> {
                 let mod_buffer = mod_buffer.buffer();
 
                 let mut phase = 0.0;
-                self.apply_modulation_fn(move |buffers, out_level, d_phase| {
+                self.apply_modulation_fn(waveform, move |buffers, waveform, out_level, d_phase| {
                     buffers.read_1_and_write(mod_buffer, out_buffer, out_level, |s| {
-                        let signal = oscillator_fn((phase + s).rem_euclid(1.0));
+                        let signal = waveform.sample((phase + s).rem_euclid(1.0));
                         phase = (phase + d_phase).rem_euclid(1.0);
                         signal
                     });
@@ -120,10 +208,10 @@ impl<A: AutomationSpec> OscillatorRunner for StageOscillatorRunner<'_, A> {
                 let mod_buffer = mod_buffer.buffer();
 
                 let mut phase = 0.0;
-                self.apply_modulation_fn(move |buffers, out_level, d_phase| {
+                self.apply_modulation_fn(waveform, move |buffers, waveform, out_level, d_phase| {
                     let sample_width_secs = buffers.sample_width_secs();
                     buffers.read_1_and_write(mod_buffer, out_buffer, out_level, |s| {
-                        let signal = oscillator_fn(phase);
+                        let signal = waveform.sample(phase);
                         phase = (phase + d_phase + s * sample_width_secs).rem_euclid(1.0);
                         signal
                     });
@@ -131,27 +219,30 @@ impl<A: AutomationSpec> OscillatorRunner for StageOscillatorRunner<'_, A> {
             }
         }
     }
-}
 
-impl<A: AutomationSpec> StageOscillatorRunner<'_, A> {
-    fn apply_modulation_fn(
+    fn apply_modulation_fn<W: Waveform>(
         &self,
-        mut modulation_fn: impl FnMut(&mut BufferWriter, f64, f64) + Send + 'static,
+        mut waveform: W,
+        mut modulation_fn: impl FnMut(&mut BufferWriter, &mut W, OutLevel, f64) + Send + 'static,
     ) -> Stage<A::Context> {
         let mut saved_phase = 0.0;
         self.creator.create_stage(
             (
-                &self.spec.out_spec.out_level,
-                &self.spec.frequency,
-                &self.spec.phase,
+                (&self.spec.out_spec, &self.spec.frequency),
+                (&self.spec.phase, &self.spec.position),
             ),
-            move |buffers, (out_level, frequency, phase)| {
+            move |buffers, ((out_level, frequency), (phase, position))| {
                 let to_phase = phase.unwrap_or_default();
 
                 let d_phase = frequency * buffers.sample_width_secs()
                     + (to_phase - saved_phase) / buffers.buffer_len() as f64;
 
-                modulation_fn(buffers, out_level, d_phase);
+                waveform.update(
+                    frequency,
+                    position.unwrap_or_default(),
+                    buffers.sample_width_secs(),
+                );
+                modulation_fn(buffers, &mut waveform, out_level, d_phase);
 
                 saved_phase = to_phase;
 
@@ -161,6 +252,58 @@ impl<A: AutomationSpec> StageOscillatorRunner<'_, A> {
     }
 }
 
+/// Signal shape of a stage oscillator that can adapt to the current frequency and position once per buffer.
+trait Waveform: Send + 'static {
+    fn update(&mut self, frequency: f64, position: f64, sample_width_secs: f64);
+
+    fn sample(&mut self, phase: f64) -> f64;
+}
+
+struct FunctionWaveform<F>(F);
+
+impl<F: FnMut(f64) -> f64 + Send + 'static> Waveform for FunctionWaveform<F> {
+    fn update(&mut self, _frequency: f64, _position: f64, _sample_width_secs: f64) {}
+
+    fn sample(&mut self, phase: f64) -> f64 {
+        (self.0)(phase)
+    }
+}
+
+struct PolyBlepWaveform {
+    shape: DiscontinuousShape,
+    d_phase: f64,
+}
+
+impl Waveform for PolyBlepWaveform {
+    fn update(&mut self, frequency: f64, _position: f64, sample_width_secs: f64) {
+        self.d_phase = (frequency.abs() * sample_width_secs).min(0.5);
+    }
+
+    fn sample(&mut self, phase: f64) -> f64 {
+        self.shape.poly_blep(phase, self.d_phase)
+    }
+}
+
+struct WavetableWaveform {
+    wavetable: Wavetable,
+    position: f64,
+    level: usize,
+}
+
+impl Waveform for WavetableWaveform {
+    fn update(&mut self, frequency: f64, position: f64, sample_width_secs: f64) {
+        let nyquist_frequency = 0.5 / sample_width_secs;
+        self.level = self
+            .wavetable
+            .level_for(nyquist_frequency / frequency.abs());
+        self.position = position;
+    }
+
+    fn sample(&mut self, phase: f64) -> f64 {
+        self.wavetable.sample(phase, self.position, self.level)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_approx_eq::assert_approx_eq;
@@ -238,4 +381,28 @@ mod tests {
         assert_approx_eq!(sawtooth(6.0 / 8.0), -0.5);
         assert_approx_eq!(sawtooth(7.0 / 8.0), -0.25);
     }
+
+    #[test]
+    fn poly_blep_smooths_discontinuities() {
+        let d_phase = 0.01;
+        let square = |phase| DiscontinuousShape::Square.poly_blep(phase, d_phase);
+        let sawtooth = |phase| DiscontinuousShape::Sawtooth.poly_blep(phase, d_phase);
+
+        // Unaffected far away from the jumps
+        assert_approx_eq!(square(0.25), 1.0);
+        assert_approx_eq!(square(0.75), -1.0);
+        assert_approx_eq!(sawtooth(0.25), 0.5);
+        assert_approx_eq!(sawtooth(0.75), -0.5);
+
+        // Midpoint of the jumps
+        assert_approx_eq!(square(0.0), 0.0);
+        assert_approx_eq!(square(0.5), 0.0);
+        assert_approx_eq!(sawtooth(0.5), 0.0);
+
+        // Continuous transition
+        assert_approx_eq!(square(0.005), 0.75);
+        assert_approx_eq!(square(0.995), -0.75);
+        assert_approx_eq!(sawtooth(0.495), 0.74);
+        assert_approx_eq!(sawtooth(0.505), -0.74);
+    }
 }
@@ -9,14 +9,21 @@ use super::{AutomationSpec, OutSpec};
 
 #[derive(Serialize, Deserialize)]
 pub struct SignalSpec<A> {
-    pub kind: SignalKind,
+    pub kind: SignalKind<A>,
     #[serde(flatten)]
     pub out_spec: OutSpec<A>,
 }
 
 #[derive(Serialize, Deserialize)]
-pub enum SignalKind {
+pub enum SignalKind<A> {
+    /// White noise.
     Noise,
+    /// Noise with a power density falling off by 3dB per octave.
+    PinkNoise,
+    /// Noise with a power density falling off by 6dB per octave.
+    BrownNoise,
+    /// Random values held constant for `1/rate` seconds.
+    SampleAndHold { rate: A },
 }
 
 impl<A: AutomationSpec> Spec<A> for SignalSpec<A> {
@@ -25,15 +32,78 @@ impl<A: AutomationSpec> Spec<A> for SignalSpec<A> {
     fn use_creator(&self, creator: &Creator<A>) -> Self::Created {
         let out_buffer = self.out_spec.out_buffer.buffer();
 
-        match self.kind {
+        match &self.kind {
             SignalKind::Noise => {
-                let mut rng = SmallRng::from_entropy();
-                creator.create_stage(&self.out_spec.out_level, move |buffers, out_level| {
+                let mut rng = SmallRng::seed_from_u64(creator.next_seed());
+                creator.create_stage(&self.out_spec, move |buffers, out_level| {
                     buffers.read_0_and_write(out_buffer, out_level, || rng.gen_range(-1.0..1.0));
 
+                    StageState::Active
+                })
+            }
+            SignalKind::PinkNoise => {
+                let mut rng = SmallRng::seed_from_u64(creator.next_seed());
+                let mut pink_filter = PinkFilter::default();
+                creator.create_stage(&self.out_spec, move |buffers, out_level| {
+                    buffers.read_0_and_write(out_buffer, out_level, || {
+                        pink_filter.process(rng.gen_range(-1.0..1.0))
+                    });
+
+                    StageState::Active
+                })
+            }
+            SignalKind::BrownNoise => {
+                let mut rng = SmallRng::seed_from_u64(creator.next_seed());
+                let mut integral = 0.0;
+                creator.create_stage(&self.out_spec, move |buffers, out_level| {
+                    buffers.read_0_and_write(out_buffer, out_level, || {
+                        // Leaky integrator s.t. the signal does not drift away
+                        integral = (integral + 0.02 * rng.gen_range(-1.0..1.0)) / 1.02;
+                        3.5 * integral
+                    });
+
+                    StageState::Active
+                })
+            }
+            SignalKind::SampleAndHold { rate } => {
+                let mut rng = SmallRng::seed_from_u64(creator.next_seed());
+                let mut held_value = rng.gen_range(-1.0..1.0);
+                let mut phase = 0.0;
+                creator.create_stage((&self.out_spec, rate), move |buffers, (out_level, rate)| {
+                    let d_phase = rate * buffers.sample_width_secs();
+                    buffers.read_0_and_write(out_buffer, out_level, || {
+                        phase += d_phase;
+                        if phase >= 1.0 {
+                            phase = phase.fract();
+                            held_value = rng.gen_range(-1.0..1.0);
+                        }
+                        held_value
+                    });
+
                     StageState::Active
                 })
             }
         }
     }
 }
+
+/// Paul Kellet's refined pink noise filter. The output is scaled to roughly match the range of the white input signal.
+#[derive(Default)]
+struct PinkFilter {
+    b: [f64; 7],
+}
+
+impl PinkFilter {
+    fn process(&mut self, white: f64) -> f64 {
+        let b = &mut self.b;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        0.11 * pink
+    }
+}
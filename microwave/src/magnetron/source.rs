@@ -25,6 +25,11 @@ pub trait StorageAccess: Clone + Send + 'static {
     fn access(&mut self, storage: &Self::Storage) -> f64;
 }
 
+/// Storage that provides the tempo of the global transport, e.g. for [`LfSourceExpr::TempoSynced`].
+pub trait TempoAccess {
+    fn tempo_bpm(&self) -> f64;
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 pub enum NoAccess {}
 
@@ -126,6 +131,25 @@ pub enum LfSourceExpr<P, C> {
         map0: LfSource<P, C>,
         map1: LfSource<P, C>,
     },
+    /// Locks to the tempo of the global transport. `division` is the length of one cycle in beats, e.g. 0.5 for eighth notes.
+    TempoSynced {
+        division: LfSource<P, C>,
+        #[serde(default = "default_tempo_unit")]
+        unit: TempoUnit,
+    },
+}
+
+/// Unit of the value provided by [`LfSourceExpr::TempoSynced`].
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TempoUnit {
+    /// Cycles per second, e.g. for oscillator frequencies and rotary speaker speeds.
+    Hz,
+    /// Length of one cycle in seconds, e.g. for echo delay times.
+    Secs,
+}
+
+fn default_tempo_unit() -> TempoUnit {
+    TempoUnit::Hz
 }
 
 impl<P, C> LfSource<P, C> {
@@ -140,7 +164,10 @@ impl<P, C> LfSourceExpr<P, C> {
     }
 }
 
-impl<P: StorageAccess, C: StorageAccess> Spec<LfSource<P, C>> for LfSource<P, C> {
+impl<P: StorageAccess, C: StorageAccess> Spec<LfSource<P, C>> for LfSource<P, C>
+where
+    C::Storage: TempoAccess,
+{
     type Created = Automation<(P::Storage, C::Storage)>;
 
     fn use_creator(&self, creator: &Creator<LfSource<P, C>>) -> Self::Created {
@@ -216,6 +243,16 @@ impl<P: StorageAccess, C: StorageAccess> Spec<LfSource<P, C>> for LfSource<P, C>
                         kind.access(&context.payload.1)
                     })
                 }
+                &LfSourceExpr::TempoSynced { ref division, unit } => creator.create_automation(
+                    division,
+                    move |context: &AutomationContext<(P::Storage, C::Storage)>, division| {
+                        let cycle_secs = division * 60.0 / context.payload.1.tempo_bpm();
+                        match unit {
+                            TempoUnit::Hz => cycle_secs.recip(),
+                            TempoUnit::Secs => cycle_secs,
+                        }
+                    },
+                ),
             },
         }
     }
@@ -226,7 +263,10 @@ fn create_scaled_value_automation<P: StorageAccess, C: StorageAccess>(
     from: &LfSource<P, C>,
     to: &LfSource<P, C>,
     mut value_fn: impl FnMut(&AutomationContext<(P::Storage, C::Storage)>) -> f64 + Send + 'static,
-) -> Automation<(P::Storage, C::Storage)> {
+) -> Automation<(P::Storage, C::Storage)>
+where
+    C::Storage: TempoAccess,
+{
     creator.create_automation((from, to), move |context, (from, to)| {
         from + value_fn(context) * (to - from)
     })
@@ -240,7 +280,10 @@ struct LfSourceOscillatorRunner<'a, P, C> {
     amplitude: &'a LfSource<P, C>,
 }
 
-impl<P: StorageAccess, C: StorageAccess> OscillatorRunner for LfSourceOscillatorRunner<'_, P, C> {
+impl<P: StorageAccess, C: StorageAccess> OscillatorRunner for LfSourceOscillatorRunner<'_, P, C>
+where
+    C::Storage: TempoAccess,
+{
     type Result = Automation<(P::Storage, C::Storage)>;
 
     fn apply_oscillator_fn(
@@ -266,7 +309,10 @@ impl<P: StorageAccess, C: StorageAccess> OscillatorRunner for LfSourceOscillator
     }
 }
 
-impl<P: StorageAccess, C: StorageAccess> AutomationSpec for LfSource<P, C> {
+impl<P: StorageAccess, C: StorageAccess> AutomationSpec for LfSource<P, C>
+where
+    C::Storage: TempoAccess,
+{
     type Context = (P::Storage, C::Storage);
 }
 
@@ -294,7 +340,7 @@ mod tests {
     use magnetron::{automation::AutomationContext, spec::Creator, waveform::WaveformProperties};
 
     use crate::{
-        control::LiveParameter,
+        control::{LiveParameter, LiveParameterStorage},
         magnetron::{
             filter::{Filter, FilterKind},
             StageSpec, WaveformProperty,
@@ -329,6 +375,33 @@ Oscillator:
         assert_approx_eq!(context.read(&mut automation), (0.2 * TAU).cos());
     }
 
+    #[test]
+    fn lf_source_tempo_synced_correctness() {
+        let creator = Creator::new(HashMap::new(), HashMap::new());
+        let mut storage = LiveParameterStorage::default();
+        storage.set_tempo_bpm(120.0);
+
+        let context = AutomationContext {
+            render_window_secs: 1.0 / 100.0,
+            payload: &(WaveformProperties::initial(0.0, 0.0), storage),
+        };
+
+        let mut frequency = creator.create(parse_lf_source(
+            r"
+TempoSynced:
+  division: 0.25",
+        ));
+        assert_approx_eq!(context.read(&mut frequency), 8.0);
+
+        let mut delay_time = creator.create(parse_lf_source(
+            r"
+TempoSynced:
+  division: 0.75
+  unit: Secs",
+        ));
+        assert_approx_eq!(context.read(&mut delay_time), 0.375);
+    }
+
     #[test]
     fn deserialize_stage_with_missing_lf_source() {
         let yml = r"
@@ -428,7 +501,7 @@ Filter:
   out_level: 1.0";
         assert_eq!(
            get_parse_error(yml),
-            "Filter: unknown variant `InvalidExpr`, expected one of `Add`, `Mul`, `Linear`, `Oscillator`, `Time`, `Semitones`, `Property`, `Controller` at line 3 column 7"
+            "Filter: unknown variant `InvalidExpr`, expected one of `Add`, `Mul`, `Linear`, `Oscillator`, `Time`, `Semitones`, `Property`, `Controller`, `TempoSynced` at line 3 column 7"
         )
     }
 
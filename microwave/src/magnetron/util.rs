@@ -311,12 +311,63 @@ impl AllPassDelay {
     }
 }
 
+/// In-place iterative radix-2 FFT of complex numbers given as (re, im) pairs. The length of `buffer` must be a power of two.
+pub fn fft(buffer: &mut [(f64, f64)]) {
+    let len = buffer.len();
+    debug_assert!(len.is_power_of_two());
+
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= len {
+        let angle = -TAU / size as f64;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let (w_re, w_im) = ((angle * k as f64).cos(), (angle * k as f64).sin());
+                let (a_re, a_im) = buffer[start + k];
+                let (b_re, b_im) = buffer[start + k + size / 2];
+                let (t_re, t_im) = (b_re * w_re - b_im * w_im, b_re * w_im + b_im * w_re);
+                buffer[start + k] = (a_re + t_re, a_im + t_im);
+                buffer[start + k + size / 2] = (a_re - t_re, a_im - t_im);
+            }
+        }
+        size *= 2;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_approx_eq::assert_approx_eq;
 
     use super::*;
 
+    #[test]
+    fn fft_of_sine_wave() {
+        let mut buffer: Vec<_> = (0..64)
+            .map(|index| ((TAU * 5.0 * index as f64 / 64.0).sin(), 0.0))
+            .collect();
+        fft(&mut buffer);
+
+        for (bin, &(re, im)) in buffer.iter().enumerate() {
+            let magnitude = (re * re + im * im).sqrt();
+            match bin {
+                5 | 59 => assert_approx_eq!(magnitude, 32.0),
+                _ => assert_approx_eq!(magnitude, 0.0),
+            }
+        }
+    }
+
     #[test]
     fn soft_clip_correctness() {
         use assert_approx_eq::assert_approx_eq;
@@ -45,7 +45,7 @@ impl<A: AutomationSpec> Spec<A> for WaveguideSpec<A> {
 
         creator.create_stage(
             (
-                &self.out_spec.out_level,
+                &self.out_spec,
                 (&self.frequency, &self.cutoff, &self.feedback),
             ),
             move |buffers, (out_level, (frequency, cutoff, feedback))| {
@@ -0,0 +1,265 @@
+use std::{path::PathBuf, sync::Arc};
+
+use hound::{SampleFormat, WavReader};
+use serde::{Deserialize, Serialize};
+
+use super::util;
+
+/// Minimum number of samples per frame after loading. Shorter frames are upsampled s.t. short inline tables are played back smoothly.
+const MIN_FRAME_LEN: usize = 2048;
+
+/// Definition of a wavetable in the config file.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct WavetableSpec {
+    pub source: WavetableSource,
+    /// Number of samples per frame. Must be a power of two. If absent, the whole table is a single frame.
+    pub frame_size: Option<usize>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub enum WavetableSource {
+    Samples(Vec<f64>),
+    /// Location of a WAV file. Only the first channel is used.
+    WavFile(PathBuf),
+}
+
+/// A sequence of single-cycle frames, each of which is prepared for band-limited playback.
+///
+/// The table is loaded and analyzed when the config file is read s.t. notes can be started without any delay.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(try_from = "WavetableSpec", into = "WavetableSpec")]
+pub struct Wavetable {
+    spec: WavetableSpec,
+    /// Indexed by frame, level and sample. Level `l` contains the harmonics up to `max_harmonics[l]`.
+    frames: Arc<Vec<Vec<Vec<f64>>>>,
+    max_harmonics: Arc<Vec<usize>>,
+}
+
+impl TryFrom<WavetableSpec> for Wavetable {
+    type Error = String;
+
+    fn try_from(spec: WavetableSpec) -> Result<Self, Self::Error> {
+        let samples = match &spec.source {
+            WavetableSource::Samples(samples) => samples.clone(),
+            WavetableSource::WavFile(location) => load_wav_file(location)
+                .map_err(|err| format!("Could not load wavetable {}: {err}", location.display()))?,
+        };
+
+        let frame_size = spec.frame_size.unwrap_or(samples.len());
+        if frame_size < 2 || !frame_size.is_power_of_two() {
+            return Err(format!(
+                "Invalid wavetable frame size {frame_size}: Must be a power of two"
+            ));
+        }
+        if samples.len() % frame_size != 0 {
+            return Err(format!(
+                "Invalid wavetable length {}: Must be a multiple of the frame size {frame_size}",
+                samples.len()
+            ));
+        }
+
+        let max_harmonics = (0..)
+            .map(|level| (frame_size / 2) >> level)
+            .take_while(|&max_harmonic| max_harmonic > 0)
+            .collect::<Vec<_>>();
+
+        let frames = samples
+            .chunks_exact(frame_size)
+            .map(|frame| {
+                let spectrum = spectrum_of(frame);
+                max_harmonics
+                    .iter()
+                    .map(|&max_harmonic| band_limited_frame(&spectrum, max_harmonic))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            spec,
+            frames: Arc::new(frames),
+            max_harmonics: Arc::new(max_harmonics),
+        })
+    }
+}
+
+impl From<Wavetable> for WavetableSpec {
+    fn from(wavetable: Wavetable) -> Self {
+        wavetable.spec
+    }
+}
+
+impl Wavetable {
+    /// Returns the level with the most harmonics that does not exceed `max_harmonic`. Falls back to the level with the fundamental only.
+    pub fn level_for(&self, max_harmonic: f64) -> usize {
+        self.max_harmonics
+            .iter()
+            .position(|&num_harmonics| num_harmonics as f64 <= max_harmonic)
+            .unwrap_or(self.max_harmonics.len() - 1)
+    }
+
+    /// Reads the table at the given `phase` (0 to 1) and `position` (0 = first frame, 1 = last frame). Adjacent frames and samples are interpolated linearly.
+    pub fn sample(&self, phase: f64, position: f64, level: usize) -> f64 {
+        let frame_position = position.clamp(0.0, 1.0) * (self.frames.len() - 1) as f64;
+        let lower_frame = frame_position as usize;
+        let upper_frame = (lower_frame + 1).min(self.frames.len() - 1);
+        let fraction = frame_position - lower_frame as f64;
+
+        let lower = read_frame(&self.frames[lower_frame][level], phase);
+        let upper = read_frame(&self.frames[upper_frame][level], phase);
+        lower + fraction * (upper - lower)
+    }
+}
+
+fn read_frame(frame: &[f64], phase: f64) -> f64 {
+    let position = phase.rem_euclid(1.0) * frame.len() as f64;
+    let index = position as usize % frame.len();
+    let next_index = (index + 1) % frame.len();
+    let fraction = position.fract();
+    frame[index] + fraction * (frame[next_index] - frame[index])
+}
+
+fn spectrum_of(frame: &[f64]) -> Vec<(f64, f64)> {
+    let mut spectrum: Vec<_> = frame.iter().map(|&sample| (sample, 0.0)).collect();
+    util::fft(&mut spectrum);
+    spectrum
+}
+
+/// Synthesizes a frame of at least [`MIN_FRAME_LEN`] samples containing the DC offset and the harmonics up to `max_harmonic` of `spectrum`.
+fn band_limited_frame(spectrum: &[(f64, f64)], max_harmonic: usize) -> Vec<f64> {
+    let spectrum_len = spectrum.len();
+    let frame_len = spectrum_len.max(MIN_FRAME_LEN);
+
+    let mut bins = vec![(0.0, 0.0); frame_len];
+    bins[0] = spectrum[0];
+    for harmonic in 1..=max_harmonic {
+        let (positive, negative) = if 2 * harmonic == spectrum_len {
+            // The Nyquist bin represents the positive and the negative frequency at the same time
+            let (re, im) = spectrum[harmonic];
+            ((re / 2.0, im / 2.0), (re / 2.0, im / 2.0))
+        } else {
+            (spectrum[harmonic], spectrum[spectrum_len - harmonic])
+        };
+        bins[harmonic] = add(bins[harmonic], positive);
+        bins[frame_len - harmonic] = add(bins[frame_len - harmonic], negative);
+    }
+
+    // Inverse FFT by conjugating the input and the output of the forward FFT
+    for bin in &mut bins {
+        bin.1 = -bin.1;
+    }
+    util::fft(&mut bins);
+    bins.iter()
+        .map(|&(re, _)| re / spectrum_len as f64)
+        .collect()
+}
+
+fn add(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn load_wav_file(location: &PathBuf) -> Result<Vec<f64>, hound::Error> {
+    let reader = WavReader::open(location)?;
+    let spec = reader.spec();
+    let samples: Vec<f64> = match spec.sample_format {
+        SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .map(|sample| sample.map(f64::from))
+            .collect::<Result<_, _>>()?,
+        SampleFormat::Int if !(1..=32).contains(&spec.bits_per_sample) => {
+            return Err(hound::Error::Unsupported)
+        }
+        SampleFormat::Int => {
+            let scale = f64::from(1u32 << (spec.bits_per_sample - 1));
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| f64::from(sample) / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    Ok(samples
+        .chunks_exact(usize::from(spec.channels.max(1)))
+        .map(|frame| frame[0])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    fn create_wavetable(samples: Vec<f64>, frame_size: Option<usize>) -> Result<Wavetable, String> {
+        Wavetable::try_from(WavetableSpec {
+            source: WavetableSource::Samples(samples),
+            frame_size,
+        })
+    }
+
+    #[test]
+    fn band_limited_playback() {
+        // Fundamental and 4th harmonic
+        let samples = (0..16)
+            .map(|index| {
+                let phase = index as f64 / 16.0;
+                (TAU * phase).sin() + 0.5 * (4.0 * TAU * phase).sin()
+            })
+            .collect();
+        let wavetable = create_wavetable(samples, None).unwrap();
+
+        assert_eq!(*wavetable.max_harmonics, [8, 4, 2, 1]);
+        assert_eq!(wavetable.level_for(100.0), 0);
+        assert_eq!(wavetable.level_for(4.0), 1);
+        assert_eq!(wavetable.level_for(3.9), 2);
+        assert_eq!(wavetable.level_for(0.5), 3);
+
+        for phase in [0.0, 0.1, 0.25, 0.3, 0.9] {
+            let full = (TAU * phase).sin() + 0.5 * (4.0 * TAU * phase).sin();
+            assert_approx_eq!(wavetable.sample(phase, 0.0, 1), full, 1e-4);
+            assert_approx_eq!(wavetable.sample(phase, 0.0, 2), (TAU * phase).sin(), 1e-4);
+        }
+    }
+
+    #[test]
+    fn morph_between_frames() {
+        let samples = [[1.0; 4], [-1.0; 4], [0.5; 4]].concat();
+        let wavetable = create_wavetable(samples, Some(4)).unwrap();
+
+        assert_approx_eq!(wavetable.sample(0.3, 0.0, 0), 1.0);
+        assert_approx_eq!(wavetable.sample(0.3, 0.25, 0), 0.0);
+        assert_approx_eq!(wavetable.sample(0.3, 0.5, 0), -1.0);
+        assert_approx_eq!(wavetable.sample(0.3, 0.75, 0), -0.25);
+        assert_approx_eq!(wavetable.sample(0.3, 1.0, 0), 0.5);
+        assert_approx_eq!(wavetable.sample(0.3, 2.0, 0), 0.5);
+    }
+
+    #[test]
+    fn deserialize_inline_table() {
+        let wavetable: Wavetable = serde_yaml::from_str(
+            r"
+source:
+  Samples: [1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 0.0]
+frame_size: 4",
+        )
+        .unwrap();
+
+        assert_eq!(wavetable.frames.len(), 2);
+        assert_eq!(wavetable.frames[0][0].len(), MIN_FRAME_LEN);
+        assert_approx_eq!(wavetable.sample(0.0, 1.0, 0), 0.0);
+    }
+
+    #[test]
+    fn reject_invalid_tables() {
+        assert_eq!(
+            create_wavetable(vec![0.0; 6], None).err(),
+            Some("Invalid wavetable frame size 6: Must be a power of two".to_owned())
+        );
+        assert_eq!(
+            create_wavetable(vec![0.0; 6], Some(4)).err(),
+            Some("Invalid wavetable length 6: Must be a multiple of the frame size 4".to_owned())
+        );
+        assert!(create_wavetable(vec![0.0; 8], Some(4)).is_ok());
+    }
+}
@@ -2,35 +2,69 @@ mod assets;
 mod audio;
 mod bench;
 mod control;
+mod csound;
+mod cue;
+mod drone;
+mod event_log;
+mod feedback;
 mod fluid;
+mod governor;
+mod history;
 mod keyboard;
 mod keypress;
+#[cfg(feature = "link")]
+mod link;
 mod magnetron;
 mod midi;
 mod model;
+#[cfg(feature = "mts-esp")]
+mod mts_esp;
+mod pad;
 mod piano;
+mod recorder;
+mod registry;
+mod reload;
+mod replay;
+mod roughness;
+mod scope;
+mod sfz;
+mod shutdown;
+mod snapshot;
+mod spectrogram;
+mod supercollider;
 mod synth;
 mod task;
 mod tunable;
+mod tuning_sync;
 mod view;
 
-use std::{cell::RefCell, env, io, path::PathBuf, sync::mpsc};
+use std::{cell::RefCell, env, io, net::SocketAddr, path::PathBuf, sync::mpsc, time::Duration};
 
-use ::magnetron::spec::Creator;
-use assets::MicrowaveConfig;
-use audio::{AudioModel, AudioOptions, AudioStage};
+use assets::{KeyboardSpec, MicrowaveConfig, ScaleSpec};
+use audio::{AudioModel, AudioOptions};
 use clap::Parser;
-use control::{LiveParameter, LiveParameterMapper, LiveParameterStorage, ParameterValue};
+use control::{
+    LiveParameter, LiveParameterMapper, LiveParameterStorage, ParameterValue, MAX_TEMPO_BPM,
+    MIN_INPUT_GATE_DB, MIN_TEMPO_BPM,
+};
+use cue::{CueList, CueListSpec, Scene};
+use drone::{Drone, DroneString};
+use event_log::EventLog;
+use history::PitchHistory;
 use keyboard::KeyboardLayout;
-use model::{Model, SourceId};
+use model::{KeyboardStrip, Model, PressureTarget, ScalePreset, ScalePresets};
 use nannou::{
     app::{self, App},
     wgpu::Backends,
 };
-use piano::{Backend, NoAudio, PianoEngine};
+use pad::ControlPad;
+use piano::{NoAudio, PianoEngine};
+use registry::{BackendContext, BackendRegistry, ZoneSpec};
+use reload::{ConfigAssets, ConfigReloader};
 use ringbuf::RingBuffer;
+use shutdown::ShutdownSignal;
 use tune::{
-    key::{Keyboard, PianoKey},
+    key::{Keyboard, KeyboardGeometry, PianoKey},
     note::NoteLetter,
     pitch::Ratio,
     scala::{Kbm, Scl},
@@ -39,8 +73,10 @@ use tune::{
 use tune_cli::{
     shared::{
         self,
-        midi::{MidiInArgs, MidiOutArgs, TuningMethod},
-        KbmOptions, SclCommand,
+        midi::{MidiInArgs, MidiOutArgs, Midir, TuningMethod},
+        preset::SclPreset,
+        sync::SyncArgs,
+        KbmOptions, NoteNameConvention, SclCommand, StretchOptions,
     },
     CliResult,
 };
@@ -72,6 +108,20 @@ enum MainOptions {
         options: RunOptions,
     },
 
+    /// Replay a JSONL event log (see --event-log) or a MIDI file
+    #[command(name = "replay")]
+    Replay {
+        /// The location of the event log (.jsonl) or MIDI file (.mid) to replay
+        replay_file_location: PathBuf,
+
+        /// Playback speed factor
+        #[arg(long = "speed", default_value = "1.0", value_parser = parse_positive_number)]
+        speed: f64,
+
+        #[command(flatten)]
+        options: RunOptions,
+    },
+
     /// List MIDI devices
     #[command(name = "devices")]
     Devices,
@@ -82,12 +132,16 @@ enum MainOptions {
         /// Analyze benchmark
         #[arg(long = "analyze")]
         analyze: bool,
+
+        /// Seed of the random generators. Makes the order of the benchmarked waveforms and the rendered noise reproducible
+        #[arg(long = "seed")]
+        seed: Option<u64>,
     },
 }
 
 const TUN_METHOD_ARG: &str = "tun-method";
 #[derive(Parser)]
-struct RunOptions {
+pub struct RunOptions {
     /// MIDI input device
     #[arg(long = "midi-in")]
     midi_in_device: Option<String>,
@@ -95,6 +149,10 @@ struct RunOptions {
     #[command(flatten)]
     midi_in_args: MidiInArgs,
 
+    /// Adopt the tunings received as MTS SysEx messages (e.g. from a DAW or a master keyboard) on the MIDI input
+    #[arg(long = "mts-in")]
+    adopt_mts: bool,
+
     /// MIDI output device
     #[arg(long = "midi-out")]
     midi_out_device: Option<String>,
@@ -106,6 +164,14 @@ struct RunOptions {
     #[arg(long = TUN_METHOD_ARG)]
     midi_tuning_method: Option<TuningMethod>,
 
+    /// Report parameter values, the selected program and waveform and scale names to the given MIDI device (e.g. a controller with displays)
+    #[arg(long = "feedback-out")]
+    midi_feedback_device: Option<String>,
+
+    /// Channel of the MIDI feedback messages
+    #[arg(long = "feedback-chan", default_value = "0")]
+    midi_feedback_channel: u8,
+
     /// Waveforms file location (waveform synth)
     #[arg(
         long = "cfg-loc",
@@ -118,6 +184,14 @@ struct RunOptions {
     #[arg(long = "wv-bufs", default_value = "8")]
     num_waveform_buffers: usize,
 
+    /// Length of the loop into which the freeze switch captures the held notes [s]
+    #[arg(long = "freeze-secs", default_value = "4.0")]
+    freeze_secs: f64,
+
+    /// Seed of the random generators, e.g. of noise stages. Makes renders of a replay reproducible
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+
     #[command(flatten)]
     control_change: ControlChangeParameters,
 
@@ -125,10 +199,62 @@ struct RunOptions {
     #[arg(long = "log")]
     logging: bool,
 
+    /// Record every engine event with a timestamp to the given JSONL file
+    #[arg(long = "event-log")]
+    event_log_location: Option<PathBuf>,
+
+    /// Additionally record a MIDI file while recording is active (foot switch / Space key). The notes are retuned using the given tuning method
+    #[arg(long = "midi-rec")]
+    midi_recording_method: Option<TuningMethod>,
+
+    #[command(flatten)]
+    sync: SyncArgs,
+
     /// Enable soundfont rendering using the soundfont file at the given location
     #[arg(long = "sf-loc", env = "MICROWAVE_SF_LOC")]
     soundfont_file_location: Option<PathBuf>,
 
+    /// Enable sample playback using the SFZ file at the given location. Can be specified multiple times to switch between instruments via program changes
+    #[arg(long = "sfz-loc")]
+    sfz_file_locations: Vec<PathBuf>,
+
+    /// Send notes with their exact frequencies to the SuperCollider server (scsynth) at the given address, e.g. 127.0.0.1:57110
+    #[arg(long = "sc-out")]
+    sc_server_address: Option<SocketAddr>,
+
+    /// Name of the SynthDef to play on the SuperCollider server. Can be specified multiple times to switch between SynthDefs via program changes
+    #[arg(long = "sc-synth", default_value = "default")]
+    sc_synth_defs: Vec<String>,
+
+    /// Send notes with their exact frequencies to the UDP server of a Csound instance (started with --port) at the given address, e.g. 127.0.0.1:10000
+    #[arg(long = "csound-out")]
+    csound_server_address: Option<SocketAddr>,
+
+    /// Csound instrument number (>= 1) to play. Can be specified multiple times to switch between instruments via program changes
+    #[arg(long = "csound-instr", default_value = "1", value_parser = clap::value_parser!(u16).range(1..))]
+    csound_instruments: Vec<u16>,
+
+    /// Connect to MTS-ESP as master (publish the tuning) or client (import the master's tuning)
+    #[cfg(feature = "mts-esp")]
+    #[arg(long = "mts-esp")]
+    mts_esp_mode: Option<mts_esp::MtsEspMode>,
+
+    /// Follow the tempo of the Ableton Link session on the local network. Listen-only: microwave does not join the session and does not align the beat phase
+    #[cfg(feature = "link")]
+    #[arg(long = "link")]
+    link: bool,
+
+    /// Route MIDI notes within a key range and/or on the given MIDI-in channels to a fixed backend, regardless of the selected backend.
+    /// Syntax: backend[:lo-hi][@channel,...], e.g. fluid:0-59 or waveform@1,2.
+    /// Backends: midi-out, mts-esp, sc, csound, fluid, waveform, none.
+    /// Can be specified multiple times. The first matching zone wins. Notes outside of all zones are played by the selected backend.
+    #[arg(long = "zone")]
+    zones: Vec<ZoneSpec>,
+
+    /// Zero-based MIDI-in channel whose notes do not sound. Instead, they set the scale root while the movable root (Alt+R) is switched on
+    #[arg(long = "root-chan")]
+    root_channel: Option<u8>,
+
     #[command(flatten)]
     audio: AudioParameters,
 
@@ -148,6 +274,10 @@ struct RunOptions {
     #[arg(long = "s-step")]
     secondary_step: Option<i16>,
 
+    /// Isomorphic layout of the computer keyboard (bosanquet, wicki-hayden, janko or grid)
+    #[arg(long = "geom", default_value = "bosanquet", value_parser = parse_keyboard_geometry)]
+    keyboard_geometry: KeyboardGeometry,
+
     /// Physical keyboard layout.
     /// [ansi] Large backspace key, horizontal enter key, large left shift key.
     /// [var] Subdivided backspace key, large enter key, large left shift key.
@@ -155,14 +285,86 @@ struct RunOptions {
     #[arg(long = "keyb", default_value = "iso")]
     keyboard_layout: KeyboardLayout,
 
+    /// Duration of the crossfade when switching between the scales of the config file [s]
+    #[arg(long = "crossfade", default_value = "0", value_parser = parse_non_negative_number)]
+    crossfade_secs: f64,
+
+    /// Initial tempo of the global transport used by tempo-synced LF sources [BPM]
+    #[arg(long = "bpm", default_value = "120", value_parser = parse_tempo)]
+    tempo_bpm: f64,
+
+    /// Initial glide time, i.e. the time it takes a new note to slide from the pitch of the previous note to its own pitch [s]
+    #[arg(long = "glide", default_value = "0")]
+    glide_secs: f64,
+
+    /// Strings of the drone (Alt+D). Integers are scale degrees relative to the root, other values (e.g. 3/4 or 702c) are ratios above the root that are tuned to the nearest scale degree
+    #[arg(long = "drone", value_delimiter = ',', default_value = "3/4,0,0,1/2")]
+    drone_strings: Vec<DroneString>,
+
+    /// Duration of a strumming cycle of the drone, including a rest of one pluck interval [s]
+    #[arg(long = "drone-cycle", default_value = "4", value_parser = parse_positive_number)]
+    drone_cycle_secs: f64,
+
+    /// Velocity at which the strings of the drone are plucked
+    #[arg(long = "drone-vel", default_value = "80")]
+    drone_velocity: u8,
+
+    /// Time span of the pitch history (piano roll) view. Alt+H toggles the view and its ratio annotations [s]
+    #[arg(long = "hist", default_value = "10", value_parser = parse_positive_number)]
+    history_secs: f64,
+
+    /// Cue list file location. PageDown or the cue switch steps to the next scene, PageUp to the previous one
+    #[arg(long = "cues")]
+    cue_list_location: Option<PathBuf>,
+
     /// Odd limit for frequency ratio indicators
     #[arg(long = "lim", default_value = "11")]
-    odd_limit: u16,
+    odd_limit: u32,
 
-    /// Render a second scale-specific keyboard using the given color pattern (e.g. wgrwwgrwgrwgrwwgr for 17-EDO)
+    /// Number of harmonic partials per note assumed by the roughness meter. The partials have an amplitude of 1/n regardless of the actual waveform
+    #[arg(long = "partials", default_value = "8")]
+    num_partials: u16,
+
+    /// Naming convention of the notes displayed in the info panel
+    #[arg(long = "note-names", default_value = "english")]
+    note_names: NoteNameConvention,
+
+    /// Pitch-bend range of the connected controller [semitones]. Used to display the current pitch-bend amount
+    #[arg(long = "pb-range", default_value = "2")]
+    pitch_bend_range: f64,
+
+    /// Render an additional keyboard for the selected scale using the given color pattern (e.g. wgrwwgrwgrwgrwwgr for 17-EDO)
     #[arg(long = "kb2", value_parser = parse_keyboard_colors)]
     second_keyboard_colors: Option<KeyColors>,
 
+    /// Targets controlled by the vertical mouse position. Overrides the pointer_mapping section of the config file.
+    /// [key] The note triggered by the mouse.
+    /// [source] All notes triggered by the mouse.
+    /// Any other value is interpreted as a live parameter, e.g. breath, modulation or sound-1.
+    #[arg(long = "mouse-y", value_delimiter = ',')]
+    mouse_y: Vec<PressureTarget>,
+
+    /// Targets controlled by the vertical touch position. Overrides the pointer_mapping section of the config file.
+    /// [key] The note triggered by the finger.
+    /// [source] All notes triggered by any finger.
+    /// Any other value is interpreted as a live parameter, e.g. breath, modulation or sound-1.
+    #[arg(long = "touch-y", value_delimiter = ',')]
+    touch_y: Vec<PressureTarget>,
+
+    /// Render an on-screen control pad bound to the given live parameters.
+    /// [x] A horizontal slider controlling parameter x (e.g. breath).
+    /// [x:y] An XY pad controlling parameters x and y (e.g. sound-1:sound-2).
+    /// Can be specified multiple times.
+    #[arg(long = "pad")]
+    pads: Vec<ControlPad>,
+
+    /// Built-in scale to add to the scales of the config file, e.g. raga:yaman. Can be specified multiple times
+    #[arg(long = "preset")]
+    presets: Vec<SclPreset>,
+
+    #[command(flatten)]
+    stretch: StretchOptions,
+
     #[command(subcommand)]
     scl: Option<SclCommand>,
 }
@@ -205,6 +407,10 @@ struct ControlChangeParameters {
     #[arg(long = "legato-ccn", default_value = "68")]
     legato_ccn: u8,
 
+    /// Cue switch control number - steps to the next scene of the cue list
+    #[arg(long = "cue-ccn", default_value = "69")]
+    cue_ccn: u8,
+
     /// Sound 1 control number. Triggered by F1 key
     #[arg(long = "sound-1-ccn", default_value = "70")]
     sound_1_ccn: u8,
@@ -244,6 +450,42 @@ struct ControlChangeParameters {
     /// Sound 10 control number. Triggered by F10 key
     #[arg(long = "sound-10-ccn", default_value = "79")]
     sound_10_ccn: u8,
+
+    /// Morph control number - interpolates between the current scale and the next scale of the config file
+    #[arg(long = "morph-ccn", default_value = "80")]
+    morph_ccn: u8,
+
+    /// Tempo control number - sets the tempo of tempo-synced LF sources between 30 and 300 BPM
+    #[arg(long = "tempo-ccn", default_value = "81")]
+    tempo_ccn: u8,
+
+    /// Glide control number - sets the glide time of new notes between 0 and 2 s
+    #[arg(long = "glide-ccn", default_value = "82")]
+    glide_ccn: u8,
+
+    /// Input gain control number - sets the gain of the audio-in signal between -24 and +24 dB
+    #[arg(long = "in-gain-ccn", default_value = "83")]
+    input_gain_ccn: u8,
+
+    /// Input gate control number - sets the noise gate threshold of the audio-in signal between -80 (off) and 0 dB
+    #[arg(long = "in-gate-ccn", default_value = "85")]
+    input_gate_ccn: u8,
+
+    /// Input monitor control number - sets the level at which the audio-in signal is mixed into the output
+    #[arg(long = "in-monitor-ccn", default_value = "86")]
+    input_monitor_ccn: u8,
+
+    /// Freeze switch control number - captures the held notes of the waveform synth into a loop
+    #[arg(long = "freeze-ccn", default_value = "87")]
+    freeze_ccn: u8,
+
+    /// Drone switch control number - plucks the strings of the drone
+    #[arg(long = "drone-ccn", default_value = "89")]
+    drone_ccn: u8,
+
+    /// Movable root switch control number - makes the scale root follow the lowest held note, e.g. via a footswitch
+    #[arg(long = "movable-root-ccn", default_value = "90")]
+    movable_root_ccn: u8,
 }
 
 #[derive(Parser)]
@@ -252,6 +494,18 @@ struct AudioParameters {
     #[arg(long = "audio-in")]
     audio_in_enabled: bool,
 
+    /// Initial gain of the audio-in signal [dB]
+    #[arg(long = "in-gain", default_value = "0", allow_negative_numbers = true)]
+    in_gain_db: f64,
+
+    /// Initial noise gate threshold of the audio-in signal between -80 and 0 [dB]. Signals below the threshold are muted. If no value is specified the gate is disabled
+    #[arg(long = "in-gate", allow_negative_numbers = true, value_parser = parse_input_gate)]
+    in_gate_db: Option<f64>,
+
+    /// Initial level at which the audio-in signal is mixed into the output (0 = no monitoring, 1 = unity gain)
+    #[arg(long = "in-monitor", default_value = "0")]
+    in_monitor: f64,
+
     /// Audio-out buffer size in frames
     #[arg(long = "out-buf", default_value = "1024")]
     out_buffer_size: u32,
@@ -268,9 +522,13 @@ struct AudioParameters {
     #[arg(long = "s-rate")]
     sample_rate: Option<u32>,
 
-    /// Prefix for wav file recordings
+    /// Prefix for wav and MIDI file recordings
     #[arg(long = "wav-prefix", default_value = "microwave")]
     wav_file_prefix: String,
+
+    /// Keep the full audio quality even if the CPU load is high. By default, the number of voices and the reverb density are reduced before buffer underruns occur
+    #[arg(long = "fixed-quality")]
+    fixed_quality: bool,
 }
 
 #[derive(Clone)]
@@ -306,6 +564,48 @@ fn parse_keyboard_colors(src: &str) -> Result<KeyColors, String> {
         .map_err(|c| format!("Received an invalid character '{c}'. Only wrgbcmyk are allowed."))
 }
 
+fn parse_positive_number(src: &str) -> Result<f64, String> {
+    match src.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => Ok(number),
+        _ => Err("Should be a finite number greater than 0".to_owned()),
+    }
+}
+
+fn parse_non_negative_number(src: &str) -> Result<f64, String> {
+    match src.parse::<f64>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+        _ => Err("Should be a finite number greater than or equal to 0".to_owned()),
+    }
+}
+
+fn parse_tempo(src: &str) -> Result<f64, String> {
+    match src.parse::<f64>() {
+        Ok(tempo_bpm) if (MIN_TEMPO_BPM..=MAX_TEMPO_BPM).contains(&tempo_bpm) => Ok(tempo_bpm),
+        _ => Err(format!(
+            "Should be a number between {MIN_TEMPO_BPM} and {MAX_TEMPO_BPM}"
+        )),
+    }
+}
+
+fn parse_input_gate(src: &str) -> Result<f64, String> {
+    match src.parse::<f64>() {
+        Ok(threshold_db) if (MIN_INPUT_GATE_DB..=0.0).contains(&threshold_db) => Ok(threshold_db),
+        _ => Err(format!(
+            "Should be a number between {MIN_INPUT_GATE_DB} and 0"
+        )),
+    }
+}
+
+fn parse_keyboard_geometry(src: &str) -> Result<KeyboardGeometry, String> {
+    match src {
+        "bosanquet" => Ok(KeyboardGeometry::Bosanquet),
+        "wicki-hayden" => Ok(KeyboardGeometry::WickiHayden),
+        "janko" => Ok(KeyboardGeometry::Janko),
+        "grid" => Ok(KeyboardGeometry::Grid),
+        _ => Err("Should be `bosanquet`, `wicki-hayden`, `janko` or `grid`".to_owned()),
+    }
+}
+
 fn main() {
     let options = if env::args().len() < 2 {
         println!("[WARNING] Use a subcommand, e.g. `microwave run` to start microwave properly");
@@ -338,16 +638,30 @@ fn create_model_from_main_options(options: MainOptions) -> CliResult<Option<Mode
             options,
         } => create_model_from_run_options(shared::import_kbm_file(&kbm_file_location)?, options)
             .map(Some),
+        MainOptions::Replay {
+            replay_file_location,
+            speed,
+            options,
+        } => {
+            let events = replay::load(&replay_file_location)?;
+            let midi_source = options.midi_in_args.get_midi_source()?;
+            let model = create_model_from_run_options(
+                Kbm::builder(NoteLetter::D.in_octave(4)).build()?,
+                options,
+            )?;
+            replay::start(model.engine.clone(), events, midi_source, speed);
+            Ok(Some(model))
+        }
         MainOptions::Devices => {
             let stdout = io::stdout();
-            shared::midi::print_midi_devices(stdout.lock(), "microwave")?;
+            shared::midi::print_midi_devices(stdout.lock(), &Midir::new("microwave"))?;
             Ok(None)
         }
-        MainOptions::Bench { analyze } => {
+        MainOptions::Bench { analyze, seed } => {
             if analyze {
                 bench::analyze_benchmark()?;
             } else {
-                bench::run_benchmark()?;
+                bench::run_benchmark(seed)?;
             }
             Ok(None)
         }
@@ -355,6 +669,17 @@ fn create_model_from_main_options(options: MainOptions) -> CliResult<Option<Mode
 }
 
 fn create_model_from_run_options(kbm: Kbm, options: RunOptions) -> CliResult<Model> {
+    #[cfg(feature = "mts-esp")]
+    if let Some(mts_esp::MtsEspMode::Client) = options.mts_esp_mode {
+        if options.scl.is_some() {
+            return Err("A scale cannot be specified in MTS-ESP client mode"
+                .to_owned()
+                .into());
+        }
+        let (scl, kbm) = mts_esp::import_tuning()?;
+        return create_model(scl, kbm, options);
+    }
+
     let scl = options
         .scl
         .as_ref()
@@ -367,98 +692,169 @@ fn create_model_from_run_options(kbm: Kbm, options: RunOptions) -> CliResult<Mod
                 .build()
                 .unwrap()
         });
+    let scl = options.stretch.apply(scl)?;
+
+    create_model(scl, kbm, options)
+}
+
+fn create_model(scl: Scl, kbm: Kbm, options: RunOptions) -> CliResult<Model> {
+    let mut config = MicrowaveConfig::load(&options.waveforms_file_location)?;
 
-    let keyboard = create_keyboard(&scl, &options);
+    let asset_options = AssetOptions {
+        stretch: options.stretch.clone(),
+        use_porcupine: options.use_porcupine,
+        primary_step: options.primary_step,
+        secondary_step: options.secondary_step,
+        keyboard_geometry: options.keyboard_geometry,
+        presets: options.presets.clone(),
+        second_keyboard_colors: options.second_keyboard_colors.clone(),
+        mouse_y: options.mouse_y.clone(),
+        touch_y: options.touch_y.clone(),
+    };
+
+    let initial_scale_preset = ScalePreset {
+        keyboard: create_keyboard(&scl, &asset_options),
+        scl: scl.clone(),
+        kbm: Some(kbm.clone()),
+    };
+
+    let ConfigAssets {
+        scale_presets,
+        keyboard_strips,
+        pointer_mapping,
+        modulation_routes,
+    } = create_config_assets(&mut config, &asset_options)?;
+
+    let cues = match &options.cue_list_location {
+        Some(cue_list_location) => CueList::new(
+            CueListSpec::load(cue_list_location)?
+                .scenes
+                .into_iter()
+                .map(|spec| Scene::load(spec, |spec| create_scale_preset(spec, &asset_options)))
+                .collect::<CliResult<_>>()?,
+        ),
+        None => CueList::default(),
+    };
 
     let (info_send, info_recv) = mpsc::channel();
 
     let (audio_in_prod, audio_in_cons) =
         RingBuffer::new(options.audio.exchange_buffer_size * 2).split();
-    let mut audio_stages = Vec::<Box<dyn AudioStage<((), LiveParameterStorage)>>>::new();
-    let mut backends = Vec::<Box<dyn Backend<SourceId>>>::new();
-
-    if let Some(target_port) = options.midi_out_device {
-        let midi_backend = midi::create(
-            info_send.clone(),
-            &target_port,
-            options.midi_out_args,
-            options
-                .midi_tuning_method
-                .ok_or_else(|| format!("MIDI out requires --{TUN_METHOD_ARG} argument"))?,
-        )?;
-        backends.push(Box::new(midi_backend));
-    }
-
     let output_stream_params =
         audio::get_output_stream_params(options.audio.out_buffer_size, options.audio.sample_rate);
     let sample_rate = output_stream_params.1.sample_rate;
     let sample_rate_hz_u32 = sample_rate.0;
     let sample_rate_hz_f64 = f64::from(sample_rate_hz_u32);
 
-    let (fluid_backend, fluid_synth) = fluid::create(
-        info_send.clone(),
-        options.soundfont_file_location.as_deref(),
-        sample_rate_hz_f64,
-    )?;
-    if options.soundfont_file_location.is_some() {
-        backends.push(Box::new(fluid_backend));
-        audio_stages.push(Box::new(fluid_synth));
-    }
-
-    let mut config = MicrowaveConfig::load(&options.waveforms_file_location)?;
+    let (tuning_send, tuning_recv) = mpsc::channel();
+    let mut registry = BackendRegistry::create(&BackendContext {
+        options: &options,
+        config: &config,
+        info_send: info_send.clone(),
+        tuning_send: tuning_send.clone(),
+        sample_rate_hz: sample_rate_hz_f64,
+    })?;
+    let (master_out, spectrogram) = spectrogram::create(sample_rate_hz_f64);
+    let (scope_out, scope) = scope::create(sample_rate_hz_f64);
 
-    let effect_templates = config
-        .effect_templates
-        .drain(..)
-        .map(|spec| (spec.name, spec.value))
-        .collect();
-
-    let creator = Creator::new(effect_templates, Default::default());
-
-    let effects: Vec<_> = config
-        .effects
-        .iter()
-        .map(|spec| creator.create(spec))
-        .collect();
+    let effects = config.take_effects();
 
+    let (config_send, config_recv) = mpsc::channel();
     let (waveform_backend, waveform_synth) = synth::create(
         info_send.clone(),
         config,
+        config_recv,
         options.num_waveform_buffers,
+        options.seed,
         options.audio.out_buffer_size,
         sample_rate_hz_f64,
+        options.freeze_secs,
         audio_in_cons,
     );
-    backends.push(Box::new(waveform_backend));
-    audio_stages.push(Box::new(waveform_synth));
-    backends.push(Box::new(NoAudio::new(info_send)));
-    for effect in effects {
-        audio_stages.push(effect);
-    }
+    registry.push_backend("waveform", waveform_backend);
+    registry.push_audio_stage(waveform_synth);
+    registry.push_backend("none", NoAudio::new(info_send));
+
+    let zones = registry.resolve_zones(&options.zones)?;
+
+    let BackendRegistry {
+        backends,
+        listeners,
+        audio_stages,
+        midi_out_forward,
+        soundfont_updates,
+        ..
+    } = registry;
+
+    let config_reloader = ConfigReloader::new(
+        options.waveforms_file_location.clone(),
+        options.soundfont_file_location.clone(),
+        config_send,
+        soundfont_updates,
+        move |config| create_config_assets(config, &asset_options),
+    );
 
     let mut storage = LiveParameterStorage::default();
     storage.set_parameter(LiveParameter::Volume, 100.0.as_f64());
     storage.set_parameter(LiveParameter::Legato, 1.0);
+    storage.set_tempo_bpm(options.tempo_bpm);
+    storage.set_glide_secs(options.glide_secs);
+    storage.set_input_gain_db(options.audio.in_gain_db);
+    storage.set_input_gate_db(options.audio.in_gate_db);
+    storage.set_parameter(LiveParameter::InputMonitor, options.audio.in_monitor);
 
     let (storage_send, storage_recv) = mpsc::channel();
 
+    let event_log = options
+        .event_log_location
+        .as_deref()
+        .map(EventLog::create)
+        .transpose()?;
+
     let (engine, engine_snapshot) = PianoEngine::new(
-        scl.clone(),
+        scl,
         kbm,
         backends,
         options.program_number,
         options.control_change.to_parameter_mapper(),
+        options.control_change.cue_ccn,
         storage,
         storage_send,
+        event_log,
+        listeners,
+        zones,
+        options.root_channel,
+        modulation_routes,
+        Drone::new(
+            options.drone_strings.clone(),
+            Duration::from_secs_f64(options.drone_cycle_secs),
+            options.drone_velocity,
+        ),
     );
 
+    #[cfg(feature = "link")]
+    if options.link {
+        let engine = engine.clone();
+        link::listen(move |tempo_bpm| {
+            println!("[INFO] Link session tempo: {tempo_bpm:.1} BPM");
+            // The session tempo is not a controller movement and must not be echoed to the backends
+            engine.set_parameter_without_backends_update(
+                LiveParameter::Tempo,
+                control::tempo_as_parameter_value(tempo_bpm),
+            );
+        })?;
+    }
+
     let audio = AudioModel::new(
         audio_stages,
+        effects,
         output_stream_params,
         options.audio.into_options(),
         storage,
         storage_recv,
         audio_in_prod,
+        master_out,
+        scope_out,
     );
 
     let midi_in = options
@@ -468,6 +864,8 @@ fn create_model_from_run_options(kbm: Kbm, options: RunOptions) -> CliResult<Mod
                 engine.clone(),
                 &midi_in_device,
                 options.midi_in_args,
+                options.adopt_mts.then(|| tuning_send.clone()),
+                midi_out_forward,
                 options.logging,
             )
         })
@@ -478,20 +876,149 @@ fn create_model_from_run_options(kbm: Kbm, options: RunOptions) -> CliResult<Mod
         audio,
         engine,
         engine_snapshot,
-        scl,
-        options
-            .second_keyboard_colors
-            .map(|colors| colors.0)
-            .unwrap_or_else(Vec::new),
-        keyboard,
+        ScalePresets::new(initial_scale_preset, scale_presets),
+        cues,
+        Duration::from_secs_f64(options.crossfade_secs),
+        PitchHistory::new(options.history_secs as f32),
+        spectrogram,
+        scope,
+        keyboard_strips,
         options.keyboard_layout,
         options.odd_limit,
+        options.num_partials,
+        options.note_names.note_names(),
+        Ratio::from_semitones(options.pitch_bend_range),
+        pointer_mapping,
+        options.pads,
         midi_in,
+        config_reloader,
         info_recv,
+        tuning_recv,
+        ShutdownSignal::register(),
     ))
 }
 
-fn create_keyboard(scl: &Scl, config: &RunOptions) -> Keyboard {
+/// The command-line options involved in setting up the scales, keyboards and pointer mapping of the config file.
+///
+/// They are kept for the whole session s.t. a reloaded config file is set up the same way as the initial one.
+struct AssetOptions {
+    stretch: StretchOptions,
+    use_porcupine: bool,
+    primary_step: Option<i16>,
+    secondary_step: Option<i16>,
+    keyboard_geometry: KeyboardGeometry,
+    presets: Vec<SclPreset>,
+    second_keyboard_colors: Option<KeyColors>,
+    mouse_y: Vec<PressureTarget>,
+    touch_y: Vec<PressureTarget>,
+}
+
+/// Takes the scales, keyboards and modulation routes out of the config file. The command-line options take precedence.
+fn create_config_assets(
+    config: &mut MicrowaveConfig,
+    options: &AssetOptions,
+) -> CliResult<ConfigAssets> {
+    let mut scale_presets = Vec::new();
+    for spec in config.scales.drain(..) {
+        scale_presets.push(create_scale_preset(spec, options)?);
+    }
+    for preset in &options.presets {
+        let scl = options.stretch.apply(preset.to_scl(None)?)?;
+        scale_presets.push(ScalePreset {
+            keyboard: create_keyboard(&scl, options),
+            scl,
+            kbm: None,
+        });
+    }
+
+    let keyboard_strips = create_keyboard_strips(
+        config.keyboards.drain(..).collect(),
+        options.second_keyboard_colors.clone(),
+    )?;
+
+    let mut pointer_mapping = config.pointer_mapping.clone();
+    if !options.mouse_y.is_empty() {
+        pointer_mapping.mouse_y = options.mouse_y.clone();
+    }
+    if !options.touch_y.is_empty() {
+        pointer_mapping.touch_y = options.touch_y.clone();
+    }
+
+    Ok(ConfigAssets {
+        scale_presets,
+        keyboard_strips,
+        pointer_mapping,
+        modulation_routes: config.modulation_routes.drain(..).collect(),
+    })
+}
+
+fn create_scale_preset(spec: ScaleSpec, options: &AssetOptions) -> CliResult<ScalePreset> {
+    let scl = options
+        .stretch
+        .apply(shared::import_scl_file(&spec.scl_file_location)?)?;
+    let kbm = spec
+        .kbm_file_location
+        .as_deref()
+        .map(shared::import_kbm_file)
+        .transpose()?;
+
+    Ok(ScalePreset {
+        keyboard: create_keyboard(&scl, options),
+        scl,
+        kbm,
+    })
+}
+
+/// Without any configured keyboards, a 12-EDO reference keyboard is rendered.
+fn create_keyboard_strips(
+    specs: Vec<KeyboardSpec>,
+    second_keyboard_colors: Option<KeyColors>,
+) -> CliResult<Vec<KeyboardStrip>> {
+    let mut keyboard_strips = if specs.is_empty() {
+        vec![KeyboardStrip {
+            scl: Some(
+                Scl::builder()
+                    .push_ratio(Ratio::from_semitones(1))
+                    .build()
+                    .unwrap(),
+            ),
+            colors: None,
+            height: 0.25,
+        }]
+    } else {
+        specs
+            .into_iter()
+            .map(|spec| {
+                Ok(KeyboardStrip {
+                    scl: spec
+                        .scl_file_location
+                        .as_deref()
+                        .map(shared::import_scl_file)
+                        .transpose()?,
+                    colors: spec
+                        .colors
+                        .as_deref()
+                        .map(parse_keyboard_colors)
+                        .transpose()?
+                        .map(|colors| colors.0),
+                    height: spec.height,
+                })
+            })
+            .collect::<CliResult<_>>()?
+    };
+
+    if let Some(colors) = second_keyboard_colors {
+        keyboard_strips.push(KeyboardStrip {
+            scl: None,
+            colors: Some(colors.0),
+            height: 0.25,
+        });
+    }
+
+    Ok(keyboard_strips)
+}
+
+fn create_keyboard(scl: &Scl, config: &AssetOptions) -> Keyboard {
     let preference = if config.use_porcupine {
         TemperamentPreference::Porcupine
     } else {
@@ -515,7 +1042,9 @@ fn create_keyboard(scl: &Scl, config: &RunOptions) -> Keyboard {
         .secondary_step
         .unwrap_or_else(|| keyboard.secondary_step());
 
-    keyboard.with_steps(primary_step, secondary_step)
+    keyboard
+        .with_steps(primary_step, secondary_step)
+        .with_geometry(config.keyboard_geometry)
 }
 
 fn run_app(model: Model) {
@@ -530,6 +1059,7 @@ fn run_app(model: Model) {
     })
     .backends(Backends::PRIMARY | Backends::GL)
     .update(model::update)
+    .exit(model::exit)
     .run();
 }
 
@@ -571,6 +1101,15 @@ impl ControlChangeParameters {
         mapper.push_mapping(LiveParameter::Sound8, self.sound_8_ccn);
         mapper.push_mapping(LiveParameter::Sound9, self.sound_9_ccn);
         mapper.push_mapping(LiveParameter::Sound10, self.sound_10_ccn);
+        mapper.push_mapping(LiveParameter::Morph, self.morph_ccn);
+        mapper.push_mapping(LiveParameter::Tempo, self.tempo_ccn);
+        mapper.push_mapping(LiveParameter::Glide, self.glide_ccn);
+        mapper.push_mapping(LiveParameter::InputGain, self.input_gain_ccn);
+        mapper.push_mapping(LiveParameter::InputGate, self.input_gate_ccn);
+        mapper.push_mapping(LiveParameter::InputMonitor, self.input_monitor_ccn);
+        mapper.push_mapping(LiveParameter::Freeze, self.freeze_ccn);
+        mapper.push_mapping(LiveParameter::Drone, self.drone_ccn);
+        mapper.push_mapping(LiveParameter::MovableRoot, self.movable_root_ccn);
         mapper
     }
 }
@@ -583,6 +1122,7 @@ impl AudioParameters {
             input_buffer_size: self.in_buffer_size,
             exchange_buffer_size: self.exchange_buffer_size,
             wav_file_prefix: self.wav_file_prefix,
+            adaptive_quality: !self.fixed_quality,
         }
     }
 }
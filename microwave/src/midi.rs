@@ -12,75 +12,159 @@ use midir::MidiInputConnection;
 use tune::{
     midi::{ChannelMessage, ChannelMessageType},
     pitch::Pitch,
-    scala::{KbmRoot, Scl},
-    tuner::{MidiTunerMessage, MidiTunerMessageHandler, TunableMidi},
+    scala::{Kbm, KbmRoot, Scl},
+    tuner::{MidiTunerMessage, MidiTunerMessageHandler, TunableMidi, UntunableKey},
 };
 use tune_cli::{
-    shared::midi::{self, MidiInArgs, MidiOutArgs, MidiSource, TuningMethod},
+    shared::{
+        midi::{self, MidiInArgs, MidiOutArgs, MidiSource, TuningMethod},
+        mts::MtsClient,
+    },
     CliResult,
 };
 
 use crate::{
     piano::{Backend, PianoEngine},
-    tunable::TunableBackend,
+    tunable::{JitClashes, TunableBackend},
 };
 
+/// Controller number of the MIDI *Bank Select* message (coarse).
+pub const BANK_SELECT_MSB_CCN: u8 = 0;
+
+/// Controller number of the MIDI *Bank Select* message (fine).
+pub const BANK_SELECT_LSB_CCN: u8 = 32;
+
+/// Controller number of the MIDI *All Sound Off* channel mode message.
+pub const ALL_SOUND_OFF_CCN: u8 = 120;
+
+/// Controller number of the MIDI *All Notes Off* channel mode message.
+pub const ALL_NOTES_OFF_CCN: u8 = 123;
+
 pub struct MidiOutBackend<I, S> {
     info_sender: Sender<I>,
     device: String,
-    tuning_method: TuningMethod,
+    requested_tuning_method: TuningMethod,
     curr_program: usize,
-    backend: TunableBackend<S, TunableMidi<MidiOutHandler>>,
+    /// One backend per tuning method, from the most to the least preferable. See [`TuningMethod::candidates`].
+    backends: Vec<(TuningMethod, TunableBackend<S, TunableMidi<MidiOutHandler>>)>,
+    curr_backend: usize,
+    midi_send: Sender<Vec<u8>>,
 }
 
 pub fn create<I, S: Copy + Eq + Hash>(
     info_sender: Sender<I>,
     target_port: &str,
-    midi_out_args: MidiOutArgs,
+    midi_out_args: &MidiOutArgs,
     tuning_method: TuningMethod,
 ) -> CliResult<MidiOutBackend<I, S>> {
     let (device, mut midi_out) = midi::connect_to_out_device("microwave", target_port)?;
 
-    let (midi_send, midi_recv) = mpsc::channel::<MidiTunerMessage>();
+    let (midi_send, midi_recv) = mpsc::channel::<Vec<u8>>();
 
     crate::task::spawn(async move {
         for message in midi_recv {
-            message.send_to(|m| midi_out.send(m).unwrap());
+            midi_out.send(&message).unwrap();
         }
     });
 
-    let target = midi_out_args.get_midi_target(MidiOutHandler { midi_send })?;
-    let synth = midi_out_args.create_synth(target, tuning_method);
+    let backends = midi_out_args
+        .tuning_method_candidates(tuning_method)
+        .into_iter()
+        .map(|candidate| {
+            let target = midi_out_args.get_midi_target(
+                MidiOutHandler {
+                    midi_send: midi_send.clone(),
+                },
+                candidate,
+            )?;
+            let synth = midi_out_args.create_synth(target, candidate);
+            Ok((candidate, TunableBackend::new(synth)))
+        })
+        .collect::<CliResult<_>>()?;
 
     Ok(MidiOutBackend {
         info_sender,
         device,
-        tuning_method,
+        requested_tuning_method: tuning_method,
         curr_program: 0,
-        backend: TunableBackend::new(synth),
+        backends,
+        curr_backend: 0,
+        midi_send,
     })
 }
 
+impl<I, S> MidiOutBackend<I, S> {
+    fn backend(&self) -> &TunableBackend<S, TunableMidi<MidiOutHandler>> {
+        &self.backends[self.curr_backend].1
+    }
+
+    fn backend_mut(&mut self) -> &mut TunableBackend<S, TunableMidi<MidiOutHandler>> {
+        &mut self.backends[self.curr_backend].1
+    }
+
+    /// Returns a [`Sender`] for raw MIDI messages that bypass the tuning logic, e.g. forwarded SysEx messages.
+    pub fn forward_sender(&self) -> Sender<Vec<u8>> {
+        self.midi_send.clone()
+    }
+}
+
+impl<I, S: Copy + Eq + Hash + Debug + Send> MidiOutBackend<I, S> {
+    fn switch_backend(&mut self, index: usize) {
+        if index == self.curr_backend {
+            return;
+        }
+
+        // Release the notes of the previous backend. The new backend sends its own tuning messages.
+        self.backend_mut().set_no_tuning();
+        self.curr_backend = index;
+
+        // The new backend might address different channels, e.g. MPE member channels
+        let program = u8::try_from(self.curr_program).unwrap();
+        self.backend_mut()
+            .send_monophonic_message(ChannelMessageType::ProgramChange { program });
+    }
+}
+
 impl<I: From<MidiInfo> + Send, S: Copy + Eq + Hash + Debug + Send> Backend<S>
     for MidiOutBackend<I, S>
 {
     fn set_tuning(&mut self, tuning: (&Scl, KbmRoot)) {
-        self.backend.set_tuning(tuning);
+        if self.backend().has_tuning(tuning) {
+            return;
+        }
+
+        // Fall back to a less precise tuning method if the tuning exceeds the channels of the more precise ones
+        let fitting_backend = match self.backends.len() {
+            1 => 0,
+            _ => self
+                .backends
+                .iter()
+                .position(|(_, backend)| backend.tuning_fits(tuning))
+                .unwrap_or(0),
+        };
+        self.switch_backend(fitting_backend);
+        self.backend_mut().set_tuning(tuning);
     }
 
     fn set_no_tuning(&mut self) {
-        self.backend.set_no_tuning();
+        self.switch_backend(0);
+        self.backend_mut().set_no_tuning();
     }
 
     fn send_status(&mut self) {
-        let is_tuned = self.backend.is_tuned();
+        let is_tuned = self.backend().is_tuned();
+        let untunable_degrees = self.backend().untunable_degrees().to_vec();
+        let jit_clashes = self.backend().jit_clashes().clone();
 
         self.info_sender
             .send(
                 MidiInfo {
                     device: self.device.clone(),
                     program_number: self.curr_program,
-                    tuning_method: is_tuned.then(|| self.tuning_method),
+                    requested_tuning_method: self.requested_tuning_method,
+                    tuning_method: is_tuned.then(|| self.backends[self.curr_backend].0),
+                    untunable_degrees,
+                    jit_clashes,
                 }
                 .into(),
             )
@@ -88,42 +172,62 @@ impl<I: From<MidiInfo> + Send, S: Copy + Eq + Hash + Debug + Send> Backend<S>
     }
 
     fn start(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
-        self.backend.start(id, degree, pitch, velocity);
+        if self.backend_mut().start(id, degree, pitch, velocity) {
+            self.send_status();
+        }
     }
 
     fn update_pitch(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
-        self.backend.update_pitch(id, degree, pitch, velocity);
+        self.backend_mut().update_pitch(id, degree, pitch, velocity);
     }
 
     fn update_pressure(&mut self, id: S, pressure: u8) {
-        self.backend.update_pressure(id, pressure);
+        self.backend_mut().update_pressure(id, pressure);
     }
 
     fn stop(&mut self, id: S, velocity: u8) {
-        self.backend.stop(id, velocity);
+        self.backend_mut().stop(id, velocity);
+    }
+
+    fn all_sound_off(&mut self) {
+        self.backend_mut().release_all();
+
+        // Also silence the notes that are not tracked by the tuner, e.g. sustained or forwarded notes
+        for channel in 0..16 {
+            for controller in [ALL_SOUND_OFF_CCN, ALL_NOTES_OFF_CCN] {
+                let message = ChannelMessageType::ControlChange {
+                    controller,
+                    value: 0,
+                }
+                .in_channel(channel)
+                .unwrap();
+                self.midi_send
+                    .send(message.to_raw_message().to_vec())
+                    .unwrap();
+            }
+        }
     }
 
     fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
         self.curr_program = update_fn(self.curr_program).min(127);
 
-        self.backend
-            .send_monophonic_message(ChannelMessageType::ProgramChange {
-                program: u8::try_from(self.curr_program).unwrap(),
-            });
+        let program = u8::try_from(self.curr_program).unwrap();
+        self.backend_mut()
+            .send_monophonic_message(ChannelMessageType::ProgramChange { program });
     }
 
     fn control_change(&mut self, controller: u8, value: u8) {
-        self.backend
+        self.backend_mut()
             .send_monophonic_message(ChannelMessageType::ControlChange { controller, value });
     }
 
     fn channel_pressure(&mut self, pressure: u8) {
-        self.backend
+        self.backend_mut()
             .send_monophonic_message(ChannelMessageType::ChannelPressure { pressure });
     }
 
     fn pitch_bend(&mut self, value: i16) {
-        self.backend
+        self.backend_mut()
             .send_monophonic_message(ChannelMessageType::PitchBendChange { value });
     }
 
@@ -132,24 +236,53 @@ impl<I: From<MidiInfo> + Send, S: Copy + Eq + Hash + Debug + Send> Backend<S>
     fn has_legato(&self) -> bool {
         true
     }
+
+    fn has_continuous_pitch(&self) -> bool {
+        !self.backend().is_aot()
+    }
 }
 
+/// If `mts_updates` is given, the tunings received as MTS SysEx messages are forwarded to it.
+/// If `midi_out_forward` is given, the non-channel messages selected by `midi_in_args` are forwarded to it.
 pub fn connect_to_midi_device(
     mut engine: Arc<PianoEngine>,
     target_port: &str,
     midi_in_args: MidiInArgs,
+    mts_updates: Option<Sender<(Scl, Kbm)>>,
+    midi_out_forward: Option<Sender<Vec<u8>>>,
     midi_logging: bool,
 ) -> CliResult<(String, MidiInputConnection<()>)> {
     let midi_source = midi_in_args.get_midi_source()?;
+    let mut mts_client = mts_updates.map(|mts_updates| (MtsClient::default(), mts_updates));
 
     Ok(midi::connect_to_in_device(
         "microwave",
         target_port,
-        move |message| process_midi_event(message, &mut engine, &midi_source, midi_logging),
+        move |message| {
+            if let Some((mts_client, mts_updates)) = &mut mts_client {
+                match mts_client.process_raw_message(message, |scl, kbm_root| {
+                    let _ = mts_updates.send((scl, kbm_root.to_kbm()));
+                }) {
+                    Ok(true) => return,
+                    Ok(false) => {}
+                    Err(err) => {
+                        eprintln!("[WARNING] Could not adopt MTS tuning: {err:?}");
+                        return;
+                    }
+                }
+            }
+            if let Some(midi_out_forward) = &midi_out_forward {
+                if midi_source.forwards(message) {
+                    let _ = midi_out_forward.send(message.to_vec());
+                    return;
+                }
+            }
+            process_midi_event(message, &mut engine, &midi_source, midi_logging)
+        },
     )?)
 }
 
-fn process_midi_event(
+pub fn process_midi_event(
     message: &[u8],
     engine: &mut Arc<PianoEngine>,
     midi_source: &MidiSource,
@@ -166,6 +299,7 @@ fn process_midi_event(
         if midi_source.channels.contains(&channel_message.channel()) {
             engine.handle_midi_event(
                 channel_message.message_type(),
+                channel_message.channel(),
                 midi_source.get_offset(channel_message.channel()),
             );
         }
@@ -179,17 +313,21 @@ fn process_midi_event(
 }
 
 struct MidiOutHandler {
-    midi_send: Sender<MidiTunerMessage>,
+    midi_send: Sender<Vec<u8>>,
 }
 
 impl MidiTunerMessageHandler for MidiOutHandler {
     fn handle(&mut self, message: MidiTunerMessage) {
-        self.midi_send.send(message).unwrap();
+        message.send_to(|message| self.midi_send.send(message.to_vec()).unwrap());
     }
 }
 
 pub struct MidiInfo {
     pub device: String,
+    pub requested_tuning_method: TuningMethod,
+    /// The tuning method in use or [`None`] if the tuning exceeds the available channels.
     pub tuning_method: Option<TuningMethod>,
+    pub untunable_degrees: Vec<UntunableKey<i32>>,
+    pub jit_clashes: JitClashes,
     pub program_number: usize,
 }
@@ -1,7 +1,11 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    mem,
     ops::Deref,
+    str::FromStr,
     sync::{mpsc::Receiver, Arc},
+    thread,
+    time::{Duration, Instant},
 };
 
 use midir::MidiInputConnection;
@@ -10,40 +14,137 @@ use nannou::{
     prelude::*,
     winit::event::WindowEvent,
 };
+use serde::{Deserialize, Serialize};
 use tune::{
     key::{Keyboard, PianoKey},
-    note::NoteLetter,
+    note::{NoteLetter, NoteNames},
     pitch::{Pitch, Pitched, Ratio},
-    scala::Scl,
+    scala::{Kbm, Scl},
 };
 
 use crate::{
     audio::AudioModel,
     control::LiveParameter,
+    cue::CueList,
+    history::PitchHistory,
     keyboard::{self, KeyboardLayout},
+    pad::{self, ControlPad},
     piano::{PianoEngine, PianoEngineSnapshot},
+    reload::{ConfigAssets, ConfigReloader},
+    scope::Scope,
+    shutdown::ShutdownSignal,
+    snapshot::Snapshot,
+    spectrogram::Spectrogram,
     view::DynViewModel,
     KeyColor,
 };
 
+/// Minimum time between two morph steps s.t. the backends are not flooded with retuning messages.
+const MIN_MORPH_INTERVAL: Duration = Duration::from_millis(50);
+
 pub struct Model {
     pub audio: AudioModel,
     pub engine: Arc<PianoEngine>,
     pub engine_snapshot: PianoEngineSnapshot,
     pub scl: Scl,
-    pub scl_key_colors: Vec<KeyColor>,
-    pub reference_scl: Scl,
+    pub scale_presets: ScalePresets,
+    pub morph: f64,
+    morph_applied_at: Instant,
+    pub cues: CueList,
+    pub crossfade_duration: Duration,
+    pub pitch_history: PitchHistory,
+    pub spectrogram: Spectrogram,
+    pub scope: Scope,
+    pub keyboard_strips: Vec<KeyboardStrip>,
     pub keyboard: Keyboard,
     pub layout: KeyboardLayout,
-    pub odd_limit: u16,
+    pub odd_limit: u32,
+    pub num_partials: u16,
+    pub note_names: NoteNames,
+    pub pitch_bend_range: Ratio,
+    pub pointer_mapping: PointerMapping,
+    pub pads: Vec<ControlPad>,
+    pub grabbed_pads: HashMap<SourceId, usize>,
     pub midi_in: Option<MidiInputConnection<()>>,
+    pub config_reloader: ConfigReloader,
     pub pitch_at_left_border: Pitch,
     pub pitch_at_right_border: Pitch,
     pub pressed_physical_keys: HashSet<(i8, i8)>,
+    pub glissando_mode: GlissandoMode,
+    pub touches: HashMap<u64, Touch>,
     pub alt: bool,
     pub ctrl: bool,
     pub view_model: Option<DynViewModel>,
     pub view_updates: Receiver<DynViewModel>,
+    pub tuning_updates: Receiver<(Scl, Kbm)>,
+    pub shutdown_signal: ShutdownSignal,
+}
+
+/// A scale the user can switch to while microwave is running.
+pub struct ScalePreset {
+    pub scl: Scl,
+    /// If absent, the current keyboard mapping is kept.
+    pub kbm: Option<Kbm>,
+    pub keyboard: Keyboard,
+}
+
+/// The list of scales the user can cycle through while microwave is running.
+pub struct ScalePresets {
+    presets: Vec<ScalePreset>,
+    curr_preset: usize,
+}
+
+impl ScalePresets {
+    /// Presets without a keyboard mapping use `initial_kbm` s.t. switching to a preset always yields the same tuning.
+    pub fn new(initial_preset: ScalePreset, other_presets: Vec<ScalePreset>) -> Self {
+        let initial_kbm = initial_preset.kbm.clone();
+        let mut presets = vec![initial_preset];
+        presets.extend(other_presets.into_iter().map(|preset| ScalePreset {
+            kbm: preset.kbm.or_else(|| initial_kbm.clone()),
+            ..preset
+        }));
+
+        Self {
+            presets,
+            curr_preset: 0,
+        }
+    }
+
+    /// Replaces all presets except for the initial one. The current preset stays selected if it still exists.
+    pub fn replace_others(&mut self, other_presets: Vec<ScalePreset>) {
+        let initial_preset = self.presets.remove(0);
+        let curr_preset = self.curr_preset;
+        *self = Self::new(initial_preset, other_presets);
+        if curr_preset < self.presets.len() {
+            self.curr_preset = curr_preset;
+        }
+    }
+
+    pub fn curr(&self) -> &ScalePreset {
+        &self.presets[self.curr_preset]
+    }
+
+    /// The preset that follows the current one, wrapping around at the end of the list.
+    pub fn next(&self) -> &ScalePreset {
+        &self.presets[(self.curr_preset + 1) % self.presets.len()]
+    }
+
+    /// Moves `delta` presets forward or backward, wrapping around at both ends of the list.
+    pub fn step_by(&mut self, delta: isize) -> &ScalePreset {
+        let num_presets = self.presets.len() as isize;
+        self.curr_preset = (self.curr_preset as isize + delta).rem_euclid(num_presets) as usize;
+        self.curr()
+    }
+}
+
+/// An on-screen keyboard rendered at the bottom of the window.
+pub struct KeyboardStrip {
+    /// If absent, the currently selected scale is rendered.
+    pub scl: Option<Scl>,
+    /// If absent, keys are colored like a 12-EDO piano keyboard.
+    pub colors: Option<Vec<KeyColor>>,
+    /// Fraction of the window height.
+    pub height: f32,
 }
 
 pub enum Event {
@@ -58,45 +159,260 @@ pub enum SourceId {
     Touchpad(u64),
     Keyboard(i8, i8),
     Midi(PianoKey),
+    /// A string of the drone, identified by its index.
+    Drone(usize),
+}
+
+impl SourceId {
+    pub fn is_same_kind_as(&self, other: &SourceId) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
 }
 
+#[derive(Clone, Copy)]
 pub enum Location {
     Pitch(Pitch),
     Degree(i32),
 }
 
+/// Defines how the pitches of multiple fingers evolve when they slide over the touch screen.
+#[derive(Clone, Copy, Debug)]
+pub enum GlissandoMode {
+    /// Each finger follows its own position, snapped to the nearest scale degree.
+    Absolute,
+    /// All fingers follow their average movement s.t. the intervals between them are locked.
+    Relative,
+}
+
+impl GlissandoMode {
+    fn toggle(&mut self) {
+        *self = match *self {
+            GlissandoMode::Absolute => GlissandoMode::Relative,
+            GlissandoMode::Relative => GlissandoMode::Absolute,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Touch {
+    /// The pitch the finger had when the other fingers changed.
+    locked: Pitch,
+    /// The position the finger had when the other fingers changed.
+    anchor: Pitch,
+    /// The current position of the finger.
+    current: Pitch,
+}
+
+/// Defines which targets are controlled by the mouse, the touch screen and the scroll wheel.
+///
+/// Targets and live parameters are spelled the same way as on the command line, e.g. `key` or `sound-1`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PointerMapping {
+    pub mouse_x: AxisTarget,
+    pub mouse_y: Vec<PressureTarget>,
+    pub touch_y: Vec<PressureTarget>,
+    #[serde(with = "optional_live_parameter")]
+    pub ctrl_scroll: Option<LiveParameter>,
+}
+
+impl Default for PointerMapping {
+    fn default() -> Self {
+        Self {
+            mouse_x: AxisTarget::Pitch,
+            mouse_y: vec![
+                PressureTarget::Key,
+                PressureTarget::Parameter(LiveParameter::Breath),
+            ],
+            touch_y: vec![PressureTarget::Key],
+            ctrl_scroll: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AxisTarget {
+    /// The pitch of the note triggered by the pointer.
+    Pitch,
+    /// The given live parameter. No notes are triggered.
+    Parameter(LiveParameter),
+}
+
+impl FromStr for AxisTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pitch" => Ok(AxisTarget::Pitch),
+            parameter => parameter.parse().map(AxisTarget::Parameter),
+        }
+    }
+}
+
+impl TryFrom<String> for AxisTarget {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<AxisTarget> for String {
+    fn from(target: AxisTarget) -> Self {
+        match target {
+            AxisTarget::Pitch => "pitch".to_owned(),
+            AxisTarget::Parameter(parameter) => parameter.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PressureTarget {
+    /// Only the note triggered by the pointer.
+    Key,
+    /// All notes triggered by the same kind of source, e.g. all fingers on a touch screen.
+    Source,
+    /// The given live parameter.
+    Parameter(LiveParameter),
+}
+
+impl FromStr for PressureTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "key" => Ok(PressureTarget::Key),
+            "source" => Ok(PressureTarget::Source),
+            parameter => parameter.parse().map(PressureTarget::Parameter),
+        }
+    }
+}
+
+impl TryFrom<String> for PressureTarget {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PressureTarget> for String {
+    fn from(target: PressureTarget) -> Self {
+        match target {
+            PressureTarget::Key => "key".to_owned(),
+            PressureTarget::Source => "source".to_owned(),
+            PressureTarget::Parameter(parameter) => parameter.to_string(),
+        }
+    }
+}
+
+mod optional_live_parameter {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::control::LiveParameter;
+
+    pub fn serialize<S: Serializer>(
+        parameter: &Option<LiveParameter>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        parameter
+            .map(|parameter| parameter.to_string())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<LiveParameter>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|parameter| parameter.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+impl PointerMapping {
+    fn x_target(&self, id: SourceId) -> AxisTarget {
+        match id {
+            SourceId::Mouse => self.mouse_x,
+            SourceId::Touchpad(_)
+            | SourceId::Keyboard(..)
+            | SourceId::Midi(_)
+            | SourceId::Drone(_) => AxisTarget::Pitch,
+        }
+    }
+
+    fn y_targets(&self, id: SourceId) -> &[PressureTarget] {
+        match id {
+            SourceId::Mouse => &self.mouse_y,
+            SourceId::Touchpad(_) => &self.touch_y,
+            SourceId::Keyboard(..) | SourceId::Midi(_) | SourceId::Drone(_) => &[],
+        }
+    }
+}
+
 impl Model {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         audio: AudioModel,
         engine: Arc<PianoEngine>,
         engine_snapshot: PianoEngineSnapshot,
-        scl: Scl,
-        scl_key_colors: Vec<KeyColor>,
-        keyboard: Keyboard,
+        scale_presets: ScalePresets,
+        cues: CueList,
+        crossfade_duration: Duration,
+        pitch_history: PitchHistory,
+        spectrogram: Spectrogram,
+        scope: Scope,
+        keyboard_strips: Vec<KeyboardStrip>,
         layout: KeyboardLayout,
-        odd_limit: u16,
+        odd_limit: u32,
+        num_partials: u16,
+        note_names: NoteNames,
+        pitch_bend_range: Ratio,
+        pointer_mapping: PointerMapping,
+        pads: Vec<ControlPad>,
         midi_in: Option<MidiInputConnection<()>>,
+        config_reloader: ConfigReloader,
         view_updates: Receiver<DynViewModel>,
+        tuning_updates: Receiver<(Scl, Kbm)>,
+        shutdown_signal: ShutdownSignal,
     ) -> Self {
         Self {
             audio,
             engine,
             engine_snapshot,
-            scl,
-            scl_key_colors,
-            reference_scl: Scl::builder().push_cents(100.0).build().unwrap(),
-            keyboard,
+            scl: scale_presets.curr().scl.clone(),
+            keyboard: scale_presets.curr().keyboard.clone(),
+            scale_presets,
+            morph: 0.0,
+            morph_applied_at: Instant::now(),
+            cues,
+            crossfade_duration,
+            pitch_history,
+            spectrogram,
+            scope,
+            keyboard_strips,
             layout,
             odd_limit,
+            num_partials,
+            note_names,
+            pitch_bend_range,
+            pointer_mapping,
+            pads,
+            grabbed_pads: HashMap::new(),
             midi_in,
+            config_reloader,
             pitch_at_left_border: NoteLetter::A.in_octave(0).pitch(),
             pitch_at_right_border: NoteLetter::C.in_octave(8).pitch(),
             pressed_physical_keys: HashSet::new(),
+            glissando_mode: GlissandoMode::Absolute,
+            touches: HashMap::new(),
             alt: false,
             ctrl: false,
             view_model: None,
             view_updates,
+            tuning_updates,
+            shutdown_signal,
         }
     }
 
@@ -104,7 +420,125 @@ impl Model {
         for update in self.view_updates.try_iter() {
             self.view_model = Some(update);
         }
+        for (scl, kbm) in self.tuning_updates.try_iter() {
+            println!("[INFO] Received tuning `{}`", scl.description());
+            self.scl = scl.clone();
+            self.engine
+                .set_scale(scl, Some(kbm), self.crossfade_duration);
+        }
+        if let Some(assets) = self.config_reloader.update(&self.engine, &self.audio) {
+            self.apply_config_assets(assets);
+        }
+        self.audio.report_bypassed_stages();
+        self.engine.update_crossfade();
+        self.engine.update_glides();
+        self.engine.update_drone();
         self.engine.take_snapshot(&mut self.engine_snapshot);
+
+        // Every morph step rebakes the tuning. Hence, fast controller movements are throttled
+        let morph = self.storage.read_parameter(LiveParameter::Morph);
+        if morph != self.morph && self.morph_applied_at.elapsed() >= MIN_MORPH_INTERVAL {
+            self.morph = morph;
+            self.morph_applied_at = Instant::now();
+            self.apply_morph();
+        }
+
+        let num_new_triggers = self
+            .cues
+            .take_triggers(self.engine_snapshot.num_cue_triggers);
+        if num_new_triggers > 0 {
+            self.step_cue_by(num_new_triggers as isize);
+        }
+    }
+
+    /// The current scale stays active until the next scale preset is selected.
+    fn apply_config_assets(&mut self, assets: ConfigAssets) {
+        self.scale_presets.replace_others(assets.scale_presets);
+        self.keyboard_strips = assets.keyboard_strips;
+        self.pointer_mapping = assets.pointer_mapping;
+        self.engine.set_modulation_routes(assets.modulation_routes);
+    }
+
+    /// Stops all notes of all backends and mutes the audio stages s.t. no stuck notes remain.
+    pub fn panic(&self) {
+        self.engine.panic();
+        self.audio.mute();
+    }
+
+    pub fn export_snapshot(&self) {
+        Snapshot::new(
+            self.pitch_at_left_border..self.pitch_at_right_border,
+            (&self.scl, self.kbm.kbm_root()),
+            self.pressed_keys
+                .values()
+                .map(|pressed_key| pressed_key.pitch),
+            self.odd_limit,
+        )
+        .export();
+    }
+
+    pub fn step_cue_by(&mut self, delta: isize) {
+        let scene = match self.cues.step_by(delta) {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        println!("[INFO] Entering scene `{}`", scene.name);
+        if let Some(preset) = &scene.scale {
+            self.scl = preset.scl.clone();
+            self.keyboard = preset.keyboard.clone();
+            self.engine.set_scale(
+                preset.scl.clone(),
+                preset.kbm.clone(),
+                self.crossfade_duration,
+            );
+        }
+        if let Some(program) = scene.program {
+            self.engine.set_program(program);
+        }
+        for &(parameter, value) in &scene.parameters {
+            self.engine.set_parameter(parameter, value);
+        }
+    }
+
+    pub fn change_scale_preset_by(&mut self, delta: isize) {
+        // The morph starts over from the new preset
+        self.morph = 0.0;
+        self.engine
+            .set_parameter_without_backends_update(LiveParameter::Morph, 0.0);
+
+        let preset = self.scale_presets.step_by(delta);
+        self.scl = preset.scl.clone();
+        self.keyboard = preset.keyboard.clone();
+        self.engine.set_scale(
+            preset.scl.clone(),
+            preset.kbm.clone(),
+            self.crossfade_duration,
+        );
+    }
+
+    /// Interpolates between the current scale preset and the next one according to the [`LiveParameter::Morph`] position.
+    ///
+    /// The scale changes without a crossfade s.t. the pitches follow the controller immediately.
+    fn apply_morph(&mut self) {
+        let preset = self.scale_presets.curr();
+        let target = self.scale_presets.next();
+
+        match preset
+            .scl
+            .interpolate(&target.scl, self.morph.clamp(0.0, 1.0))
+        {
+            Ok(scl) => {
+                self.scl = scl.clone();
+                self.engine
+                    .set_scale(scl, preset.kbm.clone(), Duration::ZERO);
+            }
+            Err(err) => eprintln!(
+                "[WARNING] Cannot morph from `{}` to `{}`: {err:?}",
+                preset.scl.description(),
+                target.scl.description()
+            ),
+        }
     }
 
     pub fn keyboard_event(&mut self, (x, y): (i8, i8), pressed: bool) {
@@ -127,6 +561,28 @@ impl Model {
             self.engine.handle_event(event)
         }
     }
+
+    /// The common movement of all fingers since the last finger was pressed or released.
+    fn glide(&self) -> Ratio {
+        if self.touches.is_empty() {
+            return Ratio::default();
+        }
+        let total_cents: f64 = self
+            .touches
+            .values()
+            .map(|touch| Ratio::between_pitches(touch.anchor, touch.current).as_cents())
+            .sum();
+        Ratio::from_cents(total_cents / self.touches.len() as f64)
+    }
+
+    /// Re-anchors all fingers s.t. pressing or releasing a finger does not cause any pitch jumps.
+    fn rebase_touches(&mut self) {
+        let glide = self.glide();
+        for touch in self.touches.values_mut() {
+            touch.locked = touch.locked * glide;
+            touch.anchor = touch.current;
+        }
+    }
 }
 
 impl Deref for Model {
@@ -177,6 +633,18 @@ pub fn key_pressed(_app: &App, model: &mut Model, key: Key) {
         Key::E if model.alt => engine.toggle_envelope_type(),
         Key::O if model.alt => engine.toggle_synth_mode(),
         Key::L if model.alt => engine.toggle_parameter(LiveParameter::Legato),
+        Key::F if model.alt => engine.toggle_parameter(LiveParameter::Freeze),
+        Key::D if model.alt => engine.toggle_parameter(LiveParameter::Drone),
+        Key::R if model.alt => engine.toggle_parameter(LiveParameter::MovableRoot),
+        Key::P if model.alt => model.panic(),
+        Key::H if model.alt => model.pitch_history.mode.toggle(),
+        Key::W if model.alt => model.spectrogram.visible = !model.spectrogram.visible,
+        Key::A if model.alt => model.scope.visible = !model.scope.visible,
+        Key::S if model.alt => model.export_snapshot(),
+        Key::G if model.alt => {
+            model.rebase_touches();
+            model.glissando_mode.toggle();
+        }
         Key::F1 => engine.toggle_parameter(LiveParameter::Sound1),
         Key::F2 => engine.toggle_parameter(LiveParameter::Sound2),
         Key::F3 => engine.toggle_parameter(LiveParameter::Sound3),
@@ -188,18 +656,24 @@ pub fn key_pressed(_app: &App, model: &mut Model, key: Key) {
         Key::F9 => engine.toggle_parameter(LiveParameter::Sound9),
         Key::F10 => engine.toggle_parameter(LiveParameter::Sound10),
         Key::Space => engine.toggle_parameter(LiveParameter::Foot),
+        Key::Up if model.alt => model.change_scale_preset_by(-1),
+        Key::Down if model.alt => model.change_scale_preset_by(1),
+        Key::Up if model.ctrl => engine.change_bank_by(-1),
+        Key::Down if model.ctrl => engine.change_bank_by(1),
         Key::Up if !model.alt => engine.dec_program(),
         Key::Down if !model.alt => engine.inc_program(),
         Key::Left if model.alt => engine.change_ref_note_by(-1),
         Key::Right if model.alt => engine.change_ref_note_by(1),
         Key::Left if !model.alt => engine.change_root_offset_by(-1),
         Key::Right if !model.alt => engine.change_root_offset_by(1),
+        Key::PageUp => model.step_cue_by(-1),
+        Key::PageDown => model.step_cue_by(1),
         _ => {}
     }
 }
 
 pub fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
-    if button == MouseButton::Left {
+    if button == MouseButton::Left && !grab_pad(app, model, app.mouse.position(), SourceId::Mouse) {
         position_event(
             app,
             model,
@@ -211,13 +685,15 @@ pub fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
 }
 
 pub fn mouse_moved(app: &App, model: &mut Model, position: Point2) {
-    position_event(app, model, position, SourceId::Mouse, |location| {
-        Event::Moved(SourceId::Mouse, location)
-    });
+    if !move_pad(app, model, position, SourceId::Mouse) {
+        position_event(app, model, position, SourceId::Mouse, |location| {
+            Event::Moved(SourceId::Mouse, location)
+        });
+    }
 }
 
 pub fn mouse_released(_app: &App, model: &mut Model, button: MouseButton) {
-    if button == MouseButton::Left {
+    if button == MouseButton::Left && !release_pad(model, SourceId::Mouse) {
         model
             .engine
             .handle_event(Event::Released(SourceId::Mouse, 100));
@@ -235,6 +711,14 @@ pub fn mouse_wheel(
         MouseScrollDelta::PixelDelta(pos) => (pos.x, pos.y),
     };
 
+    if model.ctrl {
+        if let Some(parameter) = model.pointer_mapping.ctrl_scroll {
+            let value = model.storage.read_parameter(parameter) + y_delta / 200.0;
+            model.engine.set_parameter(parameter, value.clamp(0.0, 1.0));
+            return;
+        }
+    }
+
     if model.alt {
         let tmp = x_delta;
         x_delta = -y_delta;
@@ -259,21 +743,118 @@ pub fn mouse_wheel(
 
 pub fn touch(app: &App, model: &mut Model, event: TouchEvent) {
     let id = SourceId::Touchpad(event.id);
+
+    let consumed_by_pad = match event.phase {
+        TouchPhase::Started => grab_pad(app, model, event.position, id),
+        TouchPhase::Moved => move_pad(app, model, event.position, id),
+        TouchPhase::Ended | TouchPhase::Cancelled => release_pad(model, id),
+    };
+    if consumed_by_pad {
+        return;
+    }
+
+    let pitch = pitch_at_position(app, model, event.position);
     match event.phase {
-        TouchPhase::Started => position_event(app, model, event.position, id, |location| {
-            Event::Pressed(id, location, 100)
-        }),
+        TouchPhase::Started => {
+            model.rebase_touches();
+            model.touches.insert(
+                event.id,
+                Touch {
+                    locked: pitch,
+                    anchor: pitch,
+                    current: pitch,
+                },
+            );
+            let location = match model.glissando_mode {
+                GlissandoMode::Absolute => Location::Pitch(model.engine.snap_to_scale(pitch)),
+                GlissandoMode::Relative => Location::Pitch(pitch),
+            };
+            position_event(app, model, event.position, id, |_| {
+                Event::Pressed(id, location, 100)
+            })
+        }
         TouchPhase::Moved => {
-            position_event(app, model, event.position, id, |location| {
-                Event::Moved(id, location)
-            });
+            if let Some(touch) = model.touches.get_mut(&event.id) {
+                touch.current = pitch;
+            }
+            match model.glissando_mode {
+                GlissandoMode::Absolute => {
+                    let snapped_pitch = model.engine.snap_to_scale(pitch);
+                    position_event(app, model, event.position, id, |_| {
+                        Event::Moved(id, Location::Pitch(snapped_pitch))
+                    });
+                }
+                GlissandoMode::Relative => {
+                    let glide = model.glide();
+                    for (&touch_id, touch) in &model.touches {
+                        if touch_id != event.id {
+                            model.engine.handle_event(Event::Moved(
+                                SourceId::Touchpad(touch_id),
+                                Location::Pitch(touch.locked * glide),
+                            ));
+                        }
+                    }
+                    let locked_pitch = model
+                        .touches
+                        .get(&event.id)
+                        .map(|touch| touch.locked * glide)
+                        .unwrap_or(pitch);
+                    position_event(app, model, event.position, id, |_| {
+                        Event::Moved(id, Location::Pitch(locked_pitch))
+                    });
+                }
+            }
         }
         TouchPhase::Ended | TouchPhase::Cancelled => {
+            model.rebase_touches();
+            model.touches.remove(&event.id);
             model.engine.handle_event(Event::Released(id, 100))
         }
     }
 }
 
+/// Returns `true` if the position hits a control pad.
+fn grab_pad(app: &App, model: &mut Model, position: Point2, id: SourceId) -> bool {
+    match pad::hit_test(app.window_rect(), &model.pads, position) {
+        Some(index) => {
+            model.grabbed_pads.insert(id, index);
+            move_pad(app, model, position, id)
+        }
+        None => false,
+    }
+}
+
+/// Returns `true` if the source has grabbed a control pad.
+fn move_pad(app: &App, model: &Model, position: Point2, id: SourceId) -> bool {
+    let index = match model.grabbed_pads.get(&id) {
+        Some(&index) => index,
+        None => return false,
+    };
+
+    let pad = model.pads[index];
+    let rect = pad::layout(app.window_rect(), &model.pads)[index];
+    let (x_value, y_value) = pad.values_at(rect, position);
+
+    model.engine.set_parameter(pad.x, x_value);
+    if let (Some(y_parameter), Some(y_value)) = (pad.y, y_value) {
+        model.engine.set_parameter(y_parameter, y_value);
+    }
+    true
+}
+
+/// Returns `true` if the source has released a control pad.
+fn release_pad(model: &mut Model, id: SourceId) -> bool {
+    model.grabbed_pads.remove(&id).is_some()
+}
+
+fn pitch_at_position(app: &App, model: &Model, position: Point2) -> Pitch {
+    let x_normalized = position.x / app.window_rect().w() + 0.5;
+
+    let keyboard_range =
+        Ratio::between_pitches(model.pitch_at_left_border, model.pitch_at_right_border);
+    model.pitch_at_left_border * keyboard_range.repeated(x_normalized)
+}
+
 fn position_event(
     app: &App,
     model: &Model,
@@ -284,19 +865,184 @@ fn position_event(
     let x_normalized = position.x / app.window_rect().w() + 0.5;
     let y_normalized = position.y / app.window_rect().h() + 0.5;
 
-    let keyboard_range =
-        Ratio::between_pitches(model.pitch_at_left_border, model.pitch_at_right_border);
-    let pitch = model.pitch_at_left_border * keyboard_range.repeated(x_normalized);
+    let targets = model.pointer_mapping.y_targets(id);
 
-    if let SourceId::Mouse = id {
-        model
-            .engine
-            .set_parameter(LiveParameter::Breath, y_normalized.into());
+    for target in targets {
+        if let PressureTarget::Parameter(parameter) = *target {
+            model.engine.set_parameter(parameter, y_normalized.into());
+        }
+    }
+    match model.pointer_mapping.x_target(id) {
+        AxisTarget::Pitch => {
+            let pitch = pitch_at_position(app, model, position);
+            model.engine.handle_event(to_event(Location::Pitch(pitch)));
+        }
+        AxisTarget::Parameter(parameter) => {
+            model.engine.set_parameter(parameter, x_normalized.into());
+        }
+    }
+    for target in targets {
+        match target {
+            PressureTarget::Key => model.engine.set_key_pressure(id, y_normalized.into()),
+            PressureTarget::Source => model.engine.set_source_pressure(id, y_normalized.into()),
+            PressureTarget::Parameter(_) => {}
+        }
+    }
+}
+
+pub fn update(app: &App, model: &mut Model, _: Update) {
+    if model.shutdown_signal.is_requested() {
+        app.quit();
     }
-    model.engine.handle_event(to_event(Location::Pitch(pitch)));
-    model.engine.set_key_pressure(id, y_normalized.into());
+    model.update();
+    model
+        .pitch_history
+        .update(app.time, &model.engine_snapshot.pressed_keys);
+    model
+        .spectrogram
+        .update(model.pitch_at_left_border..model.pitch_at_right_border);
+    model.scope.update();
 }
 
-pub fn update(_: &App, model: &mut Model, _: Update) {
-    model.update()
+pub fn exit(_: &App, model: Model) {
+    model.panic();
+    // Give the backends some time to deliver their final messages, e.g. MIDI All Sound Off
+    thread::sleep(Duration::from_millis(100));
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::scala::{KbmRoot, Scl};
+
+    use crate::assets::ScaleSpec;
+
+    use super::*;
+
+    fn scale_preset(num_semitones: i32, kbm: Option<Kbm>) -> ScalePreset {
+        ScalePreset {
+            scl: Scl::builder()
+                .push_ratio(Ratio::from_semitones(num_semitones))
+                .build()
+                .unwrap(),
+            kbm,
+            keyboard: Keyboard::root_at(PianoKey::from_midi_number(0)),
+        }
+    }
+
+    #[test]
+    fn parse_scale_specs() {
+        let specs: Vec<ScaleSpec> = serde_yaml::from_str(
+            "- scl_file_location: meantone.scl\n\
+             - scl_file_location: bohlen-pierce.scl\n  \
+               kbm_file_location: bohlen-pierce.kbm",
+        )
+        .unwrap();
+
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].scl_file_location.to_str(), Some("meantone.scl"));
+        assert_eq!(specs[0].kbm_file_location, None);
+        assert_eq!(
+            specs[1].scl_file_location.to_str(),
+            Some("bohlen-pierce.scl")
+        );
+        assert_eq!(
+            specs[1]
+                .kbm_file_location
+                .as_deref()
+                .and_then(|x| x.to_str()),
+            Some("bohlen-pierce.kbm")
+        );
+    }
+
+    #[test]
+    fn cycle_scale_presets_with_initial_kbm() {
+        let initial_kbm = KbmRoot::from(NoteLetter::D.in_octave(4)).to_kbm();
+        let other_kbm = KbmRoot::from(NoteLetter::A.in_octave(4)).to_kbm();
+
+        let mut presets = ScalePresets::new(
+            scale_preset(1, Some(initial_kbm.clone())),
+            vec![
+                scale_preset(2, None),
+                scale_preset(3, Some(other_kbm.clone())),
+            ],
+        );
+
+        let num_semitones = |preset: &ScalePreset| preset.scl.period().as_semitones().round();
+
+        assert_eq!(num_semitones(presets.curr()), 1.0);
+        assert_eq!(num_semitones(presets.next()), 2.0);
+
+        let preset = presets.step_by(1);
+        assert_eq!(num_semitones(preset), 2.0);
+        assert_eq!(preset.kbm.as_ref(), Some(&initial_kbm));
+
+        let preset = presets.step_by(1);
+        assert_eq!(num_semitones(preset), 3.0);
+        assert_eq!(preset.kbm.as_ref(), Some(&other_kbm));
+        assert_eq!(num_semitones(presets.next()), 1.0);
+
+        // Switching back after a different kbm restores the initial kbm
+        let preset = presets.step_by(-1);
+        assert_eq!(num_semitones(preset), 2.0);
+        assert_eq!(preset.kbm.as_ref(), Some(&initial_kbm));
+
+        // Wrap around at both ends
+        let preset = presets.step_by(2);
+        assert_eq!(num_semitones(preset), 1.0);
+        let preset = presets.step_by(-1);
+        assert_eq!(num_semitones(preset), 3.0);
+    }
+
+    #[test]
+    fn replace_scale_presets_on_reload() {
+        let initial_kbm = KbmRoot::from(NoteLetter::D.in_octave(4)).to_kbm();
+
+        let mut presets = ScalePresets::new(
+            scale_preset(1, Some(initial_kbm.clone())),
+            vec![scale_preset(2, None), scale_preset(3, None)],
+        );
+        presets.step_by(2);
+
+        let num_semitones = |preset: &ScalePreset| preset.scl.period().as_semitones().round();
+
+        presets.replace_others(vec![scale_preset(4, None), scale_preset(5, None)]);
+        assert_eq!(num_semitones(presets.curr()), 5.0);
+        assert_eq!(presets.curr().kbm.as_ref(), Some(&initial_kbm));
+        assert_eq!(num_semitones(presets.next()), 1.0);
+
+        // The selected preset no longer exists
+        presets.replace_others(vec![scale_preset(6, None)]);
+        assert_eq!(num_semitones(presets.curr()), 1.0);
+        assert_eq!(num_semitones(presets.next()), 6.0);
+    }
+
+    #[test]
+    fn parse_pointer_mapping() {
+        let pointer_mapping: PointerMapping = serde_yaml::from_str(
+            "mouse-x: modulation\nmouse-y: [key, source, sound-1]\nctrl-scroll: expression",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pointer_mapping.mouse_x,
+            AxisTarget::Parameter(LiveParameter::Modulation)
+        );
+        assert_eq!(
+            pointer_mapping.mouse_y,
+            [
+                PressureTarget::Key,
+                PressureTarget::Source,
+                PressureTarget::Parameter(LiveParameter::Sound1)
+            ]
+        );
+        assert_eq!(pointer_mapping.touch_y, [PressureTarget::Key]);
+        assert_eq!(pointer_mapping.ctrl_scroll, Some(LiveParameter::Expression));
+
+        let yml = serde_yaml::to_string(&pointer_mapping).unwrap();
+        assert!(yml.contains("mouse-x: modulation"));
+        assert!(yml.contains("- sound-1"));
+        assert!(yml.contains("ctrl-scroll: expression"));
+
+        assert!(serde_yaml::from_str::<PointerMapping>("mouse-x: Pitch").is_err());
+    }
 }
@@ -0,0 +1,218 @@
+use std::{ffi::CString, os::raw::c_char, ptr, sync::mpsc::Sender};
+
+use clap::ValueEnum;
+use libloading::Library;
+use tune::{
+    key::PianoKey,
+    note::Note,
+    pitch::{Pitch, Pitched, Ratio},
+    scala::{Kbm, KbmRoot, Scl},
+    tuning::Tuning,
+};
+use tune_cli::CliResult;
+
+use crate::piano::Backend;
+
+#[cfg(target_os = "windows")]
+const LIB_MTS_LOCATION: &str = "C:\\Program Files\\Common Files\\MTS-ESP\\LIBMTS.dll";
+#[cfg(target_os = "macos")]
+const LIB_MTS_LOCATION: &str = "/Library/Application Support/MTS-ESP/libMTS.dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIB_MTS_LOCATION: &str = "/usr/local/lib/libMTS.so";
+
+const NUM_NOTES: usize = 128;
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum MtsEspMode {
+    /// Publish the current tuning to all MTS-ESP clients
+    #[value(name = "master")]
+    Master,
+
+    /// Import the tuning of the running MTS-ESP master at startup
+    #[value(name = "client")]
+    Client,
+}
+
+/// Function table of the dynamically loaded libMTS. The [`Library`] must outlive the function pointers.
+struct LibMts {
+    _library: Library,
+    can_register_master: unsafe extern "C" fn() -> bool,
+    register_master: unsafe extern "C" fn(*mut ()),
+    deregister_master: unsafe extern "C" fn(),
+    has_master: unsafe extern "C" fn() -> bool,
+    set_note_tunings: unsafe extern "C" fn(*const f64),
+    set_scale_name: unsafe extern "C" fn(*const c_char),
+    register_client: unsafe extern "C" fn(),
+    deregister_client: unsafe extern "C" fn(),
+    get_tuning_table: unsafe extern "C" fn() -> *const f64,
+}
+
+impl LibMts {
+    fn load() -> CliResult<Self> {
+        let error = |err: libloading::Error| {
+            format!("Could not load MTS-ESP library at {LIB_MTS_LOCATION} ({err})")
+        };
+
+        // Safety: libMTS is a C library without initialization routines and the symbol types match the MTS-ESP SDK.
+        unsafe {
+            let library = Library::new(LIB_MTS_LOCATION).map_err(error)?;
+            Ok(Self {
+                can_register_master: *library.get(b"MTS_CanRegisterMaster\0").map_err(error)?,
+                register_master: *library.get(b"MTS_RegisterMaster\0").map_err(error)?,
+                deregister_master: *library.get(b"MTS_DeregisterMaster\0").map_err(error)?,
+                has_master: *library.get(b"MTS_HasMaster\0").map_err(error)?,
+                set_note_tunings: *library.get(b"MTS_SetNoteTunings\0").map_err(error)?,
+                set_scale_name: *library.get(b"MTS_SetScaleName\0").map_err(error)?,
+                register_client: *library.get(b"MTS_RegisterClient\0").map_err(error)?,
+                deregister_client: *library.get(b"MTS_DeregisterClient\0").map_err(error)?,
+                get_tuning_table: *library.get(b"MTS_GetTuningTable\0").map_err(error)?,
+                _library: library,
+            })
+        }
+    }
+}
+
+/// Silent backend registering microwave as the MTS-ESP master. Every tuning change is published to all connected clients.
+pub struct MtsEspBackend<I> {
+    lib_mts: LibMts,
+    info_sender: Sender<I>,
+    is_tuned: bool,
+}
+
+pub fn create_master<I>(info_sender: Sender<I>) -> CliResult<MtsEspBackend<I>> {
+    let lib_mts = LibMts::load()?;
+
+    unsafe {
+        if !(lib_mts.can_register_master)() {
+            return Err("Another MTS-ESP master is already registered"
+                .to_owned()
+                .into());
+        }
+        (lib_mts.register_master)(ptr::null_mut());
+    }
+
+    Ok(MtsEspBackend {
+        lib_mts,
+        info_sender,
+        is_tuned: false,
+    })
+}
+
+impl<I> MtsEspBackend<I> {
+    fn publish(&self, scale_name: &str, pitches: impl Fn(PianoKey) -> Pitch) {
+        let frequencies: Vec<_> = (0..NUM_NOTES)
+            .map(|midi_number| pitches(PianoKey::from_midi_number(midi_number as i32)).as_hz())
+            .collect();
+        let scale_name = CString::new(scale_name.replace('\0', "")).unwrap();
+
+        unsafe {
+            (self.lib_mts.set_note_tunings)(frequencies.as_ptr());
+            (self.lib_mts.set_scale_name)(scale_name.as_ptr());
+        }
+    }
+}
+
+impl<I> Drop for MtsEspBackend<I> {
+    fn drop(&mut self) {
+        unsafe { (self.lib_mts.deregister_master)() }
+    }
+}
+
+impl<I: From<MtsEspInfo> + Send, S> Backend<S> for MtsEspBackend<I> {
+    fn set_tuning(&mut self, tuning: (&Scl, KbmRoot)) {
+        self.publish(tuning.0.description(), |key| tuning.pitch_of(key));
+        self.is_tuned = true;
+    }
+
+    fn set_no_tuning(&mut self) {
+        self.publish("12-EDO", |key| {
+            Note::from_midi_number(key.midi_number()).pitch()
+        });
+        self.is_tuned = false;
+    }
+
+    fn send_status(&mut self) {
+        self.info_sender
+            .send(
+                MtsEspInfo {
+                    is_tuned: self.is_tuned,
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+
+    fn start(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pitch(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pressure(&mut self, _id: S, _pressure: u8) {}
+
+    fn stop(&mut self, _id: S, _velocity: u8) {}
+
+    fn all_sound_off(&mut self) {}
+
+    fn program_change(&mut self, _update_fn: Box<dyn FnMut(usize) -> usize + Send>) {}
+
+    fn control_change(&mut self, _controller: u8, _value: u8) {}
+
+    fn channel_pressure(&mut self, _pressure: u8) {}
+
+    fn pitch_bend(&mut self, _value: i16) {}
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+
+    fn publishes_tuning(&self) -> bool {
+        true
+    }
+}
+
+pub struct MtsEspInfo {
+    pub is_tuned: bool,
+}
+
+/// Reads the 128-note tuning table of the running MTS-ESP master and converts it into a 127-step scale rooted at MIDI note 0.
+pub fn import_tuning() -> CliResult<(Scl, Kbm)> {
+    let lib_mts = LibMts::load()?;
+
+    let frequencies = unsafe {
+        (lib_mts.register_client)();
+        let frequencies = if (lib_mts.has_master)() {
+            let tuning_table = (lib_mts.get_tuning_table)();
+            Some(
+                (0..NUM_NOTES)
+                    .map(|index| *tuning_table.add(index))
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        };
+        (lib_mts.deregister_client)();
+        frequencies
+    };
+
+    let frequencies = frequencies.ok_or_else(|| "No MTS-ESP master is running".to_owned())?;
+
+    let root_pitch = Pitch::from_hz(frequencies[0]);
+    let mut builder = Scl::builder();
+    for &frequency in &frequencies[1..] {
+        builder = builder.push_ratio(Ratio::between_pitches(
+            root_pitch,
+            Pitch::from_hz(frequency),
+        ));
+    }
+    let scl = builder.build_with_description("Imported from MTS-ESP master")?;
+
+    let kbm = KbmRoot {
+        ref_key: PianoKey::from_midi_number(0),
+        ref_pitch: root_pitch,
+        root_offset: 0,
+    }
+    .to_kbm();
+
+    Ok((scl, kbm))
+}
@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use nannou::geom::{Point2, Rect};
+
+use crate::control::LiveParameter;
+
+const PAD_SIZE: f32 = 150.0;
+const SLIDER_HEIGHT: f32 = 40.0;
+const PADDING: f32 = 10.0;
+
+/// An on-screen widget controlling one (slider) or two (XY pad) live parameters.
+#[derive(Clone, Copy, Debug)]
+pub struct ControlPad {
+    pub x: LiveParameter,
+    pub y: Option<LiveParameter>,
+}
+
+impl FromStr for ControlPad {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((x, y)) => Ok(Self {
+                x: x.parse()?,
+                y: Some(y.parse()?),
+            }),
+            None => Ok(Self {
+                x: s.parse()?,
+                y: None,
+            }),
+        }
+    }
+}
+
+impl ControlPad {
+    /// Maps the given position to the normalized (x, y) parameter values.
+    pub fn values_at(&self, rect: Rect, position: Point2) -> (f64, Option<f64>) {
+        let x_normalized = ((position.x - rect.left()) / rect.w()).clamp(0.0, 1.0);
+        let y_normalized = ((position.y - rect.bottom()) / rect.h()).clamp(0.0, 1.0);
+        (x_normalized.into(), self.y.map(|_| y_normalized.into()))
+    }
+}
+
+/// Arranges the given pads from left to right, starting at the top left corner of the window.
+pub fn layout(window_rect: Rect, pads: &[ControlPad]) -> Vec<Rect> {
+    let mut left = window_rect.left() + PADDING;
+    pads.iter()
+        .map(|pad| {
+            let height = match pad.y {
+                Some(_) => PAD_SIZE,
+                None => SLIDER_HEIGHT,
+            };
+            let rect = Rect::from_x_y_w_h(
+                left + PAD_SIZE / 2.0,
+                window_rect.top() - PADDING - height / 2.0,
+                PAD_SIZE,
+                height,
+            );
+            left += PAD_SIZE + PADDING;
+            rect
+        })
+        .collect()
+}
+
+/// Finds the index of the pad at the given position.
+pub fn hit_test(window_rect: Rect, pads: &[ControlPad], position: Point2) -> Option<usize> {
+    layout(window_rect, pads)
+        .iter()
+        .position(|rect| rect.contains(position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_control_pad() {
+        let pad = "sound-1:breath".parse::<ControlPad>().unwrap();
+        assert_eq!(pad.x, LiveParameter::Sound1);
+        assert_eq!(pad.y, Some(LiveParameter::Breath));
+
+        let slider = "modulation".parse::<ControlPad>().unwrap();
+        assert_eq!(slider.x, LiveParameter::Modulation);
+        assert_eq!(slider.y, None);
+
+        assert!("modulation:unknown".parse::<ControlPad>().is_err());
+    }
+}
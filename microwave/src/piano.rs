@@ -1,19 +1,29 @@
 use std::{
-    collections::HashMap,
-    ops::{Deref, DerefMut},
+    collections::{BTreeSet, HashMap},
+    mem,
+    ops::{Deref, DerefMut, Range, RangeInclusive},
     sync::{mpsc::Sender, Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 
+use serde::{Deserialize, Serialize};
 use tune::{
+    key::PianoKey,
     midi::ChannelMessageType,
-    pitch::Pitch,
+    pitch::{Pitch, Ratio},
     scala::{Kbm, KbmRoot, Scl},
-    tuning::Tuning,
+    tuning::{CrossfadedTuning, Tuning, TuningTable},
 };
 use tune_cli::shared::midi::MultiChannelOffset;
 
 use crate::{
-    control::{LiveParameter, LiveParameterMapper, LiveParameterStorage, ParameterValue},
+    control::{
+        LiveParameter, LiveParameterMapper, LiveParameterStorage, ModulationRoute,
+        ModulationSource, ParameterValue,
+    },
+    drone::{Drone, DroneString},
+    event_log::{EventLog, LoggedEvent},
+    midi::{BANK_SELECT_LSB_CCN, BANK_SELECT_MSB_CCN},
     model::{Event, Location, SourceId},
 };
 
@@ -31,9 +41,11 @@ pub struct PianoEngineSnapshot {
     pub pressed_keys: HashMap<SourceId, PressedKey>,
     pub mapper: LiveParameterMapper,
     pub storage: LiveParameterStorage,
+    /// Number of times the cue switch has been pressed since startup.
+    pub num_cue_triggers: u64,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum TuningMode {
     Fixed,
     Continuous,
@@ -51,14 +63,107 @@ impl TuningMode {
 #[derive(Clone, Debug)]
 pub struct PressedKey {
     pub backend: usize,
+    pub degree: i32,
     pub pitch: Pitch,
 }
 
+type BakedTuning = TuningTable<(Scl, KbmRoot)>;
+
+/// A transition from the previous scale to the current scale.
+struct Crossfade {
+    source: BakedTuning,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Crossfade {
+    fn progress(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() / self.duration.as_secs_f64()
+    }
+}
+
+/// A slide of a new note from the pitch of the previous note to its own pitch.
+struct Glide {
+    from: Pitch,
+    to: Pitch,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Glide {
+    fn progress(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() / self.duration.as_secs_f64()
+    }
+
+    /// The pitch moves at a constant rate on a logarithmic scale.
+    fn pitch(&self) -> Pitch {
+        self.from * Ratio::between_pitches(self.from, self.to).repeated(self.progress().min(1.0))
+    }
+}
+
+/// The current tuning, taking an ongoing crossfade into account.
+fn crossfaded_tuning<'a>(
+    tuning_table: &'a BakedTuning,
+    crossfade: &'a Option<Crossfade>,
+) -> CrossfadedTuning<&'a BakedTuning, &'a BakedTuning> {
+    match crossfade {
+        Some(crossfade) => CrossfadedTuning::new(&crossfade.source, tuning_table)
+            .with_progress(crossfade.progress()),
+        None => CrossfadedTuning::new(tuning_table, tuning_table),
+    }
+}
+
+/// Restricts a backend to a range of MIDI keys and/or a set of MIDI-in channels.
+///
+/// Matching MIDI notes are played by the zone's backend regardless of the currently selected backend.
+#[derive(Clone, Debug)]
+pub struct KeyZone {
+    pub keys: Option<RangeInclusive<i32>>,
+    pub channels: Option<BTreeSet<u8>>,
+}
+
+impl KeyZone {
+    pub fn contains(&self, piano_key: PianoKey, channel: u8) -> bool {
+        self.keys
+            .as_ref()
+            .map(|keys| keys.contains(&piano_key.midi_number()))
+            .unwrap_or(true)
+            && self
+                .channels
+                .as_ref()
+                .map(|channels| channels.contains(&channel))
+                .unwrap_or(true)
+    }
+}
+
+/// Scale degrees for which pitches are precomputed.
+const TUNING_TABLE_DEGREES: Range<i32> = -1024..1024;
+
+/// Number of banks that can be reached via [`PianoEngine::change_bank_by`], i.e. the melodic banks 0-127 and the drum bank 128.
+const NUM_STEPPED_BANKS: i32 = 129;
+
 struct PianoEngineModel {
     snapshot: PianoEngineSnapshot,
     backends: Vec<Box<dyn Backend<SourceId>>>,
     scl: Scl,
+    tuning_table: BakedTuning,
+    crossfade: Option<Crossfade>,
+    glides: HashMap<SourceId, Glide>,
+    last_pitch: Option<Pitch>,
     storage_updates: Sender<LiveParameterStorage>,
+    event_log: Option<EventLog>,
+    listeners: Vec<Box<dyn Backend<SourceId>>>,
+    zones: Vec<(usize, KeyZone)>,
+    root_channel: Option<u8>,
+    /// The keyboard root from before the movable root took over. New roots are tuned relative to it s.t. no commas accumulate.
+    home_kbm_root: Option<KbmRoot>,
+    modulation_routes: Vec<ModulationRoute>,
+    cue_ccn: u8,
+    cue_switch_pressed: bool,
+    /// The bank most recently selected via bank select messages. Like in oxisynth, a bank select MSB message on its own selects the bank with the given number.
+    bank: u16,
+    bank_msb: u8,
+    drone: Drone,
 }
 
 impl Deref for PianoEngineModel {
@@ -75,15 +180,25 @@ impl DerefMut for PianoEngineModel {
 }
 
 impl PianoEngine {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         scl: Scl,
         kbm: Kbm,
         backends: Vec<Box<dyn Backend<SourceId>>>,
         program_number: u8,
         mapper: LiveParameterMapper,
+        cue_ccn: u8,
         storage: LiveParameterStorage,
         storage_updates: Sender<LiveParameterStorage>,
+        event_log: Option<EventLog>,
+        listeners: Vec<Box<dyn Backend<SourceId>>>,
+        zones: Vec<(usize, KeyZone)>,
+        root_channel: Option<u8>,
+        modulation_routes: Vec<ModulationRoute>,
+        drone: Drone,
     ) -> (Arc<Self>, PianoEngineSnapshot) {
+        let tuning_table = TuningTable::bake((scl.clone(), kbm.kbm_root()), TUNING_TABLE_DEGREES);
+
         let snapshot = PianoEngineSnapshot {
             curr_backend: 0,
             tuning_mode: TuningMode::Fixed,
@@ -91,17 +206,34 @@ impl PianoEngine {
             pressed_keys: HashMap::new(),
             storage,
             mapper,
+            num_cue_triggers: 0,
         };
 
         let mut model = PianoEngineModel {
             snapshot: snapshot.clone(),
             backends,
             scl,
+            tuning_table,
+            crossfade: None,
+            glides: HashMap::new(),
+            last_pitch: None,
             storage_updates,
+            event_log,
+            listeners,
+            zones,
+            root_channel,
+            home_kbm_root: None,
+            modulation_routes,
+            cue_ccn,
+            cue_switch_pressed: false,
+            bank: 0,
+            bank_msb: 0,
+            drone,
         };
 
         model.retune();
         model.set_program(program_number);
+        model.send_parameters_to_listeners();
 
         let engine = Self {
             model: Mutex::new(model),
@@ -110,8 +242,14 @@ impl PianoEngine {
         (Arc::new(engine), snapshot)
     }
 
-    pub fn handle_midi_event(&self, message_type: ChannelMessageType, offset: MultiChannelOffset) {
-        self.lock_model().handle_midi_event(message_type, offset);
+    pub fn handle_midi_event(
+        &self,
+        message_type: ChannelMessageType,
+        channel: u8,
+        offset: MultiChannelOffset,
+    ) {
+        self.lock_model()
+            .handle_midi_event(message_type, channel, offset);
     }
 
     pub fn handle_event(&self, event: Event) {
@@ -122,10 +260,31 @@ impl PianoEngine {
         self.lock_model().set_parameter(parameter, value);
     }
 
+    /// Sets the parameter without sending a control change message to the backends, e.g. because the value is only relevant to microwave itself.
+    pub fn set_parameter_without_backends_update(&self, parameter: LiveParameter, value: f64) {
+        self.lock_model()
+            .set_parameter_without_backends_update(parameter, value);
+    }
+
+    pub fn set_modulation_routes(&self, modulation_routes: Vec<ModulationRoute>) {
+        self.lock_model().modulation_routes = modulation_routes;
+    }
+
+    /// Returns the pitch of the scale degree closest to the given pitch.
+    pub fn snap_to_scale(&self, pitch: Pitch) -> Pitch {
+        let model = self.lock_model();
+        let tuning = crossfaded_tuning(&model.tuning_table, &model.crossfade);
+        tuning.pitch_of(tuning.find_by_pitch(pitch).approx_value)
+    }
+
     pub fn set_key_pressure(&self, id: SourceId, value: f64) {
         self.lock_model().set_key_pressure(id, value.as_u8());
     }
 
+    pub fn set_source_pressure(&self, id: SourceId, value: f64) {
+        self.lock_model().set_source_pressure(id, value.as_u8());
+    }
+
     pub fn toggle_tuning_mode(&self) {
         let mut model = self.lock_model();
         model.tuning_mode.toggle();
@@ -150,18 +309,31 @@ impl PianoEngine {
         self.lock_model().toggle_parameter(parameter);
     }
 
+    pub fn set_program(&self, program: u8) {
+        self.lock_model().set_program(program);
+    }
+
     pub fn inc_program(&self) {
         let mut model = self.lock_model();
-        let backend = &mut model.backend_mut();
-        backend.program_change(Box::new(|p| p.saturating_add(1)));
-        backend.send_status();
+        model.log(|| LoggedEvent::ProgramStep { delta: 1 });
+        model.change_program(|p| p.saturating_add(1));
     }
 
     pub fn dec_program(&self) {
         let mut model = self.lock_model();
-        let backend = &mut model.backend_mut();
-        backend.program_change(Box::new(|p| p.saturating_sub(1)));
-        backend.send_status();
+        model.log(|| LoggedEvent::ProgramStep { delta: -1 });
+        model.change_program(|p| p.saturating_sub(1));
+    }
+
+    /// Sends bank select messages to all backends. The selected backend applies the new bank to its current program immediately.
+    ///
+    /// The banks wrap around after the usual drum bank 128 s.t. the drum kits are only one step away from bank 0.
+    pub fn change_bank_by(&self, delta: i32) {
+        let mut model = self.lock_model();
+        let bank =
+            u16::try_from((i32::from(model.bank) + delta).rem_euclid(NUM_STEPPED_BANKS)).unwrap();
+        model.control_change(BANK_SELECT_MSB_CCN, u8::try_from(bank >> 7).unwrap());
+        model.control_change(BANK_SELECT_LSB_CCN, u8::try_from(bank & 0x7f).unwrap());
     }
 
     pub fn change_ref_note_by(&self, delta: i32) {
@@ -169,6 +341,7 @@ impl PianoEngine {
         let mut kbm_root = model.kbm.kbm_root();
         kbm_root = kbm_root.shift_ref_key_by(delta);
         model.kbm.set_kbm_root(kbm_root);
+        model.home_kbm_root = None;
         model.retune();
     }
 
@@ -177,9 +350,100 @@ impl PianoEngine {
         let mut kbm_root = model.kbm.kbm_root();
         kbm_root.root_offset += delta;
         model.kbm.set_kbm_root(kbm_root);
+        model.home_kbm_root = None;
+        model.retune();
+    }
+
+    pub fn set_tuning(&self, tuning_mode: TuningMode, kbm_root: KbmRoot) {
+        let mut model = self.lock_model();
+        model.tuning_mode = tuning_mode;
+        model.kbm.set_kbm_root(kbm_root);
+        model.home_kbm_root = None;
         model.retune();
     }
 
+    /// Switches to a new scale and, if given, a new keyboard mapping while the engine is running.
+    ///
+    /// In fixed tuning mode, held notes are retuned to their new pitch. Held notes on backends without legato support are released.
+    ///
+    /// If `crossfade_duration` is non-zero, the pitches of all degrees glide from the old to the new scale over the given duration.
+    /// During the crossfade, the backends operate in continuous tuning mode. Call [`PianoEngine::update_crossfade`] periodically to advance the glide.
+    pub fn set_scale(&self, scl: Scl, kbm: Option<Kbm>, crossfade_duration: Duration) {
+        let mut model = self.lock_model();
+        if let Some(kbm) = kbm {
+            model.kbm = kbm;
+            model.home_kbm_root = None;
+        }
+        model.release_keys_without_legato();
+        let tuning_table =
+            TuningTable::bake((scl.clone(), model.kbm.kbm_root()), TUNING_TABLE_DEGREES);
+        let source = mem::replace(&mut model.tuning_table, tuning_table);
+        model.scl = scl;
+
+        match (model.tuning_mode, crossfade_duration.is_zero()) {
+            (TuningMode::Fixed, false) => {
+                model.crossfade = Some(Crossfade {
+                    source,
+                    start: Instant::now(),
+                    duration: crossfade_duration,
+                });
+                model.retune();
+            }
+            _ => {
+                model.crossfade = None;
+                model.retune();
+                model.retune_pressed_keys();
+            }
+        }
+    }
+
+    /// Retunes held notes according to the progress of the current crossfade. Finalizes the crossfade once it is complete.
+    pub fn update_crossfade(&self) {
+        let mut model = self.lock_model();
+        let progress = match &model.crossfade {
+            Some(crossfade) => crossfade.progress(),
+            None => return,
+        };
+        model.retune_pressed_keys();
+        if progress >= 1.0 {
+            model.crossfade = None;
+            model.retune();
+        }
+    }
+
+    /// Moves gliding notes towards their target pitches. Call periodically while glide is enabled.
+    pub fn update_glides(&self) {
+        self.lock_model().update_glides();
+    }
+
+    /// Plucks the next string of the drone while the drone is switched on. Call periodically.
+    pub fn update_drone(&self) {
+        self.lock_model().update_drone();
+    }
+
+    /// Silences all backends and listeners and forgets about the pressed keys s.t. no note keeps sounding.
+    ///
+    /// Called when the user hits the panic key and when microwave shuts down.
+    pub fn panic(&self) {
+        let mut model = self.lock_model();
+        model.log(|| LoggedEvent::AllSoundOff);
+        model.pressed_keys.clear();
+        model.glides.clear();
+        if model.storage.is_active(LiveParameter::Drone) {
+            model.drone.stop();
+            model.set_parameter(LiveParameter::Drone, 0.0);
+        }
+        let model = &mut *model;
+        for backend in model.backends.iter_mut().chain(&mut model.listeners) {
+            backend.all_sound_off();
+        }
+    }
+
+    /// Sends the status of the selected backend, e.g. after its configuration has been reloaded.
+    pub fn send_status(&self) {
+        self.lock_model().backend_mut().send_status();
+    }
+
     pub fn take_snapshot(&self, target: &mut PianoEngineSnapshot) {
         target.clone_from(&self.lock_model())
     }
@@ -190,7 +454,12 @@ impl PianoEngine {
 }
 
 impl PianoEngineModel {
-    fn handle_midi_event(&mut self, message_type: ChannelMessageType, offset: MultiChannelOffset) {
+    fn handle_midi_event(
+        &mut self,
+        message_type: ChannelMessageType,
+        channel: u8,
+        offset: MultiChannelOffset,
+    ) {
         match message_type {
             // Forwarded to all backends.
             ChannelMessageType::NoteOff { key, velocity }
@@ -198,34 +467,55 @@ impl PianoEngineModel {
                 key,
                 velocity: velocity @ 0,
             } => {
-                let piano_key = offset.get_piano_key(key);
-                self.handle_event(Event::Released(SourceId::Midi(piano_key), velocity));
+                if Some(channel) == self.root_channel {
+                    return;
+                }
+                if let Some(piano_key) = offset.get_piano_key(key) {
+                    self.handle_event(Event::Released(SourceId::Midi(piano_key), velocity));
+                }
             }
-            // Forwarded to current backend.
+            // Not forwarded. The note becomes the new root.
+            ChannelMessageType::NoteOn { key, .. } if Some(channel) == self.root_channel => {
+                let degree = offset
+                    .get_piano_key(key)
+                    .and_then(|piano_key| self.kbm.scale_degree_of(piano_key));
+                if let Some(degree) = degree {
+                    if self.storage.is_active(LiveParameter::MovableRoot) {
+                        self.move_root_to(degree);
+                    }
+                }
+            }
+            // Forwarded to the backend of the first matching zone or, if no zone matches, to the current backend.
             ChannelMessageType::NoteOn { key, velocity } => {
-                let piano_key = offset.get_piano_key(key);
+                let piano_key = match offset.get_piano_key(key) {
+                    Some(piano_key) => piano_key,
+                    None => return,
+                };
                 if let Some(degree) = self.kbm.scale_degree_of(piano_key) {
-                    self.handle_event(Event::Pressed(
+                    let backend = self
+                        .zones
+                        .iter()
+                        .find(|(_, zone)| zone.contains(piano_key, channel))
+                        .map(|&(backend, _)| backend)
+                        .unwrap_or(self.curr_backend);
+                    self.press(
                         SourceId::Midi(piano_key),
                         Location::Degree(degree),
                         velocity,
-                    ));
+                        backend,
+                    );
                 }
             }
             // Forwarded to all backends.
             ChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
-                let piano_key = offset.get_piano_key(key);
-                self.set_key_pressure(SourceId::Midi(piano_key), pressure);
+                if let Some(piano_key) = offset.get_piano_key(key) {
+                    self.set_key_pressure(SourceId::Midi(piano_key), pressure);
+                }
+                self.route_modulation(ModulationSource::KeyPressure, pressure.as_f64());
             }
             // Forwarded to all backends.
             ChannelMessageType::ControlChange { controller, value } => {
-                // Take a shortcut s.t. controller numbers are conserved
-                for backend in &mut self.backends {
-                    backend.control_change(controller, value);
-                }
-                for parameter in self.mapper.resolve_ccn(controller) {
-                    self.set_parameter_without_backends_update(parameter, value.as_f64());
-                }
+                self.control_change(controller, value);
             }
             // Forwarded to current backend.
             ChannelMessageType::ProgramChange { program } => {
@@ -234,19 +524,51 @@ impl PianoEngineModel {
             // Forwarded to current backend.
             ChannelMessageType::ChannelPressure { pressure } => {
                 self.set_parameter(LiveParameter::ChannelPressure, pressure);
+                self.route_modulation(ModulationSource::ChannelPressure, pressure.as_f64());
             }
             // Forwarded to all backends
-            ChannelMessageType::PitchBendChange { value } => self.pitch_bend(value),
+            ChannelMessageType::PitchBendChange { value } => {
+                self.pitch_bend(value);
+                self.route_modulation(
+                    ModulationSource::PitchBend,
+                    (f64::from(value) + 8192.0) / 16383.0,
+                );
+            }
         }
     }
 
+    fn control_change(&mut self, controller: u8, value: u8) {
+        // Take a shortcut s.t. controller numbers are conserved
+        self.log(|| LoggedEvent::ControlChange { controller, value });
+        for backend in self.backends.iter_mut().chain(&mut self.listeners) {
+            backend.control_change(controller, value);
+        }
+        for parameter in self.mapper.resolve_ccn(controller) {
+            self.set_parameter_without_backends_update(parameter, value.as_f64());
+        }
+        self.route_modulation(ModulationSource::ControlChange(controller), value.as_f64());
+        if controller == self.cue_ccn {
+            let cue_switch_pressed = value >= 64;
+            if cue_switch_pressed && !self.cue_switch_pressed {
+                self.snapshot.num_cue_triggers += 1;
+            }
+            self.cue_switch_pressed = cue_switch_pressed;
+        }
+        match controller {
+            BANK_SELECT_MSB_CCN => {
+                self.bank_msb = value;
+                self.bank = u16::from(value);
+            }
+            BANK_SELECT_LSB_CCN => self.bank = u16::from(self.bank_msb) << 7 | u16::from(value),
+            _ => return,
+        }
+        self.backend_mut().send_status();
+    }
+
     fn handle_event(&mut self, event: Event) {
         match event {
             Event::Pressed(id, location, velocity) => {
-                let (degree, pitch) = self.degree_and_pitch(location);
-                self.backend_mut().start(id, degree, pitch, velocity);
-                let backend = self.curr_backend;
-                self.pressed_keys.insert(id, PressedKey { backend, pitch });
+                self.press(id, location, velocity, self.curr_backend);
             }
             Event::Moved(id, location) => {
                 if self.storage.is_active(LiveParameter::Legato) {
@@ -254,25 +576,207 @@ impl PianoEngineModel {
                     let (pressed_keys, backends) =
                         (&mut self.snapshot.pressed_keys, &mut self.backends);
                     if let Some(pressed_key) = pressed_keys.get_mut(&id) {
+                        // The note follows the finger from now on
+                        self.glides.remove(&id);
+                        if let Some(event_log) = &mut self.event_log {
+                            event_log.write(LoggedEvent::PitchUpdate {
+                                source: id.into(),
+                                degree,
+                                pitch_hz: pitch.as_hz(),
+                            });
+                        }
                         let backend = &mut backends[pressed_key.backend];
                         backend.update_pitch(id, degree, pitch, 100);
                         if backend.has_legato() {
+                            pressed_key.degree = degree;
                             pressed_key.pitch = pitch;
+                            for listener in &mut self.listeners {
+                                listener.update_pitch(id, degree, pitch, 100);
+                            }
                         }
                     }
                 }
             }
             Event::Released(id, velocity) => {
-                for backend in &mut self.backends {
+                self.log(|| LoggedEvent::NoteOff {
+                    source: id.into(),
+                    velocity,
+                });
+                for backend in self.backends.iter_mut().chain(&mut self.listeners) {
                     backend.stop(id, velocity);
                 }
                 self.pressed_keys.remove(&id);
+                self.glides.remove(&id);
             }
         }
     }
 
+    fn press(&mut self, id: SourceId, mut location: Location, velocity: u8, backend: usize) {
+        // The computer keyboard layout is fixed to the home root s.t. each physical key keeps its pitch when the root moves
+        if let (SourceId::Keyboard(..), Location::Degree(degree)) = (id, &mut location) {
+            *degree = self.degree_of_home_degree(*degree);
+        }
+
+        if self.storage.is_active(LiveParameter::MovableRoot) && self.root_channel.is_none() {
+            let (degree, pitch) = self.degree_and_pitch(location);
+            let is_lowest = self
+                .pressed_keys
+                .iter()
+                .filter(|(id, _)| !matches!(id, SourceId::Drone(_)))
+                .all(|(_, pressed_key)| pressed_key.pitch > pitch);
+            if is_lowest {
+                self.move_root_to(degree);
+                if let Location::Degree(degree) = &mut location {
+                    *degree = 0;
+                }
+            }
+        }
+
+        let (degree, target_pitch) = self.degree_and_pitch(location);
+        let glide_duration = self.storage.glide_duration();
+        let pitch = match self.last_pitch.replace(target_pitch) {
+            Some(last_pitch)
+                if !glide_duration.is_zero() && self.backends[backend].has_continuous_pitch() =>
+            {
+                self.glides.insert(
+                    id,
+                    Glide {
+                        from: last_pitch,
+                        to: target_pitch,
+                        start: Instant::now(),
+                        duration: glide_duration,
+                    },
+                );
+                last_pitch
+            }
+            _ => {
+                self.glides.remove(&id);
+                target_pitch
+            }
+        };
+        self.start(id, degree, pitch, velocity, backend);
+    }
+
+    fn start(&mut self, id: SourceId, degree: i32, pitch: Pitch, velocity: u8, backend: usize) {
+        self.log(|| LoggedEvent::NoteOn {
+            source: id.into(),
+            degree,
+            pitch_hz: pitch.as_hz(),
+            velocity,
+        });
+        self.backends[backend].start(id, degree, pitch, velocity);
+        for listener in &mut self.listeners {
+            listener.start(id, degree, pitch, velocity);
+        }
+        self.pressed_keys.insert(
+            id,
+            PressedKey {
+                backend,
+                degree,
+                pitch,
+            },
+        );
+    }
+
+    /// Re-roots the scale at the given degree of the current scale. The root is tuned to its pitch in the home tuning s.t. no commas accumulate when the root moves back and forth.
+    ///
+    /// The degrees of the held notes are shifted accordingly but their pitches are kept.
+    fn move_root_to(&mut self, degree: i32) {
+        let kbm_root = self.kbm.kbm_root();
+        let home_kbm_root = *self.home_kbm_root.get_or_insert(kbm_root);
+
+        let root_key = kbm_root.ref_key.plus_steps(kbm_root.root_offset + degree);
+        let home_degree = home_kbm_root
+            .ref_key
+            .plus_steps(home_kbm_root.root_offset)
+            .num_keys_before(root_key);
+
+        self.set_kbm_root(KbmRoot {
+            ref_key: root_key,
+            ref_pitch: (&self.scl, home_kbm_root).pitch_of(home_degree),
+            root_offset: 0,
+        });
+    }
+
+    /// Translates a degree relative to the home root into a degree relative to the current root.
+    fn degree_of_home_degree(&self, home_degree: i32) -> i32 {
+        match self.home_kbm_root {
+            Some(home_kbm_root) => {
+                let kbm_root = self.kbm.kbm_root();
+                kbm_root
+                    .ref_key
+                    .plus_steps(kbm_root.root_offset)
+                    .num_keys_before(
+                        home_kbm_root
+                            .ref_key
+                            .plus_steps(home_kbm_root.root_offset + home_degree),
+                    )
+            }
+            None => home_degree,
+        }
+    }
+
+    /// Switches back to the root from before the movable root took over.
+    fn restore_home_root(&mut self) {
+        if let Some(home_kbm_root) = self.home_kbm_root.take() {
+            self.set_kbm_root(home_kbm_root);
+        }
+    }
+
+    fn set_kbm_root(&mut self, new_kbm_root: KbmRoot) {
+        let kbm_root = self.kbm.kbm_root();
+        let shift = kbm_root
+            .ref_key
+            .plus_steps(kbm_root.root_offset)
+            .num_keys_before(new_kbm_root.ref_key.plus_steps(new_kbm_root.root_offset));
+        for pressed_key in self.snapshot.pressed_keys.values_mut() {
+            pressed_key.degree -= shift;
+        }
+        self.kbm.set_kbm_root(new_kbm_root);
+        self.retune();
+    }
+
+    /// Drone strings bypass [`Self::press`] s.t. they neither glide themselves nor make the played notes glide.
+    fn update_drone(&mut self) {
+        if !self.storage.is_active(LiveParameter::Drone) {
+            if self.drone.stop() {
+                let drone_ids: Vec<_> = self
+                    .pressed_keys
+                    .keys()
+                    .filter(|id| matches!(id, SourceId::Drone(_)))
+                    .copied()
+                    .collect();
+                for id in drone_ids {
+                    self.handle_event(Event::Released(id, 100));
+                }
+            }
+            return;
+        }
+
+        let (index, string) = match self.drone.pluck_due(Instant::now()) {
+            Some(pluck) => pluck,
+            None => return,
+        };
+        let id = SourceId::Drone(index);
+        if self.pressed_keys.contains_key(&id) {
+            self.handle_event(Event::Released(id, 100));
+        }
+        let tuning = crossfaded_tuning(&self.tuning_table, &self.crossfade);
+        let degree = match string {
+            DroneString::Degree(degree) => degree,
+            DroneString::Ratio(ratio) => {
+                tuning
+                    .find_by_pitch(tuning.pitch_of(0) * ratio)
+                    .approx_value
+            }
+        };
+        let pitch = tuning.pitch_of(degree);
+        let velocity = self.drone.velocity();
+        self.start(id, degree, pitch, velocity, self.curr_backend);
+    }
+
     fn degree_and_pitch(&self, location: Location) -> (i32, Pitch) {
-        let tuning = (&self.scl, self.kbm.kbm_root());
+        let tuning = crossfaded_tuning(&self.tuning_table, &self.crossfade);
         match location {
             Location::Pitch(pitch) => {
                 let degree = tuning.find_by_pitch(pitch).approx_value;
@@ -287,9 +791,29 @@ impl PianoEngineModel {
     }
 
     fn set_program(&mut self, program: u8) {
+        self.log(|| LoggedEvent::ProgramChange { program });
+        self.change_program(move |_| usize::from(program));
+    }
+
+    /// Changes the program of the selected backend. The listeners follow but are limited to the number of programs of the selected backend.
+    fn change_program(&mut self, update_fn: impl Fn(usize) -> usize + Copy + Send + 'static) {
         let backend = &mut self.backend_mut();
-        backend.program_change(Box::new(move |_| usize::from(program)));
+        backend.program_change(Box::new(update_fn));
         backend.send_status();
+        let max_program = backend.num_programs().saturating_sub(1);
+        for listener in &mut self.listeners {
+            listener.program_change(Box::new(move |p| update_fn(p).min(max_program)));
+        }
+    }
+
+    /// Sends the mapped parameter values to the listeners s.t., e.g., controllers with displays start in sync with the engine.
+    fn send_parameters_to_listeners(&mut self) {
+        for (parameter, ccn) in self.snapshot.mapper.mappings() {
+            let value = self.snapshot.storage.read_parameter(parameter).as_u8();
+            for listener in &mut self.listeners {
+                listener.control_change(ccn, value);
+            }
+        }
     }
 
     fn toggle_parameter(&mut self, parameter: LiveParameter) {
@@ -305,13 +829,13 @@ impl PianoEngineModel {
         let value = value.as_u8();
         match parameter {
             LiveParameter::ChannelPressure => {
-                for backend in &mut self.backends {
+                for backend in self.backends.iter_mut().chain(&mut self.listeners) {
                     backend.channel_pressure(value);
                 }
             }
             _ => {
                 if let Some(ccn) = self.mapper.get_ccn(parameter) {
-                    for backend in &mut self.backends {
+                    for backend in self.backends.iter_mut().chain(&mut self.listeners) {
                         backend.control_change(ccn, value);
                     }
                 }
@@ -320,44 +844,197 @@ impl PianoEngineModel {
     }
 
     fn set_parameter_without_backends_update(&mut self, parameter: LiveParameter, value: f64) {
+        self.log(|| LoggedEvent::Parameter { parameter, value });
+        let continuous_tuning_was_required = self.continuous_tuning_required();
         self.storage.set_parameter(parameter, value);
         self.storage_updates.send(self.storage).unwrap();
+        if !self.storage.is_active(LiveParameter::MovableRoot) {
+            self.restore_home_root();
+        }
+        if self.continuous_tuning_required() != continuous_tuning_was_required {
+            self.retune();
+        }
+    }
+
+    /// Gliding notes, moving roots and crossfades leave the baked tuning. Hence, the backends operate in continuous tuning mode while glide or the movable root is enabled or a crossfade is ongoing.
+    fn continuous_tuning_required(&self) -> bool {
+        !self.storage.glide_duration().is_zero()
+            || self.storage.is_active(LiveParameter::MovableRoot)
+            || self.crossfade.is_some()
+    }
+
+    fn update_glides(&mut self) {
+        let (pressed_keys, backends) = (&mut self.snapshot.pressed_keys, &mut self.backends);
+        self.glides.retain(|&id, glide| {
+            let pressed_key = match pressed_keys.get_mut(&id) {
+                Some(pressed_key) => pressed_key,
+                None => return false,
+            };
+            let degree = pressed_key.degree;
+            let pitch = glide.pitch();
+            if let Some(event_log) = &mut self.event_log {
+                event_log.write(LoggedEvent::PitchUpdate {
+                    source: id.into(),
+                    degree,
+                    pitch_hz: pitch.as_hz(),
+                });
+            }
+            backends[pressed_key.backend].update_pitch(id, degree, pitch, 100);
+            for listener in &mut self.listeners {
+                listener.update_pitch(id, degree, pitch, 100);
+            }
+            pressed_key.pitch = pitch;
+            glide.progress() < 1.0
+        });
+    }
+
+    /// Updates the [`LiveParameter`]s of all [`ModulationRoute`]s with the given `source`. The backends are not notified since they receive the original MIDI message.
+    fn route_modulation(&mut self, source: ModulationSource, normalized_value: f64) {
+        let updates: Vec<_> = self
+            .modulation_routes
+            .iter()
+            .filter(|route| route.source == source)
+            .map(|route| (route.target, route.map(normalized_value)))
+            .collect();
+        for (parameter, value) in updates {
+            self.set_parameter_without_backends_update(parameter, value);
+        }
     }
 
     fn set_key_pressure(&mut self, id: SourceId, pressure: u8) {
-        for backend in &mut self.backends {
+        self.log(|| LoggedEvent::KeyPressure {
+            source: id.into(),
+            pressure,
+        });
+        for backend in self.backends.iter_mut().chain(&mut self.listeners) {
             backend.update_pressure(id, pressure);
         }
     }
 
+    fn set_source_pressure(&mut self, id: SourceId, pressure: u8) {
+        let ids: Vec<_> = self
+            .pressed_keys
+            .keys()
+            .filter(|pressed_id| pressed_id.is_same_kind_as(&id))
+            .copied()
+            .collect();
+        for id in ids {
+            self.set_key_pressure(id, pressure);
+        }
+    }
+
     fn pitch_bend(&mut self, value: i16) {
+        self.log(|| LoggedEvent::PitchBend { value });
         self.storage
             .set_parameter(LiveParameter::PitchBend, f64::from(value) / 8192.0);
         self.storage_updates.send(self.storage).unwrap();
-        for backend in &mut self.backends {
+        for backend in self.backends.iter_mut().chain(&mut self.listeners) {
             backend.pitch_bend(value);
         }
     }
 
+    fn log(&mut self, event: impl FnOnce() -> LoggedEvent) {
+        if let Some(event_log) = &mut self.event_log {
+            event_log.write(event());
+        }
+    }
+
+    fn release_keys_without_legato(&mut self) {
+        if let TuningMode::Continuous = self.tuning_mode {
+            return;
+        }
+        let (pressed_keys, backends) = (&mut self.snapshot.pressed_keys, &mut self.backends);
+        pressed_keys.retain(|&id, pressed_key| {
+            let backend = &mut backends[pressed_key.backend];
+            if backend.has_legato() {
+                return true;
+            }
+            if let Some(event_log) = &mut self.event_log {
+                event_log.write(LoggedEvent::NoteOff {
+                    source: id.into(),
+                    velocity: 100,
+                });
+            }
+            backend.stop(id, 100);
+            for listener in &mut self.listeners {
+                listener.stop(id, 100);
+            }
+            false
+        });
+    }
+
+    fn retune_pressed_keys(&mut self) {
+        if let TuningMode::Continuous = self.tuning_mode {
+            return;
+        }
+        let tuning = crossfaded_tuning(&self.tuning_table, &self.crossfade);
+        let (pressed_keys, backends) = (&mut self.snapshot.pressed_keys, &mut self.backends);
+        for (&id, pressed_key) in pressed_keys {
+            let degree = pressed_key.degree;
+            let pitch = tuning.pitch_of(degree);
+            if let Some(glide) = self.glides.get_mut(&id) {
+                glide.to = pitch;
+                continue;
+            }
+            if let Some(event_log) = &mut self.event_log {
+                event_log.write(LoggedEvent::PitchUpdate {
+                    source: id.into(),
+                    degree,
+                    pitch_hz: pitch.as_hz(),
+                });
+            }
+            backends[pressed_key.backend].update_pitch(id, degree, pitch, 100);
+            for listener in &mut self.listeners {
+                listener.update_pitch(id, degree, pitch, 100);
+            }
+            pressed_key.pitch = pitch;
+        }
+    }
+
     fn retune(&mut self) {
         let kbm_root = self.kbm.kbm_root();
         let tuning_mode = self.tuning_mode;
 
-        for backend in &mut self.backends {
-            match tuning_mode {
-                TuningMode::Fixed => backend.set_tuning((&self.scl, kbm_root)),
-                TuningMode::Continuous => backend.set_no_tuning(),
+        if self.tuning_table.inner().1 != kbm_root {
+            self.tuning_table =
+                TuningTable::bake((self.scl.clone(), kbm_root), TUNING_TABLE_DEGREES);
+        }
+
+        self.log(|| LoggedEvent::Tuning {
+            mode: tuning_mode,
+            ref_key: kbm_root.ref_key.midi_number(),
+            ref_pitch_hz: kbm_root.ref_pitch.as_hz(),
+            root_offset: kbm_root.root_offset,
+        });
+
+        let continuous_tuning_required = self.continuous_tuning_required();
+        let kbm = &self.snapshot.kbm;
+        for backend in self.backends.iter_mut().chain(&mut self.listeners) {
+            backend.set_kbm(kbm);
+            match (tuning_mode, continuous_tuning_required) {
+                (TuningMode::Fixed, false) => backend.set_tuning((&self.scl, kbm_root)),
+                _ if backend.publishes_tuning() => backend.set_tuning((&self.scl, kbm_root)),
+                (TuningMode::Fixed, true) | (TuningMode::Continuous, _) => backend.set_no_tuning(),
             }
         }
         self.backend_mut().send_status();
     }
 }
 
+/// Extension point for sound generators and other consumers of the [`PianoEngine`]'s events.
+///
+/// Implementations are instantiated by the factories in [`crate::registry`]. `S` identifies the source of a note s.t. several notes can sound at the same time.
 pub trait Backend<S>: Send {
+    /// Called whenever the scale or the keyboard mapping changes.
     fn set_tuning(&mut self, tuning: (&Scl, KbmRoot));
 
+    /// Called with the full keyboard mapping before every call to [`Backend::set_tuning`] or [`Backend::set_no_tuning`]. Only backends that forward non-linear mappings to other applications need to implement this method.
+    fn set_kbm(&mut self, _kbm: &Kbm) {}
+
+    /// Called when notes should be played at their exact pitches instead of a fixed tuning, e.g. in continuous mode or during crossfades.
     fn set_no_tuning(&mut self);
 
+    /// Sends status information to the info channel, e.g. when the backend is selected or retuned.
     fn send_status(&mut self);
 
     fn start(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8);
@@ -368,8 +1045,16 @@ pub trait Backend<S>: Send {
 
     fn stop(&mut self, id: S, velocity: u8);
 
+    /// Stops all notes immediately, including notes that are still in their release phase.
+    fn all_sound_off(&mut self);
+
     fn program_change(&mut self, update_fn: Box<dyn FnMut(usize) -> usize + Send>);
 
+    /// Number of programs the backend can switch between. When the backend is selected, the listeners cannot exceed this number.
+    fn num_programs(&self) -> usize {
+        128
+    }
+
     fn control_change(&mut self, controller: u8, value: u8);
 
     fn channel_pressure(&mut self, pressure: u8);
@@ -378,7 +1063,18 @@ pub trait Backend<S>: Send {
 
     fn toggle_envelope_type(&mut self);
 
+    /// Returns `true` if a sounding note can be moved to a new pitch without being restarted.
     fn has_legato(&self) -> bool;
+
+    /// Returns `true` if the pitch of a sounding note can be changed gradually, e.g. to glide from one pitch to another. Backends that are tuned ahead of time can only jump between the keys of their tuning.
+    fn has_continuous_pitch(&self) -> bool {
+        true
+    }
+
+    /// Returns `true` if the backend publishes the scale to other applications. Such backends receive the full scale even while the notes are played at their exact pitches.
+    fn publishes_tuning(&self) -> bool {
+        false
+    }
 }
 
 impl PianoEngineModel {
@@ -415,6 +1111,8 @@ impl<E, I: From<()> + Send> Backend<E> for NoAudio<I> {
 
     fn stop(&mut self, _id: E, _velocity: u8) {}
 
+    fn all_sound_off(&mut self) {}
+
     fn program_change(&mut self, _update_fn: Box<dyn FnMut(usize) -> usize + Send>) {}
 
     fn control_change(&mut self, _controller: u8, _value: u8) {}
@@ -429,3 +1127,58 @@ impl<E, I: From<()> + Send> Backend<E> for NoAudio<I> {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use assert_approx_eq::assert_approx_eq;
+    use tune::{note::NoteLetter, pitch::Pitched};
+
+    use super::*;
+
+    #[test]
+    fn keyboard_keys_keep_their_pitch_when_the_root_moves() {
+        let (info_send, _info_recv) = mpsc::channel::<()>();
+        let (storage_send, _storage_recv) = mpsc::channel();
+
+        let (engine, mut snapshot) = PianoEngine::new(
+            Scl::builder()
+                .push_ratio(Ratio::from_semitones(1))
+                .build()
+                .unwrap(),
+            Kbm::builder(NoteLetter::D.in_octave(4)).build().unwrap(),
+            vec![Box::new(NoAudio::new(info_send))],
+            0,
+            LiveParameterMapper::new(),
+            4,
+            LiveParameterStorage::default(),
+            storage_send,
+            None,
+            Vec::new(),
+            Vec::new(),
+            None,
+            Vec::new(),
+            Drone::new(Vec::new(), Duration::from_secs(1), 100),
+        );
+        engine.set_parameter(LiveParameter::MovableRoot, 1.0);
+
+        let mut press_and_release = || {
+            let id = SourceId::Keyboard(0, 0);
+            engine.handle_event(Event::Pressed(id, Location::Degree(5), 100));
+            engine.take_snapshot(&mut snapshot);
+            let pitch = snapshot.pressed_keys[&id].pitch;
+            engine.handle_event(Event::Released(id, 100));
+            pitch
+        };
+
+        let first_pitch = press_and_release();
+        let second_pitch = press_and_release();
+
+        assert_approx_eq!(first_pitch.as_hz(), second_pitch.as_hz());
+        assert_approx_eq!(
+            first_pitch.as_hz(),
+            NoteLetter::G.in_octave(4).pitch().as_hz()
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    fs,
+    hash::Hash,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Instant,
+};
+
+use chrono::Local;
+use tune::{
+    midi::ChannelMessageType,
+    pitch::Pitch,
+    scala::{KbmRoot, Scl},
+    tuner::{MidiTunerMessage, MidiTunerMessageHandler, TunableMidi},
+};
+use tune_cli::{
+    shared::midi::{MidiOutArgs, TuningMethod},
+    CliResult,
+};
+
+use crate::{
+    control::{LiveParameter, LiveParameterMapper},
+    piano::Backend,
+    tunable::TunableBackend,
+};
+
+/// Microseconds per quarter note (120 BPM).
+const TEMPO: u32 = 500_000;
+
+/// Together with [`TEMPO`], one tick corresponds to one millisecond.
+const TICKS_PER_QUARTER_NOTE: u16 = 500;
+
+/// Records all engine events to a Standard MIDI File while the foot switch is active.
+///
+/// The notes are retuned using the given tuning method, i.e. the file contains the same tuning messages a MIDI-out device would receive.
+pub struct MidiRecorder<S> {
+    backend: TunableBackend<S, TunableMidi<SmfHandler>>,
+    midi_recv: Receiver<Vec<u8>>,
+    foot_ccn: Option<u8>,
+    pedal_ccns: Vec<u8>,
+    recording_send: Sender<Vec<(u64, Vec<u8>)>>,
+    curr_tuning: Option<(Scl, KbmRoot)>,
+    curr_program: usize,
+    curr_controllers: BTreeMap<u8, u8>,
+    curr_pitch_bend: i16,
+    sounding_notes: HashMap<S, (i32, Pitch, u8)>,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    start: Instant,
+    events: Vec<(u64, Vec<u8>)>,
+}
+
+pub fn create<S: Copy + Eq + Hash>(
+    midi_out_args: &MidiOutArgs,
+    tuning_method: TuningMethod,
+    mapper: &LiveParameterMapper,
+    file_prefix: String,
+) -> CliResult<MidiRecorder<S>> {
+    let (midi_send, midi_recv) = mpsc::channel();
+
+    let target = midi_out_args.get_midi_target(SmfHandler { midi_send }, tuning_method)?;
+    let synth = midi_out_args.create_synth(target, tuning_method);
+
+    // Encoding and writing the file happens on a separate thread s.t. the engine is not blocked
+    let (recording_send, recording_recv) = mpsc::channel::<Vec<(u64, Vec<u8>)>>();
+    thread::spawn(move || {
+        for events in recording_recv {
+            write_smf(&file_prefix, &events);
+        }
+    });
+
+    Ok(MidiRecorder {
+        backend: TunableBackend::new(synth),
+        midi_recv,
+        foot_ccn: mapper.get_ccn(LiveParameter::Foot),
+        pedal_ccns: [
+            LiveParameter::Damper,
+            LiveParameter::Sostenuto,
+            LiveParameter::Soft,
+        ]
+        .into_iter()
+        .filter_map(|parameter| mapper.get_ccn(parameter))
+        .collect(),
+        recording_send,
+        curr_tuning: None,
+        curr_program: 0,
+        curr_controllers: BTreeMap::new(),
+        curr_pitch_bend: 0,
+        sounding_notes: HashMap::new(),
+        recording: None,
+    })
+}
+
+impl<S: Copy + Eq + Hash + Debug + Send> MidiRecorder<S> {
+    fn set_recording_active(&mut self, recording_active: bool) {
+        match (recording_active, self.recording.is_some()) {
+            (true, false) => self.start_recording(),
+            (false, true) => self.stop_recording(),
+            _ => {}
+        }
+    }
+
+    fn start_recording(&mut self) {
+        // Release all notes of the previous tuner before the recording starts
+        self.backend.set_no_tuning();
+        self.flush();
+
+        self.recording = Some(Recording {
+            start: Instant::now(),
+            events: Vec::new(),
+        });
+
+        // Restore the current state s.t. the recording starts with the correct tuning, pedal positions and held notes
+        if let Some((scl, kbm_root)) = &self.curr_tuning {
+            self.backend.set_tuning((scl, *kbm_root));
+        }
+        self.backend
+            .send_monophonic_message(ChannelMessageType::ProgramChange {
+                program: u8::try_from(self.curr_program).unwrap(),
+            });
+        for (&controller, &value) in &self.curr_controllers {
+            self.backend
+                .send_monophonic_message(ChannelMessageType::ControlChange { controller, value });
+        }
+        self.backend
+            .send_monophonic_message(ChannelMessageType::PitchBendChange {
+                value: self.curr_pitch_bend,
+            });
+        for (&id, &(degree, pitch, velocity)) in &self.sounding_notes {
+            self.backend.start(id, degree, pitch, velocity);
+        }
+        self.flush();
+
+        println!("[INFO] MIDI recording started");
+    }
+
+    fn stop_recording(&mut self) {
+        // Release all held notes and pedals s.t. no notes are left hanging at the end of the file
+        for &id in self.sounding_notes.keys() {
+            self.backend.stop(id, 0);
+        }
+        for &controller in &self.pedal_ccns {
+            self.backend
+                .send_monophonic_message(ChannelMessageType::ControlChange {
+                    controller,
+                    value: 0,
+                });
+        }
+        self.flush();
+
+        if let Some(recording) = self.recording.take() {
+            self.recording_send.send(recording.events).unwrap();
+        }
+
+        // Resume tracking the held notes and pedals without recording them
+        for (&id, &(degree, pitch, velocity)) in &self.sounding_notes {
+            self.backend.start(id, degree, pitch, velocity);
+        }
+        for &controller in &self.pedal_ccns {
+            if let Some(&value) = self.curr_controllers.get(&controller) {
+                self.backend
+                    .send_monophonic_message(ChannelMessageType::ControlChange {
+                        controller,
+                        value,
+                    });
+            }
+        }
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        for message in self.midi_recv.try_iter() {
+            if let Some(recording) = &mut self.recording {
+                let time_ms = recording.start.elapsed().as_millis() as u64;
+                recording.events.push((time_ms, message));
+            }
+        }
+    }
+}
+
+impl<S: Copy + Eq + Hash + Debug + Send> Backend<S> for MidiRecorder<S> {
+    fn set_tuning(&mut self, tuning: (&Scl, KbmRoot)) {
+        self.curr_tuning = Some((tuning.0.clone(), tuning.1));
+        self.backend.set_tuning(tuning);
+        self.flush();
+    }
+
+    fn set_no_tuning(&mut self) {
+        self.curr_tuning = None;
+        self.backend.set_no_tuning();
+        self.flush();
+    }
+
+    fn send_status(&mut self) {}
+
+    fn start(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
+        self.sounding_notes.insert(id, (degree, pitch, velocity));
+        self.backend.start(id, degree, pitch, velocity);
+        self.flush();
+    }
+
+    fn update_pitch(&mut self, id: S, degree: i32, pitch: Pitch, velocity: u8) {
+        if let Some(sounding_note) = self.sounding_notes.get_mut(&id) {
+            sounding_note.0 = degree;
+            sounding_note.1 = pitch;
+        }
+        self.backend.update_pitch(id, degree, pitch, velocity);
+        self.flush();
+    }
+
+    fn update_pressure(&mut self, id: S, pressure: u8) {
+        self.backend.update_pressure(id, pressure);
+        self.flush();
+    }
+
+    fn stop(&mut self, id: S, velocity: u8) {
+        if self.sounding_notes.remove(&id).is_some() {
+            self.backend.stop(id, velocity);
+            self.flush();
+        }
+    }
+
+    fn all_sound_off(&mut self) {
+        for (id, _) in self.sounding_notes.drain() {
+            self.backend.stop(id, 0);
+        }
+        self.flush();
+    }
+
+    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        self.curr_program = update_fn(self.curr_program).min(127);
+
+        self.backend
+            .send_monophonic_message(ChannelMessageType::ProgramChange {
+                program: u8::try_from(self.curr_program).unwrap(),
+            });
+        self.flush();
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        if Some(controller) == self.foot_ccn {
+            self.set_recording_active(value >= 64);
+            return;
+        }
+
+        self.curr_controllers.insert(controller, value);
+        self.backend
+            .send_monophonic_message(ChannelMessageType::ControlChange { controller, value });
+        self.flush();
+    }
+
+    fn channel_pressure(&mut self, pressure: u8) {
+        self.backend
+            .send_monophonic_message(ChannelMessageType::ChannelPressure { pressure });
+        self.flush();
+    }
+
+    fn pitch_bend(&mut self, value: i16) {
+        self.curr_pitch_bend = value;
+        self.backend
+            .send_monophonic_message(ChannelMessageType::PitchBendChange { value });
+        self.flush();
+    }
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+
+    fn has_continuous_pitch(&self) -> bool {
+        !self.backend.is_aot()
+    }
+}
+
+struct SmfHandler {
+    midi_send: Sender<Vec<u8>>,
+}
+
+impl MidiTunerMessageHandler for SmfHandler {
+    fn handle(&mut self, message: MidiTunerMessage) {
+        message.send_to(|bytes| self.midi_send.send(bytes.to_vec()).unwrap());
+    }
+}
+
+fn write_smf(file_prefix: &str, events: &[(u64, Vec<u8>)]) {
+    let output_file_name = format!(
+        "{}_{}.mid",
+        file_prefix,
+        Local::now().format("%Y%m%d_%H%M%S")
+    );
+
+    match fs::write(&output_file_name, encode_smf(events)) {
+        Ok(()) => println!("[INFO] Created `{output_file_name}`"),
+        Err(err) => eprintln!("[WARNING] Cannot write `{output_file_name}`: {err}"),
+    }
+}
+
+/// Encodes the given millisecond-timestamped messages as a single-track (format 0) Standard MIDI File.
+///
+/// Universal MIDI Packets cannot be represented in a Standard MIDI File and are skipped.
+fn encode_smf(events: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let mut track = Vec::new();
+
+    write_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xff, 0x51, 0x03]);
+    track.extend_from_slice(&TEMPO.to_be_bytes()[1..]);
+
+    let mut last_tick = 0;
+    for (tick, message) in events {
+        let delta = u32::try_from(tick.saturating_sub(last_tick)).unwrap_or(u32::MAX);
+        match message.split_first() {
+            Some((0xf0, sysex_data)) => {
+                write_vlq(&mut track, delta);
+                track.push(0xf0);
+                write_vlq(&mut track, sysex_data.len() as u32);
+                track.extend_from_slice(sysex_data);
+            }
+            Some((0x80..=0xef, _)) => {
+                write_vlq(&mut track, delta);
+                track.extend_from_slice(message);
+            }
+            _ => continue,
+        }
+        last_tick = *tick;
+    }
+
+    write_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xff, 0x2f, 0x00]);
+
+    let mut smf = Vec::new();
+    smf.extend_from_slice(b"MThd");
+    smf.extend_from_slice(&6u32.to_be_bytes());
+    smf.extend_from_slice(&0u16.to_be_bytes());
+    smf.extend_from_slice(&1u16.to_be_bytes());
+    smf.extend_from_slice(&TICKS_PER_QUARTER_NOTE.to_be_bytes());
+    smf.extend_from_slice(b"MTrk");
+    smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    smf.extend_from_slice(&track);
+    smf
+}
+
+fn write_vlq(target: &mut Vec<u8>, value: u32) {
+    let mut shift = 28;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        target.push((value >> shift) as u8 & 0x7f | 0x80);
+        shift -= 7;
+    }
+    target.push(value as u8 & 0x7f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_smf_with_sysex_and_skipped_ump_messages() {
+        let events = [
+            (0, vec![0x90, 60, 100]),
+            (192, vec![0x40, 0x90, 0x3c, 0x00]), // UMP message, skipped
+            (200, vec![0xf0, 0x7f, 0x7f, 0x08, 0xf7]),
+            (200, vec![0x80, 60, 0]),
+        ];
+
+        assert_eq!(
+            encode_smf(&events),
+            [
+                b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0x01, 0xf4, // Header
+                b'M', b'T', b'r', b'k', 0, 0, 0, 27, // Track header
+                0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // Tempo
+                0x00, 0x90, 60, 100, // Note on
+                0x81, 0x48, 0xf0, 0x04, 0x7f, 0x7f, 0x08, 0xf7, // Sysex at tick 200
+                0x00, 0x80, 60, 0, // Note off
+                0x00, 0xff, 0x2f, 0x00, // End of track
+            ]
+        );
+    }
+
+    #[test]
+    fn encode_variable_length_quantities() {
+        for (value, expected) in [
+            (0x00, vec![0x00]),
+            (0x7f, vec![0x7f]),
+            (0x80, vec![0x81, 0x00]),
+            (0x3fff, vec![0xff, 0x7f]),
+            (0x4000, vec![0x81, 0x80, 0x00]),
+            (0x0fff_ffff, vec![0xff, 0xff, 0xff, 0x7f]),
+        ] {
+            let mut target = Vec::new();
+            write_vlq(&mut target, value);
+            assert_eq!(target, expected);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Central place to instantiate all optional [`Backend`]s.
+//!
+//! To add a new backend, implement [`Backend`] and append a [`BackendFactory`] to [`FACTORIES`].
+//! Backends that depend on optional crates should be put behind a feature flag s.t. their factory is only compiled if the feature is enabled.
+
+use std::{str::FromStr, sync::mpsc::Sender};
+
+use tune::scala::{Kbm, Scl};
+use tune_cli::{CliError, CliResult};
+
+#[cfg(feature = "mts-esp")]
+use crate::mts_esp;
+use crate::{
+    assets::MicrowaveConfig,
+    audio::AudioStage,
+    control::LiveParameterStorage,
+    csound, feedback,
+    fluid::{self, SoundFontUpdates},
+    midi,
+    model::SourceId,
+    piano::{Backend, KeyZone},
+    recorder, sfz, supercollider, tuning_sync,
+    view::DynViewModel,
+    RunOptions, TUN_METHOD_ARG,
+};
+
+/// Resources that can be used by a [`BackendFactory`].
+pub struct BackendContext<'a> {
+    pub options: &'a RunOptions,
+    pub config: &'a MicrowaveConfig,
+    pub info_send: Sender<DynViewModel>,
+    pub tuning_send: Sender<(Scl, Kbm)>,
+    pub sample_rate_hz: f64,
+}
+
+/// Collects the backends, listeners and audio stages created by the registered [`BackendFactory`]s.
+///
+/// Backends can be selected by the user while listeners receive all events regardless of the selected backend.
+/// Every backend has a name s.t. it can be referred to on the command line, e.g. by a [`ZoneSpec`].
+#[derive(Default)]
+pub struct BackendRegistry {
+    pub backends: Vec<Box<dyn Backend<SourceId>>>,
+    pub backend_names: Vec<&'static str>,
+    pub listeners: Vec<Box<dyn Backend<SourceId>>>,
+    pub audio_stages: Vec<Box<dyn AudioStage<((), LiveParameterStorage)>>>,
+    /// Receives the non-channel messages that are forwarded from the MIDI input to the MIDI output.
+    pub midi_out_forward: Option<Sender<Vec<u8>>>,
+    /// Receives reloaded soundfonts that replace the soundfont of the fluid backend.
+    pub soundfont_updates: Option<SoundFontUpdates>,
+}
+
+/// Registers the backends enabled by the command line options. Factories of disabled backends should return without registering anything.
+pub type BackendFactory = fn(&BackendContext, &mut BackendRegistry) -> CliResult<()>;
+
+/// The order of the entries determines the order in which the user can cycle through the backends.
+const FACTORIES: &[BackendFactory] = &[
+    create_midi_recorder,
+    create_tuning_sync,
+    create_midi_feedback,
+    create_midi_out,
+    #[cfg(feature = "mts-esp")]
+    create_mts_esp_master,
+    create_supercollider,
+    create_csound,
+    create_fluid,
+    create_sfz,
+];
+
+impl BackendRegistry {
+    pub fn create(context: &BackendContext) -> CliResult<Self> {
+        let mut registry = Self::default();
+        for factory in FACTORIES {
+            factory(context, &mut registry)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn push_backend(&mut self, name: &'static str, backend: impl Backend<SourceId> + 'static) {
+        self.backends.push(Box::new(backend));
+        self.backend_names.push(name);
+    }
+
+    pub fn push_listener(&mut self, listener: impl Backend<SourceId> + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Resolves the backend names of the given zones to backend indices.
+    pub fn resolve_zones(&self, zone_specs: &[ZoneSpec]) -> CliResult<Vec<(usize, KeyZone)>> {
+        zone_specs
+            .iter()
+            .map(|spec| {
+                self.backend_names
+                    .iter()
+                    .position(|&name| name == spec.backend)
+                    .map(|backend| (backend, spec.zone.clone()))
+                    .ok_or_else(|| {
+                        CliError::CommandError(format!(
+                            "Backend {} is not enabled. Enabled backends: {}",
+                            spec.backend,
+                            self.backend_names.join(", ")
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    pub fn push_audio_stage(
+        &mut self,
+        audio_stage: impl AudioStage<((), LiveParameterStorage)> + 'static,
+    ) {
+        self.audio_stages.push(Box::new(audio_stage));
+    }
+}
+
+fn create_midi_recorder(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let options = context.options;
+    if let Some(tuning_method) = options.midi_recording_method {
+        registry.push_listener(recorder::create(
+            &options.midi_out_args,
+            tuning_method,
+            &options.control_change.to_parameter_mapper(),
+            options.audio.wav_file_prefix.clone(),
+        )?);
+    }
+    Ok(())
+}
+
+fn create_tuning_sync(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    if let Some(tuning_sync) = context.options.sync.connect()? {
+        registry.push_listener(tuning_sync::create(
+            tuning_sync,
+            context.tuning_send.clone(),
+        )?);
+    }
+    Ok(())
+}
+
+fn create_midi_feedback(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let options = context.options;
+    if let Some(target_port) = &options.midi_feedback_device {
+        registry.push_listener(feedback::create(
+            target_port,
+            options.midi_feedback_channel,
+            context
+                .config
+                .waveforms
+                .iter()
+                .map(|spec| spec.name.clone())
+                .collect(),
+        )?);
+    }
+    Ok(())
+}
+
+fn create_midi_out(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let options = context.options;
+    if let Some(target_port) = &options.midi_out_device {
+        let midi_out = midi::create(
+            context.info_send.clone(),
+            target_port,
+            &options.midi_out_args,
+            options
+                .midi_tuning_method
+                .ok_or_else(|| format!("MIDI out requires --{TUN_METHOD_ARG} argument"))?,
+        )?;
+        registry.midi_out_forward = Some(midi_out.forward_sender());
+        registry.push_backend("midi-out", midi_out);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "mts-esp")]
+fn create_mts_esp_master(
+    context: &BackendContext,
+    registry: &mut BackendRegistry,
+) -> CliResult<()> {
+    if let Some(mts_esp::MtsEspMode::Master) = context.options.mts_esp_mode {
+        registry.push_backend(
+            "mts-esp",
+            mts_esp::create_master(context.info_send.clone())?,
+        );
+    }
+    Ok(())
+}
+
+fn create_supercollider(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let options = context.options;
+    if let Some(server_address) = options.sc_server_address {
+        registry.push_backend(
+            "sc",
+            supercollider::create(
+                context.info_send.clone(),
+                server_address,
+                options.sc_synth_defs.clone(),
+            )?,
+        );
+    }
+    Ok(())
+}
+
+fn create_csound(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let options = context.options;
+    if let Some(server_address) = options.csound_server_address {
+        registry.push_backend(
+            "csound",
+            csound::create(
+                context.info_send.clone(),
+                server_address,
+                options.csound_instruments.clone(),
+            )?,
+        );
+    }
+    Ok(())
+}
+
+fn create_fluid(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    if let Some(soundfont_file_location) = context.options.soundfont_file_location.as_deref() {
+        let (fluid_backend, fluid_synth, soundfont_updates) = fluid::create(
+            context.info_send.clone(),
+            Some(soundfont_file_location),
+            context.sample_rate_hz,
+        )?;
+        registry.soundfont_updates = Some(soundfont_updates);
+        registry.push_backend("fluid", fluid_backend);
+        registry.push_audio_stage(fluid_synth);
+    }
+    Ok(())
+}
+
+fn create_sfz(context: &BackendContext, registry: &mut BackendRegistry) -> CliResult<()> {
+    let sfz_file_locations = &context.options.sfz_file_locations;
+    if !sfz_file_locations.is_empty() {
+        let (sfz_backend, sfz_synth) = sfz::create(
+            context.info_send.clone(),
+            sfz_file_locations,
+            context.sample_rate_hz,
+        )?;
+        registry.push_backend("sfz", sfz_backend);
+        registry.push_audio_stage(sfz_synth);
+    }
+    Ok(())
+}
+
+/// Restricts the backend with the given name to a [`KeyZone`].
+///
+/// Syntax: `backend[:lo-hi][@channel,channel,...]`, e.g. `fluid:0-59` or `waveform@1,2`. Keys are MIDI numbers and channels are zero-based.
+#[derive(Clone, Debug)]
+pub struct ZoneSpec {
+    pub backend: String,
+    pub zone: KeyZone,
+}
+
+impl FromStr for ZoneSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (backend_and_keys, channels) = match s.split_once('@') {
+            Some((backend_and_keys, channels)) => (backend_and_keys, Some(channels)),
+            None => (s, None),
+        };
+        let (backend, keys) = match backend_and_keys.split_once(':') {
+            Some((backend, keys)) => (backend, Some(keys)),
+            None => (backend_and_keys, None),
+        };
+
+        let keys = keys
+            .map(|keys| {
+                let (lo, hi) = keys
+                    .split_once('-')
+                    .ok_or_else(|| format!("Invalid key range '{keys}', expected lo-hi"))?;
+                let parse_key = |key: &str| {
+                    key.trim()
+                        .parse::<i32>()
+                        .map_err(|err| format!("Invalid key '{key}': {err}"))
+                };
+                Ok::<_, String>(parse_key(lo)?..=parse_key(hi)?)
+            })
+            .transpose()?;
+
+        let channels = channels
+            .map(|channels| {
+                channels
+                    .split(',')
+                    .map(|channel| match channel.trim().parse::<u8>() {
+                        Ok(channel) if channel < 16 => Ok(channel),
+                        _ => Err(format!("Invalid MIDI channel '{channel}'")),
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            backend: backend.trim().to_owned(),
+            zone: KeyZone { keys, channels },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::key::PianoKey;
+
+    use super::*;
+
+    #[test]
+    fn parse_zone_specs() {
+        let spec: ZoneSpec = "fluid:0-59".parse().unwrap();
+        assert_eq!(spec.backend, "fluid");
+        assert!(spec.zone.contains(PianoKey::from_midi_number(59), 15));
+        assert!(!spec.zone.contains(PianoKey::from_midi_number(60), 0));
+
+        let spec: ZoneSpec = "waveform@1,2".parse().unwrap();
+        assert_eq!(spec.backend, "waveform");
+        assert!(spec.zone.contains(PianoKey::from_midi_number(127), 2));
+        assert!(!spec.zone.contains(PianoKey::from_midi_number(60), 0));
+
+        let spec: ZoneSpec = "sc:48-72@9".parse().unwrap();
+        assert!(spec.zone.contains(PianoKey::from_midi_number(48), 9));
+        assert!(!spec.zone.contains(PianoKey::from_midi_number(48), 8));
+        assert!(!spec.zone.contains(PianoKey::from_midi_number(73), 9));
+
+        assert!("fluid:60".parse::<ZoneSpec>().is_err());
+        assert!("fluid@16".parse::<ZoneSpec>().is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use tune_cli::{shared::watch::WatchedFile, CliResult};
+
+use crate::{
+    assets::MicrowaveConfig,
+    audio::AudioModel,
+    control::ModulationRoute,
+    fluid::SoundFontUpdates,
+    model::{KeyboardStrip, PointerMapping, ScalePreset},
+    piano::PianoEngine,
+};
+
+/// Minimum time between two checks of the file system.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The parts of the config file that are owned by the UI and the [`PianoEngine`] rather than by the audio backends.
+pub struct ConfigAssets {
+    pub scale_presets: Vec<ScalePreset>,
+    pub keyboard_strips: Vec<KeyboardStrip>,
+    pub pointer_mapping: PointerMapping,
+    pub modulation_routes: Vec<ModulationRoute>,
+}
+
+type CreateConfigAssets = Box<dyn Fn(&mut MicrowaveConfig) -> CliResult<ConfigAssets>>;
+
+/// Watches the config file and the soundfont for changes and hands over the reloaded content to the running backends.
+///
+/// If a file cannot be loaded the previous content stays active s.t. a typo in the config file does not interrupt a session.
+pub struct ConfigReloader {
+    config_file: WatchedFile,
+    soundfont_file: Option<WatchedFile>,
+    last_poll: Instant,
+    config_updates: Sender<MicrowaveConfig>,
+    soundfont_updates: Option<SoundFontUpdates>,
+    create_assets: CreateConfigAssets,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        config_file_location: PathBuf,
+        soundfont_file_location: Option<PathBuf>,
+        config_updates: Sender<MicrowaveConfig>,
+        soundfont_updates: Option<SoundFontUpdates>,
+        create_assets: impl Fn(&mut MicrowaveConfig) -> CliResult<ConfigAssets> + 'static,
+    ) -> Self {
+        Self {
+            config_file: WatchedFile::new(config_file_location),
+            soundfont_file: soundfont_file_location.map(WatchedFile::new),
+            last_poll: Instant::now(),
+            config_updates,
+            soundfont_updates,
+            create_assets: Box::new(create_assets),
+        }
+    }
+
+    /// Returns the [`ConfigAssets`] of the reloaded config file s.t. the caller can replace its own.
+    pub fn update(&mut self, engine: &PianoEngine, audio: &AudioModel) -> Option<ConfigAssets> {
+        if let Some(soundfont_updates) = &mut self.soundfont_updates {
+            soundfont_updates.drop_replaced_soundfonts();
+        }
+
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        if let (Some(soundfont_file), Some(soundfont_updates)) =
+            (&mut self.soundfont_file, &self.soundfont_updates)
+        {
+            if soundfont_file.has_changed() {
+                soundfont_updates.reload(soundfont_file.location().to_owned());
+            }
+        }
+
+        if self.config_file.has_changed() {
+            return self.reload_config(engine, audio);
+        }
+
+        None
+    }
+
+    fn reload_config(&self, engine: &PianoEngine, audio: &AudioModel) -> Option<ConfigAssets> {
+        let mut config = match MicrowaveConfig::load(self.config_file.location()) {
+            Ok(config) => config,
+            Err(err) => {
+                println!("[ERROR] Could not reload config file: {err:?}");
+                return None;
+            }
+        };
+
+        if config.waveforms.is_empty() {
+            println!("[ERROR] Could not reload config file: No waveforms defined");
+            return None;
+        }
+
+        let assets = match (self.create_assets)(&mut config) {
+            Ok(assets) => assets,
+            Err(err) => {
+                println!("[ERROR] Could not reload config file: {err:?}");
+                return None;
+            }
+        };
+
+        audio.replace_effects(config.take_effects());
+        self.config_updates.send(config).unwrap();
+        engine.send_status();
+
+        Some(assets)
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tune::{key::PianoKey, midi::ChannelMessageType, pitch::Pitch, scala::KbmRoot};
+use tune_cli::{shared::midi::MidiSource, CliResult};
+
+use crate::{
+    event_log::{LogEntry, LoggedEvent},
+    midi,
+    model::{Event, Location},
+    piano::PianoEngine,
+};
+
+/// Microseconds per quarter note when no tempo is specified (120 BPM).
+const DEFAULT_TEMPO: u32 = 500_000;
+
+pub enum ReplayEvent {
+    Logged(LoggedEvent),
+    Midi(Vec<u8>),
+}
+
+/// Loads a JSONL event log (as written by `--event-log`) or, if the file extension is `mid` or `midi`, a Standard MIDI File.
+pub fn load(location: &Path) -> CliResult<Vec<(u64, ReplayEvent)>> {
+    let is_midi_file = location
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| matches!(extension.to_lowercase().as_str(), "mid" | "midi"))
+        .unwrap_or(false);
+
+    if is_midi_file {
+        Ok(parse_midi_file(&fs::read(location)?)?)
+    } else {
+        Ok(parse_event_log(&fs::read_to_string(location)?)?)
+    }
+}
+
+/// Feeds the events into the engine in real time, scaled by the given `speed` factor.
+pub fn start(
+    mut engine: Arc<PianoEngine>,
+    events: Vec<(u64, ReplayEvent)>,
+    midi_source: MidiSource,
+    speed: f64,
+) {
+    thread::spawn(move || {
+        let start = Instant::now();
+        for (time_us, event) in events {
+            let target = Duration::from_secs_f64(time_us as f64 / 1_000_000.0 / speed);
+            if let Some(remaining) = target.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+            match event {
+                ReplayEvent::Logged(event) => apply_logged_event(&engine, &midi_source, event),
+                ReplayEvent::Midi(message) => {
+                    midi::process_midi_event(&message, &mut engine, &midi_source, false)
+                }
+            }
+        }
+        println!("[INFO] Replay finished");
+    });
+}
+
+fn apply_logged_event(engine: &PianoEngine, midi_source: &MidiSource, event: LoggedEvent) {
+    let offset = || midi_source.get_offset(0);
+    match event {
+        LoggedEvent::NoteOn {
+            source,
+            pitch_hz,
+            velocity,
+            ..
+        } => engine.handle_event(Event::Pressed(
+            source.into(),
+            Location::Pitch(Pitch::from_hz(pitch_hz)),
+            velocity,
+        )),
+        LoggedEvent::PitchUpdate {
+            source, pitch_hz, ..
+        } => engine.handle_event(Event::Moved(
+            source.into(),
+            Location::Pitch(Pitch::from_hz(pitch_hz)),
+        )),
+        LoggedEvent::NoteOff { source, velocity } => {
+            engine.handle_event(Event::Released(source.into(), velocity))
+        }
+        LoggedEvent::KeyPressure { source, pressure } => {
+            engine.set_key_pressure(source.into(), f64::from(pressure) / 127.0)
+        }
+        LoggedEvent::Parameter { parameter, value } => engine.set_parameter(parameter, value),
+        LoggedEvent::ControlChange { controller, value } => engine.handle_midi_event(
+            ChannelMessageType::ControlChange { controller, value },
+            0,
+            offset(),
+        ),
+        LoggedEvent::PitchBend { value } => {
+            engine.handle_midi_event(ChannelMessageType::PitchBendChange { value }, 0, offset())
+        }
+        LoggedEvent::ProgramChange { program } => {
+            engine.handle_midi_event(ChannelMessageType::ProgramChange { program }, 0, offset())
+        }
+        LoggedEvent::ProgramStep { delta } => {
+            if delta > 0 {
+                engine.inc_program()
+            } else {
+                engine.dec_program()
+            }
+        }
+        LoggedEvent::AllSoundOff => engine.panic(),
+        LoggedEvent::Tuning {
+            mode,
+            ref_key,
+            ref_pitch_hz,
+            root_offset,
+        } => engine.set_tuning(
+            mode,
+            KbmRoot {
+                ref_key: PianoKey::from_midi_number(ref_key),
+                ref_pitch: Pitch::from_hz(ref_pitch_hz),
+                root_offset,
+            },
+        ),
+    }
+}
+
+fn parse_event_log(content: &str) -> Result<Vec<(u64, ReplayEvent)>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            serde_json::from_str::<LogEntry>(line)
+                .map(|entry| (entry.time_us, ReplayEvent::Logged(entry.event)))
+                .map_err(|err| format!("Invalid event in line {} ({err})", line_number + 1))
+        })
+        .collect()
+}
+
+fn parse_midi_file(data: &[u8]) -> Result<Vec<(u64, ReplayEvent)>, String> {
+    let mut reader = Reader { data, pos: 0 };
+
+    if reader.bytes(4)? != b"MThd" {
+        return Err("Not a Standard MIDI File".to_owned());
+    }
+    let header_len = reader.u32()? as usize;
+    let _format = reader.u16()?;
+    let _num_tracks = reader.u16()?;
+    let division = reader.u16()?;
+    reader.bytes(header_len.saturating_sub(6))?;
+
+    let mut timed_events = Vec::new();
+    while reader.pos < data.len() {
+        let chunk_type = reader.bytes(4)?;
+        let chunk_len = reader.u32()? as usize;
+        let chunk = reader.bytes(chunk_len)?;
+        if chunk_type == b"MTrk" {
+            parse_track(chunk, &mut timed_events)?;
+        }
+    }
+    timed_events.sort_by_key(|&(tick, _)| tick);
+
+    let mut tempo = DEFAULT_TEMPO;
+    let mut last_tick = 0;
+    let mut time_us = 0.0;
+    let mut events = Vec::new();
+    for (tick, event) in timed_events {
+        let us_per_tick = if division & 0x8000 == 0 {
+            f64::from(tempo) / f64::from(division.max(1))
+        } else {
+            let frames_per_second = -f64::from((division >> 8) as u8 as i8);
+            let ticks_per_frame = f64::from(division & 0xff);
+            1_000_000.0 / (frames_per_second * ticks_per_frame)
+        };
+        time_us += (tick - last_tick) as f64 * us_per_tick;
+        last_tick = tick;
+
+        match event {
+            TrackEvent::Tempo(new_tempo) => tempo = new_tempo,
+            TrackEvent::Channel(message) => {
+                events.push((time_us.round() as u64, ReplayEvent::Midi(message)))
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+enum TrackEvent {
+    Tempo(u32),
+    Channel(Vec<u8>),
+}
+
+fn parse_track(data: &[u8], timed_events: &mut Vec<(u64, TrackEvent)>) -> Result<(), String> {
+    let mut reader = Reader { data, pos: 0 };
+    let mut tick = 0;
+    let mut running_status = None;
+
+    while reader.pos < data.len() {
+        tick += u64::from(reader.vlq()?);
+        let status = match reader.peek()? {
+            status if status & 0x80 != 0 => {
+                reader.pos += 1;
+                status
+            }
+            _ => running_status.ok_or("Data byte without running status")?,
+        };
+
+        match status {
+            0xff => {
+                let meta_type = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let meta_data = reader.bytes(len)?;
+                match (meta_type, meta_data) {
+                    (0x2f, _) => break,
+                    (0x51, &[a, b, c]) => timed_events
+                        .push((tick, TrackEvent::Tempo(u32::from_be_bytes([0, a, b, c])))),
+                    _ => {}
+                }
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.vlq()? as usize;
+                reader.bytes(len)?;
+            }
+            0x80..=0xef => {
+                running_status = Some(status);
+                let num_data_bytes = match status >> 4 {
+                    0xc | 0xd => 1,
+                    _ => 2,
+                };
+                let mut message = vec![status];
+                message.extend_from_slice(reader.bytes(num_data_bytes)?);
+                timed_events.push((tick, TrackEvent::Channel(message)));
+            }
+            _ => return Err(format!("Unsupported status byte {status:#04x}")),
+        }
+    }
+
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Result<u8, String> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| "Unexpected end of MIDI file".to_owned())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn vlq(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Variable-length quantity too long".to_owned())
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| "Unexpected end of MIDI file".to_owned())?;
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_midi_file_with_tempo_change_and_running_status() {
+        let track = [
+            0x00, 0x90, 60, 100, // Note on at tick 0
+            0x81, 0x40, 62, 100, // Note on at tick 192 using running status
+            0x00, 0xff, 0x51, 0x03, 0x0f, 0x42, 0x40, // Tempo 1_000_000 µs per quarter note
+            0x83, 0x00, 0x80, 60, 0, // Note off at tick 576
+            0x00, 0xff, 0x2f, 0x00, // End of track
+        ];
+        let mut data = Vec::new();
+        data.extend_from_slice(b"MThd");
+        data.extend_from_slice(&[0, 0, 0, 6, 0, 0, 0, 1, 0, 192]);
+        data.extend_from_slice(b"MTrk");
+        data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        data.extend_from_slice(&track);
+
+        let events = parse_midi_file(&data).unwrap();
+        let events = events
+            .iter()
+            .map(|(time_us, event)| match event {
+                ReplayEvent::Midi(message) => (*time_us, message.as_slice()),
+                ReplayEvent::Logged(_) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            [
+                (0, &[0x90, 60, 100][..]),
+                (500_000, &[0x90, 62, 100][..]),
+                (2_500_000, &[0x80, 60, 0][..]),
+            ]
+        );
+    }
+}
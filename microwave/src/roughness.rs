@@ -0,0 +1,94 @@
+use tune::pitch::Pitch;
+
+/// Roughness of two pure tones of amplitude 1 at the critical interval. Used to normalize the result s.t. 1.0 means "as rough as the worst possible pair of sine waves".
+const MAX_PAIR_ROUGHNESS: f64 = 0.1808;
+
+/// Estimates the sensory roughness of the given pitches sounding simultaneously.
+///
+/// Every pitch is modeled as a harmonic tone with `num_partials` partials whose amplitudes fall off with 1/n. The actual waveform or sound of the synthesizer is not taken into account. The roughness of two tones is the sum of the Plomp–Levelt dissonance curve, as parametrized by W. A. Sethares, over all pairs of their partials.
+/// The result is the average roughness over all pairs of tones s.t. it does not grow with the number of held notes. Thus, a single tone is never rough.
+pub fn roughness(pitches: &[Pitch], num_partials: u16) -> f64 {
+    let partials = |pitch: Pitch| {
+        (1..=num_partials).map(move |harmonic| {
+            let harmonic = f64::from(harmonic);
+            (pitch.as_hz() * harmonic, 1.0 / harmonic)
+        })
+    };
+
+    let mut roughness = 0.0;
+    for (index, &pitch) in pitches.iter().enumerate() {
+        for &other_pitch in &pitches[index + 1..] {
+            for partial in partials(pitch) {
+                for other_partial in partials(other_pitch) {
+                    roughness += pair_roughness(partial, other_partial);
+                }
+            }
+        }
+    }
+
+    let num_pairs = pitches.len() * pitches.len().saturating_sub(1) / 2;
+    if num_pairs == 0 {
+        return 0.0;
+    }
+    roughness / num_pairs as f64 / MAX_PAIR_ROUGHNESS
+}
+
+fn pair_roughness((freq_a, amplitude_a): (f64, f64), (freq_b, amplitude_b): (f64, f64)) -> f64 {
+    const X_STAR: f64 = 0.24;
+    const S1: f64 = 0.0207;
+    const S2: f64 = 18.96;
+    const B1: f64 = 3.5;
+    const B2: f64 = 5.75;
+
+    let (lower, upper) = if freq_a < freq_b {
+        (freq_a, freq_b)
+    } else {
+        (freq_b, freq_a)
+    };
+    let s = X_STAR / (S1 * lower + S2);
+    let distance = s * (upper - lower);
+    amplitude_a.min(amplitude_b) * ((-B1 * distance).exp() - (-B2 * distance).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::{
+        note::NoteLetter,
+        pitch::{Pitched, Ratio},
+    };
+
+    use super::*;
+
+    #[test]
+    fn roughness_of_intervals() {
+        let root = NoteLetter::C.in_octave(4).pitch();
+        let interval = |ratio: f64| roughness(&[root, root * Ratio::from_float(ratio)], 8);
+
+        assert_eq!(roughness(&[root], 8), 0.0);
+
+        let octave = interval(2.0);
+        let fifth = interval(3.0 / 2.0);
+        let major_third = interval(5.0 / 4.0);
+        let minor_second = interval(16.0 / 15.0);
+        let tempered_fifth = interval(Ratio::from_semitones(7).as_float());
+
+        assert!(octave < fifth);
+        assert!(fifth < tempered_fifth);
+        assert!(tempered_fifth < major_third);
+        assert!(major_third < minor_second);
+    }
+
+    #[test]
+    fn roughness_is_averaged_over_pairs_of_tones() {
+        let root = NoteLetter::C.in_octave(4).pitch();
+        let minor_second = root * Ratio::from_float(16.0 / 15.0);
+
+        let interval = roughness(&[root, minor_second], 8);
+        let unison = roughness(&[minor_second, minor_second], 8);
+        // Two minor seconds and one unison
+        let chord = roughness(&[root, minor_second, minor_second], 8);
+
+        assert!((chord - (2.0 * interval + unison) / 3.0).abs() < 1e-9);
+        assert!(chord < interval);
+    }
+}
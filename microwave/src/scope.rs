@@ -0,0 +1,176 @@
+use std::{collections::VecDeque, f64::consts::TAU};
+
+use ringbuf::{Consumer, Producer, RingBuffer};
+
+use crate::magnetron::util::fft;
+
+/// Number of samples analyzed per frame. Must be a power of two for the FFT.
+const WINDOW_SIZE: usize = 4096;
+
+/// Number of mono samples that can be buffered between two frames.
+const EXCHANGE_BUFFER_SIZE: usize = 4 * WINDOW_SIZE;
+
+/// Number of samples displayed in the oscilloscope.
+pub const NUM_WAVEFORM_SAMPLES: usize = 1024;
+
+/// Number of bars of the spectrum analyzer. The bars are spaced logarithmically between [`LOWEST_FREQ_HZ`] and [`HIGHEST_FREQ_HZ`].
+pub const NUM_SPECTRUM_BINS: usize = 128;
+
+pub const LOWEST_FREQ_HZ: f64 = 20.0;
+
+pub const HIGHEST_FREQ_HZ: f64 = 20000.0;
+
+/// Level below which a bar is rendered as silent [dB].
+const FLOOR_DB: f64 = -72.0;
+
+/// Oscilloscope and spectrum analyzer of the master output.
+pub struct Scope {
+    pub visible: bool,
+    master_out: Consumer<f32>,
+    sample_rate_hz: f64,
+    window: VecDeque<f32>,
+    window_function: Vec<f64>,
+    waveform: Vec<f32>,
+    spectrum: Vec<f32>,
+}
+
+/// Creates a [`Scope`] and the [`Producer`] the audio thread should feed the master output into.
+pub fn create(sample_rate_hz: f64) -> (Producer<f32>, Scope) {
+    let (send, recv) = RingBuffer::new(EXCHANGE_BUFFER_SIZE).split();
+
+    // Hann window
+    let window_function = (0..WINDOW_SIZE)
+        .map(|index| 0.5 - 0.5 * (TAU * index as f64 / WINDOW_SIZE as f64).cos())
+        .collect();
+
+    (
+        send,
+        Scope {
+            visible: false,
+            master_out: recv,
+            sample_rate_hz,
+            window: VecDeque::with_capacity(WINDOW_SIZE),
+            window_function,
+            waveform: Vec::new(),
+            spectrum: Vec::new(),
+        },
+    )
+}
+
+impl Scope {
+    /// Samples of the most recent waveform period, starting at a rising zero crossing if possible.
+    pub fn waveform(&self) -> &[f32] {
+        &self.waveform
+    }
+
+    /// Normalized levels (0.0 to 1.0) of the spectrum analyzer bars.
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// Consumes the samples rendered since the last call and, if the scope is visible, analyzes the most recent ones.
+    pub fn update(&mut self) {
+        while let Some(sample) = self.master_out.pop() {
+            if self.window.len() == WINDOW_SIZE {
+                self.window.pop_front();
+            }
+            self.window.push_back(sample);
+        }
+
+        if !self.visible || self.window.len() < WINDOW_SIZE {
+            self.waveform.clear();
+            self.spectrum.clear();
+            return;
+        }
+
+        self.update_waveform();
+        self.update_spectrum();
+    }
+
+    /// Triggers on the latest rising zero crossing that still leaves enough samples to be displayed s.t. periodic waveforms stand still.
+    fn update_waveform(&mut self) {
+        let last_start = WINDOW_SIZE - NUM_WAVEFORM_SAMPLES;
+        let trigger = (1..=last_start)
+            .rev()
+            .find(|&index| self.window[index - 1] < 0.0 && self.window[index] >= 0.0)
+            .unwrap_or(last_start);
+
+        self.waveform.clear();
+        self.waveform.extend(
+            self.window
+                .range(trigger..trigger + NUM_WAVEFORM_SAMPLES)
+                .copied(),
+        );
+    }
+
+    fn update_spectrum(&mut self) {
+        let mut buffer: Vec<_> = self
+            .window
+            .iter()
+            .zip(&self.window_function)
+            .map(|(&sample, &weight)| (f64::from(sample) * weight, 0.0))
+            .collect();
+        fft(&mut buffer);
+
+        // The Hann window halves the amplitude of the analyzed signal
+        let amplitudes: Vec<_> = buffer[..WINDOW_SIZE / 2]
+            .iter()
+            .map(|&(re, im)| 4.0 * (re * re + im * im).sqrt() / WINDOW_SIZE as f64)
+            .collect();
+
+        let bin_width_hz = self.sample_rate_hz / WINDOW_SIZE as f64;
+        let total_range = HIGHEST_FREQ_HZ / LOWEST_FREQ_HZ;
+        let freq_at =
+            |bar: usize| LOWEST_FREQ_HZ * total_range.powf(bar as f64 / NUM_SPECTRUM_BINS as f64);
+
+        self.spectrum.clear();
+        self.spectrum.extend((0..NUM_SPECTRUM_BINS).map(|bar| {
+            // Narrow bars at the low end fall between two FFT bins. Use at least the nearest bin.
+            let lower_bin = (freq_at(bar) / bin_width_hz).round() as usize;
+            let upper_bin = ((freq_at(bar + 1) / bin_width_hz).round() as usize).max(lower_bin + 1);
+            let amplitude = amplitudes
+                .get(lower_bin..upper_bin.min(amplitudes.len()))
+                .unwrap_or_default()
+                .iter()
+                .fold(0.0, |max, &amplitude| f64::max(max, amplitude));
+            let level_db = 20.0 * amplitude.log10();
+            ((level_db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0) as f32
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn analyze_sine_wave() {
+        let sample_rate_hz = 48000.0;
+        let (mut send, mut scope) = create(sample_rate_hz);
+        scope.visible = true;
+
+        send.push_iter(
+            &mut (0..WINDOW_SIZE)
+                .map(|index| (TAU * 440.0 * index as f64 / sample_rate_hz).sin() as f32),
+        );
+        scope.update();
+
+        let waveform = scope.waveform();
+        assert_eq!(waveform.len(), NUM_WAVEFORM_SAMPLES);
+        assert!(waveform[0] >= 0.0 && waveform[0] < 0.1);
+        assert!(waveform[1] > waveform[0]);
+
+        let spectrum = scope.spectrum();
+        assert_eq!(spectrum.len(), NUM_SPECTRUM_BINS);
+        let loudest_bar = (0..NUM_SPECTRUM_BINS)
+            .max_by(|&a, &b| spectrum[a].partial_cmp(&spectrum[b]).unwrap())
+            .unwrap();
+        let bar_freq_hz = LOWEST_FREQ_HZ
+            * (HIGHEST_FREQ_HZ / LOWEST_FREQ_HZ)
+                .powf(loudest_bar as f64 / NUM_SPECTRUM_BINS as f64);
+        assert!((400.0..440.0).contains(&bar_freq_hz), "{bar_freq_hz}");
+        assert_approx_eq!(spectrum[loudest_bar], 1.0, 0.1);
+    }
+}
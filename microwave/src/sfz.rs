@@ -0,0 +1,618 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+};
+
+use hound::{SampleFormat, WavReader};
+use magnetron::automation::AutomationContext;
+use tune::{
+    note::Note,
+    pitch::{Pitch, Pitched, Ratio},
+    scala::{KbmRoot, Scl},
+};
+use tune_cli::CliResult;
+
+use crate::{audio::AudioStage, piano::Backend};
+
+/// Shortest release time in seconds. Prevents clicks when a region does not define a release time.
+const MIN_RELEASE_SECS: f64 = 0.005;
+
+/// Plays SFZ instruments by resampling their recorded samples.
+///
+/// For every note, the region is selected by the nearest 12-EDO key and the velocity. Its sample is then played back at the rate required to hit the exact pitch of the note s.t. any tuning can be played without tuning messages.
+/// Loops are ignored, i.e. a note ends when the end of its sample is reached or its release phase is over.
+/// Multiple SFZ files can be loaded to switch between them via program changes.
+pub struct SfzBackend<I, S> {
+    messages: Sender<Message<S>>,
+    info_sender: Sender<I>,
+    instruments: Vec<SfzInstrument>,
+    curr_program: usize,
+    active_root_pitches: HashMap<S, Pitch>,
+}
+
+pub fn create<I, S>(
+    info_sender: Sender<I>,
+    sfz_file_locations: &[PathBuf],
+    sample_rate_hz: f64,
+) -> CliResult<(SfzBackend<I, S>, SfzSynth<S>)> {
+    let instruments = sfz_file_locations
+        .iter()
+        .map(|location| SfzInstrument::load(location))
+        .collect::<CliResult<Vec<_>>>()?;
+
+    if instruments.is_empty() {
+        return Err("At least one SFZ file is required".to_owned().into());
+    }
+
+    let (send, recv) = mpsc::channel();
+
+    Ok((
+        SfzBackend {
+            messages: send,
+            info_sender,
+            instruments,
+            curr_program: 0,
+            active_root_pitches: HashMap::new(),
+        },
+        SfzSynth {
+            messages: recv,
+            sample_rate_hz,
+            voices: HashMap::new(),
+        },
+    ))
+}
+
+impl<I, S> SfzBackend<I, S> {
+    fn send(&self, id: S, action: Action) {
+        self.messages.send(Message { id, action }).unwrap();
+    }
+}
+
+impl<I: From<SfzInfo> + Send, S: Copy + Eq + Hash + Send> Backend<S> for SfzBackend<I, S> {
+    fn set_tuning(&mut self, _tuning: (&Scl, KbmRoot)) {}
+
+    fn set_no_tuning(&mut self) {}
+
+    fn send_status(&mut self) {
+        let instrument = &self.instruments[self.curr_program];
+        self.info_sender
+            .send(
+                SfzInfo {
+                    sfz_file_location: instrument.file_location.clone(),
+                    program_number: self.curr_program,
+                    num_regions: instrument.regions.len(),
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+
+    fn start(&mut self, id: S, _degree: i32, pitch: Pitch, velocity: u8) {
+        let key = pitch.find_in_tuning(()).approx_value.midi_number();
+        let region = match self.instruments[self.curr_program].find_region(key, velocity) {
+            Some(region) => region,
+            None => return,
+        };
+
+        let root_pitch = region.spec.root_pitch();
+        let action = Action::Start {
+            sample: region.sample.clone(),
+            playback_ratio: Ratio::between_pitches(root_pitch, pitch).as_float(),
+            amplitude: region.spec.amplitude() * (f64::from(velocity) / 127.0).powi(2),
+            release_secs: region.spec.release_secs.max(MIN_RELEASE_SECS),
+        };
+        self.active_root_pitches.insert(id, root_pitch);
+        self.send(id, action);
+    }
+
+    fn update_pitch(&mut self, id: S, _degree: i32, pitch: Pitch, _velocity: u8) {
+        if let Some(&root_pitch) = self.active_root_pitches.get(&id) {
+            self.send(
+                id,
+                Action::UpdatePitch {
+                    playback_ratio: Ratio::between_pitches(root_pitch, pitch).as_float(),
+                },
+            );
+        }
+    }
+
+    fn update_pressure(&mut self, _id: S, _pressure: u8) {}
+
+    fn stop(&mut self, id: S, _velocity: u8) {
+        if self.active_root_pitches.remove(&id).is_some() {
+            self.send(id, Action::Stop);
+        }
+    }
+
+    fn all_sound_off(&mut self) {
+        // The voices themselves are cut when the audio stage is muted
+        self.active_root_pitches.clear();
+    }
+
+    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        self.curr_program = update_fn(self.curr_program).min(self.instruments.len() - 1);
+        self.send_status();
+    }
+
+    fn num_programs(&self) -> usize {
+        self.instruments.len()
+    }
+
+    fn control_change(&mut self, _controller: u8, _value: u8) {}
+
+    fn channel_pressure(&mut self, _pressure: u8) {}
+
+    fn pitch_bend(&mut self, _value: i16) {}
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+}
+
+pub struct SfzSynth<S> {
+    messages: Receiver<Message<S>>,
+    sample_rate_hz: f64,
+    voices: HashMap<S, Voice>,
+}
+
+impl<S: Eq + Hash + Send, T> AudioStage<T> for SfzSynth<S> {
+    fn render(&mut self, buffer: &mut [f64], _context: &AutomationContext<T>) {
+        for message in self.messages.try_iter() {
+            match message.action {
+                Action::Start {
+                    sample,
+                    playback_ratio,
+                    amplitude,
+                    release_secs,
+                } => {
+                    let voice = Voice {
+                        sample_rate_ratio: sample.sample_rate_hz / self.sample_rate_hz,
+                        sample,
+                        position: 0.0,
+                        playback_ratio,
+                        amplitude,
+                        release_secs,
+                        release_level: None,
+                    };
+                    self.voices.insert(message.id, voice);
+                }
+                Action::UpdatePitch { playback_ratio } => {
+                    if let Some(voice) = self.voices.get_mut(&message.id) {
+                        voice.playback_ratio = playback_ratio;
+                    }
+                }
+                Action::Stop => {
+                    if let Some(voice) = self.voices.get_mut(&message.id) {
+                        voice.release_level.get_or_insert(1.0);
+                    }
+                }
+            }
+        }
+
+        let sample_rate_hz = self.sample_rate_hz;
+        self.voices
+            .retain(|_, voice| voice.render(buffer, sample_rate_hz));
+    }
+
+    fn mute(&mut self) {
+        self.voices.clear();
+    }
+}
+
+struct Message<S> {
+    id: S,
+    action: Action,
+}
+
+enum Action {
+    Start {
+        sample: Arc<Sample>,
+        playback_ratio: f64,
+        amplitude: f64,
+        release_secs: f64,
+    },
+    UpdatePitch {
+        playback_ratio: f64,
+    },
+    Stop,
+}
+
+struct Voice {
+    sample: Arc<Sample>,
+    sample_rate_ratio: f64,
+    position: f64,
+    playback_ratio: f64,
+    amplitude: f64,
+    release_secs: f64,
+    release_level: Option<f64>,
+}
+
+impl Voice {
+    /// Adds the voice to the interleaved stereo `buffer`. Returns `false` when the voice has finished.
+    fn render(&mut self, buffer: &mut [f64], sample_rate_hz: f64) -> bool {
+        let release_step = 1.0 / (self.release_secs * sample_rate_hz);
+        let position_step = self.playback_ratio * self.sample_rate_ratio;
+
+        for frame in buffer.chunks_mut(2) {
+            let (left, right) = match self.sample.frame_at(self.position) {
+                Some(frame) => frame,
+                None => return false,
+            };
+
+            let level = match &mut self.release_level {
+                Some(release_level) => {
+                    *release_level -= release_step;
+                    if *release_level <= 0.0 {
+                        return false;
+                    }
+                    *release_level
+                }
+                None => 1.0,
+            };
+
+            let amplitude = self.amplitude * level;
+            frame[0] += left * amplitude;
+            frame[1] += right * amplitude;
+            self.position += position_step;
+        }
+
+        true
+    }
+}
+
+pub struct SfzInfo {
+    pub sfz_file_location: PathBuf,
+    pub program_number: usize,
+    pub num_regions: usize,
+}
+
+struct SfzInstrument {
+    file_location: PathBuf,
+    regions: Vec<SfzRegion>,
+}
+
+impl SfzInstrument {
+    fn load(file_location: &Path) -> CliResult<Self> {
+        let content = fs::read_to_string(file_location)
+            .map_err(|err| format!("Could not read SFZ file {}: {err}", file_location.display()))?;
+        let base_dir = file_location.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut samples = HashMap::new();
+        let regions = parse_sfz(&content)?
+            .into_iter()
+            .map(|spec| {
+                let sample_location = base_dir.join(&spec.sample);
+                let sample = match samples.get(&sample_location) {
+                    Some(sample) => Arc::clone(sample),
+                    None => {
+                        let sample = Arc::new(Sample::load(&sample_location)?);
+                        samples.insert(sample_location, sample.clone());
+                        sample
+                    }
+                };
+                Ok(SfzRegion { spec, sample })
+            })
+            .collect::<CliResult<_>>()?;
+
+        Ok(Self {
+            file_location: file_location.to_owned(),
+            regions,
+        })
+    }
+
+    fn find_region(&self, key: i32, velocity: u8) -> Option<&SfzRegion> {
+        self.regions.iter().find(|region| {
+            (region.spec.lokey..=region.spec.hikey).contains(&key)
+                && (region.spec.lovel..=region.spec.hivel).contains(&velocity)
+        })
+    }
+}
+
+struct SfzRegion {
+    spec: RegionSpec,
+    sample: Arc<Sample>,
+}
+
+/// The opcodes of a `<region>` after applying the opcodes of the enclosing headers.
+#[derive(Clone, Debug, PartialEq)]
+struct RegionSpec {
+    sample: String,
+    lokey: i32,
+    hikey: i32,
+    lovel: u8,
+    hivel: u8,
+    pitch_keycenter: i32,
+    transpose: i32,
+    tune_cents: f64,
+    volume_db: f64,
+    release_secs: f64,
+}
+
+impl RegionSpec {
+    /// The pitch at which the sample sounds when played back at its original rate.
+    fn root_pitch(&self) -> Pitch {
+        Note::from_midi_number(self.pitch_keycenter).pitch()
+            / Ratio::from_semitones(self.transpose)
+            / Ratio::from_cents(self.tune_cents)
+    }
+
+    fn amplitude(&self) -> f64 {
+        10f64.powf(self.volume_db / 20.0)
+    }
+}
+
+/// Parses the subset of the SFZ format needed to retune simple instruments.
+///
+/// Supported headers are `<control>`, `<global>`, `<master>`, `<group>` and `<region>`. Opcodes of enclosing headers are inherited by the regions. Unknown opcodes are ignored.
+fn parse_sfz(content: &str) -> Result<Vec<RegionSpec>, String> {
+    let mut parser = SfzParser::default();
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap().trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        for word in line.split_whitespace() {
+            if let Some(header) = word.strip_prefix('<').and_then(|w| w.strip_suffix('>')) {
+                parser.open_header(header)?;
+            } else if let Some((opcode, value)) = word.split_once('=') {
+                parser.push_opcode(opcode, value);
+            } else if let Some((_, value)) = parser.curr_opcodes().last_mut() {
+                // Sample names may contain spaces
+                value.push(' ');
+                value.push_str(word);
+            } else {
+                return Err(format!("Invalid token '{word}'"));
+            }
+        }
+    }
+    parser.close_header()?;
+
+    Ok(parser.regions)
+}
+
+/// Opcodes of the `<control>`, `<global>`, `<master>`, `<group>` and `<region>` headers, in this order, followed by the opcodes of the ignored headers.
+#[derive(Default)]
+struct SfzParser {
+    scopes: [Vec<(String, String)>; 6],
+    curr_scope: usize,
+    regions: Vec<RegionSpec>,
+}
+
+impl SfzParser {
+    const REGION_SCOPE: usize = 4;
+    const IGNORED_SCOPE: usize = 5;
+
+    fn open_header(&mut self, header: &str) -> Result<(), String> {
+        self.close_header()?;
+        self.curr_scope = match header {
+            "control" => 0,
+            "global" => 1,
+            "master" => 2,
+            "group" => 3,
+            "region" => Self::REGION_SCOPE,
+            // Curves, effects and MIDI settings do not affect the sample playback
+            "curve" | "effect" | "midi" => Self::IGNORED_SCOPE,
+            _ => return Err(format!("Unsupported header <{header}>")),
+        };
+        for scope in &mut self.scopes[self.curr_scope..] {
+            scope.clear();
+        }
+        Ok(())
+    }
+
+    fn close_header(&mut self) -> Result<(), String> {
+        if self.curr_scope == Self::REGION_SCOPE {
+            let default_path = self.scopes[0]
+                .iter()
+                .rev()
+                .find(|(opcode, _)| opcode == "default_path")
+                .map(|(_, value)| value.as_str())
+                .unwrap_or_default();
+            let region = resolve_region(self.scopes[1..].iter().flatten(), default_path)?;
+            self.regions.push(region);
+        }
+        Ok(())
+    }
+
+    fn push_opcode(&mut self, opcode: &str, value: &str) {
+        self.curr_opcodes()
+            .push((opcode.to_owned(), value.to_owned()));
+    }
+
+    fn curr_opcodes(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.scopes[self.curr_scope]
+    }
+}
+
+fn resolve_region<'a>(
+    opcodes: impl IntoIterator<Item = &'a (String, String)>,
+    default_path: &str,
+) -> Result<RegionSpec, String> {
+    let mut region = RegionSpec {
+        sample: String::new(),
+        lokey: 0,
+        hikey: 127,
+        lovel: 0,
+        hivel: 127,
+        pitch_keycenter: 60,
+        transpose: 0,
+        tune_cents: 0.0,
+        volume_db: 0.0,
+        release_secs: 0.0,
+    };
+
+    for (opcode, value) in opcodes {
+        let invalid_value = |err: &dyn std::fmt::Display| {
+            format!("Invalid value '{value}' for opcode {opcode}: {err}")
+        };
+        let parse_f64 = || value.parse::<f64>().map_err(|err| invalid_value(&err));
+        let parse_u8 = || value.parse::<u8>().map_err(|err| invalid_value(&err));
+        let parse_key = || parse_key(value).map_err(|err| invalid_value(&err));
+
+        match opcode.as_str() {
+            "sample" => region.sample = format!("{default_path}{value}").replace('\\', "/"),
+            "lokey" => region.lokey = parse_key()?,
+            "hikey" => region.hikey = parse_key()?,
+            "key" => {
+                let key = parse_key()?;
+                region.lokey = key;
+                region.hikey = key;
+                region.pitch_keycenter = key;
+            }
+            "pitch_keycenter" => region.pitch_keycenter = parse_key()?,
+            "lovel" => region.lovel = parse_u8()?,
+            "hivel" => region.hivel = parse_u8()?,
+            "transpose" => region.transpose = parse_key()?,
+            "tune" => region.tune_cents = parse_f64()?,
+            "volume" => region.volume_db = parse_f64()?,
+            "ampeg_release" => region.release_secs = parse_f64()?,
+            _ => {}
+        }
+    }
+
+    if region.sample.is_empty() {
+        return Err("Region without sample opcode".to_owned());
+    }
+
+    Ok(region)
+}
+
+/// Parses a MIDI number or a note name like `c4` (= 60), `c#4` or `db4`.
+fn parse_key(value: &str) -> Result<i32, String> {
+    if let Ok(key) = value.parse() {
+        return Ok(key);
+    }
+
+    value
+        .parse::<Note>()
+        .map(|note| note.midi_number())
+        .map_err(|_| "Expected MIDI number or note name".to_owned())
+}
+
+/// Stereo frames of a WAV file. Mono files are played back on both channels.
+struct Sample {
+    sample_rate_hz: f64,
+    frames: Vec<(f64, f64)>,
+}
+
+impl Sample {
+    fn load(location: &Path) -> CliResult<Self> {
+        let describe_err =
+            |err: hound::Error| format!("Could not load sample {}: {err}", location.display());
+
+        let reader = WavReader::open(location).map_err(describe_err)?;
+        let spec = reader.spec();
+        let samples: Vec<f64> = match spec.sample_format {
+            SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .map(|sample| sample.map(f64::from))
+                .collect::<Result<_, _>>(),
+            SampleFormat::Int if !(1..=32).contains(&spec.bits_per_sample) => {
+                Err(hound::Error::Unsupported)
+            }
+            SampleFormat::Int => {
+                let scale = f64::from(1u32 << (spec.bits_per_sample - 1));
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| f64::from(sample) / scale))
+                    .collect::<Result<_, _>>()
+            }
+        }
+        .map_err(describe_err)?;
+
+        let num_channels = usize::from(spec.channels.max(1));
+        let frames = samples
+            .chunks_exact(num_channels)
+            .map(|frame| (frame[0], frame[num_channels.min(2) - 1]))
+            .collect();
+
+        Ok(Self {
+            sample_rate_hz: f64::from(spec.sample_rate),
+            frames,
+        })
+    }
+
+    /// Linearly interpolates between the frames around `position`.
+    fn frame_at(&self, position: f64) -> Option<(f64, f64)> {
+        let index = position as usize;
+        let (l0, r0) = *self.frames.get(index)?;
+        let (l1, r1) = *self.frames.get(index + 1)?;
+        let fraction = position.fract();
+        Some((l0 + (l1 - l0) * fraction, r0 + (r1 - r0) * fraction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sfz_with_inherited_opcodes() {
+        let regions = parse_sfz(
+            "// Piano
+            <control> default_path=samples\\
+            <global> ampeg_release=0.5
+            <group> lovel=64 volume=-6
+            <region> sample=Piano C4.wav key=c4
+            <region> sample=d4.wav lokey=61 hikey=d#4 pitch_keycenter=62 tune=-12.5
+            <group>
+            <region> sample=e4.wav lokey=64 hikey=64 transpose=-1",
+        )
+        .unwrap();
+
+        assert_eq!(regions.len(), 3);
+
+        assert_eq!(regions[0].sample, "samples/Piano C4.wav");
+        assert_eq!((regions[0].lokey, regions[0].hikey), (60, 60));
+        assert_eq!(regions[0].pitch_keycenter, 60);
+        assert_eq!((regions[0].lovel, regions[0].hivel), (64, 127));
+        assert_eq!(regions[0].volume_db, -6.0);
+        assert_eq!(regions[0].release_secs, 0.5);
+
+        assert_eq!(regions[1].sample, "samples/d4.wav");
+        assert_eq!((regions[1].lokey, regions[1].hikey), (61, 63));
+        assert_eq!(regions[1].tune_cents, -12.5);
+
+        assert_eq!((regions[2].lovel, regions[2].volume_db), (0, 0.0));
+        assert_eq!(regions[2].release_secs, 0.5);
+        assert!(
+            (regions[2].root_pitch().as_hz() - Note::from_midi_number(61).pitch().as_hz()).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn skip_unsupported_headers() {
+        let regions = parse_sfz(
+            "<curve> curve_index=7 v000=0 v127=1
+            <effect> type=reverb
+            <midi> sample=ignored.wav
+            <region> sample=c4.wav key=60
+            <effect> bus=fx1 sample=ignored.wav",
+        )
+        .unwrap();
+
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].sample, "c4.wav");
+    }
+
+    #[test]
+    fn parse_note_names() {
+        assert_eq!(parse_key("60"), Ok(60));
+        assert_eq!(parse_key("c4"), Ok(60));
+        assert_eq!(parse_key("C#4"), Ok(61));
+        assert_eq!(parse_key("db4"), Ok(61));
+        assert_eq!(parse_key("a-1"), Ok(9));
+        assert!(parse_key("h4").is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Becomes active when the process receives a termination signal, e.g. when the user hits Ctrl+C in the terminal.
+///
+/// This allows the app to silence all backends before it quits. A second signal terminates the process immediately.
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn register() -> Self {
+        use signal_hook::{consts::TERM_SIGNALS, flag};
+
+        let signal = Self::default();
+        for &sig in TERM_SIGNALS {
+            flag::register_conditional_shutdown(sig, 1, signal.requested.clone()).unwrap();
+            flag::register(sig, signal.requested.clone()).unwrap();
+        }
+        signal
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn register() -> Self {
+        Self::default()
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt::Write, fs, ops::Range};
+
+use chrono::Local;
+use tune::{
+    pitch::{Pitch, Ratio},
+    tuning::Scale,
+};
+
+/// Size of the exported canvas in user units. SVG viewers scale the canvas to any resolution.
+const WIDTH: f64 = 1600.0;
+const HEIGHT: f64 = 900.0;
+const MARGIN: f64 = 80.0;
+
+/// Resolution-independent snapshot of the scale lines, the held notes and the rational approximations of the intervals between them.
+pub struct Snapshot {
+    pitch_range: Range<Pitch>,
+    scale_pitches: Vec<(i32, Pitch)>,
+    held_pitches: Vec<Pitch>,
+    odd_limit: u32,
+}
+
+impl Snapshot {
+    pub fn new(
+        pitch_range: Range<Pitch>,
+        tuning: impl Scale,
+        held_pitches: impl IntoIterator<Item = Pitch>,
+        odd_limit: u32,
+    ) -> Self {
+        let scale_pitches = tuning.sorted_pitches_within(pitch_range.clone()).collect();
+        let mut held_pitches: Vec<_> = held_pitches.into_iter().collect();
+        held_pitches.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        Self {
+            pitch_range,
+            scale_pitches,
+            held_pitches,
+            odd_limit,
+        }
+    }
+
+    /// Writes the snapshot to a time-stamped SVG file in the current working directory.
+    pub fn export(&self) {
+        let output_file_name = format!(
+            "microwave_snapshot_{}.svg",
+            Local::now().format("%Y%m%d_%H%M%S")
+        );
+
+        match fs::write(&output_file_name, self.to_svg()) {
+            Ok(()) => println!("[INFO] Created `{output_file_name}`"),
+            Err(err) => eprintln!("[WARNING] Cannot write `{output_file_name}`: {err}"),
+        }
+    }
+
+    /// Renders the scale lines labeled with their degrees, the held notes labeled with their frequencies and, next to each held note, the nearest fractions of the intervals to all lower held notes.
+    pub fn to_svg(&self) -> String {
+        let top = MARGIN;
+        let bottom = HEIGHT - MARGIN;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif">"#
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r##"<rect width="{WIDTH}" height="{HEIGHT}" fill="#2E3440"/>"##
+        )
+        .unwrap();
+
+        for &(degree, pitch) in &self.scale_pitches {
+            let x = self.x_position(pitch);
+            let stroke_width = if degree == 0 { 2.0 } else { 1.0 };
+            writeln!(
+                svg,
+                r##"<line x1="{x:.2}" y1="{top}" x2="{x:.2}" y2="{bottom}" stroke="#4C566A" stroke-width="{stroke_width}"/>"##
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r##"<text x="{x:.2}" y="{}" font-size="12" fill="#D8DEE9" text-anchor="middle">{degree}</text>"##,
+                top - 8.0
+            )
+            .unwrap();
+        }
+
+        for (index, &pitch) in self.held_pitches.iter().enumerate() {
+            let x = self.x_position(pitch);
+            writeln!(
+                svg,
+                r##"<line x1="{x:.2}" y1="{top}" x2="{x:.2}" y2="{bottom}" stroke="#88C0D0" stroke-width="3"/>"##
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r##"<text x="{x:.2}" y="{}" font-size="14" fill="#D8DEE9" text-anchor="middle">{:.2} Hz</text>"##,
+                bottom + 20.0,
+                pitch.as_hz()
+            )
+            .unwrap();
+
+            let mut y = top + 20.0;
+            for &lower_pitch in self.held_pitches[..index].iter().rev() {
+                let approximation =
+                    Ratio::between_pitches(lower_pitch, pitch).nearest_fraction(self.odd_limit);
+                writeln!(
+                    svg,
+                    r##"<text x="{:.2}" y="{y}" font-size="14" fill="#EBCB8B">{}/{} [{:+.1}c]</text>"##,
+                    x + 6.0,
+                    approximation.numer,
+                    approximation.denom,
+                    approximation.deviation.as_cents()
+                )
+                .unwrap();
+                y += 20.0;
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn x_position(&self, pitch: Pitch) -> f64 {
+        let total_range = Ratio::between_pitches(self.pitch_range.start, self.pitch_range.end);
+        let relative_position = Ratio::between_pitches(self.pitch_range.start, pitch).as_octaves()
+            / total_range.as_octaves();
+        MARGIN + relative_position * (WIDTH - 2.0 * MARGIN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::{
+        note::NoteLetter,
+        pitch::Pitched,
+        scala::{KbmRoot, Scl},
+    };
+
+    use super::*;
+
+    #[test]
+    fn render_svg() {
+        let scl = Scl::builder()
+            .push_ratio(Ratio::from_semitones(1))
+            .build()
+            .unwrap();
+        let kbm_root = KbmRoot::from(NoteLetter::C.in_octave(4));
+        let pitch_range = NoteLetter::C.in_octave(4).pitch()..NoteLetter::D.in_octave(5).pitch();
+        let held_pitches = [
+            NoteLetter::G.in_octave(4).pitch(),
+            NoteLetter::C.in_octave(4).pitch(),
+        ];
+
+        let svg = Snapshot::new(pitch_range, (&scl, kbm_root), held_pitches, 11).to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<line ").count(), 14 + 2);
+        assert!(svg.contains(r#"<line x1="80.00" y1="80" x2="80.00" y2="820""#));
+        assert!(svg.contains(">261.63 Hz</text>"));
+        assert!(svg.contains(">392.00 Hz</text>"));
+        assert!(svg.contains(">3/2 [-2.0c]</text>"));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::VecDeque, f64::consts::TAU, ops::Range};
+
+use ringbuf::{Consumer, Producer, RingBuffer};
+use tune::pitch::{Pitch, Ratio};
+
+/// Number of samples analyzed per row. Determines the frequency resolution of the spectrogram.
+const WINDOW_SIZE: usize = 4096;
+
+/// Number of mono samples that can be buffered between two frames.
+const EXCHANGE_BUFFER_SIZE: usize = 4 * WINDOW_SIZE;
+
+/// Number of frequency bins per row. The bins are spaced logarithmically across the visible pitch range.
+pub const NUM_BINS: usize = 160;
+
+/// Number of rows kept in the waterfall. A new row is added on every frame.
+pub const NUM_ROWS: usize = 120;
+
+/// Level below which a bin is rendered as silent [dB].
+const FLOOR_DB: f64 = -72.0;
+
+/// Waterfall spectrogram of the master output.
+///
+/// The bins are computed on the same logarithmic frequency axis as the scale lines s.t. the partials of the sounding notes line up with the pitches of the scale.
+pub struct Spectrogram {
+    pub visible: bool,
+    master_out: Consumer<f32>,
+    sample_rate_hz: f64,
+    window: VecDeque<f32>,
+    window_function: Vec<f64>,
+    pitch_range: Option<Range<Pitch>>,
+    rows: VecDeque<Vec<f32>>,
+}
+
+/// Creates a [`Spectrogram`] and the [`Producer`] the audio thread should feed the master output into.
+pub fn create(sample_rate_hz: f64) -> (Producer<f32>, Spectrogram) {
+    let (send, recv) = RingBuffer::new(EXCHANGE_BUFFER_SIZE).split();
+
+    // Hann window
+    let window_function = (0..WINDOW_SIZE)
+        .map(|index| 0.5 - 0.5 * (TAU * index as f64 / WINDOW_SIZE as f64).cos())
+        .collect();
+
+    (
+        send,
+        Spectrogram {
+            visible: false,
+            master_out: recv,
+            sample_rate_hz,
+            window: VecDeque::with_capacity(WINDOW_SIZE),
+            window_function,
+            pitch_range: None,
+            rows: VecDeque::with_capacity(NUM_ROWS),
+        },
+    )
+}
+
+impl Spectrogram {
+    /// Normalized levels (0.0 to 1.0) of the frequency bins, newest row first.
+    pub fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        self.rows.iter().map(Vec::as_slice)
+    }
+
+    /// Consumes the samples rendered since the last call and, if the spectrogram is visible, analyzes the most recent ones.
+    ///
+    /// Rows computed for a different pitch range are discarded since their bins would not line up with the scale lines anymore.
+    pub fn update(&mut self, pitch_range: Range<Pitch>) {
+        while let Some(sample) = self.master_out.pop() {
+            if self.window.len() == WINDOW_SIZE {
+                self.window.pop_front();
+            }
+            self.window.push_back(sample);
+        }
+
+        if !self.visible || self.window.len() < WINDOW_SIZE {
+            self.rows.clear();
+            return;
+        }
+
+        if self.pitch_range.as_ref() != Some(&pitch_range) {
+            self.rows.clear();
+            self.pitch_range = Some(pitch_range.clone());
+        }
+
+        let total_range = Ratio::between_pitches(pitch_range.start, pitch_range.end);
+        let row = (0..NUM_BINS)
+            .map(|bin| {
+                let bin_center = total_range.repeated((bin as f64 + 0.5) / NUM_BINS as f64);
+                let amplitude = self.amplitude_at((pitch_range.start * bin_center).as_hz());
+                let level_db = 20.0 * amplitude.log10();
+                ((level_db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0) as f32
+            })
+            .collect();
+
+        if self.rows.len() == NUM_ROWS {
+            self.rows.pop_back();
+        }
+        self.rows.push_front(row);
+    }
+
+    /// Evaluates a single DFT bin using the Goertzel algorithm. Returns the amplitude of a sine wave with the given frequency.
+    fn amplitude_at(&self, freq_hz: f64) -> f64 {
+        if freq_hz >= self.sample_rate_hz / 2.0 {
+            return 0.0;
+        }
+
+        let coeff = 2.0 * (TAU * freq_hz / self.sample_rate_hz).cos();
+        let (mut s1, mut s2) = (0.0, 0.0);
+        for (&sample, &weight) in self.window.iter().zip(&self.window_function) {
+            let s0 = f64::from(sample) * weight + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+
+        // The Hann window halves the amplitude of the analyzed signal
+        4.0 * power.max(0.0).sqrt() / WINDOW_SIZE as f64
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::{SocketAddr, UdpSocket},
+    sync::mpsc::Sender,
+};
+
+use tune::{
+    pitch::Pitch,
+    scala::{KbmRoot, Scl},
+};
+use tune_cli::CliResult;
+
+use crate::piano::Backend;
+
+/// Node ID of the default group of scsynth.
+const DEFAULT_GROUP: i32 = 1;
+
+/// Node IDs below this value are reserved for nodes created by sclang.
+const FIRST_NODE_ID: i32 = 1000;
+
+/// Plays every note as a separate synth node on a running scsynth server.
+///
+/// Notes are started with `/s_new` using their exact frequency s.t. no tuning messages are required. Pitch changes, note offs and controllers are sent via `/n_set`.
+pub struct ScBackend<I, S> {
+    info_sender: Sender<I>,
+    socket: UdpSocket,
+    server_address: SocketAddr,
+    synth_defs: Vec<String>,
+    curr_program: usize,
+    next_node_id: i32,
+    active_nodes: HashMap<S, i32>,
+}
+
+pub fn create<I, S>(
+    info_sender: Sender<I>,
+    server_address: SocketAddr,
+    synth_defs: Vec<String>,
+) -> CliResult<ScBackend<I, S>> {
+    if synth_defs.is_empty() {
+        return Err("At least one synth definition is required"
+            .to_owned()
+            .into());
+    }
+
+    let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+
+    Ok(ScBackend {
+        info_sender,
+        socket,
+        server_address,
+        synth_defs,
+        curr_program: 0,
+        next_node_id: FIRST_NODE_ID,
+        active_nodes: HashMap::new(),
+    })
+}
+
+impl<I, S> ScBackend<I, S> {
+    fn send(&self, address: &str, args: &[OscArg]) {
+        if let Err(err) = self
+            .socket
+            .send_to(&encode_osc(address, args), self.server_address)
+        {
+            eprintln!(
+                "[WARNING] Could not send OSC message to {}: {err}",
+                self.server_address
+            );
+        }
+    }
+
+    fn set_group_control(&self, control: &str, value: f32) {
+        self.send(
+            "/n_set",
+            &[
+                OscArg::Int(DEFAULT_GROUP),
+                OscArg::Str(control),
+                OscArg::Float(value),
+            ],
+        );
+    }
+}
+
+impl<I: From<ScInfo> + Send, S: Copy + Eq + Hash + Send> Backend<S> for ScBackend<I, S> {
+    fn set_tuning(&mut self, _tuning: (&Scl, KbmRoot)) {}
+
+    fn set_no_tuning(&mut self) {}
+
+    fn send_status(&mut self) {
+        self.info_sender
+            .send(
+                ScInfo {
+                    server_address: self.server_address,
+                    program_number: self.curr_program,
+                    synth_def: self.synth_defs[self.curr_program].clone(),
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+
+    fn start(&mut self, id: S, _degree: i32, pitch: Pitch, velocity: u8) {
+        let node_id = self.next_node_id;
+        self.next_node_id = self.next_node_id.checked_add(1).unwrap_or(FIRST_NODE_ID);
+
+        self.send(
+            "/s_new",
+            &[
+                OscArg::Str(&self.synth_defs[self.curr_program]),
+                OscArg::Int(node_id),
+                OscArg::Int(0),
+                OscArg::Int(DEFAULT_GROUP),
+                OscArg::Str("freq"),
+                OscArg::Float(pitch.as_hz() as f32),
+                OscArg::Str("amp"),
+                OscArg::Float(f32::from(velocity) / 127.0),
+            ],
+        );
+
+        if let Some(replaced_node_id) = self.active_nodes.insert(id, node_id) {
+            self.send(
+                "/n_set",
+                &[
+                    OscArg::Int(replaced_node_id),
+                    OscArg::Str("gate"),
+                    OscArg::Float(0.0),
+                ],
+            );
+        }
+    }
+
+    fn update_pitch(&mut self, id: S, _degree: i32, pitch: Pitch, _velocity: u8) {
+        if let Some(&node_id) = self.active_nodes.get(&id) {
+            self.send(
+                "/n_set",
+                &[
+                    OscArg::Int(node_id),
+                    OscArg::Str("freq"),
+                    OscArg::Float(pitch.as_hz() as f32),
+                ],
+            );
+        }
+    }
+
+    fn update_pressure(&mut self, id: S, pressure: u8) {
+        if let Some(&node_id) = self.active_nodes.get(&id) {
+            self.send(
+                "/n_set",
+                &[
+                    OscArg::Int(node_id),
+                    OscArg::Str("pressure"),
+                    OscArg::Float(f32::from(pressure) / 127.0),
+                ],
+            );
+        }
+    }
+
+    fn stop(&mut self, id: S, _velocity: u8) {
+        if let Some(node_id) = self.active_nodes.remove(&id) {
+            self.send(
+                "/n_set",
+                &[
+                    OscArg::Int(node_id),
+                    OscArg::Str("gate"),
+                    OscArg::Float(0.0),
+                ],
+            );
+        }
+    }
+
+    fn all_sound_off(&mut self) {
+        self.active_nodes.clear();
+        // Frees the released nodes as well
+        self.send("/g_freeAll", &[OscArg::Int(DEFAULT_GROUP)]);
+    }
+
+    fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
+        self.curr_program = update_fn(self.curr_program).min(self.synth_defs.len() - 1);
+        self.send_status();
+    }
+
+    fn num_programs(&self) -> usize {
+        self.synth_defs.len()
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        self.set_group_control(&format!("cc{controller}"), f32::from(value) / 127.0);
+    }
+
+    fn channel_pressure(&mut self, pressure: u8) {
+        self.set_group_control("pressure", f32::from(pressure) / 127.0);
+    }
+
+    fn pitch_bend(&mut self, value: i16) {
+        self.set_group_control("bend", f32::from(value) / 8192.0);
+    }
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+}
+
+pub struct ScInfo {
+    pub server_address: SocketAddr,
+    pub program_number: usize,
+    pub synth_def: String,
+}
+
+enum OscArg<'a> {
+    Int(i32),
+    Float(f32),
+    Str(&'a str),
+}
+
+fn encode_osc(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut message = Vec::new();
+    write_osc_string(&mut message, address);
+
+    let type_tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::Str(_) => 's',
+        }))
+        .collect();
+    write_osc_string(&mut message, &type_tags);
+
+    for arg in args {
+        match arg {
+            OscArg::Int(value) => message.extend(value.to_be_bytes()),
+            OscArg::Float(value) => message.extend(value.to_be_bytes()),
+            OscArg::Str(value) => write_osc_string(&mut message, value),
+        }
+    }
+
+    message
+}
+
+/// Writes a null-terminated string padded to a multiple of 4 bytes.
+fn write_osc_string(target: &mut Vec<u8>, value: &str) {
+    target.extend(value.as_bytes());
+    let num_nulls = 4 - value.len() % 4;
+    target.extend(std::iter::repeat(0).take(num_nulls));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_osc_message() {
+        assert_eq!(
+            encode_osc(
+                "/n_set",
+                &[OscArg::Int(1000), OscArg::Str("freq"), OscArg::Float(440.0)]
+            ),
+            [
+                b"/n_set\0\0".as_slice(),
+                b",isf\0\0\0\0",
+                &[0, 0, 0x03, 0xe8],
+                b"freq\0\0\0\0",
+                &[0x43, 0xdc, 0, 0],
+            ]
+            .concat()
+        );
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
     assets::MicrowaveConfig,
     audio::AudioStage,
     control::{LiveParameter, LiveParameterStorage, ParameterValue},
+    governor::Quality,
     magnetron::{
         source::{LfSource, StorageAccess},
         WaveformProperty, WaveformSpec,
@@ -28,12 +29,16 @@ use crate::{
     piano::Backend,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn create<I, S>(
     info_sender: Sender<I>,
     config: MicrowaveConfig,
+    config_updates: Receiver<MicrowaveConfig>,
     num_buffers: usize,
+    seed: Option<u64>,
     buffer_size: u32,
     sample_rate_hz: f64,
+    freeze_secs: f64,
     audio_in: Consumer<f64>,
 ) -> (WaveformBackend<I, S>, WaveformSynth<S>) {
     let state = SynthState {
@@ -44,11 +49,46 @@ pub fn create<I, S>(
             2 * usize::try_from(buffer_size).unwrap(),
         ), // The first invocation of cpal uses the double buffer size
         last_id: 0,
+        max_voices: None,
         audio_in_synchronized: false,
+        input_processor: InputProcessor::new(sample_rate_hz),
+        freezer: Freezer::new(
+            (freeze_secs * sample_rate_hz).round() as usize,
+            (FREEZE_CROSSFADE_SECS * sample_rate_hz).round() as usize,
+        ),
     };
 
     let (send, recv) = mpsc::channel();
 
+    let (waveforms, envelope_names, creator) = unpack_config(config, seed);
+
+    (
+        WaveformBackend {
+            messages: send,
+            info_sender,
+            waveforms,
+            curr_waveform: 0,
+            curr_envelope: envelope_names.len(), // curr_envelope == num_envelopes means default envelope
+            envelope_names,
+            creator,
+            config_updates,
+            seed,
+        },
+        WaveformSynth {
+            messages: recv,
+            state,
+            audio_in,
+        },
+    )
+}
+
+type UnpackedConfig = (
+    Vec<WaveformSpec<LfSource<WaveformProperty, LiveParameter>>>,
+    Vec<String>,
+    Creator<LfSource<WaveformProperty, LiveParameter>>,
+);
+
+fn unpack_config(config: MicrowaveConfig, seed: Option<u64>) -> UnpackedConfig {
     let templates = config
         .waveform_templates
         .into_iter()
@@ -67,20 +107,14 @@ pub fn create<I, S>(
         .map(|spec| (spec.name, spec.spec))
         .collect();
 
+    let creator = Creator::new(templates, envelopes);
+
     (
-        WaveformBackend {
-            messages: send,
-            info_sender,
-            waveforms: config.waveforms,
-            curr_waveform: 0,
-            curr_envelope: envelope_names.len(), // curr_envelope == num_envelopes means default envelope
-            envelope_names,
-            creator: Creator::new(templates, envelopes),
-        },
-        WaveformSynth {
-            messages: recv,
-            state,
-            audio_in,
+        config.waveforms,
+        envelope_names,
+        match seed {
+            Some(seed) => creator.with_seed(seed),
+            None => creator,
         },
     )
 }
@@ -93,6 +127,8 @@ pub struct WaveformBackend<I, S> {
     envelope_names: Vec<String>,
     curr_envelope: usize,
     creator: Creator<LfSource<WaveformProperty, LiveParameter>>,
+    config_updates: Receiver<MicrowaveConfig>,
+    seed: Option<u64>,
 }
 
 impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S> {
@@ -101,6 +137,7 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
     fn set_no_tuning(&mut self) {}
 
     fn send_status(&mut self) {
+        self.apply_config_updates();
         self.info_sender
             .send(
                 WaveformInfo {
@@ -115,17 +152,20 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
     }
 
     fn start(&mut self, id: S, _degree: i32, pitch: Pitch, velocity: u8) {
+        self.apply_config_updates();
         let selected_envelope = self.selected_envelope().to_owned();
 
         let waveform_spec = &mut self.waveforms[self.curr_waveform];
         let default_envelope = mem::replace(&mut waveform_spec.envelope, selected_envelope);
         let waveform = self.creator.create(&*waveform_spec);
+        let waveform_name = waveform_spec.name.clone();
         waveform_spec.envelope = default_envelope;
 
-        self.send(Message {
+        self.send(Message::Note {
             id,
             action: Action::Start {
                 waveform,
+                waveform_name,
                 pitch,
                 velocity: velocity.as_f64(),
             },
@@ -134,14 +174,14 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
 
     fn update_pitch(&mut self, id: S, _degree: i32, pitch: Pitch, _velocity: u8) {
         // Should we update the velocity as well?
-        self.send(Message {
+        self.send(Message::Note {
             id,
             action: Action::UpdatePitch { pitch },
         });
     }
 
     fn update_pressure(&mut self, id: S, pressure: u8) {
-        self.send(Message {
+        self.send(Message::Note {
             id,
             action: Action::UpdatePressure {
                 pressure: f64::from(pressure) / 127.0,
@@ -150,7 +190,7 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
     }
 
     fn stop(&mut self, id: S, velocity: u8) {
-        self.send(Message {
+        self.send(Message::Note {
             id,
             action: Action::Stop {
                 velocity: velocity.as_f64(),
@@ -158,10 +198,18 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
         });
     }
 
+    fn all_sound_off(&mut self) {
+        self.send(Message::AllSoundOff);
+    }
+
     fn program_change(&mut self, mut update_fn: Box<dyn FnMut(usize) -> usize + Send>) {
         self.curr_waveform = update_fn(self.curr_waveform).min(self.waveforms.len() - 1);
     }
 
+    fn num_programs(&self) -> usize {
+        self.waveforms.len()
+    }
+
     fn control_change(&mut self, _controller: u8, _value: u8) {}
 
     fn channel_pressure(&mut self, _pressure: u8) {}
@@ -178,6 +226,20 @@ impl<I: From<WaveformInfo> + Send, S: Send> Backend<S> for WaveformBackend<I, S>
 }
 
 impl<I, S> WaveformBackend<I, S> {
+    /// Replaces the waveforms, envelopes and templates with the ones of the most recently reloaded config. Sounding notes keep their waveforms.
+    fn apply_config_updates(&mut self) {
+        if let Some(config) = self.config_updates.try_iter().last() {
+            let (waveforms, envelope_names, creator) = unpack_config(config, self.seed);
+            self.curr_waveform = self.curr_waveform.min(waveforms.len() - 1);
+            if envelope_names != self.envelope_names {
+                self.curr_envelope = envelope_names.len();
+            }
+            self.waveforms = waveforms;
+            self.envelope_names = envelope_names;
+            self.creator = creator;
+        }
+    }
+
     fn send(&self, message: Message<S>) {
         self.messages
             .send(message)
@@ -197,14 +259,15 @@ pub struct WaveformSynth<S> {
     audio_in: Consumer<f64>,
 }
 
-struct Message<S> {
-    id: S,
-    action: Action,
+enum Message<S> {
+    Note { id: S, action: Action },
+    AllSoundOff,
 }
 
 enum Action {
     Start {
         waveform: Waveform<(WaveformProperties, LiveParameterStorage)>,
+        waveform_name: String,
         pitch: Pitch,
         velocity: f64,
     },
@@ -223,7 +286,10 @@ struct SynthState<S> {
     active: HashMap<ActiveWaveformId<S>, ActiveWaveform>,
     magnetron: Magnetron,
     last_id: u64,
+    max_voices: Option<usize>,
     audio_in_synchronized: bool,
+    input_processor: InputProcessor,
+    freezer: Freezer,
 }
 
 #[derive(Eq, Hash, PartialEq)]
@@ -235,6 +301,7 @@ enum ActiveWaveformId<S> {
 type ActiveWaveform = (
     Waveform<(WaveformProperties, LiveParameterStorage)>,
     WaveformProperties,
+    String,
 );
 
 impl<S: Eq + Hash + Send> AudioStage<((), LiveParameterStorage)> for WaveformSynth<S> {
@@ -246,6 +313,9 @@ impl<S: Eq + Hash + Send> AudioStage<((), LiveParameterStorage)> for WaveformSyn
         for message in self.messages.try_iter() {
             self.state.process_message(message)
         }
+        if let Some(max_voices) = self.state.max_voices {
+            self.state.limit_voices(max_voices);
+        }
 
         let mut context = (WaveformProperties::initial(0.0, 0.0), context.payload.1);
 
@@ -256,10 +326,22 @@ impl<S: Eq + Hash + Send> AudioStage<((), LiveParameterStorage)> for WaveformSyn
                 self.state.audio_in_synchronized = true;
                 println!("[INFO] Audio-in synchronized");
             }
+            let gain = context.1.input_gain();
+            let gate_threshold = context.1.input_gate_threshold();
+            let monitor_level = context.1.input_monitor_level();
+            let mut monitor_out = buffer.chunks_mut(2);
             self.state.magnetron.set_audio_in(|| {
                 let l = self.audio_in.pop().unwrap_or_default();
                 let r = self.audio_in.pop().unwrap_or_default();
-                l + r / 2.0
+                let input = self
+                    .state
+                    .input_processor
+                    .process((l + r) / 2.0, gain, gate_threshold);
+                if let Some([left, right]) = monitor_out.next() {
+                    *left += input * monitor_level;
+                    *right += input * monitor_level;
+                }
+                input
             });
         } else if self.state.audio_in_synchronized {
             self.state.audio_in_synchronized = false;
@@ -268,49 +350,338 @@ impl<S: Eq + Hash + Send> AudioStage<((), LiveParameterStorage)> for WaveformSyn
 
         let volume = LiveParameter::Volume.access(&context.1) / 16.0;
 
-        self.state.active.retain(|_, waveform| {
-            context.0 = waveform.1;
-            self.state.magnetron.write(&mut waveform.0, &context);
-            waveform.0.is_active
-        });
+        self.state
+            .freezer
+            .update(context.1.is_active(LiveParameter::Freeze));
+
+        if self.state.freezer.is_recording() {
+            // Render the held notes first s.t. they can be captured without the released ones
+            self.state
+                .render_voices(&mut context, |id| matches!(id, ActiveWaveformId::Stable(_)));
+            let magnetron = &self.state.magnetron;
+            let is_frozen =
+                self.state
+                    .freezer
+                    .record(magnetron.mix(), magnetron.mix_side(), buffer, volume);
+            self.state
+                .render_voices(&mut context, |id| matches!(id, ActiveWaveformId::Fading(_)));
+            if is_frozen {
+                // The held notes live on in the loop
+                self.state
+                    .active
+                    .retain(|id, _| matches!(id, ActiveWaveformId::Fading(_)));
+                println!("[INFO] Held notes frozen");
+            }
+        } else {
+            self.state.render_voices(&mut context, |_| true);
+            self.state.freezer.play(buffer, volume);
+        }
 
-        for (&out, target) in self.state.magnetron.mix().iter().zip(buffer.chunks_mut(2)) {
+        let magnetron = &self.state.magnetron;
+        for ((&mid, &side), target) in magnetron
+            .mix()
+            .iter()
+            .zip(magnetron.mix_side())
+            .zip(buffer.chunks_mut(2))
+        {
             if let [left, right] = target {
-                *left += out * volume;
-                *right += out * volume;
+                *left += (mid + side) * volume;
+                *right += (mid - side) * volume;
             }
         }
     }
 
     fn mute(&mut self) {}
+
+    fn set_quality(&mut self, quality: Quality) {
+        self.state.max_voices = quality.max_voices();
+    }
+}
+
+fn report_faulty_stage<T>(waveform: &Waveform<T>, faulty_stage: usize, waveform_name: &str) {
+    let stage_description = if faulty_stage < waveform.stages.len() {
+        format!("Stage {}", faulty_stage + 1)
+    } else {
+        "The envelope".to_owned()
+    };
+    println!("[ERROR] {stage_description} of waveform `{waveform_name}` produced NaN or infinite values. The voice has been muted.");
+}
+
+/// Attack time of the audio-in noise gate [s].
+const GATE_ATTACK_SECS: f64 = 0.001;
+
+/// Release time of the audio-in noise gate [s].
+const GATE_RELEASE_SECS: f64 = 0.05;
+
+/// Amplifies the audio-in signal and mutes it while its level is below the noise gate threshold.
+struct InputProcessor {
+    attack_coefficient: f64,
+    release_coefficient: f64,
+    envelope: f64,
+    gate_gain: f64,
+}
+
+impl InputProcessor {
+    fn new(sample_rate_hz: f64) -> Self {
+        let smoothing_coefficient = |time_secs: f64| (-1.0 / (time_secs * sample_rate_hz)).exp();
+        Self {
+            attack_coefficient: smoothing_coefficient(GATE_ATTACK_SECS),
+            release_coefficient: smoothing_coefficient(GATE_RELEASE_SECS),
+            envelope: 0.0,
+            gate_gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, input: f64, gain: f64, gate_threshold: Option<f64>) -> f64 {
+        let signal = input * gain;
+
+        let level = signal.abs();
+        self.envelope = smooth(
+            self.envelope,
+            level,
+            self.coefficient(level > self.envelope),
+        );
+
+        let target_gate_gain = match gate_threshold {
+            Some(threshold) if self.envelope < threshold => 0.0,
+            _ => 1.0,
+        };
+        self.gate_gain = smooth(
+            self.gate_gain,
+            target_gate_gain,
+            self.coefficient(target_gate_gain > self.gate_gain),
+        );
+
+        signal * self.gate_gain
+    }
+
+    fn coefficient(&self, is_rising: bool) -> f64 {
+        if is_rising {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        }
+    }
+}
+
+fn smooth(current: f64, target: f64, coefficient: f64) -> f64 {
+    target + coefficient * (current - target)
+}
+
+/// Length of the crossfade from the end to the beginning of a frozen loop [s].
+const FREEZE_CROSSFADE_SECS: f64 = 0.05;
+
+/// Captures the held notes into a loop s.t. they keep sounding without occupying any voices.
+///
+/// During the last samples of the recording, the held notes are crossfaded into the beginning of the loop. Thus, neither the transition from the live notes to the loop nor the loop point itself produces a click.
+struct Freezer {
+    /// Mid and side samples. The loop starts at `crossfade_len` and wraps around at the end of the buffer.
+    buffer: Vec<(f64, f64)>,
+    crossfade_len: usize,
+    is_switched_on: bool,
+    state: FreezeState,
+}
+
+#[derive(Clone, Copy)]
+enum FreezeState {
+    Idle,
+    Recording { position: usize },
+    Playing { position: usize },
+    FadingOut { position: usize, progress: usize },
+}
+
+impl Freezer {
+    fn new(loop_len: usize, crossfade_len: usize) -> Self {
+        Self {
+            buffer: vec![(0.0, 0.0); loop_len.max(crossfade_len) + crossfade_len],
+            crossfade_len,
+            is_switched_on: false,
+            state: FreezeState::Idle,
+        }
+    }
+
+    /// Starts a recording when the freeze switch is turned on and fades out the loop when it is turned off.
+    fn update(&mut self, is_switched_on: bool) {
+        let was_switched_on = mem::replace(&mut self.is_switched_on, is_switched_on);
+        self.state = match (self.state, is_switched_on) {
+            (FreezeState::Idle, true) if !was_switched_on => FreezeState::Recording { position: 0 },
+            (FreezeState::Recording { .. }, false) => FreezeState::Idle,
+            (FreezeState::Playing { position }, false) => FreezeState::FadingOut {
+                position,
+                progress: 0,
+            },
+            (state, _) => state,
+        };
+    }
+
+    fn is_recording(&self) -> bool {
+        matches!(self.state, FreezeState::Recording { .. })
+    }
+
+    /// Captures the held notes given by `mid` and `side` and replaces them in `output` with the content of the loop. Returns `true` when the recording is complete.
+    fn record(&mut self, mid: &[f64], side: &[f64], output: &mut [f64], volume: f64) -> bool {
+        for ((&mid, &side), target) in mid.iter().zip(side).zip(output.chunks_mut(2)) {
+            let (loop_mid, loop_side) = match self.state {
+                FreezeState::Recording { position } => {
+                    let crossfade_start = self.buffer.len() - self.crossfade_len;
+                    let sample = match position.checked_sub(crossfade_start) {
+                        None => (mid, side),
+                        Some(index) => {
+                            let fraction = index as f64 / self.crossfade_len as f64;
+                            let (head_mid, head_side) = self.buffer[index];
+                            (
+                                mid + fraction * (head_mid - mid),
+                                side + fraction * (head_side - side),
+                            )
+                        }
+                    };
+                    self.buffer[position] = sample;
+                    self.state = if position + 1 < self.buffer.len() {
+                        FreezeState::Recording {
+                            position: position + 1,
+                        }
+                    } else {
+                        FreezeState::Playing {
+                            position: self.crossfade_len,
+                        }
+                    };
+                    sample
+                }
+                _ => self.next_sample().unwrap_or_default(),
+            };
+            if let [left, right] = target {
+                let (mid, side) = (loop_mid - mid, loop_side - side);
+                *left += (mid + side) * volume;
+                *right += (mid - side) * volume;
+            }
+        }
+        matches!(self.state, FreezeState::Playing { .. })
+    }
+
+    fn play(&mut self, output: &mut [f64], volume: f64) {
+        for target in output.chunks_mut(2) {
+            let (mid, side) = match self.next_sample() {
+                Some(sample) => sample,
+                None => break,
+            };
+            if let [left, right] = target {
+                *left += (mid + side) * volume;
+                *right += (mid - side) * volume;
+            }
+        }
+    }
+
+    fn next_sample(&mut self) -> Option<(f64, f64)> {
+        match self.state {
+            FreezeState::Playing { position } => {
+                self.state = FreezeState::Playing {
+                    position: self.next_position(position),
+                };
+                Some(self.buffer[position])
+            }
+            FreezeState::FadingOut { position, progress } => {
+                let fade_out_len = self.crossfade_len.max(1);
+                if progress >= fade_out_len {
+                    self.state = FreezeState::Idle;
+                    return None;
+                }
+                self.state = FreezeState::FadingOut {
+                    position: self.next_position(position),
+                    progress: progress + 1,
+                };
+                let gain = 1.0 - progress as f64 / fade_out_len as f64;
+                let (mid, side) = self.buffer[position];
+                Some((mid * gain, side * gain))
+            }
+            FreezeState::Idle | FreezeState::Recording { .. } => None,
+        }
+    }
+
+    fn next_position(&self, position: usize) -> usize {
+        if position + 1 < self.buffer.len() {
+            position + 1
+        } else {
+            self.crossfade_len
+        }
+    }
+
+    fn clear(&mut self) {
+        self.state = FreezeState::Idle;
+    }
 }
 
 impl<S: Eq + Hash> SynthState<S> {
+    fn render_voices(
+        &mut self,
+        context: &mut (WaveformProperties, LiveParameterStorage),
+        mut is_selected: impl FnMut(&ActiveWaveformId<S>) -> bool,
+    ) {
+        let magnetron = &mut self.magnetron;
+        self.active.retain(|id, waveform| {
+            if !is_selected(id) {
+                return true;
+            }
+            context.0 = waveform.1;
+            magnetron.write(&mut waveform.0, context);
+            if let Some(faulty_stage) = waveform.0.faulty_stage {
+                report_faulty_stage(&waveform.0, faulty_stage, &waveform.2);
+            }
+            waveform.0.is_active
+        });
+    }
+
+    /// Cuts the release phase of the oldest released notes until at most `max_voices` voices are left. Held notes are never cut.
+    fn limit_voices(&mut self, max_voices: usize) {
+        while self.active.len() > max_voices {
+            let oldest_fading_id = self
+                .active
+                .keys()
+                .filter_map(|id| match id {
+                    ActiveWaveformId::Stable(_) => None,
+                    ActiveWaveformId::Fading(id) => Some(*id),
+                })
+                .min();
+            match oldest_fading_id {
+                Some(id) => self.active.remove(&ActiveWaveformId::Fading(id)),
+                None => break,
+            };
+        }
+    }
+
     fn process_message(&mut self, message: Message<S>) {
-        match message.action {
+        let (id, action) = match message {
+            Message::Note { id, action } => (id, action),
+            Message::AllSoundOff => {
+                self.active.clear();
+                self.freezer.clear();
+                return;
+            }
+        };
+        match action {
             Action::Start {
                 waveform,
+                waveform_name,
                 pitch,
                 velocity,
             } => {
                 let properties = WaveformProperties::initial(pitch.as_hz(), velocity);
-                self.active
-                    .insert(ActiveWaveformId::Stable(message.id), (waveform, properties));
+                self.active.insert(
+                    ActiveWaveformId::Stable(id),
+                    (waveform, properties, waveform_name),
+                );
             }
             Action::UpdatePitch { pitch } => {
-                if let Some(waveform) = self.active.get_mut(&ActiveWaveformId::Stable(message.id)) {
+                if let Some(waveform) = self.active.get_mut(&ActiveWaveformId::Stable(id)) {
                     waveform.1.pitch_hz = pitch.as_hz();
                 }
             }
             Action::UpdatePressure { pressure } => {
-                if let Some(waveform) = self.active.get_mut(&ActiveWaveformId::Stable(message.id)) {
+                if let Some(waveform) = self.active.get_mut(&ActiveWaveformId::Stable(id)) {
                     waveform.1.key_pressure = Some(pressure)
                 }
             }
             Action::Stop { velocity } => {
-                if let Some(mut waveform) =
-                    self.active.remove(&ActiveWaveformId::Stable(message.id))
-                {
+                if let Some(mut waveform) = self.active.remove(&ActiveWaveformId::Stable(id)) {
                     waveform.1.off_velocity = Some(velocity);
                     self.active
                         .insert(ActiveWaveformId::Fading(self.last_id), waveform);
@@ -327,3 +698,72 @@ pub struct WaveformInfo {
     pub envelope_name: String,
     pub is_default_envelope: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn gate_audio_in() {
+        let mut input_processor = InputProcessor::new(1000.0);
+
+        assert_approx_eq!(input_processor.process(0.25, 2.0, None), 0.5);
+
+        let quiet_input = (0..500)
+            .map(|_| input_processor.process(0.001, 1.0, Some(0.01)))
+            .last()
+            .unwrap();
+        assert!(quiet_input.abs() < 1e-4);
+
+        let loud_input = (0..10)
+            .map(|_| input_processor.process(0.5, 1.0, Some(0.01)))
+            .last()
+            .unwrap();
+        assert_approx_eq!(loud_input, 0.5, 1e-3);
+    }
+    #[test]
+    fn freeze_held_notes() {
+        let mut freezer = Freezer::new(8, 4);
+        freezer.update(true);
+        assert!(freezer.is_recording());
+
+        let mid = (0..16).map(f64::from).collect::<Vec<_>>();
+        let side = [0.0; 16];
+        let mut output = [0.0; 32];
+        assert!(freezer.record(&mid, &side, &mut output, 1.0));
+
+        let heard = output
+            .chunks(2)
+            .zip(&mid)
+            .map(|(frame, &mid)| frame[0] + mid)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            heard,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 7.0, 6.0, 5.0, 4.0, 5.0, 6.0, 7.0]
+        );
+
+        let mut output = [0.0; 12];
+        freezer.play(&mut output, 0.5);
+        assert_eq!(
+            output,
+            [4.0, 4.0, 3.5, 3.5, 3.0, 3.0, 2.5, 2.5, 2.0, 2.0, 2.5, 2.5]
+        );
+
+        freezer.update(false);
+        let mut output = [0.0; 12];
+        freezer.play(&mut output, 1.0);
+        assert_eq!(
+            output,
+            [6.0, 6.0, 5.25, 5.25, 4.0, 4.0, 1.75, 1.75, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert!(matches!(freezer.state, FreezeState::Idle));
+
+        freezer.update(true);
+        assert!(freezer.is_recording());
+        freezer.clear();
+        freezer.update(true);
+        assert!(!freezer.is_recording());
+    }
+}
@@ -1,10 +1,10 @@
-use std::{fmt::Debug, hash::Hash, mem};
+use std::{fmt::Debug, hash::Hash, mem, ops::Range};
 
 use tune::{
     note::Note,
-    pitch::{Pitch, Pitched},
+    pitch::{Pitch, Pitched, Ratio},
     scala::{KbmRoot, Scl},
-    tuner::{AotTuner, JitTuner, PoolingMode, TunableSynth},
+    tuner::{AotTuner, AotTuningModel, GroupBy, JitTuner, PoolingMode, TunableSynth, UntunableKey},
     tuning::{Scale, Tuning},
 };
 
@@ -12,17 +12,56 @@ use crate::keypress::{IllegalState, KeypressTracker, LiftAction, PlaceAction};
 
 pub struct TunableBackend<K, S> {
     tuner: Tuner<K, S>,
+    num_channels: usize,
+    group_by: GroupBy,
+    curr_tuning: Option<(Scl, KbmRoot)>,
+    single_channel_tuning: bool,
+    jit_clashes: JitClashes,
+}
+
+/// Notes that exceeded the available channels in just-in-time mode s.t. the oldest note had to be stopped.
+#[derive(Clone, Debug, Default)]
+pub struct JitClashes {
+    /// Number of clashes since the just-in-time tuning was started.
+    pub count: usize,
+
+    /// The degree, note and detuning of the most recent note that exceeded the available channels.
+    pub last: Option<(i32, Note, Ratio)>,
 }
 
 impl<K, S: TunableSynth> TunableBackend<K, S> {
     pub fn new(synth: S) -> Self {
         Self {
+            num_channels: synth.num_channels(),
+            group_by: synth.group_by(),
             tuner: Tuner::Aot {
                 aot_tuner: AotTuner::start(synth),
                 keypress_tracker: KeypressTracker::new(),
             },
+            curr_tuning: None,
+            single_channel_tuning: false,
+            jit_clashes: JitClashes::default(),
         }
     }
+
+    /// Tunes every key exactly on a single channel if the tuning fits into 128 notes. Otherwise, the keys are distributed across multiple channels.
+    pub fn with_single_channel_tuning(mut self) -> Self {
+        self.single_channel_tuning = true;
+        self
+    }
+}
+
+/// The keys of the given tuning that are tuned ahead of time, i.e. the keys whose pitches lie around the MIDI range.
+fn keys_in_midi_range(tuning: (&Scl, KbmRoot)) -> Range<i32> {
+    let lowest_key = tuning
+        .find_by_pitch_sorted(Note::from_midi_number(-1).pitch())
+        .approx_value;
+
+    let highest_key = tuning
+        .find_by_pitch_sorted(Note::from_midi_number(128).pitch())
+        .approx_value;
+
+    lowest_key..highest_key
 }
 
 enum Tuner<K, S> {
@@ -41,29 +80,38 @@ where
     S::Result: Debug,
 {
     pub fn set_tuning(&mut self, tuning: (&Scl, KbmRoot)) {
-        let synth = self.destroy_tuning();
-
-        let lowest_key = tuning
-            .find_by_pitch_sorted(Note::from_midi_number(-1).pitch())
-            .approx_value;
+        // Avoid recomputing the tuning and interrupting sounding notes if nothing has changed
+        if self.has_tuning(tuning) {
+            return;
+        }
 
-        let highest_key = tuning
-            .find_by_pitch_sorted(Note::from_midi_number(128).pitch())
-            .approx_value;
+        let synth = self.destroy_tuning();
+        self.curr_tuning = Some((tuning.0.clone(), tuning.1));
 
         let mut aot_tuner = AotTuner::start(synth);
 
+        let keys = keys_in_midi_range(tuning);
         let tuning = Tuning::<i32>::as_linear_mapping(tuning);
-        let keys = lowest_key..highest_key;
 
-        match aot_tuner.set_tuning(tuning, keys) {
-            Ok(required_channels) => {
-                if !aot_tuner.tuned() {
-                    eprintln!("[WARNING] Cannot apply tuning. The tuning requires {required_channels} channels");
+        let tuned_on_single_channel = self.single_channel_tuning
+            && match aot_tuner.set_single_channel_tuning(&tuning, keys.clone()) {
+                Ok(tuned) => tuned,
+                Err(err) => {
+                    eprintln!("[WARNING] Cannot apply single-channel tuning: {err:?}");
+                    false
+                }
+            };
+
+        if !tuned_on_single_channel {
+            match aot_tuner.set_tuning(tuning, keys) {
+                Ok(required_channels) => {
+                    if !aot_tuner.tuned() {
+                        eprintln!("[WARNING] Cannot apply tuning. The tuning requires {required_channels} channels");
+                    }
+                }
+                Err(err) => {
+                    eprintln!("[WARNING] Cannot apply tuning: {err:?}");
                 }
-            }
-            Err(err) => {
-                eprintln!("[WARNING] Cannot apply tuning: {err:?}");
             }
         }
 
@@ -73,12 +121,34 @@ where
         };
     }
 
+    /// Returns `true` if the given tuning can be applied ahead of time without exceeding the available channels.
+    ///
+    /// Single-channel tuning is not taken into account.
+    pub fn tuning_fits(&self, tuning: (&Scl, KbmRoot)) -> bool {
+        let (_, channel_detunings) = AotTuningModel::apply_tuning(
+            self.group_by,
+            Tuning::<i32>::as_linear_mapping(tuning),
+            keys_in_midi_range(tuning),
+        );
+        channel_detunings.len() <= self.num_channels
+    }
+
     pub fn set_no_tuning(&mut self) {
         let synth = self.destroy_tuning();
+        self.curr_tuning = None;
+        self.jit_clashes = JitClashes::default();
         let jit_tuner = JitTuner::start(synth, PoolingMode::Stop);
         self.tuner = Tuner::Jit { jit_tuner };
     }
 
+    /// Returns `true` if the given ahead-of-time tuning is already applied.
+    pub fn has_tuning(&self, tuning: (&Scl, KbmRoot)) -> bool {
+        match (&self.tuner, &self.curr_tuning) {
+            (Tuner::Aot { .. }, Some((scl, kbm_root))) => scl == tuning.0 && *kbm_root == tuning.1,
+            _ => false,
+        }
+    }
+
     pub fn is_tuned(&self) -> bool {
         match &self.tuner {
             Tuner::Destroyed => false,
@@ -87,11 +157,33 @@ where
         }
     }
 
-    pub fn start(&mut self, id: K, degree: i32, pitch: Pitch, velocity: S::NoteAttr) {
+    /// Returns the degrees that could not be tuned because the tuning requires more channels than available.
+    pub fn untunable_degrees(&self) -> &[UntunableKey<i32>] {
+        match &self.tuner {
+            Tuner::Destroyed | Tuner::Jit { .. } => &[],
+            Tuner::Aot { aot_tuner, .. } => aot_tuner.untunable_keys(),
+        }
+    }
+
+    /// Returns the notes that exceeded the available channels since the just-in-time tuning was started.
+    pub fn jit_clashes(&self) -> &JitClashes {
+        &self.jit_clashes
+    }
+
+    /// Returns `true` if the note exceeded the available channels s.t. the oldest note had to be stopped.
+    pub fn start(&mut self, id: K, degree: i32, pitch: Pitch, velocity: S::NoteAttr) -> bool {
         match &mut self.tuner {
             Tuner::Destroyed => {}
             Tuner::Jit { jit_tuner } => {
+                let exhausted = jit_tuner.channels_exhausted(pitch);
+                if exhausted {
+                    let approximation = pitch.find_in_tuning(());
+                    self.jit_clashes.count += 1;
+                    self.jit_clashes.last =
+                        Some((degree, approximation.approx_value, approximation.deviation));
+                }
                 jit_tuner.note_on(id, pitch, velocity);
+                return exhausted;
             }
             Tuner::Aot {
                 keypress_tracker,
@@ -109,6 +201,7 @@ where
                 }
             },
         }
+        false
     }
 
     pub fn update_pitch(&mut self, id: K, degree: i32, pitch: Pitch, velocity: S::NoteAttr) {
@@ -170,6 +263,22 @@ where
         }
     }
 
+    /// Releases all held notes while keeping the current tuning.
+    pub fn release_all(&mut self) {
+        match self.curr_tuning.take() {
+            // Reapplying the tuning releases the held notes of the previous tuner
+            Some((scl, kbm_root)) => self.set_tuning((&scl, kbm_root)),
+            None if self.is_aot() => {
+                let synth = self.destroy_tuning();
+                self.tuner = Tuner::Aot {
+                    aot_tuner: AotTuner::start(synth),
+                    keypress_tracker: KeypressTracker::new(),
+                };
+            }
+            None => self.set_no_tuning(),
+        }
+    }
+
     pub fn send_monophonic_message(&mut self, message_type: S::GlobalAttr) {
         match &mut self.tuner {
             Tuner::Destroyed => {}
@@ -325,6 +434,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tunable_backend_report_jit_clashes() {
+        let synth = FakeSynth {
+            state: Rc::new(RefCell::new(CapturedState::default())),
+        };
+
+        let mut backend = TunableBackend::<usize, _>::new(synth);
+        backend.set_no_tuning();
+
+        let pitch = Note::from_midi_number(60).pitch() * Ratio::from_cents(25.0);
+        for id in 0..8 {
+            assert!(!backend.start(id, 0, pitch, ()));
+        }
+        assert_eq!(backend.jit_clashes().count, 0);
+
+        assert!(backend.start(8, 3, pitch, ()));
+        let jit_clashes = backend.jit_clashes();
+        assert_eq!(jit_clashes.count, 1);
+        let (degree, note, detuning) = jit_clashes.last.unwrap();
+        assert_eq!(degree, 3);
+        assert_eq!(note, Note::from_midi_number(60));
+        assert_approx_eq!(detuning.as_cents(), 25.0);
+
+        backend.set_no_tuning();
+        assert_eq!(backend.jit_clashes().count, 0);
+    }
+
+    #[test]
+    fn tunable_backend_check_if_tuning_fits() {
+        let synth = FakeSynth {
+            state: Rc::new(RefCell::new(CapturedState::default())),
+        };
+
+        let backend = TunableBackend::<usize, _>::new(synth);
+        let kbm_root = KbmRoot::from(NoteLetter::D.in_octave(4));
+
+        // 16-EDO has 4 different deviations from 12-EDO
+        let scl = Scl::builder().push_cents(75.0).build().unwrap();
+        assert!(backend.tuning_fits((&scl, kbm_root)));
+
+        // 31-EDO has more than 8 different deviations from 12-EDO
+        let scl = Scl::builder().push_cents(1200.0 / 31.0).build().unwrap();
+        assert!(!backend.tuning_fits((&scl, kbm_root)));
+    }
+
     fn create_non_monotonous_tuning() -> (Scl, KbmRoot) {
         let scl = Scl::builder()
             .push_cents(100.0)
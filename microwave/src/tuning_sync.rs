@@ -0,0 +1,98 @@
+use std::sync::{mpsc::Sender, Arc, Mutex};
+
+use tune::{
+    pitch::Pitch,
+    scala::{Kbm, KbmRoot, Scl},
+};
+use tune_cli::{shared::sync::TuningSync, CliResult};
+
+use crate::piano::Backend;
+
+/// Publishes every tuning change of the engine, including the full keyboard mapping, to the peers of a [`TuningSync`].
+///
+/// The tuning is published in continuous tuning mode as well s.t. the peers always share the current scale.
+pub struct TuningSyncBackend {
+    tuning_sync: TuningSync,
+    kbm: Option<Kbm>,
+    last_tuning: Arc<Mutex<Option<(Scl, Kbm)>>>,
+}
+
+/// Creates a [`TuningSyncBackend`] and forwards all tunings received from other instances to `updates`.
+pub fn create(
+    tuning_sync: TuningSync,
+    updates: Sender<(Scl, Kbm)>,
+) -> CliResult<TuningSyncBackend> {
+    println!(
+        "[INFO] Synchronizing tuning via {}",
+        tuning_sync.local_addr()?
+    );
+
+    let last_tuning = Arc::new(Mutex::new(None));
+
+    let received_tuning = last_tuning.clone();
+    tuning_sync.try_clone()?.listen(move |scl, kbm| {
+        // Remember the received tuning s.t. it is not echoed back to the peers once applied
+        *received_tuning.lock().unwrap() = Some((scl.clone(), kbm.clone()));
+        let _ = updates.send((scl, kbm));
+    });
+
+    Ok(TuningSyncBackend {
+        tuning_sync,
+        kbm: None,
+        last_tuning,
+    })
+}
+
+impl<S> Backend<S> for TuningSyncBackend {
+    fn set_kbm(&mut self, kbm: &Kbm) {
+        if self.kbm.as_ref() != Some(kbm) {
+            self.kbm = Some(kbm.clone());
+        }
+    }
+
+    fn set_tuning(&mut self, (scl, kbm_root): (&Scl, KbmRoot)) {
+        let mut kbm = self.kbm.clone().unwrap_or_else(|| kbm_root.to_kbm());
+        kbm.set_kbm_root(kbm_root);
+
+        let mut last_tuning = self.last_tuning.lock().unwrap();
+        if let Some((last_scl, last_kbm)) = &*last_tuning {
+            if last_scl == scl && *last_kbm == kbm {
+                return;
+            }
+        }
+        self.tuning_sync.publish((scl, &kbm));
+        *last_tuning = Some((scl.clone(), kbm));
+    }
+
+    fn set_no_tuning(&mut self) {}
+
+    fn send_status(&mut self) {}
+
+    fn start(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pitch(&mut self, _id: S, _degree: i32, _pitch: Pitch, _velocity: u8) {}
+
+    fn update_pressure(&mut self, _id: S, _pressure: u8) {}
+
+    fn stop(&mut self, _id: S, _velocity: u8) {}
+
+    fn all_sound_off(&mut self) {}
+
+    fn program_change(&mut self, _update_fn: Box<dyn FnMut(usize) -> usize + Send>) {}
+
+    fn control_change(&mut self, _controller: u8, _value: u8) {}
+
+    fn channel_pressure(&mut self, _pressure: u8) {}
+
+    fn pitch_bend(&mut self, _value: i16) {}
+
+    fn toggle_envelope_type(&mut self) {}
+
+    fn has_legato(&self) -> bool {
+        true
+    }
+
+    fn publishes_tuning(&self) -> bool {
+        true
+    }
+}
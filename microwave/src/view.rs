@@ -7,17 +7,23 @@ use geom::Range as NannouRange;
 use nannou::prelude::*;
 use nannou::color::rgb_u32;
 use tune::{
-    note::Note,
+    note::{Note, NoteNames},
     pitch::{Pitch, Pitched, Ratio},
     scala::KbmRoot,
+    tuner::UntunableKey,
     tuning::Scale,
 };
-use tune_cli::shared::midi::TuningMethod;
+use tune_cli::shared::midi::{self, TuningMethod};
 
 use crate::{
-    control::LiveParameter, fluid::FluidInfo, midi::MidiInfo, synth::WaveformInfo, KeyColor, Model,
+    control::LiveParameter, csound::CsoundInfo, fluid::FluidInfo, governor::Quality,
+    history::HistoryMode, midi::MidiInfo, pad, roughness, scope, sfz::SfzInfo, spectrogram,
+    supercollider::ScInfo, synth::WaveformInfo, tunable::JitClashes, KeyColor, Model,
 };
 
+#[cfg(feature = "mts-esp")]
+use crate::mts_esp::MtsEspInfo;
+
 pub trait ViewModel: Send + 'static {
     fn pitch_range(&self) -> Option<Range<Pitch>>;
 
@@ -42,27 +48,47 @@ pub fn view(app: &App, model: &Model, frame: Frame) {
 
     let kbm_root = model.kbm.kbm_root();
     let selected_tuning = (&model.scl, kbm_root);
-    let reference_tuning = (
-        &model.reference_scl,
-        KbmRoot::from(Note::from_piano_key(kbm_root.ref_key)),
-    );
-
-    let keyboard_rect = Rect::from_w_h(window_rect.w(), window_rect.h() / 4.0);
-    let lower_keyboard_rect = keyboard_rect.align_bottom_of(window_rect);
 
     draw.background().color(rgb_u32(0x2E3440));
+    render_spectrogram(model, &draw, window_rect);
     render_scale_lines(model, &draw, window_rect, octave_width, selected_tuning);
-    render_keyboard(
-        model,
-        &draw,
-        lower_keyboard_rect,
-        octave_width,
-        reference_tuning,
-        |key| get_12edo_key_color(key + kbm_root.ref_key.midi_number()),
-    );
+    render_pitch_history(app, model, &draw, window_rect, octave_width);
+
+    let mut strip_bottom = window_rect.bottom();
+    for strip in &model.keyboard_strips {
+        let strip_height = window_rect.h() * strip.height;
+        let strip_rect = Rect::from_x_y_w_h(
+            window_rect.x(),
+            strip_bottom + strip_height / 2.0,
+            window_rect.w(),
+            strip_height,
+        );
+        strip_bottom += strip_height;
+
+        let tuning = match &strip.scl {
+            Some(scl) => (scl, KbmRoot::from(Note::from_piano_key(kbm_root.ref_key))),
+            None => selected_tuning,
+        };
+
+        render_keyboard(model, &draw, strip_rect, octave_width, tuning, |key| {
+            match strip.colors.as_ref().filter(|colors| !colors.is_empty()) {
+                Some(colors) => colors[key.rem_euclid(colors.len() as i32) as usize],
+                None => get_12edo_key_color(key + kbm_root.ref_key.midi_number()),
+            }
+        });
+    }
 
     render_just_ratios_with_deviations(model, &draw, window_rect, octave_width);
     render_recording_indicator(model, &draw, window_rect);
+    render_scope(model, &draw, window_rect);
+    let pad_rects = pad::layout(app.window_rect(), &model.pads);
+    render_pads(model, &draw, &pad_rects);
+    // The HUD starts below the pads s.t. they do not overlap
+    let hud_top = pad_rects
+        .iter()
+        .map(|rect| rect.bottom() - 10.0)
+        .fold(window_rect.top(), f32::min);
+    render_hud(model, &draw, window_rect, hud_top);
     draw.to_frame(app, &frame).unwrap();
 }
 
@@ -73,18 +99,11 @@ fn render_scale_lines(
     octave_width: f32,
     tuning: impl Scale,
 ) {
-    let leftmost_degree = tuning
-        .find_by_pitch_sorted(model.pitch_at_left_border)
-        .approx_value;
-    let rightmost_degree = tuning
-        .find_by_pitch_sorted(model.pitch_at_right_border)
-        .approx_value;
-
     let pitch_range = model.view_model.as_ref().and_then(|m| m.pitch_range());
 
-    for degree in leftmost_degree..=rightmost_degree {
-        let pitch = tuning.sorted_pitch_of(degree);
-
+    for (degree, pitch) in
+        tuning.sorted_pitches_within(model.pitch_at_left_border..model.pitch_at_right_border)
+    {
         let pitch_position = Ratio::between_pitches(model.pitch_at_left_border, pitch).as_octaves()
             as f32
             * octave_width;
@@ -96,7 +115,12 @@ fn render_scale_lines(
             Some(_) => GRAY,
         };
 
+        // The root follows the played notes. Hence, it is highlighted in every period.
+        let is_moving_root = model.storage.is_active(LiveParameter::MovableRoot)
+            && degree.rem_euclid(model.scl.num_items().into()) == 0;
+
         let line_color = match degree {
+            _ if is_moving_root => rgb_u32(0xEBCB8B),
             0 => rgb_u32(0x434C5E),
             _ => rgb_u32(0x434C5E),
         };
@@ -113,6 +137,93 @@ fn render_scale_lines(
     }
 }
 
+/// Renders the spectrogram rows from the bottom (newest) to the top (oldest). The bins share the pitch axis of the scale lines.
+fn render_spectrogram(model: &Model, draw: &Draw, window_rect: Rect) {
+    if !model.spectrogram.visible {
+        return;
+    }
+
+    let bin_width = window_rect.w() / spectrogram::NUM_BINS as f32;
+    let row_height = window_rect.h() / spectrogram::NUM_ROWS as f32;
+
+    for (row_index, row) in model.spectrogram.rows().enumerate() {
+        let y = window_rect.bottom() + (row_index as f32 + 0.5) * row_height;
+        for (bin_index, &level) in row.iter().enumerate() {
+            // Skip silent bins to keep the number of rendered rects low
+            if level < 0.05 {
+                continue;
+            }
+            let x = window_rect.left() + (bin_index as f32 + 0.5) * bin_width;
+            draw.rect()
+                .x_y(x, y)
+                .w_h(bin_width, row_height)
+                .color(rgba(0.53, 0.75, 0.82, level));
+        }
+    }
+}
+
+/// Renders the recent notes as vertical bars at their exact pitch. The current time is at the bottom, older notes scroll upwards.
+fn render_pitch_history(
+    app: &App,
+    model: &Model,
+    draw: &Draw,
+    window_rect: Rect,
+    octave_width: f32,
+) {
+    let history = &model.pitch_history;
+    if history.mode == HistoryMode::Hidden {
+        return;
+    }
+
+    let now_secs = app.time;
+    let ref_pitch = model.kbm.kbm_root().ref_pitch;
+    let time_to_y = |secs: f32| {
+        window_rect.bottom() + (now_secs - secs) / history.time_span_secs() * window_rect.h()
+    };
+
+    for segment in history.segments() {
+        let pitch_position = Ratio::between_pitches(model.pitch_at_left_border, segment.pitch)
+            .as_octaves() as f32
+            * octave_width;
+
+        let pitch_position_on_screen = (pitch_position - 0.5) * window_rect.w();
+
+        let bottom = time_to_y(segment.end_secs.unwrap_or(now_secs));
+        let top = time_to_y(segment.start_secs).min(window_rect.top());
+        let bar_rect = Rect {
+            x: NannouRange::from_pos_and_len(pitch_position_on_screen, 4.0),
+            y: NannouRange::new(bottom, top),
+        };
+
+        draw.rect()
+            .xy(bar_rect.xy())
+            .wh(bar_rect.wh())
+            .color(rgb_u32(0x88C0D0));
+
+        if history.mode == HistoryMode::VisibleWithRatios && top < window_rect.top() {
+            let approximation =
+                Ratio::between_pitches(ref_pitch, segment.pitch).nearest_fraction(model.odd_limit);
+
+            let text_rect = Rect::from_w_h(100.0, 16.0)
+                .mid_left_of(bar_rect)
+                .shift_x(bar_rect.w() + 4.0)
+                .shift_y(top - bar_rect.y());
+
+            draw.text(&format!(
+                "{}/{} [{:+.0}c]",
+                approximation.numer,
+                approximation.denom,
+                approximation.deviation.as_cents()
+            ))
+            .xy(text_rect.xy())
+            .wh(text_rect.wh())
+            .left_justify()
+            .color(rgb_u32(0xD8DEE9))
+            .font_size(12);
+        }
+    }
+}
+
 fn render_just_ratios_with_deviations(
     model: &Model,
     draw: &Draw,
@@ -213,8 +324,7 @@ fn render_keyboard(
 
     let (mut mid, mut right) = Default::default();
 
-    for iterated_key in (leftmost_key - 1)..=(rightmost_key + 1) {
-        let pitch = tuning.sorted_pitch_of(iterated_key);
+    for (iterated_key, pitch) in tuning.sorted_pitches((leftmost_key - 1)..(rightmost_key + 2)) {
         let coord = Ratio::between_pitches(model.pitch_at_left_border, pitch).as_octaves() as f32
             * octave_width;
 
@@ -276,6 +386,203 @@ fn render_recording_indicator(model: &Model, draw: &Draw, window_rect: Rect) {
     }
 }
 
+/// Renders the oscilloscope (left) and the spectrum analyzer (right) in a pane at the top of the window.
+fn render_scope(model: &Model, draw: &Draw, window_rect: Rect) {
+    if !model.scope.visible {
+        return;
+    }
+
+    let pane_rect = Rect::from_w_h(window_rect.w() / 2.0, 160.0).mid_top_of(window_rect);
+    let waveform_rect = Rect::from_w_h(pane_rect.w() / 2.0, pane_rect.h()).top_left_of(pane_rect);
+    let spectrum_rect = Rect::from_w_h(pane_rect.w() / 2.0, pane_rect.h()).top_right_of(pane_rect);
+
+    for rect in [waveform_rect, spectrum_rect] {
+        draw.rect()
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(rgb_u32(0x3B4252))
+            .stroke(rgb_u32(0x4C566A))
+            .stroke_weight(1.0);
+    }
+
+    let waveform = model.scope.waveform();
+    let sample_width = waveform_rect.w() / scope::NUM_WAVEFORM_SAMPLES as f32;
+    draw.polyline()
+        .weight(1.5)
+        .points(waveform.iter().enumerate().map(|(index, &sample)| {
+            Point2::new(
+                waveform_rect.left() + index as f32 * sample_width,
+                waveform_rect.y() + sample.clamp(-1.0, 1.0) * waveform_rect.h() / 2.0,
+            )
+        }))
+        .color(rgb_u32(0xA3BE8C));
+
+    let bar_width = spectrum_rect.w() / scope::NUM_SPECTRUM_BINS as f32;
+    for (bar_index, &level) in model.scope.spectrum().iter().enumerate() {
+        let bar_height = level * spectrum_rect.h();
+        draw.rect()
+            .x_y(
+                spectrum_rect.left() + (bar_index as f32 + 0.5) * bar_width,
+                spectrum_rect.bottom() + bar_height / 2.0,
+            )
+            .w_h(bar_width, bar_height)
+            .color(rgb_u32(0x88C0D0));
+    }
+}
+
+fn render_pads(model: &Model, draw: &Draw, pad_rects: &[Rect]) {
+    for (pad, &rect) in model.pads.iter().zip(pad_rects) {
+        draw.rect()
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(rgb_u32(0x3B4252))
+            .stroke(rgb_u32(0x81A1C1))
+            .stroke_weight(1.0);
+
+        let x = rect.left() + model.storage.read_parameter(pad.x) as f32 * rect.w();
+        let (y, label) = match pad.y {
+            Some(y_parameter) => (
+                rect.bottom() + model.storage.read_parameter(y_parameter) as f32 * rect.h(),
+                format!("{:?} / {:?}", pad.x, y_parameter),
+            ),
+            None => (rect.y(), format!("{:?}", pad.x)),
+        };
+
+        draw.ellipse()
+            .x_y(x, y)
+            .radius(6.0)
+            .color(rgb_u32(0x88C0D0));
+        draw.text(&label)
+            .xy(rect.xy())
+            .wh(rect.pad(4.0).wh())
+            .left_justify()
+            .align_text_top()
+            .color(rgb_u32(0xD8DEE9))
+            .font_size(12);
+    }
+}
+
+fn render_hud(model: &Model, draw: &Draw, window_rect: Rect, top: f32) {
+    let pitch_bend = model
+        .pitch_bend_range
+        .repeated(model.storage.read_parameter(LiveParameter::PitchBend));
+    let average_step_size = model
+        .scl
+        .period()
+        .divided_into_equal_steps(model.scl.num_items());
+
+    let mut hud_text = format!(
+        "Pitch bend: {:+.1}c ({:.4}) = {:+.2} degrees\n\
+         Modulation: {:.0}%",
+        pitch_bend.as_cents(),
+        pitch_bend.as_float(),
+        pitch_bend.num_equal_steps_of_size(average_step_size),
+        model.storage.read_parameter(LiveParameter::Modulation) * 100.0,
+    );
+
+    let pitches: Vec<_> = model
+        .pressed_keys
+        .values()
+        .map(|pressed_key| pressed_key.pitch)
+        .collect();
+    let roughness = roughness::roughness(&pitches, model.num_partials);
+    write!(hud_text, "\nRoughness: {roughness:.2}").unwrap();
+
+    write_pressed_notes(&mut hud_text, &pitches, model.note_names).unwrap();
+
+    let num_scenes = model.cues.num_scenes();
+    if num_scenes > 0 {
+        match model.cues.curr_scene() {
+            Some((index, scene)) => write!(
+                hud_text,
+                "\nCue [PgUp/PgDn]: {}/{num_scenes} - {}",
+                index + 1,
+                scene.name
+            ),
+            None => write!(hud_text, "\nCue [PgUp/PgDn]: -/{num_scenes}"),
+        }
+        .unwrap();
+    }
+
+    if model.storage.is_active(LiveParameter::Freeze) {
+        write!(hud_text, "\nFreeze [Alt+F]: On").unwrap();
+    }
+
+    if model.storage.is_active(LiveParameter::Drone) {
+        write!(hud_text, "\nDrone [Alt+D]: On").unwrap();
+    }
+
+    if model.storage.is_active(LiveParameter::MovableRoot) {
+        write!(hud_text, "\nMovable root [Alt+R]: On").unwrap();
+    }
+
+    let load_status = model.audio.load_status();
+    let quality = load_status.quality();
+    if quality != Quality::Full {
+        write!(
+            hud_text,
+            "\nQuality: {quality:?} (audio load {}%)",
+            load_status.load_percent()
+        )
+        .unwrap();
+    }
+
+    let hud_rect = Rect::from_w_h(400.0, 120.0)
+        .top_left_of(window_rect)
+        .shift_y(top - window_rect.top());
+    draw.text(&hud_text)
+        .xy(hud_rect.xy())
+        .wh(hud_rect.wh())
+        .left_justify()
+        .align_text_top()
+        .color(rgb_u32(0xD8DEE9))
+        .font_size(16);
+
+    // Roughness meter. A full bar corresponds to the roughest pair of sine waves, on average over all pairs of held notes
+    let meter_rect = Rect::from_w_h(200.0, 8.0)
+        .below(hud_rect)
+        .align_left_of(hud_rect);
+    let level_rect = Rect::from_w_h(meter_rect.w() * roughness.min(1.0) as f32, meter_rect.h())
+        .align_left_of(meter_rect)
+        .align_middle_y_of(meter_rect);
+    draw.rect()
+        .xy(meter_rect.xy())
+        .wh(meter_rect.wh())
+        .color(rgb_u32(0x3B4252));
+    draw.rect()
+        .xy(level_rect.xy())
+        .wh(level_rect.wh())
+        .color(rgb_u32(0xBF616A));
+}
+
+/// Maximum number of pressed notes listed s.t. the HUD still fits on the screen.
+const MAX_LISTED_PRESSED_NOTES: usize = 6;
+
+/// Lists the nearest 12-EDO note and the deviation of each pressed pitch, from low to high.
+fn write_pressed_notes(
+    target: &mut String,
+    pitches: &[Pitch],
+    note_names: NoteNames,
+) -> fmt::Result {
+    let mut pitches = pitches.to_vec();
+    pitches.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    write!(target, "\nNotes:")?;
+    for pitch in pitches.iter().take(MAX_LISTED_PRESSED_NOTES) {
+        let approximation = pitch.find_in_tuning(());
+        write!(
+            target,
+            " {} ({:+.0}c)",
+            approximation.approx_value.named(note_names),
+            approximation.deviation.as_cents()
+        )?;
+    }
+    if pitches.len() > MAX_LISTED_PRESSED_NOTES {
+        write!(target, " ...")?;
+    }
+    Ok(())
+}
+
 fn get_12edo_key_color(key: i32) -> KeyColor {
     if [1, 3, 6, 8, 10].contains(&key.rem_euclid(12)) {
         KeyColor::Black
@@ -323,15 +630,28 @@ impl ViewModel for FluidInfo {
             "Output [Alt+O]: Soundfont\n\
              Soundfont File: {soundfont_file}\n\
              Tuning method: {tuning_method}\n\
+             Bank [Ctrl+Up/Down]: {bank}\n\
              Program [Up/Down]: {program_number} - {program_name}",
             soundfont_file = self.soundfont_file_location.as_deref().unwrap_or("Unknown"),
+            bank = self.bank,
             program_number = self
                 .program
                 .map(|p| p.to_string())
                 .as_deref()
                 .unwrap_or("Unknown"),
             program_name = self.program_name.as_deref().unwrap_or("Unknown"),
-        )
+        )?;
+
+        if !self.programs.is_empty() {
+            writeln!(target, "Programs in bank {}:", self.bank)?;
+            for (program, program_name) in &self.programs {
+                let marker = if Some(*program) == self.program { '>' } else { ' ' };
+                writeln!(target, "{marker} {program:>3} - {program_name}")?;
+            }
+        }
+
+        write_untunable_degrees(target, &self.untunable_degrees)?;
+        write_jit_clashes(target, &self.jit_clashes)
     }
 }
 
@@ -342,15 +662,13 @@ impl ViewModel for MidiInfo {
 
     fn write_info(&self, target: &mut String) -> fmt::Result {
         let tuning_method = match self.tuning_method {
-            Some(TuningMethod::FullKeyboard) => "Single Note Tuning Change",
-            Some(TuningMethod::FullKeyboardRt) => "Single Note Tuning Change (realtime)",
-            Some(TuningMethod::Octave1) => "Scale/Octave Tuning (1-Byte)",
-            Some(TuningMethod::Octave1Rt) => "Scale/Octave Tuning (1-Byte) (realtime)",
-            Some(TuningMethod::Octave2) => "Scale/Octave Tuning (2-Byte)",
-            Some(TuningMethod::Octave2Rt) => "Scale/Octave Tuning (2-Byte) (realtime)",
-            Some(TuningMethod::ChannelFineTuning) => "Channel Fine Tuning",
-            Some(TuningMethod::PitchBend) => "Pitch Bend",
-            None => "None. Tuning channels exceeded! Change tuning mode.",
+            Some(tuning_method) if tuning_method != self.requested_tuning_method => format!(
+                "{} - {}",
+                tuning_method_name(self.requested_tuning_method),
+                tuning_method_name(tuning_method)
+            ),
+            Some(tuning_method) => tuning_method_name(tuning_method).to_owned(),
+            None => "None. Tuning channels exceeded! Change tuning mode.".to_owned(),
         };
 
         writeln!(
@@ -361,6 +679,83 @@ impl ViewModel for MidiInfo {
              Program [Up/Down]: {program_number}",
             device = self.device,
             program_number = self.program_number,
+        )?;
+
+        write_untunable_degrees(target, &self.untunable_degrees)?;
+        write_jit_clashes(target, &self.jit_clashes)
+    }
+}
+
+#[cfg(feature = "mts-esp")]
+impl ViewModel for MtsEspInfo {
+    fn pitch_range(&self) -> Option<Range<Pitch>> {
+        Some(Note::from_midi_number(0).pitch()..Note::from_midi_number(127).pitch())
+    }
+
+    fn write_info(&self, target: &mut String) -> fmt::Result {
+        let tuning = match self.is_tuned {
+            true => "Published to all MTS-ESP clients",
+            false => "12-EDO. Change tuning mode to publish the scale.",
+        };
+
+        writeln!(
+            target,
+            "Output [Alt+O]: MTS-ESP master (no audio)\n\
+             Tuning: {tuning}"
+        )
+    }
+}
+
+impl ViewModel for ScInfo {
+    fn pitch_range(&self) -> Option<Range<Pitch>> {
+        None
+    }
+
+    fn write_info(&self, target: &mut String) -> fmt::Result {
+        writeln!(
+            target,
+            "Output [Alt+O]: SuperCollider\n\
+             Server: {server_address}\n\
+             SynthDef [Up/Down]: {program_number} - {synth_def}",
+            server_address = self.server_address,
+            program_number = self.program_number,
+            synth_def = self.synth_def,
+        )
+    }
+}
+
+impl ViewModel for CsoundInfo {
+    fn pitch_range(&self) -> Option<Range<Pitch>> {
+        None
+    }
+
+    fn write_info(&self, target: &mut String) -> fmt::Result {
+        writeln!(
+            target,
+            "Output [Alt+O]: Csound\n\
+             Server: {server_address}\n\
+             Instrument [Up/Down]: {program_number} - instr {instrument}",
+            server_address = self.server_address,
+            program_number = self.program_number,
+            instrument = self.instrument,
+        )
+    }
+}
+
+impl ViewModel for SfzInfo {
+    fn pitch_range(&self) -> Option<Range<Pitch>> {
+        None
+    }
+
+    fn write_info(&self, target: &mut String) -> fmt::Result {
+        writeln!(
+            target,
+            "Output [Alt+O]: SFZ\n\
+             Instrument [Up/Down]: {program_number} - {sfz_file_location}\n\
+             Regions: {num_regions}",
+            program_number = self.program_number,
+            sfz_file_location = self.sfz_file_location.display(),
+            num_regions = self.num_regions,
         )
     }
 }
@@ -374,3 +769,56 @@ impl ViewModel for () {
         writeln!(target, "Output [Alt+O]: No Audio")
     }
 }
+
+fn tuning_method_name(tuning_method: TuningMethod) -> &'static str {
+    match tuning_method {
+        TuningMethod::FullKeyboard => "Single Note Tuning Change",
+        TuningMethod::FullKeyboardRt => "Single Note Tuning Change (realtime)",
+        TuningMethod::Octave1 => "Scale/Octave Tuning (1-Byte)",
+        TuningMethod::Octave1Rt => "Scale/Octave Tuning (1-Byte) (realtime)",
+        TuningMethod::Octave2 => "Scale/Octave Tuning (2-Byte)",
+        TuningMethod::Octave2Rt => "Scale/Octave Tuning (2-Byte) (realtime)",
+        TuningMethod::ChannelFineTuning => "Channel Fine Tuning",
+        TuningMethod::PitchBend => "Pitch Bend",
+        TuningMethod::MpeLower => "MPE (lower zone)",
+        TuningMethod::MpeUpper => "MPE (upper zone)",
+        TuningMethod::Auto => "Automatic",
+    }
+}
+
+/// Maximum number of untunable degrees listed s.t. the info text still fits on the screen.
+const MAX_LISTED_UNTUNABLE_DEGREES: usize = 8;
+
+fn write_untunable_degrees(
+    target: &mut String,
+    untunable_degrees: &[UntunableKey<i32>],
+) -> fmt::Result {
+    if untunable_degrees.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(
+        target,
+        "Degrees exceeding the available channels ({}):",
+        untunable_degrees.len()
+    )?;
+    midi::write_untunable_keys(
+        target,
+        untunable_degrees,
+        MAX_LISTED_UNTUNABLE_DEGREES,
+        |degree| format!("degree {degree}"),
+    )
+}
+
+fn write_jit_clashes(target: &mut String, jit_clashes: &JitClashes) -> fmt::Result {
+    if let Some((degree, note, detuning)) = jit_clashes.last {
+        writeln!(
+            target,
+            "Notes stopped since all channels were in use: {}\n\
+             - last: degree {degree}: {note} {:+.1}c",
+            jit_clashes.count,
+            detuning.as_cents()
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Harmonic entropy, a measure of the perceived dissonance of intervals.
+
+use crate::{math, pitch::Ratio, scala::Scl};
+
+/// Number of standard deviations beyond which the contribution of a fraction is neglected.
+const CUTOFF: f64 = 5.0;
+
+/// Harmonic entropy model based on Paul Erlich's definition.
+///
+/// The listener is assumed to perceive an interval as one of the fractions `n/d` with `n * d <= max_product`.
+/// The probability of each fraction is given by a normal distribution around the heard interval weighted by `1 / sqrt(n * d)`.
+/// The harmonic entropy is the Shannon entropy (in nats) of this probability distribution.
+/// Low values indicate that the interval is unambiguously heard as a simple fraction, i.e. it is consonant.
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::he::HarmonicEntropy;
+/// # use tune::pitch::Ratio;
+/// let he = HarmonicEntropy::default();
+///
+/// let just_fifth = he.of_ratio(Ratio::from_float(3.0 / 2.0));
+/// let tempered_fifth = he.of_ratio(Ratio::from_semitones(7));
+/// let tritone = he.of_ratio(Ratio::from_semitones(6));
+///
+/// assert!(just_fifth < tempered_fifth);
+/// assert!(tempered_fifth < tritone);
+/// assert_approx_eq!(he.of_ratio(Ratio::default()), 2.421, 1e-3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HarmonicEntropy {
+    spread_in_cents: f64,
+    candidates: Vec<Candidate>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Candidate {
+    cents: f64,
+    weight: f64,
+}
+
+impl HarmonicEntropy {
+    /// Creates a new [`HarmonicEntropy`] model.
+    ///
+    /// `spread` is the standard deviation of the perceived interval. `max_product` is the largest allowed product of the numerator and denominator of a candidate fraction.
+    pub fn new(spread: Ratio, max_product: u32) -> Self {
+        let mut candidates = Vec::new();
+        for denom in 1..=max_product {
+            for numer in 1..=max_product / denom {
+                if math::gcd_u32(numer, denom) == 1 {
+                    candidates.push(Candidate {
+                        cents: Ratio::from_float(f64::from(numer) / f64::from(denom)).as_cents(),
+                        weight: 1.0 / f64::from(numer * denom).sqrt(),
+                    });
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.cents.partial_cmp(&b.cents).unwrap());
+
+        Self {
+            spread_in_cents: spread.as_cents().abs(),
+            candidates,
+        }
+    }
+
+    /// Calculates the harmonic entropy of the given interval.
+    ///
+    /// Returns [`f64::NAN`] if the interval is out of the range covered by the candidate fractions.
+    pub fn of_ratio(&self, ratio: Ratio) -> f64 {
+        let cents = ratio.as_cents();
+        let lower = cents - CUTOFF * self.spread_in_cents;
+        let upper = cents + CUTOFF * self.spread_in_cents;
+
+        let start = self
+            .candidates
+            .partition_point(|candidate| candidate.cents < lower);
+        let probabilities: Vec<_> = self.candidates[start..]
+            .iter()
+            .take_while(|candidate| candidate.cents <= upper)
+            .map(|candidate| {
+                let z = (candidate.cents - cents) / self.spread_in_cents;
+                candidate.weight * (-0.5 * z * z).exp()
+            })
+            .filter(|&probability| probability > 0.0)
+            .collect();
+
+        let total: f64 = probabilities.iter().sum();
+        if total == 0.0 {
+            return f64::NAN;
+        }
+
+        probabilities
+            .iter()
+            .map(|probability| {
+                let p = probability / total;
+                -p * p.ln()
+            })
+            .sum()
+    }
+
+    /// Calculates the mean harmonic entropy of all intervals between the degrees of a scale, including the unison and the period.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::he::HarmonicEntropy;
+    /// # use tune::scala::Scl;
+    /// let he = HarmonicEntropy::default();
+    ///
+    /// let just_major_triad = Scl::builder()
+    ///     .push_fraction(5, 4)
+    ///     .push_fraction(3, 2)
+    ///     .push_fraction(2, 1)
+    ///     .build()
+    ///     .unwrap();
+    /// let neutral_triad = Scl::builder()
+    ///     .push_cents(350.0)
+    ///     .push_cents(700.0)
+    ///     .push_cents(1200.0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(he.of_scl(&just_major_triad) < he.of_scl(&neutral_triad));
+    /// ```
+    pub fn of_scl(&self, scl: &Scl) -> f64 {
+        let pitches: Vec<_> = (0..=i32::from(scl.num_items()))
+            .map(|degree| scl.relative_pitch_of(degree))
+            .collect();
+
+        let mut sum = 0.0;
+        let mut num_intervals = 0;
+        for (index, &lower) in pitches.iter().enumerate() {
+            for &upper in &pitches[index + 1..] {
+                sum += self.of_ratio(upper.deviation_from(lower));
+                num_intervals += 1;
+            }
+        }
+
+        sum / f64::from(num_intervals)
+    }
+}
+
+impl Default for HarmonicEntropy {
+    /// Creates a [`HarmonicEntropy`] model with a spread of 17¢ (approx. 1%) and a maximum product of 10000.
+    fn default() -> Self {
+        Self::new(Ratio::from_cents(17.0), 10_000)
+    }
+}
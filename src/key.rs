@@ -97,11 +97,37 @@ impl PianoKey {
     }
 }
 
+/// An isomorphic keyboard that maps two-dimensional key coordinates to [`PianoKey`]s.
+///
+/// The coordinates used by [`Keyboard::get_key`] follow the same contract for every [`KeyboardGeometry`]:
+/// `x` counts the keys within a row (to the right) and `y` counts the rows (upwards).
+/// For staggered geometries, each row is shifted by half a key to the left relative to the row below, i.e. `(x, y + 1)` is the upper-left neighbor of `(x, y)`, as on a computer keyboard.
+///
+/// The step sizes are given in scale degrees. The primary step is the whole tone and the secondary step is the diatonic semitone of the underlying temperament.
 #[derive(Debug, Clone)]
 pub struct Keyboard {
     root_key: PianoKey,
     primary_step: i16,
     secondary_step: i16,
+    geometry: KeyboardGeometry,
+}
+
+/// The arrangement of the steps of a [`Keyboard`] on a two-dimensional key surface.
+///
+/// In the examples below, `T` denotes the primary step (whole tone) and `s` the secondary step (diatonic semitone).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeyboardGeometry {
+    /// Staggered rows with `T` to the right and the chromatic semitone `T - s` to the upper right s.t. sharps are located above their naturals.
+    Bosanquet,
+
+    /// Staggered rows with `T` to the right, the fourth `2T + s` to the upper left and the fifth `3T + s` to the upper right.
+    WickiHayden,
+
+    /// Staggered rows with `T` to the right. Every second row is raised by `s` s.t. each pair of rows forms a chromatic scale, like on a Jankó keyboard.
+    Janko,
+
+    /// Rectangular grid with a single scale degree to the right and the fourth `2T + s` upwards, like the default layout of a LinnStrument.
+    Grid,
 }
 
 impl Keyboard {
@@ -110,6 +136,7 @@ impl Keyboard {
             root_key,
             primary_step: 2,
             secondary_step: 1,
+            geometry: KeyboardGeometry::Bosanquet,
         }
     }
 
@@ -119,6 +146,39 @@ impl Keyboard {
         self
     }
 
+    /// Arranges the keys according to the given [`KeyboardGeometry`]. The default geometry is [`KeyboardGeometry::Bosanquet`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::key::Keyboard;
+    /// # use tune::key::KeyboardGeometry;
+    /// # use tune::key::PianoKey;
+    /// // 12-EDO: Whole tone = 2 degrees, diatonic semitone = 1 degree
+    /// let keyboard = Keyboard::root_at(PianoKey::from_midi_number(60)).with_steps(2, 1);
+    /// let key = |keyboard: &Keyboard, x, y| keyboard.get_key(x, y).midi_number();
+    ///
+    /// let bosanquet = keyboard.clone();
+    /// assert_eq!(key(&bosanquet, 1, 0), 62);
+    /// assert_eq!(key(&bosanquet, 1, 1), 61);
+    ///
+    /// let wicki_hayden = keyboard.clone().with_geometry(KeyboardGeometry::WickiHayden);
+    /// assert_eq!(key(&wicki_hayden, 0, 1), 65);
+    /// assert_eq!(key(&wicki_hayden, 1, 1), 67);
+    ///
+    /// let janko = keyboard.clone().with_geometry(KeyboardGeometry::Janko);
+    /// assert_eq!(key(&janko, 1, 1), 61);
+    /// assert_eq!(key(&janko, 1, 2), 60);
+    ///
+    /// let grid = keyboard.with_geometry(KeyboardGeometry::Grid);
+    /// assert_eq!(key(&grid, 1, 0), 61);
+    /// assert_eq!(key(&grid, 0, 1), 65);
+    /// ```
+    pub fn with_geometry(mut self, geometry: KeyboardGeometry) -> Self {
+        self.geometry = geometry;
+        self
+    }
+
     pub fn with_steps_of(self, temperament: &EqualTemperament) -> Self {
         self.with_steps(temperament.primary_step(), temperament.secondary_step())
     }
@@ -156,9 +216,27 @@ impl Keyboard {
         self.secondary_step
     }
 
+    pub fn geometry(&self) -> KeyboardGeometry {
+        self.geometry
+    }
+
+    /// Returns the [`PianoKey`] at the given coordinates. See [`Keyboard`] for the meaning of `x` and `y`.
     pub fn get_key(&self, x: i16, y: i16) -> PianoKey {
-        let num_steps = i32::from(self.primary_step) * i32::from(x)
-            - i32::from(self.secondary_step) * i32::from(y);
+        let (x, y) = (i32::from(x), i32::from(y));
+        let primary_step = i32::from(self.primary_step);
+        let secondary_step = i32::from(self.secondary_step);
+        let fourth = 2 * primary_step + secondary_step;
+
+        let num_steps = match self.geometry {
+            KeyboardGeometry::Bosanquet => primary_step * x - secondary_step * y,
+            KeyboardGeometry::WickiHayden => primary_step * x + fourth * y,
+            KeyboardGeometry::Janko => {
+                // Rows two apart carry the same pitches, i.e. the same pitch reappears one key to the left
+                let num_row_pairs = -(-y).div_euclid(2);
+                primary_step * (x - num_row_pairs) + secondary_step * y.rem_euclid(2)
+            }
+            KeyboardGeometry::Grid => x + fourth * y,
+        };
         self.root_key.plus_steps(num_steps)
     }
 }
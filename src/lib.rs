@@ -1,6 +1,7 @@
 mod parse;
 
 pub mod comma;
+pub mod he;
 pub mod key;
 pub mod math;
 pub mod midi;
@@ -10,5 +11,7 @@ pub mod pergen;
 pub mod pitch;
 pub mod scala;
 pub mod temperament;
+pub mod tun;
 pub mod tuner;
 pub mod tuning;
+pub mod uncertain;
@@ -118,8 +118,36 @@ pub fn i32_dr_u<D: U32Denom>(numer: i32, denom: D) -> (i32, D) {
 /// assert_eq!(math::simplify_u16(0, 0), (0, 0));
 /// assert_eq!(math::simplify_u16(35, 0), (1, 0));
 /// assert_eq!(math::simplify_u16(0, 21), (0, 1));
-pub fn simplify_u16(mut numer: u16, mut denom: u16) -> (u16, u16) {
-    let gcd = gcd_u16(numer, denom);
+pub fn simplify_u16(numer: u16, denom: u16) -> (u16, u16) {
+    let (numer, denom) = simplify_u64(numer.into(), denom.into());
+    (narrow(numer), narrow(denom))
+}
+
+/// Simplifies a fraction of `u32`s.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::simplify_u32(3_000_000, 2_000_000), (3, 2));
+/// assert_eq!(math::simplify_u32(u32::MAX, u32::MAX), (1, 1));
+/// ```
+pub fn simplify_u32(numer: u32, denom: u32) -> (u32, u32) {
+    let (numer, denom) = simplify_u64(numer.into(), denom.into());
+    (narrow(numer), narrow(denom))
+}
+
+/// Simplifies a fraction of `u64`s.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::simplify_u64(3 << 40, 2 << 40), (3, 2));
+/// assert_eq!(math::simplify_u64(u64::MAX, u64::MAX), (1, 1));
+/// ```
+pub fn simplify_u64(mut numer: u64, mut denom: u64) -> (u64, u64) {
+    let gcd = gcd_u64(numer, denom);
     if gcd != 0 {
         numer /= gcd;
         denom /= gcd;
@@ -148,7 +176,35 @@ pub fn simplify_u16(mut numer: u16, mut denom: u16) -> (u16, u16) {
 /// assert_eq!(math::gcd_u16(0, 21), 21);
 /// assert_eq!(math::gcd_u16(0, 0), 1);
 /// ```
-pub fn gcd_u16(mut x: u16, mut y: u16) -> u16 {
+pub fn gcd_u16(x: u16, y: u16) -> u16 {
+    narrow(gcd_u64(x.into(), y.into()))
+}
+
+/// Determines the greatest common divisor of two `u32`s.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::gcd_u32(3_000_000, 2_000_000), 1_000_000);
+/// assert_eq!(math::gcd_u32(u32::MAX, 0), u32::MAX);
+/// assert_eq!(math::gcd_u32(0, 0), 1);
+/// ```
+pub fn gcd_u32(x: u32, y: u32) -> u32 {
+    narrow(gcd_u64(x.into(), y.into()))
+}
+
+/// Determines the greatest common divisor of two `u64`s.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::gcd_u64(3 << 40, 2 << 40), 1 << 40);
+/// assert_eq!(math::gcd_u64(u64::MAX, 0), u64::MAX);
+/// assert_eq!(math::gcd_u64(0, 0), 1);
+/// ```
+pub fn gcd_u64(mut x: u64, mut y: u64) -> u64 {
     while y != 0 {
         let t = y;
         y = x % y;
@@ -171,11 +227,46 @@ pub fn gcd_u16(mut x: u16, mut y: u16) -> u16 {
 /// assert_eq!(math::odd_factors_u16(24), 3);
 /// assert_eq!(math::odd_factors_u16(35), 35);
 /// ```
-pub fn odd_factors_u16(mut number: u16) -> u16 {
-    if number != 0 {
-        while number % 2 == 0 {
-            number /= 2;
-        }
+pub fn odd_factors_u16(number: u16) -> u16 {
+    narrow(odd_factors_u64(number.into()))
+}
+
+/// Removes all powers of two from a `u32`.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::odd_factors_u32(0), 0);
+/// assert_eq!(math::odd_factors_u32(1 << 31), 1);
+/// assert_eq!(math::odd_factors_u32(3 << 20), 3);
+/// assert_eq!(math::odd_factors_u32(u32::MAX), u32::MAX);
+/// ```
+pub fn odd_factors_u32(number: u32) -> u32 {
+    narrow(odd_factors_u64(number.into()))
+}
+
+/// Removes all powers of two from a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::math;
+/// assert_eq!(math::odd_factors_u64(0), 0);
+/// assert_eq!(math::odd_factors_u64(1 << 63), 1);
+/// assert_eq!(math::odd_factors_u64(3 << 40), 3);
+/// assert_eq!(math::odd_factors_u64(u64::MAX), u64::MAX);
+/// ```
+pub fn odd_factors_u64(number: u64) -> u64 {
+    match number {
+        0 => 0,
+        _ => number >> number.trailing_zeros(),
     }
-    number
+}
+
+/// Converts the result of a `u64` operation back to the (narrower) type of its operands.
+///
+/// The operations of this module never yield values that are larger than their operands s.t. the conversion cannot fail.
+fn narrow<T: TryFrom<u64>>(value: u64) -> T {
+    T::try_from(value).ok().unwrap()
 }
@@ -2,6 +2,12 @@
 //!
 //! References:
 //! - [MIDI messages](https://www.midi.org/specifications-old/item/table-1-summary-of-midi-message)
+//! - [Universal MIDI Packet (UMP) Format and MIDI 2.0 Protocol](https://www.midi.org/specifications/midi-2-0-specifications)
+
+use crate::{
+    note::Note,
+    pitch::{Pitch, Pitched, Ratio},
+};
 
 /// Status bits for "Note Off event".
 pub const NOTE_OFF: u8 = 0b1000;
@@ -18,6 +24,23 @@ pub const CHANNEL_PRESSURE: u8 = 0b1101;
 /// Status bits for "Channel Pressure (After-touch)".
 pub const PITCH_BEND_CHANGE: u8 = 0b1110;
 
+/// Status bits for the MIDI 2.0 "Registered Per-Note Controller" message.
+pub const REGISTERED_PER_NOTE_CONTROLLER: u8 = 0b0000;
+/// Status bits for the MIDI 2.0 "Assignable Per-Note Controller" message.
+pub const ASSIGNABLE_PER_NOTE_CONTROLLER: u8 = 0b0001;
+/// Status bits for the MIDI 2.0 "Per-Note Pitch Bend" message.
+pub const PER_NOTE_PITCH_BEND: u8 = 0b0110;
+
+/// UMP message type of MIDI 1.0 Channel Voice messages.
+pub const UMP_MIDI1_CHANNEL_VOICE: u8 = 0x2;
+/// UMP message type of MIDI 2.0 Channel Voice messages.
+pub const UMP_MIDI2_CHANNEL_VOICE: u8 = 0x4;
+
+/// Attribute type of the "Pitch 7.9" attribute of MIDI 2.0 Note On / Note Off messages.
+pub const NOTE_ATTRIBUTE_PITCH_7_9: u8 = 0x03;
+/// Index of the registered per-note controller "Pitch 7.25".
+pub const PER_NOTE_CONTROLLER_PITCH_7_25: u8 = 3;
+
 /// A type-safe representation of MIDI messages that aren't System Common messages.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct ChannelMessage {
@@ -157,6 +180,37 @@ impl ChannelMessage {
     pub fn message_type(&self) -> ChannelMessageType {
         self.message_type
     }
+
+    /// Returns the Universal MIDI Packet representation of a MIDI 1.0 message in the given `group`.
+    ///
+    /// [`None`] is returned if the group value is outside the range [0..16).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::midi::ChannelMessageType;
+    /// let message = ChannelMessageType::NoteOn {
+    ///         key: 77,
+    ///         velocity: 88
+    ///     }
+    ///     .in_channel(7)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(message.to_ump_word(3), Some(0x2397_4d58));
+    /// assert_eq!(message.to_ump_word(16), None);
+    /// ```
+    pub fn to_ump_word(&self, group: u8) -> Option<u32> {
+        if group >= 16 {
+            return None;
+        }
+        let [status_byte, payload1, payload2] = self.to_raw_message();
+        Some(u32::from_be_bytes([
+            UMP_MIDI1_CHANNEL_VOICE << 4 | group,
+            status_byte,
+            payload1,
+            payload2,
+        ]))
+    }
 }
 
 fn channel_message(prefix: u8, channel: u8, payload1: u8, payload2: u8) -> [u8; 3] {
@@ -207,6 +261,388 @@ impl ChannelMessageType {
     }
 }
 
+/// A MIDI 2.0 Channel Voice message in the Universal MIDI Packet (UMP) format.
+///
+/// Only the note-specific subset of MIDI 2.0 Channel Voice messages is supported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UmpChannelMessage {
+    group: u8,
+    channel: u8,
+    message_type: UmpChannelMessageType,
+}
+
+impl UmpChannelMessage {
+    /// Parses a 64-bit MIDI 2.0 Channel Voice message.
+    ///
+    /// When no supported message is provided [`None`] is returned.
+    /// Note attributes other than [`NOTE_ATTRIBUTE_PITCH_7_9`] are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::midi::NotePitch7_9;
+    /// # use tune::midi::UmpChannelMessage;
+    /// # use tune::midi::UmpChannelMessageType;
+    /// let message = UmpChannelMessage::from_ump_words(&[0x4397_4d03, 0xb000_9b00]).unwrap();
+    /// assert_eq!(message.group(), 3);
+    /// assert_eq!(message.channel(), 7);
+    /// assert_eq!(
+    ///     message.message_type(),
+    ///     UmpChannelMessageType::NoteOn {
+    ///         key: 77,
+    ///         velocity: 0xb000,
+    ///         pitch: Some(NotePitch7_9::from_bits(0x9b00)),
+    ///     }
+    /// );
+    ///
+    /// let midi1_message = [0x2397_4d58];
+    /// assert_eq!(UmpChannelMessage::from_ump_words(&midi1_message), None);
+    /// ```
+    pub fn from_ump_words(words: &[u32]) -> Option<UmpChannelMessage> {
+        let [message_type_and_group, status_byte, payload1, payload2] =
+            words.first()?.to_be_bytes();
+        if message_type_and_group >> 4 != UMP_MIDI2_CHANNEL_VOICE {
+            return None;
+        }
+        let data = *words.get(1)?;
+
+        let key = payload1 & 0x7f;
+        let pitch =
+            || (payload2 == NOTE_ATTRIBUTE_PITCH_7_9).then(|| NotePitch7_9::from_bits(data as u16));
+        let message_type = match status_byte >> 4 {
+            NOTE_OFF => UmpChannelMessageType::NoteOff {
+                key,
+                velocity: (data >> 16) as u16,
+                pitch: pitch(),
+            },
+            NOTE_ON => UmpChannelMessageType::NoteOn {
+                key,
+                velocity: (data >> 16) as u16,
+                pitch: pitch(),
+            },
+            POLYPHONIC_KEY_PRESSURE => UmpChannelMessageType::PolyphonicKeyPressure {
+                key,
+                pressure: data,
+            },
+            REGISTERED_PER_NOTE_CONTROLLER => UmpChannelMessageType::RegisteredPerNoteController {
+                key,
+                controller: payload2,
+                value: data,
+            },
+            ASSIGNABLE_PER_NOTE_CONTROLLER => UmpChannelMessageType::AssignablePerNoteController {
+                key,
+                controller: payload2,
+                value: data,
+            },
+            PER_NOTE_PITCH_BEND => UmpChannelMessageType::PerNotePitchBend { key, value: data },
+            _ => return None,
+        };
+
+        Some(UmpChannelMessage {
+            group: message_type_and_group & 0b0000_1111,
+            channel: status_byte & 0b0000_1111,
+            message_type,
+        })
+    }
+
+    /// Returns the two 32-bit words of a MIDI 2.0 Channel Voice message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::midi::UmpChannelMessageType;
+    /// let message = UmpChannelMessageType::PerNotePitchBend {
+    ///         key: 77,
+    ///         value: 0x8000_0000,
+    ///     }
+    ///     .in_group_and_channel(3, 7)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(message.to_ump_words(), [0x4367_4d00, 0x8000_0000]);
+    /// ```
+    pub fn to_ump_words(&self) -> [u32; 2] {
+        let (status, payload1, payload2, data) = match self.message_type {
+            UmpChannelMessageType::NoteOff {
+                key,
+                velocity,
+                pitch,
+            } => note_message(NOTE_OFF, key, velocity, pitch),
+            UmpChannelMessageType::NoteOn {
+                key,
+                velocity,
+                pitch,
+            } => note_message(NOTE_ON, key, velocity, pitch),
+            UmpChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
+                (POLYPHONIC_KEY_PRESSURE, key, 0, pressure)
+            }
+            UmpChannelMessageType::RegisteredPerNoteController {
+                key,
+                controller,
+                value,
+            } => (REGISTERED_PER_NOTE_CONTROLLER, key, controller, value),
+            UmpChannelMessageType::AssignablePerNoteController {
+                key,
+                controller,
+                value,
+            } => (ASSIGNABLE_PER_NOTE_CONTROLLER, key, controller, value),
+            UmpChannelMessageType::PerNotePitchBend { key, value } => {
+                (PER_NOTE_PITCH_BEND, key, 0, value)
+            }
+        };
+
+        [
+            u32::from_be_bytes([
+                UMP_MIDI2_CHANNEL_VOICE << 4 | self.group,
+                status << 4 | self.channel,
+                payload1,
+                payload2,
+            ]),
+            data,
+        ]
+    }
+
+    /// Returns the UMP group of a MIDI 2.0 message.
+    pub fn group(&self) -> u8 {
+        self.group
+    }
+
+    /// Returns the channel of a MIDI 2.0 message.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Returns the group- and channel-agnostic part of a MIDI 2.0 message.
+    pub fn message_type(&self) -> UmpChannelMessageType {
+        self.message_type
+    }
+}
+
+fn note_message(
+    status: u8,
+    key: u8,
+    velocity: u16,
+    pitch: Option<NotePitch7_9>,
+) -> (u8, u8, u8, u32) {
+    let (attribute_type, attribute_data) = match pitch {
+        Some(pitch) => (NOTE_ATTRIBUTE_PITCH_7_9, pitch.bits()),
+        None => (0, 0),
+    };
+    (
+        status,
+        key,
+        attribute_type,
+        u32::from(velocity) << 16 | u32::from(attribute_data),
+    )
+}
+
+/// A parsed representation of the group- and channel-agnostic part of a MIDI 2.0 message.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UmpChannelMessageType {
+    NoteOff {
+        key: u8,
+        velocity: u16,
+        pitch: Option<NotePitch7_9>,
+    },
+    NoteOn {
+        key: u8,
+        velocity: u16,
+        pitch: Option<NotePitch7_9>,
+    },
+    PolyphonicKeyPressure {
+        key: u8,
+        pressure: u32,
+    },
+    RegisteredPerNoteController {
+        key: u8,
+        controller: u8,
+        value: u32,
+    },
+    AssignablePerNoteController {
+        key: u8,
+        controller: u8,
+        value: u32,
+    },
+    PerNotePitchBend {
+        key: u8,
+        value: u32,
+    },
+}
+
+impl UmpChannelMessageType {
+    /// Creates a "Pitch 7.25" registered per-note controller message for the given `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::midi::NotePitch7_25;
+    /// # use tune::midi::UmpChannelMessageType;
+    /// let pitch = NotePitch7_25::from_bits(0x9b00_0000);
+    ///
+    /// assert_eq!(
+    ///     UmpChannelMessageType::per_note_pitch(77, pitch),
+    ///     UmpChannelMessageType::RegisteredPerNoteController {
+    ///         key: 77,
+    ///         controller: 3,
+    ///         value: 0x9b00_0000,
+    ///     }
+    /// );
+    /// ```
+    pub fn per_note_pitch(key: u8, pitch: NotePitch7_25) -> Self {
+        UmpChannelMessageType::RegisteredPerNoteController {
+            key,
+            controller: PER_NOTE_CONTROLLER_PITCH_7_25,
+            value: pitch.bits(),
+        }
+    }
+
+    /// Creates a new [`UmpChannelMessage`] from `self` with the given `group` and `channel`.
+    ///
+    /// [`None`] is returned if the group or channel value is outside the range [0..16).
+    pub fn in_group_and_channel(self, group: u8, channel: u8) -> Option<UmpChannelMessage> {
+        match group < 16 && channel < 16 {
+            true => Some(UmpChannelMessage {
+                group,
+                channel,
+                message_type: self,
+            }),
+            false => None,
+        }
+    }
+}
+
+/// A fractional MIDI note number in the 7.9 fixed-point format of the MIDI 2.0 "Pitch 7.9" note attribute.
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::midi::NotePitch7_9;
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitched;
+/// # use tune::pitch::Ratio;
+/// let a4_plus_half_a_semitone = NoteLetter::A.in_octave(4).pitch() * Ratio::from_cents(50.0);
+/// let pitch = NotePitch7_9::from_pitch(a4_plus_half_a_semitone);
+///
+/// assert_eq!(pitch.bits(), 69 << 9 | 256);
+/// assert_approx_eq!(pitch.pitch().as_hz(), a4_plus_half_a_semitone.as_hz());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct NotePitch7_9 {
+    bits: u16,
+}
+
+impl NotePitch7_9 {
+    /// Finds the closest representable value of the given `pitch`, saturating at the borders of the MIDI note range.
+    pub fn from_pitch(pitch: impl Pitched) -> Self {
+        Self {
+            bits: fixed_point_note_number(pitch, 9, u16::MAX.into()) as u16,
+        }
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self { bits }
+    }
+
+    pub fn bits(self) -> u16 {
+        self.bits
+    }
+}
+
+impl Pitched for NotePitch7_9 {
+    fn pitch(&self) -> Pitch {
+        pitch_of_fixed_point_note_number(self.bits.into(), 9)
+    }
+}
+
+/// A fractional MIDI note number in the 7.25 fixed-point format of the MIDI 2.0 "Pitch 7.25" registered per-note controller.
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::midi::NotePitch7_25;
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitched;
+/// # use tune::pitch::Ratio;
+/// let a4_plus_half_a_semitone = NoteLetter::A.in_octave(4).pitch() * Ratio::from_cents(50.0);
+/// let pitch = NotePitch7_25::from_pitch(a4_plus_half_a_semitone);
+///
+/// assert_eq!(pitch.bits(), 69 << 25 | 1 << 24);
+/// assert_approx_eq!(pitch.pitch().as_hz(), a4_plus_half_a_semitone.as_hz());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct NotePitch7_25 {
+    bits: u32,
+}
+
+impl NotePitch7_25 {
+    /// Finds the closest representable value of the given `pitch`, saturating at the borders of the MIDI note range.
+    pub fn from_pitch(pitch: impl Pitched) -> Self {
+        Self {
+            bits: fixed_point_note_number(pitch, 25, u32::MAX.into()) as u32,
+        }
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self { bits }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.bits
+    }
+}
+
+impl Pitched for NotePitch7_25 {
+    fn pitch(&self) -> Pitch {
+        pitch_of_fixed_point_note_number(self.bits.into(), 25)
+    }
+}
+
+fn fixed_point_note_number(pitch: impl Pitched, fraction_bits: i32, max_bits: f64) -> u64 {
+    let note_number =
+        Ratio::between_pitches(Note::from_midi_number(0), pitch.pitch()).as_semitones();
+    (note_number * 2f64.powi(fraction_bits))
+        .round()
+        .max(0.0)
+        .min(max_bits) as u64
+}
+
+fn pitch_of_fixed_point_note_number(bits: u64, fraction_bits: i32) -> Pitch {
+    Note::from_midi_number(0).pitch()
+        * Ratio::from_semitones(bits as f64 / 2f64.powi(fraction_bits))
+}
+
+/// Converts a MIDI 1.0 value with `src_bits` bits into a MIDI 2.0 value with `dst_bits` bits s.t. the minimum, center and maximum values are preserved.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::midi::scale_up;
+/// assert_eq!(scale_up(0, 7, 16), 0x0000);
+/// assert_eq!(scale_up(64, 7, 16), 0x8000);
+/// assert_eq!(scale_up(127, 7, 16), 0xffff);
+/// ```
+pub fn scale_up(value: u32, src_bits: u32, dst_bits: u32) -> u32 {
+    let scale_bits = dst_bits - src_bits;
+    let mut result = value << scale_bits;
+    if value <= 1 << (src_bits - 1) {
+        return result;
+    }
+
+    let repeat_bits = src_bits - 1;
+    let mut repeat_value = value & ((1 << repeat_bits) - 1);
+    if scale_bits > repeat_bits {
+        repeat_value <<= scale_bits - repeat_bits;
+    } else {
+        repeat_value >>= repeat_bits - scale_bits;
+    }
+    while repeat_value != 0 {
+        result |= repeat_value;
+        repeat_value >>= repeat_bits;
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,4 +818,83 @@ mod tests {
         };
         assert_eq!(message.to_raw_message(), [0b1110_1101, 22, 33]);
     }
+
+    #[test]
+    fn parse_ump_registered_per_note_controller() {
+        let message = UmpChannelMessage::from_ump_words(&[0x4e0c_3c03, 0x7800_0000]).unwrap();
+        assert_eq!(
+            message,
+            UmpChannelMessage {
+                group: 14,
+                channel: 12,
+                message_type: UmpChannelMessageType::RegisteredPerNoteController {
+                    key: 60,
+                    controller: 3,
+                    value: 0x7800_0000
+                }
+            }
+        );
+        assert_eq!(
+            NotePitch7_25::from_bits(0x7800_0000),
+            NotePitch7_25::from_pitch(Note::from_midi_number(60))
+        );
+    }
+
+    #[test]
+    fn serialize_ump_note_off_without_pitch() {
+        let message = UmpChannelMessage {
+            group: 1,
+            channel: 2,
+            message_type: UmpChannelMessageType::NoteOff {
+                key: 88,
+                velocity: 0x1234,
+                pitch: None,
+            },
+        };
+        assert_eq!(message.to_ump_words(), [0x4182_5800, 0x1234_0000]);
+    }
+
+    #[test]
+    fn ump_round_trip() {
+        let message_types = [
+            UmpChannelMessageType::NoteOn {
+                key: 60,
+                velocity: 0xffff,
+                pitch: Some(NotePitch7_9::from_bits(0x7880)),
+            },
+            UmpChannelMessageType::PolyphonicKeyPressure {
+                key: 61,
+                pressure: 0x8000_0000,
+            },
+            UmpChannelMessageType::AssignablePerNoteController {
+                key: 62,
+                controller: 74,
+                value: 0x1234_5678,
+            },
+            UmpChannelMessageType::PerNotePitchBend {
+                key: 63,
+                value: 0x9abc_def0,
+            },
+        ];
+
+        for message_type in message_types {
+            let message = message_type.in_group_and_channel(15, 15).unwrap();
+            assert_eq!(
+                UmpChannelMessage::from_ump_words(&message.to_ump_words()),
+                Some(message)
+            );
+        }
+    }
+
+    #[test]
+    fn pitch_7_9_saturates() {
+        assert_eq!(
+            NotePitch7_9::from_pitch(Note::from_midi_number(-1)).bits(),
+            0
+        );
+        assert_eq!(
+            NotePitch7_9::from_pitch(Note::from_midi_number(128)).bits(),
+            u16::MAX
+        );
+    }
 }
@@ -9,7 +9,7 @@ use std::{collections::HashSet, fmt::Debug, iter};
 use crate::{
     key::PianoKey,
     midi::{ChannelMessage, ChannelMessageType},
-    note::NoteLetter,
+    note::{Note, NoteLetter},
     pitch::{Pitch, Pitched, Ratio},
     tuning::KeyboardMapping,
 };
@@ -664,20 +664,7 @@ impl ScaleOctaveTuningMessage {
             }
         }
 
-        let pitch_bends = [
-            octave_tuning.c,
-            octave_tuning.csh,
-            octave_tuning.d,
-            octave_tuning.dsh,
-            octave_tuning.e,
-            octave_tuning.f,
-            octave_tuning.fsh,
-            octave_tuning.g,
-            octave_tuning.gsh,
-            octave_tuning.a,
-            octave_tuning.ash,
-            octave_tuning.b,
-        ];
+        let pitch_bends = octave_tuning.as_array();
 
         match options.format {
             ScaleOctaveTuningFormat::OneByte => {
@@ -763,6 +750,14 @@ pub struct ScaleOctaveTuning {
 }
 
 impl ScaleOctaveTuning {
+    /// The detunings ordered by pitch class, starting with C.
+    fn as_array(&self) -> [Ratio; 12] {
+        [
+            self.c, self.csh, self.d, self.dsh, self.e, self.f, self.fsh, self.g, self.gsh, self.a,
+            self.ash, self.b,
+        ]
+    }
+
     pub fn as_mut(&mut self, letter: NoteLetter) -> &mut Ratio {
         match letter {
             NoteLetter::C => &mut self.c,
@@ -800,6 +795,249 @@ impl From<u8> for Channels {
     }
 }
 
+// 08 00 Bulk Dump Request
+// 08 01 Bulk Dump Reply
+
+const BULK_DUMP_REQUEST: u8 = 0x00;
+const BULK_DUMP_REPLY: u8 = 0x01;
+
+const NO_CHANGE: [u8; 3] = [0x7f, 0x7f, 0x7f];
+
+/// A *MIDI Tuning Standard* message decoded from its SysEx representation.
+///
+/// Useful to inspect the tuning messages sent or received by hardware synthesizers.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::mts::MtsMessage;
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitched;
+/// let message = MtsMessage::from_sysex_bytes(&[
+///     0xf0, 0x7f, 0x7f, 0x08, 0x02, // RT Single Note Tuning Change
+///     0, 1,                         // Tuning program / number of changes
+///     69, 69, 25, 5,                // Tuning changes
+///     0xf7,                         // Sysex end
+/// ])
+/// .unwrap();
+///
+/// let tuning_changes = message.tuning_changes();
+/// assert_eq!(tuning_changes.len(), 1);
+/// assert_eq!(tuning_changes[0].key, NoteLetter::A.in_octave(4).as_piano_key());
+/// assert_eq!(
+///     (tuning_changes[0].target_pitch.as_hz() * 1000.0).round(),
+///     445000.0
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub enum MtsMessage {
+    /// *Bulk Tuning Dump Request* asking for the tuning program with the given number.
+    BulkTuningDumpRequest { device_id: u8, tuning_program: u8 },
+
+    /// *Bulk Tuning Dump* containing the pitches of all 128 MIDI notes. Notes marked as unchanged are `None`.
+    BulkTuningDump {
+        device_id: u8,
+        tuning_program: u8,
+        name: String,
+        pitches: Vec<Option<Pitch>>,
+    },
+
+    /// *Single Note Tuning Change* message, optionally with bank select.
+    SingleNoteTuningChange {
+        options: SingleNoteTuningChangeOptions,
+        tuning_changes: Vec<SingleNoteTuningChange>,
+    },
+
+    /// *Scale/Octave Tuning* message in 1-byte or 2-byte format.
+    ScaleOctaveTuning {
+        options: ScaleOctaveTuningOptions,
+        octave_tuning: ScaleOctaveTuning,
+    },
+}
+
+impl MtsMessage {
+    /// Decodes a single SysEx message including the `f0` and `f7` bytes.
+    pub fn from_sysex_bytes(sysex_bytes: &[u8]) -> Result<Self, MtsDecodeError> {
+        let (realtime, device_id, sub_id, payload) = match sysex_bytes {
+            [SYSEX_START, header @ (SYSEX_NON_RT | SYSEX_RT), device_id, MIDI_TUNING_STANDARD, sub_id, payload @ .., SYSEX_END] => {
+                (*header == SYSEX_RT, *device_id, *sub_id, payload)
+            }
+            _ => return Err(MtsDecodeError::NotAnMtsMessage),
+        };
+
+        match (realtime, sub_id) {
+            (false, BULK_DUMP_REQUEST) => match payload {
+                &[tuning_program] => Ok(MtsMessage::BulkTuningDumpRequest {
+                    device_id,
+                    tuning_program,
+                }),
+                _ => Err(MtsDecodeError::InvalidLength),
+            },
+            (false, BULK_DUMP_REPLY) => {
+                if payload.len() != 1 + 16 + 3 * 128 + 1 {
+                    return Err(MtsDecodeError::InvalidLength);
+                }
+                let (data, checksum) = payload.split_at(payload.len() - 1);
+                let expected_checksum = [SYSEX_NON_RT, device_id, MIDI_TUNING_STANDARD, sub_id]
+                    .iter()
+                    .chain(data)
+                    .fold(0, |checksum, byte| checksum ^ byte)
+                    & 0x7f;
+                if checksum[0] != expected_checksum {
+                    return Err(MtsDecodeError::ChecksumMismatch);
+                }
+                Ok(MtsMessage::BulkTuningDump {
+                    device_id,
+                    tuning_program: data[0],
+                    name: data[1..17].iter().map(|&byte| char::from(byte)).collect(),
+                    pitches: data[17..].chunks(3).map(decode_pitch).collect(),
+                })
+            }
+            (_, SINGLE_NOTE_TUNING_CHANGE | SINGLE_NOTE_TUNING_CHANGE_WITH_BANK_SELECT) => {
+                let (with_bank_select, payload) = match (sub_id, payload) {
+                    (SINGLE_NOTE_TUNING_CHANGE, payload) => (None, payload),
+                    (_, [tuning_bank, payload @ ..]) => (Some(*tuning_bank), payload),
+                    _ => return Err(MtsDecodeError::InvalidLength),
+                };
+                let (tuning_program, tuning_list) = match payload {
+                    [tuning_program, num_changes, tuning_list @ ..]
+                        if tuning_list.len() == 4 * usize::from(*num_changes) =>
+                    {
+                        (*tuning_program, tuning_list)
+                    }
+                    _ => return Err(MtsDecodeError::InvalidLength),
+                };
+                Ok(MtsMessage::SingleNoteTuningChange {
+                    options: SingleNoteTuningChangeOptions {
+                        realtime,
+                        device_id,
+                        tuning_program,
+                        with_bank_select,
+                    },
+                    tuning_changes: tuning_list
+                        .chunks(4)
+                        .filter_map(|change| {
+                            decode_pitch(&change[1..]).map(|target_pitch| SingleNoteTuningChange {
+                                key: PianoKey::from_midi_number(change[0]),
+                                target_pitch,
+                            })
+                        })
+                        .collect(),
+                })
+            }
+            (_, SCALE_OCTAVE_TUNING_1_BYTE_FORMAT | SCALE_OCTAVE_TUNING_2_BYTE_FORMAT) => {
+                let format = match sub_id {
+                    SCALE_OCTAVE_TUNING_1_BYTE_FORMAT => ScaleOctaveTuningFormat::OneByte,
+                    _ => ScaleOctaveTuningFormat::TwoByte,
+                };
+                let (encoded_channels, detunings) = match payload {
+                    [row2, row1, row0, detunings @ ..] => ([*row0, *row1, *row2], detunings),
+                    _ => return Err(MtsDecodeError::InvalidLength),
+                };
+                let detunings: Vec<_> = match format {
+                    ScaleOctaveTuningFormat::OneByte if detunings.len() == 12 => detunings
+                        .iter()
+                        .map(|&value| Ratio::from_cents(f64::from(value) - 64.0))
+                        .collect(),
+                    ScaleOctaveTuningFormat::TwoByte if detunings.len() == 24 => detunings
+                        .chunks(2)
+                        .map(|value| {
+                            let value = u16::from(value[0]) << 7 | u16::from(value[1]);
+                            Ratio::from_semitones(f64::from(value) / 8192.0 - 1.0)
+                        })
+                        .collect(),
+                    _ => return Err(MtsDecodeError::InvalidLength),
+                };
+
+                let channels: HashSet<_> = (0..16)
+                    .filter(|channel| {
+                        encoded_channels[usize::from(channel / 7)] & 1 << (channel % 7) != 0
+                    })
+                    .collect();
+
+                let mut octave_tuning = ScaleOctaveTuning::default();
+                for (pitch_class, detuning) in (0..12).zip(detunings) {
+                    let (letter, _) = Note::from_midi_number(pitch_class).letter_and_octave();
+                    *octave_tuning.as_mut(letter) = detuning;
+                }
+
+                Ok(MtsMessage::ScaleOctaveTuning {
+                    options: ScaleOctaveTuningOptions {
+                        realtime,
+                        device_id,
+                        channels: match channels.len() {
+                            16 => Channels::All,
+                            _ => Channels::Some(channels),
+                        },
+                        format,
+                    },
+                    octave_tuning,
+                })
+            }
+            _ => Err(MtsDecodeError::UnsupportedMessageType(sub_id)),
+        }
+    }
+
+    /// Returns the pitches that the message assigns to the MIDI keys.
+    ///
+    /// *Scale/Octave Tuning* messages assign a pitch to all 128 keys. Keys that are not retuned by the message are omitted.
+    pub fn tuning_changes(&self) -> Vec<SingleNoteTuningChange> {
+        match self {
+            MtsMessage::BulkTuningDumpRequest { .. } => Vec::new(),
+            MtsMessage::BulkTuningDump { pitches, .. } => (0..)
+                .zip(pitches)
+                .filter_map(|(midi_number, pitch)| {
+                    pitch.map(|target_pitch| SingleNoteTuningChange {
+                        key: PianoKey::from_midi_number(midi_number),
+                        target_pitch,
+                    })
+                })
+                .collect(),
+            MtsMessage::SingleNoteTuningChange { tuning_changes, .. } => tuning_changes.clone(),
+            MtsMessage::ScaleOctaveTuning { octave_tuning, .. } => {
+                let detunings = octave_tuning.as_array();
+                (0u8..128)
+                    .map(|midi_number| {
+                        let note = Note::from_midi_number(midi_number);
+                        SingleNoteTuningChange {
+                            key: note.as_piano_key(),
+                            target_pitch: note.pitch() * detunings[usize::from(midi_number % 12)],
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Decodes the 3-byte frequency data format `xx yy zz` where `xx` is the 12-EDO note and `yyzz` the fraction of a semitone above it.
+fn decode_pitch(bytes: &[u8]) -> Option<Pitch> {
+    if bytes == NO_CHANGE {
+        return None;
+    }
+    let fraction = u16::from(bytes[1]) << 7 | u16::from(bytes[2]);
+    Some(
+        Note::from_midi_number(bytes[0]).pitch()
+            * Ratio::from_semitones(f64::from(fraction) / U14_UPPER_BOUND_AS_F64),
+    )
+}
+
+/// Decoding an [`MtsMessage`] failed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MtsDecodeError {
+    /// The bytes do not form a Universal SysEx message of the MIDI Tuning Standard.
+    NotAnMtsMessage,
+
+    /// The MIDI Tuning Standard message type (sub-ID#2) is not supported.
+    UnsupportedMessageType(u8),
+
+    /// The message is shorter or longer than required by its type.
+    InvalidLength,
+
+    /// The checksum of a *Bulk Tuning Dump* does not match its content.
+    ChecksumMismatch,
+}
+
 pub fn channel_fine_tuning(channel: u8, detuning: Ratio) -> Option<[ChannelMessage; 4]> {
     const CHANNEL_FINE_TUNING_MSB: u8 = 0x00;
     const CHANNEL_FINE_TUNING_LSB: u8 = 0x01;
@@ -839,6 +1077,39 @@ pub fn tuning_bank_change(channel: u8, tuning_bank: u8) -> Option<[ChannelMessag
     )
 }
 
+/// Sets the pitch bend range of the given `channel` to `semitones` semitones.
+pub fn pitch_bend_sensitivity(channel: u8, semitones: u8) -> Option<[ChannelMessage; 4]> {
+    const PITCH_BEND_SENSITIVITY_MSB: u8 = 0x00;
+    const PITCH_BEND_SENSITIVITY_LSB: u8 = 0x00;
+
+    rpn_message_2_byte(
+        channel,
+        PITCH_BEND_SENSITIVITY_MSB,
+        PITCH_BEND_SENSITIVITY_LSB,
+        semitones,
+        0,
+    )
+}
+
+/// Configures an MPE zone by sending the *MPE Configuration Message* to the zone's `manager_channel`.
+///
+/// A `manager_channel` of 0 configures the lower zone, a `manager_channel` of 15 configures the upper zone.
+/// Setting `num_member_channels` to 0 disables the zone.
+pub fn mpe_configuration(
+    manager_channel: u8,
+    num_member_channels: u8,
+) -> Option<[ChannelMessage; 3]> {
+    const MPE_CONFIGURATION_MSB: u8 = 0x00;
+    const MPE_CONFIGURATION_LSB: u8 = 0x06;
+
+    rpn_message_1_byte(
+        manager_channel,
+        MPE_CONFIGURATION_MSB,
+        MPE_CONFIGURATION_LSB,
+        num_member_channels,
+    )
+}
+
 // RPN format reference: https://www.midi.org/specifications-old/item/table-3-control-change-messages-data-bytes-2
 
 const RPN_MSB: u8 = 0x65;
@@ -1183,4 +1454,162 @@ mod test {
         );
         assert_eq!(tuning_message.out_of_range_notes().len(), 4);
     }
+
+    #[test]
+    fn decode_single_note_tuning_change() {
+        let options = SingleNoteTuningChangeOptions {
+            realtime: false,
+            device_id: 55,
+            tuning_program: 66,
+            with_bank_select: Some(77),
+        };
+        let tuning_changes =
+            [(60, 60.25), (61, 59.0), (62, 127.5)].map(|(source, target)| SingleNoteTuningChange {
+                key: PianoKey::from_midi_number(source),
+                target_pitch: Note::from_midi_number(0).pitch() * Ratio::from_semitones(target),
+            });
+        let tuning_message =
+            SingleNoteTuningChangeMessage::from_tuning_changes(&options, tuning_changes).unwrap();
+        let sysex_bytes = tuning_message.sysex_bytes().next().unwrap();
+
+        let (decoded_options, decoded_changes) = match MtsMessage::from_sysex_bytes(sysex_bytes) {
+            Ok(MtsMessage::SingleNoteTuningChange {
+                options,
+                tuning_changes,
+            }) => (options, tuning_changes),
+            other => panic!("Unexpected result: {other:?}"),
+        };
+
+        assert!(!decoded_options.realtime);
+        assert_eq!(decoded_options.device_id, 55);
+        assert_eq!(decoded_options.tuning_program, 66);
+        assert_eq!(decoded_options.with_bank_select, Some(77));
+        assert_eq!(decoded_changes.len(), 3);
+        for (decoded, original) in decoded_changes.iter().zip(tuning_changes) {
+            assert_eq!(decoded.key, original.key);
+            assert_approx_eq(decoded.target_pitch, original.target_pitch);
+        }
+    }
+
+    #[test]
+    fn decode_scale_octave_tuning() {
+        for format in [
+            ScaleOctaveTuningFormat::OneByte,
+            ScaleOctaveTuningFormat::TwoByte,
+        ] {
+            let options = ScaleOctaveTuningOptions {
+                realtime: true,
+                device_id: 11,
+                channels: HashSet::from([0, 7, 15]).into(),
+                format,
+            };
+            let octave_tuning = ScaleOctaveTuning {
+                c: Ratio::from_cents(-64.0),
+                d: Ratio::from_cents(10.0),
+                b: Ratio::from_cents(63.0),
+                ..Default::default()
+            };
+            let tuning_message =
+                ScaleOctaveTuningMessage::from_octave_tuning(&options, &octave_tuning).unwrap();
+
+            let message = MtsMessage::from_sysex_bytes(tuning_message.sysex_bytes()).unwrap();
+            match &message {
+                MtsMessage::ScaleOctaveTuning { options, .. } => {
+                    assert!(options.realtime);
+                    assert_eq!(options.device_id, 11);
+                    assert!(
+                        matches!(&options.channels, Channels::Some(channels) if channels == &HashSet::from([0, 7, 15]))
+                    );
+                }
+                other => panic!("Unexpected message: {other:?}"),
+            }
+
+            let tuning_changes = message.tuning_changes();
+            assert_eq!(tuning_changes.len(), 128);
+            for (midi_number, detuning) in [(0, -64.0), (1, 0.0), (62, 10.0), (71, 63.0)] {
+                let note = Note::from_midi_number(midi_number);
+                assert_eq!(
+                    tuning_changes[midi_number as usize].key,
+                    note.as_piano_key()
+                );
+                assert_approx_eq(
+                    tuning_changes[midi_number as usize].target_pitch,
+                    note.pitch() * Ratio::from_cents(detuning),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_bulk_tuning_dump() {
+        let mut sysex_bytes = vec![0xf0, 0x7e, 0x7f, 0x08, 0x01, 3];
+        sysex_bytes.extend(b"31-EDO          ");
+        for midi_number in 0..128 {
+            match midi_number {
+                0 => sysex_bytes.extend([0x7f, 0x7f, 0x7f]),
+                69 => sysex_bytes.extend([69, 25, 5]),
+                _ => sysex_bytes.extend([midi_number, 0, 0]),
+            }
+        }
+        let checksum = sysex_bytes[1..]
+            .iter()
+            .fold(0, |checksum, byte| checksum ^ byte)
+            & 0x7f;
+        sysex_bytes.extend([checksum, 0xf7]);
+
+        let message = MtsMessage::from_sysex_bytes(&sysex_bytes).unwrap();
+        match &message {
+            MtsMessage::BulkTuningDump {
+                device_id,
+                tuning_program,
+                name,
+                pitches,
+            } => {
+                assert_eq!((*device_id, *tuning_program), (0x7f, 3));
+                assert_eq!(name.trim_end(), "31-EDO");
+                assert_eq!(pitches.len(), 128);
+                assert!(pitches[0].is_none());
+            }
+            other => panic!("Unexpected message: {other:?}"),
+        }
+
+        let tuning_changes = message.tuning_changes();
+        assert_eq!(tuning_changes.len(), 127);
+        assert_eq!(tuning_changes[68].key, PianoKey::from_midi_number(69));
+        assert_approx_eq(tuning_changes[68].target_pitch, Pitch::from_hz(445.0));
+
+        let checksum_index = sysex_bytes.len() - 2;
+        sysex_bytes[checksum_index] ^= 1;
+        assert_eq!(
+            MtsMessage::from_sysex_bytes(&sysex_bytes).unwrap_err(),
+            MtsDecodeError::ChecksumMismatch
+        );
+    }
+
+    #[test]
+    fn decode_invalid_messages() {
+        let decode = |sysex_bytes: &[u8]| MtsMessage::from_sysex_bytes(sysex_bytes).unwrap_err();
+
+        assert_eq!(
+            decode(&[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7]),
+            MtsDecodeError::NotAnMtsMessage
+        );
+        assert_eq!(
+            decode(&[0xf0, 0x7e, 0x7f, 0x08, 0x03, 0x00, 0xf7]),
+            MtsDecodeError::UnsupportedMessageType(0x03)
+        );
+        assert_eq!(
+            decode(&[0xf0, 0x7f, 0x7f, 0x08, 0x02, 0, 2, 69, 69, 25, 5, 0xf7]),
+            MtsDecodeError::InvalidLength
+        );
+        assert_eq!(
+            decode(&[0xf0, 0x7e, 0x7f, 0x08, 0x08, 0x03, 0x7f, 0x7f, 64, 0xf7]),
+            MtsDecodeError::InvalidLength
+        );
+    }
+
+    fn assert_approx_eq(left: Pitch, right: Pitch) {
+        let deviation = Ratio::between_pitches(left, right).as_cents().abs();
+        assert!(deviation < 0.01, "{left:?} != {right:?}");
+    }
 }
@@ -1,6 +1,9 @@
 //! Abstractions for working with notes, letters and octaves.
 
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 
 use crate::{
     key::PianoKey,
@@ -125,6 +128,25 @@ impl Note {
         )
     }
 
+    /// Renders `self` in scientific pitch notation using the given [`NoteNames`] convention.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::note::Note;
+    /// # use tune::note::NoteNames;
+    /// assert_eq!(Note::from_midi_number(70).named(NoteNames::German).to_string(), "Ais/B 4");
+    /// assert_eq!(Note::from_midi_number(71).named(NoteNames::German).to_string(), "H 4");
+    /// assert_eq!(format!("{:-}", Note::from_midi_number(70).named(NoteNames::Solfege)), "Sib 4");
+    /// assert_eq!(Note::from_midi_number(60).named(NoteNames::Byzantine).to_string(), "Ni 4");
+    /// ```
+    pub fn named(self, note_names: NoteNames) -> NamedNote {
+        NamedNote {
+            note: self,
+            note_names,
+        }
+    }
+
     /// Retrieves the associated [`PianoKey`] assuming standard 12-EDO tuning.
     pub fn as_piano_key(self) -> PianoKey {
         PianoKey::from_midi_number(self.midi_number())
@@ -250,7 +272,93 @@ impl Pitched for Note {
 /// ```
 impl Display for Note {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let (letter, octave) = self.letter_and_octave();
+        self.named(NoteNames::English).fmt(f)
+    }
+}
+
+/// Parses a note letter followed by any number of accidentals (# or b) and an octave number.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::note::Note;
+/// let midi_number = |s: &str| s.parse::<Note>().map(|note| note.midi_number());
+///
+/// assert_eq!(midi_number("C4"), Ok(60));
+/// assert_eq!(midi_number("a4"), Ok(69));
+/// assert_eq!(midi_number("F#3"), Ok(54));
+/// assert_eq!(midi_number("Bb2"), Ok(46));
+/// assert_eq!(midi_number("Cbb0"), Ok(10));
+/// assert_eq!(midi_number("C-1"), Ok(0));
+///
+/// assert_eq!(
+///     midi_number("H4"),
+///     Err("Invalid note 'H4': Should start with a note letter from A to G".to_owned())
+/// );
+/// assert_eq!(
+///     midi_number("Cx4"),
+///     Err("Invalid note 'Cx4': Accidentals should be # or b".to_owned())
+/// );
+/// assert_eq!(
+///     midi_number("C"),
+///     Err("Invalid note 'C': Should end with an octave number, e.g. C4".to_owned())
+/// );
+/// ```
+impl FromStr for Note {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let semitone = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => 0,
+            Some('D') => 2,
+            Some('E') => 4,
+            Some('F') => 5,
+            Some('G') => 7,
+            Some('A') => 9,
+            Some('B') => 11,
+            _ => {
+                return Err(format!(
+                    "Invalid note '{s}': Should start with a note letter from A to G"
+                ))
+            }
+        };
+
+        let rest = chars.as_str();
+        let (accidentals, octave) = rest.split_at(
+            rest.find(|c: char| c == '-' || c.is_ascii_digit())
+                .unwrap_or(rest.len()),
+        );
+        let alteration = accidentals
+            .chars()
+            .map(|c| match c {
+                '#' => Ok(1),
+                'b' => Ok(-1),
+                _ => Err(format!("Invalid note '{s}': Accidentals should be # or b")),
+            })
+            .sum::<Result<i32, _>>()?;
+        let octave = octave.parse::<i16>().map_err(|_| {
+            format!("Invalid note '{s}': Should end with an octave number, e.g. C4")
+        })?;
+
+        Ok(NoteLetter::C
+            .in_octave(octave)
+            .plus_semitones(semitone + alteration))
+    }
+}
+
+/// A [`Note`] rendered using a specific [`NoteNames`] convention. Created by [`Note::named`].
+#[derive(Copy, Clone, Debug)]
+pub struct NamedNote {
+    note: Note,
+    note_names: NoteNames,
+}
+
+/// Supports the same format flags as the [`Display`] impl of [`Note`].
+impl Display for NamedNote {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (letter, octave) = self.note.letter_and_octave();
+        let letter = letter.named(self.note_names);
 
         let formatted_note = match (f.sign_plus(), f.sign_minus()) {
             (false, false) => format!("{letter}"),
@@ -293,49 +401,151 @@ impl NoteLetter {
     pub fn in_octave(self, octave: impl Into<Octave>) -> Note {
         Note::from_letter_and_octave(self, octave)
     }
+
+    /// Renders `self` using the given [`NoteNames`] convention.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::note::NoteLetter;
+    /// # use tune::note::NoteNames;
+    /// assert_eq!(NoteLetter::B.named(NoteNames::German).to_string(), "H");
+    /// assert_eq!(NoteLetter::Ash.named(NoteNames::German).to_string(), "Ais/B");
+    /// assert_eq!(NoteLetter::G.named(NoteNames::Solfege).to_string(), "Sol");
+    /// assert_eq!(NoteLetter::Fsh.named(NoteNames::Byzantine).to_string(), "Ga#/Dib");
+    ///
+    /// // Format flags
+    /// assert_eq!(format!("{:+}", NoteLetter::Dsh.named(NoteNames::German)), "Dis");
+    /// assert_eq!(format!("{:-}", NoteLetter::Dsh.named(NoteNames::German)), "Es");
+    /// assert_eq!(format!("{:>6}", NoteLetter::Ash.named(NoteNames::Solfege)), "La#/Sib");
+    /// ```
+    pub fn named(self, note_names: NoteNames) -> NamedNoteLetter {
+        NamedNoteLetter {
+            letter: self,
+            note_names,
+        }
+    }
+
+    fn semitone(self) -> usize {
+        match self {
+            NoteLetter::C => 0,
+            NoteLetter::Csh => 1,
+            NoteLetter::D => 2,
+            NoteLetter::Dsh => 3,
+            NoteLetter::E => 4,
+            NoteLetter::F => 5,
+            NoteLetter::Fsh => 6,
+            NoteLetter::G => 7,
+            NoteLetter::Gsh => 8,
+            NoteLetter::A => 9,
+            NoteLetter::Ash => 10,
+            NoteLetter::B => 11,
+        }
+    }
 }
 
+/// Format flags: `+` prints the sharp spelling, `-` prints the flat spelling. By default, both spellings are printed.
 impl Display for NoteLetter {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        enum Sign {
-            Sharp,
-            Flat,
-            Both,
+        self.named(NoteNames::English).fmt(f)
+    }
+}
+
+/// Convention for naming the notes within an octave.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum NoteNames {
+    /// C, C#/Db, D, ..., B
+    English,
+    /// C, Cis/Des, D, ..., Ais/B, H
+    German,
+    /// Fixed-do solfège: Do, Do#/Reb, Re, ..., Si
+    Solfege,
+    /// Byzantine note names: Ni, Ni#/Pab, Pa, ..., Zo
+    Byzantine,
+}
+
+impl NoteNames {
+    /// The (sharp, flat) spellings of the 12 semitones, starting at C.
+    fn spellings(self) -> [(&'static str, &'static str); 12] {
+        match self {
+            NoteNames::English => [
+                ("C", "C"),
+                ("C#", "Db"),
+                ("D", "D"),
+                ("D#", "Eb"),
+                ("E", "E"),
+                ("F", "F"),
+                ("F#", "Gb"),
+                ("G", "G"),
+                ("G#", "Ab"),
+                ("A", "A"),
+                ("A#", "Bb"),
+                ("B", "B"),
+            ],
+            NoteNames::German => [
+                ("C", "C"),
+                ("Cis", "Des"),
+                ("D", "D"),
+                ("Dis", "Es"),
+                ("E", "E"),
+                ("F", "F"),
+                ("Fis", "Ges"),
+                ("G", "G"),
+                ("Gis", "As"),
+                ("A", "A"),
+                ("Ais", "B"),
+                ("H", "H"),
+            ],
+            NoteNames::Solfege => [
+                ("Do", "Do"),
+                ("Do#", "Reb"),
+                ("Re", "Re"),
+                ("Re#", "Mib"),
+                ("Mi", "Mi"),
+                ("Fa", "Fa"),
+                ("Fa#", "Solb"),
+                ("Sol", "Sol"),
+                ("Sol#", "Lab"),
+                ("La", "La"),
+                ("La#", "Sib"),
+                ("Si", "Si"),
+            ],
+            NoteNames::Byzantine => [
+                ("Ni", "Ni"),
+                ("Ni#", "Pab"),
+                ("Pa", "Pa"),
+                ("Pa#", "Voub"),
+                ("Vou", "Vou"),
+                ("Ga", "Ga"),
+                ("Ga#", "Dib"),
+                ("Di", "Di"),
+                ("Di#", "Keb"),
+                ("Ke", "Ke"),
+                ("Ke#", "Zob"),
+                ("Zo", "Zo"),
+            ],
         }
+    }
+}
 
-        let sign = match (f.sign_plus(), f.sign_minus()) {
-            (false, false) => Sign::Both,
-            (true, false) => Sign::Sharp,
-            (false, true) => Sign::Flat,
-            (true, true) => unreachable!("Impossible format string"),
-        };
+/// A [`NoteLetter`] rendered using a specific [`NoteNames`] convention. Created by [`NoteLetter::named`].
+#[derive(Copy, Clone, Debug)]
+pub struct NamedNoteLetter {
+    letter: NoteLetter,
+    note_names: NoteNames,
+}
 
-        let note_name = match (self, sign) {
-            (NoteLetter::C, _) => "C",
-            (NoteLetter::Csh, Sign::Both) => "C#/Db",
-            (NoteLetter::Csh, Sign::Sharp) => "C#",
-            (NoteLetter::Csh, Sign::Flat) => "Db",
-            (NoteLetter::D, _) => "D",
-            (NoteLetter::Dsh, Sign::Both) => "D#/Eb",
-            (NoteLetter::Dsh, Sign::Sharp) => "D#",
-            (NoteLetter::Dsh, Sign::Flat) => "Eb",
-            (NoteLetter::E, _) => "E",
-            (NoteLetter::F, _) => "F",
-            (NoteLetter::Fsh, Sign::Both) => "F#/Gb",
-            (NoteLetter::Fsh, Sign::Sharp) => "F#",
-            (NoteLetter::Fsh, Sign::Flat) => "Gb",
-            (NoteLetter::G, _) => "G",
-            (NoteLetter::Gsh, Sign::Both) => "G#/Ab",
-            (NoteLetter::Gsh, Sign::Sharp) => "G#",
-            (NoteLetter::Gsh, Sign::Flat) => "Ab",
-            (NoteLetter::A, _) => "A",
-            (NoteLetter::Ash, Sign::Both) => "A#/Bb",
-            (NoteLetter::Ash, Sign::Sharp) => "A#",
-            (NoteLetter::Ash, Sign::Flat) => "Bb",
-            (NoteLetter::B, _) => "B",
-        };
+/// Supports the same format flags as the [`Display`] impl of [`NoteLetter`].
+impl Display for NamedNoteLetter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (sharp, flat) = self.note_names.spellings()[self.letter.semitone()];
 
-        f.pad(note_name)
+        match (f.sign_plus(), f.sign_minus()) {
+            (false, false) if sharp != flat => f.pad(&format!("{sharp}/{flat}")),
+            (false, false) | (true, false) => f.pad(sharp),
+            (false, true) => f.pad(flat),
+            (true, true) => unreachable!("Impossible format string"),
+        }
     }
 }
 
@@ -3,12 +3,12 @@
 use std::{
     cmp::Ordering,
     fmt::{self, Display, Formatter},
-    ops::{Div, Mul},
+    ops::{Div, Mul, RangeInclusive},
     str::FromStr,
 };
 
 use crate::{
-    math, parse,
+    parse,
     tuning::{Approximation, Tuning},
 };
 
@@ -380,7 +380,7 @@ impl Ratio {
     ///
     /// The largest acceptable numerator or denominator can be controlled using the `odd_limit` parameter.
     /// Only odd factors are compared against the `odd_limit` which means that 12 is 3, effectively, while 11 stays 11.
-    /// Read the documentation of [`math::odd_factors_u16`] for more examples.
+    /// Read the documentation of [`odd_factors_u64`](crate::math::odd_factors_u64) for more examples.
     ///
     /// # Examples
     ///
@@ -422,7 +422,18 @@ impl Ratio {
     /// assert_eq!(f.num_octaves, -1);
     /// assert_approx_eq!(f.deviation.as_cents(), 0.0);
     /// ```
-    pub fn nearest_fraction(self, odd_limit: u16) -> NearestFraction {
+    ///
+    /// High odd limits are supported as well.
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// let schisma_above_a_fifth = Ratio::from_float(3.0 / 2.0 * 32805.0 / 32768.0);
+    /// let f = schisma_above_a_fifth.nearest_fraction(98415);
+    /// assert_eq!((f.numer, f.denom), (98415, 65536));
+    /// assert_approx_eq!(f.deviation.as_cents(), 0.0);
+    /// ```
+    pub fn nearest_fraction(self, odd_limit: u32) -> NearestFraction {
         NearestFraction::for_ratio(self, odd_limit)
     }
 }
@@ -624,9 +635,9 @@ fn parse_ratio_as_float(s: &str, name: &str) -> Result<f64, String> {
 #[derive(Copy, Clone, Debug)]
 pub struct NearestFraction {
     /// The numerator of the approximation.
-    pub numer: u16,
+    pub numer: u64,
     /// The denominator of the approximation.
-    pub denom: u16,
+    pub denom: u64,
     /// The deviation of the target value from the approximation.
     pub deviation: Ratio,
     /// The number of even factors that have been removed from the approximation to account for octave equivalence.
@@ -634,9 +645,13 @@ pub struct NearestFraction {
 }
 
 impl NearestFraction {
-    fn for_ratio(ratio: Ratio, odd_limit: u16) -> Self {
+    fn for_ratio(ratio: Ratio, odd_limit: u32) -> Self {
+        let odd_limit = u64::from(odd_limit);
+
         let num_octaves = ratio.as_octaves().floor() as i32;
         let target_ratio = ratio.deviation_from(Ratio::from_octaves(num_octaves));
+        let ratio_of =
+            |fraction: (u64, u64)| Ratio::from_float(fraction.0 as f64 / fraction.1 as f64);
 
         let mut left = (0, 1);
         let mut right = (1, 0);
@@ -644,34 +659,69 @@ impl NearestFraction {
         let mut best = (0, 0);
         let mut best_deviation = Ratio::from_float(f64::INFINITY);
 
-        while let Some(mid) =
-            u16::checked_add(left.0, right.0).zip(u16::checked_add(left.1, right.1))
-        {
-            let odd_factors_numer = math::odd_factors_u16(mid.0);
-            let odd_factors_denom = math::odd_factors_u16(mid.1);
+        // Walk down the Stern-Brocot tree. All mediants on the way are considered but consecutive steps in the same direction (semiconvergents) are processed at once s.t. the number of iterations grows logarithmically with the odd limit.
+        loop {
+            let first_mid = (left.0 + right.0, left.1 + right.1);
+            let ordering = target_ratio.partial_cmp(&ratio_of(first_mid));
+
+            // The mediants of this run are base + j * step with j >= 1
+            let (base, step) = match ordering {
+                Some(Ordering::Less) => (right, left),
+                Some(Ordering::Greater) => (left, right),
+                Some(Ordering::Equal) | None => (first_mid, (0, 0)),
+            };
+
+            // Once both components exceed the odd limit, at least one of them is odd (the mediants are reduced fractions) and all subsequent mediants are out of range.
+            // Moreover, the mediants are exactly representable as f64 as long as they do not exceed 2^53.
+            let num_considered = match (
+                num_steps_within(base.0, step.0, odd_limit),
+                num_steps_within(base.1, step.1, odd_limit),
+            ) {
+                (Some(numer_steps), Some(denom_steps)) => numer_steps.max(denom_steps),
+                (Some(steps), None) | (None, Some(steps)) => steps,
+                (None, None) => 0,
+            }
+            .min(num_steps_within(base.0, step.0, MAX_EXACT_F64_INTEGER).unwrap_or(0))
+            .min(num_steps_within(base.1, step.1, MAX_EXACT_F64_INTEGER).unwrap_or(0));
 
-            if odd_factors_numer > odd_limit && odd_factors_denom > odd_limit {
+            if num_considered == 0 {
                 break;
             }
 
-            let mid_ratio = Ratio::from_float(f64::from(mid.0) / f64::from(mid.1));
+            let mid_at =
+                |num_steps: u64| (base.0 + num_steps * step.0, base.1 + num_steps * step.1);
+
+            // The mediants approach the target monotonically until the direction changes
+            let num_steps = match ordering {
+                Some(Ordering::Less) | Some(Ordering::Greater) => {
+                    let (mut lower, mut upper) = (1, num_considered);
+                    while lower < upper {
+                        let probe = upper - (upper - lower) / 2;
+                        if target_ratio.partial_cmp(&ratio_of(mid_at(probe))) == ordering {
+                            lower = probe;
+                        } else {
+                            upper = probe - 1;
+                        }
+                    }
+                    lower
+                }
+                Some(Ordering::Equal) | None => 1,
+            };
 
-            if odd_factors_numer <= odd_limit && odd_factors_denom <= odd_limit {
-                let mid_deviation = target_ratio.deviation_from(mid_ratio);
+            // The mediant closest to the target is the last one that complies with the odd limit
+            if let Some(num_steps) = last_step_within_odd_limit(base, step, num_steps, odd_limit) {
+                let mid = mid_at(num_steps);
+                let mid_deviation = target_ratio.deviation_from(ratio_of(mid));
                 if mid_deviation.abs() < best_deviation.abs() {
                     best = mid;
                     best_deviation = mid_deviation;
                 }
             }
 
-            match target_ratio.partial_cmp(&mid_ratio) {
-                Some(Ordering::Less) => {
-                    right = mid;
-                }
-                Some(Ordering::Greater) => {
-                    left = mid;
-                }
-                Some(Ordering::Equal) | None => break,
+            match ordering {
+                Some(Ordering::Less) if num_steps < num_considered => right = mid_at(num_steps),
+                Some(Ordering::Greater) if num_steps < num_considered => left = mid_at(num_steps),
+                _ => break,
             }
         }
 
@@ -684,6 +734,89 @@ impl NearestFraction {
     }
 }
 
+const MAX_EXACT_F64_INTEGER: u64 = 1 << f64::MANTISSA_DIGITS;
+
+/// Returns the largest `j` s.t. `base + j * step <= max`. `None` if `base` itself exceeds `max`.
+fn num_steps_within(base: u64, step: u64, max: u64) -> Option<u64> {
+    let headroom = max.checked_sub(base)?;
+    Some(headroom.checked_div(step).unwrap_or(u64::MAX))
+}
+
+/// Returns the largest `j` in `1..=num_steps` s.t. the odd factors of both components of `base + j * step` do not exceed `odd_limit`.
+///
+/// Requires at least one component to stay within `odd_limit` for all `j` in `1..=num_steps`.
+fn last_step_within_odd_limit(
+    base: (u64, u64),
+    step: (u64, u64),
+    num_steps: u64,
+    odd_limit: u64,
+) -> Option<u64> {
+    let numer_steps = num_steps_within(base.0, step.0, odd_limit).unwrap_or(0);
+    let denom_steps = num_steps_within(base.1, step.1, odd_limit).unwrap_or(0);
+
+    // Up to here, both components are within the odd limit
+    let num_steps_unconditionally = numer_steps.min(denom_steps).min(num_steps);
+    if num_steps_unconditionally == num_steps {
+        return Some(num_steps).filter(|&num_steps| num_steps > 0);
+    }
+
+    // Beyond, one component exceeds the odd limit unless it contains enough even factors
+    let (exceeding_base, exceeding_step) = if numer_steps < denom_steps {
+        (base.0, step.0)
+    } else {
+        (base.1, step.1)
+    };
+
+    last_step_with_small_odd_factors(
+        exceeding_base,
+        exceeding_step,
+        num_steps_unconditionally + 1..=num_steps,
+        odd_limit,
+    )
+    .or(Some(num_steps_unconditionally).filter(|&num_steps| num_steps > 0))
+}
+
+/// Returns the largest `j` in `steps` s.t. the odd factors of `base + j * step` do not exceed `odd_limit`.
+///
+/// The odd factors of a number `n` do not exceed `odd_limit` iff `n` is divisible by `2^k` and `n <= odd_limit * 2^k` for some `k`.
+fn last_step_with_small_odd_factors(
+    base: u64,
+    step: u64,
+    steps: RangeInclusive<u64>,
+    odd_limit: u64,
+) -> Option<u64> {
+    (0..u64::BITS)
+        .filter_map(|num_even_factors| {
+            let max_value = odd_limit.saturating_mul(1 << num_even_factors);
+            let last_step = num_steps_within(base, step, max_value)?.min(*steps.end());
+
+            // Solve base + j * step = 0 (mod 2^num_even_factors) for j
+            let step_even_factors = step.trailing_zeros().min(num_even_factors);
+            if base.trailing_zeros() < step_even_factors {
+                return None;
+            }
+            let modulus_bits = num_even_factors - step_even_factors;
+            let mask = (1 << modulus_bits) - 1;
+            let remainder = (base >> step_even_factors)
+                .wrapping_neg()
+                .wrapping_mul(inverse_of_odd(step >> step_even_factors))
+                & mask;
+
+            last_step
+                .checked_sub(last_step.wrapping_sub(remainder) & mask)
+                .filter(|step| steps.contains(step))
+        })
+        .max()
+}
+
+/// Computes the multiplicative inverse of an odd number modulo 2^64 using Newton's method.
+fn inverse_of_odd(number: u64) -> u64 {
+    // Each iteration doubles the number of correct bits, starting with 3 bits
+    (0..5).fold(number, |inverse, _| {
+        inverse.wrapping_mul(2u64.wrapping_sub(number.wrapping_mul(inverse)))
+    })
+}
+
 impl Display for NearestFraction {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let formatted = format!(
@@ -872,4 +1005,79 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn find_nearest_fraction_like_stepwise_search() {
+        for odd_limit in [0, 1, 3, 11, 15, 99, 1001, 65535, 100_001] {
+            for ratio in iter::successors(Some(0.5), |prev| Some(prev * 1.0123)).take(150) {
+                let ratio = Ratio::from_float(ratio);
+                let expected = stepwise_nearest_fraction(ratio, odd_limit);
+                let actual = ratio.nearest_fraction(odd_limit);
+                assert_eq!(
+                    (actual.numer, actual.denom, actual.num_octaves),
+                    expected,
+                    "ratio = {ratio}, odd_limit = {odd_limit}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn find_nearest_fraction_with_high_odd_limit() {
+        let f = Ratio::from_float(1.5 + 1e-12).nearest_fraction(u32::MAX);
+        assert_eq!((f.numer, f.denom, f.num_octaves), (3, 2, 0));
+        assert!(f.deviation.as_cents().abs() < 1e-8);
+
+        let f = Ratio::from_float(1.0 + 1e-12).nearest_fraction(u32::MAX);
+        assert_eq!((f.numer, f.denom, f.num_octaves), (1, 1, 0));
+
+        let f = Ratio::from_float(2.0 - 1e-12).nearest_fraction(u32::MAX);
+        assert_eq!((f.numer, f.denom, f.num_octaves), (2, 1, 0));
+    }
+
+    /// Reference implementation visiting every mediant on the way down the Stern-Brocot tree.
+    fn stepwise_nearest_fraction(ratio: Ratio, odd_limit: u32) -> (u64, u64, i32) {
+        let odd_limit = u64::from(odd_limit);
+
+        let num_octaves = ratio.as_octaves().floor() as i32;
+        let target_ratio = ratio.deviation_from(Ratio::from_octaves(num_octaves));
+
+        let mut left = (0u64, 1u64);
+        let mut right = (1u64, 0u64);
+
+        let mut best = (0, 0);
+        let mut best_deviation = Ratio::from_float(f64::INFINITY);
+
+        loop {
+            let mid = (left.0 + right.0, left.1 + right.1);
+            if mid.0.max(mid.1) > MAX_EXACT_F64_INTEGER {
+                break;
+            }
+
+            let odd_factors_numer = crate::math::odd_factors_u64(mid.0);
+            let odd_factors_denom = crate::math::odd_factors_u64(mid.1);
+
+            if odd_factors_numer > odd_limit && odd_factors_denom > odd_limit {
+                break;
+            }
+
+            let mid_ratio = Ratio::from_float(mid.0 as f64 / mid.1 as f64);
+
+            if odd_factors_numer <= odd_limit && odd_factors_denom <= odd_limit {
+                let mid_deviation = target_ratio.deviation_from(mid_ratio);
+                if mid_deviation.abs() < best_deviation.abs() {
+                    best = mid;
+                    best_deviation = mid_deviation;
+                }
+            }
+
+            match target_ratio.partial_cmp(&mid_ratio) {
+                Some(Ordering::Less) => right = mid,
+                Some(Ordering::Greater) => left = mid,
+                Some(Ordering::Equal) | None => break,
+            }
+        }
+
+        (best.0, best.1, num_octaves)
+    }
 }
@@ -5,6 +5,7 @@ mod import;
 use std::{
     borrow::Borrow,
     fmt::{self, Display, Formatter, Write},
+    hash::{Hash, Hasher},
     io::Read,
     ops::{Neg, Range},
     str::FromStr,
@@ -15,12 +16,15 @@ use crate::{
     math,
     note::{Note, PitchedNote},
     parse,
-    pitch::{Pitch, Ratio},
-    tuning::{Approximation, KeyboardMapping, Scale, Tuning},
+    pitch::{Pitch, Pitched, Ratio},
+    tuning::{Approximation, ConcertPitch, KeyboardMapping, Scale, Tuning},
 };
 
 pub use self::import::*;
 
+/// Number of scale degrees in each direction from the root covered exactly by [`Scl::with_railsback_curve`].
+pub const RAILSBACK_CURVE_RANGE: i32 = 72;
+
 /// Scale format according to <http://www.huygens-fokker.org/scala/scl_format.html>.
 ///
 /// The [`Scl`] format describes a periodic scale in *relative* pitches. You can access those pitches using [`Scl::relative_pitch_of`].
@@ -146,6 +150,90 @@ impl Scl {
             .stretched_by(ratio_within_period)
     }
 
+    /// Returns whether the items of the scale are sorted in strictly increasing order, starting above the unison.
+    ///
+    /// If not, the scale degrees used by [`Scl::relative_pitch_of`] and [`Scl::sorted_relative_pitch_of`] differ.
+    /// Use [`Scl::sorted_degree_of`] and [`Scl::degree_of_sorted`] to convert between the two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::scala::Scl;
+    /// let sorted_scl = Scl::builder().push_cents(100.0).push_cents(200.0).build().unwrap();
+    /// assert!(sorted_scl.is_strictly_increasing());
+    ///
+    /// let unsorted_scl = Scl::builder().push_cents(150.0).push_cents(100.0).push_cents(200.0).build().unwrap();
+    /// assert!(!unsorted_scl.is_strictly_increasing());
+    ///
+    /// let duplicate_scl = Scl::builder().push_cents(100.0).push_cents(100.0).push_cents(200.0).build().unwrap();
+    /// assert!(!duplicate_scl.is_strictly_increasing());
+    /// ```
+    pub fn is_strictly_increasing(&self) -> bool {
+        self.pitch_values
+            .iter()
+            .try_fold(Ratio::default(), |prev, pitch_value| {
+                let curr = pitch_value.as_ratio();
+                if curr > prev {
+                    Some(curr)
+                } else {
+                    None
+                }
+            })
+            .is_some()
+    }
+
+    /// Converts a scale degree as used by [`Scl::relative_pitch_of`] into the corresponding degree in ascending order as used by [`Scl::sorted_relative_pitch_of`].
+    ///
+    /// Items with equal pitches keep their original order. The period is always mapped onto itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::scala::Scl;
+    /// let scl = Scl::builder()
+    ///     .push_cents(100.0)
+    ///     .push_cents(50.0)
+    ///     .push_cents(100.0)
+    ///     .push_cents(150.0)
+    ///     .build().unwrap();
+    ///
+    /// let sorted_degrees = Vec::from_iter((-1..6).map(|degree| scl.sorted_degree_of(degree)));
+    /// assert_eq!(sorted_degrees, [-1, 0, 2, 1, 3, 4, 6]);
+    ///
+    /// for degree in -10..10 {
+    ///     let sorted_degree = scl.sorted_degree_of(degree);
+    ///     assert_eq!(scl.degree_of_sorted(sorted_degree), degree);
+    ///     assert_eq!(scl.sorted_relative_pitch_of(sorted_degree), scl.relative_pitch_of(degree));
+    /// }
+    /// ```
+    pub fn sorted_degree_of(&self, degree: i32) -> i32 {
+        let (num_periods, degree_within_period) = math::i32_dr_u(degree, self.num_items());
+        let sorted_degree_within_period = if degree_within_period == 0 {
+            0
+        } else {
+            let index = usize::from(degree_within_period - 1);
+            self.pitch_value_ordering
+                .iter()
+                .position(|&probe| probe == index)
+                .expect("Ordering should be a permutation")
+                + 1
+        };
+        num_periods * i32::from(self.num_items()) + sorted_degree_within_period as i32
+    }
+
+    /// Converts a scale degree in ascending order as used by [`Scl::sorted_relative_pitch_of`] into the corresponding degree as used by [`Scl::relative_pitch_of`].
+    ///
+    /// This is the inverse operation of [`Scl::sorted_degree_of`].
+    pub fn degree_of_sorted(&self, sorted_degree: i32) -> i32 {
+        let (num_periods, degree_within_period) = math::i32_dr_u(sorted_degree, self.num_items());
+        let unsorted_degree_within_period = if degree_within_period == 0 {
+            0
+        } else {
+            self.pitch_value_ordering[usize::from(degree_within_period - 1)] + 1
+        };
+        num_periods * i32::from(self.num_items()) + unsorted_degree_within_period as i32
+    }
+
     /// Finds the approximate degree of a relative pitch without requiring any [`Kbm`] reference.
     ///
     /// # Examples
@@ -274,6 +362,147 @@ impl Scl {
         }
     }
 
+    /// Stretches all intervals of the scale s.t. an octave becomes larger by `stretch`, e.g. to compensate for the inharmonicity of piano strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::scala::Scl;
+    /// let scl = Scl::builder()
+    ///     .push_cents(400.0)
+    ///     .push_cents(700.0)
+    ///     .push_cents(1200.0)
+    ///     .build_with_description("Major triad")
+    ///     .unwrap();
+    ///
+    /// let stretched = scl.with_stretch(Ratio::from_cents(12.0)).unwrap();
+    ///
+    /// assert_eq!(stretched.description(), "Major triad, stretched by +12.0c");
+    /// assert_approx_eq!(stretched.relative_pitch_of(1).as_cents(), 404.0);
+    /// assert_approx_eq!(stretched.relative_pitch_of(2).as_cents(), 707.0);
+    /// assert_approx_eq!(stretched.relative_pitch_of(3).as_cents(), 1212.0);
+    /// assert_approx_eq!(stretched.relative_pitch_of(-3).as_cents(), -1212.0);
+    /// ```
+    pub fn with_stretch(&self, stretch: Ratio) -> Result<Scl, SclBuildError> {
+        let factor = Ratio::octave().stretched_by(stretch).as_octaves();
+
+        let mut builder = Scl::builder();
+        for degree in 1..=i32::from(self.num_items) {
+            builder = builder.push_cents(self.relative_pitch_of(degree).as_cents() * factor);
+        }
+        builder.build_with_description(format!(
+            "{}, stretched by {:+.1}c",
+            self.description,
+            stretch.as_cents()
+        ))
+    }
+
+    /// Applies a Railsback curve, i.e. a stretch that increases with the distance to the root, as typically found in piano tunings.
+    ///
+    /// The deviation from the original scale is `deviation` at one octave above the root and grows with the cube of the number of octaves, e.g. a `deviation` of 0.5c yields +4c two octaves above and -13.5c three octaves below the root.
+    ///
+    /// Since an [`Scl`] is periodic, the curve is unrolled over the number of periods that spans at least [`RAILSBACK_CURVE_RANGE`] degrees in each direction.
+    /// Outside of this range, the curve repeats itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::scala::Scl;
+    /// let scl = Scl::builder().push_cents(100.0).build().unwrap();
+    ///
+    /// let curved = scl.with_railsback_curve(Ratio::from_cents(0.5)).unwrap();
+    ///
+    /// assert_eq!(curved.num_items(), 144);
+    /// assert_approx_eq!(curved.relative_pitch_of(0).as_cents(), 0.0);
+    /// assert_approx_eq!(curved.relative_pitch_of(6).as_cents(), 600.0625);
+    /// assert_approx_eq!(curved.relative_pitch_of(12).as_cents(), 1200.5);
+    /// assert_approx_eq!(curved.relative_pitch_of(24).as_cents(), 2404.0);
+    /// assert_approx_eq!(curved.relative_pitch_of(-36).as_cents(), -3613.5);
+    /// ```
+    pub fn with_railsback_curve(&self, deviation: Ratio) -> Result<Scl, SclBuildError> {
+        let num_items = i32::from(self.num_items);
+        let num_periods = (RAILSBACK_CURVE_RANGE + num_items - 1) / num_items;
+        let half_range = num_periods * num_items;
+
+        let curved_pitch_of = |degree| {
+            let relative_pitch = self.relative_pitch_of(degree);
+            relative_pitch.as_cents() + deviation.as_cents() * relative_pitch.as_octaves().powi(3)
+        };
+
+        let new_period = curved_pitch_of(half_range) - curved_pitch_of(-half_range);
+
+        let mut builder = Scl::builder();
+        for degree in 1..=2 * half_range {
+            builder = builder.push_cents(match degree <= half_range {
+                true => curved_pitch_of(degree),
+                false => curved_pitch_of(degree - 2 * half_range) + new_period,
+            });
+        }
+        builder.build_with_description(format!(
+            "{}, Railsback curve of {:+.1}c",
+            self.description,
+            deviation.as_cents()
+        ))
+    }
+
+    /// Interpolates between `self` and `other` item by item in logarithmic space, i.e. on the cents scale.
+    ///
+    /// A `t` of 0 yields the pitches of `self`, a `t` of 1 yields the pitches of `other`. The period is interpolated as well. Values outside of [0, 1] extrapolate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::scala::Scl;
+    /// # use tune::scala::SclBuildError;
+    /// let pythagorean_triad = Scl::builder()
+    ///     .push_fraction(81, 64)
+    ///     .push_fraction(3, 2)
+    ///     .push_int(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let just_triad = Scl::builder()
+    ///     .push_fraction(5, 4)
+    ///     .push_fraction(3, 2)
+    ///     .push_int(2)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let halfway = pythagorean_triad.interpolate(&just_triad, 0.5).unwrap();
+    ///
+    /// assert_approx_eq!(halfway.relative_pitch_of(1).as_cents(), 397.066859);
+    /// assert_approx_eq!(halfway.relative_pitch_of(2).as_cents(), 701.955001);
+    /// assert_approx_eq!(halfway.relative_pitch_of(3).as_cents(), 1200.0);
+    ///
+    /// let dyad = Scl::builder().push_fraction(3, 2).push_int(2).build().unwrap();
+    /// assert_eq!(
+    ///     pythagorean_triad.interpolate(&dyad, 0.5).unwrap_err(),
+    ///     SclBuildError::NumItemsMismatch
+    /// );
+    /// ```
+    pub fn interpolate(&self, other: &Scl, t: f64) -> Result<Scl, SclBuildError> {
+        if self.num_items != other.num_items {
+            return Err(SclBuildError::NumItemsMismatch);
+        }
+
+        let mut builder = Scl::builder();
+        for degree in 1..=i32::from(self.num_items) {
+            let cents = self.relative_pitch_of(degree).as_cents();
+            let other_cents = other.relative_pitch_of(degree).as_cents();
+            builder = builder.push_cents(cents + (other_cents - cents) * t);
+        }
+        builder.build_with_description(format!(
+            "{} -> {} ({:.0}%)",
+            self.description,
+            other.description,
+            t * 100.0
+        ))
+    }
+
     /// Imports the given file in SCL format.
     ///
     /// ```
@@ -332,8 +561,202 @@ impl Scl {
     ///     ["Example scale", "4", "100.000", "146.304", "4/3", "2"]
     /// );
     /// ```
+    ///
+    /// The formatting of the items can be customized for the needs of third-party consumers.
+    ///
+    /// ```
+    /// # use tune::scala::Scl;
+    /// # use tune::scala::SclNotation;
+    /// let scl = Scl::builder()
+    ///     .push_cents(701.955)
+    ///     .push_cents(1000.0)
+    ///     .push_int(2)
+    ///     .build_with_description("Example scale")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     format!("{}", scl.export().with_explicit_denominators(true))
+    ///         .lines()
+    ///         .collect::<Vec<_>>(),
+    ///     ["Example scale", "3", "701.955", "1000.000", "2/1"]
+    /// );
+    /// assert_eq!(
+    ///     format!("{}", scl.export().with_notation(SclNotation::PreferRatios(9)))
+    ///         .lines()
+    ///         .collect::<Vec<_>>(),
+    ///     ["Example scale", "3", "3/2", "1000.000", "2"]
+    /// );
+    /// assert_eq!(
+    ///     format!(
+    ///         "{}",
+    ///         scl.export()
+    ///             .with_notation(SclNotation::Cents)
+    ///             .with_cents_precision(1)
+    ///     )
+    ///     .lines()
+    ///     .collect::<Vec<_>>(),
+    ///     ["Example scale", "3", "702.0", "1000.0", "1200.0"]
+    /// );
+    /// ```
     pub fn export(&self) -> SclExport {
-        SclExport(self)
+        SclExport {
+            scl: self,
+            notation: SclNotation::AsDefined,
+            cents_precision: 3,
+            explicit_denominators: false,
+        }
+    }
+
+    /// Brings the scale into a canonical form.
+    ///
+    /// - Fractions are reduced.
+    /// - Items are sorted in ascending order.
+    /// - Duplicate items and items equal to the unison are removed.
+    /// - If the scale consists of repetitions of a smaller period, only the first repetition is kept.
+    ///
+    /// Duplicates are detected without any tolerance (see [`Scl`]'s [`PartialEq`] implementation) while repeated periods are detected with a tolerance of 1e-6 cents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::scala::Scl;
+    /// let scl = Scl::builder()
+    ///     .push_fraction(9, 6)
+    ///     .push_cents(600.0)
+    ///     .push_fraction(3, 2)
+    ///     .push_int(2)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let canonical = scl.canonicalize();
+    /// assert!(!canonical.was_strictly_increasing);
+    /// assert_eq!(canonical.num_repetitions, 1);
+    /// assert_eq!(
+    ///     canonical.scl.export().to_string().lines().collect::<Vec<_>>(),
+    ///     ["Custom scale", "3", "600.000", "3/2", "2"]
+    /// );
+    ///
+    /// let repeated_scl = Scl::builder()
+    ///     .push_cents(100.0)
+    ///     .push_cents(600.0)
+    ///     .push_cents(700.0)
+    ///     .push_cents(1200.0)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let canonical = repeated_scl.canonicalize();
+    /// assert!(canonical.was_strictly_increasing);
+    /// assert_eq!(canonical.num_repetitions, 2);
+    /// assert_eq!(canonical.scl.num_items(), 2);
+    /// assert_approx_eq!(canonical.scl.period().as_cents(), 600.0);
+    /// ```
+    pub fn canonicalize(&self) -> CanonicalScl {
+        let was_strictly_increasing = self.is_strictly_increasing();
+
+        let mut pitch_values = self
+            .pitch_values
+            .iter()
+            .map(|&pitch_value| pitch_value.reduced())
+            .filter(|pitch_value| pitch_value.as_ratio() != Ratio::default())
+            .collect::<Vec<_>>();
+        pitch_values.sort_by(|a, b| a.as_ratio().total_cmp(&b.as_ratio()));
+        pitch_values.dedup_by_key(|pitch_value| pitch_value.canonical_form());
+
+        let num_items = pitch_values.len();
+        let cents = |index: usize| pitch_values[index].as_ratio().as_cents();
+        let num_items_per_repetition = (1..=num_items)
+            .filter(|num_items_per_repetition| num_items % num_items_per_repetition == 0)
+            .find(|&num_items_per_repetition| {
+                let repeated_period = cents(num_items_per_repetition - 1);
+                (num_items_per_repetition..num_items).all(|index| {
+                    let num_periods = index / num_items_per_repetition;
+                    let expected = num_periods as f64 * repeated_period
+                        + cents(index % num_items_per_repetition);
+                    (cents(index) - expected).abs() < 1e-6
+                })
+            })
+            .unwrap_or(num_items);
+        pitch_values.truncate(num_items_per_repetition);
+
+        let mut builder = Scl::builder();
+        for pitch_value in pitch_values {
+            builder = builder.push_pitch_value(pitch_value);
+        }
+
+        CanonicalScl {
+            scl: builder
+                .build_with_description(self.description.clone())
+                .expect("Canonical form of a valid scale should be valid"),
+            was_strictly_increasing,
+            num_repetitions: (num_items / num_items_per_repetition) as u16,
+        }
+    }
+}
+
+/// Result of [`Scl::canonicalize`].
+#[derive(Clone, Debug)]
+pub struct CanonicalScl {
+    /// The scale in canonical form.
+    pub scl: Scl,
+
+    /// Whether the items of the original scale were sorted in strictly increasing order, starting above the unison.
+    pub was_strictly_increasing: bool,
+
+    /// The number of times the canonical period fits into the original period. 1 if the original period was not redundant.
+    pub num_repetitions: u16,
+}
+
+/// Two [`Scl`]s are equal if they consist of the same pitch values. The description is ignored.
+///
+/// The comparison is tolerance-free, i.e. cents values must match exactly and fractions must be equal after reduction.
+/// This makes [`Scl`] suitable as a cache key for derived data.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::scala::Scl;
+/// let scl = Scl::builder()
+///     .push_fraction(6, 4)
+///     .push_cents(1200.0)
+///     .build_with_description("Fifth and octave")
+///     .unwrap();
+///
+/// let equivalent_scl = Scl::builder()
+///     .push_fraction(3, 2)
+///     .push_cents(1200.0)
+///     .build()
+///     .unwrap();
+///
+/// let different_scl = Scl::builder()
+///     .push_fraction(3, 2)
+///     .push_int(2)
+///     .build()
+///     .unwrap();
+///
+/// assert!(scl == equivalent_scl);
+/// assert!(scl != different_scl);
+/// ```
+impl PartialEq for Scl {
+    fn eq(&self, other: &Self) -> bool {
+        self.pitch_values
+            .iter()
+            .map(|pitch_value| pitch_value.canonical_form())
+            .eq(other
+                .pitch_values
+                .iter()
+                .map(|pitch_value| pitch_value.canonical_form()))
+    }
+}
+
+impl Eq for Scl {}
+
+impl Hash for Scl {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pitch_values.len().hash(state);
+        for pitch_value in &self.pitch_values {
+            pitch_value.canonical_form().hash(state);
+        }
     }
 }
 
@@ -420,6 +843,7 @@ impl SclBuilder {
 
 /// Error reported when building an [`Scl`] fails.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum SclBuildError {
     /// The scale does not contain any items except for the default ratio (0 cents).
     ///
@@ -497,6 +921,11 @@ pub enum SclBuildError {
     /// assert_eq!(above.build().unwrap_err(), SclBuildError::ScaleTooLarge);
     /// ```
     ScaleTooLarge,
+
+    /// Two scales that are to be combined item by item do not have the same number of items.
+    ///
+    /// See [`Scl::interpolate`] for an example.
+    NumItemsMismatch,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -505,7 +934,35 @@ enum PitchValue {
     Fraction(u32, Option<u32>),
 }
 
+#[derive(Eq, Hash, PartialEq)]
+enum CanonicalPitchValue {
+    Cents(u64),
+    Fraction(u32, u32),
+}
+
 impl PitchValue {
+    fn reduced(self) -> PitchValue {
+        match self.canonical_form() {
+            CanonicalPitchValue::Fraction(numer, 1) => PitchValue::Fraction(numer, None),
+            CanonicalPitchValue::Fraction(numer, denom) => PitchValue::Fraction(numer, Some(denom)),
+            CanonicalPitchValue::Cents(_) => self,
+        }
+    }
+
+    fn canonical_form(self) -> CanonicalPitchValue {
+        match self {
+            // Normalize -0.0 to 0.0
+            PitchValue::Cents(cents_value) => {
+                CanonicalPitchValue::Cents((cents_value + 0.0).to_bits())
+            }
+            PitchValue::Fraction(numer, denom) => {
+                let denom = denom.unwrap_or(1);
+                let gcd = math::gcd_u32(numer, denom);
+                CanonicalPitchValue::Fraction(numer / gcd, denom / gcd)
+            }
+        }
+    }
+
     fn as_ratio(self) -> Ratio {
         match self {
             PitchValue::Cents(cents_value) => Ratio::from_cents(cents_value),
@@ -527,14 +984,102 @@ impl Display for PitchValue {
 }
 
 /// Format / [`Display`] wrapper created by [`Scl::export`].
-pub struct SclExport<'a>(&'a Scl);
+pub struct SclExport<'a> {
+    scl: &'a Scl,
+    notation: SclNotation,
+    cents_precision: usize,
+    explicit_denominators: bool,
+}
+
+/// Notation of the items written by [`SclExport`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SclNotation {
+    /// Write every item in the notation it was defined with.
+    AsDefined,
+
+    /// Write cents values as fractions if they match a fraction within the given odd limit at the chosen cents precision.
+    PreferRatios(u32),
+
+    /// Write every item, including fractions, as a cents value.
+    Cents,
+}
+
+impl<'a> SclExport<'a> {
+    pub fn with_notation(mut self, notation: SclNotation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    /// Number of decimal places of cents values. Defaults to 3.
+    ///
+    /// A trailing period is kept even if the precision is 0 s.t. the value is not mistaken for an integer ratio.
+    pub fn with_cents_precision(mut self, cents_precision: usize) -> Self {
+        self.cents_precision = cents_precision;
+        self
+    }
+
+    /// Write integer ratios as `n/1` instead of `n`.
+    pub fn with_explicit_denominators(mut self, explicit_denominators: bool) -> Self {
+        self.explicit_denominators = explicit_denominators;
+        self
+    }
+
+    fn write_pitch_value(&self, f: &mut Formatter, pitch_value: PitchValue) -> fmt::Result {
+        let pitch_value = match (self.notation, pitch_value) {
+            (SclNotation::AsDefined, _)
+            | (SclNotation::PreferRatios(_), PitchValue::Fraction(..)) => pitch_value,
+            (SclNotation::PreferRatios(odd_limit), PitchValue::Cents(cents_value)) => self
+                .find_matching_fraction(cents_value, odd_limit)
+                .unwrap_or(pitch_value),
+            (SclNotation::Cents, _) => PitchValue::Cents(pitch_value.as_ratio().as_cents()),
+        };
+
+        match pitch_value {
+            PitchValue::Cents(cents_value) => {
+                write!(f, "{:.*}", self.cents_precision, cents_value)?;
+                if self.cents_precision == 0 {
+                    write!(f, ".")?;
+                }
+                Ok(())
+            }
+            PitchValue::Fraction(numer, None) if self.explicit_denominators => {
+                write!(f, "{numer}/1")
+            }
+            pitch_value => write!(f, "{pitch_value}"),
+        }
+    }
+
+    fn find_matching_fraction(&self, cents_value: f64, odd_limit: u32) -> Option<PitchValue> {
+        let fraction = Ratio::from_cents(cents_value).nearest_fraction(odd_limit);
+
+        let tolerance_in_cents = 0.5 * 10f64.powi(-i32::try_from(self.cents_precision).ok()?);
+        if fraction.deviation.as_cents().abs() >= tolerance_in_cents {
+            return None;
+        }
+
+        let octave_factor = 1u64.checked_shl(fraction.num_octaves.unsigned_abs())?;
+        let (numer, denom) = if fraction.num_octaves >= 0 {
+            (fraction.numer.checked_mul(octave_factor)?, fraction.denom)
+        } else {
+            (fraction.numer, fraction.denom.checked_mul(octave_factor)?)
+        };
+        let (numer, denom) = math::simplify_u64(numer, denom);
+
+        let numer = u32::try_from(numer).ok()?;
+        Some(match denom {
+            1 => PitchValue::Fraction(numer, None),
+            denom => PitchValue::Fraction(numer, Some(u32::try_from(denom).ok()?)),
+        })
+    }
+}
 
 impl<'a> Display for SclExport<'a> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        writeln!(f, "{}", self.0.description())?;
-        writeln!(f, "{}", self.0.pitch_values.len())?;
-        for pitch_value in &self.0.pitch_values {
-            writeln!(f, "{pitch_value}")?;
+        writeln!(f, "{}", self.scl.description())?;
+        writeln!(f, "{}", self.scl.pitch_values.len())?;
+        for &pitch_value in &self.scl.pitch_values {
+            self.write_pitch_value(f, pitch_value)?;
+            writeln!(f)?;
         }
         Ok(())
     }
@@ -545,7 +1090,9 @@ impl<'a> Display for SclExport<'a> {
 /// To better understand how keyboard mappings work have a look at the documented methods of this struct.
 ///
 /// For more specialized linear keyboard mappings use [`KbmRoot`].
-#[derive(Clone, Debug)]
+///
+/// [`Kbm`] implements [`Eq`] and [`Hash`] s.t. it can be used as a cache key for derived data.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Kbm {
     kbm_root: KbmRoot,
     range: Range<PianoKey>,
@@ -591,6 +1138,27 @@ impl Kbm {
         self.num_items
     }
 
+    /// Returns the raw mapping table. Unmapped keys are represented by [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::note::Note;
+    /// # use tune::scala::Kbm;
+    /// let kbm = Kbm::builder(Note::from_midi_number(62))
+    ///    .push_mapped_key(0)
+    ///    .push_unmapped_key()
+    ///    .push_mapped_key(1)
+    ///    .formal_octave(2)
+    ///    .build()
+    ///    .unwrap();
+    ///
+    /// assert_eq!(kbm.key_mapping(), [Some(0), None, Some(1)]);
+    /// ```
+    pub fn key_mapping(&self) -> &[Option<i16>] {
+        &self.key_mapping
+    }
+
     /// Returns the scale degree for the given [`PianoKey`] .
     ///
     /// # Examples
@@ -758,7 +1326,7 @@ impl Kbm {
 /// In order to enable invertibility the mapping described by [`KbmRoot`] is linear.
 /// In other words, the keyboard mapping degree and the scale degree are the same number.
 /// If the mapping is required to be non-linear [`KbmRoot`] needs to be surrounded by the more general [`Kbm`] struct.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug)]
 pub struct KbmRoot {
     /// The [`PianoKey`] that maps to degree 0 of the keyboard mapping.
     /// If a [`Kbm`] surrounding is used with the first entry being *n*, `ref_key` maps to scale degree *n*.
@@ -771,6 +1339,39 @@ pub struct KbmRoot {
     pub root_offset: i32,
 }
 
+/// The `ref_pitch` is compared and hashed without any tolerance.
+///
+/// The result is the same as comparing the `ref_pitch` frequencies with `==`, i.e. `0.0 Hz == -0.0 Hz`, with one exception: A NaN `ref_pitch` is equal to itself.
+/// This exception is required by the reflexivity contract of [`Eq`] s.t. a [`KbmRoot`] (and the surrounding [`Kbm`]) can be used as a [`HashMap`](std::collections::HashMap) key.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitch;
+/// # use tune::scala::KbmRoot;
+/// let kbm_root =
+///     |ref_hz| KbmRoot::from(NoteLetter::A.in_octave(4).at_pitch(Pitch::from_hz(ref_hz)));
+///
+/// assert!(kbm_root(440.0) == kbm_root(440.0));
+/// assert!(kbm_root(440.0) != kbm_root(440.000001));
+/// assert!(kbm_root(0.0) == kbm_root(-0.0));
+/// assert!(kbm_root(f64::NAN) == kbm_root(f64::NAN));
+/// ```
+impl PartialEq for KbmRoot {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical_form() == other.canonical_form()
+    }
+}
+
+impl Eq for KbmRoot {}
+
+impl Hash for KbmRoot {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_form().hash(state);
+    }
+}
+
 impl KbmRoot {
     /// Shifts the `ref_key` of a scale by `num_degrees` correcting the scale's vertical location.
     ///
@@ -801,6 +1402,34 @@ impl KbmRoot {
         }
     }
 
+    /// Moves the `ref_pitch` from 440&nbsp;Hz standard tuning to the given `concert_pitch`, retaining its deviation from the `ref_key`'s original note.
+    ///
+    /// This allows applying "A4 = 432&nbsp;Hz"-style global offsets to any [`KbmRoot`] and, consequently, to all [`Kbm`]s and MTS messages derived from it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Pitch;
+    /// # use tune::scala::KbmRoot;
+    /// # use tune::tuning::ConcertPitch;
+    /// let concert_pitch = ConcertPitch::from_a4_pitch(Pitch::from_hz(432.0));
+    ///
+    /// let d4 = "62".parse::<KbmRoot>().unwrap().at_concert_pitch(concert_pitch);
+    /// assert_approx_eq!(d4.ref_pitch.as_hz(), 288.325409);
+    ///
+    /// let detuned_a4 = "69+100c".parse::<KbmRoot>().unwrap().at_concert_pitch(concert_pitch);
+    /// assert_approx_eq!(detuned_a4.ref_pitch.as_hz(), 457.688057);
+    /// ```
+    pub fn at_concert_pitch(self, concert_pitch: ConcertPitch) -> Self {
+        let ref_note = Note::from_midi_number(self.ref_key.midi_number());
+        Self {
+            ref_pitch: concert_pitch.pitch_of(ref_note)
+                * Ratio::between_pitches(ref_note.pitch(), self.ref_pitch),
+            ..self
+        }
+    }
+
     /// Creates a quasi-equivalent [`Kbm`] surrounding which can be exported.
     ///
     /// # Examples
@@ -829,6 +1458,12 @@ impl KbmRoot {
             .build()
             .unwrap()
     }
+
+    fn canonical_form(self) -> (PianoKey, u64, i32) {
+        // Normalize -0.0 to 0.0
+        let ref_pitch_bits = (self.ref_pitch.as_hz() + 0.0).to_bits();
+        (self.ref_key, ref_pitch_bits, self.root_offset)
+    }
 }
 
 impl<N: PitchedNote> From<N> for KbmRoot {
@@ -1325,6 +1960,250 @@ pub fn create_harmonics_scale(
     builder.build_with_description(description.into().unwrap_or(builtin_description))
 }
 
+/// Creates a tonality diamond, i.e. all ratios `a/b` where `a` and `b` are odd numbers up to `odd_limit`, reduced into the octave.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::scala;
+/// let diamond_5 = scala::create_tonality_diamond_scale(None, 5).unwrap();
+///
+/// assert_eq!(
+///     format!("{}", diamond_5.export()).lines().collect::<Vec<_>>(),
+///     ["5-odd-limit tonality diamond",
+///      "7", "6/5", "5/4", "4/3", "3/2", "8/5", "5/3", "2/1"]
+/// );
+/// ```
+pub fn create_tonality_diamond_scale(
+    description: impl Into<Option<String>>,
+    odd_limit: u16,
+) -> Result<Scl, SclBuildError> {
+    let odd_numbers = (1..=u64::from(odd_limit)).step_by(2);
+    let fractions = odd_numbers
+        .clone()
+        .flat_map(|numer| odd_numbers.clone().map(move |denom| Some((numer, denom))));
+
+    create_octave_reduced_ji_scale(
+        description
+            .into()
+            .unwrap_or_else(|| format!("{odd_limit}-odd-limit tonality diamond")),
+        fractions,
+    )
+}
+
+/// Creates an Euler-Fokker genus, i.e. the products of all combinations of `factors`, reduced into the octave.
+///
+/// [`SclBuildError::ScaleTooLarge`] is returned if a product exceeds the range of [`u64`].
+///
+/// # Examples
+///
+/// ```
+/// # use tune::scala;
+/// let genus_335 = scala::create_euler_genus_scale(None, &[3, 3, 5]).unwrap();
+///
+/// assert_eq!(
+///     format!("{}", genus_335.export()).lines().collect::<Vec<_>>(),
+///     ["Euler-Fokker genus [3 3 5]",
+///      "6", "9/8", "5/4", "45/32", "3/2", "15/8", "2/1"]
+/// );
+/// ```
+pub fn create_euler_genus_scale(
+    description: impl Into<Option<String>>,
+    factors: &[u16],
+) -> Result<Scl, SclBuildError> {
+    let mut products = vec![Some(1u64)];
+    for &factor in factors {
+        let num_products = products.len();
+        for index in 0..num_products {
+            let product = products[index].and_then(|product| product.checked_mul(factor.into()));
+            products.push(product);
+        }
+    }
+
+    create_octave_reduced_ji_scale(
+        description.into().unwrap_or_else(|| {
+            let factors = Vec::from_iter(factors.iter().map(u16::to_string));
+            format!("Euler-Fokker genus [{}]", factors.join(" "))
+        }),
+        products
+            .into_iter()
+            .map(|product| product.map(|product| (product, 1))),
+    )
+}
+
+/// Creates a block of a just intonation lattice, i.e. the products of all combinations of powers given by the [`LatticeAxis`] items, reduced into the octave.
+///
+/// [`SclBuildError::ScaleTooLarge`] is returned if a product exceeds the range of [`u64`].
+///
+/// # Examples
+///
+/// ```
+/// # use tune::scala;
+/// # use tune::scala::LatticeAxis;
+/// let lattice = scala::create_lattice_scale(
+///     None,
+///     &["3:-1:1".parse::<LatticeAxis>().unwrap(), "5:0:1".parse().unwrap()],
+/// ).unwrap();
+///
+/// assert_eq!(
+///     format!("{}", lattice.export()).lines().collect::<Vec<_>>(),
+///     ["JI lattice 3^-1..3^1 * 5^0..5^1",
+///      "6", "5/4", "4/3", "3/2", "5/3", "15/8", "2/1"]
+/// );
+/// ```
+pub fn create_lattice_scale(
+    description: impl Into<Option<String>>,
+    axes: &[LatticeAxis],
+) -> Result<Scl, SclBuildError> {
+    let mut fractions = vec![Some((1u64, 1u64))];
+    for axis in axes {
+        let generator = u64::from(axis.generator);
+        fractions = fractions
+            .into_iter()
+            .flat_map(|fraction| {
+                (axis.min_power..=axis.max_power).map(move |power| {
+                    let (numer, denom) = fraction?;
+                    let factor = generator.checked_pow(u32::from(power.unsigned_abs()))?;
+                    if power >= 0 {
+                        Some((numer.checked_mul(factor)?, denom))
+                    } else {
+                        Some((numer, denom.checked_mul(factor)?))
+                    }
+                })
+            })
+            .collect();
+    }
+
+    create_octave_reduced_ji_scale(
+        description.into().unwrap_or_else(|| {
+            let axes = Vec::from_iter(axes.iter().map(|axis| {
+                format!(
+                    "{generator}^{}..{generator}^{}",
+                    axis.min_power,
+                    axis.max_power,
+                    generator = axis.generator
+                )
+            }));
+            format!("JI lattice {}", axes.join(" * "))
+        }),
+        fractions,
+    )
+}
+
+/// Axis of a just intonation lattice used by [`create_lattice_scale`].
+///
+/// # Examples
+///
+/// ```
+/// # use tune::scala::LatticeAxis;
+/// let axis: LatticeAxis = "5:-1:2".parse().unwrap();
+///
+/// assert_eq!(axis.generator, 5);
+/// assert_eq!(axis.min_power, -1);
+/// assert_eq!(axis.max_power, 2);
+///
+/// assert!("5:2:-1".parse::<LatticeAxis>().is_err());
+/// assert!("0:-1:2".parse::<LatticeAxis>().is_err());
+/// assert!("5:-1".parse::<LatticeAxis>().is_err());
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct LatticeAxis {
+    /// The generator of the axis, usually a prime number.
+    pub generator: u16,
+
+    /// The lowest power of `generator` to include.
+    pub min_power: i16,
+
+    /// The highest power of `generator` to include.
+    pub max_power: i16,
+}
+
+impl FromStr for LatticeAxis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let [generator, min_power, max_power] = s.split(':').collect::<Vec<_>>().as_slice() {
+            let generator = generator
+                .parse()
+                .ok()
+                .filter(|&generator| generator > 0)
+                .ok_or_else(|| {
+                    format!("Invalid generator '{generator}': Must be a positive integer")
+                })?;
+            let min_power = min_power
+                .parse()
+                .map_err(|_| format!("Invalid power '{min_power}': Must be an integer"))?;
+            let max_power = max_power
+                .parse()
+                .map_err(|_| format!("Invalid power '{max_power}': Must be an integer"))?;
+            if min_power > max_power {
+                return Err(
+                    "The lowest power must not be greater than the highest power".to_owned(),
+                );
+            }
+            Ok(LatticeAxis {
+                generator,
+                min_power,
+                max_power,
+            })
+        } else {
+            Err("Must be an expression of type 3:-1:5".to_owned())
+        }
+    }
+}
+
+fn create_octave_reduced_ji_scale(
+    description: String,
+    fractions: impl IntoIterator<Item = Option<(u64, u64)>>,
+) -> Result<Scl, SclBuildError> {
+    let mut fractions = fractions
+        .into_iter()
+        .map(|fraction| fraction.and_then(|(numer, denom)| reduce_into_octave(numer, denom)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(SclBuildError::ScaleTooLarge)?;
+
+    fractions.retain(|&fraction| fraction != (1, 1));
+    fractions.sort_by(|&(numer_a, denom_a), &(numer_b, denom_b)| {
+        (u128::from(numer_a) * u128::from(denom_b))
+            .cmp(&(u128::from(numer_b) * u128::from(denom_a)))
+    });
+    fractions.dedup();
+
+    let mut builder = Scl::builder();
+    for (numer, denom) in fractions {
+        builder = match (u32::try_from(numer), u32::try_from(denom)) {
+            (Ok(numer), Ok(denom)) => builder.push_fraction(numer, denom),
+            _ => builder.push_ratio(Ratio::from_float(numer as f64 / denom as f64)),
+        };
+    }
+    builder
+        .push_fraction(2, 1)
+        .build_with_description(description)
+}
+
+fn reduce_into_octave(numer: u64, denom: u64) -> Option<(u64, u64)> {
+    if numer == 0 || denom == 0 {
+        return None;
+    }
+
+    let (mut numer, mut denom) = math::simplify_u64(numer, denom);
+    while u128::from(numer) >= 2 * u128::from(denom) {
+        if numer % 2 == 0 {
+            numer /= 2;
+        } else {
+            denom = denom.checked_mul(2)?;
+        }
+    }
+    while numer < denom {
+        if denom % 2 == 0 {
+            denom /= 2;
+        } else {
+            numer = numer.checked_mul(2)?;
+        }
+    }
+    Some((numer, denom))
+}
+
 /// Type of harmonic series segment to use.
 #[derive(Copy, Clone, Debug)]
 pub enum SegmentType {
@@ -1516,6 +2395,169 @@ mod tests {
             .maps_frequency_to_key_and_deviation(881.0, 77, 881.0 / 880.0);
     }
 
+    #[test]
+    fn scl_and_kbm_as_cache_keys() {
+        use std::collections::{HashMap, HashSet};
+
+        let create_kbm = |ref_hz| {
+            Kbm::builder(NoteLetter::A.in_octave(4).at_pitch(Pitch::from_hz(ref_hz)))
+                .push_mapped_key(0)
+                .push_unmapped_key()
+                .formal_octave(1)
+                .build()
+                .unwrap()
+        };
+        let create_scl = |cents| Scl::builder().push_cents(cents).build().unwrap();
+
+        let mut cache = HashMap::new();
+        cache.insert((create_scl(100.0), create_kbm(440.0)), "12-EDO");
+        cache.insert((create_scl(100.0), create_kbm(432.0)), "12-EDO @ 432 Hz");
+        cache.insert((create_scl(50.0), create_kbm(440.0)), "24-EDO");
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache[&(create_scl(100.0), create_kbm(440.0))], "12-EDO");
+        assert_eq!(
+            cache[&(create_scl(100.0), create_kbm(432.0))],
+            "12-EDO @ 432 Hz"
+        );
+        assert_eq!(cache[&(create_scl(50.0), create_kbm(440.0))], "24-EDO");
+        assert!(!cache.contains_key(&(create_scl(100.000001), create_kbm(440.0))));
+
+        let create_kbm_root =
+            |ref_hz| KbmRoot::from(NoteLetter::A.in_octave(4).at_pitch(Pitch::from_hz(ref_hz)));
+        let mut kbm_roots = HashSet::new();
+        kbm_roots.insert(create_kbm_root(0.0));
+        kbm_roots.insert(create_kbm_root(-0.0));
+        kbm_roots.insert(create_kbm_root(f64::NAN));
+        kbm_roots.insert(create_kbm_root(f64::NAN));
+
+        assert_eq!(kbm_roots.len(), 2);
+        assert!(kbm_roots.contains(&create_kbm_root(-0.0)));
+        assert!(kbm_roots.contains(&create_kbm_root(f64::NAN)));
+    }
+
+    #[test]
+    fn scl_export_with_custom_notation() {
+        let scl = Scl::builder()
+            .push_cents(701.9)
+            .push_fraction(5, 4)
+            .push_cents(1200.0)
+            .push_cents(2400.0)
+            .build()
+            .unwrap();
+
+        let export_lines = |export: SclExport| {
+            export
+                .to_string()
+                .lines()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            export_lines(scl.export().with_notation(SclNotation::PreferRatios(5))),
+            ["Custom scale", "4", "701.900", "5/4", "2", "4"]
+        );
+        assert_eq!(
+            export_lines(
+                scl.export()
+                    .with_notation(SclNotation::PreferRatios(5))
+                    .with_cents_precision(0)
+            ),
+            ["Custom scale", "4", "3/2", "5/4", "2", "4"]
+        );
+
+        let exported = scl
+            .export()
+            .with_notation(SclNotation::Cents)
+            .with_cents_precision(0)
+            .to_string();
+        assert_eq!(
+            exported.lines().collect::<Vec<_>>(),
+            ["Custom scale", "4", "702.", "386.", "1200.", "2400."]
+        );
+
+        let imported = Scl::import(exported.as_bytes()).unwrap();
+        assert_approx_eq!(imported.relative_pitch_of(1).as_cents(), 702.0);
+        assert_approx_eq!(imported.period().as_cents(), 2400.0);
+    }
+
+    #[test]
+    fn lattice_scale_with_large_powers() {
+        let axis = |s: &str| s.parse::<LatticeAxis>().unwrap();
+
+        assert_eq!(
+            create_lattice_scale(None, &[axis("3:-40:-40")]).unwrap_err(),
+            SclBuildError::ScaleTooLarge
+        );
+        assert_eq!(
+            create_lattice_scale(None, &[axis("3:-41:0")]).unwrap_err(),
+            SclBuildError::ScaleTooLarge
+        );
+        assert_eq!(
+            create_lattice_scale(None, &[axis("3:40:40")])
+                .unwrap()
+                .num_items(),
+            2
+        );
+    }
+
+    #[test]
+    fn sparse_kbm_round_trip() {
+        let kbm_root = KbmRoot {
+            ref_key: PianoKey::from_midi_number(62),
+            ref_pitch: Pitch::from_hz(293.665),
+            root_offset: -2,
+        };
+
+        let kbm = Kbm::builder(kbm_root)
+            .range(PianoKey::from_midi_number(21)..PianoKey::from_midi_number(109))
+            .push_mapped_key(0)
+            .push_unmapped_key()
+            .push_mapped_key(2)
+            .push_mapped_key(2)
+            .push_unmapped_key()
+            .push_mapped_key(-1)
+            .push_unmapped_key()
+            .formal_octave(5)
+            .build()
+            .unwrap();
+
+        let exported = kbm.export().to_string();
+        assert_eq!(
+            exported.lines().collect::<Vec<_>>(),
+            ["7", "21", "108", "60", "62", "293.665", "5", "0", "x", "2", "2", "x", "-1", "x"]
+        );
+
+        let imported = Kbm::import(exported.as_bytes()).unwrap();
+
+        assert_eq!(imported.kbm_root(), kbm.kbm_root());
+        assert_eq!(imported.range(), kbm.range());
+        assert_eq!(imported.num_items(), kbm.num_items());
+        assert_eq!(imported.formal_octave(), kbm.formal_octave());
+        assert_eq!(imported.key_mapping(), kbm.key_mapping());
+        for midi_number in 0..128 {
+            let key = PianoKey::from_midi_number(midi_number);
+            assert_eq!(imported.scale_degree_of(key), kbm.scale_degree_of(key));
+        }
+        assert_eq!(imported.export().to_string(), exported);
+    }
+
+    #[test]
+    fn sparse_kbm_import_pads_unmapped_keys() {
+        let input = ["12", "0", "127", "60", "69", "440.0", "12", "0", "X", "2"];
+
+        let kbm = Kbm::import(input.join("\n").as_bytes()).unwrap();
+
+        assert_eq!(kbm.num_items(), 12);
+        assert_eq!(kbm.key_mapping()[..4], [Some(0), None, Some(2), None]);
+        assert!(kbm.key_mapping()[3..].iter().all(Option::is_none));
+        assert_eq!(
+            kbm.export().to_string().lines().skip(7).collect::<Vec<_>>(),
+            ["0", "x", "2", "x", "x", "x", "x", "x", "x", "x", "x", "x"]
+        );
+    }
+
     struct AssertScale(Scl, KbmRoot);
 
     impl AssertScale {
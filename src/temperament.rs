@@ -1,6 +1,9 @@
-//! Explore equal temperaments and vals.
+//! Explore equal temperaments, vals and rank-2 temperaments.
 
-use std::fmt::Display;
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Write},
+};
 
 use crate::{
     comma::Comma,
@@ -93,6 +96,7 @@ impl EqualTemperament {
         TemperamentFinder {
             second_best_fifth_allowed: true,
             preference: TemperamentPreference::PorcupineWhenMeantoneIsBad,
+            period: Ratio::octave(),
         }
     }
 
@@ -150,6 +154,47 @@ impl EqualTemperament {
         self.formatter
             .format(&self.pergen.get_accidentals(&self.acc_format, index))
     }
+
+    fn get_spellings(&self, index: u16) -> (Option<u16>, Vec<Spelling>) {
+        let accidentals = self.pergen.get_accidentals(&self.acc_format, index);
+        let spelling = |index: u16, num_accidentals: i16| Spelling {
+            note_name: *self
+                .formatter
+                .note_names
+                .get(usize::from(index))
+                .unwrap_or(&'?'),
+            num_accidentals,
+        };
+
+        let sharp = spelling(accidentals.sharp_index, accidentals.sharp_count as i16);
+        let flat = spelling(accidentals.flat_index, -(accidentals.flat_count as i16));
+
+        let spellings = if accidentals.sharp_count == 0
+            && accidentals.flat_count == 0
+            && accidentals.sharp_index == accidentals.flat_index
+        {
+            vec![sharp]
+        } else {
+            match accidentals.sharp_count.cmp(&accidentals.flat_count) {
+                Ordering::Greater => vec![flat],
+                Ordering::Less => vec![sharp],
+                Ordering::Equal => match self.formatter.order {
+                    AccidentalsOrder::SharpFlat => vec![sharp, flat],
+                    AccidentalsOrder::FlatSharp => vec![flat, sharp],
+                },
+            }
+        };
+
+        (accidentals.cycle, spellings)
+    }
+
+    fn accidental_sign(&self, num_accidentals: i16) -> char {
+        if num_accidentals >= 0 {
+            self.formatter.sharp_sign
+        } else {
+            self.formatter.flat_sign
+        }
+    }
 }
 
 fn sharp_sign_from_sharpness(sharpness: i16) -> char {
@@ -168,9 +213,242 @@ fn flat_sign_from_sharpness(sharpness: i16) -> char {
     }
 }
 
+/// A system for naming the steps of an [`EqualTemperament`].
+///
+/// # Examples
+///
+/// ```
+/// # use tune::temperament::AccidentalCountNotation;
+/// # use tune::temperament::EqualTemperament;
+/// # use tune::temperament::HeptatonicNotation;
+/// # use tune::temperament::NotationSystem;
+/// # use tune::temperament::SagittalNotation;
+/// # use tune::temperament::UpsAndDownsNotation;
+/// let note_names = |notation: &dyn NotationSystem, temperament: &EqualTemperament| {
+///     Vec::from_iter((0..8).map(|index| notation.note_name(temperament, index))).join(", ")
+/// };
+///
+/// let edo_41 = EqualTemperament::find().by_edo(41);
+///
+/// assert_eq!(
+///     note_names(&HeptatonicNotation, &edo_41),
+///     "D, C##, Fbb, Eb, D#, C###/Gbbb, Fb, E"
+/// );
+/// assert_eq!(
+///     note_names(&AccidentalCountNotation, &edo_41),
+///     "D, C#2, Fb2, Eb, D#, C#3/Gb3, Fb, E"
+/// );
+/// assert_eq!(
+///     note_names(&UpsAndDownsNotation, &edo_41),
+///     "D, ^D, vEb, Eb, D#, ^D#/vFb, Fb, E"
+/// );
+/// assert_eq!(
+///     note_names(&SagittalNotation, &edo_41),
+///     r"D, D/|, D/|\, D||\, E!!/, E\!/, E\!, E"
+/// );
+/// ```
+pub trait NotationSystem {
+    /// Returns the name of step `index` within the period of `temperament`.
+    fn note_name(&self, temperament: &EqualTemperament, index: u16) -> String;
+}
+
+/// Names steps by their letter followed by repeated accidentals, e.g. C## or Dbb.
+///
+/// This is the notation returned by [`EqualTemperament::get_heptatonic_name`].
+#[derive(Copy, Clone, Debug)]
+pub struct HeptatonicNotation;
+
+impl NotationSystem for HeptatonicNotation {
+    fn note_name(&self, temperament: &EqualTemperament, index: u16) -> String {
+        temperament.get_heptatonic_name(index)
+    }
+}
+
+/// Names steps by their letter followed by an accidental and the number of accidentals, e.g. C#2 or Db2.
+#[derive(Copy, Clone, Debug)]
+pub struct AccidentalCountNotation;
+
+impl NotationSystem for AccidentalCountNotation {
+    fn note_name(&self, temperament: &EqualTemperament, index: u16) -> String {
+        let (cycle, spellings) = temperament.get_spellings(index);
+
+        render_spellings(
+            cycle,
+            spellings.iter().map(|spelling| {
+                let mut name = spelling.note_name.to_string();
+                let accidental = temperament.accidental_sign(spelling.num_accidentals);
+                match spelling.num_accidentals.unsigned_abs() {
+                    0 => {}
+                    1 => name.push(accidental),
+                    num_accidentals => write!(name, "{accidental}{num_accidentals}").unwrap(),
+                }
+                name
+            }),
+        )
+    }
+}
+
+/// Names steps by a letter with accidentals, raised by ups (^) or lowered by downs (v).
+///
+/// Among all spellings, the one with the fewest ups, downs and accidentals in total is chosen, where double accidentals count extra.
+/// Thus, ups and downs are only used for steps that cannot be reached by a natural note or a single sharp or flat.
+/// Multiple cycles are notated with ups and downs as well, s.t. no cycle number is necessary.
+#[derive(Copy, Clone, Debug)]
+pub struct UpsAndDownsNotation;
+
+impl NotationSystem for UpsAndDownsNotation {
+    fn note_name(&self, temperament: &EqualTemperament, index: u16) -> String {
+        let num_steps_per_octave = i32::from(temperament.num_steps_per_octave());
+
+        let mut best_rating = None;
+        let mut names = Vec::new();
+
+        for num_inflections in 0..num_steps_per_octave {
+            if let Some((min_num_symbols, _)) = best_rating {
+                if min_num_symbols <= num_inflections {
+                    break;
+                }
+            }
+
+            let num_directions = if num_inflections == 0 { 1 } else { 2 };
+            for (offset, inflection) in [(num_inflections, '^'), (-num_inflections, 'v')]
+                .into_iter()
+                .take(num_directions)
+            {
+                let candidate_index =
+                    (i32::from(index) - offset).rem_euclid(num_steps_per_octave) as u16;
+                let (cycle, spellings) = temperament.get_spellings(candidate_index);
+                if cycle.unwrap_or(0) != 0 {
+                    continue;
+                }
+
+                for spelling in spellings {
+                    let num_accidentals = spelling.num_accidentals.unsigned_abs();
+                    let rating = (
+                        num_inflections
+                            + i32::from(num_accidentals)
+                            + i32::from(num_accidentals.saturating_sub(1)),
+                        num_inflections,
+                    );
+                    if best_rating
+                        .map(|best_rating| rating < best_rating)
+                        .unwrap_or(true)
+                    {
+                        best_rating = Some(rating);
+                        names.clear();
+                    }
+                    if best_rating == Some(rating) {
+                        let mut name = String::new();
+                        for _ in 0..num_inflections {
+                            name.push(inflection);
+                        }
+                        name.push(spelling.note_name);
+                        for _ in 0..num_accidentals {
+                            name.push(temperament.accidental_sign(spelling.num_accidentals));
+                        }
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        names.join("/")
+    }
+}
+
+/// Names steps by their nearest natural letter followed by a pure ASCII Sagittal accidental, e.g. C/|\ or D\!.
+///
+/// The Sagittal accidentals are mapped to EDO steps via the number of steps of the apotome (sharp) and of the 5-comma (81/80), 7-comma (64/63) and 11-diesis (33/32).
+/// If no accidental matches the distance to a natural note the step falls back to [`HeptatonicNotation`].
+#[derive(Copy, Clone, Debug)]
+pub struct SagittalNotation;
+
+impl NotationSystem for SagittalNotation {
+    fn note_name(&self, temperament: &EqualTemperament, index: u16) -> String {
+        let num_steps_per_octave = temperament.num_steps_per_octave();
+        let step_size = temperament
+            .size_of_octave()
+            .divided_into_equal_steps(num_steps_per_octave);
+        let num_steps_of = |ratio: f64| {
+            Ratio::from_float(ratio)
+                .num_equal_steps_of_size(step_size)
+                .round() as i32
+        };
+
+        let apotome = i32::from(temperament.sharpness());
+        let comma_5 = num_steps_of(81.0 / 80.0);
+        let comma_7 = num_steps_of(64.0 / 63.0);
+        let diesis_11 = num_steps_of(33.0 / 32.0);
+
+        let mut symbols = vec![
+            (comma_5, "/|", "\\!"),
+            (diesis_11, "/|\\", "\\!/"),
+            (comma_7, "|)", "!)"),
+        ];
+        if apotome > 0 {
+            symbols.insert(0, (apotome, "/||\\", "\\!!/"));
+            symbols.extend([
+                (apotome - comma_5, "||\\", "!!/"),
+                (apotome - diesis_11, "(||(", "(!!("),
+                (apotome - comma_7, "||)", "!!)"),
+            ]);
+        }
+
+        let get_natural = |index: i32| {
+            let index = index.rem_euclid(i32::from(num_steps_per_octave)) as u16;
+            let (cycle, spellings) = temperament.get_spellings(index);
+            spellings
+                .into_iter()
+                .find(|spelling| cycle.unwrap_or(0) == 0 && spelling.num_accidentals == 0)
+                .map(|spelling| spelling.note_name)
+        };
+
+        for offset in 0..=i32::from(num_steps_per_octave / 2) {
+            for (natural_index, is_up) in [
+                (i32::from(index) - offset, true),
+                (i32::from(index) + offset, false),
+            ] {
+                let natural = match get_natural(natural_index) {
+                    Some(natural) => natural,
+                    None => continue,
+                };
+                if offset == 0 {
+                    return natural.to_string();
+                }
+                if let Some(&(_, up, down)) =
+                    symbols.iter().find(|&&(num_steps, ..)| num_steps == offset)
+                {
+                    return format!("{natural}{}", if is_up { up } else { down });
+                }
+            }
+        }
+
+        temperament.get_heptatonic_name(index)
+    }
+}
+
+struct Spelling {
+    note_name: char,
+    num_accidentals: i16,
+}
+
+fn render_spellings(cycle: Option<u16>, spellings: impl IntoIterator<Item = String>) -> String {
+    let spellings = Vec::from_iter(spellings);
+
+    let mut rendered = spellings.join("/");
+    if let Some(cycle) = cycle {
+        if spellings.len() > 1 {
+            rendered = format!("({rendered})");
+        }
+        write!(rendered, "[{cycle}]").unwrap();
+    }
+    rendered
+}
+
 pub struct TemperamentFinder {
     second_best_fifth_allowed: bool,
     preference: TemperamentPreference,
+    period: Ratio,
 }
 
 impl TemperamentFinder {
@@ -184,16 +462,45 @@ impl TemperamentFinder {
         self
     }
 
+    /// Uses `period` instead of the octave as the interval of equivalence, e.g. `3/1` for equal divisions of the tritave.
+    ///
+    /// The notation is derived from a generator that has the same relative size within `period` as the perfect fifth within the octave.
+    /// The octave-related properties of the found [`EqualTemperament`], e.g. [`EqualTemperament::num_steps_per_octave`], then refer to `period`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::temperament::EqualTemperament;
+    /// # use tune::temperament::TemperamentType;
+    /// let tritave = Ratio::from_float(3.0);
+    /// let bohlen_pierce = EqualTemperament::find()
+    ///     .with_period(tritave)
+    ///     .by_step_size(tritave.divided_into_equal_steps(13));
+    ///
+    /// assert_eq!(bohlen_pierce.num_steps_per_octave(), 13);
+    /// assert_approx_eq!(bohlen_pierce.size_of_octave().as_float(), 3.0);
+    /// assert_eq!(bohlen_pierce.num_steps_per_fifth(), 7);
+    /// assert_eq!(bohlen_pierce.temperament_type(), TemperamentType::Porcupine);
+    /// assert_eq!(bohlen_pierce.primary_step(), 2);
+    /// assert_eq!(bohlen_pierce.secondary_step(), 1);
+    /// ```
+    pub fn with_period(mut self, period: Ratio) -> Self {
+        self.period = period;
+        self
+    }
+
     pub fn by_edo(&self, num_steps_per_octave: impl Into<f64>) -> EqualTemperament {
         self.by_step_size(Ratio::octave().divided_into_equal_steps(num_steps_per_octave))
     }
 
     pub fn by_step_size(&self, step_size: Ratio) -> EqualTemperament {
-        let num_steps_per_octave =
-            Ratio::octave().num_equal_steps_of_size(step_size).round() as u16;
-        let best_fifth = Ratio::from_float(1.5)
-            .num_equal_steps_of_size(step_size)
-            .round() as u16;
+        let num_steps_per_octave = self.period.num_equal_steps_of_size(step_size).round() as u16;
+        let best_fifth =
+            Ratio::from_octaves(self.period.as_octaves() * Ratio::from_float(1.5).as_octaves())
+                .num_equal_steps_of_size(step_size)
+                .round() as u16;
 
         self.by_edo_and_fifth(num_steps_per_octave, best_fifth)
             .with_size_of_octave(step_size.repeated(num_steps_per_octave))
@@ -558,6 +865,349 @@ impl Val {
     }
 }
 
+/// Well-known rank-2 temperaments given by the Hermite normal form of their mapping.
+const KNOWN_RANK2_TEMPERAMENTS: &[(&str, &[i32], &[i32])] = &[
+    ("meantone", &[1, 0, -4], &[0, 1, 4]),
+    ("schismatic", &[1, 0, 15], &[0, 1, -8]),
+    ("mavila", &[1, 0, 7], &[0, 1, -3]),
+    ("father", &[1, 0, 4], &[0, 1, -1]),
+    ("dicot", &[1, 1, 2], &[0, 2, 1]),
+    ("porcupine", &[1, 2, 3], &[0, 3, 5]),
+    ("tetracot", &[1, 1, 1], &[0, 4, 9]),
+    ("negri", &[1, 2, 2], &[0, 4, 3]),
+    ("magic", &[1, 0, 2], &[0, 5, 1]),
+    ("hanson", &[1, 0, 1], &[0, 6, 5]),
+    ("sensi", &[1, 6, 8], &[0, 7, 9]),
+    ("würschmidt", &[1, 7, 3], &[0, 8, 1]),
+    ("srutal", &[2, 0, 11], &[0, 1, -2]),
+    ("augmented", &[3, 0, 7], &[0, 1, 0]),
+    ("diminished", &[4, 0, 3], &[0, 1, 1]),
+    ("blackwood", &[5, 8, 0], &[0, 0, 1]),
+    ("septimal meantone", &[1, 0, -4, -13], &[0, 1, 4, 10]),
+    ("superpyth", &[1, 0, -12, 6], &[0, 1, 9, -2]),
+    ("septimal porcupine", &[1, 2, 3, 2], &[0, 3, 5, -6]),
+    ("mothra", &[1, 1, 0, 3], &[0, 3, 12, -1]),
+    ("septimal magic", &[1, 0, 2, -1], &[0, 5, 1, 12]),
+    ("miracle", &[1, 1, 3, 3], &[0, 6, -7, -2]),
+    ("orwell", &[1, 0, 3, 1], &[0, 7, -3, 8]),
+    ("valentine", &[1, 1, 2, 3], &[0, 9, 5, -3]),
+    ("pajara", &[2, 0, 11, 12], &[0, 1, -2, -2]),
+];
+
+/// A rank-2 temperament, i.e. a mapping from the prime numbers [2, 3, 5, 7, ...] to a combination of a period and a generator.
+///
+/// The period and generator sizes are optimized to minimize the Tenney-Euclidean (TE) error.
+#[derive(Clone, Debug)]
+pub struct Rank2Temperament {
+    hermite_form: [Vec<i32>; 2],
+    period_mapping: Vec<i32>,
+    generator_mapping: Vec<i32>,
+    period: Ratio,
+    generator: Ratio,
+    te_error: Ratio,
+}
+
+impl Rank2Temperament {
+    /// Creates a [`Rank2Temperament`] from the two rows of a mapping matrix.
+    ///
+    /// [`None`] is returned if the rows have different lengths, exceed the supported prime limit or are linearly dependent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::temperament::Rank2Temperament;
+    /// // Octave and fifth
+    /// let meantone = Rank2Temperament::from_mapping(&[1, 1, 0], &[0, 1, 4]).unwrap();
+    ///
+    /// assert_eq!(meantone.name(), Some("meantone"));
+    /// assert_eq!(meantone.num_periods_per_octave(), 1);
+    /// assert_approx_eq!(meantone.period().as_cents(), 1201.396851);
+    /// assert_approx_eq!(meantone.generator().as_cents(), 504.347741);
+    /// assert_eq!(meantone.period_mapping(), [1, 2, 4]);
+    /// assert_eq!(meantone.generator_mapping(), [0, -1, -4]);
+    ///
+    /// assert!(Rank2Temperament::from_mapping(&[1, 1, 0], &[2, 2, 0]).is_none());
+    /// ```
+    pub fn from_mapping(first_row: &[i32], second_row: &[i32]) -> Option<Self> {
+        if first_row.len() != second_row.len() || first_row.len() > math::U8_PRIMES.len() {
+            return None;
+        }
+        let hermite_form = hermite_normal_form(first_row, second_row)?;
+
+        let (period, hermite_generator) = te_optimal_generators(&hermite_form);
+        let te_error = te_error(&hermite_form, period, hermite_generator);
+
+        // Reduce the generator to the range [0, period / 2]
+        let num_periods = (hermite_generator / period).floor();
+        let mut generator = hermite_generator - num_periods * period;
+        let mut period_mapping: Vec<_> = hermite_form[0]
+            .iter()
+            .zip(&hermite_form[1])
+            .map(|(&p, &g)| p + num_periods as i32 * g)
+            .collect();
+        let mut generator_mapping = hermite_form[1].clone();
+        if generator > period / 2.0 {
+            generator = period - generator;
+            for (p, g) in period_mapping.iter_mut().zip(&mut generator_mapping) {
+                *p += *g;
+                *g = -*g;
+            }
+        }
+
+        Some(Self {
+            hermite_form,
+            period_mapping,
+            generator_mapping,
+            period: Ratio::from_octaves(period),
+            generator: Ratio::from_octaves(generator),
+            te_error: Ratio::from_octaves(te_error),
+        })
+    }
+
+    /// Creates the [`Rank2Temperament`] supported by both of the given [`Val`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::pitch::Ratio;
+    /// # use tune::temperament::Rank2Temperament;
+    /// # use tune::temperament::Val;
+    /// let val_of_12_edo = Val::patent(Ratio::octave().divided_into_equal_steps(12), 5);
+    /// let val_of_19_edo = Val::patent(Ratio::octave().divided_into_equal_steps(19), 5);
+    ///
+    /// let temperament = Rank2Temperament::from_vals(&val_of_12_edo, &val_of_19_edo).unwrap();
+    /// assert_eq!(temperament.name(), Some("meantone"));
+    /// ```
+    pub fn from_vals(first_val: &Val, second_val: &Val) -> Option<Self> {
+        let to_row = |val: &Val| Vec::from_iter(val.values().iter().map(|&v| i32::from(v)));
+        Self::from_mapping(&to_row(first_val), &to_row(second_val))
+    }
+
+    /// Looks up a well-known temperament by its name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::temperament::Rank2Temperament;
+    /// let pajara = Rank2Temperament::by_name("Pajara").unwrap();
+    /// assert_eq!(pajara.num_periods_per_octave(), 2);
+    /// assert_eq!(pajara.prime_limit(), 7);
+    ///
+    /// assert!(Rank2Temperament::by_name("unknown").is_none());
+    /// ```
+    pub fn by_name(name: &str) -> Option<Self> {
+        KNOWN_RANK2_TEMPERAMENTS
+            .iter()
+            .find(|(known_name, ..)| known_name.eq_ignore_ascii_case(name))
+            .and_then(|(_, first_row, second_row)| Self::from_mapping(first_row, second_row))
+    }
+
+    /// Starts a search for rank-2 temperaments.
+    pub fn find() -> Rank2TemperamentFinder {
+        Rank2TemperamentFinder {
+            max_edo: 72,
+            max_error: Ratio::from_cents(5.0),
+        }
+    }
+
+    /// Returns the name of the temperament if it is well-known.
+    pub fn name(&self) -> Option<&'static str> {
+        KNOWN_RANK2_TEMPERAMENTS
+            .iter()
+            .find(|(_, first_row, second_row)| {
+                self.hermite_form[0] == *first_row && self.hermite_form[1] == *second_row
+            })
+            .map(|&(name, ..)| name)
+    }
+
+    /// Returns the number of periods for each prime number.
+    pub fn period_mapping(&self) -> &[i32] {
+        &self.period_mapping
+    }
+
+    /// Returns the number of generators for each prime number.
+    pub fn generator_mapping(&self) -> &[i32] {
+        &self.generator_mapping
+    }
+
+    pub fn num_periods_per_octave(&self) -> i32 {
+        self.hermite_form[0][0]
+    }
+
+    pub fn prime_limit(&self) -> u8 {
+        math::U8_PRIMES[self.hermite_form[0].len() - 1]
+    }
+
+    /// Returns the TE-optimal period.
+    pub fn period(&self) -> Ratio {
+        self.period
+    }
+
+    /// Returns the TE-optimal generator, reduced to the range [0, `period` / 2].
+    pub fn generator(&self) -> Ratio {
+        self.generator
+    }
+
+    /// Returns the root-mean-square of the Tenney-weighted prime errors.
+    pub fn te_error(&self) -> Ratio {
+        self.te_error
+    }
+}
+
+pub struct Rank2TemperamentFinder {
+    max_edo: u16,
+    max_error: Ratio,
+}
+
+impl Rank2TemperamentFinder {
+    /// Limits the search to pairs of equal temperaments with at most `max_edo` steps per octave.
+    pub fn with_max_edo(mut self, max_edo: u16) -> Self {
+        self.max_edo = max_edo;
+        self
+    }
+
+    /// Skips temperaments whose TE error exceeds `max_error`.
+    pub fn with_max_error(mut self, max_error: Ratio) -> Self {
+        self.max_error = max_error;
+        self
+    }
+
+    /// Finds all distinct rank-2 temperaments supported by two patent vals of the given `prime_limit`.
+    ///
+    /// The result is ordered by the total number of steps of the equal temperaments the temperament was first found in, i.e. simpler temperaments come first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::pitch::Ratio;
+    /// # use tune::temperament::Rank2Temperament;
+    /// let temperaments = Rank2Temperament::find()
+    ///     .with_max_edo(31)
+    ///     .with_max_error(Ratio::from_cents(2.0))
+    ///     .by_prime_limit(5);
+    ///
+    /// let names = Vec::from_iter(temperaments.iter().filter_map(Rank2Temperament::name));
+    /// assert_eq!(
+    ///     names,
+    ///     ["srutal", "meantone", "magic", "hanson", "sensi", "schismatic", "würschmidt", "tetracot"]
+    /// );
+    /// ```
+    pub fn by_prime_limit(&self, prime_limit: u8) -> Vec<Rank2Temperament> {
+        let vals = Vec::from_iter((1..=self.max_edo).map(|num_steps_per_octave| {
+            Val::patent(
+                Ratio::octave().divided_into_equal_steps(num_steps_per_octave),
+                prime_limit,
+            )
+        }));
+
+        let mut temperaments = Vec::<Rank2Temperament>::new();
+        for total_num_steps in 3..=2 * vals.len() {
+            for first_index in 0..(total_num_steps - 1) / 2 {
+                let second_index = total_num_steps - first_index - 2;
+                if second_index >= vals.len() {
+                    continue;
+                }
+                if let Some(temperament) =
+                    Rank2Temperament::from_vals(&vals[first_index], &vals[second_index])
+                {
+                    if temperament.te_error.as_cents() <= self.max_error.as_cents()
+                        && !temperaments
+                            .iter()
+                            .any(|found| found.hermite_form == temperament.hermite_form)
+                    {
+                        temperaments.push(temperament);
+                    }
+                }
+            }
+        }
+        temperaments
+    }
+}
+
+/// Brings the mapping into Hermite normal form which is unique for each temperament. Common factors of the rows are removed.
+fn hermite_normal_form(first_row: &[i32], second_row: &[i32]) -> Option<[Vec<i32>; 2]> {
+    let (gcd, x, y) = extended_gcd(*first_row.first()?, second_row[0]);
+    if gcd == 0 {
+        return None;
+    }
+    let (a, b) = (first_row[0] / gcd, second_row[0] / gcd);
+
+    let mut upper = Vec::from_iter(
+        first_row
+            .iter()
+            .zip(second_row)
+            .map(|(&u, &l)| x * u + y * l),
+    );
+    let mut lower = Vec::from_iter(
+        first_row
+            .iter()
+            .zip(second_row)
+            .map(|(&u, &l)| -b * u + a * l),
+    );
+
+    let lower_gcd = lower.iter().fold(0, |acc, &l| extended_gcd(acc, l).0);
+    let pivot_index = lower.iter().position(|&l| l != 0)?;
+    let sign = lower[pivot_index].signum();
+    for l in &mut lower {
+        *l = sign * *l / lower_gcd;
+    }
+
+    let num_lower_rows = upper[pivot_index].div_euclid(lower[pivot_index]);
+    for (u, l) in upper.iter_mut().zip(&lower) {
+        *u -= num_lower_rows * l;
+    }
+
+    Some([upper, lower])
+}
+
+/// Returns (gcd, x, y) s.t. `x * a + y * b == gcd` and `gcd >= 0`.
+fn extended_gcd(a: i32, b: i32) -> (i32, i32, i32) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (gcd, x, y) = extended_gcd(b, a.rem_euclid(b));
+        (gcd, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// Solves the weighted least-squares problem for the sizes (in octaves) of the two generators of the given mapping.
+fn te_optimal_generators(mapping: &[Vec<i32>; 2]) -> (f64, f64) {
+    let (mut a11, mut a12, mut a22, mut b1, mut b2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (weighted_first, weighted_second) in weighted_columns(mapping) {
+        a11 += weighted_first * weighted_first;
+        a12 += weighted_first * weighted_second;
+        a22 += weighted_second * weighted_second;
+        b1 += weighted_first;
+        b2 += weighted_second;
+    }
+    let det = a11 * a22 - a12 * a12;
+    ((a22 * b1 - a12 * b2) / det, (a11 * b2 - a12 * b1) / det)
+}
+
+fn te_error(mapping: &[Vec<i32>; 2], first_generator: f64, second_generator: f64) -> f64 {
+    let (sum_of_squares, num_primes) = weighted_columns(mapping)
+        .map(|(weighted_first, weighted_second)| {
+            weighted_first * first_generator + weighted_second * second_generator - 1.0
+        })
+        .fold((0.0, 0), |(sum, count), error| {
+            (sum + error * error, count + 1)
+        });
+    (sum_of_squares / f64::from(num_primes)).sqrt()
+}
+
+/// Iterates over the mapping's columns divided by the size of the corresponding prime (in octaves).
+fn weighted_columns(mapping: &[Vec<i32>; 2]) -> impl Iterator<Item = (f64, f64)> + '_ {
+    mapping[0]
+        .iter()
+        .zip(&mapping[1])
+        .zip(math::U8_PRIMES)
+        .map(|((&first, &second), &prime)| {
+            let weight = Ratio::from_float(f64::from(prime)).as_octaves();
+            (f64::from(first) / weight, f64::from(second) / weight)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -597,4 +1247,73 @@ mod tests {
         std::fs::write("edo-notes-1-to-99.txt", &output).unwrap();
         assert_eq!(output, include_str!("../edo-notes-1-to-99.txt"));
     }
+
+    #[test]
+    fn note_names_of_multi_cycle_edo() {
+        let temperament = EqualTemperament::find().by_edo(24);
+        let note_names = |notation: &dyn NotationSystem| {
+            Vec::from_iter((0..6).map(|index| notation.note_name(&temperament, index))).join(", ")
+        };
+
+        assert_eq!(
+            note_names(&HeptatonicNotation),
+            "D[0], D[1], (D#/Eb)[0], (D#/Eb)[1], E[0], E[1]"
+        );
+        assert_eq!(
+            note_names(&AccidentalCountNotation),
+            "D[0], D[1], (D#/Eb)[0], (D#/Eb)[1], E[0], E[1]"
+        );
+        assert_eq!(
+            note_names(&UpsAndDownsNotation),
+            "D, ^D, D#/Eb, vE, E, ^E/vF"
+        );
+        assert_eq!(
+            note_names(&SagittalNotation),
+            r"D, D/|\, D/||\, E\!/, E, E/|\"
+        );
+    }
+
+    #[test]
+    fn rank2_temperaments_from_edo_pairs() {
+        let patent_val = |num_steps_per_octave: u16, prime_limit| {
+            Val::patent(
+                Ratio::octave().divided_into_equal_steps(num_steps_per_octave),
+                prime_limit,
+            )
+        };
+
+        for (first_edo, second_edo, prime_limit, expected_name) in [
+            (12, 19, 5, "meantone"),
+            (12, 19, 7, "septimal meantone"),
+            (16, 19, 5, "magic"),
+            (19, 22, 7, "septimal magic"),
+            (15, 19, 5, "hanson"),
+            (15, 22, 5, "porcupine"),
+            (12, 15, 5, "augmented"),
+            (12, 16, 5, "diminished"),
+            (41, 53, 5, "schismatic"),
+            (10, 21, 7, "miracle"),
+            (22, 31, 7, "orwell"),
+            (12, 22, 7, "pajara"),
+        ] {
+            let temperament = Rank2Temperament::from_vals(
+                &patent_val(first_edo, prime_limit),
+                &patent_val(second_edo, prime_limit),
+            )
+            .unwrap();
+            assert_eq!(temperament.name(), Some(expected_name));
+            assert_eq!(temperament.prime_limit(), prime_limit);
+        }
+
+        for &(name, first_row, second_row) in KNOWN_RANK2_TEMPERAMENTS {
+            let temperament = Rank2Temperament::by_name(name).unwrap();
+            assert_eq!(temperament.name(), Some(name));
+            assert_eq!(
+                temperament.hermite_form,
+                [first_row.to_vec(), second_row.to_vec()]
+            );
+            assert!(temperament.generator().as_cents() >= 0.0);
+            assert!(temperament.generator() <= temperament.period().divided_into_equal_steps(2));
+        }
+    }
 }
@@ -0,0 +1,115 @@
+//! Interop with [AnaMark TUN](https://www.mark-henning.de/files/am/Tuning_File_V2_Doc.pdf) tuning files.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    key::PianoKey,
+    note::Note,
+    pitch::{Pitch, Ratio},
+    tuning::KeyboardMapping,
+};
+
+/// Exports the given [`KeyboardMapping`] in AnaMark TUN (version 2) file format.
+///
+/// The file contains a `[Tuning]` section with integer cent values for compatibility with older synthesizers and an `[Exact Tuning]` section with high-precision cent values.
+/// All cent values are relative to the base frequency of MIDI note 0 in standard tuning (8.1758 Hz).
+/// Unmapped keys are omitted s.t. synthesizers fall back to their default 12-EDO pitch.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::note::Note;
+/// # use tune::scala::KbmRoot;
+/// # use tune::scala::Scl;
+/// # use tune::tun;
+/// let scl = Scl::builder()
+///     .push_cents(150.0)
+///     .build()
+///     .unwrap();
+/// let kbm = KbmRoot::from(Note::from_midi_number(60)).to_kbm();
+///
+/// let exported = tun::export("Example scale", (scl, kbm)).to_string();
+/// let lines = exported.lines().collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     lines[..13],
+///     [
+///         "; Created by tune",
+///         "[Scale Begin]",
+///         "Format= \"AnaMark-TUN\"",
+///         "FormatVersion= 200",
+///         "FormatSpecs= \"http://www.mark-henning.de/eternity/tuningspecs.html\"",
+///         "",
+///         "[Info]",
+///         "Name= \"Example scale\"",
+///         "",
+///         "[Tuning]",
+///         "note 0= -3000",
+///         "note 1= -2850",
+///         "note 2= -2700",
+///     ]
+/// );
+/// assert_eq!(lines[138..142], ["", "[Exact Tuning]", "BaseFreq= 8.1757989156", "note 0= -3000.000000"]);
+/// assert_eq!(lines[lines.len() - 3..], ["note 127= 16050.000000", "", "[Scale End]"]);
+/// ```
+pub fn export<K: KeyboardMapping<PianoKey>>(name: impl Into<String>, mapping: K) -> TunExport<K> {
+    TunExport {
+        name: name.into(),
+        mapping,
+    }
+}
+
+/// Format / [`Display`] wrapper created by [`export`].
+pub struct TunExport<K> {
+    name: String,
+    mapping: K,
+}
+
+impl<K: KeyboardMapping<PianoKey>> TunExport<K> {
+    fn cents_values(&self) -> impl Iterator<Item = (i32, f64)> + '_ {
+        let base_pitch = base_pitch();
+        (0..128).filter_map(move |midi_number| {
+            self.mapping
+                .maybe_pitch_of(PianoKey::from_midi_number(midi_number))
+                .map(|pitch| {
+                    (
+                        midi_number,
+                        Ratio::between_pitches(base_pitch, pitch).as_cents(),
+                    )
+                })
+        })
+    }
+}
+
+impl<K: KeyboardMapping<PianoKey>> Display for TunExport<K> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "; Created by tune")?;
+        writeln!(f, "[Scale Begin]")?;
+        writeln!(f, "Format= \"AnaMark-TUN\"")?;
+        writeln!(f, "FormatVersion= 200")?;
+        writeln!(
+            f,
+            "FormatSpecs= \"http://www.mark-henning.de/eternity/tuningspecs.html\""
+        )?;
+        writeln!(f)?;
+        writeln!(f, "[Info]")?;
+        writeln!(f, "Name= \"{}\"", self.name.replace('"', "'"))?;
+        writeln!(f)?;
+        writeln!(f, "[Tuning]")?;
+        for (midi_number, cents) in self.cents_values() {
+            writeln!(f, "note {midi_number}= {}", cents.round())?;
+        }
+        writeln!(f)?;
+        writeln!(f, "[Exact Tuning]")?;
+        writeln!(f, "BaseFreq= {:.10}", base_pitch().as_hz())?;
+        for (midi_number, cents) in self.cents_values() {
+            writeln!(f, "note {midi_number}= {cents:.6}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "[Scale End]")
+    }
+}
+
+fn base_pitch() -> Pitch {
+    Pitch::of(Note::from_midi_number(0))
+}
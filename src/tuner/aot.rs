@@ -1,4 +1,7 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{BTreeSet, HashMap},
+    hash::Hash,
+};
 
 use crate::{
     mts::{
@@ -11,12 +14,13 @@ use crate::{
     tuning::KeyboardMapping,
 };
 
-use super::{Group, GroupBy, IsErr, TunableSynth};
+use super::{channels_attr, Group, GroupBy, IsErr, TunableSynth};
 
 pub struct AotTuner<K, S> {
     model: AotTuningModel<K>,
     synth: S,
     tuned: bool,
+    untunable_keys: Vec<UntunableKey<K>>,
 }
 
 impl<K, S: TunableSynth> AotTuner<K, S> {
@@ -26,6 +30,7 @@ impl<K, S: TunableSynth> AotTuner<K, S> {
             model: AotTuningModel::empty(synth.num_channels()),
             synth,
             tuned: false,
+            untunable_keys: Vec::new(),
         }
     }
 }
@@ -42,15 +47,18 @@ impl<K: Copy + Eq + Hash, S: TunableSynth> AotTuner<K, S> {
 
         let num_detunings = channel_detunings.len();
         if num_detunings > self.synth.num_channels() {
+            self.untunable_keys = model.untunable_keys(self.synth.num_channels());
             self.model = AotTuningModel::empty(self.synth.num_channels());
             self.tuned = false;
         } else {
             for (channel, channel_detuning) in channel_detunings.iter().enumerate() {
-                let detuned_notes: Vec<_> = channel_detuning
+                let mut detuned_notes: Vec<_> = channel_detuning
                     .tuning_map
                     .iter()
                     .map(|(&group, &detuning)| (group.ungroup(), detuning))
                     .collect();
+                // Keep the emitted messages reproducible
+                detuned_notes.sort_by_key(|&(note, _)| note.midi_number());
 
                 let result = self.synth.notes_detune(channel, &detuned_notes);
                 if result.is_err() {
@@ -59,15 +67,59 @@ impl<K: Copy + Eq + Hash, S: TunableSynth> AotTuner<K, S> {
             }
             self.model = model;
             self.tuned = true;
+            self.untunable_keys.clear();
         }
 
         Ok(num_detunings)
     }
 
+    /// Apply the ahead-of-time `tuning` for the given `keys` using a single channel.
+    ///
+    /// See [`AotTuningModel::apply_single_channel_tuning`] for how the keys are assigned to the notes of the channel.
+    /// Returns `Ok(false)` without touching the current tuning if the synthesizer does not support full keyboard tuning or if the tuning does not fit into 128 notes.
+    pub fn set_single_channel_tuning(
+        &mut self,
+        tuning: impl KeyboardMapping<K>,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<bool, S::Result> {
+        if self.synth.group_by() != GroupBy::Note {
+            return Ok(false);
+        }
+
+        let (model, channel_detuning) =
+            match AotTuningModel::apply_single_channel_tuning(tuning, keys) {
+                Some(result) => result,
+                None => return Ok(false),
+            };
+
+        let mut detuned_notes: Vec<_> = channel_detuning
+            .tuning_map
+            .iter()
+            .map(|(&note, &detuning)| (note, detuning))
+            .collect();
+        // Keep the emitted messages reproducible
+        detuned_notes.sort_by_key(|&(note, _)| note.midi_number());
+
+        let result = self.synth.notes_detune(0, &detuned_notes);
+        if result.is_err() {
+            return Err(result);
+        }
+        self.model = model;
+        self.tuned = true;
+        self.untunable_keys.clear();
+
+        Ok(true)
+    }
+
     pub fn tuned(&self) -> bool {
         self.tuned
     }
 
+    /// Returns the keys that prevented the last call to [`AotTuner::set_tuning`] from succeeding because they require more channels than the synthesizer provides.
+    pub fn untunable_keys(&self) -> &[UntunableKey<K>] {
+        &self.untunable_keys
+    }
+
     /// Starts a note with a pitch given by the currently loaded tuning.
     pub fn note_on(&mut self, key: K, attr: S::NoteAttr) -> S::Result {
         if let Some((channel, started_note)) = self.model.get_channel_and_note_for_key(key) {
@@ -97,6 +149,33 @@ impl<K: Copy + Eq + Hash, S: TunableSynth> AotTuner<K, S> {
         self.synth.global_attr(attr)
     }
 
+    /// Sets a channel-global attribute on the channel of the note with the given `key` only.
+    pub fn note_channel_attr(&mut self, key: K, attr: S::GlobalAttr) -> S::Result {
+        if let Some((channel, _)) = self.model.get_channel_and_note_for_key(key) {
+            return self.synth.channel_attr(channel, attr);
+        }
+        S::Result::ok()
+    }
+
+    /// Sets a channel-global attribute on the channels of the notes with the given `keys` only.
+    ///
+    /// Each channel receives the attribute once, even if it is shared by several notes.
+    pub fn notes_channel_attr(
+        &mut self,
+        keys: impl IntoIterator<Item = K>,
+        attr: S::GlobalAttr,
+    ) -> S::Result
+    where
+        S::GlobalAttr: Clone,
+    {
+        let channels: BTreeSet<_> = keys
+            .into_iter()
+            .filter_map(|key| self.model.get_channel_and_note_for_key(key))
+            .map(|(channel, _)| channel)
+            .collect();
+        channels_attr(&mut self.synth, channels, attr)
+    }
+
     /// Stops the current [`AotTuner`] yielding the consumed [`TunableSynth`] for future reuse.
     pub fn stop(self) -> S {
         self.synth
@@ -105,7 +184,7 @@ impl<K: Copy + Eq + Hash, S: TunableSynth> AotTuner<K, S> {
 
 /// Maps keys across multiple channels to overcome several tuning limitations.
 pub struct AotTuningModel<K> {
-    key_map: HashMap<K, (usize, Note)>,
+    key_map: HashMap<K, (usize, Note, Ratio)>,
     num_channels: usize,
 }
 
@@ -251,6 +330,128 @@ impl<K: Copy + Eq + Hash> AotTuningModel<K> {
         Self::apply_tuning_internal(|_| (), tuning, keys)
     }
 
+    /// Maps the provided [`KeyboardMapping`] onto the 128 notes of a single channel s.t. every key is played by a note of its own which is tuned to the exact pitch of the key.
+    ///
+    /// The keys are assigned to the notes in ascending pitch order and the notes are chosen s.t. the largest distance between the pitch of a key and its nominal note becomes minimal.
+    /// Keys whose pitch is outside of the MIDI range remain unmapped.
+    /// Returns [`None`] if more than 128 keys are within the MIDI range.
+    ///
+    /// Apply this strategy if your synthesizer has full keyboard tuning support and polyphony is more important than playing back each sample at its original speed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::note::Note;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::scala::KbmRoot;
+    /// # use tune::scala::Scl;
+    /// # use tune::tuner::AotTuningModel;
+    /// # use tune::tuning::Tuning;
+    /// let scl = Scl::builder()
+    ///     .push_ratio(Ratio::octave().divided_into_equal_steps(24))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let kbm_root = KbmRoot::from(Note::from_midi_number(62));
+    /// let edo_24_tuning = Tuning::<i32>::as_linear_mapping((&scl, kbm_root));
+    ///
+    /// // Two octaves of 24-EDO fit into the 128 notes of a single channel
+    /// let (model, channel_tuning) = AotTuningModel::apply_single_channel_tuning(
+    ///     &edo_24_tuning,
+    ///     -24..=24,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(model.num_channels(), 1);
+    /// assert_eq!(
+    ///     model.get_channel_and_note_for_key(-24),
+    ///     Some((0, Note::from_midi_number(38)))
+    /// );
+    /// assert_eq!(
+    ///     model.get_channel_and_note_for_key(0),
+    ///     Some((0, Note::from_midi_number(62)))
+    /// );
+    /// assert_eq!(
+    ///     model.get_channel_and_note_for_key(24),
+    ///     Some((0, Note::from_midi_number(86)))
+    /// );
+    ///
+    /// let fluid_tuning = channel_tuning.to_fluid_format();
+    /// assert_approx_eq!(fluid_tuning[38], 5000.0);
+    /// assert_approx_eq!(fluid_tuning[62], 6200.0);
+    /// assert_approx_eq!(fluid_tuning[63], 6250.0);
+    /// assert_approx_eq!(fluid_tuning[86], 7400.0);
+    ///
+    /// // The full MIDI range of 24-EDO does not fit into 128 notes
+    /// assert!(AotTuningModel::apply_single_channel_tuning(
+    ///     &edo_24_tuning,
+    ///     -200..200,
+    /// )
+    /// .is_none());
+    /// ```
+    pub fn apply_single_channel_tuning(
+        tuning: impl KeyboardMapping<K>,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Option<(Self, ChannelDetuning<Note>)> {
+        let mut to_assign: Vec<_> = keys
+            .into_iter()
+            .flat_map(|key| tuning.maybe_pitch_of(key).map(|pitch| (key, pitch)))
+            .filter(|(_, pitch)| {
+                pitch
+                    .find_in_tuning(())
+                    .approx_value
+                    .checked_midi_number()
+                    .is_some()
+            })
+            .collect();
+
+        if to_assign.len() > 128 {
+            return None;
+        }
+
+        to_assign.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        // Strictly ascending notes are equivalent to non-descending offsets (note - index).
+        // The minimax solution for the offsets is the mean of the prefix maximum and the suffix minimum of the ideal offsets.
+        let ideal_offsets: Vec<_> = (0..)
+            .zip(&to_assign)
+            .map(|(index, (_, pitch))| pitch.find_in_tuning(()).approx_value.midi_number() - index)
+            .collect();
+
+        let mut suffix_min = ideal_offsets.clone();
+        for index in (1..suffix_min.len()).rev() {
+            suffix_min[index - 1] = suffix_min[index - 1].min(suffix_min[index]);
+        }
+
+        let max_offset = 128 - i32::try_from(to_assign.len()).unwrap();
+        let mut prefix_max = i32::MIN;
+        let mut tuning_map = HashMap::new();
+        let mut key_map = HashMap::new();
+
+        for (index, ((key, pitch), (ideal_offset, suffix_min))) in (0..).zip(
+            to_assign
+                .into_iter()
+                .zip(ideal_offsets.into_iter().zip(suffix_min)),
+        ) {
+            prefix_max = prefix_max.max(ideal_offset);
+            let offset = (prefix_max + suffix_min).div_euclid(2).clamp(0, max_offset);
+            let note = Note::from_midi_number(index + offset);
+
+            let detuning = Ratio::between_pitches(note, pitch);
+            tuning_map.insert(note, detuning);
+            key_map.insert(key, (0, note, detuning));
+        }
+
+        Some((
+            Self {
+                key_map,
+                num_channels: 1,
+            },
+            ChannelDetuning { tuning_map },
+        ))
+    }
+
     pub fn apply_tuning(
         group_by: GroupBy,
         tuning: impl KeyboardMapping<K>,
@@ -294,7 +495,10 @@ impl<K: Copy + Eq + Hash> AotTuningModel<K> {
                     .is_none();
                 if note_slot_is_usable {
                     tuning_map.insert(note, approx.deviation);
-                    key_map.insert(key, (channel_tunings.len(), approx.approx_value));
+                    key_map.insert(
+                        key,
+                        (channel_tunings.len(), approx.approx_value, approx.deviation),
+                    );
                     notes_retuned_on_current_channel.insert(note, approx.deviation);
                 }
                 !note_slot_is_usable
@@ -317,7 +521,67 @@ impl<K: Copy + Eq + Hash> AotTuningModel<K> {
     ///
     /// See [`AotTuningModel::apply_full_keyboard_tuning`] for an explanation of how to use this method.
     pub fn get_channel_and_note_for_key(&self, key: K) -> Option<(usize, Note)> {
-        self.key_map.get(&key).copied()
+        self.key_map
+            .get(&key)
+            .map(|&(channel, note, _)| (channel, note))
+    }
+
+    /// Returns the keys that are assigned to a channel index of `num_channels` or higher, ordered by channel and note.
+    ///
+    /// Use this method to report which keys cannot be tuned if the synthesizer provides fewer channels than required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::key::PianoKey;
+    /// # use tune::note::Note;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::scala::KbmRoot;
+    /// # use tune::scala::Scl;
+    /// # use tune::tuner::AotTuningModel;
+    /// let scl = Scl::builder()
+    ///     .push_ratio(Ratio::octave().divided_into_equal_steps(24))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let kbm = KbmRoot::from(Note::from_midi_number(62)).to_kbm();
+    ///
+    /// let (model, _) = AotTuningModel::apply_full_keyboard_tuning(
+    ///     (scl, kbm),
+    ///     (60..64).map(PianoKey::from_midi_number),
+    /// );
+    ///
+    /// // The quarter tones require a second channel
+    /// assert_eq!(model.num_channels(), 2);
+    /// assert!(model.untunable_keys(2).is_empty());
+    ///
+    /// let untunable_keys = model.untunable_keys(1);
+    /// assert_eq!(untunable_keys.len(), 2);
+    /// assert_eq!(untunable_keys[0].key, PianoKey::from_midi_number(61));
+    /// assert_eq!(untunable_keys[0].channel, 1);
+    /// assert_eq!(untunable_keys[0].note, Note::from_midi_number(61));
+    /// assert_approx_eq!(untunable_keys[0].detuning.as_cents(), 50.0);
+    /// assert_eq!(untunable_keys[1].key, PianoKey::from_midi_number(63));
+    /// ```
+    pub fn untunable_keys(&self, num_channels: usize) -> Vec<UntunableKey<K>> {
+        let mut untunable_keys: Vec<_> = self
+            .key_map
+            .iter()
+            .filter(|(_, &(channel, _, _))| channel >= num_channels)
+            .map(|(&key, &(channel, note, detuning))| UntunableKey {
+                key,
+                channel,
+                note,
+                detuning,
+            })
+            .collect();
+        untunable_keys.sort_by(|a, b| {
+            (a.channel, a.note.midi_number())
+                .cmp(&(b.channel, b.note.midi_number()))
+                .then_with(|| a.detuning.total_cmp(&b.detuning))
+        });
+        untunable_keys
     }
 
     /// Returns the number of channels that this [`AotTuner`] will make use of.
@@ -326,6 +590,22 @@ impl<K: Copy + Eq + Hash> AotTuningModel<K> {
     }
 }
 
+/// A key that cannot be tuned since it is assigned to a channel that the synthesizer does not provide.
+#[derive(Copy, Clone, Debug)]
+pub struct UntunableKey<K> {
+    /// The affected key.
+    pub key: K,
+
+    /// The channel index the key would be played on.
+    pub channel: usize,
+
+    /// The note the key would be played on.
+    pub note: Note,
+
+    /// The detuning of `note` required to reach the pitch of `key`.
+    pub detuning: Ratio,
+}
+
 /// Defines the amount by which a group of notes is supposed to be detuned.
 #[derive(Clone, Debug)]
 pub struct ChannelDetuning<G> {
@@ -676,6 +956,44 @@ mod tests {
         assert_eq!(tunings.len(), 13); // The number of channels is high since no note letter can be reused
     }
 
+    #[test]
+    fn apply_single_channel_tuning_with_non_octave_scale() {
+        let scl = Scl::builder()
+            .push_ratio(Ratio::from_float(3.0).divided_into_equal_steps(13))
+            .build()
+            .unwrap();
+
+        let kbm = KbmRoot::from(Note::from_midi_number(62)).to_kbm();
+
+        let (model, tuning) = AotTuningModel::apply_single_channel_tuning(
+            (scl, kbm),
+            (0..128).map(PianoKey::from_midi_number),
+        )
+        .unwrap();
+
+        let (channels, notes) = extract_channels_and_notes(&model);
+        // Keys outside of the MIDI range remain unmapped
+        assert_eq!(channels[..20], [999; 20]);
+        assert_eq!(channels[20..107], [0; 87]);
+        assert_eq!(channels[107..], [999; 21]);
+        // All other keys keep their nearest note since there is at most one key per note
+        assert_eq!(
+            notes[20..107],
+            [
+                1, 2, 3, 5, 6, 8, 9, 11, 12, 14, 15, 17, 18, 20, 21, 22, 24, 25, 27, 28, 30, 31,
+                33, 34, 36, 37, 39, 40, 42, 43, 44, 46, 47, 49, 50, 52, 53, 55, 56, 58, 59, 61, 62,
+                63, 65, 66, 68, 69, 71, 72, 74, 75, 77, 78, 80, 81, 82, 84, 85, 87, 88, 90, 91, 93,
+                94, 96, 97, 99, 100, 102, 103, 104, 106, 107, 109, 110, 112, 113, 115, 116, 118,
+                119, 121, 122, 123, 125, 126
+            ]
+        );
+
+        let fluid_tuning = tuning.to_fluid_format();
+        assert_approx_eq!(fluid_tuning[61], 6053.696, 1e-3);
+        assert_approx_eq!(fluid_tuning[62], 6200.0);
+        assert_approx_eq!(fluid_tuning[63], 6346.304, 1e-3);
+    }
+
     fn extract_channels_and_notes(tuner: &AotTuningModel<PianoKey>) -> (Vec<usize>, Vec<i32>) {
         (0..128)
             .map(|midi_number| {
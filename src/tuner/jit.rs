@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     hash::Hash,
 };
 
@@ -9,7 +9,7 @@ use crate::{
     tuning::Approximation,
 };
 
-use super::{Group, GroupBy, IsErr, TunableSynth};
+use super::{channels_attr, Group, GroupBy, IsErr, TunableSynth};
 
 pub struct JitTuner<K, S> {
     model: JitTuningModel<K>,
@@ -92,11 +92,47 @@ impl<K: Copy + Eq + Hash, S: TunableSynth> JitTuner<K, S> {
         }
     }
 
+    /// Returns `true` if a new note with the given `pitch` cannot be started on a free channel.
+    ///
+    /// Depending on the [`PoolingMode`], starting the note will either be rejected, stop an old note or retune an old note.
+    pub fn channels_exhausted(&self, pitch: Pitch) -> bool {
+        self.model.channels_exhausted(pitch)
+    }
+
     /// Sets a channel-global attribute.
     pub fn global_attr(&mut self, attr: S::GlobalAttr) -> S::Result {
         self.synth.global_attr(attr)
     }
 
+    /// Sets a channel-global attribute on the channel of the note with the given `key` only.
+    pub fn note_channel_attr(&mut self, key: K, attr: S::GlobalAttr) -> S::Result {
+        match self.model.access_key(key) {
+            AccessKeyResult::Found { channel, .. } => self.synth.channel_attr(channel, attr),
+            AccessKeyResult::NotFound => S::Result::ok(),
+        }
+    }
+
+    /// Sets a channel-global attribute on the channels of the notes with the given `keys` only.
+    ///
+    /// Each channel receives the attribute once, even if it is shared by several notes.
+    pub fn notes_channel_attr(
+        &mut self,
+        keys: impl IntoIterator<Item = K>,
+        attr: S::GlobalAttr,
+    ) -> S::Result
+    where
+        S::GlobalAttr: Clone,
+    {
+        let channels: BTreeSet<_> = keys
+            .into_iter()
+            .filter_map(|key| match self.model.access_key(key) {
+                AccessKeyResult::Found { channel, .. } => Some(channel),
+                AccessKeyResult::NotFound => None,
+            })
+            .collect();
+        channels_attr(&mut self.synth, channels, attr)
+    }
+
     /// Stops the current [`JitTuner`] yielding the consumed [`TunableSynth`] for future reuse.
     pub fn stop(mut self) -> S {
         let active_keys: Vec<_> = self.model.active_keys().collect();
@@ -163,6 +199,16 @@ impl<K: Copy + Eq + Hash> JitTuningModel<K> {
         }
     }
 
+    /// Returns `true` if a new key with the given `pitch` cannot be registered on a free channel.
+    pub fn channels_exhausted(&self, pitch: Pitch) -> bool {
+        let group = self.group_by.group(pitch.find_in_tuning(()).approx_value);
+
+        match self.pools.get(&group) {
+            Some(pool) => pool.free.is_empty(),
+            None => self.num_channels == 0,
+        }
+    }
+
     pub fn deregister_key(&mut self, key: K) -> AccessKeyResult {
         let pools = &mut self.pools;
         match self
@@ -439,4 +485,23 @@ mod tests {
         assert_eq!(pool.find_key("keyD"), None);
         assert_eq!(pool.find_key("keyE"), None);
     }
+
+    #[test]
+    fn channels_exhausted() {
+        let mut model = JitTuningModel::new(2, GroupBy::Channel, PoolingMode::Block);
+        let pitch = Note::from_midi_number(60).pitch();
+
+        assert!(!model.channels_exhausted(pitch));
+        model.register_key("keyA", pitch);
+        assert!(!model.channels_exhausted(pitch));
+        model.register_key("keyB", pitch);
+        assert!(model.channels_exhausted(pitch));
+        assert!(matches!(
+            model.register_key("keyC", pitch),
+            RegisterKeyResult::Rejected
+        ));
+
+        model.deregister_key("keyA");
+        assert!(!model.channels_exhausted(pitch));
+    }
 }
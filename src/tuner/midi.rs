@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
 use crate::{
-    midi::{ChannelMessage, ChannelMessageType},
+    midi::{
+        self, ChannelMessage, ChannelMessageType, NotePitch7_25, NotePitch7_9, UmpChannelMessage,
+        UmpChannelMessageType,
+    },
     mts::{
         self, ScaleOctaveTuning, ScaleOctaveTuningFormat, ScaleOctaveTuningMessage,
         ScaleOctaveTuningOptions, SingleNoteTuningChange, SingleNoteTuningChangeMessage,
@@ -65,6 +68,63 @@ impl<H> TunableMidi<H> {
             midi_tuning_creator: MidiTuningCreator::PitchBend,
         }
     }
+
+    /// Tunes each note individually using MIDI 2.0 Universal MIDI Packets in the given UMP `group`.
+    ///
+    /// Notes are started with the "Pitch 7.9" note attribute and retuned with the "Pitch 7.25" registered per-note controller.
+    /// Since the tuning is applied per [`Note`], a single channel is sufficient.
+    /// Channel-global messages are sent as MIDI 1.0 Channel Voice messages within the same UMP group.
+    pub fn per_note_pitch(midi_target: MidiTarget<H>, group: u8) -> Self {
+        Self {
+            midi_target,
+            midi_tuning_creator: MidiTuningCreator::PerNotePitch {
+                group,
+                detunings: HashMap::new(),
+            },
+        }
+    }
+}
+
+impl<H: MidiTunerMessageHandler> TunableMidi<H> {
+    /// Tunes each note individually using MIDI Polyphonic Expression (MPE).
+    ///
+    /// The channels of `midi_target` are used as the member channels of the given MPE `zone`, s.t. [`MpeZone::member_channels`] is the natural choice.
+    /// On creation, the zone is configured via the *MPE Configuration Message* and the pitch bend range of each member channel is set to `pitch_bend_range` semitones.
+    ///
+    /// Each note is tuned via pitch bend on its own member channel and polyphonic key pressure is sent as channel pressure on that channel.
+    /// Channel-global messages are sent to the zone's manager channel.
+    pub fn mpe(mut midi_target: MidiTarget<H>, zone: MpeZone, pitch_bend_range: u8) -> Self {
+        let manager_channel = zone.manager_channel();
+        let num_member_channels = u8::try_from(midi_target.channels.len()).unwrap_or(u8::MAX);
+
+        for channel_message in mts::mpe_configuration(manager_channel, num_member_channels)
+            .into_iter()
+            .flatten()
+        {
+            midi_target
+                .handler
+                .handle(MidiTunerMessage::new(channel_message));
+        }
+
+        for &member_channel in &midi_target.channels {
+            for channel_message in mts::pitch_bend_sensitivity(member_channel, pitch_bend_range)
+                .into_iter()
+                .flatten()
+            {
+                midi_target
+                    .handler
+                    .handle(MidiTunerMessage::new(channel_message));
+            }
+        }
+
+        Self {
+            midi_target,
+            midi_tuning_creator: MidiTuningCreator::Mpe {
+                manager_channel,
+                pitch_bend_range,
+            },
+        }
+    }
 }
 
 impl<H: MidiTunerMessageHandler> TunableSynth for TunableMidi<H> {
@@ -87,7 +147,8 @@ impl<H: MidiTunerMessageHandler> TunableSynth for TunableMidi<H> {
 
     fn note_on(&mut self, channel: usize, started_note: Note, velocity: u8) {
         if let Some(started_note) = started_note.checked_midi_number() {
-            self.midi_target.send(
+            self.midi_tuning_creator.send(
+                &mut self.midi_target,
                 ChannelMessageType::NoteOn {
                     key: started_note,
                     velocity,
@@ -99,7 +160,8 @@ impl<H: MidiTunerMessageHandler> TunableSynth for TunableMidi<H> {
 
     fn note_off(&mut self, channel: usize, stopped_note: Note, velocity: u8) {
         if let Some(stopped_note) = stopped_note.checked_midi_number() {
-            self.midi_target.send(
+            self.midi_tuning_creator.send(
+                &mut self.midi_target,
                 ChannelMessageType::NoteOff {
                     key: stopped_note,
                     velocity,
@@ -111,7 +173,8 @@ impl<H: MidiTunerMessageHandler> TunableSynth for TunableMidi<H> {
 
     fn note_attr(&mut self, channel: usize, affected_note: Note, pressure: u8) {
         if let Some(affected_note) = affected_note.checked_midi_number() {
-            self.midi_target.send(
+            self.midi_tuning_creator.send(
+                &mut self.midi_target,
                 ChannelMessageType::PolyphonicKeyPressure {
                     key: affected_note,
                     pressure,
@@ -122,14 +185,68 @@ impl<H: MidiTunerMessageHandler> TunableSynth for TunableMidi<H> {
     }
 
     fn global_attr(&mut self, message_type: ChannelMessageType) {
+        if let MidiTuningCreator::Mpe {
+            manager_channel, ..
+        } = self.midi_tuning_creator
+        {
+            return self
+                .midi_target
+                .handler
+                .handle_channel_message(message_type, manager_channel);
+        }
+
         for channel in 0..self.num_channels() {
-            if self.midi_tuning_creator.allow_pitch_bend()
-                || !matches!(message_type, ChannelMessageType::PitchBendChange { .. })
-            {
-                self.midi_target.send(message_type, channel);
-            }
+            self.channel_attr(channel, message_type);
         }
     }
+
+    fn channel_attr(&mut self, channel: usize, message_type: ChannelMessageType) {
+        if self.midi_tuning_creator.allow_pitch_bend()
+            || !matches!(message_type, ChannelMessageType::PitchBendChange { .. })
+        {
+            self.midi_tuning_creator
+                .send(&mut self.midi_target, message_type, channel);
+        }
+    }
+
+    fn has_channel_attr(&self) -> bool {
+        true
+    }
+}
+
+/// The lower or upper zone of an MPE setup.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpeZone {
+    /// Zone managed by channel 0 with member channels counting upwards from channel 1.
+    Lower,
+    /// Zone managed by channel 15 with member channels counting downwards from channel 14.
+    Upper,
+}
+
+impl MpeZone {
+    pub fn manager_channel(self) -> u8 {
+        match self {
+            MpeZone::Lower => 0,
+            MpeZone::Upper => 15,
+        }
+    }
+
+    /// Allocates `num_member_channels` member channels. At most 15 member channels are available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::tuner::MpeZone;
+    /// assert_eq!(MpeZone::Lower.member_channels(3).collect::<Vec<_>>(), [1, 2, 3]);
+    /// assert_eq!(MpeZone::Upper.member_channels(3).collect::<Vec<_>>(), [14, 13, 12]);
+    /// assert_eq!(MpeZone::Lower.member_channels(20).count(), 15);
+    /// ```
+    pub fn member_channels(self, num_member_channels: u8) -> impl Iterator<Item = u8> {
+        (1..=num_member_channels.min(15)).map(move |index| match self {
+            MpeZone::Lower => index,
+            MpeZone::Upper => 15 - index,
+        })
+    }
 }
 
 pub struct MidiTarget<H> {
@@ -166,6 +283,14 @@ enum MidiTuningCreator {
     },
     ChannelFineTuning,
     PitchBend,
+    PerNotePitch {
+        group: u8,
+        detunings: HashMap<(usize, Note), Ratio>,
+    },
+    Mpe {
+        manager_channel: u8,
+        pitch_bend_range: u8,
+    },
 }
 
 impl MidiTuningCreator {
@@ -249,7 +374,7 @@ impl MidiTuningCreator {
             }
             MidiTuningCreator::PitchBend => {
                 for &(_, detuning) in detuned_notes {
-                    let channel_message = pitch_bend_message(detuning)
+                    let channel_message = pitch_bend_message(detuning, 2)
                         .in_channel(midi_channel)
                         .unwrap();
                     target
@@ -257,14 +382,104 @@ impl MidiTuningCreator {
                         .handle(MidiTunerMessage::new(channel_message));
                 }
             }
+            MidiTuningCreator::Mpe {
+                pitch_bend_range, ..
+            } => {
+                for &(_, detuning) in detuned_notes {
+                    target.send(
+                        pitch_bend_message(detuning, *pitch_bend_range),
+                        tuner_channel,
+                    );
+                }
+            }
+            MidiTuningCreator::PerNotePitch { group, detunings } => {
+                for &(note, detuning) in detuned_notes {
+                    detunings.insert((tuner_channel, note), detuning);
+
+                    if let Some(key) = note.checked_midi_number() {
+                        let pitch = NotePitch7_25::from_pitch(note.pitch() * detuning);
+                        if let Some(ump_message) = UmpChannelMessageType::per_note_pitch(key, pitch)
+                            .in_group_and_channel(*group, midi_channel)
+                        {
+                            target.handler.handle(MidiTunerMessage::new(ump_message));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        target: &mut MidiTarget<impl MidiTunerMessageHandler>,
+        message_type: ChannelMessageType,
+        tuner_channel: usize,
+    ) {
+        let (group, detunings) = match self {
+            MidiTuningCreator::PerNotePitch { group, detunings } => (*group, detunings),
+            MidiTuningCreator::Mpe { .. } => {
+                let message_type = match message_type {
+                    ChannelMessageType::PolyphonicKeyPressure { pressure, .. } => {
+                        ChannelMessageType::ChannelPressure { pressure }
+                    }
+                    message_type => message_type,
+                };
+                return target.send(message_type, tuner_channel);
+            }
+            _ => return target.send(message_type, tuner_channel),
+        };
+
+        let midi_channel = target.midi_channel(tuner_channel);
+        let pitch = |key| {
+            let note = Note::from_midi_number(key);
+            let detuning = detunings
+                .get(&(tuner_channel, note))
+                .copied()
+                .unwrap_or_default();
+            Some(NotePitch7_9::from_pitch(note.pitch() * detuning))
+        };
+
+        let ump_message_type = match message_type {
+            ChannelMessageType::NoteOff { key, velocity } => UmpChannelMessageType::NoteOff {
+                key,
+                velocity: midi::scale_up(velocity.into(), 7, 16) as u16,
+                pitch: pitch(key),
+            },
+            ChannelMessageType::NoteOn { key, velocity } => UmpChannelMessageType::NoteOn {
+                key,
+                velocity: midi::scale_up(velocity.into(), 7, 16) as u16,
+                pitch: pitch(key),
+            },
+            ChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
+                UmpChannelMessageType::PolyphonicKeyPressure {
+                    key,
+                    pressure: midi::scale_up(pressure.into(), 7, 32),
+                }
+            }
+            _ => {
+                if let Some(ump_word) = message_type
+                    .in_channel(midi_channel)
+                    .and_then(|message| message.to_ump_word(group))
+                {
+                    target.handler.handle(MidiTunerMessage::new(ump_word));
+                }
+                return;
+            }
+        };
+
+        if let Some(ump_message) = ump_message_type.in_group_and_channel(group, midi_channel) {
+            target.handler.handle(MidiTunerMessage::new(ump_message));
         }
     }
 
     fn group_by(&self) -> GroupBy {
         match self {
-            MidiTuningCreator::SingleNoteTuningChange { .. } => GroupBy::Note,
+            MidiTuningCreator::SingleNoteTuningChange { .. }
+            | MidiTuningCreator::PerNotePitch { .. } => GroupBy::Note,
             MidiTuningCreator::ScaleOctaveTuning { .. } => GroupBy::NoteLetter,
-            MidiTuningCreator::ChannelFineTuning | MidiTuningCreator::PitchBend => GroupBy::Channel,
+            MidiTuningCreator::ChannelFineTuning
+            | MidiTuningCreator::PitchBend
+            | MidiTuningCreator::Mpe { .. } => GroupBy::Channel,
         }
     }
 
@@ -272,8 +487,9 @@ impl MidiTuningCreator {
         match self {
             MidiTuningCreator::SingleNoteTuningChange { .. }
             | MidiTuningCreator::ScaleOctaveTuning { .. }
-            | MidiTuningCreator::ChannelFineTuning => true,
-            MidiTuningCreator::PitchBend => false,
+            | MidiTuningCreator::ChannelFineTuning
+            | MidiTuningCreator::PerNotePitch { .. } => true,
+            MidiTuningCreator::PitchBend | MidiTuningCreator::Mpe { .. } => false,
         }
     }
 }
@@ -302,6 +518,15 @@ impl MidiTunerMessage {
                     receiver(sysex_bytes);
                 }
             }
+            MidiTunerMessageVariant::UmpMidi1(ump_word) => {
+                receiver(&ump_word.to_be_bytes());
+            }
+            MidiTunerMessageVariant::UmpMidi2(ump_message) => {
+                let [word1, word2] = ump_message.to_ump_words();
+                let [b0, b1, b2, b3] = word1.to_be_bytes();
+                let [b4, b5, b6, b7] = word2.to_be_bytes();
+                receiver(&[b0, b1, b2, b3, b4, b5, b6, b7]);
+            }
         }
     }
 }
@@ -310,6 +535,8 @@ enum MidiTunerMessageVariant {
     Channel(ChannelMessage),
     ScaleOctaveTuning(ScaleOctaveTuningMessage),
     SingleNoteTuningChange(SingleNoteTuningChangeMessage),
+    UmpMidi1(u32),
+    UmpMidi2(UmpChannelMessage),
 }
 
 impl From<ChannelMessage> for MidiTunerMessageVariant {
@@ -330,6 +557,18 @@ impl From<SingleNoteTuningChangeMessage> for MidiTunerMessageVariant {
     }
 }
 
+impl From<u32> for MidiTunerMessageVariant {
+    fn from(v: u32) -> Self {
+        Self::UmpMidi1(v)
+    }
+}
+
+impl From<UmpChannelMessage> for MidiTunerMessageVariant {
+    fn from(v: UmpChannelMessage) -> Self {
+        Self::UmpMidi2(v)
+    }
+}
+
 pub trait MidiTunerMessageHandler {
     fn handle(&mut self, message: MidiTunerMessage);
 
@@ -346,8 +585,206 @@ impl<F: FnMut(MidiTunerMessage)> MidiTunerMessageHandler for F {
     }
 }
 
-fn pitch_bend_message(detuning: Ratio) -> ChannelMessageType {
+fn pitch_bend_message(detuning: Ratio, pitch_bend_range: u8) -> ChannelMessageType {
     ChannelMessageType::PitchBendChange {
-        value: ((detuning.as_semitones() / 2.0 * 8192.0) as i16).clamp(-8192, 8192),
+        value: ((detuning.as_semitones() / f64::from(pitch_bend_range) * 8192.0) as i16)
+            .clamp(-8192, 8192),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        note::NoteLetter,
+        tuner::{JitTuner, PoolingMode},
+    };
+
+    use super::*;
+
+    #[test]
+    fn per_note_pitch_on_single_channel() {
+        let mut sent_messages = Vec::new();
+
+        let target = MidiTarget {
+            handler: |message: MidiTunerMessage| {
+                message.send_to(|bytes| sent_messages.push(bytes.to_vec()))
+            },
+            channels: vec![5],
+        };
+        let mut tuner = JitTuner::start(TunableMidi::per_note_pitch(target, 2), PoolingMode::Stop);
+
+        let a4 = NoteLetter::A.in_octave(4).pitch();
+        let c5_plus_25_cents = NoteLetter::C.in_octave(5).pitch() * Ratio::from_cents(25.0);
+
+        tuner.note_on('a', a4, 100);
+        tuner.note_on('c', c5_plus_25_cents, 64);
+        tuner.note_off('a', 127);
+        tuner.global_attr(ChannelMessageType::ControlChange {
+            controller: 64,
+            value: 127,
+        });
+        tuner.stop();
+
+        assert_eq!(
+            sent_messages,
+            [
+                // Pitch 7.25 of A4
+                vec![0x42, 0x05, 69, 3, 0x8a, 0x00, 0x00, 0x00],
+                // Note On A4 with Pitch 7.9
+                vec![0x42, 0x95, 69, 3, 0xc9, 0x24, 0x8a, 0x00],
+                // Pitch 7.25 of C5 + 25c
+                vec![0x42, 0x05, 72, 3, 0x90, 0x80, 0x00, 0x00],
+                // Note On C5 + 25c with Pitch 7.9
+                vec![0x42, 0x95, 72, 3, 0x80, 0x00, 0x90, 0x80],
+                // Note Off A4 with Pitch 7.9
+                vec![0x42, 0x85, 69, 3, 0xff, 0xff, 0x8a, 0x00],
+                // MIDI 1.0 Control Change
+                vec![0x22, 0xb5, 64, 127],
+                // Note Off C5 + 25c with Pitch 7.9
+                vec![0x42, 0x85, 72, 3, 0x00, 0x00, 0x90, 0x80],
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_attr_on_note_channel_only() {
+        let mut sent_messages = Vec::new();
+
+        let target = MidiTarget {
+            handler: |message: MidiTunerMessage| {
+                message.send_to(|bytes| sent_messages.push(bytes.to_vec()))
+            },
+            channels: vec![0, 1, 2],
+        };
+        let mut tuner = JitTuner::start(TunableMidi::pitch_bend(target), PoolingMode::Stop);
+
+        let a4 = NoteLetter::A.in_octave(4).pitch();
+        let c5 = NoteLetter::C.in_octave(5).pitch();
+
+        tuner.note_on('a', a4, 100);
+        tuner.note_on('c', c5, 100);
+        tuner.note_channel_attr('c', ChannelMessageType::ChannelPressure { pressure: 80 });
+        tuner.note_channel_attr('x', ChannelMessageType::ChannelPressure { pressure: 90 });
+        tuner.note_channel_attr('a', ChannelMessageType::PitchBendChange { value: 4096 });
+        tuner.note_off('a', 0);
+        tuner.note_off('c', 0);
+        tuner.stop();
+
+        assert_eq!(
+            sent_messages,
+            [
+                // A4 on channel 0
+                vec![0xe0, 0x00, 0x40],
+                vec![0x90, 69, 100],
+                // C5 on channel 1
+                vec![0xe1, 0x00, 0x40],
+                vec![0x91, 72, 100],
+                // Channel pressure on the channel of C5 only. Unknown keys and pitch bend are ignored
+                vec![0xd1, 80, 0],
+                // Note Offs
+                vec![0x80, 69, 0],
+                vec![0x81, 72, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_attr_once_per_channel() {
+        let mut sent_messages = Vec::new();
+
+        let target = MidiTarget {
+            handler: |message: MidiTunerMessage| {
+                message.send_to(|bytes| sent_messages.push(bytes.to_vec()))
+            },
+            channels: vec![0, 1],
+        };
+        let mut tuner =
+            JitTuner::start(TunableMidi::channel_fine_tuning(target), PoolingMode::Stop);
+
+        let a4 = NoteLetter::A.in_octave(4).pitch();
+        let c5 = NoteLetter::C.in_octave(5).pitch();
+
+        tuner.note_on('a', a4, 100);
+        tuner.note_on('c', c5, 100);
+        tuner.note_on('A', a4, 100);
+        tuner.notes_channel_attr(
+            ['a', 'A', 'c', 'x'],
+            ChannelMessageType::ChannelPressure { pressure: 80 },
+        );
+        tuner.stop();
+
+        let channel_pressure_messages: Vec<_> = sent_messages
+            .into_iter()
+            .filter(|message| message[0] & 0xf0 == 0xd0)
+            .collect();
+
+        assert_eq!(
+            channel_pressure_messages,
+            [
+                // A4 and C5 share channel 0
+                vec![0xd0, 80, 0],
+                // The second A4 is on channel 1
+                vec![0xd1, 80, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn mpe_in_upper_zone() {
+        let mut sent_messages = Vec::new();
+
+        let target = MidiTarget {
+            handler: |message: MidiTunerMessage| {
+                message.send_to(|bytes| sent_messages.push(bytes.to_vec()))
+            },
+            channels: MpeZone::Upper.member_channels(2).collect(),
+        };
+        let mut tuner = JitTuner::start(
+            TunableMidi::mpe(target, MpeZone::Upper, 48),
+            PoolingMode::Stop,
+        );
+
+        let a4 = NoteLetter::A.in_octave(4).pitch();
+        let c5_plus_24_cents = NoteLetter::C.in_octave(5).pitch() * Ratio::from_cents(24.0);
+
+        tuner.note_on('a', a4, 100);
+        tuner.note_on('c', c5_plus_24_cents, 64);
+        tuner.note_attr('c', 80);
+        tuner.global_attr(ChannelMessageType::PitchBendChange { value: 4096 });
+        tuner.note_off('a', 0);
+        tuner.note_off('c', 0);
+        tuner.stop();
+
+        assert_eq!(
+            sent_messages,
+            [
+                // MPE Configuration Message: Upper zone with 2 member channels
+                vec![0xbf, 0x65, 0x00],
+                vec![0xbf, 0x64, 0x06],
+                vec![0xbf, 0x06, 2],
+                // Pitch bend sensitivity of 48 semitones on member channels 14 and 13
+                vec![0xbe, 0x65, 0x00],
+                vec![0xbe, 0x64, 0x00],
+                vec![0xbe, 0x06, 48],
+                vec![0xbe, 0x26, 0],
+                vec![0xbd, 0x65, 0x00],
+                vec![0xbd, 0x64, 0x00],
+                vec![0xbd, 0x06, 48],
+                vec![0xbd, 0x26, 0],
+                // A4 on channel 14
+                vec![0xee, 0x00, 0x40],
+                vec![0x9e, 69, 100],
+                // C5 + 24c on channel 13: 8192 + 0.24 / 48 * 8192 = 8232
+                vec![0xed, 0x28, 0x40],
+                vec![0x9d, 72, 64],
+                // Per-note pressure on channel 13
+                vec![0xdd, 80, 0],
+                // Zone-wide pitch bend on manager channel 15
+                vec![0xef, 0x00, 0x60],
+                // Note Offs on member channels
+                vec![0x8e, 69, 0],
+                vec![0x8d, 72, 0],
+            ]
+        );
     }
 }
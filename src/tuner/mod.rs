@@ -4,7 +4,7 @@ mod aot;
 mod jit;
 mod midi;
 
-use std::hash::Hash;
+use std::{collections::BTreeSet, hash::Hash};
 
 use crate::{
     note::{Note, NoteLetter},
@@ -43,6 +43,42 @@ pub trait TunableSynth {
     ) -> Self::Result;
 
     fn global_attr(&mut self, attr: Self::GlobalAttr) -> Self::Result;
+
+    /// Sets a channel-global attribute on the given `channel` only.
+    ///
+    /// The default implementation falls back to [`TunableSynth::global_attr`] which is reported by [`TunableSynth::has_channel_attr`].
+    fn channel_attr(&mut self, _channel: usize, attr: Self::GlobalAttr) -> Self::Result {
+        self.global_attr(attr)
+    }
+
+    /// Returns `false` if [`TunableSynth::channel_attr`] affects all channels instead of the given channel only.
+    fn has_channel_attr(&self) -> bool {
+        false
+    }
+}
+
+/// Sets a channel-global attribute once on each of the given `channels` or once globally if the synth does not support channel attributes.
+fn channels_attr<S: TunableSynth>(
+    synth: &mut S,
+    channels: BTreeSet<usize>,
+    attr: S::GlobalAttr,
+) -> S::Result
+where
+    S::GlobalAttr: Clone,
+{
+    if channels.is_empty() {
+        return S::Result::ok();
+    }
+    if !synth.has_channel_attr() {
+        return synth.global_attr(attr);
+    }
+    for channel in channels {
+        let result = synth.channel_attr(channel, attr.clone());
+        if result.is_err() {
+            return result;
+        }
+    }
+    S::Result::ok()
 }
 
 pub trait IsErr {
@@ -2,6 +2,8 @@
 
 #![allow(clippy::wrong_self_convention)] // Would require a breaking change. Fix when appropriate.
 
+use std::ops::Range;
+
 use crate::{
     note::{Note, NoteLetter},
     pitch::{Pitch, Pitched, Ratio},
@@ -91,13 +93,94 @@ impl<K: Copy, T: Tuning<K> + ?Sized> Pitched for (K, T) {
 /// A [`Scale`] is a tuning whose [`Pitch`]es can be accessed in a sorted manner.
 ///
 /// Accessing pitches in order can be important, e.g. when handling pitches in a certain frequency window.
+///
+/// Implementors guarantee that [`Scale::sorted_pitch_of`] is non-decreasing in `degree`, even if the underlying scale items are not monotonic (e.g. an [`Scl`](crate::scala::Scl) with unsorted items).
+/// This allows [`Scale::find_by_pitch_sorted`] to be implemented as a binary search.
+///
+/// Scales with duplicate items, or items equal to the unison or the period, contain several degrees sharing the same [`Pitch`].
+/// For those, [`Scale::find_by_pitch_sorted`] returns the shared degree closest to the searched [`Pitch`], i.e. the lowest one if the searched [`Pitch`] is lower than or equal to the shared [`Pitch`] and the highest one otherwise.
+/// Use [`Scl::sorted_degree_of`](crate::scala::Scl::sorted_degree_of) and [`Scl::degree_of_sorted`](crate::scala::Scl::degree_of_sorted) to convert between sorted and unsorted degrees.
 pub trait Scale {
     /// Returns the [`Pitch`] at the given scale degree in the current [`Scale`].
     fn sorted_pitch_of(&self, degree: i32) -> Pitch;
 
     /// Finds a closest scale degree [`Approximation`] for the given [`Pitch`] in the current [`Scale`].
+    ///
+    /// The returned degree is one of the two degrees enclosing `pitch`, s.t. `sorted_pitch_of(find_by_pitch_sorted(pitch).approx_value)` is as close to `pitch` as possible.
+    /// See the [`Scale`] documentation for how degrees with equal pitches are handled.
     fn find_by_pitch_sorted(&self, pitch: Pitch) -> Approximation<i32>;
 
+    /// Lazily iterates over the given scale `degrees` and their [`Pitch`]es in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::note::NoteLetter;
+    /// # use tune::scala::KbmRoot;
+    /// # use tune::scala::Scl;
+    /// use tune::tuning::Scale;
+    ///
+    /// let scl = Scl::builder()
+    ///     .push_cents(100.0)
+    ///     .push_cents(50.0)
+    ///     .push_cents(150.0)
+    ///     .build()
+    ///     .unwrap();
+    /// let kbm_root = KbmRoot::from(NoteLetter::A.in_octave(4));
+    ///
+    /// let pitches = Vec::from_iter((scl, kbm_root).sorted_pitches(-1..3));
+    ///
+    /// assert_eq!(pitches.len(), 4);
+    /// assert_eq!(pitches[0].0, -1);
+    /// assert_approx_eq!(pitches[0].1.as_hz(), 440.0 / 2f64.powf(1.0 / 24.0));
+    /// assert_approx_eq!(pitches[1].1.as_hz(), 440.0);
+    /// assert_approx_eq!(pitches[2].1.as_hz(), 440.0 * 2f64.powf(1.0 / 24.0));
+    /// assert_approx_eq!(pitches[3].1.as_hz(), 440.0 * 2f64.powf(1.0 / 12.0));
+    /// ```
+    fn sorted_pitches(&self, degrees: Range<i32>) -> SortedPitches<&Self> {
+        SortedPitches {
+            scale: self,
+            degrees,
+        }
+    }
+
+    /// Lazily iterates over all scale degrees whose [`Pitch`]es lie within `pitch_range`, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::note::NoteLetter;
+    /// # use tune::pitch::Pitch;
+    /// # use tune::scala::KbmRoot;
+    /// # use tune::scala::Scl;
+    /// use tune::tuning::Scale;
+    ///
+    /// let scl = Scl::builder().push_cents(100.0).build().unwrap();
+    /// let kbm_root = KbmRoot::from(NoteLetter::A.in_octave(4));
+    /// let tuning = (scl, kbm_root);
+    ///
+    /// let a4_to_a5 = Pitch::from_hz(440.0)..Pitch::from_hz(880.0);
+    /// let degrees = Vec::from_iter(tuning.sorted_pitches_within(a4_to_a5).map(|(degree, _)| degree));
+    /// assert_eq!(degrees, (0..12).collect::<Vec<_>>());
+    ///
+    /// let empty_range = Pitch::from_hz(441.0)..Pitch::from_hz(442.0);
+    /// assert_eq!(tuning.sorted_pitches_within(empty_range).count(), 0);
+    /// ```
+    fn sorted_pitches_within(&self, pitch_range: Range<Pitch>) -> SortedPitches<&Self> {
+        let mut lowest_degree = self.find_by_pitch_sorted(pitch_range.start).approx_value;
+        if self.sorted_pitch_of(lowest_degree) < pitch_range.start {
+            lowest_degree += 1;
+        }
+
+        let mut highest_degree = self.find_by_pitch_sorted(pitch_range.end).approx_value;
+        if self.sorted_pitch_of(highest_degree) >= pitch_range.end {
+            highest_degree -= 1;
+        }
+
+        self.sorted_pitches(lowest_degree..highest_degree + 1)
+    }
+
     /// Wraps `self` in a type adapter s.t. it can be used in functions that are generic over [`Tuning<i32>`].
     fn as_sorted_tuning(self) -> SortedTuning<Self>
     where
@@ -118,6 +201,36 @@ impl<S: Scale + ?Sized> Scale for &S {
     }
 }
 
+/// Iterator returned by [`Scale::sorted_pitches`] and [`Scale::sorted_pitches_within`].
+pub struct SortedPitches<S> {
+    scale: S,
+    degrees: Range<i32>,
+}
+
+impl<S: Scale> Iterator for SortedPitches<S> {
+    type Item = (i32, Pitch);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.degrees
+            .next()
+            .map(|degree| (degree, self.scale.sorted_pitch_of(degree)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.degrees.size_hint()
+    }
+}
+
+impl<S: Scale> DoubleEndedIterator for SortedPitches<S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.degrees
+            .next_back()
+            .map(|degree| (degree, self.scale.sorted_pitch_of(degree)))
+    }
+}
+
+impl<S: Scale> ExactSizeIterator for SortedPitches<S> {}
+
 /// Type adapter returned by [`Scale::as_sorted_tuning`].
 pub struct SortedTuning<S> {
     inner: S,
@@ -160,6 +273,353 @@ impl<K, T: Tuning<K>> KeyboardMapping<K> for LinearMapping<T> {
     }
 }
 
+/// A [`Tuning<i32>`] with precomputed pitches for a fixed range of keys.
+///
+/// Within the range, [`Tuning::pitch_of`] is a simple array access and [`Tuning::find_by_pitch`] is a binary search.
+/// Keys outside of the range as well as non-monotonic tunings, e.g. unsorted scales, fall back to the wrapped [`Tuning`].
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitch;
+/// # use tune::scala::KbmRoot;
+/// # use tune::scala::Scl;
+/// use tune::tuning::Tuning;
+/// use tune::tuning::TuningTable;
+///
+/// let scl = Scl::builder().push_cents(150.0).build().unwrap();
+/// let kbm_root = KbmRoot::from(NoteLetter::A.in_octave(4));
+/// let table = TuningTable::bake((scl, kbm_root), 0..128);
+///
+/// assert_eq!(table.keys(), 0..128);
+/// assert_approx_eq!(table.pitch_of(8).as_hz(), 880.0);
+/// assert_approx_eq!(table.pitch_of(-8).as_hz(), 220.0); // Outside of the table
+///
+/// let approximation = table.find_by_pitch(Pitch::from_hz(890.0));
+/// assert_eq!(approximation.approx_value, 8);
+/// assert_approx_eq!(approximation.deviation.as_cents(), 19.562175);
+/// ```
+pub struct TuningTable<T> {
+    tuning: T,
+    keys: Range<i32>,
+    pitches: Vec<Pitch>,
+    is_increasing: bool,
+}
+
+impl<T: Tuning<i32>> TuningTable<T> {
+    /// Precomputes the pitches of the given `tuning` for all `keys`.
+    pub fn bake(tuning: T, keys: Range<i32>) -> Self {
+        let pitches: Vec<_> = keys.clone().map(|key| tuning.pitch_of(key)).collect();
+        let is_increasing = pitches.windows(2).all(|window| window[0] < window[1]);
+        Self {
+            tuning,
+            keys,
+            pitches,
+            is_increasing,
+        }
+    }
+
+    /// Returns the range of keys with precomputed pitches.
+    pub fn keys(&self) -> Range<i32> {
+        self.keys.clone()
+    }
+
+    /// Returns the wrapped [`Tuning`].
+    pub fn inner(&self) -> &T {
+        &self.tuning
+    }
+}
+
+impl<T: Tuning<i32>> Tuning<i32> for TuningTable<T> {
+    fn pitch_of(&self, key: i32) -> Pitch {
+        if self.keys.contains(&key) {
+            self.pitches[(key - self.keys.start) as usize]
+        } else {
+            self.tuning.pitch_of(key)
+        }
+    }
+
+    fn find_by_pitch(&self, pitch: Pitch) -> Approximation<i32> {
+        match (self.pitches.first(), self.pitches.last()) {
+            (Some(&lowest), Some(&highest))
+                if self.is_increasing
+                    && self.pitches.len() > 1
+                    && lowest <= pitch
+                    && pitch <= highest =>
+            {
+                let upper_index = self.pitches.partition_point(|&probe| probe < pitch).max(1);
+                let lower_deviation = Ratio::between_pitches(self.pitches[upper_index - 1], pitch);
+                let upper_deviation = Ratio::between_pitches(self.pitches[upper_index], pitch);
+
+                let (index, deviation) =
+                    if lower_deviation.as_cents().abs() < upper_deviation.as_cents().abs() {
+                        (upper_index - 1, lower_deviation)
+                    } else {
+                        (upper_index, upper_deviation)
+                    };
+
+                Approximation {
+                    approx_value: self.keys.start + index as i32,
+                    deviation,
+                }
+            }
+            _ => self.tuning.find_by_pitch(pitch),
+        }
+    }
+}
+
+/// Two tunings stacked on top of each other, e.g. for split keyboards, detuned layers or bi-tonal setups.
+///
+/// The keys of a [`LayeredTuning`] are pairs of a [`Layer`] and a key of the underlying tunings.
+/// The pitches of the second layer are shifted by a constant offset which can be set using [`LayeredTuning::with_offset`].
+///
+/// Since [`LayeredTuning`] implements [`Tuning`] and [`KeyboardMapping`], it can be passed to any function that is generic over those traits, e.g. the tuners in [`tuner`](crate::tuner).
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::key::PianoKey;
+/// # use tune::note::NoteLetter;
+/// # use tune::pitch::Pitch;
+/// # use tune::pitch::Ratio;
+/// # use tune::scala::KbmRoot;
+/// # use tune::scala::Scl;
+/// use tune::tuning::Layer;
+/// use tune::tuning::LayeredTuning;
+/// use tune::tuning::Tuning;
+///
+/// let edo_12 = Scl::builder().push_cents(100.0).build().unwrap();
+/// let kbm_root = KbmRoot::from(NoteLetter::A.in_octave(4));
+///
+/// // Two 12-EDO layers detuned by a quarter-tone, resulting in 24-EDO
+/// let layered = LayeredTuning::new((&edo_12, kbm_root), (&edo_12, kbm_root))
+///     .with_offset(Ratio::from_cents(50.0));
+///
+/// let key = PianoKey::from_midi_number(69);
+/// assert_approx_eq!(layered.pitch_of((Layer::First, key)).as_hz(), 440.0);
+/// assert_approx_eq!(layered.pitch_of((Layer::Second, key)).as_hz(), 452.892984);
+///
+/// let approximation = layered.find_by_pitch(Pitch::from_hz(450.0));
+/// assert_eq!(approximation.approx_value, (Layer::Second, key));
+/// assert_approx_eq!(approximation.deviation.as_cents(), -11.094227);
+///
+/// let keys = Vec::from_iter(Layer::pair_with([key]));
+/// assert_eq!(keys, [(Layer::First, key), (Layer::Second, key)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LayeredTuning<T0, T1> {
+    first_layer: T0,
+    second_layer: T1,
+    offset: Ratio,
+}
+
+impl<T0, T1> LayeredTuning<T0, T1> {
+    /// Creates a [`LayeredTuning`] with both layers sounding at their original pitches.
+    pub fn new(first_layer: T0, second_layer: T1) -> Self {
+        Self {
+            first_layer,
+            second_layer,
+            offset: Ratio::default(),
+        }
+    }
+
+    /// Shifts all pitches of the second layer by `offset`.
+    pub fn with_offset(mut self, offset: Ratio) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn first_layer(&self) -> &T0 {
+        &self.first_layer
+    }
+
+    pub fn second_layer(&self) -> &T1 {
+        &self.second_layer
+    }
+
+    pub fn offset(&self) -> Ratio {
+        self.offset
+    }
+}
+
+impl<K, T0: Tuning<K>, T1: Tuning<K>> Tuning<(Layer, K)> for LayeredTuning<T0, T1> {
+    fn pitch_of(&self, (layer, key): (Layer, K)) -> Pitch {
+        match layer {
+            Layer::First => self.first_layer.pitch_of(key),
+            Layer::Second => self.second_layer.pitch_of(key) * self.offset,
+        }
+    }
+
+    /// Finds the closest key in both layers. If both keys are equally close, the key of the first layer is returned.
+    fn find_by_pitch(&self, pitch: Pitch) -> Approximation<(Layer, K)> {
+        let first = self.first_layer.find_by_pitch(pitch);
+        let second = self.second_layer.find_by_pitch(pitch / self.offset);
+
+        if first.deviation.as_cents().abs() <= second.deviation.as_cents().abs() {
+            Approximation {
+                approx_value: (Layer::First, first.approx_value),
+                deviation: first.deviation,
+            }
+        } else {
+            Approximation {
+                approx_value: (Layer::Second, second.approx_value),
+                deviation: second.deviation,
+            }
+        }
+    }
+}
+
+impl<K, T0: KeyboardMapping<K>, T1: KeyboardMapping<K>> KeyboardMapping<(Layer, K)>
+    for LayeredTuning<T0, T1>
+{
+    fn maybe_pitch_of(&self, (layer, key): (Layer, K)) -> Option<Pitch> {
+        match layer {
+            Layer::First => self.first_layer.maybe_pitch_of(key),
+            Layer::Second => self
+                .second_layer
+                .maybe_pitch_of(key)
+                .map(|pitch| pitch * self.offset),
+        }
+    }
+}
+
+/// Selects one of the two layers of a [`LayeredTuning`].
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Layer {
+    First,
+    Second,
+}
+
+impl Layer {
+    /// Pairs each of the given `keys` with both layers, e.g. to apply a [`LayeredTuning`] to a range of keys.
+    pub fn pair_with<K: Copy>(
+        keys: impl IntoIterator<Item = K>,
+    ) -> impl Iterator<Item = (Layer, K)> {
+        keys.into_iter()
+            .flat_map(|key| [(Layer::First, key), (Layer::Second, key)])
+    }
+}
+
+/// A gradual transition from a source tuning to a target tuning, e.g. for pieces that modulate between tuning systems.
+///
+/// The pitch of each key is interpolated logarithmically between its pitch in the source tuning and its pitch in the target tuning.
+/// The position of the transition is controlled by a progress value between 0 (source tuning) and 1 (target tuning).
+/// To schedule a transition over time, update the progress periodically and retune sounding notes accordingly, e.g. via [`JitTuner::note_pitch`](crate::tuner::JitTuner::note_pitch).
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::key::PianoKey;
+/// # use tune::note::NoteLetter;
+/// # use tune::scala::KbmRoot;
+/// # use tune::scala::Scl;
+/// use tune::tuning::CrossfadedTuning;
+/// use tune::tuning::Tuning;
+///
+/// let edo_12 = Scl::builder().push_cents(100.0).build().unwrap();
+/// let edo_24 = Scl::builder().push_cents(50.0).build().unwrap();
+/// let kbm_root = KbmRoot::from(NoteLetter::A.in_octave(4));
+///
+/// let mut crossfade = CrossfadedTuning::new((&edo_12, kbm_root), (&edo_24, kbm_root));
+///
+/// let key = PianoKey::from_midi_number(71);
+/// assert_approx_eq!(crossfade.pitch_of(key).as_hz(), 493.883301);
+///
+/// crossfade.set_progress(0.5);
+/// assert_approx_eq!(crossfade.pitch_of(key).as_hz(), 479.823402);
+///
+/// crossfade.set_progress(1.5);
+/// assert_approx_eq!(crossfade.progress(), 1.0);
+/// assert_approx_eq!(crossfade.pitch_of(key).as_hz(), 466.163762);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CrossfadedTuning<T0, T1> {
+    source: T0,
+    target: T1,
+    progress: f64,
+}
+
+impl<T0, T1> CrossfadedTuning<T0, T1> {
+    /// Creates a [`CrossfadedTuning`] at the beginning of the transition, i.e. sounding like `source`.
+    pub fn new(source: T0, target: T1) -> Self {
+        Self {
+            source,
+            target,
+            progress: 0.0,
+        }
+    }
+
+    /// Sets the progress of the transition. The value is clamped to the range [0, 1].
+    pub fn with_progress(mut self, progress: f64) -> Self {
+        self.set_progress(progress);
+        self
+    }
+
+    /// Sets the progress of the transition. The value is clamped to the range [0, 1].
+    pub fn set_progress(&mut self, progress: f64) {
+        self.progress = progress.clamp(0.0, 1.0);
+    }
+
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    pub fn source(&self) -> &T0 {
+        &self.source
+    }
+
+    pub fn target(&self) -> &T1 {
+        &self.target
+    }
+
+    fn interpolate(&self, source_pitch: Pitch, target_pitch: Pitch) -> Pitch {
+        source_pitch * Ratio::between_pitches(source_pitch, target_pitch).repeated(self.progress)
+    }
+}
+
+impl<K: Copy, T0: Tuning<K>, T1: Tuning<K>> Tuning<K> for CrossfadedTuning<T0, T1> {
+    fn pitch_of(&self, key: K) -> Pitch {
+        self.interpolate(self.source.pitch_of(key), self.target.pitch_of(key))
+    }
+
+    /// Finds the closest key among the best matches of the source and the target tuning.
+    fn find_by_pitch(&self, pitch: Pitch) -> Approximation<K> {
+        let source_key = self.source.find_by_pitch(pitch).approx_value;
+        let target_key = self.target.find_by_pitch(pitch).approx_value;
+
+        let source_deviation = Ratio::between_pitches(self.pitch_of(source_key), pitch);
+        let target_deviation = Ratio::between_pitches(self.pitch_of(target_key), pitch);
+
+        if source_deviation.as_cents().abs() <= target_deviation.as_cents().abs() {
+            Approximation {
+                approx_value: source_key,
+                deviation: source_deviation,
+            }
+        } else {
+            Approximation {
+                approx_value: target_key,
+                deviation: target_deviation,
+            }
+        }
+    }
+}
+
+/// Keys are only mapped if they are mapped in both the source and the target tuning.
+impl<K: Copy, T0: KeyboardMapping<K>, T1: KeyboardMapping<K>> KeyboardMapping<K>
+    for CrossfadedTuning<T0, T1>
+{
+    fn maybe_pitch_of(&self, key: K) -> Option<Pitch> {
+        Some(self.interpolate(
+            self.source.maybe_pitch_of(key)?,
+            self.target.maybe_pitch_of(key)?,
+        ))
+    }
+}
+
 /// The result of a find operation on [`Scale`]s or [`Tuning`]s.
 #[derive(Copy, Clone, Debug)]
 pub struct Approximation<K> {
@@ -173,9 +633,12 @@ pub struct Approximation<K> {
 /// A [`ConcertPitch`] enables [`Note`]s to sound at a [`Pitch`] different to what would be expected in 440&nbsp;Hz standard tuning.
 ///
 /// To access the full potential of [`ConcertPitch`]es have a look at the [`Tuning`] and [`PitchedNote`](crate::note::PitchedNote) traits.
+///
+/// Besides the global offset of A4, a [`ConcertPitch`] can compensate for transposition-dependent pitch drifts, e.g. caused by temperature or inharmonicity, via [`ConcertPitch::with_stretch_per_semitone`].
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 pub struct ConcertPitch {
     a4_pitch: Pitch,
+    stretch_per_semitone: Ratio,
 }
 
 impl ConcertPitch {
@@ -183,9 +646,29 @@ impl ConcertPitch {
     pub fn from_a4_pitch(a4_pitch: impl Pitched) -> Self {
         Self {
             a4_pitch: a4_pitch.pitch(),
+            stretch_per_semitone: Ratio::default(),
         }
     }
 
+    /// Creates a [`ConcertPitch`] with A4 deviating from 440&nbsp;Hz by the given global `offset`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Pitch;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::tuning::ConcertPitch;
+    /// let a4_at_432_hz = ConcertPitch::from_a4_pitch(Pitch::from_hz(432.0));
+    /// assert_approx_eq!(a4_at_432_hz.offset().as_cents(), -31.766654);
+    ///
+    /// let same_concert_pitch = ConcertPitch::from_offset(a4_at_432_hz.offset());
+    /// assert_approx_eq!(same_concert_pitch.a4_pitch().as_hz(), 432.0);
+    /// ```
+    pub fn from_offset(offset: Ratio) -> Self {
+        Self::default().with_offset(offset)
+    }
+
     /// Creates a [`ConcertPitch`] from the given `note` and `pitched` value.
     ///
     /// # Examples
@@ -200,18 +683,66 @@ impl ConcertPitch {
     /// assert_approx_eq!(fixed_c4_tuning.a4_pitch().as_hz(), 437.266136);
     /// ```
     pub fn from_note_and_pitch(note: Note, pitched: impl Pitched) -> Self {
-        Self {
-            a4_pitch: pitched.pitch()
+        Self::from_a4_pitch(
+            pitched.pitch()
                 * Ratio::from_semitones(f64::from(
                     note.num_semitones_before(NoteLetter::A.in_octave(4)),
                 )),
-        }
+        )
+    }
+
+    /// Shifts A4, and all other notes, by the given global `offset` relative to 440&nbsp;Hz.
+    pub fn with_offset(mut self, offset: Ratio) -> Self {
+        self.a4_pitch = ConcertPitch::default().a4_pitch * offset;
+        self
+    }
+
+    /// Adds `stretch_per_semitone` to each semitone of transposition away from A4.
+    ///
+    /// This is useful to compensate for pitch drifts that grow linearly with the distance to the reference note, e.g. on instruments tuned at a different temperature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::note::NoteLetter;
+    /// # use tune::pitch::Pitch;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::tuning::ConcertPitch;
+    /// use tune::tuning::Tuning;
+    ///
+    /// let stretched = ConcertPitch::default().with_stretch_per_semitone(Ratio::from_cents(0.5));
+    ///
+    /// let a4_pitch = stretched.pitch_of(NoteLetter::A.in_octave(4));
+    /// let a5_pitch = stretched.pitch_of(NoteLetter::A.in_octave(5));
+    /// let a3_pitch = stretched.pitch_of(NoteLetter::A.in_octave(3));
+    /// assert_approx_eq!(a4_pitch.as_hz(), 440.0);
+    /// assert_approx_eq!(Ratio::between_pitches(a4_pitch, a5_pitch).as_cents(), 1206.0);
+    /// assert_approx_eq!(Ratio::between_pitches(a3_pitch, a4_pitch).as_cents(), 1206.0);
+    ///
+    /// let approximation = stretched.find_by_pitch(a5_pitch * Ratio::from_cents(10.0));
+    /// assert_eq!(approximation.approx_value, NoteLetter::A.in_octave(5));
+    /// assert_approx_eq!(approximation.deviation.as_cents(), 10.0);
+    /// ```
+    pub fn with_stretch_per_semitone(mut self, stretch_per_semitone: Ratio) -> Self {
+        self.stretch_per_semitone = stretch_per_semitone;
+        self
     }
 
     /// Returns the [`Pitch`] of A4.
     pub fn a4_pitch(self) -> Pitch {
         self.a4_pitch
     }
+
+    /// Returns the global offset of A4 relative to 440&nbsp;Hz.
+    pub fn offset(self) -> Ratio {
+        Ratio::between_pitches(ConcertPitch::default().a4_pitch, self.a4_pitch)
+    }
+
+    /// Returns the pitch change added to each semitone of transposition away from A4.
+    pub fn stretch_per_semitone(self) -> Ratio {
+        self.stretch_per_semitone
+    }
 }
 
 /// The default [`ConcertPitch`] is A4 sounding at 440&nbsp;Hz.
@@ -253,11 +784,16 @@ impl Default for ConcertPitch {
 /// ```
 impl Tuning<Note> for ConcertPitch {
     fn pitch_of(&self, note: Note) -> Pitch {
-        self.a4_pitch * Ratio::from_semitones(NoteLetter::A.in_octave(4).num_semitones_before(note))
+        let semitones_above_a4 = NoteLetter::A.in_octave(4).num_semitones_before(note);
+        self.a4_pitch
+            * Ratio::from_semitones(semitones_above_a4)
+            * self.stretch_per_semitone.repeated(semitones_above_a4)
     }
 
     fn find_by_pitch(&self, pitch: Pitch) -> Approximation<Note> {
-        let semitones_above_a4 = Ratio::between_pitches(self.a4_pitch, pitch).as_semitones();
+        let size_of_semitone = 1.0 + self.stretch_per_semitone.as_semitones();
+        let semitones_above_a4 =
+            Ratio::between_pitches(self.a4_pitch, pitch).as_semitones() / size_of_semitone;
         let round_to_lower_step = Ratio::from_float(1.000001);
         let approx_semitones_above_a4 =
             (semitones_above_a4 - round_to_lower_step.as_semitones()).round();
@@ -266,7 +802,9 @@ impl Tuning<Note> for ConcertPitch {
             approx_value: Note::from_midi_number(
                 approx_semitones_above_a4 as i32 + NoteLetter::A.in_octave(4).midi_number(),
             ),
-            deviation: Ratio::from_semitones(semitones_above_a4 - approx_semitones_above_a4),
+            deviation: Ratio::from_semitones(
+                (semitones_above_a4 - approx_semitones_above_a4) * size_of_semitone,
+            ),
         }
     }
 }
@@ -291,3 +829,184 @@ impl Tuning<Note> for () {
         ConcertPitch::default().find_by_pitch(pitch)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        key::PianoKey,
+        scala::{KbmRoot, Scl},
+        tuner::AotTuningModel,
+    };
+
+    use super::*;
+
+    #[test]
+    fn tuning_table_matches_wrapped_tuning() {
+        let sorted_scl = Scl::builder()
+            .push_fraction(9, 8)
+            .push_fraction(5, 4)
+            .push_fraction(3, 2)
+            .push_int(2)
+            .build()
+            .unwrap();
+        let unsorted_scl = Scl::builder()
+            .push_fraction(5, 4)
+            .push_fraction(9, 8)
+            .push_fraction(3, 2)
+            .push_int(2)
+            .build()
+            .unwrap();
+        let kbm_root = KbmRoot::from(NoteLetter::D.in_octave(4));
+
+        for scl in [sorted_scl, unsorted_scl] {
+            let tuning = (&scl, kbm_root);
+            let table = TuningTable::bake(tuning, -20..20);
+
+            for key in -30..30 {
+                assert_eq!(table.pitch_of(key), tuning.pitch_of(key));
+            }
+
+            for cents in (-3000..3000).step_by(7) {
+                let pitch = Pitch::of(NoteLetter::D.in_octave(4)) * Ratio::from_cents(cents.into());
+                let expected = tuning.find_by_pitch(pitch);
+                let actual = table.find_by_pitch(pitch);
+                assert_eq!(actual.approx_value, expected.approx_value);
+                assert!((actual.deviation.as_cents() - expected.deviation.as_cents()).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_scale_guarantees_for_non_monotonic_scales() {
+        let unsorted_scl = Scl::builder()
+            .push_cents(700.0)
+            .push_cents(200.0)
+            .push_cents(1100.0)
+            .push_cents(400.0)
+            .push_cents(1200.0)
+            .build()
+            .unwrap();
+        let tuning = (&unsorted_scl, KbmRoot::from(NoteLetter::D.in_octave(4)));
+
+        let pitches = Vec::from_iter(tuning.sorted_pitches(-20..20));
+        assert_eq!(pitches.len(), 40);
+        assert!(pitches.windows(2).all(|window| window[0].1 < window[1].1));
+
+        for &(degree, pitch) in &pitches {
+            assert_eq!(tuning.find_by_pitch_sorted(pitch).approx_value, degree);
+        }
+
+        for cents in (-2000..2000).step_by(13) {
+            // The offset avoids ties between two neighboring degrees
+            let pitch =
+                Pitch::of(NoteLetter::D.in_octave(4)) * Ratio::from_cents(f64::from(cents) + 0.5);
+            let approximation = tuning.find_by_pitch_sorted(pitch);
+            let closest_degree = pitches
+                .iter()
+                .min_by(|a, b| {
+                    let deviation_a = Ratio::between_pitches(a.1, pitch).as_cents().abs();
+                    let deviation_b = Ratio::between_pitches(b.1, pitch).as_cents().abs();
+                    deviation_a.total_cmp(&deviation_b)
+                })
+                .unwrap()
+                .0;
+            assert_eq!(approximation.approx_value, closest_degree);
+
+            let within =
+                Vec::from_iter(tuning.sorted_pitches_within(pitch..pitch * Ratio::octave()));
+            assert_eq!(within.len(), 5);
+            assert!(within
+                .iter()
+                .all(|&(_, p)| pitch <= p && p < pitch * Ratio::octave()));
+        }
+    }
+
+    #[test]
+    fn sorted_scale_with_duplicate_pitches() {
+        let scl = Scl::builder()
+            .push_cents(400.0)
+            .push_cents(0.0)
+            .push_cents(400.0)
+            .push_cents(1200.0)
+            .push_cents(700.0)
+            .push_cents(1200.0)
+            .build()
+            .unwrap();
+        let root_pitch = Pitch::of(NoteLetter::D.in_octave(4));
+        let tuning = (&scl, KbmRoot::from(NoteLetter::D.in_octave(4)));
+        let at = |cents: f64| root_pitch * Ratio::from_cents(cents);
+
+        let pitches = Vec::from_iter(
+            tuning
+                .sorted_pitches(0..7)
+                .map(|(_, pitch)| Ratio::between_pitches(root_pitch, pitch).as_cents().round()),
+        );
+        assert_eq!(pitches, [0.0, 0.0, 400.0, 400.0, 700.0, 1200.0, 1200.0]);
+
+        for (cents, expected_degree) in [
+            (-0.3, -1),
+            (0.0, -1),
+            (0.3, 1),
+            (399.7, 2),
+            (400.0, 2),
+            (400.3, 3),
+            (1199.7, 5),
+            (1200.0, 5),
+            (1200.3, 7),
+        ] {
+            assert_eq!(
+                tuning.find_by_pitch_sorted(at(cents)).approx_value,
+                expected_degree,
+                "{cents}"
+            );
+        }
+
+        let degrees_within = |range: Range<f64>| {
+            Vec::from_iter(
+                tuning
+                    .sorted_pitches_within(at(range.start)..at(range.end))
+                    .map(|(degree, _)| degree),
+            )
+        };
+        assert_eq!(degrees_within(0.0..400.0), [-1, 0, 1]);
+        assert_eq!(degrees_within(0.3..400.3), [2, 3]);
+        assert_eq!(degrees_within(400.0..1200.0), [2, 3, 4]);
+
+        for degree in -20..20 {
+            let sorted_degree = scl.sorted_degree_of(degree);
+            assert_eq!(scl.degree_of_sorted(sorted_degree), degree);
+            assert_eq!(
+                tuning.sorted_pitch_of(sorted_degree),
+                tuning.pitch_of(degree)
+            );
+        }
+    }
+
+    #[test]
+    fn layered_tuning_in_aot_tuner() {
+        let edo_12 = Scl::builder().push_cents(100.0).build().unwrap();
+        let edo_19 = Scl::builder().push_cents(1200.0 / 19.0).build().unwrap();
+        let kbm = KbmRoot::from(NoteLetter::D.in_octave(4)).to_kbm();
+
+        let layered = LayeredTuning::new((&edo_12, &kbm), (&edo_19, &kbm))
+            .with_offset(Ratio::from_cents(25.0));
+
+        let keys = (60..72).map(PianoKey::from_midi_number);
+        let (model, channel_tunings) =
+            AotTuningModel::apply_full_keyboard_tuning(&layered, Layer::pair_with(keys.clone()));
+
+        assert_eq!(model.num_channels(), channel_tunings.len());
+        assert!(model.num_channels() >= 2);
+
+        for key in keys {
+            for layer in [Layer::First, Layer::Second] {
+                let (channel, note) = model.get_channel_and_note_for_key((layer, key)).unwrap();
+                let detuning =
+                    channel_tunings[channel].to_fluid_format()[note.midi_number() as usize];
+                let expected = layered.maybe_pitch_of((layer, key)).unwrap();
+                let actual = Pitch::of(Note::from_midi_number(0)) * Ratio::from_cents(detuning);
+                assert!(Ratio::between_pitches(expected, actual).as_cents().abs() < 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Values with error bounds, e.g. pitches derived from measured frequencies.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Div, Mul},
+};
+
+use crate::pitch::{Pitch, Pitched, Ratio};
+
+/// A value of type `T` together with a symmetric error bound.
+///
+/// The error bound is a [`Ratio`] s.t. the true value lies between `value / error` and `value * error`.
+/// Arithmetic operations propagate the worst-case error, i.e. the error bounds of the operands add up on a logarithmic scale.
+///
+/// # Examples
+///
+/// ```
+/// # use assert_approx_eq::assert_approx_eq;
+/// # use tune::pitch::Pitch;
+/// # use tune::pitch::Ratio;
+/// # use tune::uncertain::Uncertain;
+/// let measured_a4 = Uncertain::new(Pitch::from_hz(440.0), Ratio::from_cents(0.5));
+/// let measured_e5 = Uncertain::new(Pitch::from_hz(660.0), Ratio::from_cents(1.0));
+///
+/// let fifth = Uncertain::between_pitches(measured_a4, measured_e5);
+/// assert_approx_eq!(fifth.value().as_cents(), 701.955001);
+/// assert_approx_eq!(fifth.error().as_cents(), 1.5);
+/// assert_approx_eq!(fifth.lower_bound().as_cents(), 700.455001);
+/// assert_approx_eq!(fifth.upper_bound().as_cents(), 703.455001);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Uncertain<T> {
+    value: T,
+    error: Ratio,
+}
+
+impl<T> Uncertain<T> {
+    /// Creates a new [`Uncertain`] value. The sign of `error` is ignored.
+    pub fn new(value: T, error: Ratio) -> Self {
+        Self {
+            value,
+            error: error.abs(),
+        }
+    }
+
+    /// Creates a new [`Uncertain`] value with an error bound of zero.
+    pub fn exact(value: T) -> Self {
+        Self::new(value, Ratio::default())
+    }
+
+    /// Returns the error bound. The error bound is always greater than or equal to [`Ratio::default()`].
+    pub fn error(&self) -> Ratio {
+        self.error
+    }
+
+    /// Returns a new [`Uncertain`] value with an additional error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::uncertain::Uncertain;
+    /// let fifth = Uncertain::exact(Ratio::from_float(1.5)).with_additional_error(Ratio::from_cents(-2.0));
+    /// assert_approx_eq!(fifth.error().as_cents(), 2.0);
+    /// ```
+    pub fn with_additional_error(self, error: Ratio) -> Self {
+        Self::new(self.value, self.error.stretched_by(error.abs()))
+    }
+}
+
+impl<T: Copy> Uncertain<T> {
+    /// Returns the central value.
+    pub fn value(&self) -> T {
+        self.value
+    }
+}
+
+impl Uncertain<Pitch> {
+    /// Returns the lowest [`Pitch`] compatible with the error bound.
+    pub fn lower_bound(self) -> Pitch {
+        self.value / self.error
+    }
+
+    /// Returns the highest [`Pitch`] compatible with the error bound.
+    pub fn upper_bound(self) -> Pitch {
+        self.value * self.error
+    }
+}
+
+impl Uncertain<Ratio> {
+    /// Calculates the uncertain [`Ratio`] between two uncertain [`Pitch`]es.
+    pub fn between_pitches(pitch_a: Uncertain<Pitch>, pitch_b: Uncertain<Pitch>) -> Self {
+        Self::new(
+            Ratio::between_pitches(pitch_a.value, pitch_b.value),
+            pitch_a.error.stretched_by(pitch_b.error),
+        )
+    }
+
+    /// Stacks `self` `num_repetitions` times. The error bound scales with the absolute number of repetitions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::uncertain::Uncertain;
+    /// let step = Uncertain::new(Ratio::from_cents(100.0), Ratio::from_cents(0.5));
+    /// let octave_below = step.repeated(-12);
+    /// assert_approx_eq!(octave_below.value().as_cents(), -1200.0);
+    /// assert_approx_eq!(octave_below.error().as_cents(), 6.0);
+    /// ```
+    pub fn repeated(self, num_repetitions: impl Into<f64>) -> Self {
+        let num_repetitions = num_repetitions.into();
+        Self::new(
+            self.value.repeated(num_repetitions),
+            self.error.repeated(num_repetitions.abs()),
+        )
+    }
+
+    /// Divides `self` into `num_steps` equal steps. The error bound is divided accordingly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use assert_approx_eq::assert_approx_eq;
+    /// # use tune::pitch::Ratio;
+    /// # use tune::uncertain::Uncertain;
+    /// let measured_octave = Uncertain::new(Ratio::from_cents(1203.0), Ratio::from_cents(3.0));
+    /// let step = measured_octave.divided_into_equal_steps(12);
+    /// assert_approx_eq!(step.value().as_cents(), 100.25);
+    /// assert_approx_eq!(step.error().as_cents(), 0.25);
+    /// ```
+    pub fn divided_into_equal_steps(self, num_steps: impl Into<f64>) -> Self {
+        self.repeated(num_steps.into().recip())
+    }
+
+    /// Returns the inverse uncertain [`Ratio`]. The error bound is preserved.
+    pub fn inv(self) -> Self {
+        Self::new(self.value.inv(), self.error)
+    }
+
+    /// Returns the smallest [`Ratio`] compatible with the error bound.
+    pub fn lower_bound(self) -> Ratio {
+        self.value.deviation_from(self.error)
+    }
+
+    /// Returns the largest [`Ratio`] compatible with the error bound.
+    pub fn upper_bound(self) -> Ratio {
+        self.value.stretched_by(self.error)
+    }
+
+    /// Checks whether `ratio` is compatible with the error bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tune::pitch::Ratio;
+    /// # use tune::uncertain::Uncertain;
+    /// let measured_fifth = Uncertain::new(Ratio::from_cents(701.5), Ratio::from_cents(1.0));
+    /// assert!(measured_fifth.contains(Ratio::from_float(1.5)));
+    /// assert!(!measured_fifth.contains(Ratio::from_semitones(7.0)));
+    /// ```
+    pub fn contains(self, ratio: Ratio) -> bool {
+        self.lower_bound() <= ratio && ratio <= self.upper_bound()
+    }
+}
+
+impl Pitched for Uncertain<Pitch> {
+    fn pitch(&self) -> Pitch {
+        self.value
+    }
+}
+
+/// Stacks two uncertain [`Ratio`]s, adding up their error bounds.
+impl Mul for Uncertain<Ratio> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value.stretched_by(rhs.value),
+            self.error.stretched_by(rhs.error),
+        )
+    }
+}
+
+/// Calculates the difference between two uncertain [`Ratio`]s, adding up their error bounds.
+impl Div for Uncertain<Ratio> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value.deviation_from(rhs.value),
+            self.error.stretched_by(rhs.error),
+        )
+    }
+}
+
+/// Transposes an uncertain [`Pitch`] by an uncertain [`Ratio`], adding up their error bounds.
+impl Mul<Uncertain<Ratio>> for Uncertain<Pitch> {
+    type Output = Self;
+
+    fn mul(self, rhs: Uncertain<Ratio>) -> Self::Output {
+        Self::new(self.value * rhs.value, self.error.stretched_by(rhs.error))
+    }
+}
+
+/// Transposes an uncertain [`Pitch`] downwards by an uncertain [`Ratio`], adding up their error bounds.
+impl Div<Uncertain<Ratio>> for Uncertain<Pitch> {
+    type Output = Self;
+
+    fn div(self, rhs: Uncertain<Ratio>) -> Self::Output {
+        Self::new(self.value / rhs.value, self.error.stretched_by(rhs.error))
+    }
+}
+
+/// Uncertain [`Ratio`]s are formatted like [`Ratio`]s, followed by the error bound in cents.
+///
+/// # Examples
+///
+/// ```
+/// # use tune::pitch::Ratio;
+/// # use tune::uncertain::Uncertain;
+/// let measured_fifth = Uncertain::new(Ratio::from_float(1.5), Ratio::from_cents(1.5));
+///
+/// assert_eq!(format!("{measured_fifth}"), "1.5000 ±1.5c");
+/// assert_eq!(format!("{measured_fifth:.2}"), "1.50 ±1.5c");
+/// assert_eq!(format!("{measured_fifth:#}"), "+702.0c ±1.5c");
+/// assert_eq!(format!("{measured_fifth:#.2}"), "+701.96c ±1.50c");
+/// ```
+impl Display for Uncertain<Ratio> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            let precision = f.precision().unwrap_or(1);
+            write!(
+                f,
+                "{:#.precision$} ±{:.precision$}c",
+                self.value,
+                self.error.as_cents()
+            )
+        } else {
+            let precision = f.precision().unwrap_or(4);
+            write!(
+                f,
+                "{:.precision$} ±{:.1}c",
+                self.value,
+                self.error.as_cents()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn error_bounds_add_up() {
+        let fifth = Uncertain::new(Ratio::from_float(1.5), Ratio::from_cents(1.0));
+        let fourth = Uncertain::new(Ratio::from_float(4.0 / 3.0), Ratio::from_cents(2.0));
+
+        let octave = fifth * fourth;
+        assert_approx_eq!(octave.value().as_cents(), 1200.0);
+        assert_approx_eq!(octave.error().as_cents(), 3.0);
+
+        let major_second = fifth / fourth;
+        assert_approx_eq!(major_second.value().as_cents(), 203.910002);
+        assert_approx_eq!(major_second.error().as_cents(), 3.0);
+
+        let a4 = Uncertain::new(Pitch::from_hz(440.0), Ratio::from_cents(0.5));
+        let e5 = a4 * fifth;
+        assert_approx_eq!(e5.value().as_hz(), 660.0);
+        assert_approx_eq!(e5.error().as_cents(), 1.5);
+        assert_approx_eq!((e5 / fifth).value().as_hz(), 440.0);
+        assert_approx_eq!((e5 / fifth).error().as_cents(), 2.5);
+
+        let fifth_below = fifth.inv();
+        assert_approx_eq!(fifth_below.lower_bound().as_cents(), -702.955001);
+        assert_approx_eq!(fifth_below.upper_bound().as_cents(), -700.955001);
+    }
+}
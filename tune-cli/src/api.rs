@@ -0,0 +1,85 @@
+//! Library-level access to the analyses of the `tune` command line.
+//!
+//! The functions in this module return the same data that is printed by the `est --format json` and `scale` commands but do not require any command line arguments to be parsed.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tune::key::PianoKey;
+//! # use tune::note::NoteLetter;
+//! # use tune::pitch::Ratio;
+//! # use tune::scala::{Kbm, Scl};
+//! use tune_cli::api::{self, EstParams};
+//!
+//! let est = api::analyze_est(Ratio::from_semitones(1), &EstParams::default());
+//! assert_eq!(est.num_steps_per_period, 12);
+//! assert_eq!(est.val.values, [12, 19, 28, 34, 42, 44]);
+//!
+//! let scl = Scl::builder()
+//!     .push_ratio(Ratio::from_semitones(2))
+//!     .build()
+//!     .unwrap();
+//! let kbm = Kbm::builder(NoteLetter::C.in_octave(4))
+//!     .range(PianoKey::from_midi_number(60)..PianoKey::from_midi_number(63))
+//!     .push_mapped_key(0)
+//!     .formal_octave(1)
+//!     .build()
+//!     .unwrap();
+//!
+//! let scale = api::render_scale(&scl, &kbm);
+//! assert_eq!(scale.root_key_midi_number, 60);
+//! assert_eq!(scale.items.len(), 3);
+//! assert_eq!(scale.items[1].key_midi_number, 61);
+//! assert!((scale.items[1].pitch_in_hz - 293.665).abs() < 0.001);
+//! ```
+
+use tune::{
+    pitch::Ratio,
+    scala::{Kbm, Scl},
+};
+
+use crate::{est, scale::Scale};
+
+pub use crate::{
+    dto::{
+        CommaDto, EstDto, IntervalLocationDto, NotationDto, ProvenanceDto, ScaleDto, ScaleItemDto,
+        SourceFileDto, TemperamentSupportDto, ValDto,
+    },
+    shared::Notation,
+};
+
+/// Parameters of an equal-step tuning analysis. The defaults match the defaults of the `est` command.
+#[derive(Copy, Clone)]
+pub struct EstParams {
+    /// Period of the equal-step tuning, e.g. 3/1 for equal divisions of the tritave.
+    pub period: Ratio,
+    /// Odd limit of the patent val.
+    pub odd_limit: u8,
+    /// Error threshold for subgroup determination.
+    pub error_threshold: Ratio,
+    /// Notation used for the names of the scale steps.
+    pub notation: Notation,
+}
+
+impl Default for EstParams {
+    fn default() -> Self {
+        Self {
+            period: Ratio::octave(),
+            odd_limit: 13,
+            error_threshold: Ratio::from_cents(25.0),
+            notation: Notation::Heptatonic,
+        }
+    }
+}
+
+/// Analyzes the equal-step tuning with the given `step_size`.
+pub fn analyze_est(step_size: Ratio, params: &EstParams) -> EstDto {
+    est::analyze(step_size, params)
+}
+
+/// Calculates the pitches of all keys in the range of `kbm`.
+///
+/// Unmapped keys are omitted.
+pub fn render_scale(scl: &Scl, kbm: &Kbm) -> ScaleDto {
+    Scale::from_tuning(scl.clone(), kbm.clone()).to_dto()
+}
@@ -0,0 +1,302 @@
+use std::io;
+
+use tune::{
+    note::{Note, NoteLetter, NoteNames},
+    pitch::Pitched,
+};
+
+use crate::{scale::Scale, App};
+
+/// Number of braille cells per line of a BRF page.
+const BRF_LINE_WIDTH: usize = 40;
+/// Number of lines per BRF page.
+const BRF_PAGE_HEIGHT: usize = 25;
+/// Number of notes per line of an abc tune.
+const ABC_NOTES_PER_LINE: usize = 8;
+
+struct ChartRow {
+    degree: i32,
+    note: Note,
+    deviation_in_cents: f64,
+    pitch_in_hz: f64,
+}
+
+fn chart_rows(scale: &Scale) -> Vec<ChartRow> {
+    scale
+        .keys
+        .iter()
+        .flat_map(|&key| scale.tuning.maybe_pitch_of(key).map(|pitch| (key, pitch)))
+        .map(|(key, pitch)| {
+            let approximation = pitch.find_in_tuning(());
+            ChartRow {
+                degree: scale.origin.num_keys_before(key),
+                note: approximation.approx_value,
+                deviation_in_cents: approximation.deviation.as_cents(),
+                pitch_in_hz: pitch.as_hz(),
+            }
+        })
+        .collect()
+}
+
+/// Prints a plain-text chart without any table decorations s.t. it can be read line by line by a screen reader.
+pub fn print_chart(app: &mut App, scale: &Scale, note_names: NoteNames) -> io::Result<()> {
+    app.writeln(format_args!(
+        "{:>6}  {:<11}  {:>9}  {:>14}",
+        "Degree", "Note", "Cents", "Frequency"
+    ))?;
+    for row in chart_rows(scale) {
+        app.writeln(format_args!(
+            "{:>6}  {:<11}  {:>+9.3}  {:>11.3} Hz",
+            row.degree,
+            row.note.named(note_names).to_string(),
+            row.deviation_in_cents,
+            row.pitch_in_hz
+        ))?;
+    }
+    Ok(())
+}
+
+/// Prints the chart in Braille Ready Format (BRF), i.e. transcribed to Braille ASCII (uncontracted UEB) with 40 cells per line and 25 lines per page.
+pub fn print_brf_chart(app: &mut App, scale: &Scale, note_names: NoteNames) -> io::Result<()> {
+    let mut lines = wrap_line(&transcribe("Degree, note, cents, Hz"), BRF_LINE_WIDTH);
+    for row in chart_rows(scale) {
+        let line = format!(
+            "{} {} {:+.1} {:.2} Hz",
+            row.degree,
+            row.note.named(note_names),
+            row.deviation_in_cents,
+            row.pitch_in_hz
+        );
+        lines.extend(wrap_line(&transcribe(&line), BRF_LINE_WIDTH));
+    }
+
+    for (page_index, page) in lines.chunks(BRF_PAGE_HEIGHT).enumerate() {
+        if page_index > 0 {
+            app.write("\x0c")?;
+        }
+        for line in page {
+            app.write(format_args!("{line}\r\n"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints a LilyPond music expression with one whole note per key. Every note is the nearest 12-EDO note, annotated with its deviation in cents above and its scale degree below the staff.
+pub fn print_lilypond(app: &mut App, scale: &Scale) -> io::Result<()> {
+    app.writeln("{")?;
+    app.writeln("  \\omit Staff.TimeSignature")?;
+    app.writeln("  \\cadenzaOn")?;
+    for row in chart_rows(scale) {
+        app.writeln(format_args!(
+            "  {}1^\"{:+.1}c\"_\"{}\"",
+            lilypond_pitch(row.note),
+            row.deviation_in_cents,
+            row.degree
+        ))?;
+    }
+    app.writeln("  \\bar \"|.\"")?;
+    app.writeln("}")
+}
+
+/// Prints an abc tune with one whole note per key, annotated like the LilyPond output.
+///
+/// Every note carries an explicit accidental since abc accidentals would otherwise apply to the following notes as well.
+pub fn print_abc(app: &mut App, scale: &Scale) -> io::Result<()> {
+    app.writeln("X:1")?;
+    app.writeln("T:Tuning chart")?;
+    app.writeln("M:none")?;
+    app.writeln("L:1")?;
+    app.writeln("K:C")?;
+
+    let notes: Vec<_> = chart_rows(scale)
+        .into_iter()
+        .map(|row| {
+            format!(
+                "\"^{:+.1}c\"\"_{}\"{}",
+                row.deviation_in_cents,
+                row.degree,
+                abc_pitch(row.note)
+            )
+        })
+        .collect();
+    let num_lines = notes.chunks(ABC_NOTES_PER_LINE).len();
+    for (line_index, line) in notes.chunks(ABC_NOTES_PER_LINE).enumerate() {
+        let ending = if line_index + 1 == num_lines {
+            " |]"
+        } else {
+            ""
+        };
+        app.writeln(format_args!("{}{ending}", line.join(" ")))?;
+    }
+    Ok(())
+}
+
+/// Splits `note` into its natural letter (lower case), an accidental flag and the octave number.
+fn spell(note: Note) -> (char, bool, i32) {
+    let (letter, octave) = note.letter_and_octave();
+    let (natural, is_sharp) = match letter {
+        NoteLetter::C => ('c', false),
+        NoteLetter::Csh => ('c', true),
+        NoteLetter::D => ('d', false),
+        NoteLetter::Dsh => ('d', true),
+        NoteLetter::E => ('e', false),
+        NoteLetter::F => ('f', false),
+        NoteLetter::Fsh => ('f', true),
+        NoteLetter::G => ('g', false),
+        NoteLetter::Gsh => ('g', true),
+        NoteLetter::A => ('a', false),
+        NoteLetter::Ash => ('a', true),
+        NoteLetter::B => ('b', false),
+    };
+    (natural, is_sharp, octave.octave_number())
+}
+
+/// Renders `note` in LilyPond's absolute mode with Dutch note names, e.g. cis' for C#4.
+fn lilypond_pitch(note: Note) -> String {
+    let (natural, is_sharp, octave) = spell(note);
+    let mut pitch = natural.to_string();
+    if is_sharp {
+        pitch.push_str("is");
+    }
+    pitch.push_str(&octave_marks(octave - 3));
+    pitch
+}
+
+/// Renders `note` in abc notation with an explicit accidental, e.g. ^C for C#4 and =c' for C6.
+fn abc_pitch(note: Note) -> String {
+    let (natural, is_sharp, octave) = spell(note);
+    let mut pitch = if is_sharp { "^" } else { "=" }.to_owned();
+    if octave >= 5 {
+        pitch.push(natural);
+        pitch.push_str(&octave_marks(octave - 5));
+    } else {
+        pitch.push(natural.to_ascii_uppercase());
+        pitch.push_str(&octave_marks(octave - 4));
+    }
+    pitch
+}
+
+fn octave_marks(num_octaves: i32) -> String {
+    let mark = if num_octaves >= 0 { "'" } else { "," };
+    mark.repeat(num_octaves.unsigned_abs() as usize)
+}
+
+/// Transcribes `text` to Braille ASCII following the rules of uncontracted Unified English Braille.
+///
+/// Only the characters that occur in tuning charts are supported. Other characters are passed through unchanged.
+fn transcribe(text: &str) -> String {
+    let mut braille = String::new();
+    let mut numeric_mode = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => {
+                if !numeric_mode {
+                    braille.push('#');
+                    numeric_mode = true;
+                }
+                braille.push(b"JABCDEFGHI"[c as usize - '0' as usize] as char);
+            }
+            // The decimal point continues the number
+            '.' if numeric_mode && matches!(chars.peek(), Some(c) if c.is_ascii_digit()) => {
+                braille.push('4');
+            }
+            'A'..='Z' => {
+                braille.push(',');
+                braille.push(c);
+                numeric_mode = false;
+            }
+            'a'..='z' => {
+                // Grade 1 indicator: Letters a-j directly following a number would be read as digits
+                if numeric_mode && c <= 'j' {
+                    braille.push(';');
+                }
+                braille.push(c.to_ascii_uppercase());
+                numeric_mode = false;
+            }
+            _ => {
+                match c {
+                    '.' => braille.push('4'),
+                    ',' => braille.push('1'),
+                    '#' => braille.push_str("_?"),
+                    '/' => braille.push_str("_/"),
+                    '+' => braille.push_str("\"6"),
+                    '-' => braille.push_str("\"-"),
+                    _ => braille.push(c),
+                }
+                numeric_mode = false;
+            }
+        }
+    }
+
+    braille
+}
+
+/// Breaks `line` at spaces s.t. no line exceeds `width` cells. Continuation lines are indented by two cells.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current_line = String::new();
+
+    for word in line.split(' ') {
+        if !current_line.trim_start().is_empty() && current_line.len() + 1 + word.len() > width {
+            lines.push(current_line);
+            current_line = "  ".to_owned();
+        } else if !current_line.is_empty() {
+            current_line.push(' ');
+        }
+        current_line.push_str(word);
+    }
+    lines.push(current_line);
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcribe_chart_lines() {
+        assert_eq!(
+            transcribe("0 D 4 +0.0 293.66 Hz"),
+            "#J ,D #D \"6#J4J #BIC4FF ,HZ"
+        );
+        assert_eq!(
+            transcribe("-3 C#/Db -1 -12.5 7.73 Hz"),
+            "\"-#C ,C_?_/,DB \"-#A \"-#AB4E #G4GC ,HZ"
+        );
+        assert_eq!(transcribe("12c 3x"), "#AB;C #CX");
+        assert_eq!(
+            transcribe("Degree, note, cents, Hz"),
+            ",DEGREE1 NOTE1 CENTS1 ,HZ"
+        );
+    }
+
+    #[test]
+    fn spell_engraving_pitches() {
+        let note = Note::from_midi_number;
+
+        assert_eq!(lilypond_pitch(note(60)), "c'");
+        assert_eq!(lilypond_pitch(note(61)), "cis'");
+        assert_eq!(lilypond_pitch(note(48)), "c");
+        assert_eq!(lilypond_pitch(note(35)), "b,,");
+        assert_eq!(lilypond_pitch(note(86)), "d'''");
+
+        assert_eq!(abc_pitch(note(60)), "=C");
+        assert_eq!(abc_pitch(note(61)), "^C");
+        assert_eq!(abc_pitch(note(72)), "=c");
+        assert_eq!(abc_pitch(note(84)), "=c'");
+        assert_eq!(abc_pitch(note(59)), "=B,");
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        assert_eq!(wrap_line("ab cd", 5), ["ab cd"]);
+        assert_eq!(wrap_line("ab cd ef", 5), ["ab cd", "  ef"]);
+        assert_eq!(
+            wrap_line("abcdefgh ij kl mn", 5),
+            ["abcdefgh", "  ij", "  kl", "  mn"]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::{sync::mpsc, time::Duration};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::{
+    shared::midi::{self, DeviceIdArg, MidiInConnection},
+    App, CliError, CliResult,
+};
+
+/// Manufacturer database shipped with `tune-cli`.
+const BUILTIN_DEVICE_DATABASE: &str = include_str!("../devices.toml");
+
+/// Maximum time to wait for the identity reply of the probed device.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Parser)]
+pub(crate) struct DevicesOptions {
+    #[command(subcommand)]
+    command: Option<DevicesCommand>,
+}
+
+#[derive(Parser)]
+enum DevicesCommand {
+    /// Send a Universal SysEx Identity Request to a MIDI device and report its manufacturer, family and model
+    #[command(name = "probe")]
+    Probe(ProbeOptions),
+}
+
+#[derive(Parser)]
+struct ProbeOptions {
+    /// MIDI output device to send the identity request to
+    #[arg(long = "midi-out")]
+    midi_out_device: String,
+
+    /// MIDI input device to receive the identity reply from
+    #[arg(long = "midi-in")]
+    midi_in_device: String,
+
+    #[command(flatten)]
+    device_id: DeviceIdArg,
+}
+
+impl DevicesOptions {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        match &self.command {
+            None => midi::print_midi_devices(&mut app.output, &*app.midi)?,
+            Some(DevicesCommand::Probe(options)) => options.run(app)?,
+        }
+        Ok(())
+    }
+
+    /// Like [`DevicesOptions::run`] but waits for the identity reply of `probe` without blocking the event loop of the browser.
+    #[cfg(feature = "wasm")]
+    pub async fn run_async(&self, app: &mut App<'_>) -> CliResult<()> {
+        match &self.command {
+            Some(DevicesCommand::Probe(options)) => options.run_async(app).await,
+            None => self.run(app),
+        }
+    }
+}
+
+impl ProbeOptions {
+    fn run(&self, app: &mut App) -> CliResult<()> {
+        let (send, recv) = mpsc::channel();
+        let in_connection = self.send_identity_request(app, move |reply| {
+            send.send(reply).ok();
+        })?;
+
+        let reply = recv
+            .recv_timeout(PROBE_TIMEOUT)
+            .map_err(|_| no_reply_error())?;
+        drop(in_connection);
+
+        print_identity_reply(app, &reply)
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn run_async(&self, app: &mut App<'_>) -> CliResult<()> {
+        use futures::{
+            channel::mpsc,
+            future::{self, Either},
+            StreamExt,
+        };
+        use gloo_timers::future::TimeoutFuture;
+
+        let (send, mut recv) = mpsc::unbounded();
+        let in_connection = self.send_identity_request(app, move |reply| {
+            send.unbounded_send(reply).ok();
+        })?;
+
+        let timeout = TimeoutFuture::new(PROBE_TIMEOUT.as_millis() as u32);
+        let reply = match future::select(recv.next(), timeout).await {
+            Either::Left((Some(reply), _)) => reply,
+            _ => return Err(no_reply_error()),
+        };
+        drop(in_connection);
+
+        print_identity_reply(app, &reply)
+    }
+
+    /// Connects to the devices and sends the identity request. `on_reply` is called for every identity reply until the returned connection is dropped.
+    fn send_identity_request(
+        &self,
+        app: &mut App,
+        mut on_reply: impl FnMut(IdentityReply) + Send + 'static,
+    ) -> CliResult<Box<dyn MidiInConnection>> {
+        let (in_device, in_connection) = app.midi.connect_in(
+            &self.midi_in_device,
+            Box::new(move |message| {
+                if let Some(reply) = IdentityReply::from_raw_message(message) {
+                    on_reply(reply);
+                }
+            }),
+        )?;
+        let (out_device, mut out_connection) = app.midi.connect_out(&self.midi_out_device)?;
+
+        app.writeln(format_args!("Sending identity request to {out_device}"))?;
+        out_connection
+            .send(&identity_request(self.device_id.device_id))
+            .map_err(|err| format!("Could not send identity request ({err:?})"))?;
+
+        app.writeln(format_args!("Waiting for identity reply from {in_device}"))?;
+        Ok(in_connection)
+    }
+}
+
+fn no_reply_error() -> CliError {
+    format!(
+        "No identity reply received within {}s",
+        PROBE_TIMEOUT.as_secs()
+    )
+    .into()
+}
+
+fn print_identity_reply(app: &mut App, reply: &IdentityReply) -> CliResult<()> {
+    let database = DeviceDatabase::parse(BUILTIN_DEVICE_DATABASE).unwrap();
+
+    app.writeln(format_args!(
+        "- manufacturer: {} ({})",
+        database
+            .find_manufacturer(&reply.manufacturer)
+            .unwrap_or("Unknown"),
+        format_bytes(&reply.manufacturer)
+    ))?;
+    app.writeln(format_args!("- family: {:#06x}", reply.family))?;
+    app.writeln(format_args!("- model: {:#06x}", reply.model))?;
+    app.writeln(format_args!("- version: {}", format_bytes(&reply.version)))?;
+
+    Ok(())
+}
+
+/// Universal Non-Real Time SysEx message asking the device(s) with the given ID (127 = all) for their identity.
+fn identity_request(device_id: u8) -> [u8; 6] {
+    [0xf0, 0x7e, device_id, 0x06, 0x01, 0xf7]
+}
+
+/// Identity of a device as reported by the Universal SysEx Identity Reply.
+#[derive(Clone, Debug, PartialEq)]
+struct IdentityReply {
+    manufacturer: Vec<u8>,
+    family: u16,
+    model: u16,
+    version: [u8; 4],
+}
+
+impl IdentityReply {
+    fn from_raw_message(message: &[u8]) -> Option<Self> {
+        let body = match message {
+            [0xf0, 0x7e, _device_id, 0x06, 0x02, body @ .., 0xf7] => body,
+            _ => return None,
+        };
+
+        // Extended manufacturer IDs consist of three bytes starting with 0
+        let manufacturer_len = match body.first()? {
+            0 => 3,
+            _ => 1,
+        };
+        match body.get(manufacturer_len..)? {
+            &[family_lsb, family_msb, model_lsb, model_msb, v0, v1, v2, v3] => Some(Self {
+                manufacturer: body[..manufacturer_len].to_vec(),
+                family: u16::from(family_lsb) | u16::from(family_msb) << 7,
+                model: u16::from(model_lsb) | u16::from(model_msb) << 7,
+                version: [v0, v1, v2, v3],
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceDatabase {
+    manufacturers: Vec<ManufacturerEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManufacturerEntry {
+    id: Vec<u8>,
+    name: String,
+}
+
+impl DeviceDatabase {
+    fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    fn find_manufacturer(&self, id: &[u8]) -> Option<&str> {
+        self.manufacturers
+            .iter()
+            .find(|manufacturer| manufacturer.id == id)
+            .map(|manufacturer| manufacturer.name.as_str())
+    }
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_identity_reply() {
+        assert_eq!(
+            IdentityReply::from_raw_message(&[
+                0xf0, 0x7e, 0x10, 0x06, 0x02, 0x41, 0x42, 0x01, 0x05, 0x00, 0x01, 0x02, 0x03, 0x04,
+                0xf7
+            ]),
+            Some(IdentityReply {
+                manufacturer: vec![0x41],
+                family: 0xc2,
+                model: 0x05,
+                version: [1, 2, 3, 4],
+            })
+        );
+        assert_eq!(
+            IdentityReply::from_raw_message(&[
+                0xf0, 0x7e, 0x7f, 0x06, 0x02, 0x00, 0x20, 0x29, 0x33, 0x01, 0x00, 0x00, 0x00, 0x00,
+                0x01, 0x07, 0xf7
+            ]),
+            Some(IdentityReply {
+                manufacturer: vec![0x00, 0x20, 0x29],
+                family: 0xb3,
+                model: 0x00,
+                version: [0, 0, 1, 7],
+            })
+        );
+
+        // Identity request and truncated reply
+        assert_eq!(
+            IdentityReply::from_raw_message(&identity_request(0x7f)),
+            None
+        );
+        assert_eq!(
+            IdentityReply::from_raw_message(&[0xf0, 0x7e, 0x10, 0x06, 0x02, 0x41, 0x42, 0xf7]),
+            None
+        );
+    }
+
+    #[test]
+    fn find_manufacturer() {
+        let database = DeviceDatabase::parse(BUILTIN_DEVICE_DATABASE).unwrap();
+
+        assert_eq!(database.find_manufacturer(&[0x41]), Some("Roland"));
+        assert_eq!(
+            database.find_manufacturer(&[0x00, 0x20, 0x29]),
+            Some("Focusrite/Novation")
+        );
+        assert_eq!(database.find_manufacturer(&[0x00, 0x20, 0x30]), None);
+    }
+}
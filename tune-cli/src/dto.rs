@@ -1,13 +1,18 @@
 use io::Read;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io};
-use tune::{key::PianoKey, pitch::Pitch, tuning::KeyboardMapping};
+use std::{collections::HashMap, fs, io, path::Path};
+use tune::{
+    key::PianoKey,
+    pitch::{Pitch, Ratio, RatioExpression, RatioExpressionVariant},
+    tuning::KeyboardMapping,
+};
 
 use crate::CliResult;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum TuneDto {
     Scale(ScaleDto),
+    Est(EstDto),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -15,14 +20,104 @@ pub struct ScaleDto {
     pub root_key_midi_number: i32,
     pub root_pitch_in_hz: Option<f64>,
     pub items: Vec<ScaleItemDto>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ProvenanceDto>,
 }
 
 impl ScaleDto {
     pub fn read(input: impl Read) -> CliResult<ScaleDto> {
-        let TuneDto::Scale(scale) = serde_yaml::from_reader(input)
-            .map_err(|io_err| format!("Could not parse scale file: {io_err}"))?;
+        match serde_yaml::from_reader(input)
+            .map_err(|io_err| format!("Could not parse scale file: {io_err}"))?
+        {
+            TuneDto::Scale(scale) => Ok(scale),
+            TuneDto::Est(_) => Err("Expected a scale file".to_owned().into()),
+        }
+    }
 
-        Ok(scale)
+    /// Reads either a YAML scale file or a plain pitch list (see [`ScaleDto::from_pitch_list`]).
+    pub fn read_yaml_or_pitch_list(
+        mut input: impl Read,
+        root_key: PianoKey,
+    ) -> CliResult<ScaleDto> {
+        let mut content = String::new();
+        input.read_to_string(&mut content)?;
+
+        // YAML comments may precede the document
+        let first_line = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+        if first_line.starts_with("---") || first_line.starts_with("Scale:") {
+            ScaleDto::read(content.as_bytes())
+        } else {
+            ScaleDto::from_pitch_list(&content, root_key)
+        }
+    }
+
+    /// Creates a scale from a newline-separated list of frequencies (e.g. 440 or 440Hz) and ratios (e.g. 5/4 or 386c).
+    ///
+    /// The first line must be a frequency and is mapped to `root_key`. Ratios are relative to the first line. Empty lines and lines starting with `!` are ignored.
+    ///
+    /// All lines must be mapped to valid MIDI keys, i.e. `root_key` plus the number of pitches must not exceed 128.
+    pub fn from_pitch_list(input: &str, root_key: PianoKey) -> CliResult<ScaleDto> {
+        if !(0..128).contains(&root_key.midi_number()) {
+            return Err(format!(
+                "Invalid root key {}: Must be a MIDI key in the range 0..=127",
+                root_key.midi_number()
+            )
+            .into());
+        }
+
+        let mut root_pitch = None;
+        let mut items = Vec::new();
+
+        for (line_index, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('!') {
+                continue;
+            }
+
+            let pitch = match (parse_pitch_list_entry(line), root_pitch) {
+                (Ok(PitchListEntry::Frequency(pitch)), _) => pitch,
+                (Ok(PitchListEntry::Ratio(ratio)), Some(root_pitch)) => root_pitch * ratio,
+                (Ok(PitchListEntry::Ratio(_)), None) => {
+                    return Err(format!(
+                        "Invalid pitch list: The first line must be a frequency (line {})",
+                        line_index + 1
+                    )
+                    .into())
+                }
+                (Err(err), _) => {
+                    return Err(
+                        format!("Invalid pitch list: {err} (line {})", line_index + 1).into(),
+                    )
+                }
+            };
+
+            let key = root_key.plus_steps(items.len() as i32);
+            if key.midi_number() >= 128 {
+                return Err(format!(
+                    "Invalid pitch list: Line {} would be mapped to key {} beyond the MIDI range. Use a lower root key",
+                    line_index + 1,
+                    key.midi_number()
+                )
+                .into());
+            }
+
+            root_pitch.get_or_insert(pitch);
+            items.push(ScaleItemDto {
+                key_midi_number: key.midi_number(),
+                pitch_in_hz: pitch.as_hz(),
+            });
+        }
+
+        Ok(ScaleDto {
+            root_key_midi_number: root_key.midi_number(),
+            root_pitch_in_hz: root_pitch.map(Pitch::as_hz),
+            items,
+            provenance: None,
+        })
     }
 
     pub fn keys(&self) -> Vec<PianoKey> {
@@ -48,6 +143,26 @@ impl ScaleDto {
     }
 }
 
+enum PitchListEntry {
+    Frequency(Pitch),
+    Ratio(Ratio),
+}
+
+/// Plain numbers and values ending with Hz are frequencies. All other ratio expressions are relative.
+fn parse_pitch_list_entry(line: &str) -> Result<PitchListEntry, String> {
+    if line.ends_with("Hz") || line.ends_with("hz") {
+        return line.parse().map(PitchListEntry::Frequency);
+    }
+
+    let expression = line.parse::<RatioExpression>()?;
+    Ok(match expression.variant() {
+        RatioExpressionVariant::Float { float_value } => {
+            PitchListEntry::Frequency(Pitch::from_hz(float_value))
+        }
+        _ => PitchListEntry::Ratio(expression.ratio()),
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScaleItemDto {
     pub key_midi_number: i32,
@@ -63,3 +178,125 @@ impl KeyboardMapping<PianoKey> for DtoKeyboardMapping {
         self.key_map.get(&key).copied()
     }
 }
+
+/// Describes how a scale was generated s.t. the result can be reproduced.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProvenanceDto {
+    pub command_line: String,
+    pub generator: Option<String>,
+    pub source_files: Vec<SourceFileDto>,
+    /// Provenance of the scale file the scale was derived from, e.g. when piping one `tune scale` command into another.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<Box<ProvenanceDto>>,
+}
+
+impl ProvenanceDto {
+    /// Human-readable representation. Upstream provenance is indented by two spaces per level.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Command line: {}", self.command_line)];
+        if let Some(generator) = &self.generator {
+            lines.push(format!("Generator: {generator}"));
+        }
+        for source_file in &self.source_files {
+            lines.push(format!(
+                "Source file: {} (FNV-1a hash: {})",
+                source_file.location, source_file.fnv1a_hash
+            ));
+        }
+        if let Some(upstream) = &self.upstream {
+            lines.push("Derived from:".to_owned());
+            lines.extend(upstream.lines().into_iter().map(|line| format!("  {line}")));
+        }
+        lines
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SourceFileDto {
+    pub location: String,
+    /// 64-bit FNV-1a hash of the file content in hexadecimal notation.
+    pub fnv1a_hash: String,
+}
+
+impl SourceFileDto {
+    pub fn read(location: &Path) -> CliResult<SourceFileDto> {
+        let content = fs::read(location).map_err(|io_err| {
+            format!(
+                "Could not read source file {}: {io_err}",
+                location.display()
+            )
+        })?;
+        Ok(SourceFileDto {
+            location: location.display().to_string(),
+            fnv1a_hash: format!("{:016x}", fnv1a_hash(&content)),
+        })
+    }
+}
+
+/// A stable hash function s.t. the same file content yields the same hash on every platform and in every version.
+fn fnv1a_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EstDto {
+    /// Number of steps per octave, kept for consumers of octave-based tunings.
+    pub num_steps_per_octave: u16,
+    pub period_in_cents: f64,
+    pub num_steps_per_period: u16,
+    pub step_size_in_cents: f64,
+    pub stretch_in_cents: f64,
+    pub fret_constant: f64,
+    pub val: ValDto,
+    pub temperaments: Vec<TemperamentSupportDto>,
+    pub tempered_out_commas: Vec<CommaDto>,
+    pub interval_locations: Vec<IntervalLocationDto>,
+    pub notations: Vec<NotationDto>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ValDto {
+    pub odd_limit: u8,
+    pub values: Vec<u16>,
+    pub errors_in_cents: Vec<f64>,
+    pub errors_in_steps: Vec<f64>,
+    pub te_simple_badness: f64,
+    pub subgroup: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TemperamentSupportDto {
+    pub name: String,
+    pub is_supported: bool,
+}
+
+/// Numerators and denominators are strings since they can exceed the range of JSON numbers.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommaDto {
+    pub prime_limit: u8,
+    pub numer: String,
+    pub denom: String,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IntervalLocationDto {
+    pub numer: String,
+    pub denom: String,
+    pub tempered_location: i32,
+    pub patent_location: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotationDto {
+    pub temperament_type: String,
+    pub num_cycles: u16,
+    pub primary_step: i16,
+    pub secondary_step: i16,
+    pub sharpness: i16,
+    pub mos_type: String,
+    pub note_names: Vec<String>,
+    pub keyboard_layout: Vec<Vec<i32>>,
+}
@@ -2,23 +2,76 @@ use std::{
     cmp::Ordering,
     fmt::{self, Display},
     io,
+    path::{Path, PathBuf},
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use tune::{
-    comma::{self, CommaCatalog},
+    comma::{self, Comma, CommaCatalog},
     key::{Keyboard, PianoKey},
     math,
     pitch::Ratio,
     temperament::{EqualTemperament, TemperamentType, Val},
 };
 
-use crate::App;
+use crate::{
+    api::EstParams,
+    dto::{
+        CommaDto, EstDto, IntervalLocationDto, NotationDto, TemperamentSupportDto, TuneDto, ValDto,
+    },
+    report::{self, HtmlReport},
+    shared::Notation,
+    App, CliResult,
+};
+
+const MATCHING_TEMPERAMENTS: [(&str, &str); 3] = [
+    ("syntonic comma", "meantone"),
+    ("major chroma", "mavila"),
+    ("porcupine comma", "porcupine"),
+];
+
+const INTERVALS_TO_LOCATE: [&str; 7] = [
+    "septimal minor third",
+    "minor third",
+    "major third",
+    "perfect fourth",
+    "perfect fifth",
+    "harmonic seventh",
+    "octave",
+];
+
+/// Commas listed in the comparison table of `tune est compare`.
+const NOTABLE_COMMAS: [&str; 11] = [
+    "Pythagorean comma",
+    "syntonic comma",
+    "schisma",
+    "diaschisma",
+    "minor diesis",
+    "kleisma",
+    "porcupine comma",
+    "magic comma",
+    "septimal comma",
+    "septimal kleisma",
+    "Orwell comma",
+];
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub(crate) struct EstOptions {
+    #[command(subcommand)]
+    command: Option<EstCommand>,
+
+    /// Output format
+    #[arg(long = "format", default_value = "text")]
+    format: EstFormat,
+
     /// Size of the interval to analyze
-    step_size: Ratio,
+    #[arg(required = true)]
+    step_size: Option<Ratio>,
+
+    /// Period of the equal-step tuning, e.g. 3/1 for equal divisions of the tritave
+    #[arg(long = "period", default_value = "2")]
+    period: Ratio,
 
     /// Odd limit for val output
     #[arg(long = "limit", default_value = "13")]
@@ -27,21 +80,80 @@ pub(crate) struct EstOptions {
     /// Error threshold for subgroup determination
     #[arg(long = "error", default_value = "25c")]
     error_threshold: Ratio,
+
+    /// Notation used for the names of the scale steps
+    #[arg(long = "notation", default_value = "heptatonic")]
+    notation: Notation,
+
+    /// Additionally write the analysis as an HTML report with tables and diagrams to the given file
+    #[arg(long = "report")]
+    report_location: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum EstCommand {
+    /// Compare the prime errors, fifths and tempered-out commas of several EDOs side by side
+    #[command(name = "compare")]
+    Compare(CompareOptions),
+}
+
+#[derive(Parser)]
+struct CompareOptions {
+    /// Numbers of steps per octave of the EDOs to compare
+    #[arg(required = true, value_parser = clap::value_parser!(u16).range(1..))]
+    num_steps_per_octave: Vec<u16>,
+
+    /// Prime limit of the compared patent vals. Commas beyond this limit are marked with n/a
+    #[arg(long = "limit", default_value = "13", value_parser = clap::value_parser!(u8).range(2..))]
+    prime_limit: u8,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum EstFormat {
+    /// Human-readable analysis
+    #[value(name = "text")]
+    Text,
+    /// Machine-readable analysis
+    #[value(name = "json")]
+    Json,
 }
 
 impl EstOptions {
-    pub fn run(&self, app: &mut App) -> io::Result<()> {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        if let Some(EstCommand::Compare(options)) = &self.command {
+            return Ok(options.run(app)?);
+        }
+
+        // Guaranteed by clap if no subcommand is given
+        let step_size = self.step_size.unwrap();
+
+        match self.format {
+            EstFormat::Text => self.print_text(app, step_size)?,
+            EstFormat::Json => self.print_json(app, step_size)?,
+        }
+
+        if let Some(report_location) = &self.report_location {
+            self.write_report(report_location, step_size)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_text(&self, app: &mut App, step_size: Ratio) -> io::Result<()> {
+        let analysis = EstAnalysis::new(step_size, &self.params());
+        let temperament = &create_temperament(step_size, self.period);
+
         let mut printer = EstPrinter {
             app,
-            val: Val::patent(self.step_size, self.odd_limit),
-            catalog: CommaCatalog::new(comma::huygens_fokker_intervals()),
+            analysis,
+            equal_division_name: equal_division_name(self.period),
+            notation: self.notation,
         };
 
-        let temperament = EqualTemperament::find().by_step_size(self.step_size);
-        let stretch = temperament.size_of_octave().deviation_from(Ratio::octave());
+        let stretch = temperament.size_of_octave().deviation_from(self.period);
 
         printer.print_headline(temperament.num_steps_per_octave(), stretch)?;
-        printer.print_basic_information(self.step_size)?;
+        printer.print_basic_information(step_size)?;
 
         printer.print_newline()?;
 
@@ -49,24 +161,16 @@ impl EstOptions {
 
         printer.print_newline()?;
 
-        printer.print_matching_temperament("syntonic comma", "meantone")?;
-        printer.print_matching_temperament("major chroma", "mavila")?;
-        printer.print_matching_temperament("porcupine comma", "porcupine")?;
+        printer.print_matching_temperaments()?;
         printer.print_tempered_out_commas()?;
 
         printer.print_newline()?;
 
-        printer.print_interval_location("septimal minor third")?;
-        printer.print_interval_location("minor third")?;
-        printer.print_interval_location("major third")?;
-        printer.print_interval_location("perfect fourth")?;
-        printer.print_interval_location("perfect fifth")?;
-        printer.print_interval_location("harmonic seventh")?;
-        printer.print_interval_location("octave")?;
+        printer.print_interval_locations()?;
 
         printer.print_newline()?;
 
-        printer.print_generalized_notes(&temperament)?;
+        printer.print_generalized_notes(temperament)?;
 
         match temperament.temperament_type() {
             TemperamentType::Meantone => {
@@ -81,12 +185,467 @@ impl EstOptions {
 
         Ok(())
     }
+
+    fn print_json(&self, app: &mut App, step_size: Ratio) -> CliResult<()> {
+        let dto = TuneDto::Est(analyze(step_size, &self.params()));
+
+        app.writeln(
+            serde_json::to_string_pretty(&dto)
+                .map_err(|err| format!("Could not serialize analysis: {err}"))?,
+        )
+        .map_err(Into::into)
+    }
+
+    fn write_report(&self, report_location: &Path, step_size: Ratio) -> CliResult<()> {
+        let dto = analyze(step_size, &self.params());
+        let equal_division_name = equal_division_name(self.period);
+
+        let mut report = HtmlReport::new(&format!(
+            "Properties of {}-{}",
+            dto.num_steps_per_period, equal_division_name
+        ));
+        report.table(
+            &[],
+            [
+                vec![
+                    "step size".to_owned(),
+                    format!("{:.3}c", dto.step_size_in_cents),
+                ],
+                vec!["period".to_owned(), format!("{:.3}c", dto.period_in_cents)],
+                vec![
+                    "stretch".to_owned(),
+                    format!("{:+.3}c", dto.stretch_in_cents),
+                ],
+                vec![
+                    "fret constant".to_owned(),
+                    format!("{:.3}", dto.fret_constant),
+                ],
+            ],
+        );
+
+        let val = &dto.val;
+        let primes: Vec<_> = math::U8_PRIMES
+            .iter()
+            .take(val.values.len())
+            .map(u8::to_string)
+            .collect();
+        report.heading(&format!("Patent val ({}-limit)", val.odd_limit));
+        report.table(
+            &["prime", "steps", "error", "error (relative)"],
+            primes
+                .iter()
+                .zip(&val.values)
+                .zip(val.errors_in_cents.iter().zip(&val.errors_in_steps))
+                .map(|((prime, value), (error_in_cents, error_in_steps))| {
+                    vec![
+                        prime.clone(),
+                        value.to_string(),
+                        format!("{error_in_cents:+.1}c"),
+                        format!("{:+.1}%", error_in_steps * 100.0),
+                    ]
+                }),
+        );
+        // The error of a patent val is at most half a step
+        report.diagram(&report::error_bars(
+            primes
+                .iter()
+                .map(String::as_str)
+                .zip(val.errors_in_cents.iter().copied()),
+            dto.step_size_in_cents / 2.0,
+            "c",
+        ));
+        report.paragraph(&format!(
+            "TE simple badness: {:.3}‰",
+            val.te_simple_badness * 1000.0
+        ));
+        report.paragraph(&format!(
+            "Subgroup: {}",
+            WithSeparator(".", || &val.subgroup)
+        ));
+
+        report.heading("Supported temperaments");
+        report.list(
+            dto.temperaments
+                .iter()
+                .filter(|temperament| temperament.is_supported)
+                .map(|temperament| temperament.name.clone()),
+        );
+
+        report.heading("Tempered-out commas");
+        report.table(
+            &["limit", "comma", "description"],
+            dto.tempered_out_commas.iter().map(|comma| {
+                vec![
+                    comma.prime_limit.to_string(),
+                    format!("{}/{}", comma.numer, comma.denom),
+                    comma.description.clone(),
+                ]
+            }),
+        );
+
+        report.heading("Interval locations");
+        report.table(
+            &["interval", "tempered", "patent"],
+            dto.interval_locations.iter().map(|location| {
+                vec![
+                    format!("{}/{}", location.numer, location.denom),
+                    location.tempered_location.to_string(),
+                    location.patent_location.to_string(),
+                ]
+            }),
+        );
+
+        for notation in &dto.notations {
+            report.heading(&format!("{} notation", notation.temperament_type));
+            report.table(
+                &[],
+                [
+                    vec![
+                        "number of cycles".to_owned(),
+                        notation.num_cycles.to_string(),
+                    ],
+                    vec![
+                        "primary step".to_owned(),
+                        format!("{} {equal_division_name} steps", notation.primary_step),
+                    ],
+                    vec![
+                        "secondary step".to_owned(),
+                        format!("{} {equal_division_name} steps", notation.secondary_step),
+                    ],
+                    vec![
+                        "sharp".to_owned(),
+                        format!(
+                            "{} {equal_division_name} steps ({})",
+                            notation.sharpness, notation.mos_type
+                        ),
+                    ],
+                ],
+            );
+            report.table(
+                &["step", "name"],
+                notation
+                    .note_names
+                    .iter()
+                    .enumerate()
+                    .map(|(index, note_name)| vec![index.to_string(), note_name.clone()]),
+            );
+            report.table(
+                &[],
+                notation
+                    .keyboard_layout
+                    .iter()
+                    .map(|row| row.iter().map(i32::to_string).collect()),
+            );
+        }
+
+        report.write_to(report_location)
+    }
+
+    fn params(&self) -> EstParams {
+        EstParams {
+            period: self.period,
+            odd_limit: self.odd_limit,
+            error_threshold: self.error_threshold,
+            notation: self.notation,
+        }
+    }
+}
+
+impl CompareOptions {
+    fn run(&self, app: &mut App) -> io::Result<()> {
+        let catalog = CommaCatalog::new(comma::huygens_fokker_intervals());
+        let columns: Vec<_> = self
+            .num_steps_per_octave
+            .iter()
+            .map(|&num_steps_per_octave| CompareColumn {
+                num_steps_per_octave,
+                val: Val::patent(
+                    Ratio::octave().divided_into_equal_steps(num_steps_per_octave),
+                    self.prime_limit,
+                ),
+                temperament: EqualTemperament::find().by_edo(num_steps_per_octave),
+            })
+            .collect();
+
+        let print_row = |app: &mut App, label: &str, cell: &dyn Fn(&CompareColumn) -> String| {
+            app.write(format_args!("{label:<36}"))?;
+            for column in &columns {
+                app.write(format_args!("{:>9}", cell(column)))?;
+            }
+            app.writeln("")
+        };
+
+        app.writeln(format_args!(
+            "==== Comparison of {} ====",
+            WithSeparator(", ", || columns
+                .iter()
+                .map(|column| format!("{}-EDO", column.num_steps_per_octave)))
+        ))?;
+        app.writeln("")?;
+
+        print_row(app, "", &|column| {
+            format!("{}-EDO", column.num_steps_per_octave)
+        })?;
+        print_row(app, "step size", &|column| {
+            format!("{:.1}c", column.val.step_size().as_cents())
+        })?;
+        print_row(app, "fifth (steps)", &|column| {
+            column.temperament.num_steps_per_fifth().to_string()
+        })?;
+        print_row(app, "fifth (size)", &|column| {
+            format!("{:.1}c", column.temperament.size_of_fifth().as_cents())
+        })?;
+        print_row(app, "TE simple badness", &|column| {
+            format!("{:.3}‰", column.val.te_simple_badness() * 1000.0)
+        })?;
+
+        app.writeln("")?;
+        app.writeln(format_args!(
+            "-- Prime errors (patent val, {}-limit) --",
+            self.prime_limit
+        ))?;
+        for (index, prime) in math::U8_PRIMES
+            .iter()
+            .take_while(|&&prime| prime <= self.prime_limit)
+            .enumerate()
+        {
+            print_row(app, &prime.to_string(), &|column| {
+                format!(
+                    "{:+.1}c",
+                    column.val.errors().nth(index).unwrap().as_cents()
+                )
+            })?;
+        }
+
+        app.writeln("")?;
+        app.writeln("-- Tempered-out commas --")?;
+        for comma_name in NOTABLE_COMMAS {
+            let comma = catalog.comma_for_name(comma_name).unwrap();
+            let (numer, denom) = comma.as_fraction().unwrap();
+            print_row(app, &format!("{numer}/{denom} ({comma_name})"), &|column| {
+                match column.val.map(comma) {
+                    Some(0) => "x",
+                    Some(_) => "-",
+                    None => "n/a",
+                }
+                .to_owned()
+            })?;
+        }
+
+        Ok(())
+    }
 }
 
-struct EstPrinter<'a, 'b> {
-    app: &'a mut App<'b>,
+struct CompareColumn {
+    num_steps_per_octave: u16,
+    val: Val,
+    temperament: EqualTemperament,
+}
+
+pub(crate) fn analyze(step_size: Ratio, params: &EstParams) -> EstDto {
+    let analysis = EstAnalysis::new(step_size, params);
+    let temperament = create_temperament(step_size, params.period);
+    let val = &analysis.val;
+
+    let mut notations = vec![notation_dto(&temperament, params.notation)];
+    if let TemperamentType::Meantone = temperament.temperament_type() {
+        notations.extend(
+            temperament
+                .as_porcupine()
+                .map(|porcupine| notation_dto(&porcupine, params.notation)),
+        );
+    }
+
+    EstDto {
+        num_steps_per_octave: Ratio::octave().num_equal_steps_of_size(step_size).round() as u16,
+        period_in_cents: params.period.as_cents(),
+        num_steps_per_period: temperament.num_steps_per_octave(),
+        step_size_in_cents: step_size.as_cents(),
+        stretch_in_cents: temperament
+            .size_of_octave()
+            .deviation_from(params.period)
+            .as_cents(),
+        fret_constant: fret_constant(step_size),
+        val: ValDto {
+            odd_limit: params.odd_limit,
+            values: val.values().to_vec(),
+            errors_in_cents: val.errors().map(Ratio::as_cents).collect(),
+            errors_in_steps: val.errors_in_steps().collect(),
+            te_simple_badness: val.te_simple_badness(),
+            subgroup: val.subgroup(params.error_threshold).into_iter().collect(),
+        },
+        temperaments: MATCHING_TEMPERAMENTS
+            .iter()
+            .map(|&(comma_name, temperament_name)| TemperamentSupportDto {
+                name: temperament_name.to_owned(),
+                is_supported: analysis.supports(comma_name),
+            })
+            .collect(),
+        tempered_out_commas: analysis
+            .tempered_out_commas()
+            .filter_map(|comma| {
+                comma.as_fraction().map(|(numer, denom)| CommaDto {
+                    prime_limit: comma.prime_limit(),
+                    numer: numer.to_string(),
+                    denom: denom.to_string(),
+                    description: comma.description().to_owned(),
+                })
+            })
+            .collect(),
+        interval_locations: INTERVALS_TO_LOCATE
+            .iter()
+            .map(|&interval_name| {
+                let (fraction, tempered_location, patent_location) =
+                    analysis.interval_location(interval_name);
+                IntervalLocationDto {
+                    numer: fraction.0.to_string(),
+                    denom: fraction.1.to_string(),
+                    tempered_location,
+                    patent_location: patent_location as i32,
+                }
+            })
+            .collect(),
+        notations,
+    }
+}
+
+fn create_temperament(step_size: Ratio, period: Ratio) -> EqualTemperament {
+    EqualTemperament::find()
+        .with_period(period)
+        .by_step_size(step_size)
+}
+
+struct EstAnalysis {
     val: Val,
     catalog: CommaCatalog,
+    /// The primes that commas may consist of. [`None`] if all primes are allowed.
+    subgroup: Option<Vec<u8>>,
+}
+
+impl EstAnalysis {
+    fn new(step_size: Ratio, params: &EstParams) -> Self {
+        let val = Val::patent(step_size, params.odd_limit);
+
+        // Without an octave period, prime 2 is not special anymore. Commas are only meaningful if all of their primes are approximated well.
+        let subgroup = (!params
+            .period
+            .deviation_from(Ratio::octave())
+            .is_negligible())
+        .then(|| val.subgroup(params.error_threshold).into_iter().collect());
+
+        Self {
+            val,
+            catalog: CommaCatalog::new(comma::huygens_fokker_intervals()),
+            subgroup,
+        }
+    }
+
+    fn supports(&self, comma_name: &str) -> bool {
+        let comma = self.catalog.comma_for_name(comma_name).unwrap();
+        self.is_in_subgroup(comma) && self.val.tempers_out(comma)
+    }
+
+    fn tempered_out_commas(&self) -> impl Iterator<Item = &Comma> {
+        math::U8_PRIMES
+            .iter()
+            .take_while(|&&limit| limit <= self.val.prime_limit())
+            .flat_map(|&limit| self.catalog.commas_for_limit(limit))
+            .filter(|comma| self.is_in_subgroup(comma) && self.val.tempers_out(comma))
+    }
+
+    fn is_in_subgroup(&self, comma: &Comma) -> bool {
+        match &self.subgroup {
+            None => true,
+            Some(subgroup) => comma
+                .prime_factors()
+                .iter()
+                .zip(math::U8_PRIMES)
+                .all(|(&factor, prime)| factor == 0 || subgroup.contains(prime)),
+        }
+    }
+
+    fn interval_location(&self, interval_name: &str) -> ((u128, u128), i32, f64) {
+        let interval = self.catalog.comma_for_name(interval_name).unwrap();
+        let fraction = interval.as_fraction().unwrap();
+        let tempered_location = self.val.map(interval).unwrap_or_default();
+        let patent_location = interval
+            .as_ratio()
+            .num_equal_steps_of_size(self.val.step_size())
+            .round();
+
+        (fraction, tempered_location, patent_location)
+    }
+}
+
+/// Returns "EDO" for octave-based tunings and, e.g., "ED3" for tritave-based tunings.
+fn equal_division_name(period: Ratio) -> String {
+    let period_as_float = period.as_float();
+    if period.deviation_from(Ratio::octave()).is_negligible() {
+        "EDO".to_owned()
+    } else if (period_as_float - period_as_float.round()).abs() < 1e-9 {
+        format!("ED{}", period_as_float.round())
+    } else {
+        format!("ED{period:#}")
+    }
+}
+
+fn fret_constant(step_size: Ratio) -> f64 {
+    step_size.as_float() / (step_size.as_float() - 1.0)
+}
+
+fn mos_type(temperament: &EqualTemperament) -> &'static str {
+    match (
+        temperament.sharpness().cmp(&0),
+        temperament.temperament_type(),
+    ) {
+        (Ordering::Equal, _) => "equalized",
+        (Ordering::Greater, TemperamentType::Meantone) => "diatonic",
+        (Ordering::Less, TemperamentType::Meantone) => "antidiatonic",
+        (Ordering::Greater, TemperamentType::Porcupine) => "archeotonic",
+        (Ordering::Less, TemperamentType::Porcupine) => "antiarcheotonic",
+    }
+}
+
+fn keyboard_layout(temperament: &EqualTemperament) -> Vec<Vec<i32>> {
+    let keyboard = Keyboard::root_at(PianoKey::from_midi_number(0))
+        .with_steps_of(temperament)
+        .coprime();
+
+    (-5i16..5)
+        .rev()
+        .map(|y| {
+            (0..10)
+                .map(|x| {
+                    keyboard
+                        .get_key(x, y)
+                        .midi_number()
+                        .rem_euclid(i32::from(temperament.num_steps_per_octave()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn notation_dto(temperament: &EqualTemperament, notation: Notation) -> NotationDto {
+    NotationDto {
+        temperament_type: temperament.temperament_type().to_string(),
+        num_cycles: temperament.num_cycles(),
+        primary_step: temperament.primary_step(),
+        secondary_step: temperament.secondary_step(),
+        sharpness: temperament.sharpness(),
+        mos_type: mos_type(temperament).to_owned(),
+        note_names: (0..temperament.num_steps_per_octave())
+            .map(|index| notation.note_name(temperament, index))
+            .collect(),
+        keyboard_layout: keyboard_layout(temperament),
+    }
+}
+
+struct EstPrinter<'a, 'b> {
+    app: &'a mut App<'b>,
+    analysis: EstAnalysis,
+    equal_division_name: String,
+    notation: Notation,
 }
 
 impl<'a, 'b> EstPrinter<'a, 'b> {
@@ -94,10 +653,11 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
         self.app.writeln("")
     }
 
-    fn print_headline(&mut self, num_steps_per_octave: u16, stretch: Ratio) -> io::Result<()> {
+    fn print_headline(&mut self, num_steps_per_period: u16, stretch: Ratio) -> io::Result<()> {
         self.app.writeln(format_args!(
-            "==== Properties of {}-EDO{} ====",
-            num_steps_per_octave,
+            "==== Properties of {}-{}{} ====",
+            num_steps_per_period,
+            self.equal_division_name,
             if stretch.is_negligible() {
                 String::new()
             } else {
@@ -107,7 +667,7 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
     }
 
     fn print_basic_information(&mut self, step_size: Ratio) -> io::Result<()> {
-        let fret_constant = step_size.as_float() / (step_size.as_float() - 1.0);
+        let fret_constant = fret_constant(step_size);
         self.app.writeln(format_args!(
             "- step size: {step_size:#}\n\
              - fret constant: {fret_constant:.3}",
@@ -115,7 +675,7 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
     }
 
     fn print_val(&mut self, odd_limit: u8, threshold: Ratio) -> io::Result<()> {
-        let val = &self.val;
+        let val = &self.analysis.val;
 
         self.app
             .writeln(format_args!("-- Patent val ({odd_limit}-limit) --"))?;
@@ -135,7 +695,7 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
         ))?;
         self.app.writeln(format_args!(
             "TE simple badness: {:.3}‰",
-            self.val.te_simple_badness() * 1000.0
+            val.te_simple_badness() * 1000.0
         ))?;
         self.app.writeln(format_args!(
             "subgroup: {}",
@@ -145,74 +705,48 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
         Ok(())
     }
 
-    fn print_matching_temperament(
-        &mut self,
-        comma_name: &str,
-        temperament_name: &str,
-    ) -> io::Result<()> {
-        if self
-            .val
-            .tempers_out(self.catalog.comma_for_name(comma_name).unwrap())
-        {
-            self.app
-                .writeln(format_args!("- supports {temperament_name} temperament"))?;
+    fn print_matching_temperaments(&mut self) -> io::Result<()> {
+        for (comma_name, temperament_name) in MATCHING_TEMPERAMENTS {
+            if self.analysis.supports(comma_name) {
+                self.app
+                    .writeln(format_args!("- supports {temperament_name} temperament"))?;
+            }
         }
 
         Ok(())
     }
 
     fn print_tempered_out_commas(&mut self) -> io::Result<()> {
-        let val = &self.val;
-
-        for &limit in math::U8_PRIMES
-            .iter()
-            .take_while(|&&limit| limit <= val.prime_limit())
-        {
-            for comma in self.catalog.commas_for_limit(limit) {
-                if self.val.tempers_out(comma) {
-                    if let Some((numer, denom)) = comma.as_fraction() {
-                        self.app.writeln(format_args!(
-                            "- tempers out {}-limit {}/{} ({})",
-                            comma.prime_limit(),
-                            numer,
-                            denom,
-                            comma.description()
-                        ))?;
-                    }
-                }
+        for comma in self.analysis.tempered_out_commas() {
+            if let Some((numer, denom)) = comma.as_fraction() {
+                self.app.writeln(format_args!(
+                    "- tempers out {}-limit {}/{} ({})",
+                    comma.prime_limit(),
+                    numer,
+                    denom,
+                    comma.description()
+                ))?;
             }
         }
 
         Ok(())
     }
 
-    fn print_interval_location(&mut self, interval_name: &str) -> io::Result<()> {
-        let interval = self.catalog.comma_for_name(interval_name).unwrap();
-        let fraction = interval.as_fraction().unwrap();
-        let tempered_location = self.val.map(interval).unwrap_or_default();
-        let patent_location = interval
-            .as_ratio()
-            .num_equal_steps_of_size(self.val.step_size())
-            .round();
+    fn print_interval_locations(&mut self) -> io::Result<()> {
+        for interval_name in INTERVALS_TO_LOCATE {
+            let (fraction, tempered_location, patent_location) =
+                self.analysis.interval_location(interval_name);
 
-        self.app.writeln(format_args!(
-            "Tempered vs. patent location of {}/{}: {} vs. {}",
-            fraction.0, fraction.1, tempered_location, patent_location
-        ))
+            self.app.writeln(format_args!(
+                "Tempered vs. patent location of {}/{}: {} vs. {}",
+                fraction.0, fraction.1, tempered_location, patent_location
+            ))?;
+        }
+
+        Ok(())
     }
 
     fn print_generalized_notes(&mut self, temperament: &EqualTemperament) -> io::Result<()> {
-        let mos_type = match (
-            temperament.sharpness().cmp(&0),
-            temperament.temperament_type(),
-        ) {
-            (Ordering::Equal, _) => "equalized",
-            (Ordering::Greater, TemperamentType::Meantone) => "diatonic",
-            (Ordering::Less, TemperamentType::Meantone) => "antidiatonic",
-            (Ordering::Greater, TemperamentType::Porcupine) => "archeotonic",
-            (Ordering::Less, TemperamentType::Porcupine) => "antiarcheotonic",
-        };
-
         self.app.writeln(format_args!(
             "== {} notation ==",
             temperament.temperament_type()
@@ -226,23 +760,22 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
             temperament.num_cycles()
         ))?;
         self.app.writeln(format_args!(
-            "1 primary step = {} EDO steps",
-            temperament.primary_step()
+            "1 primary step = {} {} steps",
+            temperament.primary_step(),
+            self.equal_division_name
         ))?;
         self.app.writeln(format_args!(
-            "1 secondary step = {} EDO steps",
-            temperament.secondary_step()
+            "1 secondary step = {} {} steps",
+            temperament.secondary_step(),
+            self.equal_division_name
         ))?;
         self.app.writeln(format_args!(
-            "1 sharp (# or -) = {} EDO steps ({})",
+            "1 sharp (# or -) = {} {} steps ({})",
             temperament.sharpness(),
-            mos_type
+            self.equal_division_name,
+            mos_type(temperament)
         ))?;
 
-        let keyboard = Keyboard::root_at(PianoKey::from_midi_number(0))
-            .with_steps_of(temperament)
-            .coprime();
-
         self.print_newline()?;
 
         self.app.writeln("-- Scale steps --")?;
@@ -250,22 +783,16 @@ impl<'a, 'b> EstPrinter<'a, 'b> {
             self.app.writeln(format_args!(
                 "{:>3}. {}",
                 index,
-                temperament.get_heptatonic_name(index)
+                self.notation.note_name(temperament, index)
             ))?;
         }
 
         self.print_newline()?;
 
         self.app.writeln("-- Keyboard layout --")?;
-        for y in (-5i16..5).rev() {
-            for x in 0..10 {
-                self.app.write(format_args!(
-                    "{:^4}",
-                    keyboard
-                        .get_key(x, y)
-                        .midi_number()
-                        .rem_euclid(i32::from(temperament.num_steps_per_octave())),
-                ))?;
+        for row in keyboard_layout(temperament) {
+            for key in row {
+                self.app.write(format_args!("{key:^4}"))?;
             }
             self.print_newline()?;
         }
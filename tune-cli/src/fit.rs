@@ -0,0 +1,284 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use clap::Parser;
+use tune::{
+    key::PianoKey,
+    pitch::{Pitch, Ratio},
+    scala::Scl,
+};
+
+use crate::{dto::ScaleDto, shared, App, CliResult};
+
+/// Resolution of the coarse generator search.
+const GENERATOR_GRID_IN_CENTS: f64 = 0.1;
+
+/// Resolution of the refined generator search around the best grid value.
+const GENERATOR_REFINEMENT_IN_CENTS: f64 = 0.001;
+
+#[derive(Parser)]
+pub(crate) struct FitOptions {
+    /// Period of the equal temperament and generator candidates
+    #[arg(long = "period", default_value = "2", value_parser = parse_period)]
+    period: Ratio,
+
+    /// Largest number of steps per period of the equal temperament candidates
+    #[arg(long = "max-steps", default_value = "72")]
+    max_steps: u16,
+
+    /// Largest number of generations in each direction of the generator candidates
+    #[arg(long = "max-gens", default_value = "12")]
+    max_generations: u16,
+
+    /// Candidates with an RMS deviation below this threshold are considered a fit. The simplest fitting candidate is selected
+    #[arg(long = "tol", default_value = "3c")]
+    tolerance: Ratio,
+
+    /// Additionally compare the measured pitches to the given scl files
+    #[arg(long = "scl")]
+    scl_file_locations: Vec<PathBuf>,
+
+    /// Measured frequencies (e.g. 261.6 or 261.6Hz) or ratios relative to the first frequency. If absent, the pitches are read from stdin in the same format as `tune scale stdin`
+    pitches: Vec<String>,
+}
+
+impl FitOptions {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        let root_key = PianoKey::from_midi_number(0);
+        let scale_dto = match self.pitches.is_empty() {
+            true => ScaleDto::read_yaml_or_pitch_list(app.read(), root_key)?,
+            false => ScaleDto::from_pitch_list(&self.pitches.join("\n"), root_key)?,
+        };
+
+        let pitches: Vec<_> = scale_dto
+            .items
+            .iter()
+            .map(|item| Pitch::from_hz(item.pitch_in_hz))
+            .collect();
+        let root_pitch = match pitches.first() {
+            Some(&root_pitch) => root_pitch,
+            None => return Err("No pitches given".to_owned().into()),
+        };
+        let measured: Vec<_> = pitches
+            .iter()
+            .map(|&pitch| Ratio::between_pitches(root_pitch, pitch).as_cents())
+            .collect();
+
+        let period = self.period.as_cents();
+        let tolerance = self.tolerance.as_cents();
+
+        let equal_temperament = select_simplest(
+            tolerance,
+            (1..=self.max_steps).map(|num_steps| {
+                let step_size = period / f64::from(num_steps);
+                let fit = Fit::new(&measured, |cents| {
+                    let degree = (cents / step_size).round();
+                    (degree * step_size, format!("{degree:>4}"))
+                });
+                (num_steps, fit)
+            }),
+        );
+
+        let generator = select_simplest(
+            tolerance,
+            (1..=self.max_generations).map(|max_generations| {
+                let (generator, fit) = fit_generator(&measured, period, max_generations);
+                ((generator, max_generations), fit)
+            }),
+        );
+
+        if let Some((num_steps, fit)) = equal_temperament {
+            app.writeln(format_args!(
+                "==== Best equal temperament: {num_steps}-ED{} ====",
+                format_period(self.period)
+            ))?;
+            app.writeln(format_args!(
+                "- step size: {:+.3}c",
+                period / f64::from(num_steps)
+            ))?;
+            fit.print(app, &pitches, "step")?;
+        }
+
+        if let Some(((generator, max_generations), fit)) = generator {
+            app.writeln(format_args!("==== Best generator: {generator:+.3}c ===="))?;
+            app.writeln(format_args!("- period: {period:+.3}c"))?;
+            app.writeln(format_args!(
+                "- generations: -{max_generations}..+{max_generations}"
+            ))?;
+            fit.print(app, &pitches, "gen")?;
+        }
+
+        for scl_file_location in &self.scl_file_locations {
+            let scl = shared::import_scl_file(scl_file_location)?;
+            let fit = Fit::new(&measured, |cents| nearest_scl_degree(&scl, cents));
+
+            app.writeln(format_args!(
+                "==== {} ({}) ====",
+                scl_file_location.display(),
+                scl.description()
+            ))?;
+            fit.print(app, &pitches, "deg")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the first candidate whose RMS deviation is within `tolerance` or, if no candidate fits, the candidate with the smallest RMS deviation.
+fn select_simplest<T>(
+    tolerance: f64,
+    candidates: impl IntoIterator<Item = (T, Fit)>,
+) -> Option<(T, Fit)> {
+    let mut best: Option<(T, Fit)> = None;
+    for (candidate, fit) in candidates {
+        if fit.rms_deviation <= tolerance {
+            return Some((candidate, fit));
+        }
+        if best
+            .as_ref()
+            .map(|(_, best_fit)| fit.rms_deviation < best_fit.rms_deviation)
+            .unwrap_or(true)
+        {
+            best = Some((candidate, fit));
+        }
+    }
+    best
+}
+
+/// Finds the generator that explains the measured pitches best using at most `max_generations` generations in each direction.
+fn fit_generator(measured: &[f64], period: f64, max_generations: u16) -> (f64, Fit) {
+    let max_generations = i32::from(max_generations);
+    let fit_for = |generator: f64| {
+        Fit::new(measured, |cents| {
+            (-max_generations..=max_generations)
+                .map(|generation| {
+                    let position = (f64::from(generation) * generator).rem_euclid(period);
+                    let target = position + ((cents - position) / period).round() * period;
+                    (target, format!("{generation:>+4}"))
+                })
+                .min_by(|(a, _), (b, _)| total_cmp((cents - a).abs(), (cents - b).abs()))
+                .unwrap()
+        })
+    };
+
+    let search = |from: f64, to: f64, resolution: f64| {
+        let num_steps = ((to - from) / resolution).round() as u32;
+        (0..=num_steps)
+            .map(|step| from + f64::from(step) * resolution)
+            .map(|generator| (generator, fit_for(generator)))
+            .min_by(|(_, a), (_, b)| total_cmp(a.rms_deviation, b.rms_deviation))
+            .unwrap()
+    };
+
+    let (coarse_generator, _) = search(
+        GENERATOR_GRID_IN_CENTS,
+        period / 2.0,
+        GENERATOR_GRID_IN_CENTS,
+    );
+    search(
+        coarse_generator - GENERATOR_GRID_IN_CENTS,
+        coarse_generator + GENERATOR_GRID_IN_CENTS,
+        GENERATOR_REFINEMENT_IN_CENTS,
+    )
+}
+
+fn nearest_scl_degree(scl: &Scl, cents: f64) -> (f64, String) {
+    let num_items = i32::from(scl.num_items());
+    let num_periods = (cents / scl.period().as_cents()).floor() as i32;
+
+    ((num_periods - 1) * num_items..=(num_periods + 2) * num_items)
+        .map(|degree| (scl.relative_pitch_of(degree).as_cents(), degree))
+        .min_by(|(a, _), (b, _)| total_cmp((cents - a).abs(), (cents - b).abs()))
+        .map(|(target, degree)| (target, format!("{degree:>4}")))
+        .unwrap()
+}
+
+fn parse_period(src: &str) -> Result<Ratio, String> {
+    let period = src.parse::<Ratio>()?;
+    if period > Ratio::default() {
+        Ok(period)
+    } else {
+        Err("The period must be greater than 1/1".to_owned())
+    }
+}
+
+/// Same as [`f64::total_cmp`] which is not available in Rust 1.61. Unlike `partial_cmp`, it does not panic on NaN values.
+fn total_cmp(a: f64, b: f64) -> Ordering {
+    Ratio::from_float(a).total_cmp(&Ratio::from_float(b))
+}
+
+fn format_period(period: Ratio) -> String {
+    match period.as_float() {
+        float if (float - 2.0).abs() < 1e-9 => "O".to_owned(),
+        float if (float - 3.0).abs() < 1e-9 => "T".to_owned(),
+        _ => format!(" {:.1}c", period.as_cents()),
+    }
+}
+
+/// Assignment of the measured pitches to the nearest pitches of a candidate tuning.
+///
+/// The candidate tuning is shifted by a common offset s.t. the RMS deviation becomes minimal.
+struct Fit {
+    offset: f64,
+    rms_deviation: f64,
+    targets: Vec<(f64, String)>,
+}
+
+impl Fit {
+    /// `find_nearest` returns the nearest pitch of the candidate tuning (in cents above the first measured pitch) and a label, e.g. the scale degree.
+    fn new(measured: &[f64], mut find_nearest: impl FnMut(f64) -> (f64, String)) -> Self {
+        let mut offset = 0.0;
+        let mut targets = Vec::new();
+
+        // Assign the targets and center the deviations around 0. Repeat once in case the shift changes the assignment.
+        for _ in 0..2 {
+            targets = measured
+                .iter()
+                .map(|&cents| find_nearest(cents - offset))
+                .collect();
+            offset = measured
+                .iter()
+                .zip(&targets)
+                .map(|(cents, (target, _))| cents - target)
+                .sum::<f64>()
+                / measured.len() as f64;
+        }
+
+        let rms_deviation = (measured
+            .iter()
+            .zip(&targets)
+            .map(|(cents, (target, _))| (cents - offset - target).powi(2))
+            .sum::<f64>()
+            / measured.len() as f64)
+            .sqrt();
+
+        Self {
+            offset,
+            rms_deviation,
+            targets,
+        }
+    }
+
+    fn print(&self, app: &mut App, pitches: &[Pitch], label_header: &str) -> CliResult<()> {
+        app.writeln(format_args!("- offset: {:+.3}c", self.offset))?;
+        app.writeln(format_args!("- RMS deviation: {:.3}c", self.rms_deviation))?;
+        app.writeln(format_args!(
+            "{:>12} | {label_header:>4} | {:>10} | {:>9}",
+            "measured", "target", "deviation"
+        ))?;
+
+        let root_pitch = pitches[0];
+        for (&pitch, (target, label)) in pitches.iter().zip(&self.targets) {
+            let target_pitch = root_pitch * Ratio::from_cents(target + self.offset);
+            app.writeln(format_args!(
+                "{:>9.3} Hz | {label} | {:>7.3} Hz | {:>+8.3}c",
+                pitch.as_hz(),
+                target_pitch.as_hz(),
+                Ratio::between_pitches(target_pitch, pitch).as_cents()
+            ))?;
+        }
+
+        app.writeln("")?;
+
+        Ok(())
+    }
+}
@@ -1,28 +1,41 @@
+pub mod api;
+
+mod chart;
+mod devices;
 mod dto;
 mod est;
+mod fit;
 mod live;
+mod lumatone;
 mod mos;
 mod mts;
+mod output;
+mod report;
 mod scala;
 mod scale;
+mod watch;
 
 use std::{
     fmt::{self, Debug, Display},
-    fs::File,
     io::{self, Write},
     path::PathBuf,
 };
 
 use clap::Parser;
+use devices::DevicesOptions;
 use est::EstOptions;
+use fit::FitOptions;
 use io::Read;
 use live::LiveOptions;
+use lumatone::LumatoneOptions;
 use mos::MosCommand;
 use mts::MtsOptions;
+use output::{Output, OutputFiles};
 use scala::{KbmCommand, SclOptions};
-use scale::{DiffOptions, DumpOptions, ScaleCommand};
-use shared::midi;
+use scale::{DiffOptions, DumpOptions, ScaleCommand, ScaleOptions};
+use shared::midi::{MidiBackend, Midir};
 use tune::scala::{KbmBuildError, SclBuildError};
+use watch::WatchOptions;
 
 #[doc(hidden)]
 pub mod shared;
@@ -30,10 +43,16 @@ pub mod shared;
 #[derive(Parser)]
 #[command(version)]
 struct MainOptions {
-    /// Write output to a file instead of stdout
+    /// Write output to a file instead of stdout.
+    /// Commands that create multiple files (e.g. `scale --format scl-kbm`) replace {ext} with the extension of each file, e.g. my_tuning.{ext}.
+    /// The files are only written if the command succeeds
     #[arg(long = "of")]
     output_file: Option<PathBuf>,
 
+    /// Overwrite existing output files
+    #[arg(long = "force", requires = "output_file")]
+    force: bool,
+
     #[command(subcommand)]
     command: MainCommand,
 }
@@ -52,13 +71,17 @@ enum MainCommand {
     #[command(name = "est")]
     Est(EstOptions),
 
+    /// Find the equal temperament, generator or scale that best matches a list of measured frequencies
+    #[command(name = "fit")]
+    Fit(FitOptions),
+
     /// Find MOS scales from generators or vice versa
     #[command(subcommand, name = "mos")]
     Mos(MosCommand),
 
     /// Print a scale to stdout
-    #[command(subcommand, name = "scale")]
-    Scale(ScaleCommand),
+    #[command(name = "scale")]
+    Scale(ScaleOptions),
 
     /// Display details of a scale
     #[command(name = "dump")]
@@ -68,6 +91,10 @@ enum MainCommand {
     #[command(name = "diff")]
     Diff(DiffOptions),
 
+    /// Generate a Lumatone preset (.ltn) with an isomorphic layout for the given scale
+    #[command(name = "lumatone")]
+    Lumatone(LumatoneOptions),
+
     /// Print MIDI Tuning Standard messages and/or send them to MIDI devices
     #[command(name = "mts")]
     Mts(MtsOptions),
@@ -78,48 +105,76 @@ enum MainCommand {
     #[command(name = "live")]
     Live(LiveOptions),
 
-    /// List MIDI devices
+    /// List MIDI devices or probe their identity
     #[command(name = "devices")]
-    Devices,
+    Devices(DevicesOptions),
+
+    /// Re-run a command whenever one of its input files (e.g. scl or kbm files) changes.
+    /// The output files given via --of are rewritten on each run
+    #[command(name = "watch")]
+    Watch(WatchOptions),
 }
 
 impl MainOptions {
-    fn run(self) -> Result<(), CliError> {
-        let output: Box<dyn Write> = match self.output_file {
-            Some(output_file) => Box::new(File::create(output_file)?),
-            None => Box::new(io::stdout()),
-        };
-
-        let mut app = App {
-            input: Box::new(io::stdin()),
-            output,
-            error: Box::new(io::stderr()),
-        };
-
-        self.command.run(&mut app)
+    fn run(self, command_line: String) -> Result<(), CliError> {
+        match self.command {
+            MainCommand::Watch(options) => options.run(self.output_file, self.force),
+            command => run_with_stdio(command, self.output_file, self.force, command_line),
+        }
     }
 }
 
+fn run_with_stdio(
+    command: MainCommand,
+    output_file: Option<PathBuf>,
+    force: bool,
+    command_line: String,
+) -> CliResult<()> {
+    let output = match output_file {
+        Some(output_file) => Output::Files(OutputFiles::new(output_file, force)),
+        None => Output::Stream(Box::new(io::stdout())),
+    };
+
+    let mut app = App {
+        input: Box::new(io::stdin()),
+        output,
+        error: Box::new(io::stderr()),
+        midi: Box::new(Midir::new("tune-cli")),
+        command_line,
+    };
+
+    command.run(&mut app)?;
+    Ok(app.output.commit()?)
+}
+
 impl MainCommand {
     fn run(self, app: &mut App) -> CliResult<()> {
         match self {
             MainCommand::Scl(options) => options.run(app)?,
             MainCommand::Kbm(options) => options.run(app)?,
             MainCommand::Est(options) => options.run(app)?,
+            MainCommand::Fit(options) => options.run(app)?,
             MainCommand::Mos(options) => options.run(app)?,
             MainCommand::Scale(options) => options.run(app)?,
             MainCommand::Dump(options) => options.run(app)?,
             MainCommand::Diff(options) => options.run(app)?,
+            MainCommand::Lumatone(options) => options.run(app)?,
             MainCommand::Mts(options) => options.run(app)?,
             MainCommand::Live(options) => options.run(app)?,
-            MainCommand::Devices => midi::print_midi_devices(&mut app.output, "tune-cli")?,
+            MainCommand::Devices(options) => options.run(app)?,
+            MainCommand::Watch(_) => {
+                return Err(CliError::CommandError(
+                    "The watch command is not available in this environment".to_owned(),
+                ))
+            }
         }
         Ok(())
     }
 }
 
 pub fn run_in_shell_env(args: impl IntoIterator<Item = String>) -> CliResult<()> {
-    let options = match MainOptions::try_parse_from(args) {
+    let args: Vec<_> = args.into_iter().collect();
+    let options = match MainOptions::try_parse_from(&args) {
         Err(err) => {
             return if err.use_stderr() {
                 Err(CliError::CommandError(err.to_string()))
@@ -131,16 +186,21 @@ pub fn run_in_shell_env(args: impl IntoIterator<Item = String>) -> CliResult<()>
         Ok(options) => options,
     };
 
-    options.run()
+    options.run(format_command_line(&args))
 }
 
-pub fn run_in_wasm_env(
+/// Runs the command given by `args` with MIDI devices accessed via the given [`MidiBackend`], e.g. [`shared::midi::WebMidi`] when the `wasm` feature is enabled.
+///
+/// With the `wasm` feature, `devices probe` waits for the identity reply asynchronously s.t. the event loop of the browser can deliver it.
+pub async fn run_in_wasm_env(
     args: impl IntoIterator<Item = String>,
     input: impl Read,
     mut output: impl Write,
     error: impl Write,
+    midi: impl MidiBackend,
 ) -> CliResult<()> {
-    let command = match MainCommand::try_parse_from(args) {
+    let args: Vec<_> = args.into_iter().collect();
+    let command = match MainCommand::try_parse_from(&args) {
         Err(err) => {
             return if err.use_stderr() {
                 Err(CliError::CommandError(err.to_string()))
@@ -154,17 +214,40 @@ pub fn run_in_wasm_env(
 
     let mut app = App {
         input: Box::new(input),
-        output: Box::new(output),
+        output: Output::Stream(Box::new(output)),
         error: Box::new(error),
+        midi: Box::new(midi),
+        command_line: format_command_line(&args),
     };
 
-    command.run(&mut app)
+    match command {
+        #[cfg(feature = "wasm")]
+        MainCommand::Devices(options) => options.run_async(&mut app).await?,
+        command => command.run(&mut app)?,
+    }
+    Ok(app.output.commit()?)
+}
+
+/// Reconstructs the command line for provenance records. The binary name is normalized to `tune` s.t. the record does not depend on the installation path.
+fn format_command_line(args: &[String]) -> String {
+    let mut command_line = "tune".to_owned();
+    for arg in args.iter().skip(1) {
+        command_line.push(' ');
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            command_line.push_str(&format!("'{arg}'"));
+        } else {
+            command_line.push_str(arg);
+        }
+    }
+    command_line
 }
 
 struct App<'a> {
     input: Box<dyn 'a + Read>,
-    output: Box<dyn 'a + Write>,
+    output: Output<'a>,
     error: Box<dyn 'a + Write>,
+    midi: Box<dyn 'a + MidiBackend>,
+    command_line: String,
 }
 
 impl App<'_> {
@@ -176,6 +259,11 @@ impl App<'_> {
         writeln!(self.output, "{message}")
     }
 
+    /// Writes an additional file with the given extension, e.g. the kbm file accompanying an scl file.
+    pub fn write_artifact(&mut self, extension: &str, content: impl Display) -> io::Result<()> {
+        self.output.write_artifact(extension, content)
+    }
+
     pub fn errln(&mut self, message: impl Display) -> io::Result<()> {
         writeln!(self.error, "{message}")
     }
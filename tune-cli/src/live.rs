@@ -1,14 +1,35 @@
-use std::{mem, sync::mpsc};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    mem,
+    net::SocketAddr,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use midir::MidiInputConnection;
 use tune::{
+    key::PianoKey,
     midi::{ChannelMessage, ChannelMessageType},
-    tuner::{AotTuner, JitTuner, MidiTarget, MidiTunerMessageHandler, PoolingMode},
+    pitch::{Pitch, Pitched},
+    tuner::{
+        AotTuner, AotTuningModel, ChannelDetuning, Group, JitTuner, MidiTarget, MidiTunerMessage,
+        MidiTunerMessageHandler, PoolingMode, TunableMidi, TunableSynth,
+    },
+    tuning::KeyboardMapping,
 };
 
 use crate::{
-    shared::midi::{self, MidiInArgs, MidiOutArgs, MidiSource, MultiChannelOffset, TuningMethod},
+    scale::Scale,
+    shared::{
+        self,
+        midi::{self, MidiInArgs, MidiOutArgs, MidiSource, MultiChannelOffset, TuningMethod},
+        mts::MtsClient,
+        sync::SyncArgs,
+        TuningFiles,
+    },
     App, CliResult, ScaleCommand,
 };
 
@@ -28,10 +49,153 @@ pub(crate) struct LiveOptions {
     #[command(flatten)]
     midi_out_args: MidiOutArgs,
 
+    #[command(flatten)]
+    tuning_switch_args: TuningSwitchArgs,
+
+    /// Describes how incoming channel pressure messages are routed to the output channels.
+    /// [all] Send to all output channels.
+    /// [follow] Send to the output channels of the held notes only.
+    /// [drop] Do not forward.
+    #[arg(long = "ch-pressure", value_enum, default_value = "all")]
+    channel_pressure_policy: PressurePolicy,
+
+    /// Describes how incoming polyphonic key pressure messages are routed to the output channels.
+    /// [all] Send as channel pressure to all output channels.
+    /// [follow] Send to the output channel of the affected note only.
+    /// [drop] Do not forward.
+    #[arg(long = "poly-pressure", value_enum, default_value = "follow")]
+    poly_pressure_policy: PressurePolicy,
+
+    /// Output channel outside of the retuning group with an independent tuning, given as <channel>:<scl-file>,<kbm-file>, e.g. 0:22-edo.scl,22-edo.kbm.
+    /// Use <channel>:thru to leave the channel untouched, e.g. 9:thru for the drum channel.
+    /// Can be specified multiple times.
+    #[arg(long = "chan-tuning", value_parser = parse_channel_tuning)]
+    channel_tunings: Vec<ChannelTuning>,
+
+    /// Routes the channel messages of an input channel to an independently tuned output channel instead of the retuning group, given as <in-chan>:<out-chan>, e.g. 9:9 for the drum channel.
+    /// The output channel must be declared with --chan-tuning. Routed channels are not affected by tuning switches.
+    /// Can be specified multiple times.
+    #[arg(long = "route", value_parser = parse_channel_route)]
+    channel_routes: Vec<ChannelRoute>,
+
     #[command(subcommand)]
     mode: LiveMode,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum PressurePolicy {
+    All,
+    Follow,
+    Drop,
+}
+
+/// Tuning of a single output channel that does not belong to the retuning group.
+#[derive(Clone, Debug)]
+struct ChannelTuning {
+    channel: u8,
+    /// [`None`] if the channel should not be retuned at all.
+    tuning_files: Option<TuningFiles>,
+}
+
+fn parse_channel_tuning(src: &str) -> Result<ChannelTuning, String> {
+    let (channel, tuning) = src.split_once(':').ok_or(
+        "Invalid channel tuning. Should be <channel>:<scl-file>,<kbm-file> or <channel>:thru",
+    )?;
+    let channel = midi::parse_channel(channel)?;
+    let tuning_files = match tuning {
+        "thru" => None,
+        tuning => Some(shared::parse_tuning_files(tuning)?),
+    };
+    Ok(ChannelTuning {
+        channel,
+        tuning_files,
+    })
+}
+
+/// Ensures that the independently tuned output channels neither belong to the retuning group nor are tuned more than once.
+fn check_channel_tunings(
+    channel_tunings: &[ChannelTuning],
+    group_channels: &[u8],
+) -> CliResult<()> {
+    let mut independent_channels = BTreeSet::new();
+    for channel_tuning in channel_tunings {
+        let channel = channel_tuning.channel;
+        if group_channels.contains(&channel) {
+            return Err(format!(
+                "Output channel {channel} cannot be tuned independently since it belongs to the retuning group"
+            )
+            .into());
+        }
+        if !independent_channels.insert(channel) {
+            return Err(format!("Output channel {channel} is tuned more than once").into());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+struct ChannelRoute {
+    in_channel: u8,
+    out_channel: u8,
+}
+
+fn parse_channel_route(src: &str) -> Result<ChannelRoute, String> {
+    let (in_channel, out_channel) = src
+        .split_once(':')
+        .ok_or("Invalid route. Should be <in-chan>:<out-chan>")?;
+    Ok(ChannelRoute {
+        in_channel: midi::parse_channel(in_channel)?,
+        out_channel: midi::parse_channel(out_channel)?,
+    })
+}
+
+#[derive(Clone, Parser)]
+struct TuningSwitchArgs {
+    /// Additional tuning that can be selected at runtime, given as scl and kbm file location separated by a comma, e.g. 31-edo.scl,31-edo.kbm.
+    /// The tuning given by the scale subcommand has number 0, the additional tunings are numbered in the order they are specified.
+    #[arg(long = "alt-tuning", value_parser = shared::parse_tuning_files)]
+    alt_tunings: Vec<TuningFiles>,
+
+    /// Select the tuning with incoming program change messages. Program n selects tuning n. The messages are not forwarded
+    #[arg(long = "switch-pg")]
+    switch_with_program_change: bool,
+
+    /// Select the tuning with incoming control change messages of the given controller. Value n selects tuning n. The messages are not forwarded
+    #[arg(long = "switch-ccn")]
+    switch_ccn: Option<u8>,
+}
+
+impl TuningSwitchArgs {
+    fn load_tunings(&self, app: &mut App, scale: Scale) -> CliResult<Vec<Scale>> {
+        let mut tunings = vec![scale];
+        for tuning_files in &self.alt_tunings {
+            app.writeln(format_args!(
+                "Tuning {}: {} with {}",
+                tunings.len(),
+                tuning_files.scl_file_location.display(),
+                tuning_files.kbm_file_location.display()
+            ))?;
+            tunings.push(tuning_files.load()?);
+        }
+        Ok(tunings)
+    }
+
+    /// Returns the number of the tuning selected by the given message or [`None`] if the message should be forwarded.
+    fn get_selected_tuning(&self, message_type: ChannelMessageType) -> Option<usize> {
+        match message_type {
+            ChannelMessageType::ProgramChange { program } if self.switch_with_program_change => {
+                Some(usize::from(program))
+            }
+            ChannelMessageType::ControlChange { controller, value }
+                if Some(controller) == self.switch_ccn =>
+            {
+                Some(usize::from(value))
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Parser)]
 enum LiveMode {
     /// Just-in-time: Tracks which notes are active and injects tuning messages into the stream of MIDI events.
@@ -57,6 +221,19 @@ struct JustInTimeOptions {
     #[arg(long = "clash", default_value = "stop", value_parser = parse_mitigation)]
     clash_mitigation: PoolingMode,
 
+    /// Print a report to stderr whenever a note exceeds the available channels, including the key, the note and detuning it is played with and the applied clash mitigation
+    #[arg(long = "monitor")]
+    monitor: bool,
+
+    /// Follow the tuning updates of other instances (e.g. microwave --sync-peer) received at the given local address
+    #[arg(long = "sync-bind")]
+    sync_bind_address: Option<SocketAddr>,
+
+    /// Adopt the tunings received as MTS SysEx messages (e.g. from a DAW or a master keyboard) on the MIDI input.
+    /// Not available in aot mode since aot checks its tunings at startup
+    #[arg(long = "mts-in")]
+    adopt_mts: bool,
+
     /// MIDI-out tuning method
     #[arg(value_enum)]
     method: TuningMethod,
@@ -86,18 +263,51 @@ struct AheadOfTimeOptions {
 
 impl LiveOptions {
     pub fn run(&self, app: &mut App) -> CliResult<()> {
-        let (send, recv) = mpsc::channel();
-        let handler = move |message| send.send(message).unwrap();
+        let (send, recv) = mpsc::channel::<Vec<u8>>();
+        let forward = send.clone();
+        let create_handler = || {
+            let send = send.clone();
+            move |message: MidiTunerMessage| {
+                message.send_to(|message| send.send(message.to_vec()).unwrap())
+            }
+        };
 
         let source = self.midi_in_args.get_midi_source()?;
-        let target = self.midi_out_args.get_midi_target(handler)?;
+        // The ahead-of-time tunings are loaded first s.t. the auto tuning method can fall back to a method with enough channels
+        let (method, aot_tunings) = match &self.mode {
+            LiveMode::JustInTime(options) => (
+                self.midi_out_args.resolve_tuning_method(options.method),
+                Vec::new(),
+            ),
+            LiveMode::AheadOfTime(options) => {
+                let scale = options.scale.to_scale(app)?;
+                let tunings = self.tuning_switch_args.load_tunings(app, scale)?;
+                (options.select_tuning_method(&tunings, self)?, tunings)
+            }
+        };
+        let target = self
+            .midi_out_args
+            .get_midi_target(create_handler(), method)?;
+        check_channel_tunings(&self.channel_tunings, &target.channels)?;
+        let router = self.create_router(app, method, create_handler, forward.clone())?;
 
         let in_chans = source.channels.clone();
         let out_chans = target.channels.clone();
 
         let (in_device, in_connection) = match &self.mode {
-            LiveMode::JustInTime(options) => options.run(app, source, target, self)?,
-            LiveMode::AheadOfTime(options) => options.run(app, source, target, self)?,
+            LiveMode::JustInTime(options) => {
+                options.run(app, source, target, router, forward, self)?
+            }
+            LiveMode::AheadOfTime(options) => options.run(
+                app,
+                aot_tunings,
+                method,
+                source,
+                target,
+                router,
+                forward,
+                self,
+            )?,
         };
 
         let (out_device, mut out_connection) =
@@ -105,6 +315,7 @@ impl LiveOptions {
 
         app.writeln(format_args!("Receiving MIDI data from {in_device}"))?;
         app.writeln(format_args!("Sending MIDI data to {out_device}"))?;
+        app.writeln(format_args!("Tuning method: {}", method.name()))?;
         app.writeln(format_args!(
             "in-channels {{{}}} -> out-channels {{{}}}",
             in_chans
@@ -120,13 +331,144 @@ impl LiveOptions {
         ))?;
 
         for message in recv {
-            message.send_to(|message| out_connection.send(message).unwrap());
+            out_connection.send(&message).unwrap();
         }
 
         mem::drop(in_connection);
 
         Ok(())
     }
+
+    fn create_router<H: MidiTunerMessageHandler>(
+        &self,
+        app: &mut App,
+        method: TuningMethod,
+        mut create_handler: impl FnMut() -> H,
+        forward: Sender<Vec<u8>>,
+    ) -> CliResult<ChannelRouter<H>> {
+        let mut layers = HashMap::new();
+        for channel_tuning in &self.channel_tunings {
+            let channel = channel_tuning.channel;
+            let layer = match &channel_tuning.tuning_files {
+                Some(tuning_files) => {
+                    app.writeln(format_args!(
+                        "Out-channel {channel}: {} with {}",
+                        tuning_files.scl_file_location.display(),
+                        tuning_files.kbm_file_location.display()
+                    ))?;
+                    let scale = tuning_files.load()?;
+                    let synth =
+                        self.midi_out_args
+                            .create_channel_synth(create_handler(), channel, method);
+                    let mut tuner = AotTuner::start(synth);
+                    let keys = scale.keys.iter().copied();
+                    if !tuner
+                        .set_single_channel_tuning(&*scale.tuning, keys.clone())
+                        .unwrap()
+                    {
+                        tuner.set_tuning(&*scale.tuning, keys).unwrap();
+                    }
+                    if !tuner.tuned() {
+                        let mut message = format!("Out-channel {channel} cannot be tuned independently. The following keys cannot be tuned:\n");
+                        midi::write_untunable_keys(
+                            &mut message,
+                            tuner.untunable_keys(),
+                            usize::MAX,
+                            |key| format!("key {}", key.midi_number()),
+                        )
+                        .unwrap();
+                        return Err(message.trim_end().to_owned().into());
+                    }
+                    ChannelLayer::Tuned(tuner)
+                }
+                None => {
+                    app.writeln(format_args!("Out-channel {channel}: untouched"))?;
+                    ChannelLayer::Thru
+                }
+            };
+            layers.insert(channel, layer);
+        }
+
+        let mut routes = HashMap::new();
+        for route in &self.channel_routes {
+            if !layers.contains_key(&route.out_channel) {
+                return Err(format!(
+                    "Cannot route to out-channel {} since it is not declared with --chan-tuning",
+                    route.out_channel
+                )
+                .into());
+            }
+            if routes.insert(route.in_channel, route.out_channel).is_some() {
+                return Err(
+                    format!("In-channel {} is routed more than once", route.in_channel).into(),
+                );
+            }
+            app.writeln(format_args!(
+                "in-channel {} -> out-channel {}",
+                route.in_channel, route.out_channel
+            ))?;
+        }
+
+        Ok(ChannelRouter {
+            routes,
+            layers,
+            forward,
+        })
+    }
+}
+
+/// Sends the channel messages of routed input channels to the output channels given by [`LiveOptions::channel_tunings`], bypassing the retuning group.
+struct ChannelRouter<H> {
+    routes: HashMap<u8, u8>,
+    layers: HashMap<u8, ChannelLayer<H>>,
+    forward: Sender<Vec<u8>>,
+}
+
+enum ChannelLayer<H> {
+    Thru,
+    Tuned(AotTuner<PianoKey, TunableMidi<H>>),
+}
+
+impl<H: MidiTunerMessageHandler> ChannelRouter<H> {
+    /// Returns whether the message was consumed because its input channel is routed.
+    fn route(&mut self, in_channel: u8, message_type: ChannelMessageType) -> bool {
+        let out_channel = match self.routes.get(&in_channel) {
+            Some(&out_channel) => out_channel,
+            None => return false,
+        };
+
+        match self.layers.get_mut(&out_channel).unwrap() {
+            ChannelLayer::Thru => {
+                let message = message_type.in_channel(out_channel).unwrap();
+                self.forward
+                    .send(message.to_raw_message().to_vec())
+                    .unwrap();
+            }
+            ChannelLayer::Tuned(tuner) => match message_type {
+                ChannelMessageType::NoteOff { key, velocity }
+                | ChannelMessageType::NoteOn {
+                    key,
+                    velocity: velocity @ 0,
+                } => {
+                    tuner.note_off(PianoKey::from_midi_number(key), velocity);
+                }
+                ChannelMessageType::NoteOn { key, velocity } => {
+                    tuner.note_on(PianoKey::from_midi_number(key), velocity);
+                }
+                ChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
+                    tuner.note_attr(PianoKey::from_midi_number(key), pressure);
+                }
+                ChannelMessageType::ControlChange { .. }
+                | ChannelMessageType::ProgramChange { .. }
+                | ChannelMessageType::ChannelPressure { .. }
+                | ChannelMessageType::PitchBendChange { .. } => {
+                    tuner.global_attr(message_type);
+                }
+            },
+        }
+
+        true
+    }
 }
 
 impl JustInTimeOptions {
@@ -135,106 +477,334 @@ impl JustInTimeOptions {
         app: &mut App,
         source: MidiSource,
         target: MidiTarget<impl MidiTunerMessageHandler + Send + 'static>,
+        router: ChannelRouter<impl MidiTunerMessageHandler + Send + 'static>,
+        forward: Sender<Vec<u8>>,
         options: &LiveOptions,
     ) -> CliResult<(String, MidiInputConnection<()>)> {
-        let tuning = self.scale.to_scale(app)?.tuning;
+        let scale = self.scale.to_scale(app)?;
+        let tunings = options.tuning_switch_args.load_tunings(app, scale)?;
+        let selection = Arc::new(Mutex::new(JitTuningSelection {
+            tunings,
+            selected: 0,
+            synced: None,
+        }));
+
+        let sync_args = SyncArgs {
+            bind_address: self.sync_bind_address,
+            peer_addresses: Vec::new(),
+        };
+        if let Some(tuning_sync) = sync_args.connect()? {
+            app.writeln(format_args!(
+                "Receiving tuning updates at {}",
+                tuning_sync.local_addr()?
+            ))?;
+            let selection = selection.clone();
+            tuning_sync.listen(move |scl, kbm| {
+                selection.lock().unwrap().synced = Some(Box::new((scl, kbm)));
+            });
+        }
+
+        if self.adopt_mts {
+            app.writeln("Adopting tunings received via MTS")?;
+        }
+        let mut mts_client = self.adopt_mts.then(MtsClient::default);
+        let mts_selection = selection.clone();
+        let on_sysex = move |message: &[u8]| match &mut mts_client {
+            Some(mts_client) => mts_client
+                .process_raw_message(message, |scl, kbm_root| {
+                    mts_selection.lock().unwrap().synced = Some(Box::new((scl, kbm_root.to_kbm())));
+                })
+                .unwrap_or_else(|err| {
+                    // The performance continues with the previous tuning
+                    eprintln!("[WARNING] Could not adopt MTS tuning: {err:?}");
+                    true
+                }),
+            None => false,
+        };
 
         let synth = options.midi_out_args.create_synth(target, self.method);
+        let num_channels = synth.num_channels();
         let mut tuner = JitTuner::start(synth, self.clash_mitigation);
+        let monitor = self.monitor;
+        let clash_mitigation = self.clash_mitigation;
+
+        let tuning_switch_args = options.tuning_switch_args.clone();
+        let channel_pressure_policy = options.channel_pressure_policy;
+        let poly_pressure_policy = options.poly_pressure_policy;
+        let mut pressed_keys = HashSet::new();
 
         connect_to_in_device(
             &options.midi_in_device,
             source,
+            router,
+            forward,
+            on_sysex,
             move |message_type, offset| match message_type {
                 ChannelMessageType::NoteOff { key, velocity }
                 | ChannelMessageType::NoteOn {
                     key,
                     velocity: velocity @ 0,
                 } => {
-                    let piano_key = offset.get_piano_key(key);
-                    tuner.note_off(piano_key, velocity);
+                    if let Some(piano_key) = offset.get_piano_key(key) {
+                        pressed_keys.remove(&piano_key);
+                        tuner.note_off(piano_key, velocity);
+                    }
                 }
                 ChannelMessageType::NoteOn { key, velocity } => {
-                    let piano_key = offset.get_piano_key(key);
-                    if let Some(pitch) = tuning.maybe_pitch_of(piano_key) {
-                        tuner.note_on(piano_key, pitch, velocity);
+                    if let Some(piano_key) = offset.get_piano_key(key) {
+                        if let Some(pitch) =
+                            selection.lock().unwrap().tuning().maybe_pitch_of(piano_key)
+                        {
+                            if monitor && tuner.channels_exhausted(pitch) {
+                                report_clash(piano_key, pitch, num_channels, clash_mitigation);
+                            }
+                            pressed_keys.insert(piano_key);
+                            tuner.note_on(piano_key, pitch, velocity);
+                        }
                     }
                 }
                 ChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
-                    let piano_key = offset.get_piano_key(key);
-                    tuner.note_attr(piano_key, pressure);
+                    match poly_pressure_policy {
+                        PressurePolicy::All => {
+                            tuner.global_attr(ChannelMessageType::ChannelPressure { pressure });
+                        }
+                        PressurePolicy::Follow => {
+                            if let Some(piano_key) = offset.get_piano_key(key) {
+                                tuner.note_attr(piano_key, pressure);
+                            }
+                        }
+                        PressurePolicy::Drop => {}
+                    }
                 }
+                ChannelMessageType::ChannelPressure { .. } => match channel_pressure_policy {
+                    PressurePolicy::All => {
+                        tuner.global_attr(message_type);
+                    }
+                    PressurePolicy::Follow => {
+                        tuner.notes_channel_attr(pressed_keys.iter().copied(), message_type);
+                    }
+                    PressurePolicy::Drop => {}
+                },
                 message_type @ (ChannelMessageType::ControlChange { .. }
                 | ChannelMessageType::ProgramChange { .. }
-                | ChannelMessageType::ChannelPressure { .. }
                 | ChannelMessageType::PitchBendChange { .. }) => {
-                    tuner.global_attr(message_type);
+                    match tuning_switch_args.get_selected_tuning(message_type) {
+                        // Sounding notes keep their pitch until they are released
+                        Some(selected) => selection.lock().unwrap().select(selected),
+                        None => {
+                            tuner.global_attr(message_type);
+                        }
+                    }
                 }
             },
         )
     }
 }
 
+fn report_clash(
+    piano_key: PianoKey,
+    pitch: Pitch,
+    num_channels: usize,
+    clash_mitigation: PoolingMode,
+) {
+    let approximation = pitch.find_in_tuning(());
+    let consequence = match clash_mitigation {
+        PoolingMode::Block => "The new note is blocked",
+        PoolingMode::Stop => "The oldest note is stopped",
+        PoolingMode::Ignore => "The oldest note is retuned",
+    };
+    eprintln!(
+        "[MONITOR] Key {}: {} {:+.1}c exceeds the {num_channels} available channels. {consequence}.",
+        piano_key.midi_number(),
+        approximation.approx_value,
+        approximation.deviation.as_cents(),
+    );
+}
+
+/// The tunings available to the [`JitTuner`]. A tuning received from another instance or via MTS overrides the selected tuning until the next tuning is selected.
+struct JitTuningSelection {
+    tunings: Vec<Scale>,
+    selected: usize,
+    synced: Option<Box<dyn KeyboardMapping<PianoKey> + Send>>,
+}
+
+impl JitTuningSelection {
+    fn tuning(&self) -> &dyn KeyboardMapping<PianoKey> {
+        match &self.synced {
+            Some(synced) => &**synced,
+            None => &*self.tunings[self.selected].tuning,
+        }
+    }
+
+    fn select(&mut self, selected: usize) {
+        if selected < self.tunings.len() {
+            self.selected = selected;
+            self.synced = None;
+        }
+    }
+}
+
 impl AheadOfTimeOptions {
+    /// Resolves the tuning method. [`TuningMethod::Auto`] falls back to a less precise method if the tunings exceed the channels of the more precise ones.
+    fn select_tuning_method(
+        &self,
+        tunings: &[Scale],
+        options: &LiveOptions,
+    ) -> CliResult<TuningMethod> {
+        let candidates = options.midi_out_args.tuning_method_candidates(self.method);
+        for &candidate in &candidates {
+            let target = options
+                .midi_out_args
+                .get_midi_target(|_: MidiTunerMessage| {}, candidate)?;
+            let synth = options.midi_out_args.create_synth(target, candidate);
+            if tunings
+                .iter()
+                .all(|tuning| apply_tuning(&synth, tuning).1.len() <= synth.num_channels())
+            {
+                return Ok(candidate);
+            }
+        }
+        Ok(candidates[0])
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn run(
         &self,
         app: &mut App,
+        tunings: Vec<Scale>,
+        method: TuningMethod,
         source: MidiSource,
         target: MidiTarget<impl MidiTunerMessageHandler + Send + 'static>,
+        router: ChannelRouter<impl MidiTunerMessageHandler + Send + 'static>,
+        forward: Sender<Vec<u8>>,
         options: &LiveOptions,
     ) -> CliResult<(String, MidiInputConnection<()>)> {
-        let scale = self.scale.to_scale(app)?;
+        let synth = options.midi_out_args.create_synth(target, method);
+
+        // Check all tunings in advance s.t. switching between them cannot fail during the performance
+        for (number, tuning) in tunings.iter().enumerate() {
+            let (model, channel_detunings) = apply_tuning(&synth, tuning);
+            let required_channels = channel_detunings.len();
+            let available_channels = synth.num_channels();
+            if required_channels > available_channels {
+                let mut message = format!(
+                    "Tuning {number} requires {required_channels} MIDI channels but only {available_channels} MIDI channels are available. The following keys cannot be tuned:\n",
+                );
+                midi::write_untunable_keys(
+                    &mut message,
+                    &model.untunable_keys(available_channels),
+                    usize::MAX,
+                    |key| format!("key {}", key.midi_number()),
+                )
+                .unwrap();
+                return Err(message.trim_end().to_owned().into());
+            }
+        }
 
-        let synth = options.midi_out_args.create_synth(target, self.method);
         let mut tuner = AotTuner::start(synth);
 
-        let required_channels = tuner.set_tuning(&*scale.tuning, scale.keys).unwrap();
-        if tuner.tuned() {
-            app.writeln(format_args!(
-                "Tuning requires {required_channels} MIDI channels"
-            ))?
-        } else {
-            let available_channels = options.midi_out_args.num_out_channels;
-            return Err(format!(
-                "Tuning requires {required_channels} MIDI channels but only {available_channels} MIDI channels are available",
-            )
-            .into());
-        }
+        let required_channels = tuner
+            .set_tuning(&*tunings[0].tuning, tunings[0].keys.iter().copied())
+            .unwrap();
+        app.writeln(format_args!(
+            "Tuning requires {required_channels} MIDI channels"
+        ))?;
+
+        let tuning_switch_args = options.tuning_switch_args.clone();
+        let channel_pressure_policy = options.channel_pressure_policy;
+        let poly_pressure_policy = options.poly_pressure_policy;
+        let mut pressed_keys = HashSet::new();
 
         connect_to_in_device(
             &options.midi_in_device,
             source,
+            router,
+            forward,
+            |_| false,
             move |message_type, offset| match message_type {
                 ChannelMessageType::NoteOff { key, velocity }
                 | ChannelMessageType::NoteOn {
                     key,
                     velocity: velocity @ 0,
                 } => {
-                    let piano_key = offset.get_piano_key(key);
-                    tuner.note_off(piano_key, velocity);
+                    if let Some(piano_key) = offset.get_piano_key(key) {
+                        pressed_keys.remove(&piano_key);
+                        tuner.note_off(piano_key, velocity);
+                    }
                 }
                 ChannelMessageType::NoteOn { key, velocity } => {
-                    let piano_key = offset.get_piano_key(key);
-                    tuner.note_on(piano_key, velocity);
+                    if let Some(piano_key) = offset.get_piano_key(key) {
+                        pressed_keys.insert(piano_key);
+                        tuner.note_on(piano_key, velocity);
+                    }
                 }
                 ChannelMessageType::PolyphonicKeyPressure { key, pressure } => {
-                    let piano_key = offset.get_piano_key(key);
-                    tuner.note_attr(piano_key, pressure);
+                    match poly_pressure_policy {
+                        PressurePolicy::All => {
+                            tuner.global_attr(ChannelMessageType::ChannelPressure { pressure });
+                        }
+                        PressurePolicy::Follow => {
+                            if let Some(piano_key) = offset.get_piano_key(key) {
+                                tuner.note_attr(piano_key, pressure);
+                            }
+                        }
+                        PressurePolicy::Drop => {}
+                    }
                 }
+                ChannelMessageType::ChannelPressure { .. } => match channel_pressure_policy {
+                    PressurePolicy::All => {
+                        tuner.global_attr(message_type);
+                    }
+                    PressurePolicy::Follow => {
+                        tuner.notes_channel_attr(pressed_keys.iter().copied(), message_type);
+                    }
+                    PressurePolicy::Drop => {}
+                },
                 message_type @ (ChannelMessageType::ControlChange { .. }
                 | ChannelMessageType::ProgramChange { .. }
-                | ChannelMessageType::ChannelPressure { .. }
                 | ChannelMessageType::PitchBendChange { .. }) => {
-                    tuner.global_attr(message_type);
+                    match tuning_switch_args.get_selected_tuning(message_type) {
+                        Some(selected) => {
+                            if let Some(tuning) = tunings.get(selected) {
+                                // Sounding notes are stopped since the key-to-channel mapping changes
+                                for piano_key in pressed_keys.drain() {
+                                    tuner.note_off(piano_key, 0);
+                                }
+                                tuner
+                                    .set_tuning(&*tuning.tuning, tuning.keys.iter().copied())
+                                    .unwrap();
+                            }
+                        }
+                        None => {
+                            tuner.global_attr(message_type);
+                        }
+                    }
                 }
             },
         )
     }
 }
 
+fn apply_tuning(
+    synth: &impl TunableSynth,
+    tuning: &Scale,
+) -> (AotTuningModel<PianoKey>, Vec<ChannelDetuning<Group>>) {
+    AotTuningModel::apply_tuning(
+        synth.group_by(),
+        &*tuning.tuning,
+        tuning.keys.iter().copied(),
+    )
+}
+
+/// Channel messages of routed input channels are handled by `router`. Other channel messages are passed to `callback` if the [`MidiSource`] listens to their channel.
+/// `sysex_callback` receives all SysEx messages regardless of the channels of the [`MidiSource`] and returns whether it consumed the message.
+/// Non-channel messages that are not consumed are sent to `forward` if the [`MidiSource`] is configured to forward them.
 fn connect_to_in_device(
     port_name: &str,
     source: MidiSource,
+    mut router: ChannelRouter<impl MidiTunerMessageHandler + Send + 'static>,
+    forward: Sender<Vec<u8>>,
+    mut sysex_callback: impl FnMut(&[u8]) -> bool + Send + 'static,
     mut callback: impl FnMut(ChannelMessageType, MultiChannelOffset) + Send + 'static,
 ) -> CliResult<(String, MidiInputConnection<()>)> {
     Ok(midi::connect_to_in_device(
@@ -242,13 +812,54 @@ fn connect_to_in_device(
         port_name,
         move |raw_message| {
             if let Some(parsed_message) = ChannelMessage::from_raw_message(raw_message) {
-                if source.channels.contains(&parsed_message.channel()) {
-                    callback(
-                        parsed_message.message_type(),
-                        source.get_offset(parsed_message.channel()),
-                    );
+                let channel = parsed_message.channel();
+                if !router.route(channel, parsed_message.message_type())
+                    && source.channels.contains(&channel)
+                {
+                    callback(parsed_message.message_type(), source.get_offset(channel));
+                }
+            } else {
+                let consumed = raw_message.first() == Some(&0xf0) && sysex_callback(raw_message);
+                if !consumed && source.forwards(raw_message) {
+                    forward.send(raw_message.to_vec()).unwrap();
                 }
             }
         },
     )?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn independent_channel_tunings() {
+        let parse = |channel_tunings: &[&str]| {
+            channel_tunings
+                .iter()
+                .map(|channel_tuning| parse_channel_tuning(channel_tuning))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let channel_tunings = parse(&["9:thru", "12:22-edo.scl,22-edo.kbm"]).unwrap();
+        assert_eq!(channel_tunings.len(), 2);
+        assert_eq!(channel_tunings[0].channel, 9);
+        assert!(channel_tunings[0].tuning_files.is_none());
+        assert_eq!(channel_tunings[1].channel, 12);
+        let tuning_files = channel_tunings[1].tuning_files.as_ref().unwrap();
+        assert_eq!(tuning_files.scl_file_location.to_str(), Some("22-edo.scl"));
+        assert_eq!(tuning_files.kbm_file_location.to_str(), Some("22-edo.kbm"));
+        assert!(check_channel_tunings(&channel_tunings, &[0, 1, 2]).is_ok());
+
+        assert!(parse(&["9"]).is_err());
+        assert!(parse(&["16:thru"]).is_err());
+        assert!(parse(&["9:22-edo.scl"]).is_err());
+
+        // Channel 3 belongs to the retuning group
+        let channel_tunings = parse(&["3:thru"]).unwrap();
+        assert!(check_channel_tunings(&channel_tunings, &[0, 1, 2, 3]).is_err());
+
+        let channel_tunings = parse(&["9:thru", "9:thru"]).unwrap();
+        assert!(check_channel_tunings(&channel_tunings, &[0, 1, 2]).is_err());
+    }
+}
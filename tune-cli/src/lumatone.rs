@@ -0,0 +1,215 @@
+use std::io;
+
+use clap::Parser;
+use tune::{
+    key::{Keyboard, PianoKey},
+    note::Note,
+    scala::{Kbm, Scl},
+    temperament::{EqualTemperament, TemperamentPreference},
+    tuning::{KeyboardMapping, Tuning},
+};
+
+use crate::{
+    shared::{KbmOptions, SclCommand},
+    App, CliResult,
+};
+
+/// Number of octave boards of the Lumatone.
+const NUM_BOARDS: i16 = 5;
+
+/// (First column, number of keys) for each row of a single octave board.
+/// Columns are given in doubled hex coordinates, i.e. horizontally adjacent keys differ by 2 and each row is shifted by 1.
+const BOARD_ROWS: [(i16, i16); 11] = [
+    (0, 2),
+    (1, 5),
+    (0, 6),
+    (1, 6),
+    (2, 6),
+    (3, 6),
+    (4, 6),
+    (5, 6),
+    (6, 6),
+    (9, 5),
+    (14, 2),
+];
+
+/// Offset between two adjacent octave boards in doubled hex coordinates.
+const BOARD_OFFSET: (i16, i16) = (14, 2);
+
+/// Location of the root key (board 2, row 5, key 3) in doubled hex coordinates.
+const ROOT_LOCATION: (i16, i16) = (37, 9);
+
+const ROOT_COLOR: &str = "ff8000";
+const NATURAL_COLOR: &str = "e0e0e0";
+const ACCIDENTAL_COLOR: &str = "404040";
+const UNMAPPED_COLOR: &str = "000000";
+
+#[derive(Parser)]
+pub(crate) struct LumatoneOptions {
+    /// Offset in scale steps per channel number (see --luma-offs of the live command).
+    /// Defaults to the number of steps per octave of the matching equal temperament.
+    #[arg(long = "luma-offs")]
+    lumatone_offset: Option<i16>,
+
+    /// Use porcupine layout when possible
+    #[arg(long = "porcupine")]
+    use_porcupine: bool,
+
+    /// Primary step width (right direction)
+    #[arg(long = "p-step")]
+    primary_step: Option<i16>,
+
+    /// Secondary step width (up/left direction)
+    #[arg(long = "s-step")]
+    secondary_step: Option<i16>,
+
+    #[command(flatten)]
+    kbm: KbmOptions,
+
+    #[command(subcommand)]
+    scl: SclCommand,
+}
+
+impl LumatoneOptions {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        let scl = self.scl.to_scl(None)?;
+        let kbm = self.kbm.to_kbm()?;
+
+        let kbm_root = kbm.kbm_root();
+        let root_key = kbm_root.ref_key.plus_steps(kbm_root.root_offset);
+        let temperament = self.find_temperament(&scl);
+        let keyboard = self.create_keyboard(&temperament, root_key);
+
+        let num_steps_per_octave = i32::from(temperament.num_steps_per_octave());
+        let lumatone_offset = self
+            .lumatone_offset
+            .unwrap_or_else(|| num_steps_per_octave.try_into().unwrap_or(i16::MAX));
+
+        for board in 0..NUM_BOARDS {
+            app.writeln(format_args!("[Board{board}]"))?;
+            for (key_index, (x, y)) in board_keys(board).enumerate() {
+                let piano_key = keyboard.get_key(
+                    (x - y - ROOT_LOCATION.0 + ROOT_LOCATION.1) / 2,
+                    y - ROOT_LOCATION.1,
+                );
+                let is_root = root_key
+                    .num_keys_before(piano_key)
+                    .rem_euclid(num_steps_per_octave)
+                    == 0;
+                let key = LumatoneKey::new(piano_key, is_root, lumatone_offset, &scl, &kbm);
+                key.write(app, key_index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn find_temperament(&self, scl: &Scl) -> EqualTemperament {
+        let preference = if self.use_porcupine {
+            TemperamentPreference::Porcupine
+        } else {
+            TemperamentPreference::PorcupineWhenMeantoneIsBad
+        };
+
+        let average_step_size = scl.period().divided_into_equal_steps(scl.num_items());
+
+        EqualTemperament::find()
+            .with_preference(preference)
+            .by_step_size(average_step_size)
+    }
+
+    fn create_keyboard(&self, temperament: &EqualTemperament, root_key: PianoKey) -> Keyboard {
+        let keyboard = Keyboard::root_at(root_key)
+            .with_steps_of(temperament)
+            .coprime();
+
+        let primary_step = self.primary_step.unwrap_or_else(|| keyboard.primary_step());
+        let secondary_step = self
+            .secondary_step
+            .unwrap_or_else(|| keyboard.secondary_step());
+
+        keyboard.with_steps(primary_step, secondary_step)
+    }
+}
+
+/// Iterates over the doubled hex coordinates of all keys of the given board in Lumatone key order.
+fn board_keys(board: i16) -> impl Iterator<Item = (i16, i16)> {
+    BOARD_ROWS
+        .iter()
+        .zip(0..)
+        .flat_map(move |(&(first_column, num_keys), row)| {
+            (0..num_keys).map(move |key| {
+                (
+                    board * BOARD_OFFSET.0 + first_column + 2 * key,
+                    board * BOARD_OFFSET.1 + row,
+                )
+            })
+        })
+}
+
+struct LumatoneKey {
+    channel_and_note: Option<(u8, u8)>,
+    color: &'static str,
+}
+
+impl LumatoneKey {
+    fn new(piano_key: PianoKey, is_root: bool, lumatone_offset: i16, scl: &Scl, kbm: &Kbm) -> Self {
+        let midi_number = piano_key.midi_number();
+        let channel_and_note = if lumatone_offset > 0 {
+            let channel = midi_number.div_euclid(lumatone_offset.into());
+            let note = midi_number.rem_euclid(lumatone_offset.into());
+            u8::try_from(channel)
+                .ok()
+                .filter(|&channel| channel < 16)
+                .zip(u8::try_from(note).ok().filter(|&note| note < 128))
+        } else {
+            u8::try_from(midi_number).ok().map(|note| (0, note))
+        };
+
+        let color = match (scl, kbm).maybe_pitch_of(piano_key) {
+            Some(_) if is_root => ROOT_COLOR,
+            Some(pitch) => {
+                let note: Note = ().find_by_pitch(pitch).approx_value;
+                match note.midi_number().rem_euclid(12) {
+                    1 | 3 | 6 | 8 | 10 => ACCIDENTAL_COLOR,
+                    _ => NATURAL_COLOR,
+                }
+            }
+            None => UNMAPPED_COLOR,
+        };
+
+        Self {
+            channel_and_note,
+            color,
+        }
+    }
+
+    fn write(&self, app: &mut App, key_index: usize) -> io::Result<()> {
+        let (key_type, channel, note, color) = match self.channel_and_note {
+            Some((channel, note)) => (1, channel + 1, note, self.color),
+            None => (0, 1, 0, UNMAPPED_COLOR),
+        };
+        app.writeln(format_args!("Key_{key_index}={note}"))?;
+        app.writeln(format_args!("Chan_{key_index}={channel}"))?;
+        app.writeln(format_args!("Col_{key_index}={color}"))?;
+        app.writeln(format_args!("KTyp_{key_index}={key_type}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn board_keys_do_not_overlap() {
+        let keys = (0..NUM_BOARDS)
+            .flat_map(board_keys)
+            .inspect(|(x, y)| assert_eq!((x - y).rem_euclid(2), 0))
+            .collect::<Vec<_>>();
+        assert_eq!(keys.len(), 280);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 280);
+        assert_eq!(board_keys(2).nth(28), Some(ROOT_LOCATION));
+    }
+}
@@ -1,9 +1,17 @@
-use std::{cmp::Ordering, io, iter, mem};
+use std::{
+    cmp::Ordering,
+    io, iter, mem,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
-use tune::{math, pitch::Ratio};
+use tune::{math, pitch::Ratio, scala};
 
-use crate::App;
+use crate::{
+    report::{self, HtmlReport},
+    shared::SclExportOptions,
+    App, CliResult,
+};
 
 #[derive(Parser)]
 pub(crate) enum MosCommand {
@@ -17,10 +25,10 @@ pub(crate) enum MosCommand {
 }
 
 impl MosCommand {
-    pub fn run(&self, app: &mut App) -> io::Result<()> {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
         match self {
             MosCommand::FindMoses(options) => options.run(app),
-            MosCommand::FindGenerators(options) => options.run(app),
+            MosCommand::FindGenerators(options) => Ok(options.run(app)?),
         }
     }
 }
@@ -37,17 +45,90 @@ pub(crate) struct FindMosesOptions {
     /// Chroma size below which the scale is considered an equal-step scale
     #[arg(long = "chroma", default_value = "0.5c")]
     threshold: Ratio,
+
+    /// Instead of listing the MOSes, print the scl file of the MOS with the given number of notes
+    #[arg(long = "scl")]
+    scl_num_notes: Option<u16>,
+
+    /// Number of generations below the unison when printing an scl file. Selects the mode of the MOS
+    #[arg(long = "down", default_value = "0", requires = "scl_num_notes")]
+    num_neg_generations: u16,
+
+    #[command(flatten)]
+    export: SclExportOptions,
+
+    /// Additionally write the list of MOSes as an HTML report with step pattern diagrams to the given file
+    #[arg(long = "report")]
+    report_location: Option<PathBuf>,
 }
 
 impl FindMosesOptions {
-    pub fn run(&self, app: &mut App) -> io::Result<()> {
-        for mos in Mos::new(self.generator.num_equal_steps_of_size(self.period)).children() {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        match self.scl_num_notes {
+            Some(num_notes) => self.print_scl(app, num_notes)?,
+            None => self.print_moses(app)?,
+        }
+
+        if let Some(report_location) = &self.report_location {
+            self.write_report(report_location)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_scl(&self, app: &mut App, num_notes: u16) -> CliResult<()> {
+        let mos = self
+            .moses()
+            .find(|mos| mos.num_steps() == u32::from(num_notes))
+            .ok_or_else(|| {
+                format!("The generator does not produce a MOS with {num_notes} notes")
+            })?;
+
+        if self.num_neg_generations >= num_notes {
+            return Err(format!(
+                "The number of negative generations must be less than {num_notes}"
+            )
+            .into());
+        }
+
+        let scl = scala::create_rank2_temperament_scale(
+            format!(
+                "{}L{}s MOS of generator {} ({:#}) with period {}",
+                mos.num_large_steps,
+                mos.num_small_steps,
+                self.generator,
+                self.generator,
+                self.period
+            ),
+            self.generator,
+            num_notes - 1 - self.num_neg_generations,
+            self.num_neg_generations,
+            self.period,
+        )?;
+
+        self.export.write(app, &scl, None)
+    }
+
+    fn moses(&self) -> impl Iterator<Item = Mos> + '_ {
+        self.all_moses().take_while(|mos| !self.is_equal_step(mos))
+    }
+
+    fn all_moses(&self) -> impl Iterator<Item = Mos> {
+        Mos::new(self.generator.num_equal_steps_of_size(self.period)).children()
+    }
+
+    fn is_equal_step(&self, mos: &Mos) -> bool {
+        self.period.repeated(mos.chroma()) < self.threshold
+    }
+
+    fn print_moses(&self, app: &mut App) -> io::Result<()> {
+        for mos in self.all_moses() {
             if mos.is_convergent() {
                 app.write("* ")?;
             } else {
                 app.write("  ")?;
             }
-            if self.period.repeated(mos.chroma()) >= self.threshold {
+            if !self.is_equal_step(&mos) {
                 app.writeln(format_args!(
                     "num_notes = {}, {}L{}s, L = {:#.0}, s = {:#.0}",
                     mos.num_steps(),
@@ -71,6 +152,39 @@ impl FindMosesOptions {
 
         Ok(())
     }
+
+    fn write_report(&self, report_location: &Path) -> CliResult<()> {
+        let generator = self.generator.num_equal_steps_of_size(self.period);
+
+        let mut report = HtmlReport::new(&format!(
+            "MOSes of generator {} ({:#.0}) with period {}",
+            self.generator, self.generator, self.period
+        ));
+        report.table(
+            &["notes", "pattern", "L", "s", "convergent"],
+            self.moses().map(|mos| {
+                vec![
+                    mos.num_steps().to_string(),
+                    format!("{}L{}s", mos.num_large_steps, mos.num_small_steps),
+                    format!("{:#.0}", self.period.repeated(mos.large_step_size)),
+                    format!("{:#.0}", self.period.repeated(mos.small_step_size)),
+                    if mos.is_convergent() { "yes" } else { "no" }.to_owned(),
+                ]
+            }),
+        );
+
+        for mos in self.moses() {
+            report.heading(&format!(
+                "{}L{}s ({} notes)",
+                mos.num_large_steps,
+                mos.num_small_steps,
+                mos.num_steps()
+            ));
+            report.diagram(&report::step_pattern(mos.steps(generator)));
+        }
+
+        report.write_to(report_location)
+    }
 }
 
 #[derive(Parser)]
@@ -222,6 +336,24 @@ impl Mos {
         }
     }
 
+    /// Returns the steps of the mode generated upwards from the unison as pairs of (is large, size in periods).
+    fn steps(&self, generator: f64) -> Vec<(bool, f64)> {
+        let mut positions: Vec<_> = (0..self.num_steps())
+            .map(|generation| (f64::from(generation) * generator).rem_euclid(1.0))
+            .collect();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        positions.push(1.0);
+
+        let threshold = (self.large_step_size + self.small_step_size) / 2.0;
+        positions
+            .windows(2)
+            .map(|window| {
+                let size = window[1] - window[0];
+                (size > threshold, size)
+            })
+            .collect()
+    }
+
     fn num_steps(&self) -> u32 {
         u32::from(self.num_large_steps) + u32::from(self.num_small_steps)
     }
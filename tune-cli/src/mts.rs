@@ -1,21 +1,24 @@
 use std::{
     fs::{File, OpenOptions},
-    io::Write,
+    io::{Read, Write},
     path::PathBuf,
 };
 
 use clap::Parser;
-use midir::MidiOutputConnection;
 use tune::{
     mts::{
-        ScaleOctaveTuningFormat, ScaleOctaveTuningOptions, SingleNoteTuningChangeMessage,
-        SingleNoteTuningChangeOptions,
+        Channels, MtsMessage, ScaleOctaveTuningFormat, ScaleOctaveTuningOptions,
+        SingleNoteTuningChangeMessage, SingleNoteTuningChangeOptions,
     },
+    pitch::Pitched,
     tuner::AotTuningModel,
 };
 
 use crate::{
-    shared::midi::{self, DeviceIdArg},
+    shared::{
+        midi::{DeviceIdArg, MidiOutConnection},
+        mts::MtsClient,
+    },
     App, CliResult, ScaleCommand,
 };
 
@@ -70,6 +73,10 @@ enum MtsCommand {
     /// Select a tuning bank
     #[command(name = "tun-bk")]
     TuningBank(TuningBankOptions),
+
+    /// Decode captured MTS messages (Bulk Tuning Dump, Single Note Tuning Change, Scale/Octave Tuning) and print the resulting scale and keyboard mapping
+    #[command(name = "decode")]
+    Decode(DecodeOptions),
 }
 
 #[derive(Parser)]
@@ -122,8 +129,18 @@ struct TuningBankOptions {
     tuning_bank: u8,
 }
 
+#[derive(Parser)]
+struct DecodeOptions {
+    /// File containing the SysEx messages, either in binary form (e.g. a .syx file) or as hex bytes (e.g. the output of `tune mts`). If absent, the messages are read from stdin
+    file_location: Option<PathBuf>,
+}
+
 impl MtsOptions {
     pub fn run(&self, app: &mut App) -> CliResult<()> {
+        if let MtsCommand::Decode(options) = &self.command {
+            return options.run(app);
+        }
+
         let mut outputs = Outputs {
             open_file: self
                 .binary_file
@@ -135,7 +152,7 @@ impl MtsOptions {
             midi_out: self
                 .midi_out_device
                 .as_deref()
-                .map(|target_port| midi::connect_to_out_device("tune-cli", target_port))
+                .map(|target_port| app.midi.connect_out(target_port))
                 .transpose()?,
         };
 
@@ -156,6 +173,7 @@ impl MtsOptions {
             }
             MtsCommand::TuningProgram(options) => options.run(app, &mut outputs),
             MtsCommand::TuningBank(options) => options.run(app, &mut outputs),
+            MtsCommand::Decode(_) => unreachable!("Decode does not write any outputs"),
         }
     }
 }
@@ -273,9 +291,146 @@ impl TuningBankOptions {
     }
 }
 
+impl DecodeOptions {
+    fn run(&self, app: &mut App) -> CliResult<()> {
+        let mut input = Vec::new();
+        match &self.file_location {
+            Some(file_location) => File::open(file_location)?.read_to_end(&mut input)?,
+            None => app.read().read_to_end(&mut input)?,
+        };
+
+        let sysex_messages = split_sysex_messages(&parse_bytes(&input)?);
+        if sysex_messages.is_empty() {
+            return Err("The input does not contain any SysEx messages"
+                .to_owned()
+                .into());
+        }
+
+        let mut mts_client = MtsClient::default();
+
+        for sysex_bytes in sysex_messages {
+            let message = match MtsMessage::from_sysex_bytes(&sysex_bytes) {
+                Ok(message) => message,
+                Err(err) => {
+                    app.writeln(format_args!("== Skipped message ({err:?}) =="))?;
+                    continue;
+                }
+            };
+
+            match &message {
+                MtsMessage::BulkTuningDumpRequest {
+                    device_id,
+                    tuning_program,
+                } => app.writeln(format_args!(
+                    "== Bulk Tuning Dump Request (device {device_id}, tuning program {tuning_program}) =="
+                ))?,
+                MtsMessage::BulkTuningDump {
+                    device_id,
+                    tuning_program,
+                    name,
+                    ..
+                } => app.writeln(format_args!(
+                    "== Bulk Tuning Dump '{}' (device {device_id}, tuning program {tuning_program}) ==",
+                    name.trim_end()
+                ))?,
+                MtsMessage::SingleNoteTuningChange { options, .. } => app.writeln(format_args!(
+                    "== Single Note Tuning Change (device {}, tuning program {}{}{}) ==",
+                    options.device_id,
+                    options.tuning_program,
+                    options
+                        .with_bank_select
+                        .map(|tuning_bank| format!(", tuning bank {tuning_bank}"))
+                        .unwrap_or_default(),
+                    if options.realtime { ", realtime" } else { "" },
+                ))?,
+                MtsMessage::ScaleOctaveTuning { options, .. } => app.writeln(format_args!(
+                    "== Scale/Octave Tuning (device {}, channels {}{}) ==",
+                    options.device_id,
+                    match &options.channels {
+                        Channels::All => "all".to_owned(),
+                        Channels::Some(channels) => {
+                            let mut channels = Vec::from_iter(channels);
+                            channels.sort();
+                            channels
+                                .iter()
+                                .map(|channel| channel.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        }
+                    },
+                    if options.realtime { ", realtime" } else { "" },
+                ))?,
+            }
+
+            for tuning_change in message.tuning_changes() {
+                let approximation = tuning_change.target_pitch.find_in_tuning(());
+                app.writeln(format_args!(
+                    "- key {:>3}: {:>10.3} Hz | {} {:>+8.3}c",
+                    tuning_change.key.midi_number(),
+                    tuning_change.target_pitch.as_hz(),
+                    approximation.approx_value,
+                    approximation.deviation.as_cents()
+                ))?;
+            }
+            mts_client.apply(&message);
+        }
+
+        if !mts_client.has_retuned_keys() {
+            return Err("The messages do not retune any key".to_owned().into());
+        }
+        let (scl, kbm_root) = mts_client.tuning()?;
+
+        app.writeln("== Scale ==")?;
+        app.write(scl.export())?;
+        app.writeln("== Keyboard mapping ==")?;
+        app.write(kbm_root.to_kbm().export())?;
+
+        Ok(())
+    }
+}
+
+/// Accepts binary data or hex bytes separated by whitespace or commas, with or without `0x` prefix.
+fn parse_bytes(input: &[u8]) -> CliResult<Vec<u8>> {
+    let text = match std::str::from_utf8(input) {
+        Ok(text) if !input.contains(&0xf0) => text,
+        _ => return Ok(input.to_vec()),
+    };
+
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let digits = token.strip_prefix("0x").unwrap_or(token);
+            u8::from_str_radix(digits, 16).map_err(|_| format!("Invalid hex byte '{token}'").into())
+        })
+        .collect()
+}
+
+/// Collects the bytes from every `f0` up to the next `f7`. All other bytes, e.g. RPN messages, are ignored.
+fn split_sysex_messages(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut curr_message: Option<Vec<u8>> = None;
+    for &byte in bytes {
+        match byte {
+            0xf0 => curr_message = Some(vec![byte]),
+            0xf7 => {
+                if let Some(mut message) = curr_message.take() {
+                    message.push(byte);
+                    messages.push(message);
+                }
+            }
+            _ => {
+                if let Some(message) = &mut curr_message {
+                    message.push(byte);
+                }
+            }
+        }
+    }
+    messages
+}
+
 struct Outputs {
     open_file: Option<File>,
-    midi_out: Option<(String, MidiOutputConnection)>,
+    midi_out: Option<(String, Box<dyn MidiOutConnection>)>,
 }
 
 impl Outputs {
@@ -290,7 +445,7 @@ impl Outputs {
             app.errln(format_args!("Sending MIDI data to {device_name}"))?;
             midi_out
                 .send(message)
-                .map_err(|err| format!("Could not send MIDI message: {err}"))?
+                .map_err(|err| format!("Could not send MIDI message: {err:?}"))?
         }
 
         Ok(())
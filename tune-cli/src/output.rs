@@ -0,0 +1,154 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Placeholder in the `--of` location that is replaced by the file extension of each written file.
+const EXTENSION_PLACEHOLDER: &str = "{ext}";
+
+/// Destination of the command output.
+pub enum Output<'a> {
+    Stream(Box<dyn 'a + Write>),
+    Files(OutputFiles),
+}
+
+impl Output<'_> {
+    /// Writes an additional output file with the given extension. Streams receive the content of all files in sequence.
+    pub fn write_artifact(&mut self, extension: &str, content: impl Display) -> io::Result<()> {
+        match self {
+            Output::Stream(stream) => write!(stream, "{content}"),
+            Output::Files(files) => write!(files.open_artifact(extension)?, "{content}"),
+        }
+    }
+
+    /// Moves the written files to their final locations. Must be called after the command has succeeded.
+    pub fn commit(self) -> io::Result<()> {
+        match self {
+            Output::Stream(mut stream) => stream.flush(),
+            Output::Files(files) => files.commit(),
+        }
+    }
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stream(stream) => stream.write(buf),
+            Output::Files(files) => files.open_main_file()?.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stream(stream) => stream.flush(),
+            Output::Files(files) => files.main_file.as_mut().map_or(Ok(()), |file| file.flush()),
+        }
+    }
+}
+
+/// Files written via the `--of` option.
+///
+/// The content is written to temporary files which are renamed when the command succeeds. This way, scripted pipelines never observe partially written files and a failing command leaves the previous files untouched.
+pub struct OutputFiles {
+    location_template: PathBuf,
+    force: bool,
+    main_file: Option<PendingFile>,
+    artifacts: Vec<PendingFile>,
+}
+
+impl OutputFiles {
+    pub fn new(location_template: PathBuf, force: bool) -> Self {
+        Self {
+            location_template,
+            force,
+            main_file: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn open_main_file(&mut self) -> io::Result<&mut PendingFile> {
+        if self.main_file.is_none() {
+            let location = self.location_template.to_string_lossy();
+            if location.contains(EXTENSION_PLACEHOLDER) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("The command writes a single file. Remove {EXTENSION_PLACEHOLDER} from the output location"),
+                ));
+            }
+            self.main_file = Some(PendingFile::create(&self.location_template, self.force)?);
+        }
+        Ok(self.main_file.as_mut().unwrap())
+    }
+
+    fn open_artifact(&mut self, extension: &str) -> io::Result<&mut PendingFile> {
+        let location = self.location_template.to_string_lossy();
+        if !location.contains(EXTENSION_PLACEHOLDER) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The command writes multiple files. Add {EXTENSION_PLACEHOLDER} to the output location, e.g. my_tuning.{EXTENSION_PLACEHOLDER}"),
+            ));
+        }
+        let location = PathBuf::from(location.replace(EXTENSION_PLACEHOLDER, extension));
+        self.artifacts
+            .push(PendingFile::create(&location, self.force)?);
+        Ok(self.artifacts.last_mut().unwrap())
+    }
+
+    fn commit(self) -> io::Result<()> {
+        for mut pending_file in self.main_file.into_iter().chain(self.artifacts) {
+            pending_file.file.flush()?;
+            fs::rename(&pending_file.temp_location, &pending_file.location)?;
+        }
+        Ok(())
+    }
+}
+
+struct PendingFile {
+    location: PathBuf,
+    temp_location: PathBuf,
+    file: File,
+}
+
+impl PendingFile {
+    fn create(location: &Path, force: bool) -> io::Result<Self> {
+        if !force && location.exists() {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!(
+                    "Output file {} already exists. Use --force to overwrite it",
+                    location.display()
+                ),
+            ));
+        }
+
+        // The temporary file is located in the same directory s.t. renaming it is atomic
+        let file_name = location.file_name().unwrap_or_default().to_string_lossy();
+        let temp_location = location.with_file_name(format!(".{file_name}.{}.tmp", process::id()));
+
+        Ok(Self {
+            file: File::create(&temp_location)?,
+            location: location.to_owned(),
+            temp_location,
+        })
+    }
+}
+
+impl Write for PendingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        // Fails silently if the file has already been renamed
+        let _ = fs::remove_file(&self.temp_location);
+    }
+}
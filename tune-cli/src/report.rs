@@ -0,0 +1,190 @@
+use std::{fmt::Write, fs, path::Path};
+
+use crate::CliResult;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #2e3440; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #d8dee9; padding: 0.2em 0.6em; text-align: right; }
+th { background: #eceff4; }
+svg { display: block; margin: 1em 0; }";
+
+/// Self-contained HTML document summarizing the result of an analysis command.
+///
+/// Diagrams are embedded as inline SVG s.t. the report can be shared as a single file.
+pub struct HtmlReport {
+    html: String,
+}
+
+impl HtmlReport {
+    pub fn new(title: &str) -> Self {
+        let title = escape(title);
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, "<html>").unwrap();
+        writeln!(html, "<head>").unwrap();
+        writeln!(html, "<meta charset=\"utf-8\">").unwrap();
+        writeln!(html, "<title>{title}</title>").unwrap();
+        writeln!(html, "<style>\n{STYLE}\n</style>").unwrap();
+        writeln!(html, "</head>").unwrap();
+        writeln!(html, "<body>").unwrap();
+        writeln!(html, "<h1>{title}</h1>").unwrap();
+        Self { html }
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        writeln!(self.html, "<h2>{}</h2>", escape(text)).unwrap();
+    }
+
+    pub fn paragraph(&mut self, text: &str) {
+        writeln!(self.html, "<p>{}</p>", escape(text)).unwrap();
+    }
+
+    pub fn list(&mut self, items: impl IntoIterator<Item = String>) {
+        writeln!(self.html, "<ul>").unwrap();
+        for item in items {
+            writeln!(self.html, "<li>{}</li>", escape(&item)).unwrap();
+        }
+        writeln!(self.html, "</ul>").unwrap();
+    }
+
+    /// Adds a table with the given column headers. No header row is rendered if `header` is empty.
+    pub fn table(&mut self, header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) {
+        writeln!(self.html, "<table>").unwrap();
+        if !header.is_empty() {
+            write!(self.html, "<tr>").unwrap();
+            for cell in header {
+                write!(self.html, "<th>{}</th>", escape(cell)).unwrap();
+            }
+            writeln!(self.html, "</tr>").unwrap();
+        }
+        for row in rows {
+            write!(self.html, "<tr>").unwrap();
+            for cell in row {
+                write!(self.html, "<td>{}</td>", escape(&cell)).unwrap();
+            }
+            writeln!(self.html, "</tr>").unwrap();
+        }
+        writeln!(self.html, "</table>").unwrap();
+    }
+
+    /// Embeds a diagram created by, e.g., [`error_bars`] or [`step_pattern`].
+    pub fn diagram(&mut self, svg: &str) {
+        self.html.push_str(svg);
+    }
+
+    pub fn write_to(mut self, location: &Path) -> CliResult<()> {
+        writeln!(self.html, "</body>").unwrap();
+        writeln!(self.html, "</html>").unwrap();
+        fs::write(location, self.html)
+            .map_err(|io_err| format!("Could not write report: {io_err}"))?;
+        Ok(())
+    }
+}
+
+/// Horizontal bar chart of signed errors. A bar reaching the border of the chart corresponds to an error of `max_abs_error`.
+pub fn error_bars<'a>(
+    errors: impl IntoIterator<Item = (&'a str, f64)>,
+    max_abs_error: f64,
+    unit: &str,
+) -> String {
+    const LABEL_WIDTH: f64 = 60.0;
+    const HALF_WIDTH: f64 = 160.0;
+    const ROW_HEIGHT: f64 = 20.0;
+
+    let errors: Vec<_> = errors.into_iter().collect();
+    let width = LABEL_WIDTH + 2.0 * HALF_WIDTH + LABEL_WIDTH;
+    let height = ROW_HEIGHT * errors.len() as f64;
+    let zero = LABEL_WIDTH + HALF_WIDTH;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-size="12">"#
+    )
+    .unwrap();
+    for (index, (label, error)) in errors.iter().enumerate() {
+        let y = ROW_HEIGHT * index as f64;
+        let bar_width = (error / max_abs_error).clamp(-1.0, 1.0) * HALF_WIDTH;
+        let (x, color) = if bar_width < 0.0 {
+            (zero + bar_width, "#bf616a")
+        } else {
+            (zero, "#5e81ac")
+        };
+        writeln!(
+            svg,
+            r#"<text x="0" y="{:.1}">{}</text>"#,
+            y + 14.0,
+            escape(label)
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<rect x="{x:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{color}"/>"#,
+            y + 3.0,
+            bar_width.abs(),
+            ROW_HEIGHT - 6.0
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}">{error:+.1}{unit}</text>"#,
+            zero + HALF_WIDTH + 4.0,
+            y + 14.0,
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        r##"<line x1="{zero}" y1="0" x2="{zero}" y2="{height}" stroke="#4c566a"/>"##
+    )
+    .unwrap();
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// Draws the steps of a scale as adjacent boxes whose widths are proportional to the step sizes. Large steps are labeled with L, small steps with s.
+pub fn step_pattern(steps: impl IntoIterator<Item = (bool, f64)>) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 24.0;
+
+    let steps: Vec<_> = steps.into_iter().collect();
+    let total_size: f64 = steps.iter().map(|&(_, size)| size).sum();
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" font-size="12">"#
+    )
+    .unwrap();
+    let mut x = 0.0;
+    for (is_large, size) in steps {
+        let width = size / total_size * WIDTH;
+        let (color, label) = if is_large {
+            ("#5e81ac", "L")
+        } else {
+            ("#a3be8c", "s")
+        };
+        writeln!(
+            svg,
+            r##"<rect x="{x:.1}" y="0" width="{width:.1}" height="{HEIGHT}" fill="{color}" stroke="#eceff4"/>"##
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r##"<text x="{:.1}" y="16" text-anchor="middle" fill="#eceff4">{label}</text>"##,
+            x + width / 2.0
+        )
+        .unwrap();
+        x += width;
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,7 +1,14 @@
+use std::ops::Range;
+
 use clap::Parser;
+use tune::{
+    key::PianoKey,
+    note::Note,
+    scala::{Kbm, KbmRoot},
+};
 
 use crate::{
-    shared::{KbmOptions, SclCommand},
+    shared::{KbmOptions, SclCommand, SclExportOptions},
     App, CliResult,
 };
 
@@ -11,6 +18,9 @@ pub(crate) struct SclOptions {
     #[arg(long = "name")]
     name: Option<String>,
 
+    #[command(flatten)]
+    export: SclExportOptions,
+
     #[command(subcommand)]
     scl: SclCommand,
 }
@@ -23,17 +33,102 @@ pub(crate) enum KbmCommand {
         #[command(flatten)]
         kbm: KbmOptions,
     },
+
+    /// Derive the key range and the reference note from note names, e.g. C2..C6 --root D4
+    #[command(name = "range")]
+    FromNoteRange {
+        /// Key range, e.g. C2..C6 (upper bound exclusive) or C2..=C6 (upper bound inclusive)
+        #[arg(value_parser = parse_note_range)]
+        range: Range<PianoKey>,
+
+        /// Reference note that should sound at its usual pitch, e.g. D4 or F#3. Defaults to the lower bound of the range
+        #[arg(long = "root")]
+        root: Option<Note>,
+
+        /// Scale degree played by the reference note, e.g. -3 if the scale should start 3 keys above the reference note
+        #[arg(
+            long = "degree-offset",
+            allow_hyphen_values = true,
+            default_value = "0"
+        )]
+        degree_offset: i16,
+    },
 }
 
 impl SclOptions {
     pub fn run(self, app: &mut App) -> CliResult<()> {
-        Ok(app.write(format_args!("{}", self.scl.to_scl(self.name)?.export()))?)
+        let scl = self.scl.to_scl(self.name)?;
+        self.export
+            .write(app, &scl, self.scl.source_file_location())
     }
 }
 
 impl KbmCommand {
     pub fn run(&self, app: &mut App) -> CliResult<()> {
-        let KbmCommand::WithRefNote { kbm } = self;
-        Ok(app.write(format_args!("{}", kbm.to_kbm()?.export()))?)
+        let kbm = match self {
+            KbmCommand::WithRefNote { kbm } => kbm.to_kbm()?,
+            KbmCommand::FromNoteRange {
+                range,
+                root,
+                degree_offset,
+            } => {
+                let root = root.unwrap_or_else(|| Note::from_piano_key(range.start));
+                let kbm_root = KbmRoot {
+                    root_offset: -i32::from(*degree_offset),
+                    ..KbmRoot::from(root)
+                };
+                Kbm::builder(kbm_root).range(range.clone()).build()?
+            }
+        };
+        Ok(app.write(format_args!("{}", kbm.export()))?)
+    }
+}
+
+fn parse_note_range(s: &str) -> Result<Range<PianoKey>, String> {
+    let (start, end, is_inclusive) = if let Some((start, end)) = s.split_once("..=") {
+        (start, end, true)
+    } else if let Some((start, end)) = s.split_once("..") {
+        (start, end, false)
+    } else {
+        return Err(format!(
+            "Invalid range '{s}': Should be of the form C2..C6 or C2..=C6"
+        ));
+    };
+
+    let start = start.parse::<Note>()?;
+    let mut end = end.parse::<Note>()?;
+    if is_inclusive {
+        end = end.plus_semitones(1);
+    }
+    if end <= start {
+        return Err(format!(
+            "Invalid range '{s}': The upper bound must be above the lower bound"
+        ));
+    }
+
+    Ok(start.as_piano_key()..end.as_piano_key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_note_ranges() {
+        let midi_numbers = |s| {
+            parse_note_range(s)
+                .map(|range: Range<PianoKey>| (range.start.midi_number(), range.end.midi_number()))
+        };
+
+        assert_eq!(midi_numbers("C2..C6"), Ok((36, 84)));
+        assert_eq!(midi_numbers("C2..=C6"), Ok((36, 85)));
+        assert_eq!(
+            midi_numbers("C6..C6"),
+            Err("Invalid range 'C6..C6': The upper bound must be above the lower bound".to_owned())
+        );
+        assert_eq!(
+            midi_numbers("C2-C6"),
+            Err("Invalid range 'C2-C6': Should be of the form C2..C6 or C2..=C6".to_owned())
+        );
     }
 }
@@ -1,23 +1,85 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io,
     path::{Path, PathBuf},
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use tune::{
+    he::HarmonicEntropy,
     key::PianoKey,
+    note::{Note, NoteNames},
     pitch::{Pitch, Pitched, Ratio},
-    scala::{KbmRoot, Scl},
+    scala::{Kbm, KbmRoot, Scl},
+    temperament::EqualTemperament,
+    tun,
     tuning::{KeyboardMapping, Tuning},
+    uncertain::Uncertain,
 };
 
 use crate::{
-    dto::{ScaleDto, ScaleItemDto, TuneDto},
-    shared::{self, KbmOptions, KbmRootOptions, SclCommand},
+    chart,
+    dto::{ProvenanceDto, ScaleDto, ScaleItemDto, SourceFileDto, TuneDto},
+    shared::{
+        self, KbmOptions, KbmRootOptions, Notation, NoteNameConvention, SclCommand, StretchOptions,
+    },
     App, CliResult,
 };
 
+#[derive(Parser)]
+pub(crate) struct ScaleOptions {
+    /// Output format
+    #[arg(long = "format", default_value = "yml")]
+    format: ScaleFormat,
+
+    /// Naming convention of the note names in tuning charts
+    #[arg(long = "note-names", default_value = "english")]
+    note_names: NoteNameConvention,
+
+    /// Record the command line, the scale generator and the hashes of the source files in the YAML output
+    #[arg(long = "provenance")]
+    provenance: bool,
+
+    #[command(subcommand)]
+    command: ScaleSubcommand,
+}
+
+#[derive(Parser)]
+#[allow(clippy::large_enum_variant)]
+enum ScaleSubcommand {
+    #[command(flatten)]
+    Create(ScaleCommand),
+
+    /// Read a scale file in YAML format from stdin and display how it was generated
+    #[command(name = "provenance")]
+    Provenance,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum ScaleFormat {
+    /// YAML scale file that can be read by other tune commands
+    #[value(name = "yml")]
+    Yaml,
+    /// AnaMark TUN file (including the [Exact Tuning] section)
+    #[value(name = "tun")]
+    Tun,
+    /// Pair of an scl and a kbm file. Use --of with an {ext} placeholder to write both files
+    #[value(name = "scl-kbm")]
+    SclKbm,
+    /// Plain-text tuning chart listing the degree, note name, cent offset and frequency of each key
+    #[value(name = "chart")]
+    Chart,
+    /// Tuning chart in Braille Ready Format for braille displays and embossers
+    #[value(name = "brf")]
+    Brf,
+    /// LilyPond snippet with the nearest 12-EDO notes, annotated with their cent deviations
+    #[value(name = "ly")]
+    Lilypond,
+    /// abc tune with the nearest 12-EDO notes, annotated with their cent deviations
+    #[value(name = "abc")]
+    Abc,
+}
+
 #[derive(Parser)]
 pub(crate) enum ScaleCommand {
     /// Use a keyboard mapping with the given reference note
@@ -26,6 +88,9 @@ pub(crate) enum ScaleCommand {
         #[command(flatten)]
         kbm: KbmOptions,
 
+        #[command(flatten)]
+        stretch: StretchOptions,
+
         #[command(subcommand)]
         scl: SclCommand,
     },
@@ -36,6 +101,9 @@ pub(crate) enum ScaleCommand {
         /// The location of the kbm file to import
         kbm_file_location: PathBuf,
 
+        #[command(flatten)]
+        stretch: StretchOptions,
+
         #[command(subcommand)]
         scl: SclCommand,
     },
@@ -47,29 +115,70 @@ pub(crate) enum ScaleCommand {
         scale_file_location: PathBuf,
     },
 
-    /// Read a scale file from stdin in YAML format
+    /// Read a scale file in YAML format or a newline-separated list of frequencies (e.g. 440 or 440Hz) and ratios (e.g. 5/4 or 386c) from stdin.
+    /// Ratios are relative to the first line which must be a frequency.
     #[command(name = "stdin")]
-    ReadStdin,
+    ReadStdin {
+        #[command(flatten)]
+        pitch_list: PitchListOptions,
+    },
 }
 
 #[derive(Parser)]
 pub(crate) struct DumpOptions {
     #[command(flatten)]
-    limit: LimitOptions,
+    analysis: AnalysisOptions,
+
+    /// Naming convention of the target notes
+    #[arg(long = "note-names", default_value = "english")]
+    note_names: NoteNameConvention,
 
     #[command(subcommand)]
-    scale: ScaleCommand,
+    scale: DumpCommand,
+}
+
+#[derive(Parser)]
+enum DumpCommand {
+    #[command(flatten)]
+    Single(ScaleCommand),
+
+    /// Dump multiple scl files using the same keyboard mapping
+    #[command(name = "batch")]
+    Batch {
+        #[command(flatten)]
+        kbm: KbmOptions,
+
+        #[command(flatten)]
+        batch: BatchOptions,
+    },
 }
 
 #[derive(Parser)]
 pub(crate) struct DiffOptions {
     #[command(flatten)]
-    limit: LimitOptions,
+    analysis: AnalysisOptions,
+
+    /// Classify each source degree as matched if its deviation from the target scale is within the given tolerance (e.g. 5c).
+    /// Bare numbers are interpreted as cents.
+    /// Prints summary statistics and fails if any source degree is unmatched.
+    #[arg(long = "tolerance", value_parser = parse_tolerance)]
+    tolerance: Option<Ratio>,
 
     #[command(subcommand)]
     source_scale: SourceScaleCommand,
 }
 
+fn parse_tolerance(src: &str) -> Result<Ratio, String> {
+    let tolerance = match src.parse::<f64>() {
+        Ok(cents) => Ratio::from_cents(cents),
+        Err(_) => src.parse()?,
+    };
+    if tolerance.as_cents() < 0.0 {
+        return Err("Should not be negative".to_owned());
+    }
+    Ok(tolerance)
+}
+
 #[derive(Parser)]
 enum SourceScaleCommand {
     /// Use a scale file in YAML format
@@ -82,9 +191,13 @@ enum SourceScaleCommand {
         target_scale: TargetScaleCommand,
     },
 
-    /// Read a scale file from stdin in YAML format
+    /// Read a scale file in YAML format or a newline-separated list of frequencies (e.g. 440 or 440Hz) and ratios (e.g. 5/4 or 386c) from stdin.
+    /// Ratios are relative to the first line which must be a frequency.
     #[command(name = "stdin")]
     ReadStdin {
+        #[command(flatten)]
+        pitch_list: PitchListOptions,
+
         #[command(subcommand)]
         target_scale: TargetScaleCommand,
     },
@@ -101,44 +214,135 @@ enum TargetScaleCommand {
         #[command(subcommand)]
         scl: SclCommand,
     },
+
+    /// Compare against multiple scl files using a linear keyboard mapping with the given reference note
+    #[command(name = "batch")]
+    Batch {
+        #[command(flatten)]
+        kbm_root: KbmRootOptions,
+
+        #[command(flatten)]
+        batch: BatchOptions,
+    },
+}
+
+#[derive(Parser)]
+struct BatchOptions {
+    /// Write a CSV summary with one line per scl file to the given location
+    #[arg(long = "csv")]
+    csv_file_location: Option<PathBuf>,
+
+    /// The locations of the scl files to import. Directories are searched for scl files (non-recursively).
+    #[arg(required = true)]
+    scl_file_locations: Vec<PathBuf>,
 }
 
 #[derive(Parser)]
-struct LimitOptions {
+pub(crate) struct PitchListOptions {
+    /// Key that the first line of a plain pitch list is mapped to. Subsequent lines are mapped to subsequent keys. All keys must be within the MIDI range (0..=127).
+    #[arg(long = "root", default_value = "60")]
+    root_key: i32,
+}
+
+#[derive(Parser)]
+struct AnalysisOptions {
     /// Largest acceptable numerator or denominator (ignoring powers of two)
     #[arg(long = "lim", default_value = "11")]
-    odd_limit: u16,
+    odd_limit: u32,
+
+    /// Measurement uncertainty of the source pitches (e.g. 0.5c). Reports the worst-case range of the deviation and all nearest fractions within the error bounds.
+    #[arg(long = "uncertainty")]
+    uncertainty: Option<Ratio>,
+
+    /// Name the source degrees using the given notation. The step between the root and the next key is considered the step size of an equal temperament and the root is named after its note letter. Falls back to plain indices if the scale is not equal-stepped.
+    #[arg(long = "notation")]
+    notation: Option<Notation>,
+
+    /// Print the harmonic entropy (in nats) of the interval between the root and each source pitch
+    #[arg(long = "he")]
+    harmonic_entropy: bool,
 }
 
 pub(crate) struct Scale {
     pub origin: PianoKey,
     pub keys: Vec<PianoKey>,
     pub tuning: Box<dyn KeyboardMapping<PianoKey> + Send>,
+    /// Provenance of the scale file the scale was imported from.
+    pub provenance: Option<ProvenanceDto>,
 }
 
 impl Scale {
-    fn from_kbm_and_scl(kbm: &KbmOptions, scl: &SclCommand) -> CliResult<Self> {
-        let kbm = kbm.to_kbm()?;
-        Ok(Scale {
-            origin: kbm
-                .kbm_root()
-                .ref_key
-                .plus_steps(kbm.kbm_root().root_offset),
-            keys: kbm.range_iter().collect(),
-            tuning: Box::new((scl.to_scl(None)?, kbm)),
-        })
+    /// Returns the equal temperament formed by the scale steps and the index of the origin within that temperament.
+    ///
+    /// The origin is named after its (12-EDO) note letter. If the scale is not equal-stepped or the letter has no counterpart in the temperament, `None` is returned.
+    fn equal_temperament(&self) -> Option<(EqualTemperament, u16)> {
+        let origin_pitch = self.tuning.maybe_pitch_of(self.origin)?;
+        let step_size = Ratio::between_pitches(
+            origin_pitch,
+            self.tuning.maybe_pitch_of(self.origin.plus_steps(1))?,
+        );
+        if step_size.as_cents() < 1.0 || step_size > Ratio::octave() {
+            return None;
+        }
+
+        let is_equal_stepped = self.keys.iter().all(|&key| {
+            self.tuning.maybe_pitch_of(key).map_or(true, |pitch| {
+                Ratio::between_pitches(origin_pitch, pitch)
+                    .deviation_from(step_size.repeated(self.origin.num_keys_before(key)))
+                    .as_cents()
+                    .abs()
+                    < 0.01
+            })
+        });
+        if !is_equal_stepped {
+            return None;
+        }
+
+        let temperament = EqualTemperament::find().by_step_size(step_size);
+        let origin_letter = Note::from_piano_key(self.origin).letter_and_octave().0;
+        let origin_names = [format!("{origin_letter:+}"), format!("{origin_letter:-}")];
+        let origin_index = (0..temperament.num_steps_per_octave()).find(|&index| {
+            temperament
+                .get_heptatonic_name(index)
+                .split('/')
+                .any(|name| origin_names.iter().any(|origin_name| origin_name == name))
+        })?;
+
+        Some((temperament, origin_index))
     }
 
-    fn from_kbm_file_and_scl(kbm_file_location: &Path, scl: &SclCommand) -> CliResult<Self> {
-        let kbm = shared::import_kbm_file(kbm_file_location)?;
-        Ok(Scale {
+    pub fn from_tuning(scl: Scl, kbm: Kbm) -> Self {
+        Scale {
             origin: kbm
                 .kbm_root()
                 .ref_key
                 .plus_steps(kbm.kbm_root().root_offset),
             keys: kbm.range_iter().collect(),
-            tuning: Box::new((scl.to_scl(None)?, kbm)),
-        })
+            tuning: Box::new((scl, kbm)),
+            provenance: None,
+        }
+    }
+
+    pub fn to_dto(&self) -> ScaleDto {
+        let items = self
+            .keys
+            .iter()
+            .filter_map(|&piano_key| {
+                self.tuning
+                    .maybe_pitch_of(piano_key)
+                    .map(|pitch| ScaleItemDto {
+                        key_midi_number: piano_key.midi_number(),
+                        pitch_in_hz: pitch.as_hz(),
+                    })
+            })
+            .collect();
+
+        ScaleDto {
+            root_key_midi_number: self.origin.midi_number(),
+            root_pitch_in_hz: self.tuning.maybe_pitch_of(self.origin).map(Pitch::as_hz),
+            items,
+            provenance: None,
+        }
     }
 
     fn from_scale_file(scale_file_location: &Path) -> CliResult<Self> {
@@ -149,15 +353,20 @@ impl Scale {
             origin: PianoKey::from_midi_number(scale_dto.root_key_midi_number),
             keys: scale_dto.keys(),
             tuning: Box::new(scale_dto.to_keyboard_mapping()),
+            provenance: scale_dto.provenance,
         })
     }
 
-    fn from_stdin(app: &mut App) -> CliResult<Self> {
-        let scale_dto = ScaleDto::read(app.read())?;
+    fn from_stdin(app: &mut App, pitch_list: &PitchListOptions) -> CliResult<Self> {
+        let scale_dto = ScaleDto::read_yaml_or_pitch_list(
+            app.read(),
+            PianoKey::from_midi_number(pitch_list.root_key),
+        )?;
         Ok(Scale {
             origin: PianoKey::from_midi_number(scale_dto.root_key_midi_number),
             keys: scale_dto.keys(),
             tuning: Box::new(scale_dto.to_keyboard_mapping()),
+            provenance: scale_dto.provenance,
         })
     }
 }
@@ -165,118 +374,431 @@ impl Scale {
 impl ScaleCommand {
     pub fn to_scale(&self, app: &mut App) -> CliResult<Scale> {
         match self {
-            ScaleCommand::WithRefNote { kbm, scl } => Scale::from_kbm_and_scl(kbm, scl),
+            ScaleCommand::WithRefNote { .. } | ScaleCommand::UseKbmFile { .. } => {
+                let (scl, kbm) = self.to_scl_and_kbm()?;
+                Ok(Scale::from_tuning(scl, kbm))
+            }
+            ScaleCommand::UseScaleFile {
+                scale_file_location,
+            } => Scale::from_scale_file(scale_file_location),
+            ScaleCommand::ReadStdin { pitch_list } => Scale::from_stdin(app, pitch_list),
+        }
+    }
+
+    fn to_scl_and_kbm(&self) -> CliResult<(Scl, Kbm)> {
+        match self {
+            ScaleCommand::WithRefNote { kbm, stretch, scl } => {
+                Ok((stretch.apply(scl.to_scl(None)?)?, kbm.to_kbm()?))
+            }
             ScaleCommand::UseKbmFile {
                 kbm_file_location,
+                stretch,
                 scl,
-            } => Scale::from_kbm_file_and_scl(kbm_file_location, scl),
+            } => Ok((
+                stretch.apply(scl.to_scl(None)?)?,
+                shared::import_kbm_file(kbm_file_location)?,
+            )),
+            ScaleCommand::UseScaleFile { .. } | ScaleCommand::ReadStdin { .. } => Err(
+                "Scale files and pitch lists cannot be converted to scl and kbm files"
+                    .to_owned()
+                    .into(),
+            ),
+        }
+    }
+
+    fn to_description(&self) -> CliResult<String> {
+        match self {
+            ScaleCommand::WithRefNote { stretch, scl, .. }
+            | ScaleCommand::UseKbmFile { stretch, scl, .. } => {
+                Ok(stretch.apply(scl.to_scl(None)?)?.description().to_owned())
+            }
             ScaleCommand::UseScaleFile {
                 scale_file_location,
-            } => Scale::from_scale_file(scale_file_location),
-            ScaleCommand::ReadStdin => Scale::from_stdin(app),
+            } => Ok(scale_file_location.display().to_string()),
+            ScaleCommand::ReadStdin { .. } => Ok("Scale read from stdin".to_owned()),
         }
     }
 
-    pub fn run(&self, app: &mut App) -> CliResult<()> {
-        let scale = self.to_scale(app)?;
+    fn to_provenance(
+        &self,
+        app: &App,
+        upstream: Option<ProvenanceDto>,
+    ) -> CliResult<ProvenanceDto> {
+        let (generator, source_file_locations) = match self {
+            ScaleCommand::WithRefNote { scl, .. } => (
+                Some(self.to_description()?),
+                vec![scl.source_file_location()],
+            ),
+            ScaleCommand::UseKbmFile {
+                kbm_file_location,
+                scl,
+                ..
+            } => (
+                Some(self.to_description()?),
+                vec![scl.source_file_location(), Some(kbm_file_location)],
+            ),
+            ScaleCommand::UseScaleFile {
+                scale_file_location,
+            } => (None, vec![Some(&**scale_file_location)]),
+            ScaleCommand::ReadStdin { .. } => (None, vec![]),
+        };
 
-        let items = scale
-            .keys
-            .iter()
-            .filter_map(|&piano_key| {
-                scale
-                    .tuning
-                    .maybe_pitch_of(piano_key)
-                    .map(|pitch| ScaleItemDto {
-                        key_midi_number: piano_key.midi_number(),
-                        pitch_in_hz: pitch.as_hz(),
-                    })
-            })
-            .collect();
+        Ok(ProvenanceDto {
+            command_line: app.command_line.clone(),
+            generator,
+            source_files: source_file_locations
+                .into_iter()
+                .flatten()
+                .map(SourceFileDto::read)
+                .collect::<CliResult<_>>()?,
+            upstream: upstream.map(Box::new),
+        })
+    }
+}
 
-        let dump = ScaleDto {
-            root_key_midi_number: scale.origin.midi_number(),
-            root_pitch_in_hz: scale.tuning.maybe_pitch_of(scale.origin).map(Pitch::as_hz),
-            items,
-        };
+impl ScaleOptions {
+    pub fn run(&self, app: &mut App) -> CliResult<()> {
+        match (&self.command, self.format) {
+            (ScaleSubcommand::Create(scale), ScaleFormat::Yaml) => {
+                print_yaml(app, scale, self.provenance)
+            }
+            (ScaleSubcommand::Create(scale), ScaleFormat::Tun) => print_tun(app, scale),
+            (ScaleSubcommand::Create(scale), ScaleFormat::SclKbm) => write_scl_and_kbm(app, scale),
+            (ScaleSubcommand::Create(scale), ScaleFormat::Chart) => {
+                let scale = scale.to_scale(app)?;
+                chart::print_chart(app, &scale, self.note_names.note_names()).map_err(Into::into)
+            }
+            (ScaleSubcommand::Create(scale), ScaleFormat::Brf) => {
+                let scale = scale.to_scale(app)?;
+                chart::print_brf_chart(app, &scale, self.note_names.note_names())
+                    .map_err(Into::into)
+            }
+            (ScaleSubcommand::Create(scale), ScaleFormat::Lilypond) => {
+                let scale = scale.to_scale(app)?;
+                chart::print_lilypond(app, &scale).map_err(Into::into)
+            }
+            (ScaleSubcommand::Create(scale), ScaleFormat::Abc) => {
+                let scale = scale.to_scale(app)?;
+                chart::print_abc(app, &scale).map_err(Into::into)
+            }
+            (ScaleSubcommand::Provenance, _) => print_provenance(app),
+        }
+    }
+}
 
-        let dto = TuneDto::Scale(dump);
+fn print_yaml(app: &mut App, scale_command: &ScaleCommand, provenance: bool) -> CliResult<()> {
+    let scale = scale_command.to_scale(app)?;
+
+    let mut scale_dto = scale.to_dto();
+    scale_dto.provenance = provenance
+        .then(|| scale_command.to_provenance(app, scale.provenance))
+        .transpose()?;
+    let dto = TuneDto::Scale(scale_dto);
+
+    app.write(format_args!(
+        "{}",
+        serde_yaml::to_string(&dto)
+            .map_err(|io_err| format!("Could not write scale file: {io_err}"))?
+    ))
+    .map_err(Into::into)
+}
 
-        app.write(format_args!(
-            "{}",
-            serde_yaml::to_string(&dto)
-                .map_err(|io_err| format!("Could not write scale file: {io_err}"))?
-        ))
+fn print_tun(app: &mut App, scale_command: &ScaleCommand) -> CliResult<()> {
+    let scale = scale_command.to_scale(app)?;
+    let description = scale_command.to_description()?;
+
+    app.write(tun::export(description, &*scale.tuning))
         .map_err(Into::into)
+}
+
+fn write_scl_and_kbm(app: &mut App, scale_command: &ScaleCommand) -> CliResult<()> {
+    let (scl, kbm) = scale_command.to_scl_and_kbm()?;
+
+    app.write_artifact("scl", scl.export())?;
+    app.write_artifact("kbm", kbm.export())?;
+    Ok(())
+}
+
+fn print_provenance(app: &mut App) -> CliResult<()> {
+    let scale_dto = ScaleDto::read(app.read())?;
+    let provenance = scale_dto
+        .provenance
+        .ok_or_else(|| "The scale file does not contain any provenance information".to_owned())?;
+
+    for line in provenance.lines() {
+        app.writeln(line)?;
     }
+    Ok(())
 }
 
 impl DumpOptions {
     pub fn run(&self, app: &mut App) -> CliResult<()> {
-        let scale = self.scale.to_scale(app)?;
-
-        let mut printer = ScaleTablePrinter {
-            app,
-            root_key: scale.origin,
-            root_pitch: scale.tuning.maybe_pitch_of(scale.origin),
-            odd_limit: self.limit.odd_limit,
-        };
-
-        printer.print_table_header()?;
-        for (source_key, pitch) in scale
-            .keys
-            .iter()
-            .flat_map(|&key| scale.tuning.maybe_pitch_of(key).map(|pitch| (key, pitch)))
-        {
-            let approximation = pitch.find_in_tuning(());
-            let (letter, octave) = approximation.approx_value.letter_and_octave();
-
-            printer.print_table_row(
-                source_key,
-                pitch,
-                approximation.approx_value.midi_number(),
-                format!("{:>6} {:>2}", letter, octave.octave_number()),
-                approximation.deviation,
-            )?;
+        let analysis = &self.analysis;
+        let note_names = self.note_names.note_names();
+        match &self.scale {
+            DumpCommand::Single(scale) => {
+                let scale = scale.to_scale(app)?;
+                dump_scale(app, &scale, analysis, note_names)?;
+            }
+            DumpCommand::Batch { kbm, batch } => {
+                let kbm = kbm.to_kbm()?;
+                batch.run(app, |app, scl| {
+                    dump_scale(
+                        app,
+                        &Scale::from_tuning(scl, kbm.clone()),
+                        analysis,
+                        note_names,
+                    )
+                })?;
+            }
         }
         Ok(())
     }
 }
 
+fn dump_scale(
+    app: &mut App,
+    scale: &Scale,
+    analysis: &AnalysisOptions,
+    note_names: NoteNames,
+) -> io::Result<DeviationStats> {
+    // Leave room for the longest note names of the convention, e.g. Sol#/Lab
+    let letter_width = match note_names {
+        NoteNames::English => 6,
+        NoteNames::German | NoteNames::Solfege | NoteNames::Byzantine => 8,
+    };
+    let target_width = TARGET_COLUMN_WIDTH + letter_width - 6;
+
+    let mut printer = ScaleTablePrinter {
+        app,
+        root_key: scale.origin,
+        root_pitch: scale.tuning.maybe_pitch_of(scale.origin),
+        temperament: analysis.notation.and_then(|_| scale.equal_temperament()),
+        harmonic_entropy: analysis.harmonic_entropy.then(HarmonicEntropy::default),
+        analysis,
+        tolerance: None,
+        stats: DeviationStats::default(),
+    };
+
+    printer.print_table_header(target_width)?;
+    for (source_key, pitch) in scale
+        .keys
+        .iter()
+        .flat_map(|&key| scale.tuning.maybe_pitch_of(key).map(|pitch| (key, pitch)))
+    {
+        let approximation = pitch.find_in_tuning(());
+        let (letter, octave) = approximation.approx_value.letter_and_octave();
+
+        printer.print_table_row(
+            source_key,
+            pitch,
+            approximation.approx_value.midi_number(),
+            format!(
+                "{:>letter_width$} {:>2}",
+                letter.named(note_names),
+                octave.octave_number()
+            ),
+            approximation.deviation,
+        )?;
+    }
+    Ok(printer.stats)
+}
+
 impl DiffOptions {
     pub fn run(&self, app: &mut App) -> CliResult<()> {
         let source_scale = self.source_scale.source_scale(app)?;
-        let (target_scl, target_kbm_root) = self.source_scale.target_tuning()?;
-
-        let mut printer = ScaleTablePrinter {
-            app,
-            root_pitch: source_scale.tuning.maybe_pitch_of(source_scale.origin),
-            root_key: source_scale.origin,
-            odd_limit: self.limit.odd_limit,
+        let analysis = &self.analysis;
+        let tolerance = self.tolerance;
+
+        let mut num_unmatched_keys = 0;
+        let mut diff_and_count = |app: &mut App, target_tuning: &(Scl, KbmRoot)| -> io::Result<_> {
+            let stats = diff_scale(app, &source_scale, target_tuning, analysis, tolerance)?;
+            num_unmatched_keys += stats.num_keys - stats.num_keys_within_tolerance;
+            Ok(stats)
         };
 
-        printer.print_table_header()?;
-        for (source_key, pitch) in source_scale.keys.iter().flat_map(|&key| {
-            source_scale
-                .tuning
-                .maybe_pitch_of(key)
-                .map(|pitch| (key, pitch))
-        }) {
-            let approximation = (&target_scl, target_kbm_root).find_by_pitch(pitch);
-            let index = target_kbm_root
-                .ref_key
-                .num_keys_before(approximation.approx_value);
-
-            printer.print_table_row(
-                source_key,
-                pitch,
-                approximation.approx_value.midi_number(),
-                format!("IDX {index:>5}"),
-                approximation.deviation,
-            )?;
+        match self.source_scale.target_scale() {
+            TargetScaleCommand::WithRefNote { kbm_root, scl } => {
+                let target_tuning = (scl.to_scl(None)?, kbm_root.to_kbm_root());
+                diff_and_count(app, &target_tuning)?;
+            }
+            TargetScaleCommand::Batch { kbm_root, batch } => {
+                let kbm_root = kbm_root.to_kbm_root();
+                batch.run(app, |app, scl| diff_and_count(app, &(scl, kbm_root)))?;
+            }
+        }
+
+        match tolerance {
+            Some(tolerance) if num_unmatched_keys > 0 => Err(format!(
+                "{num_unmatched_keys} source degree(s) not within the tolerance of {:.3}¢",
+                tolerance.as_cents()
+            )
+            .into()),
+            _ => Ok(()),
         }
+    }
+}
+
+fn diff_scale(
+    app: &mut App,
+    source_scale: &Scale,
+    (target_scl, target_kbm_root): &(Scl, KbmRoot),
+    analysis: &AnalysisOptions,
+    tolerance: Option<Ratio>,
+) -> io::Result<DeviationStats> {
+    let mut printer = ScaleTablePrinter {
+        app,
+        root_pitch: source_scale.tuning.maybe_pitch_of(source_scale.origin),
+        root_key: source_scale.origin,
+        temperament: analysis
+            .notation
+            .and_then(|_| source_scale.equal_temperament()),
+        harmonic_entropy: analysis.harmonic_entropy.then(HarmonicEntropy::default),
+        analysis,
+        tolerance,
+        stats: DeviationStats::default(),
+    };
+
+    printer.print_table_header(TARGET_COLUMN_WIDTH)?;
+    for (source_key, pitch) in source_scale.keys.iter().flat_map(|&key| {
+        source_scale
+            .tuning
+            .maybe_pitch_of(key)
+            .map(|pitch| (key, pitch))
+    }) {
+        let approximation = (target_scl, *target_kbm_root).find_by_pitch(pitch);
+        let index = target_kbm_root
+            .ref_key
+            .num_keys_before(approximation.approx_value);
+
+        printer.print_table_row(
+            source_key,
+            pitch,
+            approximation.approx_value.midi_number(),
+            format!("IDX {index:>5}"),
+            approximation.deviation,
+        )?;
+    }
+
+    if let Some(tolerance) = tolerance {
+        printer.print_summary(tolerance)?;
+    }
+
+    Ok(printer.stats)
+}
+
+impl BatchOptions {
+    /// Prints a separate section for each scl file. Files that cannot be imported are skipped with a warning.
+    fn run(
+        &self,
+        app: &mut App,
+        mut print_section: impl FnMut(&mut App, Scl) -> io::Result<DeviationStats>,
+    ) -> CliResult<()> {
+        let mut csv = String::from(
+            "file,description,num_items,period_in_cents,num_keys,mean_abs_deviation_in_cents,max_abs_deviation_in_cents\n",
+        );
+
+        for scl_file_location in self.expand_scl_file_locations()? {
+            let scl = match shared::import_scl_file(&scl_file_location) {
+                Ok(scl) => scl,
+                Err(err) => {
+                    app.errln(format_args!(
+                        "[WARNING] Skipping {} ({err})",
+                        scl_file_location.display()
+                    ))?;
+                    continue;
+                }
+            };
+
+            app.writeln(format_args!(
+                "==== {} ({}) ====",
+                scl_file_location.display(),
+                scl.description()
+            ))?;
+
+            let description = scl.description().to_owned();
+            let num_items = scl.num_items();
+            let period = scl.period();
+            let stats = print_section(app, scl)?;
+
+            app.writeln("")?;
+
+            csv.push_str(&format!(
+                "{},{},{},{:.3},{},{:.3},{:.3}\n",
+                csv_field(&scl_file_location.display().to_string()),
+                csv_field(&description),
+                num_items,
+                period.as_cents(),
+                stats.num_keys,
+                stats.mean_abs_deviation_in_cents(),
+                stats.max_abs_deviation_in_cents,
+            ));
+        }
+
+        if let Some(csv_file_location) = &self.csv_file_location {
+            fs::write(csv_file_location, csv)
+                .map_err(|io_err| format!("Could not write CSV file: {io_err}"))?;
+        }
+
         Ok(())
     }
+
+    fn expand_scl_file_locations(&self) -> CliResult<Vec<PathBuf>> {
+        let mut scl_file_locations = Vec::new();
+        for location in &self.scl_file_locations {
+            if location.is_dir() {
+                let mut files_in_dir = fs::read_dir(location)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                files_in_dir.retain(|path| {
+                    path.extension()
+                        .and_then(|extension| extension.to_str())
+                        .map(|extension| extension.eq_ignore_ascii_case("scl"))
+                        .unwrap_or(false)
+                });
+                files_in_dir.sort();
+                scl_file_locations.extend(files_in_dir);
+            } else {
+                scl_file_locations.push(location.clone());
+            }
+        }
+        Ok(scl_file_locations)
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[derive(Default)]
+struct DeviationStats {
+    num_keys: usize,
+    sum_of_abs_deviations_in_cents: f64,
+    sum_of_squared_deviations_in_cents: f64,
+    max_abs_deviation_in_cents: f64,
+    num_keys_within_tolerance: usize,
+}
+
+impl DeviationStats {
+    fn mean_abs_deviation_in_cents(&self) -> f64 {
+        if self.num_keys == 0 {
+            0.0
+        } else {
+            self.sum_of_abs_deviations_in_cents / self.num_keys as f64
+        }
+    }
+
+    fn rms_deviation_in_cents(&self) -> f64 {
+        if self.num_keys == 0 {
+            0.0
+        } else {
+            (self.sum_of_squared_deviations_in_cents / self.num_keys as f64).sqrt()
+        }
+    }
 }
 
 impl SourceScaleCommand {
@@ -286,32 +808,36 @@ impl SourceScaleCommand {
                 scale_file_location,
                 ..
             } => Scale::from_scale_file(scale_file_location),
-            SourceScaleCommand::ReadStdin { .. } => Scale::from_stdin(app),
+            SourceScaleCommand::ReadStdin { pitch_list, .. } => Scale::from_stdin(app, pitch_list),
         }
     }
 
-    pub fn target_tuning(&self) -> CliResult<(Scl, KbmRoot)> {
-        let target_scale = match self {
+    fn target_scale(&self) -> &TargetScaleCommand {
+        match self {
             SourceScaleCommand::UseScaleFile { target_scale, .. } => target_scale,
-            SourceScaleCommand::ReadStdin { target_scale } => target_scale,
-        };
-
-        let TargetScaleCommand::WithRefNote { kbm_root, scl } = target_scale;
-        Ok((scl.to_scl(None)?, kbm_root.to_kbm_root()))
+            SourceScaleCommand::ReadStdin { target_scale, .. } => target_scale,
+        }
     }
 }
 
+/// Width of the target scale column if the target notes are named in English.
+const TARGET_COLUMN_WIDTH: usize = 28;
+
 struct ScaleTablePrinter<'a, 'b> {
     app: &'a mut App<'b>,
     root_key: PianoKey,
     root_pitch: Option<Pitch>,
-    odd_limit: u16,
+    temperament: Option<(EqualTemperament, u16)>,
+    harmonic_entropy: Option<HarmonicEntropy>,
+    analysis: &'a AnalysisOptions,
+    tolerance: Option<Ratio>,
+    stats: DeviationStats,
 }
 
 impl ScaleTablePrinter<'_, '_> {
-    fn print_table_header(&mut self) -> io::Result<()> {
+    fn print_table_header(&mut self, target_width: usize) -> io::Result<()> {
         self.app.writeln(format_args!(
-            "  {source:-^33} ‖ {pitch:-^14} ‖ {target:-^28}",
+            "  {source:-^33} ‖ {pitch:-^14} ‖ {target:-^target_width$}",
             source = "Source Scale",
             pitch = "Pitch",
             target = "Target Scale"
@@ -326,6 +852,16 @@ impl ScaleTablePrinter<'_, '_> {
         target_index: String,
         deviation: Ratio,
     ) -> io::Result<()> {
+        let abs_deviation_in_cents = deviation.as_cents().abs();
+        self.stats.num_keys += 1;
+        self.stats.sum_of_abs_deviations_in_cents += abs_deviation_in_cents;
+        self.stats.sum_of_squared_deviations_in_cents +=
+            abs_deviation_in_cents * abs_deviation_in_cents;
+        self.stats.max_abs_deviation_in_cents = self
+            .stats
+            .max_abs_deviation_in_cents
+            .max(abs_deviation_in_cents);
+
         let source_index = self.root_key.num_keys_before(source_key);
         if source_index == 0 {
             self.app.write(format_args!("> "))?;
@@ -333,15 +869,24 @@ impl ScaleTablePrinter<'_, '_> {
             self.app.write(format_args!("  "))?;
         }
 
-        let nearest_fraction = Ratio::between_pitches(self.root_pitch.unwrap_or(pitch), pitch)
-            .nearest_fraction(self.odd_limit);
+        let source_degree = match (self.analysis.notation, &self.temperament) {
+            (Some(notation), Some((temperament, origin_index))) => {
+                let index = (i32::from(*origin_index) + source_index)
+                    .rem_euclid(temperament.num_steps_per_octave().into());
+                format!("{:>8}", notation.note_name(temperament, index as u16))
+            }
+            _ => format!("IDX {source_index:>4}"),
+        };
 
-        self.app.writeln(format_args!(
-            "{source_midi:>3} | IDX {source_index:>4} | \
+        let interval = Ratio::between_pitches(self.root_pitch.unwrap_or(pitch), pitch);
+        let nearest_fraction = interval.nearest_fraction(self.analysis.odd_limit);
+
+        self.app.write(format_args!(
+            "{source_midi:>3} | {source_degree} | \
              {numer:>2}/{denom:<2} {fract_deviation:>+4.0}¢ {fract_octaves:>+3}o ‖ \
              {pitch:>11.3} Hz ‖ {target_midi:>4} | {target_index} | {deviation:>+8.3}¢",
             source_midi = source_key.midi_number(),
-            source_index = source_index,
+            source_degree = source_degree,
             pitch = pitch.as_hz(),
             numer = nearest_fraction.numer,
             denom = nearest_fraction.denom,
@@ -350,6 +895,87 @@ impl ScaleTablePrinter<'_, '_> {
             target_midi = target_midi,
             target_index = target_index,
             deviation = deviation.as_cents(),
+        ))?;
+
+        if let Some(tolerance) = self.tolerance {
+            if abs_deviation_in_cents <= tolerance.as_cents().abs() {
+                self.stats.num_keys_within_tolerance += 1;
+                self.app.write(" ✓")?;
+            } else {
+                self.app.write(" ✗")?;
+            }
+        }
+
+        if let Some(harmonic_entropy) = &self.harmonic_entropy {
+            self.app.write(format_args!(
+                " HE {:.3}",
+                harmonic_entropy.of_ratio(interval)
+            ))?;
+        }
+
+        match self.analysis.uncertainty {
+            Some(uncertainty) => {
+                let uncertain_pitch = Uncertain::new(pitch, uncertainty);
+                // The target pitch is exact s.t. only the source pitch contributes to the error
+                let uncertain_deviation = Uncertain::between_pitches(
+                    Uncertain::exact(pitch / deviation),
+                    uncertain_pitch,
+                );
+                let uncertain_interval = match self.root_pitch {
+                    Some(root_pitch) if source_index != 0 => Uncertain::between_pitches(
+                        Uncertain::new(root_pitch, uncertainty),
+                        uncertain_pitch,
+                    ),
+                    _ => Uncertain::exact(Ratio::default()),
+                };
+
+                // List all nearest fractions within the error bounds. 2/1 and 1/1 + 1 octave are the same fraction.
+                let mut fractions = Vec::new();
+                for ratio in [
+                    uncertain_interval.lower_bound(),
+                    uncertain_interval.value(),
+                    uncertain_interval.upper_bound(),
+                ] {
+                    let fraction = ratio.nearest_fraction(self.analysis.odd_limit);
+                    let value = fraction.numer as f64 / fraction.denom as f64
+                        * 2f64.powi(fraction.num_octaves);
+                    if !fractions
+                        .iter()
+                        .any(|&(other_value, _)| other_value == value)
+                    {
+                        fractions.push((value, format!("{}/{}", fraction.numer, fraction.denom)));
+                    }
+                }
+                let fractions: Vec<_> = fractions.into_iter().map(|(_, name)| name).collect();
+
+                self.app.writeln(format_args!(
+                    " [{:+.3}¢, {:+.3}¢] {}",
+                    uncertain_deviation.lower_bound().as_cents(),
+                    uncertain_deviation.upper_bound().as_cents(),
+                    fractions.join("|")
+                ))
+            }
+            None => self.app.writeln(format_args!("")),
+        }
+    }
+
+    fn print_summary(&mut self, tolerance: Ratio) -> io::Result<()> {
+        self.app.writeln("")?;
+        self.app.writeln(format_args!(
+            "-- Tolerance ±{:.3}¢ --",
+            tolerance.as_cents().abs()
+        ))?;
+        self.app.writeln(format_args!(
+            "matched: {} of {}",
+            self.stats.num_keys_within_tolerance, self.stats.num_keys
+        ))?;
+        self.app.writeln(format_args!(
+            "max error: {:.3}¢",
+            self.stats.max_abs_deviation_in_cents
+        ))?;
+        self.app.writeln(format_args!(
+            "RMS error: {:.3}¢",
+            self.stats.rms_deviation_in_cents()
         ))
     }
 }
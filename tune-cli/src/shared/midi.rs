@@ -1,14 +1,29 @@
-use std::{collections::BTreeSet, error::Error, io};
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use clap::{Parser, ValueEnum};
 use midir::{MidiIO, MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
 use tune::{
     key::PianoKey,
     mts::ScaleOctaveTuningFormat,
-    tuner::{MidiTarget, TunableMidi},
+    scala::Kbm,
+    tuner::{
+        MidiTarget, MidiTunerMessage, MidiTunerMessageHandler, MpeZone, TunableMidi, UntunableKey,
+    },
 };
 
-use crate::{CliError, CliResult};
+use crate::{shared, CliError, CliResult};
+
+#[cfg(feature = "wasm")]
+mod web;
+
+#[cfg(feature = "wasm")]
+pub use web::WebMidi;
 
 #[derive(Parser)]
 pub struct MidiInArgs {
@@ -26,37 +41,165 @@ pub struct MidiInArgs {
     /// Required for keyboards with more than 128 keys like the Lumatone.
     #[arg(long = "luma-offs", default_value = "0")]
     pub lumatone_offset: i16,
+
+    /// Keyboard mapping of a single input channel, e.g. 1:+31 or 2:upper.kbm. Can be specified multiple times.
+    /// [channel:offset] Shifts the keys of the channel by the given number of keys.
+    /// [channel:kbm-file] Plays the mapped keys of the channel at the key that is the given number of scale degrees above the root of the kbm file.
+    /// The result is mapped by the keyboard mapping of the scale like any other key.
+    #[arg(long = "chan-kbm", value_parser = parse_channel_mapping)]
+    pub channel_mappings: Vec<ChannelMapping>,
+
+    /// Non-channel messages to forward from the MIDI input to the MIDI output, e.g. --fwd=sysex,clock. By default, they are dropped.
+    /// [sysex] System Exclusive messages.
+    /// [clock] Timing clock and MIDI time code.
+    /// [transport] Start, continue, stop, song position and song select.
+    /// [sensing] Active sensing.
+    #[arg(long = "fwd", value_enum, value_delimiter = ',')]
+    pub forwarded_messages: Vec<SystemMessageKind>,
 }
 
 impl MidiInArgs {
     pub fn get_midi_source(&self) -> CliResult<MidiSource> {
+        let channels: BTreeSet<_> =
+            get_channels("Input", self.in_channel, self.num_in_channels)?.collect();
+
+        let mut channel_mappings = HashMap::new();
+        for channel_mapping in &self.channel_mappings {
+            if !channels.contains(&channel_mapping.channel) {
+                return Err(format!(
+                    "Channel {} is mapped but not an input channel (see --in-chan and --in-chans)",
+                    channel_mapping.channel
+                )
+                .into());
+            }
+            let mapping = match &channel_mapping.mapping {
+                &ChannelMappingKind::Offset(offset) => KeyMapping { offset, kbm: None },
+                ChannelMappingKind::KbmFile(kbm_file_location) => KeyMapping {
+                    offset: 0,
+                    kbm: Some(Arc::new(shared::import_kbm_file(kbm_file_location)?)),
+                },
+            };
+            if channel_mappings
+                .insert(channel_mapping.channel, mapping)
+                .is_some()
+            {
+                return Err(format!(
+                    "Channel {} is mapped more than once",
+                    channel_mapping.channel
+                )
+                .into());
+            }
+        }
+
         Ok(MidiSource {
-            channels: get_channels("Input", self.in_channel, self.num_in_channels)?.collect(),
+            channels,
             lumatone_offset: self.lumatone_offset,
+            channel_mappings,
+            forwarded_messages: self.forwarded_messages.clone(),
         })
     }
 }
 
+/// Keyboard mapping of a single input channel.
+#[derive(Clone, Debug)]
+pub struct ChannelMapping {
+    pub channel: u8,
+    pub mapping: ChannelMappingKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum ChannelMappingKind {
+    Offset(i32),
+    KbmFile(PathBuf),
+}
+
+fn parse_channel_mapping(src: &str) -> Result<ChannelMapping, String> {
+    let (channel, mapping) = src
+        .split_once(':')
+        .ok_or("Invalid channel mapping. Should be <channel>:<offset> or <channel>:<kbm-file>")?;
+    let channel = parse_channel(channel)?;
+    let mapping = match mapping.parse() {
+        Ok(offset) => ChannelMappingKind::Offset(offset),
+        Err(_) => ChannelMappingKind::KbmFile(mapping.into()),
+    };
+    Ok(ChannelMapping { channel, mapping })
+}
+
+/// Categories of MIDI messages that are not bound to a channel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SystemMessageKind {
+    #[value(name = "sysex")]
+    SysEx,
+    Clock,
+    Transport,
+    #[value(name = "sensing")]
+    ActiveSensing,
+}
+
+impl SystemMessageKind {
+    /// Returns [`None`] if the message is a channel message or a system message not covered by any category.
+    pub fn of_raw_message(raw_message: &[u8]) -> Option<Self> {
+        match raw_message.first()? {
+            0xf0 | 0xf7 => Some(Self::SysEx),
+            0xf1 | 0xf8 => Some(Self::Clock),
+            0xf2 | 0xf3 | 0xfa | 0xfb | 0xfc => Some(Self::Transport),
+            0xfe => Some(Self::ActiveSensing),
+            _ => None,
+        }
+    }
+}
+
 pub struct MidiSource {
     pub channels: BTreeSet<u8>,
     pub lumatone_offset: i16,
+    pub channel_mappings: HashMap<u8, KeyMapping>,
+    pub forwarded_messages: Vec<SystemMessageKind>,
 }
 
 impl MidiSource {
+    /// Determines whether the given non-channel message should be forwarded to the MIDI output unmodified.
+    pub fn forwards(&self, raw_message: &[u8]) -> bool {
+        SystemMessageKind::of_raw_message(raw_message)
+            .map(|kind| self.forwarded_messages.contains(&kind))
+            .unwrap_or(false)
+    }
+
     pub fn get_offset(&self, channel: u8) -> MultiChannelOffset {
+        let lumatone_offset = i32::from(channel) * i32::from(self.lumatone_offset);
+        let mapping = self.channel_mappings.get(&channel);
         MultiChannelOffset {
-            offset: i32::from(channel) * i32::from(self.lumatone_offset),
+            offset: lumatone_offset + mapping.map(|mapping| mapping.offset).unwrap_or(0),
+            kbm: mapping.and_then(|mapping| mapping.kbm.clone()),
         }
     }
 }
 
+/// The resolved [`ChannelMapping`] of a single input channel.
+#[derive(Clone, Debug)]
+pub struct KeyMapping {
+    offset: i32,
+    kbm: Option<Arc<Kbm>>,
+}
+
 pub struct MultiChannelOffset {
     offset: i32,
+    kbm: Option<Arc<Kbm>>,
 }
 
 impl MultiChannelOffset {
-    pub fn get_piano_key(&self, midi_number: u8) -> PianoKey {
-        PianoKey::from_midi_number(i32::from(midi_number) + self.offset)
+    /// Returns [`None`] if the key is not mapped by the kbm file of the channel.
+    pub fn get_piano_key(&self, midi_number: u8) -> Option<PianoKey> {
+        let piano_key = PianoKey::from_midi_number(midi_number);
+        let piano_key = match &self.kbm {
+            Some(kbm) => {
+                let kbm_root = kbm.kbm_root();
+                kbm_root
+                    .ref_key
+                    .plus_steps(kbm_root.root_offset + kbm.scale_degree_of(piano_key)?)
+            }
+            None => piano_key,
+        };
+        Some(piano_key.plus_steps(self.offset))
     }
 }
 
@@ -69,6 +212,7 @@ pub struct MidiOutArgs {
     /// Number of MIDI output channels that should be retuned.
     /// Wraps around at zero-based channel number 15.
     /// For example --out-chan=10 and --out-chans=15 uses all channels but the drum channel.
+    /// In MPE mode, this is the number of member channels and --out-chan is ignored.
     #[arg(long = "out-chans", default_value = "9")]
     pub num_out_channels: u8,
 
@@ -79,18 +223,71 @@ pub struct MidiOutArgs {
     /// Wraps around at tuning program number 127.
     #[arg(long = "tun-pg", default_value = "0")]
     pub tuning_program: u8,
+
+    /// Pitch bend range of the MPE member channels in semitones
+    #[arg(long = "mpe-pb-range", default_value = "48")]
+    pub mpe_pitch_bend_range: u8,
+
+    /// Tuning methods supported by the target device, e.g. --dev-caps=full,octave-2.
+    /// The auto tuning method selects the most precise of them.
+    /// Devices without known capabilities are retuned via pitch bend.
+    #[arg(long = "dev-caps", value_delimiter = ',')]
+    pub device_capabilities: Vec<TuningMethod>,
 }
 
 impl MidiOutArgs {
-    pub fn get_midi_target<H>(&self, handler: H) -> CliResult<MidiTarget<H>> {
-        Ok(MidiTarget {
+    /// Resolves [`TuningMethod::Auto`] to the most precise tuning method supported by the target device.
+    pub fn resolve_tuning_method(&self, method: TuningMethod) -> TuningMethod {
+        method.resolve(&self.device_capabilities)
+    }
+
+    /// Lists the tuning methods [`TuningMethod::Auto`] can fall back to if the tuning exceeds the channels of the more precise methods.
+    pub fn tuning_method_candidates(&self, method: TuningMethod) -> Vec<TuningMethod> {
+        method.candidates(&self.device_capabilities)
+    }
+
+    pub fn get_midi_target<H>(&self, handler: H, method: TuningMethod) -> CliResult<MidiTarget<H>> {
+        let channels: Vec<_> = match self.resolve_tuning_method(method).mpe_zone() {
+            Some(zone) => {
+                if self.num_out_channels > 15 {
+                    return Err("Cannot use more than 15 MPE member channels"
+                        .to_owned()
+                        .into());
+                }
+                zone.member_channels(self.num_out_channels).collect()
+            }
+            None => get_channels("Output", self.out_channel, self.num_out_channels)?.collect(),
+        };
+
+        Ok(MidiTarget { handler, channels })
+    }
+
+    /// Creates a [`TunableMidi`] instance that only addresses the given output channel s.t. the channel can be tuned independently of the retuning group.
+    ///
+    /// A single channel cannot form an MPE zone. Therefore, the MPE tuning methods fall back to [`TuningMethod::PitchBend`].
+    pub fn create_channel_synth<H: MidiTunerMessageHandler>(
+        &self,
+        handler: H,
+        channel: u8,
+        method: TuningMethod,
+    ) -> TunableMidi<H> {
+        let method = match self.resolve_tuning_method(method) {
+            TuningMethod::MpeLower | TuningMethod::MpeUpper => TuningMethod::PitchBend,
+            method => method,
+        };
+        let target = MidiTarget {
             handler,
-            channels: get_channels("Output", self.out_channel, self.num_out_channels)?.collect(),
-        })
+            channels: vec![channel],
+        };
+        self.create_synth(target, method)
     }
 
-    pub fn create_synth<H>(&self, target: MidiTarget<H>, method: TuningMethod) -> TunableMidi<H> {
-        match method {
+    pub fn create_synth<H: MidiTunerMessageHandler>(
+        &self,
+        target: MidiTarget<H>,
+        method: TuningMethod,
+    ) -> TunableMidi<H> {
+        match self.resolve_tuning_method(method) {
             TuningMethod::FullKeyboard => TunableMidi::single_note_tuning_change(
                 target,
                 false,
@@ -129,6 +326,13 @@ impl MidiOutArgs {
             ),
             TuningMethod::ChannelFineTuning => TunableMidi::channel_fine_tuning(target),
             TuningMethod::PitchBend => TunableMidi::pitch_bend(target),
+            TuningMethod::MpeLower => {
+                TunableMidi::mpe(target, MpeZone::Lower, self.mpe_pitch_bend_range)
+            }
+            TuningMethod::MpeUpper => {
+                TunableMidi::mpe(target, MpeZone::Upper, self.mpe_pitch_bend_range)
+            }
+            TuningMethod::Auto => unreachable!("Auto is always resolved"),
         }
     }
 }
@@ -151,6 +355,14 @@ fn get_channels(
     Ok((0..num_channels).map(move |channel| (first_channel + channel) % 16))
 }
 
+/// Parses a zero-based MIDI channel number.
+pub fn parse_channel(src: &str) -> Result<u8, String> {
+    src.parse()
+        .ok()
+        .filter(|&channel| channel < 16)
+        .ok_or_else(|| format!("Invalid channel `{src}`. Should be in the range [0..16)"))
+}
+
 #[derive(Parser)]
 pub struct DeviceIdArg {
     /// ID of the device that should respond to MTS messages
@@ -158,7 +370,7 @@ pub struct DeviceIdArg {
     pub device_id: u8,
 }
 
-#[derive(Copy, Clone, ValueEnum)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 pub enum TuningMethod {
     #[value(name = "full")]
     FullKeyboard,
@@ -176,6 +388,87 @@ pub enum TuningMethod {
     ChannelFineTuning,
     #[value(name = "pitch-bend")]
     PitchBend,
+    #[value(name = "mpe-lower")]
+    MpeLower,
+    #[value(name = "mpe-upper")]
+    MpeUpper,
+    /// Select the most precise method supported by the target device (see --dev-caps)
+    #[value(name = "auto")]
+    Auto,
+}
+
+impl TuningMethod {
+    /// Order in which [`TuningMethod::Auto`] selects the supported methods: Full keyboard > octave > MPE > fine tuning > pitch bend.
+    const PREFERENCE_ORDER: [TuningMethod; 10] = [
+        TuningMethod::FullKeyboard,
+        TuningMethod::FullKeyboardRt,
+        TuningMethod::Octave2,
+        TuningMethod::Octave2Rt,
+        TuningMethod::Octave1,
+        TuningMethod::Octave1Rt,
+        TuningMethod::MpeLower,
+        TuningMethod::MpeUpper,
+        TuningMethod::ChannelFineTuning,
+        TuningMethod::PitchBend,
+    ];
+
+    /// Resolves [`TuningMethod::Auto`] to the most preferable of the `supported` methods. Falls back to [`TuningMethod::PitchBend`] which every device understands.
+    ///
+    /// All other methods are returned unchanged.
+    pub fn resolve(self, supported: &[TuningMethod]) -> TuningMethod {
+        self.candidates(supported)[0]
+    }
+
+    /// Lists the methods [`TuningMethod::Auto`] can resolve to, from the most to the least preferable. The list always ends with [`TuningMethod::PitchBend`].
+    ///
+    /// For all other methods, the list only contains the method itself.
+    pub fn candidates(self, supported: &[TuningMethod]) -> Vec<TuningMethod> {
+        match self {
+            TuningMethod::Auto => Self::PREFERENCE_ORDER
+                .into_iter()
+                .filter(|method| supported.contains(method) || *method == TuningMethod::PitchBend)
+                .collect(),
+            method => vec![method],
+        }
+    }
+
+    /// The name of the method on the command line.
+    pub fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().to_owned()
+    }
+
+    pub fn mpe_zone(self) -> Option<MpeZone> {
+        match self {
+            TuningMethod::MpeLower => Some(MpeZone::Lower),
+            TuningMethod::MpeUpper => Some(MpeZone::Upper),
+            _ => None,
+        }
+    }
+}
+
+/// Lists the keys that cannot be tuned because they require more channels than available, together with the note and detuning they would be played with.
+///
+/// At most `max_keys` keys are listed.
+pub fn write_untunable_keys<K: Copy>(
+    target: &mut impl fmt::Write,
+    untunable_keys: &[UntunableKey<K>],
+    max_keys: usize,
+    describe_key: impl Fn(K) -> String,
+) -> fmt::Result {
+    for untunable_key in untunable_keys.iter().take(max_keys) {
+        writeln!(
+            target,
+            "- {}: {} {:+.1}c on channel {}",
+            describe_key(untunable_key.key),
+            untunable_key.note,
+            untunable_key.detuning.as_cents(),
+            untunable_key.channel
+        )?;
+    }
+    if untunable_keys.len() > max_keys {
+        writeln!(target, "- ... and {} more", untunable_keys.len() - max_keys)?;
+    }
+    Ok(())
 }
 
 pub type MidiResult<T> = Result<T, MidiError>;
@@ -205,17 +498,104 @@ impl From<MidiError> for CliError {
     }
 }
 
-pub fn print_midi_devices(mut dst: impl io::Write, client_name: &str) -> MidiResult<()> {
-    let midi_input = MidiInput::new(client_name)?;
+/// Access to the MIDI devices of the platform.
+///
+/// Native builds use [`Midir`]. Environments without native MIDI support, e.g. the browser, can plug in their own implementation.
+pub trait MidiBackend {
+    fn in_device_names(&self) -> MidiResult<Vec<String>>;
+
+    fn out_device_names(&self) -> MidiResult<Vec<String>>;
+
+    /// Connects to the readable device whose name contains `fuzzy_port_name` (case-insensitive).
+    fn connect_in(
+        &self,
+        fuzzy_port_name: &str,
+        callback: MidiInCallback,
+    ) -> MidiResult<(String, Box<dyn MidiInConnection>)>;
+
+    /// Connects to the writable device whose name contains `fuzzy_port_name` (case-insensitive).
+    fn connect_out(
+        &self,
+        fuzzy_port_name: &str,
+    ) -> MidiResult<(String, Box<dyn MidiOutConnection>)>;
+}
+
+/// Receives the raw bytes of every incoming message.
+pub type MidiInCallback = Box<dyn FnMut(&[u8]) + Send>;
+
+/// Keeps receiving messages until it is dropped.
+pub trait MidiInConnection {}
+
+pub trait MidiOutConnection {
+    fn send(&mut self, message: &[u8]) -> MidiResult<()>;
+}
+
+/// [`MidiBackend`] based on the native MIDI APIs supported by `midir`.
+pub struct Midir {
+    client_name: String,
+}
+
+impl Midir {
+    pub fn new(client_name: impl Into<String>) -> Self {
+        Self {
+            client_name: client_name.into(),
+        }
+    }
+}
+
+impl MidiBackend for Midir {
+    fn in_device_names(&self) -> MidiResult<Vec<String>> {
+        let midi_input = MidiInput::new(&self.client_name)?;
+        Ok(named_ports(&midi_input)
+            .into_iter()
+            .map(|(port_name, _)| port_name)
+            .collect())
+    }
+
+    fn out_device_names(&self) -> MidiResult<Vec<String>> {
+        let midi_output = MidiOutput::new(&self.client_name)?;
+        Ok(named_ports(&midi_output)
+            .into_iter()
+            .map(|(port_name, _)| port_name)
+            .collect())
+    }
+
+    fn connect_in(
+        &self,
+        fuzzy_port_name: &str,
+        callback: MidiInCallback,
+    ) -> MidiResult<(String, Box<dyn MidiInConnection>)> {
+        let (port_name, connection) =
+            connect_to_in_device(&self.client_name, fuzzy_port_name, callback)?;
+        Ok((port_name, Box::new(connection)))
+    }
+
+    fn connect_out(
+        &self,
+        fuzzy_port_name: &str,
+    ) -> MidiResult<(String, Box<dyn MidiOutConnection>)> {
+        let (port_name, connection) = connect_to_out_device(&self.client_name, fuzzy_port_name)?;
+        Ok((port_name, Box::new(connection)))
+    }
+}
+
+impl<T> MidiInConnection for MidiInputConnection<T> {}
+
+impl MidiOutConnection for MidiOutputConnection {
+    fn send(&mut self, message: &[u8]) -> MidiResult<()> {
+        Ok(MidiOutputConnection::send(self, message)?)
+    }
+}
+
+pub fn print_midi_devices(mut dst: impl io::Write, backend: &dyn MidiBackend) -> MidiResult<()> {
     writeln!(dst, "Readable MIDI devices:")?;
-    for port in midi_input.ports() {
-        writeln!(dst, "- {}", midi_input.port_name(&port)?)?;
+    for port_name in backend.in_device_names()? {
+        writeln!(dst, "- {port_name}")?;
     }
 
-    let midi_output = MidiOutput::new(client_name)?;
     writeln!(dst, "Writable MIDI devices:")?;
-    for port in midi_output.ports() {
-        writeln!(dst, "- {}", midi_output.port_name(&port)?)?;
+    for port_name in backend.out_device_names()? {
+        writeln!(dst, "- {port_name}")?;
     }
 
     Ok(())
@@ -228,7 +608,7 @@ pub fn connect_to_in_device(
 ) -> MidiResult<(String, MidiInputConnection<()>)> {
     let midi_input = MidiInput::new(client_name)?;
 
-    let (port_name, port) = find_port_by_name(&midi_input, fuzzy_port_name)?;
+    let (port_name, port) = find_port_by_name(named_ports(&midi_input), fuzzy_port_name)?;
 
     Ok((
         port_name,
@@ -247,36 +627,59 @@ pub fn connect_to_out_device(
 ) -> MidiResult<(String, MidiOutputConnection)> {
     let midi_output = MidiOutput::new(client_name)?;
 
-    let (port_name, port) = find_port_by_name(&midi_output, fuzzy_port_name)?;
+    let (port_name, port) = find_port_by_name(named_ports(&midi_output), fuzzy_port_name)?;
 
     Ok((port_name, midi_output.connect(&port, "MIDI in")?))
 }
 
-fn find_port_by_name<IO: MidiIO>(
-    midi_io: &IO,
-    target_port: &str,
-) -> MidiResult<(String, IO::Port)> {
-    let target_port_lowercase = target_port.to_lowercase();
+/// In-memory MIDI output device recording the raw bytes of all messages sent to it.
+///
+/// Clones share the same recording s.t. one clone can be handed over to a [`MidiTarget`] while another one is used for inspection.
+#[derive(Clone, Default)]
+pub struct VirtualMidiOut {
+    messages: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl VirtualMidiOut {
+    /// Returns all messages recorded so far and clears the recording.
+    pub fn take_messages(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.messages.lock().unwrap())
+    }
+}
+
+impl MidiTunerMessageHandler for VirtualMidiOut {
+    fn handle(&mut self, message: MidiTunerMessage) {
+        let mut messages = self.messages.lock().unwrap();
+        message.send_to(|bytes| messages.push(bytes.to_vec()));
+    }
+}
 
-    let mut matching_ports = midi_io
+fn named_ports<IO: MidiIO>(midi_io: &IO) -> Vec<(String, IO::Port)> {
+    midi_io
         .ports()
         .into_iter()
         .filter_map(|port| {
             midi_io
                 .port_name(&port)
                 .ok()
-                .filter(|port_name| port_name.to_lowercase().contains(&target_port_lowercase))
                 .map(|port_name| (port_name, port))
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+fn find_port_by_name<P>(ports: Vec<(String, P)>, target_port: &str) -> MidiResult<(String, P)> {
+    let target_port_lowercase = target_port.to_lowercase();
+
+    let (mut matching_ports, other_ports): (Vec<_>, Vec<_>) = ports
+        .into_iter()
+        .partition(|(port_name, _)| port_name.to_lowercase().contains(&target_port_lowercase));
 
     match matching_ports.len() {
         0 => Err(MidiError::DeviceNotFound {
             wanted: target_port_lowercase,
-            available: midi_io
-                .ports()
-                .iter()
-                .filter_map(|port| midi_io.port_name(port).ok())
+            available: other_ports
+                .into_iter()
+                .map(|(port_name, _)| port_name)
                 .collect(),
         }),
         1 => Ok(matching_ports.pop().unwrap()),
@@ -289,3 +692,296 @@ fn find_port_by_name<IO: MidiIO>(
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fmt::Write, fs, process};
+
+    use clap::Parser;
+    use tune::{
+        midi::ChannelMessageType,
+        note::{Note, NoteLetter},
+        pitch::Ratio,
+        scala::{KbmRoot, Scl},
+        tuner::{AotTuner, JitTuner, PoolingMode},
+        tuning::Tuning,
+    };
+
+    use super::*;
+
+    const ALL_METHODS: [(&str, TuningMethod); 10] = [
+        ("full", TuningMethod::FullKeyboard),
+        ("full_rt", TuningMethod::FullKeyboardRt),
+        ("octave_1", TuningMethod::Octave1),
+        ("octave_1_rt", TuningMethod::Octave1Rt),
+        ("octave_2", TuningMethod::Octave2),
+        ("octave_2_rt", TuningMethod::Octave2Rt),
+        ("fine_tuning", TuningMethod::ChannelFineTuning),
+        ("pitch_bend", TuningMethod::PitchBend),
+        ("mpe_lower", TuningMethod::MpeLower),
+        ("mpe_upper", TuningMethod::MpeUpper),
+    ];
+
+    #[test]
+    fn midi_out_byte_sequences() {
+        for (name, method) in ALL_METHODS {
+            let mut output = String::new();
+            record_aot_session(&mut output, method, 2);
+            record_aot_session(&mut output, method, 1);
+            record_jit_session(&mut output, method, PoolingMode::Stop);
+            record_jit_session(&mut output, method, PoolingMode::Block);
+            record_jit_session(&mut output, method, PoolingMode::Ignore);
+
+            let file_name = format!("tests/snapshots/midi_out_{name}.txt");
+            std::fs::write(&file_name, &output).unwrap();
+            assert_eq!(output, read_snapshot(name), "{file_name}");
+        }
+    }
+
+    #[test]
+    fn resolve_auto_tuning_method() {
+        let resolve = |capabilities: &[&str]| {
+            let mut args = vec!["midi-out".to_owned()];
+            if !capabilities.is_empty() {
+                args.push(format!("--dev-caps={}", capabilities.join(",")));
+            }
+            MidiOutArgs::try_parse_from(args)
+                .unwrap()
+                .resolve_tuning_method(TuningMethod::Auto)
+        };
+
+        assert_eq!(resolve(&[]), TuningMethod::PitchBend);
+        assert_eq!(resolve(&["fine-tuning"]), TuningMethod::ChannelFineTuning);
+        assert_eq!(
+            resolve(&["pitch-bend", "mpe-upper"]),
+            TuningMethod::MpeUpper
+        );
+        assert_eq!(resolve(&["mpe-lower", "octave-1"]), TuningMethod::Octave1);
+        assert_eq!(
+            resolve(&["octave-1", "octave-2", "full-rt"]),
+            TuningMethod::FullKeyboardRt
+        );
+        assert_eq!(resolve(&["auto"]), TuningMethod::PitchBend);
+
+        assert_eq!(
+            TuningMethod::Octave1.resolve(&[TuningMethod::FullKeyboard]),
+            TuningMethod::Octave1
+        );
+
+        assert_eq!(
+            TuningMethod::Auto.candidates(&[TuningMethod::Octave1, TuningMethod::FullKeyboard]),
+            [
+                TuningMethod::FullKeyboard,
+                TuningMethod::Octave1,
+                TuningMethod::PitchBend
+            ]
+        );
+        assert_eq!(
+            TuningMethod::Octave1.candidates(&[TuningMethod::FullKeyboard]),
+            [TuningMethod::Octave1]
+        );
+    }
+
+    #[test]
+    fn forward_selected_system_messages() {
+        let source = MidiInArgs::try_parse_from(["midi-in", "--fwd=sysex,transport"])
+            .unwrap()
+            .get_midi_source()
+            .unwrap();
+
+        assert!(source.forwards(&[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7]));
+        assert!(source.forwards(&[0xfa]));
+        assert!(source.forwards(&[0xf2, 0x10, 0x00]));
+        assert!(!source.forwards(&[0xf8]));
+        assert!(!source.forwards(&[0xfe]));
+        assert!(!source.forwards(&[0x90, 60, 100]));
+        assert!(!source.forwards(&[]));
+
+        let source = MidiInArgs::try_parse_from(["midi-in"])
+            .unwrap()
+            .get_midi_source()
+            .unwrap();
+        assert!(!source.forwards(&[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7]));
+    }
+
+    #[test]
+    fn map_keys_per_channel() {
+        let kbm_file_location =
+            env::temp_dir().join(format!("tune-cli-chan-kbm-test-{}.kbm", process::id()));
+        let white_keys = Kbm::builder(Note::from_midi_number(60))
+            .push_mapped_key(0)
+            .push_unmapped_key()
+            .push_mapped_key(1)
+            .formal_octave(2)
+            .build()
+            .unwrap();
+        fs::write(&kbm_file_location, white_keys.export().to_string()).unwrap();
+
+        let source = MidiInArgs::try_parse_from([
+            "midi-in".to_owned(),
+            "--luma-offs=10".to_owned(),
+            "--chan-kbm=1:+31".to_owned(),
+            format!("--chan-kbm=2:{}", kbm_file_location.display()),
+        ])
+        .unwrap()
+        .get_midi_source();
+        fs::remove_file(&kbm_file_location).unwrap();
+        let source = source.unwrap();
+
+        let key = |channel, midi_number| {
+            source
+                .get_offset(channel)
+                .get_piano_key(midi_number)
+                .map(|piano_key| piano_key.midi_number())
+        };
+        assert_eq!(key(0, 60), Some(60));
+        assert_eq!(key(1, 60), Some(101));
+        assert_eq!(key(2, 60), Some(80));
+        assert_eq!(key(2, 61), None);
+        assert_eq!(key(2, 62), Some(81));
+        assert_eq!(key(2, 63), Some(82));
+
+        assert!(MidiInArgs::try_parse_from(["midi-in", "--chan-kbm=16:+31"]).is_err());
+
+        let source = |args: &[&str]| {
+            MidiInArgs::try_parse_from(["midi-in"].iter().chain(args))
+                .unwrap()
+                .get_midi_source()
+        };
+        assert!(source(&["--in-chan=2", "--in-chans=3", "--chan-kbm=4:+31"]).is_ok());
+        assert!(source(&["--in-chan=2", "--in-chans=3", "--chan-kbm=1:+31"]).is_err());
+        assert!(source(&["--in-chan=15", "--in-chans=2", "--chan-kbm=0:+31"]).is_ok());
+        assert!(source(&["--chan-kbm=1:+31", "--chan-kbm=1:+12"]).is_err());
+    }
+
+    #[test]
+    fn single_channel_synth() {
+        let args = MidiOutArgs::try_parse_from(["midi-out"]).unwrap();
+
+        // MPE falls back to pitch bend
+        let midi_out = VirtualMidiOut::default();
+        let mut tuner = AotTuner::start(args.create_channel_synth(
+            midi_out.clone(),
+            12,
+            TuningMethod::MpeLower,
+        ));
+        let (scl, kbm_root) = quarter_tone_tuning();
+        let keys = (60..64).step_by(2).map(PianoKey::from_midi_number);
+        assert_eq!(tuner.set_tuning((scl, kbm_root.to_kbm()), keys), Ok(1));
+        tuner.note_on(PianoKey::from_midi_number(62), 100);
+        assert_eq!(
+            midi_out.take_messages(),
+            [vec![0xec, 0x00, 0x40], vec![0x9c, 62, 100]]
+        );
+    }
+
+    fn read_snapshot(name: &str) -> &'static str {
+        match name {
+            "full" => include_str!("../../tests/snapshots/midi_out_full.txt"),
+            "full_rt" => include_str!("../../tests/snapshots/midi_out_full_rt.txt"),
+            "octave_1" => include_str!("../../tests/snapshots/midi_out_octave_1.txt"),
+            "octave_1_rt" => include_str!("../../tests/snapshots/midi_out_octave_1_rt.txt"),
+            "octave_2" => include_str!("../../tests/snapshots/midi_out_octave_2.txt"),
+            "octave_2_rt" => include_str!("../../tests/snapshots/midi_out_octave_2_rt.txt"),
+            "fine_tuning" => include_str!("../../tests/snapshots/midi_out_fine_tuning.txt"),
+            "pitch_bend" => include_str!("../../tests/snapshots/midi_out_pitch_bend.txt"),
+            "mpe_lower" => include_str!("../../tests/snapshots/midi_out_mpe_lower.txt"),
+            "mpe_upper" => include_str!("../../tests/snapshots/midi_out_mpe_upper.txt"),
+            _ => unreachable!(),
+        }
+    }
+
+    /// 24-EDO quarter-tone tuning with D4 at its 12-EDO pitch.
+    fn quarter_tone_tuning() -> (Scl, KbmRoot) {
+        let scl = Scl::builder()
+            .push_ratio(Ratio::from_semitones(0.5))
+            .build()
+            .unwrap();
+        (scl, KbmRoot::from(NoteLetter::D.in_octave(4)))
+    }
+
+    /// Creates a virtual MIDI target using the output channels 15 and 0 s.t. channel wrap-around is covered.
+    fn create_synth(
+        method: TuningMethod,
+        num_channels: u8,
+    ) -> (VirtualMidiOut, TunableMidi<VirtualMidiOut>) {
+        let args = MidiOutArgs::try_parse_from([
+            "midi-out",
+            "--out-chan",
+            "15",
+            "--out-chans",
+            &num_channels.to_string(),
+        ])
+        .unwrap();
+        let midi_out = VirtualMidiOut::default();
+        let target = args.get_midi_target(midi_out.clone(), method).unwrap();
+        (midi_out, args.create_synth(target, method))
+    }
+
+    fn record_aot_session(output: &mut String, method: TuningMethod, num_channels: u8) {
+        let (midi_out, synth) = create_synth(method, num_channels);
+        let mut tuner = AotTuner::start(synth);
+
+        writeln!(output, "==== AOT, {num_channels} channel(s) ====").unwrap();
+
+        let (scl, kbm_root) = quarter_tone_tuning();
+        let keys = (60..64).map(PianoKey::from_midi_number);
+        let required_channels = tuner.set_tuning((scl, kbm_root.to_kbm()), keys).unwrap();
+        writeln!(
+            output,
+            "-- set tuning (requires {required_channels} channel(s), tuned: {}) --",
+            tuner.tuned()
+        )
+        .unwrap();
+        write_untunable_keys(output, tuner.untunable_keys(), usize::MAX, |key| {
+            format!("key {}", key.midi_number())
+        })
+        .unwrap();
+        print_messages(output, &midi_out);
+
+        for midi_number in [60, 61, 63] {
+            tuner.note_on(PianoKey::from_midi_number(midi_number), 100);
+        }
+        tuner.note_attr(PianoKey::from_midi_number(61), 50);
+        tuner.global_attr(ChannelMessageType::PitchBendChange { value: 1000 });
+        for midi_number in [60, 61, 63] {
+            tuner.note_off(PianoKey::from_midi_number(midi_number), 0);
+        }
+        writeln!(output, "-- play notes 60, 61, 63 --").unwrap();
+        print_messages(output, &midi_out);
+    }
+
+    fn record_jit_session(output: &mut String, method: TuningMethod, pooling_mode: PoolingMode) {
+        let (midi_out, synth) = create_synth(method, 2);
+        let mut tuner = JitTuner::start(synth, pooling_mode);
+        let tuning = quarter_tone_tuning();
+
+        writeln!(output, "==== JIT, 2 channels, {pooling_mode:?} ====").unwrap();
+
+        for degree in [0, 1, 3] {
+            tuner.note_on(degree, tuning.pitch_of(degree), 100);
+            writeln!(output, "-- note on degree {degree} --").unwrap();
+            print_messages(output, &midi_out);
+        }
+
+        tuner.note_pitch(1, tuning.pitch_of(2));
+        writeln!(output, "-- move degree 1 to the pitch of degree 2 --").unwrap();
+        print_messages(output, &midi_out);
+
+        for degree in [0, 1, 3] {
+            tuner.note_off(degree, 0);
+        }
+        writeln!(output, "-- note off degrees 0, 1, 3 --").unwrap();
+        print_messages(output, &midi_out);
+    }
+
+    fn print_messages(output: &mut String, midi_out: &VirtualMidiOut) {
+        for message in midi_out.take_messages() {
+            for (index, byte) in message.iter().enumerate() {
+                let separator = if index == 0 { "" } else { " " };
+                write!(output, "{separator}{byte:02x}").unwrap();
+            }
+            writeln!(output).unwrap();
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use js_sys::{Map, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{MidiAccess, MidiInput, MidiMessageEvent, MidiOutput, MidiPort};
+
+use super::{
+    find_port_by_name, MidiBackend, MidiError, MidiInCallback, MidiInConnection, MidiOutConnection,
+    MidiResult,
+};
+
+/// [`MidiBackend`] based on the Web MIDI API of the browser.
+///
+/// Requesting MIDI access is asynchronous and requires the user's permission. Therefore, the host application is responsible for obtaining the [`MidiAccess`] object, e.g. via `navigator.requestMIDIAccess({ sysex: true })`. SysEx permission is needed to send MTS messages.
+pub struct WebMidi {
+    access: Result<MidiAccess, String>,
+}
+
+impl WebMidi {
+    pub fn new(access: MidiAccess) -> Self {
+        Self { access: Ok(access) }
+    }
+
+    /// Creates a [`WebMidi`] instance that fails on every MIDI operation, e.g. because the user denied the MIDI access.
+    pub fn unavailable(reason: impl Into<String>) -> Self {
+        Self {
+            access: Err(reason.into()),
+        }
+    }
+
+    fn access(&self) -> MidiResult<&MidiAccess> {
+        self.access
+            .as_ref()
+            .map_err(|reason| MidiError::Other(reason.clone()))
+    }
+
+    fn in_ports(&self) -> MidiResult<Vec<(String, MidiInput)>> {
+        Ok(named_ports(self.access()?.inputs().unchecked_into()))
+    }
+
+    fn out_ports(&self) -> MidiResult<Vec<(String, MidiOutput)>> {
+        Ok(named_ports(self.access()?.outputs().unchecked_into()))
+    }
+}
+
+impl MidiBackend for WebMidi {
+    fn in_device_names(&self) -> MidiResult<Vec<String>> {
+        Ok(self
+            .in_ports()?
+            .into_iter()
+            .map(|(port_name, _)| port_name)
+            .collect())
+    }
+
+    fn out_device_names(&self) -> MidiResult<Vec<String>> {
+        Ok(self
+            .out_ports()?
+            .into_iter()
+            .map(|(port_name, _)| port_name)
+            .collect())
+    }
+
+    fn connect_in(
+        &self,
+        fuzzy_port_name: &str,
+        mut callback: MidiInCallback,
+    ) -> MidiResult<(String, Box<dyn MidiInConnection>)> {
+        let (port_name, port) = find_port_by_name(self.in_ports()?, fuzzy_port_name)?;
+
+        let on_message = Closure::wrap(Box::new(move |event: MidiMessageEvent| {
+            if let Ok(message) = event.data() {
+                callback(&message);
+            }
+        }) as Box<dyn FnMut(MidiMessageEvent)>);
+        port.set_onmidimessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Ok((
+            port_name,
+            Box::new(WebMidiInConnection { port, on_message }),
+        ))
+    }
+
+    fn connect_out(
+        &self,
+        fuzzy_port_name: &str,
+    ) -> MidiResult<(String, Box<dyn MidiOutConnection>)> {
+        let (port_name, port) = find_port_by_name(self.out_ports()?, fuzzy_port_name)?;
+        Ok((port_name, Box::new(port)))
+    }
+}
+
+struct WebMidiInConnection {
+    port: MidiInput,
+    // Not dead, actually. The callback is invoked as long as the closure is not dropped.
+    #[allow(dead_code)]
+    on_message: Closure<dyn FnMut(MidiMessageEvent)>,
+}
+
+impl MidiInConnection for WebMidiInConnection {}
+
+impl Drop for WebMidiInConnection {
+    fn drop(&mut self) {
+        self.port.set_onmidimessage(None);
+    }
+}
+
+impl MidiOutConnection for MidiOutput {
+    fn send(&mut self, message: &[u8]) -> MidiResult<()> {
+        MidiOutput::send(self, &Uint8Array::from(message))
+            .map_err(|err| MidiError::Other(format!("{err:?}")))
+    }
+}
+
+/// Collects the ports of a `MIDIInputMap` or `MIDIOutputMap`.
+fn named_ports<P: JsCast>(port_map: Map) -> Vec<(String, P)> {
+    let mut ports = Vec::new();
+    port_map.for_each(&mut |port: JsValue, _| {
+        let port = port.unchecked_into::<P>();
+        let name = port.unchecked_ref::<MidiPort>().name().unwrap_or_default();
+        ports.push((name, port));
+    });
+    ports
+}
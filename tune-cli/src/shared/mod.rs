@@ -1,20 +1,41 @@
 //! Code to be shared with other CLIs. At the moment, this module is not intended to become a stable API.
 
 pub mod midi;
+pub mod mts;
+pub mod preset;
+pub mod sync;
+pub mod watch;
 
 use std::{
     fs::File,
     path::{Path, PathBuf},
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use tune::{
     key::PianoKey,
-    pitch::{Ratio, RatioExpression, RatioExpressionVariant},
-    scala::{self, Kbm, KbmImportError, KbmRoot, Scl, SclBuildError, SclImportError, SegmentType},
+    math,
+    note::NoteNames,
+    pitch::{Pitch, Ratio, RatioExpression, RatioExpressionVariant},
+    scala::{
+        self, Kbm, KbmImportError, KbmRoot, LatticeAxis, Scl, SclBuildError, SclBuilder, SclExport,
+        SclImportError, SclNotation, SegmentType,
+    },
+    temperament::{
+        AccidentalCountNotation, EqualTemperament, HeptatonicNotation, NotationSystem,
+        SagittalNotation, UpsAndDownsNotation,
+    },
+    tuning::ConcertPitch,
 };
+use zip::{result::ZipError, ZipArchive};
 
-use crate::{CliError, CliResult};
+use crate::{
+    dto::{ProvenanceDto, SourceFileDto},
+    scale::Scale,
+    App, CliError, CliResult,
+};
+
+use self::preset::SclPreset;
 
 #[derive(Parser)]
 pub enum SclCommand {
@@ -44,6 +65,31 @@ pub enum SclCommand {
         period: Ratio,
     },
 
+    /// Chain of generators with optional offset chains, e.g. --gen 3/2 --down 1 --up 5 for Pythagorean major.
+    /// Fractional inputs yield fractional scale steps.
+    #[command(name = "gen-chain")]
+    GeneratorChain {
+        /// Generator of the chain, e.g. 3/2
+        #[arg(long = "gen")]
+        generator: RatioExpression,
+
+        /// Number of generations below the unison
+        #[arg(long = "down", default_value = "0")]
+        num_down: u16,
+
+        /// Number of generations above the unison
+        #[arg(long = "up", default_value = "0")]
+        num_up: u16,
+
+        /// Period to reduce the generations by
+        #[arg(long = "period", default_value = "2")]
+        period: RatioExpression,
+
+        /// Offset of an additional chain, e.g. 81/80. Can be specified multiple times
+        #[arg(long = "offset")]
+        offsets: Vec<RatioExpression>,
+    },
+
     /// Harmonic series
     #[command(name = "harm")]
     HarmonicSeries {
@@ -62,12 +108,61 @@ pub enum SclCommand {
         neji_divisions: Option<u16>,
     },
 
+    /// Just intonation scale built from a lattice of ratios
+    #[command(subcommand, name = "ji")]
+    JustIntonation(JiCommand),
+
     /// Import scl file
     #[command(name = "scl-file")]
     UseSclFile {
         /// The location of the file to import
         scl_file_location: PathBuf,
     },
+
+    /// Import scl file from a zip archive (e.g. the Scala scale archive) without unpacking it
+    #[command(name = "scl-archive")]
+    UseSclArchive {
+        /// The location of the zip archive
+        #[arg(long = "archive")]
+        archive_location: PathBuf,
+
+        /// Name of the scl file inside the archive, e.g. young-lm_piano. Partial or misspelled names are accepted if they identify a single file
+        #[arg(long = "name")]
+        name: String,
+    },
+
+    /// Built-in scale, e.g. raga:yaman for raga Yaman over the 22-shruti framework
+    #[command(name = "preset")]
+    Preset {
+        /// Name of the preset in the format <collection>:<name>
+        preset: SclPreset,
+    },
+}
+
+#[derive(Parser)]
+pub enum JiCommand {
+    /// Tonality diamond of all odd numbers up to the given odd limit, e.g. 5 for the 5-limit tonality diamond
+    #[command(name = "diamond")]
+    TonalityDiamond {
+        /// Largest odd number of the diamond
+        odd_limit: u16,
+    },
+
+    /// Euler-Fokker genus, e.g. 3 3 5 for the products of 1, 3, 3 and 5
+    #[command(name = "genus")]
+    EulerGenus {
+        /// Factors to combine
+        #[arg(required = true)]
+        factors: Vec<u16>,
+    },
+
+    /// Block of a prime-limit lattice, e.g. 3:-1:1 5:0:1 for the powers 3^-1 to 3^1 combined with 5^0 to 5^1
+    #[command(name = "lattice")]
+    Lattice {
+        /// Axes of the lattice in the format generator:min_power:max_power
+        #[arg(required = true)]
+        axes: Vec<LatticeAxis>,
+    },
 }
 
 impl SclCommand {
@@ -86,6 +181,20 @@ impl SclCommand {
                 num_neg_generations,
                 period,
             )?,
+            SclCommand::GeneratorChain {
+                generator,
+                num_down,
+                num_up,
+                period,
+                offsets,
+            } => create_generator_chain_scale(
+                description,
+                *generator,
+                *num_down,
+                *num_up,
+                *period,
+                offsets,
+            )?,
             &SclCommand::HarmonicSeries {
                 utonal,
                 segment_start,
@@ -105,6 +214,15 @@ impl SclCommand {
                     neji_divisions,
                 )?
             }
+            SclCommand::JustIntonation(ji) => match ji {
+                &JiCommand::TonalityDiamond { odd_limit } => {
+                    scala::create_tonality_diamond_scale(description, odd_limit)?
+                }
+                JiCommand::EulerGenus { factors } => {
+                    scala::create_euler_genus_scale(description, factors)?
+                }
+                JiCommand::Lattice { axes } => scala::create_lattice_scale(description, axes)?,
+            },
             SclCommand::UseSclFile { scl_file_location } => {
                 let mut scale = import_scl_file(scl_file_location)?;
                 if let Some(description) = description {
@@ -112,8 +230,30 @@ impl SclCommand {
                 }
                 scale
             }
+            SclCommand::UseSclArchive {
+                archive_location,
+                name,
+            } => {
+                let mut scale = import_scl_from_archive(archive_location, name)?;
+                if let Some(description) = description {
+                    scale.set_description(description)
+                }
+                scale
+            }
+            SclCommand::Preset { preset } => preset.to_scl(description)?,
         })
     }
+
+    /// The file the scale is imported from, if any.
+    pub fn source_file_location(&self) -> Option<&Path> {
+        match self {
+            SclCommand::UseSclFile { scl_file_location } => Some(scl_file_location),
+            SclCommand::UseSclArchive {
+                archive_location, ..
+            } => Some(archive_location),
+            _ => None,
+        }
+    }
 }
 
 fn create_custom_scale(
@@ -146,6 +286,123 @@ fn create_custom_scale(
     }
 }
 
+fn create_generator_chain_scale(
+    description: Option<String>,
+    generator: RatioExpression,
+    num_down: u16,
+    num_up: u16,
+    period: RatioExpression,
+    offsets: &[RatioExpression],
+) -> CliResult<Scl> {
+    if period.ratio() <= Ratio::default() {
+        return Err("Period must be greater than 1/1".to_owned().into());
+    }
+
+    let generator_fraction = as_fraction(generator);
+    let period_fraction = as_fraction(period);
+
+    let mut steps = Vec::new();
+    for offset in std::iter::once(None).chain(offsets.iter().map(Some)) {
+        let offset_ratio = offset.map(|offset| offset.ratio()).unwrap_or_default();
+        let offset_fraction = offset.map_or(Some((1, 1)), |&offset| as_fraction(offset));
+
+        let mut push_generations = |num_generations: u16, inverse: bool| {
+            let mut fraction = offset_fraction;
+            for generation in 0..=num_generations {
+                let cents = offset_ratio.as_cents()
+                    + f64::from(generation)
+                        * generator.ratio().as_cents()
+                        * if inverse { -1.0 } else { 1.0 };
+                steps.push((
+                    cents.rem_euclid(period.ratio().as_cents()),
+                    fraction.and_then(|fraction| reduce_by_period(fraction, period_fraction?)),
+                ));
+                fraction = fraction
+                    .zip(generator_fraction)
+                    .and_then(|(fraction, generator)| {
+                        if inverse {
+                            multiply_fractions(fraction, (generator.1, generator.0))
+                        } else {
+                            multiply_fractions(fraction, generator)
+                        }
+                    });
+            }
+        };
+
+        push_generations(num_up, false);
+        push_generations(num_down, true);
+    }
+
+    steps.retain(|&(cents, fraction)| match fraction {
+        Some(fraction) => fraction != (1, 1),
+        None => cents.abs() > 1e-6 && (cents - period.ratio().as_cents()).abs() > 1e-6,
+    });
+    steps.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+    steps.dedup_by(|(a, _), (b, _)| (*a - *b).abs() < 1e-6);
+
+    let mut builder = Scl::builder();
+    for (cents, fraction) in steps {
+        builder = push_step(builder, Ratio::from_cents(cents), fraction);
+    }
+    builder = push_step(builder, period.ratio(), period_fraction);
+
+    let description = description.unwrap_or_else(|| {
+        let mut description = format!(
+            "{num_up} positive and {num_down} negative generations of generator {} ({:#}) with period {}",
+            generator.ratio(),
+            generator.ratio(),
+            period.ratio()
+        );
+        for offset in offsets {
+            description.push_str(&format!(", offset {}", offset.ratio()));
+        }
+        description
+    });
+
+    Ok(builder.build_with_description(description)?)
+}
+
+fn push_step(builder: SclBuilder, ratio: Ratio, fraction: Option<(u64, u64)>) -> SclBuilder {
+    match fraction
+        .and_then(|(numer, denom)| Some((u32::try_from(numer).ok()?, u32::try_from(denom).ok()?)))
+    {
+        Some((numer, denom)) => builder.push_fraction(numer, denom),
+        None => builder.push_ratio(ratio),
+    }
+}
+
+fn as_fraction(ratio: RatioExpression) -> Option<(u64, u64)> {
+    match ratio.variant() {
+        RatioExpressionVariant::Float { float_value } => Some((as_int(float_value)?.into(), 1)),
+        RatioExpressionVariant::Fraction { numer, denom } => {
+            Some((as_int(numer)?.into(), as_int(denom)?.into()))
+        }
+        _ => None,
+    }
+    .filter(|&(numer, denom)| numer > 0 && denom > 0)
+    .map(|(numer, denom)| math::simplify_u64(numer, denom))
+}
+
+fn multiply_fractions(a: (u64, u64), b: (u64, u64)) -> Option<(u64, u64)> {
+    let (numer_a, denom_b) = math::simplify_u64(a.0, b.1);
+    let (numer_b, denom_a) = math::simplify_u64(b.0, a.1);
+    Some((numer_a.checked_mul(numer_b)?, denom_a.checked_mul(denom_b)?))
+}
+
+fn reduce_by_period(mut fraction: (u64, u64), period: (u64, u64)) -> Option<(u64, u64)> {
+    let is_less = |a: (u64, u64), b: (u64, u64)| {
+        u128::from(a.0) * u128::from(b.1) < u128::from(b.0) * u128::from(a.1)
+    };
+
+    while !is_less(fraction, period) {
+        fraction = multiply_fractions(fraction, (period.1, period.0))?;
+    }
+    while is_less(fraction, (1, 1)) {
+        fraction = multiply_fractions(fraction, period)?;
+    }
+    Some(fraction)
+}
+
 fn as_int(float: f64) -> Option<u32> {
     let rounded = float.round();
     if (float - rounded).abs() < 1e-6 {
@@ -163,16 +420,26 @@ pub struct KbmRootOptions {
     /// root note / "middle note" of the scale if different from reference note
     #[arg(long = "root")]
     root_note: Option<i16>,
+
+    /// Concert pitch the reference note refers to, e.g. 432Hz for A4 = 432Hz
+    #[arg(long = "a4")]
+    a4_pitch: Option<Pitch>,
 }
 
 impl KbmRootOptions {
     pub fn to_kbm_root(&self) -> KbmRoot {
+        let ref_note = match self.a4_pitch {
+            Some(a4_pitch) => self
+                .ref_note
+                .at_concert_pitch(ConcertPitch::from_a4_pitch(a4_pitch)),
+            None => self.ref_note,
+        };
         match self.root_note {
             Some(root_note) => KbmRoot {
-                root_offset: i32::from(root_note) - self.ref_note.ref_key.midi_number(),
-                ..self.ref_note
+                root_offset: i32::from(root_note) - ref_note.ref_key.midi_number(),
+                ..ref_note
             },
-            None => self.ref_note,
+            None => ref_note,
         }
     }
 }
@@ -199,6 +466,90 @@ pub struct KbmOptions {
     formal_octave: Option<i16>,
 }
 
+#[derive(Clone, Parser)]
+pub struct StretchOptions {
+    /// Stretch all intervals s.t. an octave becomes larger by the given amount, e.g. 2c
+    #[arg(long = "stretch", allow_hyphen_values = true)]
+    stretch: Option<Ratio>,
+
+    /// Apply a Railsback curve with the given deviation one octave away from the root, e.g. 0.5c for a typical piano.
+    /// The deviation grows with the cube of the number of octaves.
+    #[arg(long = "railsback", allow_hyphen_values = true)]
+    railsback_deviation: Option<Ratio>,
+}
+
+impl StretchOptions {
+    pub fn apply(&self, mut scl: Scl) -> CliResult<Scl> {
+        if let Some(stretch) = self.stretch {
+            scl = scl.with_stretch(stretch)?;
+        }
+        if let Some(railsback_deviation) = self.railsback_deviation {
+            scl = scl.with_railsback_curve(railsback_deviation)?;
+        }
+        Ok(scl)
+    }
+}
+
+#[derive(Parser)]
+pub struct SclExportOptions {
+    /// Write cents values as fractions if they match a fraction within the given odd limit, e.g. 15
+    #[arg(long = "prefer-ratios", conflicts_with = "all_cents")]
+    prefer_ratios: Option<u32>,
+
+    /// Write all items, including fractions, as cents values, e.g. 1200.000 instead of 2
+    #[arg(long = "all-cents")]
+    all_cents: bool,
+
+    /// Number of decimal places of cents values
+    #[arg(long = "cents-precision", default_value = "3")]
+    cents_precision: usize,
+
+    /// Write integer ratios with an explicit denominator, e.g. 2/1 instead of 2
+    #[arg(long = "explicit-denom")]
+    explicit_denominators: bool,
+
+    /// Precede the scl file with comments recording the command line and the hashes of the source files
+    #[arg(long = "provenance")]
+    provenance: bool,
+}
+
+impl SclExportOptions {
+    pub fn export<'a>(&self, scl: &'a Scl) -> SclExport<'a> {
+        let notation = match (self.prefer_ratios, self.all_cents) {
+            (Some(odd_limit), _) => SclNotation::PreferRatios(odd_limit),
+            (None, true) => SclNotation::Cents,
+            (None, false) => SclNotation::AsDefined,
+        };
+        scl.export()
+            .with_notation(notation)
+            .with_cents_precision(self.cents_precision)
+            .with_explicit_denominators(self.explicit_denominators)
+    }
+
+    pub(crate) fn write(
+        &self,
+        app: &mut App,
+        scl: &Scl,
+        source_file_location: Option<&Path>,
+    ) -> CliResult<()> {
+        if self.provenance {
+            let provenance = ProvenanceDto {
+                command_line: app.command_line.clone(),
+                generator: None,
+                source_files: source_file_location
+                    .map(SourceFileDto::read)
+                    .into_iter()
+                    .collect::<CliResult<_>>()?,
+                upstream: None,
+            };
+            for line in provenance.lines() {
+                app.writeln(format_args!("! {line}"))?;
+            }
+        }
+        Ok(app.write(format_args!("{}", self.export(scl)))?)
+    }
+}
+
 #[derive(Clone)]
 enum Item {
     Mapped(i16),
@@ -240,18 +591,165 @@ impl KbmOptions {
     }
 }
 
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Notation {
+    /// Letters with repeated accidentals, e.g. C##
+    #[value(name = "heptatonic")]
+    Heptatonic,
+    /// Letters with numbered accidentals, e.g. C#2
+    #[value(name = "count")]
+    AccidentalCount,
+    /// Ups-and-downs notation, e.g. ^C#
+    #[value(name = "ups-downs")]
+    UpsAndDowns,
+    /// Pure ASCII Sagittal notation, e.g. C/|\
+    #[value(name = "sagittal")]
+    Sagittal,
+}
+
+impl Notation {
+    pub fn note_name(self, temperament: &EqualTemperament, index: u16) -> String {
+        let notation: &dyn NotationSystem = match self {
+            Notation::Heptatonic => &HeptatonicNotation,
+            Notation::AccidentalCount => &AccidentalCountNotation,
+            Notation::UpsAndDowns => &UpsAndDownsNotation,
+            Notation::Sagittal => &SagittalNotation,
+        };
+        notation.note_name(temperament, index)
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum NoteNameConvention {
+    /// C, C#/Db, D, ..., B
+    #[value(name = "english")]
+    English,
+    /// C, Cis/Des, D, ..., Ais/B, H
+    #[value(name = "german")]
+    German,
+    /// Do, Do#/Reb, Re, ..., Si
+    #[value(name = "solfege")]
+    Solfege,
+    /// Ni, Ni#/Pab, Pa, ..., Zo
+    #[value(name = "byzantine")]
+    Byzantine,
+}
+
+impl NoteNameConvention {
+    pub fn note_names(self) -> NoteNames {
+        match self {
+            NoteNameConvention::English => NoteNames::English,
+            NoteNameConvention::German => NoteNames::German,
+            NoteNameConvention::Solfege => NoteNames::Solfege,
+            NoteNameConvention::Byzantine => NoteNames::Byzantine,
+        }
+    }
+}
+
 pub fn import_scl_file(file_name: &Path) -> Result<Scl, String> {
     File::open(file_name)
         .map_err(SclImportError::IoError)
         .and_then(Scl::import)
-        .map_err(|err| match err {
-            SclImportError::IoError(err) => format!("Could not read scl file: {err}"),
-            SclImportError::ParseError { line_number, kind } => {
-                format!("Could not parse scl file at line {line_number} ({kind:?})")
+        .map_err(format_scl_import_error)
+}
+
+/// Imports the scl file whose name matches `name` best.
+///
+/// An exact match (ignoring case, the file extension and the directory) is preferred over a partial match which is preferred over a subsequence match, e.g. `ylmpiano` for `young-lm_piano`.
+/// If the best match is not unique, the candidates are listed in the error message.
+pub fn import_scl_from_archive(archive_location: &Path, name: &str) -> Result<Scl, String> {
+    let mut archive = File::open(archive_location)
+        .map_err(ZipError::Io)
+        .and_then(ZipArchive::new)
+        .map_err(|err| format!("Could not read archive: {err}"))?;
+
+    let file_name = find_scl_file_name(archive.file_names(), name)?;
+
+    let scl_file = archive
+        .by_name(&file_name)
+        .map_err(|err| format!("Could not read {file_name} from archive: {err}"))?;
+
+    Scl::import(scl_file).map_err(format_scl_import_error)
+}
+
+fn find_scl_file_name<'a>(
+    file_names: impl IntoIterator<Item = &'a str>,
+    name: &str,
+) -> Result<String, String> {
+    const MAX_CANDIDATES_TO_LIST: usize = 10;
+
+    let query = normalize_scl_name(name);
+
+    let mut best_quality = None;
+    let mut candidates = Vec::new();
+    for file_name in file_names {
+        let stem = match Path::new(file_name)
+            .extension()
+            .filter(|extension| extension.eq_ignore_ascii_case("scl"))
+            .and_then(|_| Path::new(file_name).file_stem())
+            .and_then(|stem| stem.to_str())
+        {
+            Some(stem) => normalize_scl_name(stem),
+            None => continue,
+        };
+
+        let quality = if stem == query {
+            3
+        } else if stem.contains(&query) {
+            2
+        } else if is_subsequence(&query, &stem) {
+            1
+        } else {
+            continue;
+        };
+
+        if Some(quality) > best_quality {
+            best_quality = Some(quality);
+            candidates.clear();
+        }
+        if Some(quality) == best_quality {
+            candidates.push(file_name.to_owned());
+        }
+    }
+
+    candidates.sort();
+    match candidates.len() {
+        0 => Err(format!("No scl file matching `{name}` found in archive")),
+        1 => Ok(candidates.pop().unwrap()),
+        num_candidates => {
+            let mut message = format!("`{name}` matches {num_candidates} scl files:");
+            for candidate in candidates.iter().take(MAX_CANDIDATES_TO_LIST) {
+                message.push_str(&format!("\n  {candidate}"));
             }
-            SclImportError::StructuralError(err) => format!("Malformed scl file ({err:?})"),
-            SclImportError::BuildError(err) => format!("Unsupported scl file ({err:?})"),
-        })
+            if num_candidates > MAX_CANDIDATES_TO_LIST {
+                message.push_str("\n  ...");
+            }
+            Err(message)
+        }
+    }
+}
+
+fn normalize_scl_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+fn format_scl_import_error(err: SclImportError) -> String {
+    match err {
+        SclImportError::IoError(err) => format!("Could not read scl file: {err}"),
+        SclImportError::ParseError { line_number, kind } => {
+            format!("Could not parse scl file at line {line_number} ({kind:?})")
+        }
+        SclImportError::StructuralError(err) => format!("Malformed scl file ({err:?})"),
+        SclImportError::BuildError(err) => format!("Unsupported scl file ({err:?})"),
+    }
 }
 
 pub fn import_kbm_file(file_name: &Path) -> Result<Kbm, String> {
@@ -267,3 +765,29 @@ pub fn import_kbm_file(file_name: &Path) -> Result<Kbm, String> {
             KbmImportError::BuildError(err) => format!("Unsupported kbm file ({err:?})"),
         })
 }
+
+/// Locations of an scl and a kbm file that, together, describe a tuning.
+#[derive(Clone, Debug)]
+pub struct TuningFiles {
+    pub scl_file_location: PathBuf,
+    pub kbm_file_location: PathBuf,
+}
+
+impl TuningFiles {
+    pub(crate) fn load(&self) -> CliResult<Scale> {
+        Ok(Scale::from_tuning(
+            import_scl_file(&self.scl_file_location)?,
+            import_kbm_file(&self.kbm_file_location)?,
+        ))
+    }
+}
+
+pub fn parse_tuning_files(src: &str) -> Result<TuningFiles, &'static str> {
+    match src.split_once(',') {
+        Some((scl_file_location, kbm_file_location)) => Ok(TuningFiles {
+            scl_file_location: scl_file_location.into(),
+            kbm_file_location: kbm_file_location.into(),
+        }),
+        None => Err("Invalid tuning. Should be <scl-file>,<kbm-file>"),
+    }
+}
@@ -0,0 +1,227 @@
+use tune::{
+    key::PianoKey,
+    mts::{MtsDecodeError, MtsMessage},
+    note::Note,
+    pitch::{Pitch, Pitched, Ratio},
+    scala::{KbmRoot, Scl},
+};
+
+use crate::CliResult;
+
+/// Largest period (in keys) detected when reconstructing a scale from the received key pitches.
+const MAX_DETECTED_PERIOD: usize = 64;
+
+/// Maximum deviation of the period ratio between different keys. Accounts for the limited resolution of MTS messages.
+const PERIOD_TOLERANCE_IN_CENTS: f64 = 0.05;
+
+/// Keeps track of the key pitches assigned by received MTS messages, i.e. acts as an MTS client.
+///
+/// Keys that have not been retuned yet have their 12-EDO pitch.
+pub struct MtsClient {
+    key_pitches: Vec<Pitch>,
+    retuned_keys: Option<(usize, usize)>,
+    description: Option<String>,
+}
+
+impl Default for MtsClient {
+    fn default() -> Self {
+        Self {
+            key_pitches: (0..128)
+                .map(|midi_number| Note::from_midi_number(midi_number).pitch())
+                .collect(),
+            retuned_keys: None,
+            description: None,
+        }
+    }
+}
+
+impl MtsClient {
+    /// Applies the tuning changes of the given message. Returns `false` if the message does not retune any key, e.g. a *Bulk Tuning Dump Request*.
+    pub fn apply(&mut self, message: &MtsMessage) -> bool {
+        if let MtsMessage::BulkTuningDump { name, .. } = message {
+            self.description = Some(name.trim_end().to_owned());
+        }
+
+        let mut retuned = false;
+        for tuning_change in message.tuning_changes() {
+            if let Some(midi_number) = tuning_change.key.checked_midi_number() {
+                let midi_number = usize::from(midi_number);
+                self.key_pitches[midi_number] = tuning_change.target_pitch;
+                self.retuned_keys = match self.retuned_keys {
+                    Some((lo, hi)) => Some((lo.min(midi_number), hi.max(midi_number))),
+                    None => Some((midi_number, midi_number)),
+                };
+                retuned = true;
+            }
+        }
+        retuned
+    }
+
+    /// Decodes and applies a raw MIDI message. Returns `Ok(false)` if the message is not an MTS message s.t. the caller can process it in a different way.
+    ///
+    /// `on_tuning` is called with the reconstructed tuning whenever the message retunes any key. Invalid MTS messages are not applied and yield an error.
+    pub fn process_raw_message(
+        &mut self,
+        message: &[u8],
+        on_tuning: impl FnOnce(Scl, KbmRoot),
+    ) -> CliResult<bool> {
+        match MtsMessage::from_sysex_bytes(message) {
+            Ok(message) => {
+                if self.apply(&message) {
+                    let (scl, kbm_root) = self.tuning()?;
+                    on_tuning(scl, kbm_root);
+                }
+                Ok(true)
+            }
+            Err(MtsDecodeError::NotAnMtsMessage) => Ok(false),
+            Err(err) => Err(format!("Invalid MTS message ({err:?})").into()),
+        }
+    }
+
+    /// Returns `true` if any key has been retuned.
+    pub fn has_retuned_keys(&self) -> bool {
+        self.retuned_keys.is_some()
+    }
+
+    /// Reconstructs a scale and a keyboard mapping from the pitches of the retuned keys.
+    ///
+    /// If the pitches repeat with a fixed ratio after at most [`MAX_DETECTED_PERIOD`] keys, the scale consists of a single period rooted at MIDI key 60 (or the lowest retuned key if 60 is out of range).
+    /// Otherwise, every retuned key becomes a scale degree relative to the lowest retuned key.
+    ///
+    /// A single retuned key cannot form a scale on its own. In that case, the scale covers the whole keyboard, i.e. the retuned key and the 12-EDO pitches of all other keys.
+    pub fn tuning(&self) -> CliResult<(Scl, KbmRoot)> {
+        // Keys outside the retuned range keep their 12-EDO pitch and are not part of the scale
+        let (first_key, last_key) = match self.retuned_keys {
+            Some((first_key, last_key)) if first_key < last_key => (first_key, last_key),
+            _ => (0, 127),
+        };
+        let key_pitches = &self.key_pitches[first_key..=last_key];
+
+        let ratio =
+            |from: usize, to: usize| Ratio::between_pitches(key_pitches[from], key_pitches[to]);
+
+        let period = (1..=MAX_DETECTED_PERIOD.min(key_pitches.len() - 1)).find(|&period| {
+            let period_ratio = ratio(0, period);
+            (0..key_pitches.len() - period).all(|index| {
+                ratio(index, index + period)
+                    .deviation_from(period_ratio)
+                    .as_cents()
+                    .abs()
+                    < PERIOD_TOLERANCE_IN_CENTS
+            })
+        });
+
+        let (root_index, num_items) = match period {
+            Some(period) => match 60usize.checked_sub(first_key) {
+                Some(index) if index + period < key_pitches.len() => (index, period),
+                _ => (0, period),
+            },
+            None => (0, key_pitches.len() - 1),
+        };
+
+        let scl = (root_index + 1..=root_index + num_items)
+            .fold(Scl::builder(), |builder, index| {
+                builder.push_ratio(ratio(root_index, index))
+            })
+            .build_with_description(
+                self.description
+                    .clone()
+                    .unwrap_or_else(|| "Decoded MTS tuning".to_owned()),
+            )
+            .map_err(|err| format!("Could not create scale ({err:?})"))?;
+
+        let kbm_root = KbmRoot {
+            ref_key: PianoKey::from_midi_number((first_key + root_index) as i32),
+            ref_pitch: key_pitches[root_index],
+            root_offset: 0,
+        };
+
+        Ok((scl, kbm_root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::{
+        mts::{SingleNoteTuningChangeMessage, SingleNoteTuningChangeOptions},
+        tuning::Tuning,
+    };
+
+    use super::*;
+
+    #[test]
+    fn adopt_periodic_tuning() {
+        let scl = Scl::builder().push_cents(1200.0 / 7.0).build().unwrap();
+        let kbm_root = KbmRoot::from(Note::from_midi_number(62));
+        let message = SingleNoteTuningChangeMessage::from_tuning(
+            &SingleNoteTuningChangeOptions::default(),
+            (&scl, &kbm_root.to_kbm()),
+            (21..=108).map(PianoKey::from_midi_number),
+        )
+        .unwrap();
+
+        let mut mts_client = MtsClient::default();
+        let mut adopted = None;
+        for sysex_bytes in message.sysex_bytes() {
+            assert!(mts_client
+                .process_raw_message(sysex_bytes, |scl, kbm_root| {
+                    adopted = Some((scl, kbm_root))
+                })
+                .unwrap());
+        }
+
+        let (adopted_scl, adopted_kbm_root) = adopted.unwrap();
+        assert_eq!(adopted_scl.num_items(), 1);
+        assert!((adopted_scl.period().as_cents() - 1200.0 / 7.0).abs() < 0.01);
+        assert_eq!(adopted_kbm_root.ref_key, PianoKey::from_midi_number(60));
+        let expected_pitch = (&scl, kbm_root).pitch_of(PianoKey::from_midi_number(60));
+        assert!(
+            Ratio::between_pitches(adopted_kbm_root.ref_pitch, expected_pitch)
+                .as_cents()
+                .abs()
+                < 0.01
+        );
+
+        // Non-MTS messages are left to the caller
+        assert!(!mts_client
+            .process_raw_message(&[0x90, 60, 100], |_, _| unreachable!())
+            .unwrap());
+        assert!(!mts_client
+            .process_raw_message(&[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7], |_, _| {
+                unreachable!()
+            })
+            .unwrap());
+
+        // Invalid MTS messages are reported
+        assert!(mts_client
+            .process_raw_message(&[0xf0, 0x7f, 0x7f, 0x08, 0x02, 0x00, 0x05, 0xf7], |_, _| {
+                unreachable!()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn adopt_single_retuned_key() {
+        let message = MtsMessage::from_sysex_bytes(&[
+            0xf0, 0x7f, 0x7f, 0x08, 0x02, 0x00, 0x01, 0x45, 0x45, 0x40, 0x00, 0xf7,
+        ])
+        .unwrap();
+
+        let mut mts_client = MtsClient::default();
+        assert!(!mts_client.has_retuned_keys());
+        assert!(mts_client.apply(&message));
+        assert!(mts_client.has_retuned_keys());
+
+        let (scl, kbm_root) = mts_client.tuning().unwrap();
+        assert_eq!(scl.num_items(), 127);
+        assert_eq!(kbm_root.ref_key, PianoKey::from_midi_number(0));
+
+        let tuning = (&scl, kbm_root);
+        for (midi_number, expected_cents) in [(68, 0.0), (69, 50.0), (70, 0.0)] {
+            let key = PianoKey::from_midi_number(midi_number);
+            let note = Note::from_midi_number(midi_number);
+            let deviation = Ratio::between_pitches(note.pitch(), tuning.pitch_of(key));
+            assert!((deviation.as_cents() - expected_cents).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Built-in scales that can be referred to by name, e.g. `raga:yaman`.
+
+use std::str::FromStr;
+
+use tune::scala::{Scl, SclBuildError};
+
+/// A built-in scale, addressed as `<collection>:<name>`.
+#[derive(Clone, Copy, Debug)]
+pub enum SclPreset {
+    Raga(&'static Raga),
+}
+
+impl FromStr for SclPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (collection, name) = s.split_once(':').ok_or_else(|| {
+            format!(
+                "Invalid preset '{s}': Should be of the form <collection>:<name>, e.g. raga:yaman"
+            )
+        })?;
+
+        match collection {
+            "raga" => RAGAS
+                .iter()
+                .find(|raga| raga.name == name)
+                .map(SclPreset::Raga)
+                .ok_or_else(|| {
+                    let names = RAGAS.iter().map(|raga| raga.name).collect::<Vec<_>>();
+                    format!(
+                        "Unknown raga '{name}'. Should be one of: {}",
+                        names.join(", ")
+                    )
+                }),
+            _ => Err(format!(
+                "Unknown preset collection '{collection}'. Should be `raga`"
+            )),
+        }
+    }
+}
+
+impl SclPreset {
+    pub fn to_scl(&self, description: Option<String>) -> Result<Scl, SclBuildError> {
+        match self {
+            SclPreset::Raga(raga) => raga.to_scl(description),
+        }
+    }
+}
+
+/// The 22 shrutis of the octave as just ratios, together with the swara (scale degree) they belong to.
+///
+/// Komal (flat) swaras are written in lower case and tivra Ma is written as M'.
+pub const SHRUTIS: [((u32, u32), &str); 22] = [
+    ((1, 1), "S"),
+    ((256, 243), "r"),
+    ((16, 15), "r"),
+    ((10, 9), "R"),
+    ((9, 8), "R"),
+    ((32, 27), "g"),
+    ((6, 5), "g"),
+    ((5, 4), "G"),
+    ((81, 64), "G"),
+    ((4, 3), "M"),
+    ((27, 20), "M"),
+    ((45, 32), "M'"),
+    ((729, 512), "M'"),
+    ((3, 2), "P"),
+    ((128, 81), "d"),
+    ((8, 5), "d"),
+    ((5, 3), "D"),
+    ((27, 16), "D"),
+    ((16, 9), "n"),
+    ((9, 5), "n"),
+    ((15, 8), "N"),
+    ((243, 128), "N"),
+];
+
+/// A raga expressed over the [`SHRUTIS`] framework.
+///
+/// The melodic lines are given as shruti indices from Sa (0) to the upper Sa (22). The scale of the raga contains the shrutis of both lines.
+#[derive(Debug)]
+pub struct Raga {
+    pub name: &'static str,
+    /// The ascending line.
+    pub aroha: &'static [usize],
+    /// The descending line.
+    pub avaroha: &'static [usize],
+}
+
+pub const RAGAS: &[Raga] = &[
+    Raga {
+        name: "bilawal",
+        aroha: &[0, 4, 7, 9, 13, 16, 20, 22],
+        avaroha: &[22, 20, 16, 13, 9, 7, 4, 0],
+    },
+    Raga {
+        name: "yaman",
+        aroha: &[0, 4, 7, 11, 16, 20, 22],
+        avaroha: &[22, 20, 16, 13, 11, 7, 4, 0],
+    },
+    Raga {
+        name: "bhupali",
+        aroha: &[0, 4, 7, 13, 16, 22],
+        avaroha: &[22, 16, 13, 7, 4, 0],
+    },
+    Raga {
+        name: "khamaj",
+        aroha: &[0, 7, 9, 13, 16, 20, 22],
+        avaroha: &[22, 18, 16, 13, 9, 7, 4, 0],
+    },
+    Raga {
+        name: "kafi",
+        aroha: &[0, 4, 6, 9, 13, 16, 19, 22],
+        avaroha: &[22, 19, 16, 13, 9, 6, 4, 0],
+    },
+    Raga {
+        name: "asavari",
+        aroha: &[0, 4, 9, 13, 15, 22],
+        avaroha: &[22, 19, 15, 13, 9, 6, 4, 0],
+    },
+    Raga {
+        name: "darbari",
+        aroha: &[0, 4, 5, 9, 13, 14, 18, 22],
+        avaroha: &[22, 14, 18, 13, 9, 13, 5, 9, 4, 0],
+    },
+    Raga {
+        name: "malkauns",
+        aroha: &[0, 6, 9, 15, 19, 22],
+        avaroha: &[22, 19, 15, 9, 6, 0],
+    },
+    Raga {
+        name: "bhairavi",
+        aroha: &[0, 2, 6, 9, 13, 15, 19, 22],
+        avaroha: &[22, 19, 15, 13, 9, 6, 2, 0],
+    },
+    Raga {
+        name: "bhairav",
+        aroha: &[0, 1, 7, 9, 13, 14, 20, 22],
+        avaroha: &[22, 20, 14, 13, 9, 7, 1, 0],
+    },
+    Raga {
+        name: "todi",
+        aroha: &[0, 1, 5, 12, 14, 21, 22],
+        avaroha: &[22, 21, 14, 13, 12, 5, 1, 0],
+    },
+    Raga {
+        name: "marwa",
+        aroha: &[0, 2, 7, 11, 16, 20, 22],
+        avaroha: &[22, 20, 16, 11, 7, 2, 0],
+    },
+    Raga {
+        name: "purvi",
+        aroha: &[0, 2, 7, 11, 13, 15, 20, 22],
+        avaroha: &[22, 20, 15, 13, 11, 9, 7, 2, 0],
+    },
+];
+
+impl Raga {
+    /// The sorted shruti indices of the scale, excluding Sa.
+    pub fn shrutis(&self) -> Vec<usize> {
+        let mut shrutis: Vec<_> = self
+            .aroha
+            .iter()
+            .chain(self.avaroha)
+            .map(|&shruti| shruti % SHRUTIS.len())
+            .filter(|&shruti| shruti != 0)
+            .collect();
+        shrutis.sort_unstable();
+        shrutis.dedup();
+        shrutis
+    }
+
+    /// Creates a scale with the shrutis of both lines and the octave as its period. If no `description` is given, the lines are listed in the description.
+    pub fn to_scl(&self, description: Option<String>) -> Result<Scl, SclBuildError> {
+        let mut builder = Scl::builder();
+        for shruti in self.shrutis() {
+            let ((numer, denom), _) = SHRUTIS[shruti];
+            builder = builder.push_fraction(numer, denom);
+        }
+        builder = builder.push_int(2);
+
+        builder.build_with_description(description.unwrap_or_else(|| {
+            format!(
+                "Raga {} (22 shrutis), aroha: {}, avaroha: {}",
+                self.name,
+                swaras(self.aroha),
+                swaras(self.avaroha)
+            )
+        }))
+    }
+}
+
+fn swaras(line: &[usize]) -> String {
+    let mut swaras = String::new();
+    for &shruti in line {
+        if !swaras.is_empty() {
+            swaras.push(' ');
+        }
+        let (_, swara) = SHRUTIS[shruti % SHRUTIS.len()];
+        swaras.push_str(swara);
+        if shruti >= SHRUTIS.len() {
+            swaras.push('\'');
+        }
+    }
+    swaras
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_stay_within_octave() {
+        for raga in RAGAS {
+            for &shruti in raga.aroha.iter().chain(raga.avaroha) {
+                assert!(shruti <= SHRUTIS.len(), "{}", raga.name);
+            }
+            assert_eq!(raga.aroha.first(), Some(&0), "{}", raga.name);
+            assert_eq!(raga.avaroha.last(), Some(&0), "{}", raga.name);
+        }
+    }
+
+    #[test]
+    fn create_raga_scale() {
+        let scl = "raga:khamaj"
+            .parse::<SclPreset>()
+            .unwrap()
+            .to_scl(None)
+            .unwrap();
+
+        assert_eq!(
+            scl.description(),
+            "Raga khamaj (22 shrutis), aroha: S G M P D N S', avaroha: S' n D P M G R S"
+        );
+        assert_eq!(scl.num_items(), 8);
+        assert!((scl.period().as_float() - 2.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn reject_unknown_presets() {
+        assert!("raga:bilawal".parse::<SclPreset>().is_ok());
+        assert_eq!(
+            "yaman".parse::<SclPreset>().unwrap_err(),
+            "Invalid preset 'yaman': Should be of the form <collection>:<name>, e.g. raga:yaman"
+        );
+        assert_eq!(
+            "maqam:rast".parse::<SclPreset>().unwrap_err(),
+            "Unknown preset collection 'maqam'. Should be `raga`"
+        );
+        assert!("raga:foo"
+            .parse::<SclPreset>()
+            .unwrap_err()
+            .starts_with("Unknown raga 'foo'. Should be one of: bilawal, yaman,"));
+    }
+}
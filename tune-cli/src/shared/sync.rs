@@ -0,0 +1,199 @@
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    process, thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use tune::scala::{Kbm, Scl};
+
+use crate::CliResult;
+
+/// Maximum payload size of a UDP datagram.
+const MAX_MESSAGE_SIZE: usize = 65_507;
+
+#[derive(Parser)]
+pub struct SyncArgs {
+    /// Local address to receive tuning updates from other instances on, e.g. 0.0.0.0:7890
+    #[arg(long = "sync-bind")]
+    pub bind_address: Option<SocketAddr>,
+
+    /// Address to send tuning updates to, e.g. 192.168.0.42:7890 or a broadcast address like 192.168.0.255:7890.
+    /// Can be specified multiple times.
+    #[arg(long = "sync-peer")]
+    pub peer_addresses: Vec<SocketAddr>,
+}
+
+impl SyncArgs {
+    /// Returns [`None`] if neither a bind address nor any peer address is given.
+    pub fn connect(&self) -> CliResult<Option<TuningSync>> {
+        if self.bind_address.is_none() && self.peer_addresses.is_empty() {
+            return Ok(None);
+        }
+
+        let bind_address = self
+            .bind_address
+            .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+        let socket = UdpSocket::bind(bind_address)?;
+        socket.set_broadcast(true)?;
+
+        Ok(Some(TuningSync {
+            socket,
+            peer_addresses: self.peer_addresses.clone(),
+            instance_id: create_instance_id(),
+        }))
+    }
+}
+
+/// Exchanges scale and root changes with other processes via UDP s.t. several instances can be played in a common tuning.
+///
+/// Every message contains the full scale and the full keyboard mapping in Scala format s.t. non-linear mappings are reproduced as well. Messages sent by the same instance are ignored.
+pub struct TuningSync {
+    socket: UdpSocket,
+    peer_addresses: Vec<SocketAddr>,
+    instance_id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TuningMessage {
+    instance_id: u64,
+    scl: String,
+    kbm: String,
+}
+
+impl TuningSync {
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            socket: self.socket.try_clone()?,
+            peer_addresses: self.peer_addresses.clone(),
+            instance_id: self.instance_id,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Sends the given tuning to all peers. Failures are reported but do not interrupt the caller.
+    pub fn publish(&self, (scl, kbm): (&Scl, &Kbm)) {
+        let message = TuningMessage {
+            instance_id: self.instance_id,
+            scl: scl.export().to_string(),
+            kbm: kbm.export().to_string(),
+        };
+        let message = serde_json::to_vec(&message).unwrap();
+
+        for peer_address in &self.peer_addresses {
+            if let Err(err) = self.socket.send_to(&message, peer_address) {
+                eprintln!("[WARNING] Could not send tuning to {peer_address}: {err}");
+            }
+        }
+    }
+
+    /// Spawns a thread that calls `callback` for every tuning sent by another instance.
+    pub fn listen(self, mut callback: impl FnMut(Scl, Kbm) + Send + 'static) {
+        thread::spawn(move || loop {
+            match self.receive() {
+                Ok((scl, kbm)) => callback(scl, kbm),
+                Err(err) => {
+                    eprintln!("[WARNING] Stopped receiving tuning updates: {err}");
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Blocks until a tuning sent by another instance arrives. Invalid messages are reported and skipped.
+    pub fn receive(&self) -> io::Result<(Scl, Kbm)> {
+        let mut buffer = vec![0; MAX_MESSAGE_SIZE];
+        loop {
+            let (len, sender_address) = self.socket.recv_from(&mut buffer)?;
+            match parse_message(&buffer[..len]) {
+                Ok((instance_id, tuning)) if instance_id != self.instance_id => return Ok(tuning),
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!("[WARNING] Invalid tuning message from {sender_address}: {err}")
+                }
+            }
+        }
+    }
+}
+
+fn parse_message(data: &[u8]) -> Result<(u64, (Scl, Kbm)), String> {
+    let message: TuningMessage = serde_json::from_slice(data).map_err(|err| err.to_string())?;
+    let scl = Scl::import(message.scl.as_bytes()).map_err(|err| format!("{err:?}"))?;
+    let kbm = Kbm::import(message.kbm.as_bytes()).map_err(|err| format!("{err:?}"))?;
+    Ok((message.instance_id, (scl, kbm)))
+}
+
+fn create_instance_id() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or_default();
+    u64::from(process::id()) << 32 | u64::from(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use tune::{
+        key::PianoKey,
+        pitch::{Pitch, Ratio},
+        scala::KbmRoot,
+    };
+
+    use super::*;
+
+    #[test]
+    fn send_tuning_to_peer_and_skip_own_messages() {
+        let receiver = SyncArgs {
+            bind_address: Some(SocketAddr::from(([127, 0, 0, 1], 0))),
+            peer_addresses: Vec::new(),
+        }
+        .connect()
+        .unwrap()
+        .unwrap();
+
+        let sender = SyncArgs {
+            bind_address: Some(SocketAddr::from(([127, 0, 0, 1], 0))),
+            peer_addresses: vec![receiver.local_addr().unwrap()],
+        }
+        .connect()
+        .unwrap()
+        .unwrap();
+
+        let mut receiver_clone = receiver.try_clone().unwrap();
+        receiver_clone.peer_addresses = vec![receiver.local_addr().unwrap()];
+
+        let scl = Scl::builder()
+            .push_ratio(Ratio::from_semitones(2))
+            .build_with_description("Whole-tone scale")
+            .unwrap();
+        let kbm = Kbm::builder(KbmRoot {
+            ref_key: PianoKey::from_midi_number(62),
+            ref_pitch: Pitch::from_hz(293.66),
+            root_offset: 1,
+        })
+        .push_mapped_key(0)
+        .push_unmapped_key()
+        .push_mapped_key(1)
+        .formal_octave(2)
+        .build()
+        .unwrap();
+
+        let own_scl = Scl::builder()
+            .push_ratio(Ratio::from_semitones(1))
+            .build_with_description("Own scale")
+            .unwrap();
+
+        receiver_clone.publish((&own_scl, &kbm));
+        sender.publish((&scl, &kbm));
+
+        let (received_scl, received_kbm) = receiver.receive().unwrap();
+        assert_eq!(received_scl.description(), "Whole-tone scale");
+        assert_eq!(received_scl.num_items(), 1);
+        assert_eq!(received_kbm, kbm);
+    }
+}
@@ -0,0 +1,68 @@
+//! Change detection for input files based on their modification time.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub struct WatchedFile {
+    location: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub fn new(location: PathBuf) -> Self {
+        Self {
+            modified: read_modification_time(&location),
+            location,
+        }
+    }
+
+    pub fn location(&self) -> &Path {
+        &self.location
+    }
+
+    /// Returns `true` if the file has been modified since the last call. Missing files are ignored until they reappear, e.g. when an editor replaces the file on save.
+    pub fn has_changed(&mut self) -> bool {
+        let modified = read_modification_time(&self.location);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn read_modification_time(location: &Path) -> Option<SystemTime> {
+    fs::metadata(location)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn detect_file_modifications() {
+        let location = env::temp_dir().join("tune_cli_watch_test.scl");
+        let _ = fs::remove_file(&location);
+
+        let mut watched_file = WatchedFile::new(location.clone());
+        assert!(!watched_file.has_changed());
+
+        fs::write(&location, "first version").unwrap();
+        assert!(watched_file.has_changed());
+        assert!(!watched_file.has_changed());
+
+        watched_file.modified = Some(SystemTime::UNIX_EPOCH);
+        assert!(watched_file.has_changed());
+        assert!(!watched_file.has_changed());
+
+        fs::remove_file(&location).unwrap();
+        assert!(!watched_file.has_changed());
+    }
+}
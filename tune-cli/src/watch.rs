@@ -0,0 +1,87 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use clap::Parser;
+
+use crate::{shared::watch::WatchedFile, CliError, CliResult, MainCommand};
+
+#[derive(Parser)]
+pub(crate) struct WatchOptions {
+    /// Time between two checks of the input files [ms]
+    #[arg(long = "interval", default_value = "500", value_parser = clap::value_parser!(u64).range(1..))]
+    interval_ms: u64,
+
+    /// The command to re-run, e.g. `scale ref-note 62 scl-file my_tuning.scl`.
+    /// All arguments referring to existing files are watched
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+    command: Vec<String>,
+}
+
+impl WatchOptions {
+    /// Runs the command once and again whenever one of its input files changes. Errors of individual runs are reported without stopping the watch s.t. a half-edited scale file does not end the session.
+    pub fn run(self, output_file: Option<PathBuf>, force: bool) -> CliResult<()> {
+        let args: Vec<_> = ["tune".to_owned()]
+            .into_iter()
+            .chain(self.command)
+            .collect();
+
+        match parse_command(&args)? {
+            MainCommand::Live(_) | MainCommand::Watch(_) => {
+                return Err(CliError::CommandError(
+                    "Only commands that terminate can be watched".to_owned(),
+                ))
+            }
+            _ => {}
+        }
+
+        let mut watched_files: Vec<_> = args[1..]
+            .iter()
+            .map(Path::new)
+            .filter(|location| location.is_file())
+            .map(|location| WatchedFile::new(location.to_owned()))
+            .collect();
+
+        if watched_files.is_empty() {
+            return Err(CliError::CommandError(
+                "The command does not reference any input files".to_owned(),
+            ));
+        }
+
+        for watched_file in &watched_files {
+            eprintln!("[INFO] Watching `{}`", watched_file.location().display());
+        }
+
+        let command_line = crate::format_command_line(&args);
+        let mut overwrite = force;
+        loop {
+            let output_file = output_file.clone();
+            match parse_command(&args).and_then(|command| {
+                crate::run_with_stdio(command, output_file, overwrite, command_line.clone())
+            }) {
+                // The output files are ours from now on
+                Ok(()) => overwrite = true,
+                Err(err) => eprintln!("[ERROR] {err:?}"),
+            }
+
+            loop {
+                thread::sleep(Duration::from_millis(self.interval_ms));
+                let num_changed_files = watched_files
+                    .iter_mut()
+                    .map(WatchedFile::has_changed)
+                    .filter(|&has_changed| has_changed)
+                    .count();
+                if num_changed_files > 0 {
+                    break;
+                }
+            }
+            eprintln!("[INFO] Input files changed. Re-running the command");
+        }
+    }
+}
+
+fn parse_command(args: &[String]) -> CliResult<MainCommand> {
+    MainCommand::try_parse_from(args).map_err(|err| CliError::CommandError(err.to_string()))
+}
@@ -1,6 +1,9 @@
 use std::{
     env, fs,
+    io::Write,
     process::{Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
 macro_rules! check_output {
@@ -35,6 +38,25 @@ fn call_cli_piped(first_args: &[&str], second_args: &[&str]) -> Output {
         .unwrap()
 }
 
+fn call_cli_with_input(args: &[&str], input: &str) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_tune"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    command
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    command.wait_with_output().unwrap()
+}
+
 #[test]
 fn create_7_edo() {
     let output = call_cli(&[
@@ -43,6 +65,162 @@ fn create_7_edo() {
     check_output!("snapshots/README_create_7_edo.stdout", output.stdout);
 }
 
+#[test]
+fn create_7_edo_tun() {
+    let output = call_cli(&[
+        "scale", "--format", "tun", "ref-note", "62", "--lo-key", "61", "--up-key", "64", "steps",
+        "1:7:2",
+    ]);
+    check_output!("snapshots/create_7_edo_tun.stdout", output.stdout);
+}
+
+#[test]
+fn create_7_edo_chart() {
+    let output = call_cli(&[
+        "scale", "--format", "chart", "ref-note", "62", "--lo-key", "61", "--up-key", "64",
+        "steps", "1:7:2",
+    ]);
+    check_output!("snapshots/create_7_edo_chart.stdout", output.stdout);
+}
+
+#[test]
+fn create_12_edo_brf_chart() {
+    let output = call_cli(&[
+        "scale", "--format", "brf", "ref-note", "62", "--lo-key", "30", "--up-key", "64", "steps",
+        "1:12:2",
+    ]);
+    check_output!("snapshots/create_12_edo_brf_chart.stdout", output.stdout);
+}
+
+#[test]
+fn create_7_edo_lilypond_snippet() {
+    let output = call_cli(&[
+        "scale", "--format", "ly", "ref-note", "62", "--lo-key", "58", "--up-key", "66", "steps",
+        "1:7:2",
+    ]);
+    check_output!(
+        "snapshots/create_7_edo_lilypond_snippet.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn create_19_edo_abc_tune() {
+    let output = call_cli(&[
+        "scale", "--format", "abc", "ref-note", "62", "--lo-key", "60", "--up-key", "72", "steps",
+        "1:19:2",
+    ]);
+    check_output!("snapshots/create_19_edo_abc_tune.stdout", output.stdout);
+}
+
+#[test]
+fn create_7_edo_scl_and_kbm_files() {
+    let scale_args = [
+        "scale", "--format", "scl-kbm", "ref-note", "62", "--lo-key", "61", "--up-key", "64",
+        "steps", "1:7:2",
+    ];
+
+    let output = call_cli(&scale_args);
+    check_output!(
+        "snapshots/create_7_edo_scl_and_kbm_files.stdout",
+        output.stdout
+    );
+
+    let output_dir = env::temp_dir().join("tune-cli-scl-kbm-test");
+    let _ = fs::remove_dir_all(&output_dir);
+    fs::create_dir_all(&output_dir).unwrap();
+    let location_template = output_dir.join("7-edo.{ext}");
+    let file_args = [
+        &["--of", location_template.to_str().unwrap()],
+        &scale_args[..],
+    ]
+    .concat();
+
+    assert!(call_cli(&file_args).status.success());
+    let scl = fs::read_to_string(output_dir.join("7-edo.scl")).unwrap();
+    let kbm = fs::read_to_string(output_dir.join("7-edo.kbm")).unwrap();
+    assert_eq!((scl + &kbm).as_bytes(), output.stdout);
+
+    let output = call_cli(&file_args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+
+    assert!(call_cli(&[&["--force"], &file_args[..]].concat())
+        .status
+        .success());
+    assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn watch_scl_file() {
+    let watch_dir = env::temp_dir().join("tune-cli-watch-test");
+    let _ = fs::remove_dir_all(&watch_dir);
+    fs::create_dir_all(&watch_dir).unwrap();
+    let scl_location = watch_dir.join("draft.scl");
+    let output_location = watch_dir.join("draft.yml");
+    fs::write(&scl_location, "Draft\n1\n3/2\n").unwrap();
+
+    let scale_args = [
+        "scale",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "61",
+        "--up-key",
+        "64",
+        "scl-file",
+        scl_location.to_str().unwrap(),
+    ];
+
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_tune"))
+        .args(["--of", output_location.to_str().unwrap(), "watch"])
+        .args(["--interval", "50"])
+        .args(scale_args)
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let wait_for_output = |expected: &[u8]| {
+        let started = Instant::now();
+        while fs::read(&output_location).ok().as_deref() != Some(expected) {
+            assert!(started.elapsed() < Duration::from_secs(10), "Timed out");
+            thread::sleep(Duration::from_millis(50));
+        }
+    };
+
+    wait_for_output(&call_cli(&scale_args).stdout);
+
+    fs::write(&scl_location, "Draft\n1\n5/4\n").unwrap();
+    wait_for_output(&call_cli(&scale_args).stdout);
+
+    watch.kill().unwrap();
+    watch.wait().unwrap();
+}
+
+#[test]
+fn reject_zero_watch_interval() {
+    let output = call_cli(&[
+        "watch",
+        "--interval",
+        "0",
+        "scl",
+        "scl-file",
+        "tests/scl/7-edo.scl",
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value '0' for '--interval"));
+}
+
+#[test]
+fn create_31_edo_lumatone_preset() {
+    let output = call_cli(&[
+        "lumatone", "62", "--root", "57", "--lo-key", "0", "--up-key", "155", "steps", "1:31:2",
+    ]);
+    check_output!(
+        "snapshots/create_31_edo_lumatone_preset.stdout",
+        output.stdout
+    );
+}
+
 #[test]
 fn dump_7_edo() {
     let output = call_cli(&[
@@ -51,6 +229,27 @@ fn dump_7_edo() {
     check_output!("snapshots/README_dump_7_edo.stdout", output.stdout);
 }
 
+#[test]
+fn dump_12_edo_with_german_note_names() {
+    let output = call_cli(&[
+        "dump",
+        "--note-names",
+        "german",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "62",
+        "--up-key",
+        "73",
+        "steps",
+        "1:12:2",
+    ]);
+    check_output!(
+        "snapshots/dump_12_edo_with_german_note_names.stdout",
+        output.stdout
+    );
+}
+
 #[test]
 fn dump_19_edo() {
     let output = call_cli(&[
@@ -65,6 +264,240 @@ fn dump_7_edo_with_root() {
     check_output!("snapshots/dump_7_edo_with_root.stdout", output.stdout);
 }
 
+#[test]
+fn dump_7_edo_with_uncertainty() {
+    let output = call_cli(&[
+        "dump",
+        "--uncertainty",
+        "0.5c",
+        "ref-note",
+        "62",
+        "--root",
+        "60",
+        "--lo-key",
+        "59",
+        "--up-key",
+        "69",
+        "steps",
+        "1:7:2",
+    ]);
+    check_output!(
+        "snapshots/dump_7_edo_with_uncertainty.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_12_edo_with_harmonic_entropy() {
+    let output = call_cli(&[
+        "dump", "--he", "ref-note", "60", "--lo-key", "60", "--up-key", "73", "steps", "1:12:2",
+    ]);
+    check_output!(
+        "snapshots/dump_12_edo_with_harmonic_entropy.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_17_edo_with_sagittal_notation() {
+    let output = call_cli(&[
+        "dump",
+        "--notation",
+        "sagittal",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "60",
+        "--up-key",
+        "81",
+        "steps",
+        "1:17:2",
+    ]);
+    check_output!(
+        "snapshots/dump_17_edo_with_sagittal_notation.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_19_edo_with_notation_rooted_at_c() {
+    let output = call_cli(&[
+        "dump",
+        "--notation",
+        "heptatonic",
+        "ref-note",
+        "60",
+        "--lo-key",
+        "58",
+        "--up-key",
+        "80",
+        "steps",
+        "1:19:2",
+    ]);
+    check_output!(
+        "snapshots/dump_19_edo_with_notation_rooted_at_c.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_non_equal_scale_with_notation() {
+    let output = call_cli(&[
+        "dump",
+        "--notation",
+        "heptatonic",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "62",
+        "--up-key",
+        "70",
+        "rank2",
+        "3/2",
+        "6",
+    ]);
+    check_output!(
+        "snapshots/dump_non_equal_scale_with_notation.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_stretched_12_edo_with_railsback_curve() {
+    let output = call_cli(&[
+        "dump",
+        "ref-note",
+        "69",
+        "--lo-key",
+        "33",
+        "--up-key",
+        "106",
+        "--stretch",
+        "1c",
+        "--railsback",
+        "0.5c",
+        "steps",
+        "1:12:2",
+    ]);
+    check_output!(
+        "snapshots/dump_stretched_12_edo_with_railsback_curve.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn dump_scl_directory_in_batch_mode() {
+    let output = call_cli(&[
+        "dump",
+        "batch",
+        "62",
+        "--lo-key",
+        "61",
+        "--up-key",
+        "66",
+        "--csv",
+        "tests/snapshots/dump_batch_summary.csv",
+        "tests/scl",
+    ]);
+    check_output!("snapshots/dump_batch.stdout", output.stdout);
+    check_output!("snapshots/dump_batch.stderr", output.stderr);
+
+    let summary = fs::read("tests/snapshots/dump_batch_summary.csv").unwrap();
+    check_output!("snapshots/dump_batch_summary.csv", summary);
+}
+
+#[test]
+fn dump_scl_from_archive_using_fuzzy_name() {
+    let output = call_cli(&[
+        "dump",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "61",
+        "--up-key",
+        "66",
+        "scl-archive",
+        "--archive",
+        "tests/scl-archive.zip",
+        "--name",
+        "qrtr-comma",
+    ]);
+    check_output!("snapshots/dump_scl_from_archive.stdout", output.stdout);
+}
+
+#[test]
+fn list_candidates_of_ambiguous_archive_name() {
+    let output = call_cli(&[
+        "scl",
+        "scl-archive",
+        "--archive",
+        "tests/scl-archive.zip",
+        "--name",
+        "e",
+    ]);
+    check_output!("snapshots/ambiguous_archive_name.stderr", output.stderr);
+}
+
+#[test]
+fn read_pitch_list_from_stdin() {
+    let output = call_cli_with_input(
+        &["scale", "stdin", "--root", "57"],
+        "220Hz\n9/8\n\n! Comment\n5/4\n702c\n330.5\n",
+    );
+    check_output!("snapshots/read_pitch_list_from_stdin.stdout", output.stdout);
+}
+
+#[test]
+fn read_yaml_with_leading_comment_from_stdin() {
+    let yaml = call_cli(&[
+        "scale",
+        "--provenance",
+        "ref-note",
+        "62",
+        "--lo-key",
+        "61",
+        "--up-key",
+        "64",
+        "steps",
+        "1:12:2",
+    ])
+    .stdout;
+    let output = call_cli_with_input(
+        &["scale", "--provenance", "stdin"],
+        &format!("# Exported scale\n{}", String::from_utf8(yaml).unwrap()),
+    );
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert!(output.contains("root_key_midi_number: 62"));
+    assert!(output.contains("upstream:"));
+}
+
+#[test]
+fn reject_pitch_list_beyond_midi_range() {
+    let output = call_cli_with_input(&["scale", "stdin", "--root", "127"], "440Hz\n9/8\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("beyond the MIDI range"));
+
+    let output = call_cli_with_input(&["scale", "stdin", "--root", "128"], "440Hz\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid root key 128"));
+}
+
+#[test]
+fn diff_31_edo_and_scl_files_in_batch_mode() {
+    let output = call_cli_piped(
+        &[
+            "scale", "ref-note", "62", "--lo-key", "61", "--up-key", "66", "steps", "1:31:2",
+        ],
+        &[
+            "diff",
+            "stdin",
+            "batch",
+            "62",
+            "tests/scl/7-edo.scl",
+            "tests/scl/quarter-comma.scl",
+        ],
+    );
+    check_output!("snapshots/diff_batch.stdout", output.stdout);
+}
+
 #[test]
 fn diff_quarter_comma_and_31_edo() {
     let output = call_cli_piped(
@@ -92,6 +525,57 @@ fn diff_quarter_comma_and_31_edo_with_shift() {
     );
 }
 
+#[test]
+fn diff_quarter_comma_and_31_edo_with_tolerance() {
+    let output = call_cli_piped(
+        &[
+            "scale", "ref-note", "62", "--lo-key", "61", "--up-key", "71", "rank2", "1:4:5", "5",
+            "1",
+        ],
+        &[
+            "diff",
+            "--tolerance",
+            "0.5c",
+            "stdin",
+            "ref-note",
+            "62",
+            "steps",
+            "1:31:2",
+        ],
+    );
+    assert!(!output.status.success());
+    check_output!(
+        "snapshots/diff_quarter_comma_and_31_edo_with_tolerance.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn diff_with_tolerance_in_cents() {
+    let diff = |tolerance: &str| {
+        call_cli_piped(
+            &["scale", "ref-note", "62", "steps", "1:12:2"],
+            &[
+                "diff",
+                &format!("--tolerance={tolerance}"),
+                "stdin",
+                "ref-note",
+                "62",
+                "steps",
+                "1:31:2",
+            ],
+        )
+    };
+
+    // The largest deviation of 12-EDO from the keys of 31-EDO is 19.4c
+    assert!(diff("20").status.success());
+    assert!(diff("20c").status.success());
+    assert!(!diff("19").status.success());
+
+    let output = diff("-1");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Should not be negative"));
+}
+
 #[test]
 fn mts_of_7_edo() {
     let output = call_cli(&["mts", "full-rt", "ref-note", "62", "steps", "1:7:2"]);
@@ -106,6 +590,49 @@ fn mts_of_19_edo() {
     check_output!("snapshots/mts_of_19_edo.stderr", output.stderr);
 }
 
+#[test]
+fn decode_mts_of_7_edo() {
+    let output = call_cli_piped(
+        &["mts", "full-rt", "ref-note", "62", "steps", "1:7:2"],
+        &["mts", "decode"],
+    );
+    check_output!("snapshots/decode_mts_of_7_edo.stdout", output.stdout);
+}
+
+#[test]
+fn reject_mts_in_for_aot() {
+    let output = call_cli(&[
+        "live",
+        "--midi-in",
+        "foo",
+        "--midi-out",
+        "bar",
+        "aot",
+        "--mts-in",
+        "full",
+        "ref-note",
+        "62",
+        "steps",
+        "1:12:2",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'--mts-in'"));
+}
+
+#[test]
+fn decode_single_note_tuning_change() {
+    let output = call_cli_with_input(&["mts", "decode"], "f0 7f 7f 08 02 00 01 45 45 40 00 f7");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("- key  69:    452.893 Hz | A 4  +50.000c"));
+    assert!(stdout.contains("== Scale ==\nDecoded MTS tuning\n127\n"));
+
+    let output = call_cli_with_input(&["mts", "decode"], "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not contain any SysEx messages"));
+
+    let output = call_cli_with_input(&["mts", "decode"], "f0 7f 7f 08 00 00 f7");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("do not retune any key"));
+}
+
 #[test]
 fn octave_tuning_of_31_edo() {
     let output = call_cli(&[
@@ -162,6 +689,32 @@ fn analysis_of_7_edo() {
     check_output!("snapshots/analysis_of_7_edo.stdout", output.stdout);
 }
 
+#[test]
+fn analysis_of_7_edo_as_html_report() {
+    let output = call_cli(&[
+        "est",
+        "--report",
+        "tests/snapshots/analysis_of_7_edo.html",
+        "1:7:2",
+    ]);
+    check_output!("snapshots/analysis_of_7_edo.stdout", output.stdout);
+
+    let report = fs::read("tests/snapshots/analysis_of_7_edo.html").unwrap();
+    check_output!("snapshots/analysis_of_7_edo.html", report);
+}
+
+#[test]
+fn analysis_of_7_edo_as_json() {
+    let output = call_cli(&["est", "--format", "json", "1:7:2"]);
+    check_output!("snapshots/analysis_of_7_edo.json", output.stdout);
+}
+
+#[test]
+fn analysis_of_13_ed3() {
+    let output = call_cli(&["est", "--period", "3/1", "1:13:3"]);
+    check_output!("snapshots/analysis_of_13_ed3.stdout", output.stdout);
+}
+
 #[test]
 fn analysis_of_13_edo() {
     let output = call_cli(&["est", "1:13:2"]);
@@ -177,6 +730,27 @@ fn analysis_of_stretched_15_edo() {
     );
 }
 
+#[test]
+fn comparison_of_edos() {
+    let output = call_cli(&["est", "compare", "12", "19", "22", "31", "53"]);
+    check_output!("snapshots/comparison_of_edos.stdout", output.stdout);
+}
+
+#[test]
+fn comparison_of_edos_with_low_limit() {
+    let output = call_cli(&["est", "compare", "12", "22", "--limit", "5"]);
+    check_output!(
+        "snapshots/comparison_of_edos_with_low_limit.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn reject_comparison_of_zero_edo() {
+    let output = call_cli(&["est", "compare", "12", "0"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("0 is not in 1.."));
+}
+
 #[test]
 fn analysis_of_16_edo() {
     let output = call_cli(&["est", "1:16:2"]);
@@ -189,6 +763,55 @@ fn analysis_of_19_edo() {
     check_output!("snapshots/README_analysis_of_19_edo.stdout", output.stdout);
 }
 
+#[test]
+fn analysis_of_41_edo_with_ups_and_downs() {
+    let output = call_cli(&["est", "1:41:2", "--notation", "ups-downs"]);
+    check_output!(
+        "snapshots/analysis_of_41_edo_with_ups_and_downs.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn fit_detuned_12_edo() {
+    let output = call_cli(&[
+        "fit",
+        "--max-gens",
+        "6",
+        "--scl",
+        "tests/scl/quarter-comma.scl",
+        "261.8",
+        "277.0",
+        "294.0",
+        "311.5",
+        "329.2",
+        "349.6",
+        "370.3",
+        "392.0",
+        "415.5",
+        "440.2",
+        "466.0",
+        "493.6",
+        "523.6",
+    ]);
+    check_output!("snapshots/fit_detuned_12_edo.stdout", output.stdout);
+}
+
+#[test]
+fn fit_pitch_list_from_stdin() {
+    let output = call_cli_with_input(
+        &["fit", "--max-steps", "12", "--max-gens", "6"],
+        "200Hz\n5/4\n3/2\n7/4\n",
+    );
+    check_output!("snapshots/fit_pitch_list_from_stdin.stdout", output.stdout);
+}
+
+#[test]
+fn fit_rejects_non_expanding_period() {
+    let output = call_cli(&["fit", "--period", "1/1", "440", "550"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("greater than 1/1"));
+}
+
 #[test]
 fn moses_from_700_cents_generator() {
     let output = call_cli(&["mos", "find", "700c"]);
@@ -198,6 +821,24 @@ fn moses_from_700_cents_generator() {
     );
 }
 
+#[test]
+fn moses_from_700_cents_generator_as_html_report() {
+    let output = call_cli(&[
+        "mos",
+        "find",
+        "--report",
+        "tests/snapshots/moses_from_700_cents_generator.html",
+        "700c",
+    ]);
+    check_output!(
+        "snapshots/README_moses_from_700_cents_generator.stdout",
+        output.stdout
+    );
+
+    let report = fs::read("tests/snapshots/moses_from_700_cents_generator.html").unwrap();
+    check_output!("snapshots/moses_from_700_cents_generator.html", report);
+}
+
 #[test]
 fn moses_from_lowest_ratios() {
     let output = call_cli(&["mos", "find", "--per", "2", "3"]);
@@ -207,6 +848,17 @@ fn moses_from_lowest_ratios() {
     );
 }
 
+#[test]
+fn mos_scl_from_bohlen_pierce_lambda_generator() {
+    let output = call_cli(&[
+        "mos", "find", "--per", "3", "9/7", "--scl", "9", "--down", "2",
+    ]);
+    check_output!(
+        "snapshots/mos_scl_from_bohlen_pierce_lambda_generator.stdout",
+        output.stdout
+    );
+}
+
 #[test]
 fn moses_from_porcupine_generator() {
     let output = call_cli(&["mos", "find", "1:3:4/3"]);
@@ -261,6 +913,62 @@ fn create_scl() {
     check_output!("snapshots/README_create_scl.stdout", output.stdout);
 }
 
+#[test]
+fn create_scl_with_preferred_ratios() {
+    let output = call_cli(&[
+        "scl",
+        "--prefer-ratios",
+        "15",
+        "--explicit-denom",
+        "steps",
+        "203.910c",
+        "386.314c",
+        "498.045c",
+        "701.955c",
+        "884.359c",
+        "1088.269c",
+        "1100c",
+        "2",
+    ]);
+    check_output!(
+        "snapshots/create_scl_with_preferred_ratios.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn create_scl_with_provenance() {
+    let output = call_cli(&[
+        "scl",
+        "--provenance",
+        "--name",
+        "Quarter-comma meantone",
+        "scl-file",
+        "tests/scl/quarter-comma.scl",
+    ]);
+    check_output!("snapshots/create_scl_with_provenance.stdout", output.stdout);
+}
+
+#[test]
+fn provenance_of_piped_scale() {
+    let output = call_cli_piped(
+        &[
+            "scale",
+            "--provenance",
+            "ref-note",
+            "62",
+            "--lo-key",
+            "61",
+            "--up-key",
+            "64",
+            "scl-file",
+            "tests/scl/7-edo.scl",
+        ],
+        &["scale", "provenance"],
+    );
+    check_output!("snapshots/provenance_of_piped_scale.stdout", output.stdout);
+}
+
 #[test]
 fn create_harmonics_scale() {
     let output = call_cli(&["scl", "harm", "-u", "37", "74", "--neji=13"]);
@@ -270,12 +978,58 @@ fn create_harmonics_scale() {
     );
 }
 
+#[test]
+fn create_7_odd_limit_tonality_diamond() {
+    let output = call_cli(&["scl", "ji", "diamond", "7"]);
+    check_output!(
+        "snapshots/create_7_odd_limit_tonality_diamond.stdout",
+        output.stdout
+    );
+}
+
+#[test]
+fn create_raga_scale() {
+    let output = call_cli(&["scl", "preset", "raga:yaman"]);
+    check_output!("snapshots/create_raga_scale.stdout", output.stdout);
+}
+
+#[test]
+fn create_generator_chain_scale() {
+    let output = call_cli(&[
+        "scl",
+        "gen-chain",
+        "--gen",
+        "3/2",
+        "--down",
+        "3",
+        "--up",
+        "5",
+        "--period",
+        "2/1",
+        "--offset",
+        "81/80",
+    ]);
+    check_output!(
+        "snapshots/create_generator_chain_scale.stdout",
+        output.stdout
+    );
+}
+
 #[test]
 fn create_kbm_root() {
     let output = call_cli(&["kbm", "ref-note", "62"]);
     check_output!("snapshots/README_create_kbm_root.stdout", output.stdout);
 }
 
+#[test]
+fn create_kbm_root_at_concert_pitch() {
+    let output = call_cli(&["kbm", "ref-note", "62", "--a4", "432Hz"]);
+    check_output!(
+        "snapshots/create_kbm_root_at_concert_pitch.stdout",
+        output.stdout
+    );
+}
+
 #[test]
 fn crate_kbm() {
     let output = call_cli(&[
@@ -295,3 +1049,17 @@ fn crate_kbm() {
     ]);
     check_output!("snapshots/README_create_kbm.stdout", output.stdout);
 }
+
+#[test]
+fn create_kbm_from_note_range() {
+    let output = call_cli(&[
+        "kbm",
+        "range",
+        "C2..C6",
+        "--root",
+        "D4",
+        "--degree-offset",
+        "-3",
+    ]);
+    check_output!("snapshots/create_kbm_from_note_range.stdout", output.stdout);
+}
@@ -4,7 +4,10 @@
 use std::iter;
 
 use material_yew::{MatButton, MatTextArea};
-use tune_cli::CliError;
+use tune_cli::{shared::midi::WebMidi, CliError};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{MidiAccess, MidiOptions};
 use yew::prelude::*;
 
 pub fn main() {
@@ -16,12 +19,15 @@ pub struct Model {
     stdin: String,
     stdout: Vec<u8>,
     stderr: Vec<u8>,
+    midi_access: Result<MidiAccess, String>,
 }
 
 pub enum Msg {
     ArgsInput(String),
     StdinInput(String),
+    MidiAccess(Result<MidiAccess, String>),
     RunTuneCli,
+    TuneCliFinished { stdout: Vec<u8>, stderr: Vec<u8> },
     CopyToStdin,
     PreventTextAreaEdit,
 }
@@ -30,38 +36,63 @@ impl Component for Model {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link()
+            .send_future(async { Msg::MidiAccess(request_midi_access().await) });
+
         Model {
             args: "scl\nsteps\n1:31:2".to_owned(),
             stdin: String::new(),
             stdout: Vec::new(),
             stderr: Vec::new(),
+            midi_access: Err("MIDI access has not been granted yet".to_owned()),
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::ArgsInput(args) => self.args = args,
             Msg::StdinInput(stdin) => self.stdin = stdin,
+            Msg::MidiAccess(midi_access) => self.midi_access = midi_access,
             Msg::RunTuneCli => {
-                let args = iter::once("tune")
+                let args: Vec<_> = iter::once("tune")
                     .chain(self.args.lines())
                     .map(str::trim)
-                    .map(str::to_owned);
+                    .map(str::to_owned)
+                    .collect();
+                let stdin = self.stdin.clone();
+                let midi = match &self.midi_access {
+                    Ok(midi_access) => WebMidi::new(midi_access.clone()),
+                    Err(reason) => WebMidi::unavailable(reason.clone()),
+                };
                 self.stdout.clear();
                 self.stderr.clear();
-                let result = tune_cli::run_in_wasm_env(
-                    args,
-                    self.stdin.as_bytes(),
-                    &mut self.stdout,
-                    &mut self.stderr,
-                );
-
-                match result {
-                    Ok(()) => {}
-                    Err(CliError::CommandError(err)) => self.stderr.extend(err.as_bytes()),
-                    Err(CliError::IoError(err)) => self.stderr.extend(err.to_string().as_bytes()),
-                };
+
+                // Commands like `devices probe` need to wait for MIDI replies without blocking the browser
+                ctx.link().send_future(async move {
+                    let mut stdout = Vec::new();
+                    let mut stderr = Vec::new();
+                    let result = tune_cli::run_in_wasm_env(
+                        args,
+                        stdin.as_bytes(),
+                        &mut stdout,
+                        &mut stderr,
+                        midi,
+                    )
+                    .await;
+
+                    match result {
+                        Ok(()) => {}
+                        Err(CliError::CommandError(err)) => stderr.extend(err.as_bytes()),
+                        Err(CliError::IoError(err)) => stderr.extend(err.to_string().as_bytes()),
+                    };
+
+                    Msg::TuneCliFinished { stdout, stderr }
+                });
+            }
+            Msg::TuneCliFinished { stdout, stderr } => {
+                self.stdout = stdout;
+                self.stderr = stderr;
             }
             Msg::CopyToStdin => self.stdin = String::from_utf8_lossy(&self.stdout).into_owned(),
             Msg::PreventTextAreaEdit => {}
@@ -112,3 +143,22 @@ impl Component for Model {
         }
     }
 }
+
+/// Asks the browser for MIDI access including SysEx permission which is required for sending MTS messages.
+async fn request_midi_access() -> Result<MidiAccess, String> {
+    let navigator = web_sys::window()
+        .ok_or("No browser window available")?
+        .navigator();
+
+    let mut options = MidiOptions::new();
+    options.sysex(true);
+
+    let promise = navigator
+        .request_midi_access_with_options(&options)
+        .map_err(|err| format!("MIDI access is not supported ({err:?})"))?;
+
+    JsFuture::from(promise)
+        .await
+        .map(JsCast::unchecked_into)
+        .map_err(|err| format!("MIDI access has been denied ({err:?})"))
+}